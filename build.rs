@@ -14,6 +14,7 @@ fn main() {
     println!("cargo::rerun-if-changed=src/config.rs");
     println!("cargo::rerun-if-changed=src/services/lsp/client.rs");
     println!("cargo::rerun-if-changed=src/services/process_limits.rs");
+    println!("cargo::rerun-if-changed=src/services/dap/client.rs");
 
     // Skip type generation during cargo publish (files should be pre-committed)
     // DOCS_RS is set when building on docs.rs
@@ -1141,6 +1142,8 @@ struct EnumVariantInfo {
     serde_rename: Option<String>,
     doc_comment: String,
     is_default: bool,
+    /// Type of a single-field tuple variant, e.g. `Vec<Item>` for `Many(Vec<Item>)`
+    tuple_type: Option<String>,
 }
 
 /// Generate JSON Schema for configuration
@@ -1149,12 +1152,14 @@ fn generate_config_schema() -> Result<(), Box<dyn std::error::Error>> {
     let config_source = fs::read_to_string("src/config.rs")?;
     let lsp_client_source = fs::read_to_string("src/services/lsp/client.rs")?;
     let process_limits_source = fs::read_to_string("src/services/process_limits.rs")?;
+    let dap_client_source = fs::read_to_string("src/services/dap/client.rs")?;
 
     // Extract struct and enum definitions
     let mut all_structs = Vec::new();
     all_structs.extend(extract_config_structs(&config_source));
     all_structs.extend(extract_config_structs(&lsp_client_source));
     all_structs.extend(extract_config_structs(&process_limits_source));
+    all_structs.extend(extract_config_structs(&dap_client_source));
 
     // Generate JSON Schema
     let schema = generate_json_schema(&all_structs);
@@ -1450,6 +1455,11 @@ fn parse_enum_variant(
         line.to_string()
     };
 
+    let tuple_type = match (line.find('('), line.rfind(')')) {
+        (Some(open), Some(close)) if open < close => Some(line[open + 1..close].trim().to_string()),
+        _ => None,
+    };
+
     let mut serde_rename = None;
     let mut is_default = false;
 
@@ -1479,6 +1489,7 @@ fn parse_enum_variant(
         serde_rename,
         doc_comment: doc_comment.to_string(),
         is_default,
+        tuple_type,
     })
 }
 
@@ -1604,9 +1615,10 @@ fn rust_type_to_json_schema(
         }
         "f32" | "f64" => serde_json::json!({ "type": "number" }),
         _ => {
-            // Check if it's a known definition
-            if definitions.contains_key(rust_type) {
-                serde_json::json!({ "$ref": format!("#/$defs/{}", rust_type) })
+            // Check if it's a known definition, possibly referenced by path
+            let type_name = rust_type.rsplit("::").next().unwrap_or(rust_type);
+            if definitions.contains_key(type_name) {
+                serde_json::json!({ "$ref": format!("#/$defs/{}", type_name) })
             } else {
                 // Unknown type, allow anything
                 serde_json::json!({})
@@ -1626,7 +1638,7 @@ fn generate_json_schema(structs: &[ConfigStructInfo]) -> serde_json::Value {
     // Second pass: generate definitions
     for struct_info in structs {
         let schema = if struct_info.is_enum {
-            generate_enum_schema(struct_info)
+            generate_enum_schema(struct_info, &def_names)
         } else {
             generate_struct_schema(struct_info, &def_names)
         };
@@ -1698,7 +1710,36 @@ fn generate_struct_schema(
 }
 
 /// Generate JSON Schema for an enum
-fn generate_enum_schema(enum_info: &ConfigStructInfo) -> serde_json::Value {
+///
+/// Enums whose variants all wrap a single value (`#[serde(untagged)]` enums) accept any of
+/// the wrapped types; other enums are a set of strings.
+fn generate_enum_schema(
+    enum_info: &ConfigStructInfo,
+    def_names: &std::collections::HashSet<String>,
+) -> serde_json::Value {
+    let untagged = !enum_info.enum_variants.is_empty()
+        && enum_info
+            .enum_variants
+            .iter()
+            .all(|v| v.tuple_type.is_some());
+    if untagged {
+        let definitions: BTreeMap<String, serde_json::Value> = def_names
+            .iter()
+            .map(|name| (name.clone(), serde_json::json!({})))
+            .collect();
+        let any_of: Vec<serde_json::Value> = enum_info
+            .enum_variants
+            .iter()
+            .filter_map(|v| v.tuple_type.as_deref())
+            .map(|rust_type| rust_type_to_json_schema(rust_type, &definitions))
+            .collect();
+        let mut schema = serde_json::json!({ "anyOf": any_of });
+        if !enum_info.doc_comment.is_empty() {
+            schema["description"] = serde_json::Value::String(enum_info.doc_comment.clone());
+        }
+        return schema;
+    }
+
     let values: Vec<String> = enum_info
         .enum_variants
         .iter()
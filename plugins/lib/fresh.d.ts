@@ -651,6 +651,17 @@ interface EditorAPI {
    * @returns Promise resolving to the JSON response value
    */
   sendLspRequest(language: string, method: string, params?: unknown | null): Promise<unknown>;
+  /**
+   * Request the symbol outline of a buffer
+   *
+   * Uses the language server's textDocument/documentSymbol when a server is running,
+   * otherwise tree-sitter. Results are delivered via the "document_symbols" hook as
+   * { buffer_id, source, symbols } where each symbol has name, detail, kind, depth,
+   * line (1-indexed), start/end byte offsets and selection_start.
+   * @param buffer_id - The buffer ID
+   * @returns true if the request was queued
+   */
+  requestDocumentSymbols(buffer_id: number): boolean;
   /**
    * Set the ratio of a split container
    * @param split_id - ID of the split
@@ -0,0 +1,506 @@
+/// <reference path="./lib/fresh.d.ts" />
+
+/**
+ * Outline Plugin
+ *
+ * Symbol outline for the active buffer in a side split:
+ * - Symbols from the language server (textDocument/documentSymbol), or from
+ *   tree-sitter tag queries when no server is running
+ * - Follows the cursor in the source buffer, highlighting the enclosing symbol
+ * - Enter to jump to a symbol
+ * - Refreshes after edits, saves, and when switching buffers
+ */
+
+// =============================================================================
+// Types and Interfaces
+// =============================================================================
+
+interface OutlineSymbol {
+  name: string;
+  detail: string | null;
+  kind: string;
+  depth: number;
+  line: number;             // 1-based line of the symbol name
+  start: number;            // byte offset of the definition start
+  end: number;              // byte offset of the definition end
+  selection_start: number;  // byte offset of the symbol name
+}
+
+interface OutlineState {
+  isOpen: boolean;
+  bufferId: number | null;
+  splitId: number | null;
+  sourceSplitId: number | null;
+  sourceBufferId: number | null;
+  // Last known cursor byte offset in the source buffer
+  sourceCursor: number;
+  symbols: OutlineSymbol[];
+  source: string;
+  cachedContent: string;
+  // Panel line (1-based) of each symbol, by symbol index
+  symbolLines: number[];
+  // Current cursor line in the panel (1-indexed)
+  panelCursorLine: number;
+  // A request is in flight; `dirty` asks for another one when it returns
+  requestPending: boolean;
+  dirty: boolean;
+}
+
+// =============================================================================
+// State Management
+// =============================================================================
+
+const state: OutlineState = {
+  isOpen: false,
+  bufferId: null,
+  splitId: null,
+  sourceSplitId: null,
+  sourceBufferId: null,
+  sourceCursor: 0,
+  symbols: [],
+  source: "",
+  cachedContent: "",
+  symbolLines: [],
+  panelCursorLine: 1,
+  requestPending: false,
+  dirty: false,
+};
+
+// Panel lines before the first symbol (header)
+const HEADER_LINES = 1;
+
+// =============================================================================
+// Color Definitions
+// =============================================================================
+
+const colors = {
+  header: [255, 200, 100] as [number, number, number],
+  kind: [150, 150, 150] as [number, number, number],
+  location: [150, 255, 150] as [number, number, number],
+  current: [100, 200, 255] as [number, number, number],
+};
+
+// =============================================================================
+// Mode Definition
+// =============================================================================
+
+editor.defineMode(
+  "outline-list",
+  null,
+  [
+    ["Return", "outline_goto"],
+    ["Tab", "outline_goto"],
+    ["r", "outline_refresh"],
+    ["q", "outline_close"],
+    ["Escape", "outline_close"],
+  ],
+  true
+);
+
+// =============================================================================
+// Helpers
+// =============================================================================
+
+function kindLabel(kind: string): string {
+  switch (kind) {
+    case "function": return "fn";
+    case "method": return "fn";
+    case "constructor": return "new";
+    case "struct": return "struct";
+    case "class": return "class";
+    case "interface": return "iface";
+    case "enum": return "enum";
+    case "enum_member": return "variant";
+    case "module": return "mod";
+    case "namespace": return "ns";
+    case "object": return "impl";
+    case "field": return "field";
+    case "property": return "prop";
+    case "constant": return "const";
+    case "variable": return "var";
+    case "type_parameter": return "type";
+    default: return kind;
+  }
+}
+
+function entriesToContent(entries: TextPropertyEntry[]): string {
+  return entries.map(e => e.text).join("");
+}
+
+// Convert a 1-based line number to byte offset in the cached content
+function lineToByteOffset(lineNumber: number): number {
+  const lines = state.cachedContent.split("\n");
+  let offset = 0;
+  for (let i = 0; i < lineNumber - 1 && i < lines.length; i++) {
+    offset += lines[i].length + 1; // +1 for newline
+  }
+  return offset;
+}
+
+// Index of the innermost symbol containing a byte offset, or null
+function symbolAtOffset(offset: number): number | null {
+  let found: number | null = null;
+  for (let i = 0; i < state.symbols.length; i++) {
+    const symbol = state.symbols[i];
+    if (symbol.start > offset) break;
+    if (offset < symbol.end && (found === null || symbol.depth >= state.symbols[found].depth)) {
+      found = i;
+    }
+  }
+  return found;
+}
+
+// =============================================================================
+// Panel Content Building
+// =============================================================================
+
+function buildPanelEntries(): TextPropertyEntry[] {
+  const entries: TextPropertyEntry[] = [];
+  state.symbolLines = [];
+
+  const path = state.sourceBufferId !== null ? editor.getBufferPath(state.sourceBufferId) : "";
+  const fileName = path ? editor.pathBasename(path) : "No file";
+  const sourceLabel = state.source ? ` (${state.source})` : "";
+  entries.push({
+    text: `Outline: ${fileName}${sourceLabel}\n`,
+    properties: { type: "header" },
+  });
+
+  if (state.symbols.length === 0) {
+    entries.push({
+      text: state.requestPending ? "  Loading...\n" : "  No symbols\n",
+      properties: { type: "empty" },
+    });
+    return entries;
+  }
+
+  let panelLine = HEADER_LINES + 1;
+  state.symbols.forEach((symbol, index) => {
+    const indent = "  ".repeat(symbol.depth + 1);
+    state.symbolLines.push(panelLine);
+    entries.push({
+      text: `${indent}${kindLabel(symbol.kind)} ${symbol.name}  :${symbol.line}\n`,
+      properties: {
+        type: "symbol",
+        index,
+        position: symbol.selection_start,
+      },
+    });
+    panelLine++;
+  });
+
+  return entries;
+}
+
+// =============================================================================
+// Highlighting
+// =============================================================================
+
+function applyHighlighting(): void {
+  if (state.bufferId === null) return;
+
+  const bufferId = state.bufferId;
+  editor.clearNamespace(bufferId, "outline");
+
+  const content = state.cachedContent;
+  if (!content) return;
+
+  const lines = content.split("\n");
+  let byteOffset = 0;
+
+  for (let lineIdx = 0; lineIdx < lines.length; lineIdx++) {
+    const line = lines[lineIdx];
+    const lineStart = byteOffset;
+    const lineEnd = byteOffset + line.length;
+
+    if (lineIdx < HEADER_LINES) {
+      editor.addOverlay(
+        bufferId, "outline", lineStart, lineEnd,
+        colors.header[0], colors.header[1], colors.header[2],
+        false, true, false
+      );
+    } else if (line.trim().length > 0) {
+      const isCurrentLine = (lineIdx + 1) === state.panelCursorLine;
+      if (isCurrentLine) {
+        editor.addOverlay(
+          bufferId, "outline", lineStart, lineEnd,
+          colors.current[0], colors.current[1], colors.current[2],
+          false, true, false
+        );
+      } else {
+        // Dim the kind label
+        const kindStart = line.length - line.trimStart().length;
+        const kindEnd = line.indexOf(" ", kindStart);
+        if (kindEnd > kindStart) {
+          editor.addOverlay(
+            bufferId, "outline", lineStart + kindStart, lineStart + kindEnd,
+            colors.kind[0], colors.kind[1], colors.kind[2],
+            false, false, true
+          );
+        }
+      }
+
+      // Line number suffix
+      const locIdx = line.lastIndexOf("  :");
+      if (locIdx >= 0) {
+        editor.addOverlay(
+          bufferId, "outline", lineStart + locIdx + 2, lineEnd,
+          colors.location[0], colors.location[1], colors.location[2],
+          false, false, false
+        );
+      }
+    }
+
+    byteOffset += line.length + 1;
+  }
+}
+
+function updatePanel(): void {
+  if (state.bufferId === null) return;
+
+  const entries = buildPanelEntries();
+  state.cachedContent = entriesToContent(entries);
+  editor.setVirtualBufferContent(state.bufferId, entries);
+  syncPanelToSourceCursor();
+  applyHighlighting();
+}
+
+// Move the panel cursor to the symbol enclosing the source cursor
+function syncPanelToSourceCursor(): void {
+  if (state.bufferId === null) return;
+
+  const index = symbolAtOffset(state.sourceCursor);
+  if (index === null) return;
+
+  const panelLine = state.symbolLines[index];
+  state.panelCursorLine = panelLine;
+  editor.setBufferCursor(state.bufferId, lineToByteOffset(panelLine));
+}
+
+function requestSymbols(): void {
+  if (state.sourceBufferId === null) return;
+
+  if (state.requestPending) {
+    state.dirty = true;
+    return;
+  }
+  state.requestPending = true;
+  state.dirty = false;
+  if (!editor.requestDocumentSymbols(state.sourceBufferId)) {
+    state.requestPending = false;
+  }
+}
+
+// =============================================================================
+// Commands
+// =============================================================================
+
+globalThis.show_outline = async function(): Promise<void> {
+  if (state.isOpen) {
+    if (state.splitId !== null) {
+      editor.focusSplit(state.splitId);
+    }
+    return;
+  }
+
+  state.sourceSplitId = editor.getActiveSplitId();
+  state.sourceBufferId = editor.getActiveBufferId();
+  state.sourceCursor = editor.getCursorPosition();
+  state.symbols = [];
+  state.source = "";
+  state.requestPending = false;
+  state.dirty = false;
+
+  const entries = buildPanelEntries();
+  state.cachedContent = entriesToContent(entries);
+
+  // Side-by-side split to the right of the current buffer
+  const result = await editor.createVirtualBufferInSplit({
+    name: "*Outline*",
+    mode: "outline-list",
+    read_only: true,
+    entries: entries,
+    ratio: 0.75,  // Source keeps 75%, outline takes 25%
+    direction: "vertical",
+    panel_id: "outline",
+    show_line_numbers: false,
+    show_cursors: true,
+    editing_disabled: true,
+  });
+
+  if (result.buffer_id !== null) {
+    state.isOpen = true;
+    state.bufferId = result.buffer_id;
+    state.splitId = result.split_id ?? null;
+    applyHighlighting();
+    requestSymbols();
+    editor.setStatus("Outline: RET: goto | r: refresh | q: close");
+  } else {
+    state.sourceSplitId = null;
+    state.sourceBufferId = null;
+    editor.setStatus("Failed to open outline");
+  }
+};
+
+globalThis.outline_close = function(): void {
+  if (!state.isOpen) return;
+
+  // Capture values before clearing state
+  const splitId = state.splitId;
+  const sourceSplitId = state.sourceSplitId;
+  const sourceBufferId = state.sourceBufferId;
+  const bufferId = state.bufferId;
+
+  // Clear state FIRST to prevent event handlers from trying to update
+  state.isOpen = false;
+  state.bufferId = null;
+  state.splitId = null;
+  state.sourceSplitId = null;
+  state.sourceBufferId = null;
+  state.symbols = [];
+  state.cachedContent = "";
+
+  let splitClosed = false;
+  if (splitId !== null) {
+    splitClosed = editor.closeSplit(splitId);
+  }
+
+  // If split couldn't be closed (only split), switch buffer back to source
+  if (!splitClosed && splitId !== null && sourceBufferId !== null) {
+    editor.setSplitBuffer(splitId, sourceBufferId);
+  }
+
+  if (bufferId !== null) {
+    editor.closeBuffer(bufferId);
+  }
+
+  if (sourceSplitId !== null) {
+    editor.focusSplit(sourceSplitId);
+  }
+
+  editor.setStatus("Outline closed");
+};
+
+globalThis.outline_goto = function(): void {
+  if (!state.isOpen || state.bufferId === null || state.sourceBufferId === null) return;
+
+  const props = editor.getTextPropertiesAtCursor(state.bufferId);
+  if (props.length > 0 && typeof props[0].position === "number") {
+    const symbol = state.symbols[props[0].index as number];
+    if (state.sourceSplitId !== null) {
+      editor.focusSplit(state.sourceSplitId);
+    }
+    editor.setBufferCursor(state.sourceBufferId, props[0].position as number);
+    if (symbol) {
+      editor.setStatus(`${symbol.name} (line ${symbol.line})`);
+    }
+    return;
+  }
+
+  editor.setStatus("Move cursor to a symbol");
+};
+
+globalThis.outline_refresh = function(): void {
+  if (!state.isOpen) return;
+  requestSymbols();
+};
+
+globalThis.toggle_outline = function(): void {
+  if (state.isOpen) {
+    globalThis.outline_close();
+  } else {
+    globalThis.show_outline();
+  }
+};
+
+// =============================================================================
+// Event Handlers
+// =============================================================================
+
+globalThis.on_outline_document_symbols = function(data: {
+  buffer_id: number;
+  source: string;
+  symbols: OutlineSymbol[];
+}): void {
+  if (!state.isOpen || data.buffer_id !== state.sourceBufferId) return;
+
+  state.requestPending = false;
+  state.symbols = data.symbols;
+  state.source = data.source;
+  updatePanel();
+
+  // Edits arrived while the request was in flight
+  if (state.dirty) {
+    requestSymbols();
+  }
+};
+
+globalThis.on_outline_cursor_moved = function(data: {
+  buffer_id: number;
+  cursor_id: number;
+  old_position: number;
+  new_position: number;
+  line: number;
+}): void {
+  if (!state.isOpen || state.bufferId === null) return;
+
+  if (data.buffer_id === state.bufferId) {
+    state.panelCursorLine = data.line;
+    applyHighlighting();
+    return;
+  }
+
+  if (data.buffer_id === state.sourceBufferId) {
+    state.sourceCursor = data.new_position;
+    syncPanelToSourceCursor();
+    applyHighlighting();
+  }
+};
+
+globalThis.on_outline_buffer_changed = function(data: { buffer_id: number }): void {
+  if (!state.isOpen || data.buffer_id !== state.sourceBufferId) return;
+  requestSymbols();
+};
+
+globalThis.on_outline_buffer_activated = function(data: { buffer_id: number }): void {
+  if (!state.isOpen || data.buffer_id === state.bufferId) return;
+  if (data.buffer_id === state.sourceBufferId) return;
+
+  // Follow the newly active buffer
+  state.sourceBufferId = data.buffer_id;
+  state.sourceSplitId = editor.getActiveSplitId();
+  state.sourceCursor = editor.getCursorPosition();
+  state.symbols = [];
+  state.source = "";
+  state.requestPending = false;
+  state.dirty = false;
+  updatePanel();
+  requestSymbols();
+};
+
+editor.on("document_symbols", "on_outline_document_symbols");
+editor.on("cursor_moved", "on_outline_cursor_moved");
+editor.on("after_insert", "on_outline_buffer_changed");
+editor.on("after_delete", "on_outline_buffer_changed");
+editor.on("after_file_save", "on_outline_buffer_changed");
+editor.on("buffer_activated", "on_outline_buffer_activated");
+
+// =============================================================================
+// Command Registration
+// =============================================================================
+
+editor.registerCommand(
+  "Show Outline",
+  "Show the symbol outline of the current buffer",
+  "show_outline",
+  "normal"
+);
+
+editor.registerCommand(
+  "Toggle Outline",
+  "Toggle the symbol outline panel",
+  "toggle_outline",
+  "normal"
+);
+
+editor.debug("Outline plugin initialized");
@@ -191,6 +191,10 @@ const THEME_SECTIONS: ThemeSection[] = [
       { key: "variable", displayName: "Variable", description: "Variable names", section: "syntax" },
       { key: "constant", displayName: "Constant", description: "Constants and literals", section: "syntax" },
       { key: "operator", displayName: "Operator", description: "Operators (+, -, =, etc.)", section: "syntax" },
+      { key: "macro", displayName: "Macro", description: "Macro names (LSP semantic tokens)", section: "syntax" },
+      { key: "parameter", displayName: "Parameter", description: "Function parameters (LSP semantic tokens)", section: "syntax" },
+      { key: "namespace", displayName: "Namespace", description: "Modules and namespaces (LSP semantic tokens)", section: "syntax" },
+      { key: "unsafe", displayName: "Unsafe", description: "Unsafe operations (LSP semantic tokens)", section: "syntax" },
     ],
   },
 ];
@@ -1094,6 +1098,10 @@ function createDefaultTheme(): Record<string, unknown> {
       variable: [156, 220, 254],
       constant: [79, 193, 255],
       operator: [212, 212, 212],
+      macro: [197, 134, 192],
+      parameter: [117, 190, 255],
+      namespace: [78, 201, 176],
+      unsafe: [244, 71, 71],
     },
   };
 }
@@ -0,0 +1,9 @@
+; Foldable regions for Bash
+[
+  (function_definition)
+  (if_statement)
+  (case_statement)
+  (for_statement)
+  (while_statement)
+  (compound_statement)
+] @fold
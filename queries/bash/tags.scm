@@ -0,0 +1,5 @@
+; Outline symbols for Bash
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(function_definition
+  name: (word) @name) @definition.function
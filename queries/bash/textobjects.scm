@@ -0,0 +1,16 @@
+; Text objects for Bash
+; @<object>.outer is the whole object, @<object>.inner its contents; arguments
+; only capture .inner
+
+(function_definition
+  body: (_) @function.inner) @function.outer
+
+(command
+  argument: (_) @argument.inner)
+
+(comment) @comment.outer
+
+[
+  (compound_statement)
+  (do_group)
+] @block.outer
@@ -0,0 +1,8 @@
+; Foldable regions for C
+[
+  (compound_statement)
+  (field_declaration_list)
+  (enumerator_list)
+  (initializer_list)
+  (comment)
+] @fold
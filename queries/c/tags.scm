@@ -0,0 +1,39 @@
+; Outline symbols for C
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(function_definition
+  declarator: (function_declarator
+    declarator: (identifier) @name)) @definition.function
+
+(function_definition
+  declarator: (pointer_declarator
+    declarator: (function_declarator
+      declarator: (identifier) @name))) @definition.function
+
+[
+  (struct_specifier
+    name: (type_identifier) @name
+    body: (_))
+  (union_specifier
+    name: (type_identifier) @name
+    body: (_))
+] @definition.struct
+
+(enum_specifier
+  name: (type_identifier) @name
+  body: (_)) @definition.enum
+
+(enumerator
+  name: (identifier) @name) @definition.enum_member
+
+(field_declaration
+  declarator: (field_identifier) @name) @definition.field
+
+(type_definition
+  declarator: (type_identifier) @name) @definition.type_parameter
+
+(preproc_function_def
+  name: (identifier) @name) @definition.function
+
+(preproc_def
+  name: (identifier) @name) @definition.constant
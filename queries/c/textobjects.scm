@@ -0,0 +1,25 @@
+; Text objects for C
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+(function_definition
+  body: (_) @function.inner) @function.outer
+
+[
+  (struct_specifier
+    body: (_) @class.inner)
+  (union_specifier
+    body: (_) @class.inner)
+  (enum_specifier
+    body: (_) @class.inner)
+] @class.outer
+
+(parameter_list
+  (_) @parameter.inner)
+
+(argument_list
+  (_) @argument.inner)
+
+(comment) @comment.outer
+
+(compound_statement) @block.outer
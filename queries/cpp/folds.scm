@@ -0,0 +1,9 @@
+; Foldable regions for C++
+[
+  (compound_statement)
+  (field_declaration_list)
+  (enumerator_list)
+  (initializer_list)
+  (declaration_list)
+  (comment)
+] @fold
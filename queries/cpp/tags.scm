@@ -0,0 +1,63 @@
+; Outline symbols for C++
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(namespace_definition
+  name: (_) @name) @definition.namespace
+
+(class_specifier
+  name: (_) @name
+  body: (_)) @definition.class
+
+[
+  (struct_specifier
+    name: (_) @name
+    body: (_))
+  (union_specifier
+    name: (_) @name
+    body: (_))
+] @definition.struct
+
+(enum_specifier
+  name: (_) @name
+  body: (_)) @definition.enum
+
+(enumerator
+  name: (identifier) @name) @definition.enum_member
+
+; Member functions (inline or out-of-line)
+(function_definition
+  declarator: (function_declarator
+    declarator: [(field_identifier) (qualified_identifier) (destructor_name)] @name)) @definition.method
+
+(field_declaration
+  declarator: (function_declarator
+    declarator: (field_identifier) @name)) @definition.method
+
+(function_definition
+  declarator: (function_declarator
+    declarator: (identifier) @name)) @definition.function
+
+(function_definition
+  declarator: (pointer_declarator
+    declarator: (function_declarator
+      declarator: (identifier) @name))) @definition.function
+
+(function_definition
+  declarator: (reference_declarator
+    (function_declarator
+      declarator: (identifier) @name))) @definition.function
+
+(field_declaration
+  declarator: (field_identifier) @name) @definition.field
+
+(type_definition
+  declarator: (type_identifier) @name) @definition.type_parameter
+
+(alias_declaration
+  name: (type_identifier) @name) @definition.type_parameter
+
+(preproc_function_def
+  name: (identifier) @name) @definition.function
+
+(preproc_def
+  name: (identifier) @name) @definition.constant
@@ -0,0 +1,8 @@
+; Foldable regions for C#
+[
+  (declaration_list)
+  (block)
+  (enum_member_declaration_list)
+  (initializer_expression)
+  (comment)
+] @fold
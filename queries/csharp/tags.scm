@@ -0,0 +1,42 @@
+; Outline symbols for C#
+; @name is the symbol name, @definition.<kind> the whole definition
+
+[
+  (namespace_declaration
+    name: (_) @name)
+  (file_scoped_namespace_declaration
+    name: (_) @name)
+] @definition.namespace
+
+[
+  (class_declaration
+    name: (identifier) @name)
+  (record_declaration
+    name: (identifier) @name)
+] @definition.class
+
+(struct_declaration
+  name: (identifier) @name) @definition.struct
+
+(interface_declaration
+  name: (identifier) @name) @definition.interface
+
+(enum_declaration
+  name: (identifier) @name) @definition.enum
+
+(enum_member_declaration
+  name: (identifier) @name) @definition.enum_member
+
+(constructor_declaration
+  name: (identifier) @name) @definition.constructor
+
+(method_declaration
+  name: (identifier) @name) @definition.method
+
+(property_declaration
+  name: (identifier) @name) @definition.property
+
+(field_declaration
+  (variable_declaration
+    (variable_declarator
+      name: (identifier) @name))) @definition.field
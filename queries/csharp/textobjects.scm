@@ -0,0 +1,39 @@
+; Text objects for C#
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+[
+  (method_declaration
+    body: (_) @function.inner)
+  (constructor_declaration
+    body: (_) @function.inner)
+  (local_function_statement
+    body: (_) @function.inner)
+  (lambda_expression
+    body: (_) @function.inner)
+] @function.outer
+
+(method_declaration) @function.outer
+
+[
+  (class_declaration
+    body: (_) @class.inner)
+  (struct_declaration
+    body: (_) @class.inner)
+  (interface_declaration
+    body: (_) @class.inner)
+  (record_declaration
+    body: (_) @class.inner)
+  (enum_declaration
+    body: (_) @class.inner)
+] @class.outer
+
+(parameter_list
+  (_) @parameter.inner)
+
+(argument_list
+  (_) @argument.inner)
+
+(comment) @comment.outer
+
+(block) @block.outer
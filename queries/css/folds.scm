@@ -0,0 +1,5 @@
+; Foldable regions for CSS
+[
+  (block)
+  (comment)
+] @fold
@@ -0,0 +1,8 @@
+; Foldable regions for Go
+[
+  (block)
+  (field_declaration_list)
+  (literal_value)
+  (import_spec_list)
+  (comment)
+] @fold
@@ -0,0 +1,28 @@
+; Outline symbols for Go
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(function_declaration
+  name: (identifier) @name) @definition.function
+
+(method_declaration
+  name: (field_identifier) @name) @definition.method
+
+(type_spec
+  name: (type_identifier) @name
+  type: (struct_type)) @definition.struct
+
+(type_spec
+  name: (type_identifier) @name
+  type: (interface_type)) @definition.interface
+
+(type_spec
+  name: (type_identifier) @name) @definition.class
+
+(field_declaration
+  name: (field_identifier) @name) @definition.field
+
+(method_elem
+  name: (field_identifier) @name) @definition.method
+
+(const_spec
+  name: (identifier) @name) @definition.constant
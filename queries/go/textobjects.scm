@@ -0,0 +1,37 @@
+; Text objects for Go
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+[
+  (function_declaration
+    body: (_) @function.inner)
+  (method_declaration
+    body: (_) @function.inner)
+  (func_literal
+    body: (_) @function.inner)
+] @function.outer
+
+(type_declaration
+  (type_spec
+    type: (struct_type
+      (field_declaration_list) @class.inner))) @class.outer
+
+(type_declaration
+  (type_spec
+    type: (interface_type))) @class.outer
+
+(parameter_list
+  (_) @parameter.inner)
+
+(type_parameter_list
+  (_) @parameter.inner)
+
+(argument_list
+  (_) @argument.inner)
+
+(type_arguments
+  (_) @argument.inner)
+
+(comment) @comment.outer
+
+(block) @block.outer
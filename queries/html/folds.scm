@@ -0,0 +1,7 @@
+; Foldable regions for HTML
+[
+  (element)
+  (script_element)
+  (style_element)
+  (comment)
+] @fold
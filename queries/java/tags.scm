@@ -0,0 +1,27 @@
+; Outline symbols for Java
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(class_declaration
+  name: (identifier) @name) @definition.class
+
+(record_declaration
+  name: (identifier) @name) @definition.struct
+
+(interface_declaration
+  name: (identifier) @name) @definition.interface
+
+(enum_declaration
+  name: (identifier) @name) @definition.enum
+
+(enum_constant
+  name: (identifier) @name) @definition.enum_member
+
+(constructor_declaration
+  name: (identifier) @name) @definition.constructor
+
+(method_declaration
+  name: (identifier) @name) @definition.method
+
+(field_declaration
+  declarator: (variable_declarator
+    name: (identifier) @name)) @definition.field
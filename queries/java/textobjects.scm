@@ -0,0 +1,38 @@
+; Text objects for Java
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+[
+  (method_declaration
+    body: (_) @function.inner)
+  (constructor_declaration
+    body: (_) @function.inner)
+  (lambda_expression
+    body: (_) @function.inner)
+] @function.outer
+
+(method_declaration) @function.outer
+
+[
+  (class_declaration
+    body: (_) @class.inner)
+  (interface_declaration
+    body: (_) @class.inner)
+  (enum_declaration
+    body: (_) @class.inner)
+  (record_declaration
+    body: (_) @class.inner)
+] @class.outer
+
+(formal_parameters
+  (_) @parameter.inner)
+
+(argument_list
+  (_) @argument.inner)
+
+[
+  (line_comment)
+  (block_comment)
+] @comment.outer
+
+(block) @block.outer
@@ -0,0 +1,9 @@
+; Foldable regions for JavaScript
+[
+  (statement_block)
+  (class_body)
+  (object)
+  (array)
+  (switch_body)
+  (comment)
+] @fold
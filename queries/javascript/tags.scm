@@ -0,0 +1,30 @@
+; Outline symbols for JavaScript
+; @name is the symbol name, @definition.<kind> the whole definition
+
+[
+  (class
+    name: (_) @name)
+  (class_declaration
+    name: (_) @name)
+] @definition.class
+
+(method_definition
+  name: (_) @name) @definition.method
+
+(field_definition
+  property: (_) @name) @definition.field
+
+[
+  (function_declaration
+    name: (identifier) @name)
+  (generator_function_declaration
+    name: (identifier) @name)
+] @definition.function
+
+(variable_declarator
+  name: (identifier) @name
+  value: [(arrow_function) (function_expression)]) @definition.function
+
+(pair
+  key: (property_identifier) @name
+  value: [(arrow_function) (function_expression)]) @definition.function
@@ -0,0 +1,5 @@
+; Foldable regions for JSON
+[
+  (object)
+  (array)
+] @fold
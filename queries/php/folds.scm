@@ -0,0 +1,9 @@
+; Foldable regions for PHP
+[
+  (compound_statement)
+  (declaration_list)
+  (enum_declaration_list)
+  (switch_block)
+  (array_creation_expression)
+  (comment)
+] @fold
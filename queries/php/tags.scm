@@ -0,0 +1,30 @@
+; Outline symbols for PHP
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(namespace_definition
+  name: (namespace_name) @name) @definition.namespace
+
+(class_declaration
+  name: (name) @name) @definition.class
+
+[
+  (interface_declaration
+    name: (name) @name)
+  (trait_declaration
+    name: (name) @name)
+] @definition.interface
+
+(enum_declaration
+  name: (name) @name) @definition.enum
+
+(function_definition
+  name: (name) @name) @definition.function
+
+(method_declaration
+  name: (name) @name) @definition.method
+
+(property_element
+  (variable_name) @name) @definition.property
+
+(const_element
+  (name) @name) @definition.constant
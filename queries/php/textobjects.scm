@@ -0,0 +1,37 @@
+; Text objects for PHP
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+[
+  (function_definition
+    body: (_) @function.inner)
+  (method_declaration
+    body: (_) @function.inner)
+  (anonymous_function
+    body: (_) @function.inner)
+  (arrow_function
+    body: (_) @function.inner)
+] @function.outer
+
+(method_declaration) @function.outer
+
+[
+  (class_declaration
+    body: (_) @class.inner)
+  (interface_declaration
+    body: (_) @class.inner)
+  (trait_declaration
+    body: (_) @class.inner)
+  (enum_declaration
+    body: (_) @class.inner)
+] @class.outer
+
+(formal_parameters
+  (_) @parameter.inner)
+
+(arguments
+  (_) @argument.inner)
+
+(comment) @comment.outer
+
+(compound_statement) @block.outer
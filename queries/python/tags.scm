@@ -0,0 +1,25 @@
+; Outline symbols for Python
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(class_definition
+  name: (identifier) @name) @definition.class
+
+; Functions defined directly in a class body are methods
+(class_definition
+  body: (block
+    (function_definition
+      name: (identifier) @name) @definition.method))
+
+(class_definition
+  body: (block
+    (decorated_definition
+      definition: (function_definition
+        name: (identifier) @name) @definition.method)))
+
+(function_definition
+  name: (identifier) @name) @definition.function
+
+(module
+  (expression_statement
+    (assignment
+      left: (identifier) @name) @definition.variable))
@@ -0,0 +1,25 @@
+; Text objects for Python
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+(function_definition
+  body: (block) @function.inner) @function.outer
+
+(lambda
+  body: (_) @function.inner) @function.outer
+
+(class_definition
+  body: (block) @class.inner) @class.outer
+
+(parameters
+  (_) @parameter.inner)
+
+(lambda_parameters
+  (_) @parameter.inner)
+
+(argument_list
+  (_) @argument.inner)
+
+(comment) @comment.outer
+
+(block) @block.outer
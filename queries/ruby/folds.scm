@@ -0,0 +1,13 @@
+; Foldable regions for Ruby
+[
+  (method)
+  (singleton_method)
+  (class)
+  (module)
+  (do_block)
+  (block)
+  (if)
+  (case)
+  (hash)
+  (array)
+] @fold
@@ -0,0 +1,15 @@
+; Outline symbols for Ruby
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(module
+  name: (_) @name) @definition.module
+
+(class
+  name: (_) @name) @definition.class
+
+[
+  (method
+    name: (_) @name)
+  (singleton_method
+    name: (_) @name)
+] @definition.method
@@ -0,0 +1,55 @@
+; Text objects for Ruby
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+[
+  (method
+    body: (_) @function.inner)
+  (singleton_method
+    body: (_) @function.inner)
+  (lambda
+    body: (_) @function.inner)
+] @function.outer
+
+[
+  (method)
+  (singleton_method)
+] @function.outer
+
+[
+  (class
+    body: (_) @class.inner)
+  (module
+    body: (_) @class.inner)
+] @class.outer
+
+[
+  (class)
+  (module)
+] @class.outer
+
+(method_parameters
+  (_) @parameter.inner)
+
+(block_parameters
+  (_) @parameter.inner)
+
+(lambda_parameters
+  (_) @parameter.inner)
+
+(argument_list
+  (_) @argument.inner)
+
+(comment) @comment.outer
+
+[
+  (block
+    body: (_) @block.inner)
+  (do_block
+    body: (_) @block.inner)
+] @block.outer
+
+[
+  (block)
+  (do_block)
+] @block.outer
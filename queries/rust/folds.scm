@@ -0,0 +1,20 @@
+; Foldable regions for Rust
+; Each @fold node folds from its first line to its last (or the line before a
+; closing delimiter that sits on its own line)
+[
+  (mod_item)
+  (foreign_mod_item)
+  (function_item)
+  (struct_item)
+  (enum_item)
+  (union_item)
+  (trait_item)
+  (impl_item)
+  (macro_definition)
+  (macro_invocation)
+  (match_expression)
+  (block)
+  (use_declaration)
+  (array_expression)
+  (block_comment)
+] @fold
@@ -0,0 +1,56 @@
+; Outline symbols for Rust
+; @name is the symbol name, @definition.<kind> the whole definition
+
+(mod_item
+  name: (identifier) @name) @definition.module
+
+(struct_item
+  name: (type_identifier) @name) @definition.struct
+
+(union_item
+  name: (type_identifier) @name) @definition.struct
+
+(enum_item
+  name: (type_identifier) @name) @definition.enum
+
+(enum_variant
+  name: (identifier) @name) @definition.enum_member
+
+(field_declaration
+  name: (field_identifier) @name) @definition.field
+
+(trait_item
+  name: (type_identifier) @name) @definition.interface
+
+(impl_item
+  type: (_) @name) @definition.object
+
+(type_item
+  name: (type_identifier) @name) @definition.type_parameter
+
+; Functions inside impl and trait blocks are methods
+(impl_item
+  body: (declaration_list
+    (function_item
+      name: (identifier) @name) @definition.method))
+
+(trait_item
+  body: (declaration_list
+    [
+      (function_item
+        name: (identifier) @name)
+      (function_signature_item
+        name: (identifier) @name)
+    ] @definition.method))
+
+(function_item
+  name: (identifier) @name) @definition.function
+
+(const_item
+  name: (identifier) @name) @definition.constant
+
+(static_item
+  name: (identifier) @name) @definition.constant
+
+(macro_definition
+  name: (identifier) @name) @definition.function
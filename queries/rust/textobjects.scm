@@ -0,0 +1,50 @@
+; Text objects for Rust
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+(function_item
+  body: (_) @function.inner) @function.outer
+
+(function_signature_item) @function.outer
+
+(closure_expression
+  body: (_) @function.inner) @function.outer
+
+[
+  (struct_item
+    body: (_) @class.inner)
+  (enum_item
+    body: (_) @class.inner)
+  (union_item
+    body: (_) @class.inner)
+  (trait_item
+    body: (_) @class.inner)
+  (impl_item
+    body: (_) @class.inner)
+  (mod_item
+    body: (_) @class.inner)
+] @class.outer
+
+(struct_item) @class.outer
+
+(parameters
+  (_) @parameter.inner)
+
+(closure_parameters
+  (_) @parameter.inner)
+
+(type_parameters
+  (_) @parameter.inner)
+
+(arguments
+  (_) @argument.inner)
+
+(type_arguments
+  (_) @argument.inner)
+
+[
+  (line_comment)
+  (block_comment)
+] @comment.outer
+
+(block) @block.outer
@@ -0,0 +1,11 @@
+; Foldable regions for TypeScript
+[
+  (statement_block)
+  (class_body)
+  (object)
+  (array)
+  (switch_body)
+  (object_type)
+  (enum_body)
+  (comment)
+] @fold
@@ -0,0 +1,48 @@
+; Outline symbols for TypeScript
+; @name is the symbol name, @definition.<kind> the whole definition
+
+[
+  (class_declaration
+    name: (_) @name)
+  (abstract_class_declaration
+    name: (_) @name)
+] @definition.class
+
+(interface_declaration
+  name: (_) @name) @definition.interface
+
+(enum_declaration
+  name: (_) @name) @definition.enum
+
+(type_alias_declaration
+  name: (_) @name) @definition.type_parameter
+
+(internal_module
+  name: (_) @name) @definition.namespace
+
+[
+  (method_definition
+    name: (_) @name)
+  (method_signature
+    name: (_) @name)
+  (abstract_method_signature
+    name: (_) @name)
+] @definition.method
+
+[
+  (public_field_definition
+    name: (_) @name)
+  (property_signature
+    name: (_) @name)
+] @definition.property
+
+[
+  (function_declaration
+    name: (identifier) @name)
+  (generator_function_declaration
+    name: (identifier) @name)
+] @definition.function
+
+(variable_declarator
+  name: (identifier) @name
+  value: [(arrow_function) (function_expression)]) @definition.function
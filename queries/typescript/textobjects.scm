@@ -0,0 +1,45 @@
+; Text objects for TypeScript
+; @<object>.outer is the whole object, @<object>.inner its contents; parameters
+; and arguments only capture .inner (the separating comma is added to .outer)
+
+[
+  (function_declaration
+    body: (_) @function.inner)
+  (function_expression
+    body: (_) @function.inner)
+  (generator_function_declaration
+    body: (_) @function.inner)
+  (arrow_function
+    body: (_) @function.inner)
+  (method_definition
+    body: (_) @function.inner)
+] @function.outer
+
+[
+  (class_declaration
+    body: (_) @class.inner)
+  (abstract_class_declaration
+    body: (_) @class.inner)
+  (class
+    body: (_) @class.inner)
+  (interface_declaration
+    body: (_) @class.inner)
+  (enum_declaration
+    body: (_) @class.inner)
+] @class.outer
+
+(formal_parameters
+  (_) @parameter.inner)
+
+(type_parameters
+  (_) @parameter.inner)
+
+(arguments
+  (_) @argument.inner)
+
+(type_arguments
+  (_) @argument.inner)
+
+(comment) @comment.outer
+
+(statement_block) @block.outer
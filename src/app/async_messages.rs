@@ -9,13 +9,14 @@
 
 use crate::model::event::BufferId;
 use crate::services::async_bridge::{LspMessageType, LspProgressValue, LspServerStatus};
+use crate::services::lsp::client::LspFeature;
 use crate::view::file_tree::{FileTreeView, NodeId};
 use lsp_types::{Diagnostic, InlayHint};
 use serde_json::Value;
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
-use super::types::{LspMessageEntry, LspProgressInfo};
+use super::types::{LspLogKind, LspProgressInfo};
 use super::Editor;
 
 // =============================================================================
@@ -48,6 +49,8 @@ impl Editor {
             diagnostics,
             &self.theme,
         );
+        self.apply_inline_diagnostics(buffer_id, diagnostics);
+        self.request_quick_fixes(buffer_id, uri, diagnostics);
         Some(buffer_id)
     }
 }
@@ -88,19 +91,62 @@ impl Editor {
     }
 
     /// Handle LSP diagnostics (push model)
-    pub(super) fn handle_lsp_diagnostics(&mut self, uri: String, diagnostics: Vec<Diagnostic>) {
+    pub(super) fn handle_lsp_diagnostics(
+        &mut self,
+        uri: String,
+        server: String,
+        diagnostics: Vec<Diagnostic>,
+    ) {
         tracing::debug!(
-            "Processing {} LSP diagnostics for {}",
+            "Processing {} LSP diagnostics for {} from {}",
             diagnostics.len(),
-            uri
+            uri,
+            server
         );
-        self.store_and_apply_diagnostics(uri, diagnostics);
+        self.merge_server_diagnostics(uri, server, diagnostics);
+    }
+
+    /// Replace one server's diagnostics for a file and apply the merged set
+    ///
+    /// Servers whose config excludes diagnostics are ignored.
+    fn merge_server_diagnostics(
+        &mut self,
+        uri: String,
+        server: String,
+        diagnostics: Vec<Diagnostic>,
+    ) {
+        let wanted = match (self.lsp.as_ref(), self.language_for_uri(&uri)) {
+            (Some(lsp), Some(language)) => {
+                lsp.server_handles_feature(&language, &server, LspFeature::Diagnostics)
+            }
+            _ => true,
+        };
+        if !wanted {
+            tracing::debug!("Ignoring diagnostics from {} for {}", server, uri);
+            return;
+        }
+
+        let by_source = self.diagnostics_by_source.entry(uri.clone()).or_default();
+        by_source.update(&server, diagnostics);
+        let merged = by_source.merged();
+        if by_source.is_empty() {
+            self.diagnostics_by_source.remove(&uri);
+        }
+        self.store_and_apply_diagnostics(uri, merged);
+    }
+
+    /// Language of the open buffer for a file URI
+    fn language_for_uri(&self, uri: &str) -> Option<String> {
+        let buffer_id = self.find_buffer_by_uri(uri)?;
+        let path = self.buffer_metadata.get(&buffer_id)?.file_path()?;
+        crate::services::lsp::manager::detect_language(path, &self.config.languages)
     }
 
     /// Handle LSP pulled diagnostics (pull model - LSP 3.17+)
     pub(super) fn handle_lsp_pulled_diagnostics(
         &mut self,
         uri: String,
+        server: String,
         result_id: Option<String>,
         diagnostics: Vec<Diagnostic>,
         unchanged: bool,
@@ -126,7 +172,7 @@ impl Editor {
             self.diagnostic_result_ids.insert(uri.clone(), result_id);
         }
 
-        self.store_and_apply_diagnostics(uri, diagnostics);
+        self.merge_server_diagnostics(uri, server, diagnostics);
     }
 }
 
@@ -180,6 +226,9 @@ impl Editor {
             language
         );
 
+        // Tokens computed before the project loaded may be incomplete
+        self.schedule_semantic_tokens_for_language(&language);
+
         // Skip if inlay hints are disabled
         if !self.config.editor.enable_inlay_hints {
             return;
@@ -189,7 +238,7 @@ impl Editor {
             return;
         };
 
-        let Some(client) = lsp.get_or_spawn(&language) else {
+        let Some(client) = lsp.handle_for_feature(&language, LspFeature::InlayHints) else {
             return;
         };
 
@@ -236,9 +285,29 @@ impl Editor {
     pub(super) fn handle_lsp_progress(
         &mut self,
         language: String,
+        server: String,
         token: String,
         value: LspProgressValue,
     ) {
+        let title = self
+            .lsp_progress
+            .get(&token)
+            .map(|info| info.title.clone())
+            .unwrap_or_default();
+        let entry = match &value {
+            LspProgressValue::Begin {
+                title,
+                message,
+                percentage,
+            } => progress_text(title, "started", message, *percentage),
+            LspProgressValue::Report {
+                message,
+                percentage,
+            } => progress_text(&title, "", message, *percentage),
+            LspProgressValue::End { message } => progress_text(&title, "done", message, None),
+        };
+        self.record_lsp_log(language.clone(), server, LspLogKind::Progress, entry);
+
         match value {
             LspProgressValue::Begin {
                 title,
@@ -277,21 +346,16 @@ impl Editor {
     pub(super) fn handle_lsp_window_message(
         &mut self,
         language: String,
+        server: String,
         message_type: LspMessageType,
         message: String,
     ) {
-        // Add to window messages list
-        self.lsp_window_messages.push(LspMessageEntry {
-            language: language.clone(),
-            message_type,
-            message: message.clone(),
-            timestamp: Instant::now(),
-        });
-
-        // Keep only last 100 messages
-        if self.lsp_window_messages.len() > 100 {
-            self.lsp_window_messages.remove(0);
-        }
+        self.record_lsp_log(
+            language.clone(),
+            server,
+            LspLogKind::Message(message_type),
+            message.clone(),
+        );
 
         // Show important messages in status bar
         match message_type {
@@ -308,32 +372,28 @@ impl Editor {
     pub(super) fn handle_lsp_log_message(
         &mut self,
         language: String,
+        server: String,
         message_type: LspMessageType,
         message: String,
     ) {
-        self.lsp_log_messages.push(LspMessageEntry {
-            language,
-            message_type,
-            message,
-            timestamp: Instant::now(),
-        });
-
-        // Keep only last 500 log messages
-        if self.lsp_log_messages.len() > 500 {
-            self.lsp_log_messages.remove(0);
-        }
+        self.record_lsp_log(language, server, LspLogKind::Log(message_type), message);
     }
 
     /// Handle LSP server status update
-    pub(super) fn handle_lsp_status_update(&mut self, language: String, status: LspServerStatus) {
+    pub(super) fn handle_lsp_status_update(
+        &mut self,
+        language: String,
+        server: String,
+        status: LspServerStatus,
+    ) {
         use crate::services::async_bridge::LspServerStatus;
 
         // Get old status for event
-        let old_status = self.lsp_server_statuses.get(&language).cloned();
+        let key = (language.clone(), server.clone());
+        let old_status = self.lsp_server_statuses.get(&key).cloned();
 
         // Update server status
-        self.lsp_server_statuses
-            .insert(language.clone(), status.clone());
+        self.lsp_server_statuses.insert(key, status);
         self.update_lsp_status_from_server_statuses();
 
         // Handle server crash - trigger auto-restart
@@ -345,7 +405,7 @@ impl Editor {
 
             if was_running {
                 if let Some(lsp) = self.lsp.as_mut() {
-                    let message = lsp.handle_server_crash(&language);
+                    let message = lsp.handle_server_crash(&language, &server);
                     self.status_message = Some(message);
                 }
             }
@@ -373,6 +433,7 @@ impl Editor {
             crate::model::control_event::events::LSP_STATUS_CHANGED.name,
             serde_json::json!({
                 "language": language,
+                "server": server,
                 "old_status": old_status_str,
                 "status": status_str
             }),
@@ -694,8 +755,15 @@ impl Editor {
                         &self.config.languages,
                     ) {
                         if let Some(lsp) = self.lsp.as_mut() {
-                            if let Some(handle) = lsp.get_or_spawn(&lang_id) {
-                                let _ = handle.did_open(uri, content, lang_id);
+                            if lsp.get_or_spawn(&lang_id).is_some() {
+                                lsp.add_workspace_folder(&lang_id, &path);
+                                for handle in lsp.language_handles(&lang_id) {
+                                    let _ = handle.did_open(
+                                        uri.clone(),
+                                        content.clone(),
+                                        lang_id.clone(),
+                                    );
+                                }
                             }
                         }
                     }
@@ -704,3 +772,21 @@ impl Editor {
         }
     }
 }
+
+/// Describe a progress notification for a server's log, e.g. "Indexing: 3/10 (30%)"
+fn progress_text(
+    title: &str,
+    event: &str,
+    message: &Option<String>,
+    percentage: Option<u32>,
+) -> String {
+    let parts: Vec<&str> = [title, event, message.as_deref().unwrap_or_default()]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+    let mut text = parts.join(": ");
+    if let Some(percentage) = percentage {
+        text.push_str(&format!(" ({}%)", percentage));
+    }
+    text
+}
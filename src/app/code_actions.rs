@@ -0,0 +1,702 @@
+//! Code actions, server commands and workspace edits
+//!
+//! Code actions from every server that offers them are listed in a popup. Choosing one
+//! applies its `WorkspaceEdit` (resolving it first with `codeAction/resolve` when the
+//! server left it out) and then runs its command with `workspace/executeCommand` on the
+//! server that offered it. Servers also push edits of their own with
+//! `workspace/applyEdit`, typically while running such a command.
+//!
+//! Text edits to files that aren't open are applied in background buffers and left
+//! unsaved, so they can be reviewed and undone. Resource operations (creating, renaming
+//! and deleting files) are performed on disk right away.
+
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use lsp_types::{
+    ApplyWorkspaceEditResponse, CodeAction, CodeActionOrCommand, CodeActionProviderCapability,
+    Command, DocumentChangeOperation, DocumentChanges, OneOf,
+    OptionalVersionedTextDocumentIdentifier, ResourceOp, TextDocumentEdit, TextEdit, Uri,
+    WorkspaceEdit,
+};
+
+use super::{uri_to_path, BufferMetadata, Editor};
+use crate::model::event::BufferId;
+use crate::view::popup::{Popup, PopupListItem, PopupPosition};
+
+/// Title of the code actions popup, used to recognize it when an item is confirmed
+pub(super) const CODE_ACTIONS_POPUP_TITLE: &str = "Code Actions";
+
+/// A code action received from a server
+#[derive(Debug, Clone)]
+pub(super) struct OfferedCodeAction {
+    pub language: String,
+    /// Name of the server that offered the action, which also runs its command
+    pub server: String,
+    pub action: CodeActionOrCommand,
+}
+
+/// A workspace edit that failed, possibly after some of its changes were applied
+#[derive(Debug)]
+pub(super) struct WorkspaceEditError {
+    /// Index of the change that failed
+    pub failed_change: usize,
+    /// Whether changes before the failed one were applied
+    pub partially_applied: bool,
+    pub error: io::Error,
+}
+
+impl std::fmt::Display for WorkspaceEditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl From<WorkspaceEditError> for io::Error {
+    fn from(e: WorkspaceEditError) -> Self {
+        e.error
+    }
+}
+
+/// What a pending code action follow-up request was sent for
+#[derive(Debug, Clone)]
+pub(super) enum PendingCodeActionRequest {
+    /// `codeAction/resolve` for an action chosen without its edit
+    Resolve { language: String, server: String },
+    /// `workspace/executeCommand`
+    Execute { title: String },
+}
+
+impl Editor {
+    /// Handle code actions response from LSP
+    ///
+    /// With several servers the responses are collected until every server has
+    /// answered, then shown together.
+    pub(super) fn handle_code_actions_response(
+        &mut self,
+        request_id: u64,
+        language: String,
+        server: String,
+        actions: Vec<CodeActionOrCommand>,
+    ) {
+        // Check if this response is for the current pending request
+        if self.pending_code_actions_request != Some(request_id) {
+            tracing::debug!("Ignoring stale code actions response: {}", request_id);
+            return;
+        }
+
+        self.code_actions_received
+            .extend(actions.into_iter().map(|action| OfferedCodeAction {
+                language: language.clone(),
+                server: server.clone(),
+                action,
+            }));
+        self.code_actions_responses_remaining =
+            self.code_actions_responses_remaining.saturating_sub(1);
+        if self.code_actions_responses_remaining > 0 {
+            return;
+        }
+        let actions = std::mem::take(&mut self.code_actions_received);
+
+        self.pending_code_actions_request = None;
+        self.lsp_status.clear();
+
+        if actions.is_empty() {
+            self.set_status_message("No code actions available".to_string());
+            return;
+        }
+
+        let items = actions
+            .iter()
+            .enumerate()
+            .map(|(index, offered)| {
+                let item = match &offered.action {
+                    CodeActionOrCommand::Command(command) => {
+                        PopupListItem::new(command.title.clone())
+                    }
+                    CodeActionOrCommand::CodeAction(action) => {
+                        let item = PopupListItem::new(action.title.clone());
+                        match &action.disabled {
+                            Some(disabled) => item.with_detail(disabled.reason.clone()),
+                            None if action.is_preferred == Some(true) => {
+                                item.with_icon("*".to_string())
+                            }
+                            None => item,
+                        }
+                    }
+                };
+                item.with_data(index.to_string())
+            })
+            .collect();
+
+        let popup = Popup::list(items, &self.theme)
+            .with_title(CODE_ACTIONS_POPUP_TITLE.to_string())
+            .with_position(PopupPosition::BelowCursor)
+            .with_width(60)
+            .with_max_height(15);
+
+        tracing::info!("Showing code actions popup with {} actions", actions.len());
+        self.code_actions_offered = actions;
+        if let Some(state) = self.buffers.get_mut(&self.active_buffer()) {
+            state.popups.show(popup);
+        }
+    }
+
+    /// Apply the code action chosen in the popup (`data` is its index)
+    pub(super) fn confirm_code_action(&mut self, data: &str) {
+        let offered = data
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| self.code_actions_offered.get(index).cloned());
+        self.code_actions_offered.clear();
+        let Some(offered) = offered else {
+            return;
+        };
+
+        match offered.action {
+            CodeActionOrCommand::Command(command) => {
+                self.execute_lsp_command(&offered.language, &offered.server, command);
+            }
+            CodeActionOrCommand::CodeAction(action) => {
+                if let Some(disabled) = &action.disabled {
+                    self.set_status_message(format!(
+                        "'{}' is not available: {}",
+                        action.title, disabled.reason
+                    ));
+                } else if action.edit.is_none()
+                    && self.server_resolves_code_actions(&offered.language, &offered.server)
+                {
+                    self.resolve_code_action(offered.language, offered.server, action);
+                } else {
+                    self.apply_code_action(&offered.language, &offered.server, action);
+                }
+            }
+        }
+    }
+
+    /// Whether a server fills in code action edits on `codeAction/resolve`
+    fn server_resolves_code_actions(&mut self, language: &str, server: &str) -> bool {
+        self.lsp
+            .as_mut()
+            .and_then(|lsp| lsp.server_handle(language, server))
+            .and_then(|handle| handle.capabilities())
+            .is_some_and(|caps| {
+                matches!(
+                    caps.code_action_provider,
+                    Some(CodeActionProviderCapability::Options(options))
+                        if options.resolve_provider == Some(true)
+                )
+            })
+    }
+
+    fn resolve_code_action(&mut self, language: String, server: String, action: CodeAction) {
+        let request_id = self.next_lsp_request_id;
+        let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.server_handle(&language, &server))
+        else {
+            self.set_status_message(format!("Language server for {} is not running", language));
+            return;
+        };
+        let title = action.title.clone();
+        if let Err(e) = handle.resolve_code_action(request_id, action) {
+            tracing::warn!("Failed to send code action resolve request: {}", e);
+            return;
+        }
+        self.next_lsp_request_id += 1;
+        self.pending_code_action_requests.insert(
+            request_id,
+            PendingCodeActionRequest::Resolve { language, server },
+        );
+        self.set_status_message(format!("Preparing '{}'...", title));
+    }
+
+    /// Apply a code action's edit, then run its command
+    fn apply_code_action(&mut self, language: &str, server: &str, action: CodeAction) {
+        if action.edit.is_none() && action.command.is_none() {
+            self.set_status_message(format!("'{}' has nothing to apply", action.title));
+            return;
+        }
+
+        if let Some(edit) = action.edit {
+            match self.apply_workspace_edit(edit) {
+                Ok(changes) => self.set_status_message(format!(
+                    "Applied '{}' ({} changes)",
+                    action.title, changes
+                )),
+                Err(e) => {
+                    self.set_status_message(format!("Failed to apply '{}': {}", action.title, e));
+                    return;
+                }
+            }
+        }
+
+        if let Some(command) = action.command {
+            self.execute_lsp_command(language, server, command);
+        }
+    }
+
+    /// Run a command on the server that offered it (`workspace/executeCommand`)
+    pub(super) fn execute_lsp_command(&mut self, language: &str, server: &str, command: Command) {
+        let request_id = self.next_lsp_request_id;
+        let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.server_handle(language, server))
+        else {
+            self.set_status_message(format!("Language server for {} is not running", language));
+            return;
+        };
+        let title = command.title.clone();
+        if let Err(e) = handle.execute_command(request_id, command) {
+            tracing::warn!("Failed to send execute command request: {}", e);
+            return;
+        }
+        self.next_lsp_request_id += 1;
+        self.pending_code_action_requests.insert(
+            request_id,
+            PendingCodeActionRequest::Execute {
+                title: title.clone(),
+            },
+        );
+        self.set_status_message(format!("Running '{}'...", title));
+    }
+
+    /// Handle the resolved edit of a chosen code action
+    pub(super) fn handle_code_action_resolved(
+        &mut self,
+        request_id: u64,
+        result: Result<CodeAction, String>,
+    ) {
+        let Some(PendingCodeActionRequest::Resolve { language, server }) =
+            self.pending_code_action_requests.remove(&request_id)
+        else {
+            tracing::debug!(
+                "Ignoring stale code action resolve response: {}",
+                request_id
+            );
+            return;
+        };
+
+        match result {
+            Ok(action) => self.apply_code_action(&language, &server, action),
+            Err(e) => self.set_status_message(format!("Code action failed: {}", e)),
+        }
+    }
+
+    /// Handle the end of a server command
+    pub(super) fn handle_command_executed(&mut self, request_id: u64, result: Result<(), String>) {
+        let Some(PendingCodeActionRequest::Execute { title }) =
+            self.pending_code_action_requests.remove(&request_id)
+        else {
+            tracing::debug!("Ignoring stale execute command response: {}", request_id);
+            return;
+        };
+
+        match result {
+            // Keep the message of any edit the command applied
+            Ok(()) => {
+                let running = format!("Running '{}'...", title);
+                if self.status_message.as_deref() == Some(running.as_str()) {
+                    self.set_status_message(format!("Ran '{}'", title));
+                }
+            }
+            Err(e) => self.set_status_message(format!("'{}' failed: {}", title, e)),
+        }
+    }
+
+    /// Handle a workspace edit pushed by a server (`workspace/applyEdit`)
+    pub(super) fn handle_lsp_apply_edit(
+        &mut self,
+        language: String,
+        server: String,
+        label: Option<String>,
+        edit: WorkspaceEdit,
+    ) -> ApplyWorkspaceEditResponse {
+        tracing::info!(
+            "Applying workspace edit from {} ({}): {:?}",
+            server,
+            language,
+            label
+        );
+        let label = label.unwrap_or_else(|| "Applied workspace edit".to_string());
+        match self.apply_workspace_edit(edit) {
+            Ok(changes) => {
+                self.set_status_message(format!("{} ({} changes)", label, changes));
+                ApplyWorkspaceEditResponse {
+                    applied: true,
+                    failure_reason: None,
+                    failed_change: None,
+                }
+            }
+            Err(e) => {
+                if e.partially_applied {
+                    self.set_status_message(format!(
+                        "Workspace edit stopped at change {}: {}",
+                        e.failed_change + 1,
+                        e
+                    ));
+                } else {
+                    self.set_status_message(format!("Failed to apply workspace edit: {}", e));
+                }
+                ApplyWorkspaceEditResponse {
+                    applied: e.partially_applied,
+                    failure_reason: Some(e.to_string()),
+                    failed_change: Some(e.failed_change as u32),
+                }
+            }
+        }
+    }
+
+    /// Apply a `WorkspaceEdit` and return the number of changes made
+    ///
+    /// `documentChanges` is preferred over `changes` when a server sends both, as the
+    /// specification requires. Operations are applied in order, so an edit can target
+    /// a file created or renamed earlier in the same workspace edit. The paths of all
+    /// operations are checked before any is applied; an operation can still fail on
+    /// disk, leaving the operations before it applied.
+    pub(super) fn apply_workspace_edit(
+        &mut self,
+        edit: WorkspaceEdit,
+    ) -> Result<usize, WorkspaceEditError> {
+        tracing::debug!(
+            "Applying WorkspaceEdit: changes={:?}, document_changes={:?}",
+            edit.changes.as_ref().map(|c| c.len()),
+            edit.document_changes.as_ref().map(|dc| match dc {
+                DocumentChanges::Edits(e) => format!("{} edits", e.len()),
+                DocumentChanges::Operations(o) => format!("{} operations", o.len()),
+            })
+        );
+
+        let operations: Vec<DocumentChangeOperation> = match (edit.document_changes, edit.changes) {
+            (Some(DocumentChanges::Edits(edits)), _) => edits
+                .into_iter()
+                .map(DocumentChangeOperation::Edit)
+                .collect(),
+            (Some(DocumentChanges::Operations(operations)), _) => operations,
+            (None, Some(changes)) => changes
+                .into_iter()
+                .map(|(uri, edits)| {
+                    DocumentChangeOperation::Edit(TextDocumentEdit {
+                        text_document: OptionalVersionedTextDocumentIdentifier {
+                            uri,
+                            version: None,
+                        },
+                        edits: edits.into_iter().map(OneOf::Left).collect(),
+                    })
+                })
+                .collect(),
+            (None, None) => Vec::new(),
+        };
+
+        if let Err((failed_change, error)) = self.validate_workspace_edit(&operations) {
+            return Err(WorkspaceEditError {
+                failed_change,
+                partially_applied: false,
+                error,
+            });
+        }
+
+        let mut total_changes = 0;
+        for (index, operation) in operations.into_iter().enumerate() {
+            let result = match operation {
+                DocumentChangeOperation::Edit(document_edit) => {
+                    // Extract TextEdit from OneOf<TextEdit, AnnotatedTextEdit>
+                    let edits = document_edit
+                        .edits
+                        .into_iter()
+                        .map(|edit| match edit {
+                            OneOf::Left(text_edit) => text_edit,
+                            OneOf::Right(annotated) => annotated.text_edit,
+                        })
+                        .collect();
+                    self.apply_document_edits(&document_edit.text_document.uri, edits)
+                }
+                DocumentChangeOperation::Op(operation) => {
+                    self.apply_resource_operation(operation).map(usize::from)
+                }
+            };
+            match result {
+                Ok(changes) => total_changes += changes,
+                Err(error) => {
+                    return Err(WorkspaceEditError {
+                        failed_change: index,
+                        partially_applied: total_changes > 0,
+                        error,
+                    })
+                }
+            }
+        }
+
+        Ok(total_changes)
+    }
+
+    /// Check the paths of a workspace edit's operations before applying any of them
+    ///
+    /// Returns the index of the first operation that would fail, and why.
+    fn validate_workspace_edit(
+        &self,
+        operations: &[DocumentChangeOperation],
+    ) -> Result<(), (usize, io::Error)> {
+        let mut files = PlannedFiles::default();
+        for (index, operation) in operations.iter().enumerate() {
+            self.validate_document_change(operation, &mut files)
+                .map_err(|error| (index, error))?;
+        }
+        Ok(())
+    }
+
+    /// Check one operation against the files as the operations before it leave them
+    fn validate_document_change(
+        &self,
+        operation: &DocumentChangeOperation,
+        files: &mut PlannedFiles,
+    ) -> io::Result<()> {
+        match operation {
+            DocumentChangeOperation::Edit(document_edit) => {
+                // Edits to other kinds of documents are skipped when applied
+                let Ok(path) = uri_to_path(&document_edit.text_document.uri) else {
+                    return Ok(());
+                };
+                let open = self
+                    .buffers
+                    .values()
+                    .any(|state| state.buffer.file_path() == Some(path.as_path()));
+                if !open && !files.exists(&path) {
+                    return Err(missing_file(&path));
+                }
+            }
+            DocumentChangeOperation::Op(ResourceOp::Create(create)) => {
+                let path = resource_path(&create.uri)?;
+                let overwrite = create.options.as_ref().and_then(|o| o.overwrite) == Some(true);
+                if files.exists(&path) {
+                    if !overwrite {
+                        return Ok(());
+                    }
+                    self.ensure_no_unsaved_changes(&canonical(&path))?;
+                }
+                files.create(path);
+            }
+            DocumentChangeOperation::Op(ResourceOp::Rename(rename)) => {
+                let old_path = resource_path(&rename.old_uri)?;
+                let new_path = resource_path(&rename.new_uri)?;
+                let overwrite = rename.options.as_ref().and_then(|o| o.overwrite) == Some(true);
+                if files.exists(&new_path) {
+                    if !overwrite {
+                        return Ok(());
+                    }
+                    self.ensure_no_unsaved_changes(&canonical(&new_path))?;
+                }
+                if !files.exists(&old_path) {
+                    return Err(missing_file(&old_path));
+                }
+                files.remove(old_path);
+                files.create(new_path);
+            }
+            DocumentChangeOperation::Op(ResourceOp::Delete(delete)) => {
+                let path = resource_path(&delete.uri)?;
+                if !files.exists(&path) {
+                    let ignore = delete.options.as_ref().and_then(|o| o.ignore_if_not_exists);
+                    return if ignore == Some(true) {
+                        Ok(())
+                    } else {
+                        Err(missing_file(&path))
+                    };
+                }
+                self.ensure_no_unsaved_changes(&canonical(&path))?;
+                files.remove(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply text edits to a document, opening it in a background buffer if needed
+    fn apply_document_edits(&mut self, uri: &Uri, edits: Vec<TextEdit>) -> io::Result<usize> {
+        let Ok(path) = uri_to_path(uri) else {
+            tracing::warn!("Skipping edits to non-file URI {}", uri.as_str());
+            return Ok(0);
+        };
+        tracing::info!("Applying {} edits to {:?}", edits.len(), path);
+        let buffer_id = self.open_file_no_focus(&path)?;
+        self.apply_lsp_text_edits(buffer_id, edits)
+    }
+
+    /// Create, rename or delete a file; returns whether anything was done
+    ///
+    /// Existing targets are left alone unless the operation asks to overwrite them.
+    /// Operations that would drop unsaved changes fail, and so does deleting a
+    /// directory that isn't empty.
+    fn apply_resource_operation(&mut self, operation: ResourceOp) -> io::Result<bool> {
+        match operation {
+            ResourceOp::Create(create) => {
+                let path = resource_path(&create.uri)?;
+                let overwrite = create.options.as_ref().and_then(|o| o.overwrite) == Some(true);
+                if path.exists() && !overwrite {
+                    tracing::debug!("Not creating existing file {:?}", path);
+                    return Ok(false);
+                }
+                if path.exists() {
+                    self.ensure_no_unsaved_changes(&path.canonicalize()?)?;
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, "")?;
+                self.open_file_no_focus(&path)?;
+                Ok(true)
+            }
+            ResourceOp::Rename(rename) => {
+                let old_path = resource_path(&rename.old_uri)?;
+                let new_path = resource_path(&rename.new_uri)?;
+                let overwrite = rename.options.as_ref().and_then(|o| o.overwrite) == Some(true);
+                if new_path.exists() && !overwrite {
+                    tracing::debug!("Not renaming {:?} over existing {:?}", old_path, new_path);
+                    return Ok(false);
+                }
+                if new_path.exists() {
+                    self.ensure_no_unsaved_changes(&new_path.canonicalize()?)?;
+                }
+                let old_path = old_path.canonicalize()?;
+                if let Some(parent) = new_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::rename(&old_path, &new_path)?;
+                let new_path = new_path.canonicalize()?;
+                self.move_buffers_after_rename(&old_path, &new_path);
+                Ok(true)
+            }
+            ResourceOp::Delete(delete) => {
+                let path = resource_path(&delete.uri)?;
+                let options = delete.options.as_ref();
+                if !path.exists() {
+                    return if options.and_then(|o| o.ignore_if_not_exists) == Some(true) {
+                        Ok(false)
+                    } else {
+                        Err(io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("Cannot delete missing {}", path.display()),
+                        ))
+                    };
+                }
+                let path = path.canonicalize()?;
+                self.ensure_no_unsaved_changes(&path)?;
+                if path.is_dir() {
+                    // Even with `recursive`, only empty directories go without asking
+                    std::fs::remove_dir(&path).map_err(|e| {
+                        io::Error::new(
+                            e.kind(),
+                            format!("Cannot delete directory {}: {}", path.display(), e),
+                        )
+                    })?;
+                } else {
+                    std::fs::remove_file(&path)?;
+                }
+
+                // The server asked for the file to go and its buffers have no
+                // unsaved changes, so they go without asking
+                let closed: Vec<BufferId> = self
+                    .buffers
+                    .iter()
+                    .filter(|(_, state)| {
+                        state
+                            .buffer
+                            .file_path()
+                            .is_some_and(|file| file.starts_with(&path))
+                    })
+                    .map(|(id, _)| *id)
+                    .collect();
+                for buffer_id in closed {
+                    self.force_close_buffer(buffer_id)?;
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    /// Fail if a buffer of the file at `path`, or of a file under it, has unsaved changes
+    fn ensure_no_unsaved_changes(&self, path: &Path) -> io::Result<()> {
+        let modified = self.buffers.values().find_map(|state| {
+            let file = state.buffer.file_path()?;
+            (file.starts_with(path) && state.buffer.is_modified()).then_some(file)
+        });
+        match modified {
+            Some(file) => Err(io::Error::other(format!(
+                "{} has unsaved changes",
+                file.display()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Move open buffers along with a renamed file, or with the files of a renamed directory
+    pub(super) fn move_buffers_after_rename(&mut self, old_path: &Path, new_path: &Path) {
+        let moved: Vec<(BufferId, PathBuf)> = self
+            .buffers
+            .iter()
+            .filter_map(|(id, state)| {
+                let path = state.buffer.file_path()?;
+                let relative = path.strip_prefix(old_path).ok()?;
+                Some((*id, new_path.join(relative)))
+            })
+            .collect();
+        for (buffer_id, path) in moved {
+            self.move_buffer_to_path(buffer_id, &path);
+        }
+    }
+
+    /// Point a buffer at the new path of its renamed file
+    ///
+    /// Language servers see the old document closed and the new one opened.
+    fn move_buffer_to_path(&mut self, buffer_id: BufferId, path: &Path) {
+        self.notify_lsp_file_closed(buffer_id);
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.buffer.set_file_path(path.to_path_buf());
+        }
+        let mut metadata = BufferMetadata::with_file(path.to_path_buf(), &self.working_dir);
+        self.notify_lsp_file_opened(path, buffer_id, &mut metadata);
+        self.buffer_metadata.insert(buffer_id, metadata);
+        self.watch_file(path);
+    }
+}
+
+/// File path of a resource operation's URI
+fn resource_path(uri: &Uri) -> io::Result<PathBuf> {
+    uri_to_path(uri).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Files created and removed by the operations of a workspace edit checked so far
+#[derive(Default)]
+struct PlannedFiles {
+    created: HashSet<PathBuf>,
+    removed: HashSet<PathBuf>,
+}
+
+impl PlannedFiles {
+    fn exists(&self, path: &Path) -> bool {
+        !self.removed.contains(path) && (self.created.contains(path) || path.exists())
+    }
+
+    fn create(&mut self, path: PathBuf) {
+        self.removed.remove(&path);
+        self.created.insert(path);
+    }
+
+    fn remove(&mut self, path: PathBuf) {
+        self.created.remove(&path);
+        self.removed.insert(path);
+    }
+}
+
+fn missing_file(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+/// `path` canonicalized, or as given when it doesn't exist yet
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
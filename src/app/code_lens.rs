@@ -0,0 +1,758 @@
+//! Code lenses: requesting them, showing them above their lines and running them
+//!
+//! Lenses are requested with `textDocument/codeLens` when a buffer is opened, after
+//! edits (debounced) and when the server asks for a refresh. Lenses that come without
+//! a command are resolved with `codeLens/resolve` once their line scrolls into view.
+//! Each line's lenses are shown as one virtual line above it, such as
+//! "▶ Run Test | Debug".
+//!
+//! Clicking a lens, or "Run Code Lens" on its line, runs its command. Commands the
+//! server advertises go through `workspace/executeCommand`; the few client-side
+//! commands servers commonly attach to lenses (showing references, running a test)
+//! are carried out by the editor.
+
+use std::time::{Duration, Instant};
+
+use lsp_types::{CodeLens, Command, Location};
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use serde_json::Value;
+
+use super::Editor;
+use crate::model::event::{BufferId, SplitId};
+use crate::services::lsp::client::LspFeature;
+use crate::services::lsp::manager::detect_language;
+use crate::view::popup::{Popup, PopupListItem, PopupPosition};
+use crate::view::virtual_text::{VirtualTextId, VirtualTextNamespace, VirtualTextPosition};
+
+/// How long to wait after the last edit before re-requesting lenses
+pub(super) const CODE_LENS_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Title of the popup listing a line's lenses, used to recognize it when an item is
+/// confirmed
+pub(super) const CODE_LENS_POPUP_TITLE: &str = "Code Lens";
+
+/// Virtual text namespace of the lens lines
+const CODE_LENS_NAMESPACE: &str = "lsp-code-lens";
+
+/// Keeps lens lines right above their line, below other virtual lines anchored there
+const CODE_LENS_PRIORITY: i32 = 1000;
+
+const LENS_SEPARATOR: &str = " | ";
+
+/// Code lenses of a buffer, from the server that provided them
+#[derive(Debug)]
+pub(super) struct BufferCodeLenses {
+    language: String,
+    server: String,
+    /// Bumped for each response, so resolve responses for older lenses are dropped
+    generation: u64,
+    lines: Vec<CodeLensLine>,
+}
+
+/// The lenses shown above one line
+#[derive(Debug)]
+struct CodeLensLine {
+    /// Line the lenses belong to, kept current from the virtual line's anchor
+    line: u32,
+    /// Virtual line showing the lenses, once at least one has a title
+    vtext: Option<VirtualTextId>,
+    lenses: Vec<CodeLensEntry>,
+}
+
+#[derive(Debug)]
+struct CodeLensEntry {
+    /// The lens as the server sent it, which is what gets resolved
+    lens: CodeLens,
+    /// Command to run, from the lens itself or from resolving it
+    command: Option<Command>,
+    /// A resolve request was sent (they are not retried)
+    resolving: bool,
+}
+
+/// A lens waiting for its `codeLens/resolve` response
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PendingCodeLensResolve {
+    buffer_id: BufferId,
+    generation: u64,
+    line: usize,
+    lens: usize,
+}
+
+/// A lens command offered in the "Code Lens" popup
+#[derive(Debug, Clone)]
+pub(super) struct OfferedCodeLens {
+    language: String,
+    server: String,
+    command: Command,
+}
+
+impl Editor {
+    /// Request fresh lenses for a buffer once `delay` has passed
+    pub(super) fn schedule_code_lens_refresh(&mut self, buffer_id: BufferId, delay: Duration) {
+        if !self.config.editor.enable_code_lens {
+            return;
+        }
+        let is_virtual = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .is_some_and(|metadata| metadata.is_virtual() || metadata.binary);
+        if !is_virtual {
+            self.code_lens_refresh
+                .insert(buffer_id, Instant::now() + delay);
+        }
+    }
+
+    /// Schedule an immediate refresh for every open buffer of a language
+    pub(super) fn schedule_code_lens_for_language(&mut self, language: &str) {
+        for buffer_id in self.buffer_ids_for_language(language) {
+            self.schedule_code_lens_refresh(buffer_id, Duration::ZERO);
+        }
+    }
+
+    /// Send lens requests that are due, and resolve lenses that came into view
+    pub(super) fn check_code_lens_refresh(&mut self) {
+        if !self.config.editor.enable_code_lens {
+            return;
+        }
+
+        let now = Instant::now();
+        let due: Vec<BufferId> = self
+            .code_lens_refresh
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(buffer_id, _)| *buffer_id)
+            .collect();
+
+        for buffer_id in due {
+            // Wait for the in-flight request; its response may already be outdated
+            if self
+                .pending_code_lens_requests
+                .values()
+                .any(|pending| *pending == buffer_id)
+            {
+                continue;
+            }
+            // A server that isn't ready yet schedules a refresh once it initializes
+            self.code_lens_refresh.remove(&buffer_id);
+            self.request_code_lens(buffer_id);
+        }
+
+        self.resolve_visible_code_lenses();
+    }
+
+    /// Send a code lens request for a buffer, if its server is ready and supports it
+    fn request_code_lens(&mut self, buffer_id: BufferId) -> Option<()> {
+        let metadata = self.buffer_metadata.get(&buffer_id)?;
+        if !metadata.lsp_enabled {
+            return None;
+        }
+        let uri = metadata.file_uri()?.clone();
+        let language = detect_language(metadata.file_path()?, &self.config.languages)?;
+
+        let lsp = self.lsp.as_mut()?;
+        // Don't start a server just for lenses
+        if !lsp.is_server_ready(&language) {
+            return None;
+        }
+        let handle = lsp.handle_for_feature(&language, LspFeature::CodeLens)?;
+        handle.capabilities()?.code_lens_provider?;
+
+        let request_id = self.next_lsp_request_id;
+        if let Err(e) = handle.code_lens(request_id, uri) {
+            tracing::warn!("Failed to send code lens request: {}", e);
+            return None;
+        }
+        self.next_lsp_request_id += 1;
+        self.pending_code_lens_requests
+            .insert(request_id, buffer_id);
+        Some(())
+    }
+
+    /// Store a code lens response and show its lenses
+    pub(super) fn handle_code_lens_response(
+        &mut self,
+        request_id: u64,
+        language: String,
+        server: String,
+        result: Result<Vec<CodeLens>, String>,
+    ) {
+        let Some(buffer_id) = self.pending_code_lens_requests.remove(&request_id) else {
+            tracing::debug!("Ignoring stale code lens response: {}", request_id);
+            return;
+        };
+        if !self.config.editor.enable_code_lens {
+            return;
+        }
+        let mut lenses = match result {
+            Ok(lenses) => lenses,
+            Err(e) => {
+                tracing::debug!("Code lens request failed: {}", e);
+                return;
+            }
+        };
+
+        // Lenses that come back unchanged keep their resolved command, so a refresh
+        // doesn't make them flicker or resolve them again
+        let mut previous: Vec<CodeLensEntry> = self
+            .code_lenses
+            .get_mut(&buffer_id)
+            .map(|old| std::mem::take(&mut old.lines))
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|line| line.lenses)
+            .filter(|entry| entry.command.is_some())
+            .collect();
+
+        lenses.sort_by_key(|lens| (lens.range.start.line, lens.range.start.character));
+        let mut lines: Vec<CodeLensLine> = Vec::new();
+        for lens in lenses {
+            let line = lens.range.start.line;
+            let entry = match previous.iter().position(|entry| entry.lens == lens) {
+                Some(index) => previous.swap_remove(index),
+                None => CodeLensEntry {
+                    command: lens.command.clone(),
+                    lens,
+                    resolving: false,
+                },
+            };
+            match lines.last_mut() {
+                Some(last) if last.line == line => last.lenses.push(entry),
+                _ => lines.push(CodeLensLine {
+                    line,
+                    vtext: None,
+                    lenses: vec![entry],
+                }),
+            }
+        }
+
+        let generation = self
+            .code_lenses
+            .get(&buffer_id)
+            .map_or(0, |lenses| lenses.generation + 1);
+        self.remove_code_lens_lines(buffer_id);
+        self.code_lenses.insert(
+            buffer_id,
+            BufferCodeLenses {
+                language,
+                server,
+                generation,
+                lines,
+            },
+        );
+        self.render_code_lenses(buffer_id);
+        self.resolve_visible_code_lenses();
+    }
+
+    /// Send resolve requests for unresolved lenses on the visible lines of the active buffer
+    fn resolve_visible_code_lenses(&mut self) {
+        let buffer_id = self.active_buffer();
+        let Some(lenses) = self.code_lenses.get(&buffer_id) else {
+            return;
+        };
+        let visible = self.visible_lines();
+        let wanted: Vec<(usize, usize)> = lenses
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| visible.contains(&line.line))
+            .flat_map(|(line_index, line)| {
+                line.lenses
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.command.is_none() && !entry.resolving)
+                    .map(move |(lens_index, _)| (line_index, lens_index))
+            })
+            .collect();
+        if wanted.is_empty() {
+            return;
+        }
+
+        let language = lenses.language.clone();
+        let server = lenses.server.clone();
+        let generation = lenses.generation;
+        let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.server_handle(&language, &server))
+        else {
+            return;
+        };
+        let resolves = handle
+            .capabilities()
+            .and_then(|caps| caps.code_lens_provider)
+            .and_then(|options| options.resolve_provider)
+            == Some(true);
+
+        for (line_index, lens_index) in wanted {
+            let Some(entry) = self
+                .code_lenses
+                .get_mut(&buffer_id)
+                .map(|lenses| &mut lenses.lines[line_index].lenses[lens_index])
+            else {
+                return;
+            };
+            // Lenses a server can't resolve are never shown
+            entry.resolving = true;
+            if !resolves {
+                continue;
+            }
+            let lens = entry.lens.clone();
+
+            let request_id = self.next_lsp_request_id;
+            let Some(handle) = self
+                .lsp
+                .as_mut()
+                .and_then(|lsp| lsp.server_handle(&language, &server))
+            else {
+                return;
+            };
+            if let Err(e) = handle.resolve_code_lens(request_id, lens) {
+                tracing::warn!("Failed to send code lens resolve request: {}", e);
+                return;
+            }
+            self.next_lsp_request_id += 1;
+            self.pending_code_lens_resolves.insert(
+                request_id,
+                PendingCodeLensResolve {
+                    buffer_id,
+                    generation,
+                    line: line_index,
+                    lens: lens_index,
+                },
+            );
+        }
+    }
+
+    /// Show a resolved lens
+    pub(super) fn handle_code_lens_resolved(
+        &mut self,
+        request_id: u64,
+        result: Result<CodeLens, String>,
+    ) {
+        let Some(pending) = self.pending_code_lens_resolves.remove(&request_id) else {
+            tracing::debug!("Ignoring stale code lens resolve response: {}", request_id);
+            return;
+        };
+        let lens = match result {
+            Ok(lens) => lens,
+            Err(e) => {
+                tracing::debug!("Code lens resolve request failed: {}", e);
+                return;
+            }
+        };
+        let Some(entry) = self
+            .code_lenses
+            .get_mut(&pending.buffer_id)
+            .filter(|lenses| lenses.generation == pending.generation)
+            .and_then(|lenses| lenses.lines.get_mut(pending.line))
+            .and_then(|line| line.lenses.get_mut(pending.lens))
+        else {
+            return;
+        };
+        entry.command = lens.command;
+        self.render_code_lenses(pending.buffer_id);
+    }
+
+    /// Rebuild the lens lines of a buffer
+    fn render_code_lenses(&mut self, buffer_id: BufferId) {
+        self.sync_code_lens_lines(buffer_id);
+        self.remove_code_lens_lines(buffer_id);
+
+        let style = Style::default().fg(self.theme.line_number_fg);
+        let (Some(lenses), Some(state)) = (
+            self.code_lenses.get_mut(&buffer_id),
+            self.buffers.get_mut(&buffer_id),
+        ) else {
+            return;
+        };
+        let namespace = VirtualTextNamespace::from_string(CODE_LENS_NAMESPACE.to_string());
+
+        for line in &mut lenses.lines {
+            let titles = line_titles(line);
+            if titles.is_empty() {
+                continue;
+            }
+            let Some(anchor) = state.buffer.line_start_offset(line.line as usize) else {
+                continue;
+            };
+            let text = format!(
+                "{}{}",
+                line_indent(state, line.line),
+                titles.join(LENS_SEPARATOR)
+            );
+            line.vtext = Some(state.virtual_texts.add_line(
+                &mut state.marker_list,
+                anchor,
+                text,
+                style,
+                VirtualTextPosition::LineAbove,
+                namespace.clone(),
+                CODE_LENS_PRIORITY,
+            ));
+        }
+    }
+
+    /// Update each lens line's number from its anchor, which follows edits
+    fn sync_code_lens_lines(&mut self, buffer_id: BufferId) {
+        let (Some(lenses), Some(state)) = (
+            self.code_lenses.get_mut(&buffer_id),
+            self.buffers.get(&buffer_id),
+        ) else {
+            return;
+        };
+        for line in &mut lenses.lines {
+            let position = line
+                .vtext
+                .and_then(|id| state.virtual_texts.position(&state.marker_list, id));
+            if let Some(position) = position {
+                line.line = state.buffer.get_line_number(position) as u32;
+            }
+        }
+    }
+
+    fn remove_code_lens_lines(&mut self, buffer_id: BufferId) {
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            let namespace = VirtualTextNamespace::from_string(CODE_LENS_NAMESPACE.to_string());
+            state
+                .virtual_texts
+                .clear_namespace(&mut state.marker_list, &namespace);
+        }
+        if let Some(lenses) = self.code_lenses.get_mut(&buffer_id) {
+            for line in &mut lenses.lines {
+                line.vtext = None;
+            }
+        }
+    }
+
+    /// The lens line shown above a line, with its lenses' current line numbers
+    fn code_lens_line(&mut self, buffer_id: BufferId, line: u32) -> Option<&CodeLensLine> {
+        self.sync_code_lens_lines(buffer_id);
+        self.code_lenses
+            .get(&buffer_id)?
+            .lines
+            .iter()
+            .find(|lens_line| lens_line.line == line && lens_line.vtext.is_some())
+    }
+
+    /// Run the lens above the cursor line, or let the user pick one of several
+    pub fn run_code_lens_at_cursor(&mut self) {
+        let buffer_id = self.active_buffer();
+        let state = self.active_state();
+        let line = state
+            .buffer
+            .get_line_number(state.cursors.primary().position) as u32;
+
+        let commands: Vec<Command> = self
+            .code_lens_line(buffer_id, line)
+            .map(|lens_line| {
+                lens_line
+                    .lenses
+                    .iter()
+                    .filter_map(|entry| entry.command.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let Some((language, server)) = self
+            .code_lenses
+            .get(&buffer_id)
+            .map(|lenses| (lenses.language.clone(), lenses.server.clone()))
+        else {
+            self.set_status_message("No code lens on this line".to_string());
+            return;
+        };
+
+        match commands.len() {
+            0 => self.set_status_message("No code lens on this line".to_string()),
+            1 => {
+                let command = commands.into_iter().next().unwrap();
+                self.run_code_lens_command(&language, &server, command);
+            }
+            _ => {
+                let items = commands
+                    .iter()
+                    .enumerate()
+                    .map(|(index, command)| {
+                        PopupListItem::new(command.title.clone()).with_data(index.to_string())
+                    })
+                    .collect();
+                let popup = Popup::list(items, &self.theme)
+                    .with_title(CODE_LENS_POPUP_TITLE.to_string())
+                    .with_position(PopupPosition::BelowCursor)
+                    .with_width(60)
+                    .with_max_height(15);
+                self.code_lens_offered = commands
+                    .into_iter()
+                    .map(|command| OfferedCodeLens {
+                        language: language.clone(),
+                        server: server.clone(),
+                        command,
+                    })
+                    .collect();
+                if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                    state.popups.show(popup);
+                }
+            }
+        }
+    }
+
+    /// Run the lens chosen in the popup (`data` is its index)
+    pub(super) fn confirm_code_lens(&mut self, data: &str) {
+        let offered = data
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| self.code_lens_offered.get(index).cloned());
+        self.code_lens_offered.clear();
+        if let Some(offered) = offered {
+            self.run_code_lens_command(&offered.language, &offered.server, offered.command);
+        }
+    }
+
+    /// Run a lens that was clicked; returns whether the click was on a lens title
+    ///
+    /// The split must already be focused.
+    pub(super) fn handle_code_lens_click(
+        &mut self,
+        col: u16,
+        row: u16,
+        split_id: SplitId,
+        buffer_id: BufferId,
+        content_rect: Rect,
+    ) -> bool {
+        if !self.code_lenses.contains_key(&buffer_id) {
+            return false;
+        }
+        let Some(mappings) = self.cached_layout.view_line_mappings.get(&split_id) else {
+            return false;
+        };
+        let visual_row = row.saturating_sub(content_rect.y) as usize;
+        // Lens lines have no source bytes, and sit right above their line's first row
+        let is_virtual = mappings
+            .get(visual_row)
+            .is_some_and(|mapping| mapping.char_mappings.iter().all(Option::is_none));
+        let next_line_start = mappings
+            .get(visual_row + 1)
+            .and_then(|mapping| mapping.char_mappings.iter().find_map(|m| *m));
+        let (true, Some(next_line_start)) = (is_virtual, next_line_start) else {
+            return false;
+        };
+
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return false;
+        };
+        let gutter_width = state.margins.left_total_width() as u16;
+        let line = state.buffer.get_line_number(next_line_start);
+        if state.buffer.line_start_offset(line) != Some(next_line_start) {
+            return false;
+        }
+        let indent = line_indent(state, line as u32).chars().count();
+        let Some(text_col) = col
+            .saturating_sub(content_rect.x)
+            .checked_sub(gutter_width)
+            .and_then(|text_col| (text_col as usize).checked_sub(indent))
+        else {
+            return false;
+        };
+
+        let Some(lens_line) = self.code_lens_line(buffer_id, line as u32) else {
+            return false;
+        };
+        let mut start = 0;
+        let mut clicked = None;
+        for command in lens_line
+            .lenses
+            .iter()
+            .filter_map(|entry| entry.command.as_ref())
+        {
+            let end = start + command.title.chars().count();
+            if (start..end).contains(&text_col) {
+                clicked = Some(command.clone());
+                break;
+            }
+            start = end + LENS_SEPARATOR.chars().count();
+        }
+        let Some(command) = clicked else {
+            return false;
+        };
+        let Some((language, server)) = self
+            .code_lenses
+            .get(&buffer_id)
+            .map(|lenses| (lenses.language.clone(), lenses.server.clone()))
+        else {
+            return false;
+        };
+
+        self.run_code_lens_command(&language, &server, command);
+        true
+    }
+
+    /// Run a lens command, on the server or in the editor
+    fn run_code_lens_command(&mut self, language: &str, server: &str, command: Command) {
+        let arguments = command.arguments.clone().unwrap_or_default();
+        match command.command.as_str() {
+            // Arguments: document URI, position, locations
+            "editor.action.showReferences" | "rust-analyzer.showReferences" => {
+                let locations = arguments
+                    .get(2)
+                    .cloned()
+                    .and_then(|value| serde_json::from_value::<Vec<Location>>(value).ok())
+                    .unwrap_or_default();
+                self.show_references(command.title, locations);
+                return;
+            }
+            // Argument: the runnable (a cargo invocation)
+            "rust-analyzer.runSingle" => {
+                match arguments.first().and_then(runnable_command_line) {
+                    Some(line) => {
+                        self.open_terminal();
+                        self.send_terminal_input(format!("{}\n", line).as_bytes());
+                    }
+                    None => self.set_status_message(format!(
+                        "Cannot run '{}': unsupported runnable",
+                        command.title
+                    )),
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let advertised = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.server_handle(language, server))
+            .and_then(|handle| handle.capabilities())
+            .and_then(|caps| caps.execute_command_provider)
+            .is_some_and(|options| options.commands.contains(&command.command));
+        if advertised {
+            self.execute_lsp_command(language, server, command);
+        } else {
+            self.set_status_message(format!(
+                "'{}' is not supported ({})",
+                command.title, command.command
+            ));
+        }
+    }
+}
+
+/// Titles of a line's resolved lenses
+fn line_titles(line: &CodeLensLine) -> Vec<&str> {
+    line.lenses
+        .iter()
+        .filter_map(|entry| entry.command.as_ref())
+        .map(|command| command.title.as_str())
+        .collect()
+}
+
+/// Leading whitespace of a line, so its lenses line up with the code
+fn line_indent(state: &crate::state::EditorState, line: u32) -> String {
+    state
+        .buffer
+        .get_line(line as usize)
+        .map(|bytes| {
+            bytes
+                .iter()
+                .take_while(|byte| **byte == b' ' || **byte == b'\t')
+                .map(|byte| *byte as char)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Shell command line for a rust-analyzer runnable
+///
+/// Cargo runnables become `cargo <args> -- <executable args>`, run from the runnable's
+/// working directory.
+fn runnable_command_line(runnable: &Value) -> Option<String> {
+    if runnable.get("kind")?.as_str()? != "cargo" {
+        return None;
+    }
+    let args = runnable.get("args")?;
+    let strings = |key: &str| -> Vec<String> {
+        args.get(key)
+            .and_then(Value::as_array)
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(shell_quote)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let program = args
+        .get("overrideCargo")
+        .and_then(Value::as_str)
+        .unwrap_or("cargo");
+
+    let mut line = shell_quote(program);
+    for arg in strings("cargoArgs") {
+        line.push(' ');
+        line.push_str(&arg);
+    }
+    let executable_args = strings("executableArgs");
+    if !executable_args.is_empty() {
+        line.push_str(" --");
+        for arg in executable_args {
+            line.push(' ');
+            line.push_str(&arg);
+        }
+    }
+
+    let cwd = args
+        .get("cwd")
+        .or_else(|| args.get("workspaceRoot"))
+        .and_then(Value::as_str);
+    Some(match cwd {
+        Some(cwd) => format!("cd {} && {}", shell_quote(cwd), line),
+        None => line,
+    })
+}
+
+/// Quote an argument for a POSIX shell, unless it only has safe characters
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runnable_command_line() {
+        let runnable = serde_json::json!({
+            "label": "test tests::it_works",
+            "kind": "cargo",
+            "args": {
+                "workspaceRoot": "/work/my project",
+                "cargoArgs": ["test", "--package", "demo", "--lib"],
+                "executableArgs": ["tests::it_works", "--exact", "--nocapture"]
+            }
+        });
+        assert_eq!(
+            runnable_command_line(&runnable).unwrap(),
+            "cd '/work/my project' && cargo test --package demo --lib -- tests::it_works --exact --nocapture"
+        );
+    }
+
+    #[test]
+    fn test_runnable_command_line_rejects_other_kinds() {
+        let runnable = serde_json::json!({"kind": "shell", "args": {"program": "make"}});
+        assert_eq!(runnable_command_line(&runnable), None);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("--exact"), "--exact");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
@@ -0,0 +1,803 @@
+//! Completion: requesting items, filtering them as the user types and accepting them
+//!
+//! `textDocument/completion` is sent to every server that offers completion and their
+//! items are merged into one popup. Typing word characters filters the list (prefix
+//! matches on `filterText` first, then fuzzy ones, each ordered by `sortText`); when a
+//! server said its list is incomplete, typing asks it again instead.
+//!
+//! Accepting an item applies its `textEdit` (or inserts its text over the typed word)
+//! and then its `additionalTextEdits`, such as the import an auto-import completion
+//! needs. Items that leave documentation and additional edits to
+//! `completionItem/resolve` are resolved when selected, and their documentation is
+//! shown in a side popup next to the list.
+
+use std::io;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use lsp_types::{CompletionItem, CompletionTextEdit, Documentation, InsertTextFormat, MarkupKind};
+use ratatui::style::Style;
+
+use super::Editor;
+use crate::input::keybindings::Action;
+use crate::model::event::{
+    BufferId, Event, PopupContentData, PopupData, PopupListItemData, PopupPositionData,
+};
+use crate::services::lsp::client::LspFeature;
+use crate::services::lsp::manager::detect_language;
+use crate::view::popup::Popup;
+
+/// Title of the completion popup, used to recognize it
+pub(super) const COMPLETION_POPUP_TITLE: &str = "Completion";
+
+/// Completion items offered at a position, and how far the user got typing
+#[derive(Debug)]
+pub(super) struct CompletionSession {
+    /// The completion request the items came from
+    id: u64,
+    buffer_id: BufferId,
+    language: String,
+    /// Start of the word being completed, which accepting an item replaces
+    word_start: usize,
+    candidates: Vec<CompletionCandidate>,
+    /// Some server's list was incomplete, so typing re-queries instead of filtering
+    is_incomplete: bool,
+    /// Candidate whose documentation is in the side popup
+    documented: Option<usize>,
+}
+
+/// A completion item and the server it came from
+#[derive(Debug)]
+pub(super) struct CompletionCandidate {
+    server: String,
+    item: CompletionItem,
+    resolve: ResolveState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResolveState {
+    Unresolved,
+    Resolving,
+    /// Resolved, or not resolvable by its server
+    Done,
+}
+
+/// A completion item waiting for its `completionItem/resolve` response
+#[derive(Debug)]
+pub(super) struct PendingCompletionResolve {
+    session: u64,
+    candidate: usize,
+    /// The item was accepted, so its additional edits are applied on arrival
+    accepted: Option<BufferId>,
+}
+
+impl Editor {
+    /// Request completion at the cursor from every server that offers it
+    pub(super) fn request_completion(&mut self) -> io::Result<()> {
+        self.send_completion_request(false)
+    }
+
+    /// Ask the servers again after the user typed into an incomplete list
+    fn requery_completion(&mut self) -> io::Result<()> {
+        self.send_completion_request(true)
+    }
+
+    fn send_completion_request(&mut self, incomplete_retrigger: bool) -> io::Result<()> {
+        // Get the current buffer and cursor position
+        let state = self.active_state();
+        let cursor_pos = state.cursors.primary().position;
+
+        // Convert byte position to LSP position (line, UTF-16 code units)
+        let (line, character) = state.buffer.position_to_lsp_position(cursor_pos);
+
+        // Get the current file URI and path
+        let metadata = self.buffer_metadata.get(&self.active_buffer());
+        let (Some(uri), Some(path)) = (
+            metadata.and_then(|meta| meta.file_uri()),
+            metadata.and_then(|meta| meta.file_path()),
+        ) else {
+            return Ok(());
+        };
+        let Some(language) = detect_language(path, &self.config.languages) else {
+            return Ok(());
+        };
+        let Some(lsp) = self.lsp.as_mut() else {
+            return Ok(());
+        };
+
+        // Every server that offers completion is asked; see handle_completion_response
+        let handles = lsp.handles_for_feature(&language, LspFeature::Completion);
+        if handles.is_empty() {
+            return Ok(());
+        }
+        let request_id = self.next_lsp_request_id;
+        self.next_lsp_request_id += 1;
+        self.pending_completion_request = Some(request_id);
+        self.completion_responses_remaining = handles.len();
+        self.completion_items_received.clear();
+        self.completion_list_incomplete = false;
+        self.lsp_status = "LSP: completion...".to_string();
+
+        for handle in handles {
+            let _ = handle.completion(
+                request_id,
+                uri.clone(),
+                line as u32,
+                character as u32,
+                incomplete_retrigger,
+            );
+        }
+        tracing::info!(
+            "Requested completion at {}:{}:{}",
+            uri.as_str(),
+            line,
+            character
+        );
+        Ok(())
+    }
+
+    /// Handle LSP completion response
+    pub(super) fn handle_completion_response(
+        &mut self,
+        request_id: u64,
+        server: String,
+        items: Vec<CompletionItem>,
+        is_incomplete: bool,
+    ) -> io::Result<()> {
+        // Check if this is the pending completion request
+        if self.pending_completion_request != Some(request_id) {
+            tracing::debug!(
+                "Ignoring completion response for outdated request {}",
+                request_id
+            );
+            return Ok(());
+        }
+
+        // With several servers, wait for all of them and merge their items
+        self.completion_items_received
+            .extend(items.into_iter().map(|item| CompletionCandidate {
+                server: server.clone(),
+                item,
+                resolve: ResolveState::Unresolved,
+            }));
+        self.completion_list_incomplete |= is_incomplete;
+        self.completion_responses_remaining = self.completion_responses_remaining.saturating_sub(1);
+        if self.completion_responses_remaining > 0 {
+            return Ok(());
+        }
+        let mut candidates = std::mem::take(&mut self.completion_items_received);
+
+        self.pending_completion_request = None;
+        self.lsp_status.clear();
+
+        // The user's snippets are offered alongside the servers' items
+        if let Some(language) = self.active_language() {
+            candidates.extend(
+                self.snippet_completion_items(&language)
+                    .into_iter()
+                    .map(|item| CompletionCandidate {
+                        server: String::new(),
+                        item,
+                        resolve: ResolveState::Done,
+                    }),
+            );
+        }
+
+        if candidates.is_empty() {
+            tracing::debug!("No completion items received");
+            self.close_completion();
+            return Ok(());
+        }
+
+        use crate::primitives::word_navigation::find_completion_word_start;
+        let buffer_id = self.active_buffer();
+        let word_start = {
+            let state = self.active_state();
+            find_completion_word_start(&state.buffer, state.cursors.primary().position)
+        };
+        let language = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|meta| meta.file_path())
+            .and_then(|path| detect_language(path, &self.config.languages))
+            .unwrap_or_default();
+
+        self.completion_session = Some(CompletionSession {
+            id: request_id,
+            buffer_id,
+            language,
+            word_start,
+            candidates,
+            is_incomplete: self.completion_list_incomplete,
+            documented: None,
+        });
+        self.show_completion_popup();
+        Ok(())
+    }
+
+    /// Show the session's items matching what was typed, replacing the popup if open
+    fn show_completion_popup(&mut self) {
+        let Some(session) = self
+            .completion_session
+            .as_ref()
+            .filter(|session| session.buffer_id == self.active_buffer())
+        else {
+            return;
+        };
+        let state = self.active_state();
+        let cursor_pos = state.cursors.primary().position;
+        if cursor_pos < session.word_start {
+            self.close_completion();
+            return;
+        }
+
+        // Each item is matched against the text its edit would replace
+        let word = state.buffer.slice_bytes(session.word_start..cursor_pos);
+        let word = String::from_utf8_lossy(&word).into_owned();
+        let typed: Vec<(&CompletionItem, String)> = session
+            .candidates
+            .iter()
+            .map(|candidate| {
+                let start = candidate
+                    .item
+                    .text_edit
+                    .as_ref()
+                    .map(|edit| edit_range(edit).start)
+                    .map(|start| {
+                        state
+                            .buffer
+                            .lsp_position_to_byte(start.line as usize, start.character as usize)
+                    })
+                    .filter(|&start| start <= cursor_pos && start != session.word_start);
+                let typed = match start {
+                    Some(start) => {
+                        String::from_utf8_lossy(&state.buffer.slice_bytes(start..cursor_pos))
+                            .into_owned()
+                    }
+                    None => word.clone(),
+                };
+                (&candidate.item, typed)
+            })
+            .collect();
+        let ranked = rank_completion_items(&typed);
+
+        if ranked.is_empty() {
+            tracing::debug!("No completion items match '{}'", word);
+            self.close_completion_popup();
+            return;
+        }
+
+        let items = ranked
+            .iter()
+            .map(|&index| {
+                let item = &session.candidates[index].item;
+                PopupListItemData {
+                    text: item.label.clone(),
+                    detail: item.detail.clone(),
+                    icon: completion_kind_icon(item).map(str::to_string),
+                    data: Some(index.to_string()),
+                }
+            })
+            .collect();
+        let popup_data = PopupData {
+            title: Some(COMPLETION_POPUP_TITLE.to_string()),
+            content: PopupContentData::List { items, selected: 0 },
+            position: PopupPositionData::BelowCursor,
+            width: 50,
+            max_height: 15,
+            bordered: true,
+        };
+
+        self.close_completion_popup();
+        if let Some(session) = self.completion_session.as_mut() {
+            session.documented = None;
+        }
+        tracing::info!("Showing completion popup with {} items", ranked.len());
+        self.active_state_mut()
+            .apply(&Event::ShowPopup { popup: popup_data });
+        self.update_completion_documentation();
+    }
+
+    /// Whether the completion popup is the top popup of the active buffer
+    fn completion_popup_open(&self) -> bool {
+        self.active_state()
+            .popups
+            .top()
+            .is_some_and(|popup| popup.title.as_deref() == Some(COMPLETION_POPUP_TITLE))
+    }
+
+    fn close_completion_popup(&mut self) {
+        if self.completion_popup_open() {
+            self.hide_popup();
+        }
+    }
+
+    /// Close the popup and forget the offered items
+    fn close_completion(&mut self) {
+        self.close_completion_popup();
+        self.completion_session = None;
+    }
+
+    /// The candidate selected in the completion popup
+    fn selected_completion_candidate(&self) -> Option<usize> {
+        self.active_state()
+            .popups
+            .top()
+            .filter(|popup| popup.title.as_deref() == Some(COMPLETION_POPUP_TITLE))
+            .and_then(|popup| popup.selected_item())
+            .and_then(|item| item.data.as_deref())
+            .and_then(|data| data.parse().ok())
+    }
+
+    /// Handle a key typed while the completion popup is open
+    ///
+    /// Word characters and backspace edit the buffer and refilter the list; a commit
+    /// character accepts the selected item before being typed; any other character
+    /// closes the list. Returns false for keys the popup's keybindings handle.
+    pub(super) fn handle_completion_popup_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> io::Result<bool> {
+        let has_session = self
+            .completion_session
+            .as_ref()
+            .is_some_and(|session| session.buffer_id == self.active_buffer());
+        if !has_session
+            || !self.completion_popup_open()
+            || !(modifiers.is_empty() || modifiers == KeyModifiers::SHIFT)
+        {
+            return Ok(false);
+        }
+        match code {
+            KeyCode::Char(c) => {
+                let selected = self.selected_completion_candidate();
+                let commits = selected
+                    .and_then(|index| self.completion_candidate(index))
+                    .and_then(|candidate| candidate.item.commit_characters.as_ref())
+                    .is_some_and(|chars| chars.iter().any(|s| s.starts_with(c)));
+                if commits {
+                    self.hide_popup();
+                    if let Some(index) = selected {
+                        self.accept_completion(index)?;
+                    }
+                    self.completion_session = None;
+                    self.handle_action(Action::InsertChar(c))?;
+                } else if c.is_alphanumeric() || c == '_' {
+                    self.handle_action(Action::InsertChar(c))?;
+                    self.refilter_completion()?;
+                } else {
+                    self.close_completion();
+                    self.handle_action(Action::InsertChar(c))?;
+                }
+                Ok(true)
+            }
+            KeyCode::Backspace => {
+                self.handle_action(Action::DeleteBackward)?;
+                self.refilter_completion()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Update the list after the typed word changed
+    fn refilter_completion(&mut self) -> io::Result<()> {
+        let Some(session) = self.completion_session.as_ref() else {
+            return Ok(());
+        };
+        let cursor_pos = self.active_state().cursors.primary().position;
+        if cursor_pos < session.word_start {
+            self.close_completion();
+            return Ok(());
+        }
+        if session.is_incomplete {
+            // The popup stays up with the old items until the new ones arrive
+            return self.requery_completion();
+        }
+        self.show_completion_popup();
+        Ok(())
+    }
+
+    fn completion_candidate(&self, index: usize) -> Option<&CompletionCandidate> {
+        self.completion_session
+            .as_ref()
+            .filter(|session| session.buffer_id == self.active_buffer())
+            .and_then(|session| session.candidates.get(index))
+    }
+
+    /// Accept the completion item stored in a confirmed popup item
+    ///
+    /// Popups not built from a completion response (e.g. shown by plugins) carry the
+    /// text to insert over the typed word instead.
+    pub(super) fn confirm_completion(&mut self, data: &str) -> io::Result<()> {
+        let index = data
+            .parse()
+            .ok()
+            .filter(|&index| self.completion_candidate(index).is_some());
+        match index {
+            Some(index) => self.accept_completion(index)?,
+            None => self.insert_completion_text(data.to_string()),
+        }
+        self.completion_session = None;
+        Ok(())
+    }
+
+    /// Replace the word before the cursor with `text`
+    fn insert_completion_text(&mut self, text: String) {
+        use crate::primitives::word_navigation::find_completion_word_start;
+
+        let cursor_pos = self.active_state().cursors.primary().position;
+        let word_start = find_completion_word_start(&self.active_state().buffer, cursor_pos);
+        self.replace_with_completion(word_start..cursor_pos, text);
+    }
+
+    /// Replace `range` of the active buffer, leaving the cursor after the new text
+    fn replace_with_completion(&mut self, range: std::ops::Range<usize>, text: String) {
+        let cursor_id = self.active_state().cursors.primary_id();
+        if !range.is_empty() {
+            let deleted_text = self
+                .active_state_mut()
+                .get_text_range(range.start, range.end);
+            let delete_event = Event::Delete {
+                range: range.clone(),
+                deleted_text,
+                cursor_id,
+            };
+            self.active_event_log_mut().append(delete_event.clone());
+            self.apply_event_to_active_buffer(&delete_event);
+        }
+        if !text.is_empty() {
+            let insert_event = Event::Insert {
+                position: range.start.min(self.active_state().buffer.len()),
+                text,
+                cursor_id,
+            };
+            self.active_event_log_mut().append(insert_event.clone());
+            self.apply_event_to_active_buffer(&insert_event);
+        }
+    }
+
+    /// Apply a candidate's edit and its additional edits to the active buffer
+    fn accept_completion(&mut self, index: usize) -> io::Result<()> {
+        let Some(candidate) = self.completion_candidate(index) else {
+            return Ok(());
+        };
+        let item = candidate.item.clone();
+        let resolve = candidate.resolve;
+        let Some(session) = self.completion_session.as_ref() else {
+            return Ok(());
+        };
+        let (session_id, buffer_id, word_start) =
+            (session.id, session.buffer_id, session.word_start);
+
+        let (range, text) = {
+            let state = self.active_state();
+            let cursor_pos = state.cursors.primary().position;
+            match &item.text_edit {
+                Some(edit) => {
+                    let range = edit_range(edit);
+                    let start = state.buffer.lsp_position_to_byte(
+                        range.start.line as usize,
+                        range.start.character as usize,
+                    );
+                    let end = state.buffer.lsp_position_to_byte(
+                        range.end.line as usize,
+                        range.end.character as usize,
+                    );
+                    // The range was computed when the list was requested; what was
+                    // typed since then is replaced too
+                    (start.min(cursor_pos)..end.max(cursor_pos), edit_text(edit))
+                }
+                None => (
+                    word_start.min(cursor_pos)..cursor_pos,
+                    item.insert_text
+                        .clone()
+                        .unwrap_or_else(|| item.label.clone()),
+                ),
+            }
+        };
+
+        if item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+            self.insert_snippet(range, &text);
+        } else {
+            self.replace_with_completion(range, text);
+        }
+
+        if let Some(edits) = item.additional_text_edits {
+            self.apply_lsp_text_edits(buffer_id, edits)?;
+            return Ok(());
+        }
+        match resolve {
+            // Servers may only compute the additional edits (imports) on resolve
+            ResolveState::Unresolved => {
+                self.resolve_completion_candidate(index, Some(buffer_id));
+            }
+            ResolveState::Resolving => {
+                if let Some(pending) = self
+                    .pending_completion_resolves
+                    .values_mut()
+                    .find(|pending| pending.session == session_id && pending.candidate == index)
+                {
+                    pending.accepted = Some(buffer_id);
+                }
+            }
+            ResolveState::Done => {}
+        }
+        Ok(())
+    }
+
+    /// Show the selected item's documentation beside the list, resolving it if needed
+    pub(super) fn update_completion_documentation(&mut self) {
+        let Some(index) = self.selected_completion_candidate() else {
+            return;
+        };
+        let Some(candidate) = self.completion_candidate(index) else {
+            return;
+        };
+        if self
+            .completion_session
+            .as_ref()
+            .is_some_and(|session| session.documented == Some(index))
+        {
+            return;
+        }
+
+        let side_popup = candidate
+            .item
+            .documentation
+            .as_ref()
+            .and_then(|documentation| self.completion_documentation_popup(documentation));
+        let resolve = candidate.resolve;
+        let has_side_popup = side_popup.is_some();
+        if let Some(popup) = self.active_state_mut().popups.top_mut() {
+            popup.side_popup = side_popup.map(Box::new);
+        }
+        if let Some(session) = self.completion_session.as_mut() {
+            session.documented = has_side_popup.then_some(index);
+        }
+        if !has_side_popup && resolve == ResolveState::Unresolved {
+            self.resolve_completion_candidate(index, None);
+        }
+    }
+
+    fn completion_documentation_popup(&self, documentation: &Documentation) -> Option<Popup> {
+        let mut popup = match documentation {
+            Documentation::String(text) if !text.trim().is_empty() => {
+                Popup::text(text.lines().map(str::to_string).collect(), &self.theme)
+            }
+            Documentation::MarkupContent(content) if !content.value.trim().is_empty() => {
+                match content.kind {
+                    MarkupKind::Markdown => Popup::markdown(&content.value, &self.theme),
+                    MarkupKind::PlainText => Popup::text(
+                        content.value.lines().map(str::to_string).collect(),
+                        &self.theme,
+                    ),
+                }
+            }
+            _ => return None,
+        };
+        popup.width = 50;
+        popup.max_height = 15;
+        popup.border_style = Style::default().fg(self.theme.popup_border_fg);
+        popup.background_style = Style::default().bg(self.theme.popup_bg);
+        Some(popup)
+    }
+
+    /// Send `completionItem/resolve` for a candidate, if its server can resolve items
+    fn resolve_completion_candidate(&mut self, index: usize, accepted: Option<BufferId>) {
+        let Some(session) = self.completion_session.as_mut() else {
+            return;
+        };
+        let session_id = session.id;
+        let language = session.language.clone();
+        let Some(candidate) = session.candidates.get_mut(index) else {
+            return;
+        };
+        // Resolve is tried once per item
+        candidate.resolve = ResolveState::Done;
+        let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.server_handle(&language, &candidate.server))
+        else {
+            return;
+        };
+        let resolves = handle
+            .capabilities()
+            .and_then(|caps| caps.completion_provider)
+            .and_then(|options| options.resolve_provider)
+            == Some(true);
+        if !resolves {
+            return;
+        }
+
+        let request_id = self.next_lsp_request_id;
+        if let Err(e) = handle.resolve_completion_item(request_id, candidate.item.clone()) {
+            tracing::warn!("Failed to send completion item resolve request: {}", e);
+            return;
+        }
+        self.next_lsp_request_id += 1;
+        candidate.resolve = ResolveState::Resolving;
+        self.pending_completion_resolves.insert(
+            request_id,
+            PendingCompletionResolve {
+                session: session_id,
+                candidate: index,
+                accepted,
+            },
+        );
+    }
+
+    /// Handle a resolved completion item
+    pub(super) fn handle_completion_item_resolved(
+        &mut self,
+        request_id: u64,
+        result: Result<CompletionItem, String>,
+    ) -> io::Result<()> {
+        let Some(pending) = self.pending_completion_resolves.remove(&request_id) else {
+            tracing::debug!(
+                "Ignoring stale completion item resolve response: {}",
+                request_id
+            );
+            return Ok(());
+        };
+        let Some(candidate) = self
+            .completion_session
+            .as_mut()
+            .filter(|session| session.id == pending.session)
+            .and_then(|session| session.candidates.get_mut(pending.candidate))
+        else {
+            return Ok(());
+        };
+        candidate.resolve = ResolveState::Done;
+        let resolved = match result {
+            Ok(item) => item,
+            Err(e) => {
+                tracing::debug!("Completion item resolve request failed: {}", e);
+                return Ok(());
+            }
+        };
+        merge_resolved_item(&mut candidate.item, resolved);
+
+        if let Some(buffer_id) = pending.accepted {
+            let edits = candidate.item.additional_text_edits.clone();
+            self.completion_session = None;
+            if let Some(edits) = edits {
+                self.apply_lsp_text_edits(buffer_id, edits)?;
+            }
+            return Ok(());
+        }
+        self.update_completion_documentation();
+        Ok(())
+    }
+}
+
+/// Range an item's edit replaces; for insert/replace edits the insert range, so
+/// accepting an item in the middle of a word keeps the rest of it
+fn edit_range(edit: &CompletionTextEdit) -> lsp_types::Range {
+    match edit {
+        CompletionTextEdit::Edit(edit) => edit.range,
+        CompletionTextEdit::InsertAndReplace(edit) => edit.insert,
+    }
+}
+
+fn edit_text(edit: &CompletionTextEdit) -> String {
+    match edit {
+        CompletionTextEdit::Edit(edit) => edit.new_text.clone(),
+        CompletionTextEdit::InsertAndReplace(edit) => edit.new_text.clone(),
+    }
+}
+
+/// Take the properties a resolve filled in, keeping what it left out
+fn merge_resolved_item(item: &mut CompletionItem, resolved: CompletionItem) {
+    if resolved.documentation.is_some() {
+        item.documentation = resolved.documentation;
+    }
+    if resolved.detail.is_some() {
+        item.detail = resolved.detail;
+    }
+    if resolved.additional_text_edits.is_some() {
+        item.additional_text_edits = resolved.additional_text_edits;
+    }
+    if resolved.text_edit.is_some() {
+        item.text_edit = resolved.text_edit;
+    }
+    if resolved.command.is_some() {
+        item.command = resolved.command;
+    }
+}
+
+fn completion_kind_icon(item: &CompletionItem) -> Option<&'static str> {
+    use lsp_types::CompletionItemKind;
+    match item.kind? {
+        CompletionItemKind::FUNCTION | CompletionItemKind::METHOD => Some("λ"),
+        CompletionItemKind::VARIABLE => Some("v"),
+        CompletionItemKind::STRUCT | CompletionItemKind::CLASS => Some("S"),
+        CompletionItemKind::CONSTANT => Some("c"),
+        CompletionItemKind::KEYWORD => Some("k"),
+        _ => None,
+    }
+}
+
+/// Order the items matching what was typed before each, returning their indices
+///
+/// Items whose `filterText` (or label) starts with the typed text come first, then
+/// those containing its characters in order; within each group items are ordered by
+/// `sortText` (or label). Matching ignores case.
+fn rank_completion_items(items: &[(&CompletionItem, String)]) -> Vec<usize> {
+    let mut ranked: Vec<(u8, &str, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (item, typed))| {
+            let filter_text = item
+                .filter_text
+                .as_deref()
+                .unwrap_or(&item.label)
+                .to_lowercase();
+            let typed = typed.to_lowercase();
+            let group = if filter_text.starts_with(&typed) {
+                0
+            } else if is_subsequence(&typed, &filter_text) {
+                1
+            } else {
+                return None;
+            };
+            let sort_text = item.sort_text.as_deref().unwrap_or(&item.label);
+            Some((group, sort_text, index))
+        })
+        .collect();
+    ranked.sort();
+    ranked.into_iter().map(|(_, _, index)| index).collect()
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str, filter_text: Option<&str>, sort_text: Option<&str>) -> CompletionItem {
+        CompletionItem {
+            label: label.to_string(),
+            filter_text: filter_text.map(str::to_string),
+            sort_text: sort_text.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rank_prefix_matches_before_fuzzy_matches() {
+        let items = [
+            item("format_args", None, None),
+            item("from_str", None, None),
+            item("first_of_match", None, None),
+            item("unrelated", None, None),
+        ];
+        let typed: Vec<_> = items.iter().map(|item| (item, "fm".to_string())).collect();
+        assert_eq!(rank_completion_items(&typed), vec![2, 0, 1]);
+
+        let typed: Vec<_> = items.iter().map(|item| (item, "Fo".to_string())).collect();
+        assert_eq!(rank_completion_items(&typed), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn test_rank_uses_filter_text_and_sort_text() {
+        let items = [
+            item(
+                "HashMap (use std::collections::HashMap)",
+                Some("HashMap"),
+                Some("2"),
+            ),
+            item("hash", None, Some("1")),
+            item("Map", Some("xMap"), None),
+        ];
+        let typed: Vec<_> = items.iter().map(|item| (item, "has".to_string())).collect();
+        assert_eq!(rank_completion_items(&typed), vec![1, 0]);
+
+        let typed: Vec<_> = items.iter().map(|item| (item, String::new())).collect();
+        assert_eq!(rank_completion_items(&typed), vec![1, 0, 2]);
+    }
+}
@@ -0,0 +1,1031 @@
+//! Debugging through the Debug Adapter Protocol
+//!
+//! "Start Debugging" spawns the debug adapter configured for the active buffer's
+//! language (`debug` in the config), initializes it and sends the configured launch or
+//! attach request. Breakpoints are gutter indicators (toggled with the
+//! "Toggle Breakpoint" command or a click in the indicator column), so they follow
+//! edits; every file's breakpoints are sent once the adapter is initialized and again
+//! whenever they change.
+//!
+//! When the program stops, the top frame of the stopped thread is opened and its line
+//! highlighted. The Variables, Call Stack and Watches panels are read-only buffers in
+//! the "debug" mode: Enter expands a variable, selects a frame, and `a`/Delete add and
+//! remove watch expressions, which are evaluated in the current frame at every stop.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use super::Editor;
+use crate::model::event::{BufferId, Event};
+use crate::primitives::text_property::TextPropertyEntry;
+use crate::services::dap::client::{DapHandle, DebugAdapterConfig, DebugRequest};
+use crate::services::dap::protocol::{
+    body_field, initialize_arguments, set_breakpoints_arguments, Breakpoint, Scope, StackFrame,
+    StoppedEvent, Variable,
+};
+use crate::services::lsp::manager::detect_language;
+use crate::view::margin::LineIndicator;
+use crate::view::overlay::{Overlay, OverlayFace, OverlayNamespace};
+use crate::view::prompt::PromptType;
+
+/// Namespace of the breakpoint gutter indicators
+const BREAKPOINT_NAMESPACE: &str = "debug-breakpoint";
+
+/// Gutter symbol of lines with a breakpoint
+const BREAKPOINT_SYMBOL: &str = "●";
+
+/// Above quick fix markers
+const BREAKPOINT_PRIORITY: i32 = 30;
+
+/// Namespace of the current frame's line highlight and gutter indicator
+const FRAME_NAMESPACE: &str = "debug-frame";
+
+/// Gutter symbol of the current frame's line
+const FRAME_SYMBOL: &str = "▶";
+
+/// Above breakpoints, so a breakpoint that was hit shows the frame marker
+const FRAME_PRIORITY: i32 = 40;
+
+/// Buffer mode of the debug panels (see `ModeRegistry::new`)
+const DEBUG_MODE: &str = "debug";
+
+/// Text property holding the index of the variable, frame or watch on a panel line
+const DEBUG_ENTRY_PROPERTY: &str = "debug_entry";
+
+/// Frames asked for when the program stops
+const STACK_TRACE_LEVELS: i64 = 100;
+
+/// The read-only buffers showing the state of a debug session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugPanel {
+    Variables,
+    CallStack,
+    Watches,
+}
+
+impl DebugPanel {
+    fn buffer_name(self) -> &'static str {
+        match self {
+            DebugPanel::Variables => "*Debug Variables*",
+            DebugPanel::CallStack => "*Debug Call Stack*",
+            DebugPanel::Watches => "*Debug Watches*",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            DebugPanel::Variables => "Variables",
+            DebugPanel::CallStack => "Call Stack",
+            DebugPanel::Watches => "Watches",
+        }
+    }
+}
+
+/// A watch expression and its value in the current frame
+#[derive(Debug, Clone)]
+pub(super) struct DebugWatch {
+    expression: String,
+    /// `None` until evaluated in a stopped frame
+    value: Option<Result<String, String>>,
+}
+
+/// A scope or variable shown in the variables panel, in display order
+#[derive(Debug, Clone)]
+struct VariableNode {
+    depth: usize,
+    name: String,
+    /// `None` for scopes
+    value: Option<String>,
+    type_name: Option<String>,
+    /// Reference for fetching the children, 0 if there are none
+    reference: i64,
+    expanded: bool,
+}
+
+impl VariableNode {
+    fn scope(scope: Scope) -> Self {
+        Self {
+            depth: 0,
+            name: scope.name,
+            value: None,
+            type_name: None,
+            reference: scope.variables_reference,
+            expanded: !scope.expensive,
+        }
+    }
+
+    fn variable(variable: Variable, depth: usize) -> Self {
+        Self {
+            depth,
+            name: variable.name,
+            value: Some(variable.value),
+            type_name: variable.type_name,
+            reference: variable.variables_reference,
+            expanded: false,
+        }
+    }
+}
+
+/// What a request to the debug adapter was sent for
+#[derive(Debug, Clone)]
+enum PendingDapRequest {
+    Initialize,
+    /// The launch or attach request
+    Start,
+    SetBreakpoints {
+        path: PathBuf,
+    },
+    Configure,
+    /// Threads to pause
+    Threads,
+    StackTrace,
+    Scopes {
+        frame_id: i64,
+    },
+    Variables {
+        reference: i64,
+    },
+    Evaluate {
+        index: usize,
+        frame_id: i64,
+    },
+    /// continue, next, stepIn or stepOut
+    Resume,
+    Pause,
+    Disconnect,
+}
+
+/// A running debug adapter and the state of its program
+pub(super) struct DebugSession {
+    id: u64,
+    handle: DapHandle,
+    language: String,
+    config: DebugAdapterConfig,
+    /// Launch or attach arguments, with variables replaced
+    arguments: Value,
+    /// Capabilities from the initialize response
+    capabilities: Value,
+    /// Whether the adapter asked for its configuration (breakpoints) yet
+    configured: bool,
+    /// Thread that stopped, while the program is paused
+    stopped_thread: Option<i64>,
+    frames: Vec<StackFrame>,
+    /// Index of the frame shown in the editor and the panels
+    current_frame: usize,
+    variables: Vec<VariableNode>,
+    next_request_id: u64,
+    pending: HashMap<u64, PendingDapRequest>,
+}
+
+impl DebugSession {
+    fn request(&mut self, command: &str, arguments: Value, pending: PendingDapRequest) {
+        let request_id = self.next_request_id;
+        match self.handle.request(request_id, command, arguments) {
+            Ok(()) => {
+                self.next_request_id += 1;
+                self.pending.insert(request_id, pending);
+            }
+            Err(e) => tracing::warn!("Failed to send debug adapter request: {}", e),
+        }
+    }
+
+    fn capability(&self, name: &str) -> bool {
+        self.capabilities
+            .get(name)
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    fn current_frame_id(&self) -> Option<i64> {
+        self.frames.get(self.current_frame).map(|frame| frame.id)
+    }
+
+    /// Forget the stopped state once the program runs again
+    fn resume(&mut self) {
+        self.stopped_thread = None;
+        self.frames.clear();
+        self.current_frame = 0;
+        self.variables.clear();
+    }
+}
+
+impl Editor {
+    /// Start a debug session for the active buffer's language
+    pub(super) fn start_debugging(&mut self) {
+        if self.debug_session.is_some() {
+            self.set_status_message("A debug session is already running".to_string());
+            return;
+        }
+        let file = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|metadata| metadata.file_path())
+            .cloned();
+        let Some(language) = file
+            .as_deref()
+            .and_then(|path| detect_language(path, &self.config.languages))
+        else {
+            self.set_status_message("No debug adapter for this buffer".to_string());
+            return;
+        };
+        let Some(config) = self.config.debug.get(&language).cloned() else {
+            self.set_status_message(format!("No debug adapter configured for {}", language));
+            return;
+        };
+        let (Some(runtime), Some(bridge)) = (&self.tokio_runtime, &self.async_bridge) else {
+            return;
+        };
+
+        self.next_debug_session_id += 1;
+        let id = self.next_debug_session_id;
+        let handle =
+            match DapHandle::spawn(runtime.handle(), &config, id, &self.working_dir, bridge) {
+                Ok(handle) => handle,
+                Err(e) => {
+                    self.set_status_message(format!("Failed to start debug adapter: {}", e));
+                    return;
+                }
+            };
+        let arguments = config.request_arguments(file.as_deref(), &self.working_dir);
+        let mut session = DebugSession {
+            id,
+            handle,
+            language,
+            config,
+            arguments,
+            capabilities: Value::Null,
+            configured: false,
+            stopped_thread: None,
+            frames: Vec::new(),
+            current_frame: 0,
+            variables: Vec::new(),
+            next_request_id: 0,
+            pending: HashMap::new(),
+        };
+        session.request(
+            "initialize",
+            initialize_arguments(&session.config.adapter_id()),
+            PendingDapRequest::Initialize,
+        );
+        self.set_status_message(format!("Starting {}...", session.config.command));
+        self.debug_session = Some(session);
+        self.render_debug_panels();
+    }
+
+    /// End the session, terminating a launched program
+    pub(super) fn stop_debugging(&mut self) {
+        let Some(session) = self.debug_session.as_mut() else {
+            self.set_status_message("No debug session".to_string());
+            return;
+        };
+        let terminate = session.config.request == DebugRequest::Launch;
+        session.request(
+            "disconnect",
+            json!({ "terminateDebuggee": terminate }),
+            PendingDapRequest::Disconnect,
+        );
+        self.set_status_message("Stopping debug session...".to_string());
+    }
+
+    /// Resume the paused program, or start debugging
+    pub(super) fn debug_continue(&mut self) {
+        if self.debug_session.is_none() {
+            self.start_debugging();
+        } else {
+            self.debug_step("continue");
+        }
+    }
+
+    /// Send a request resuming the stopped thread (continue, next, stepIn or stepOut)
+    pub(super) fn debug_step(&mut self, command: &str) {
+        let Some(session) = self.debug_session.as_mut() else {
+            self.set_status_message("No debug session".to_string());
+            return;
+        };
+        let Some(thread_id) = session.stopped_thread else {
+            self.set_status_message("The program is not paused".to_string());
+            return;
+        };
+        session.request(
+            command,
+            json!({ "threadId": thread_id }),
+            PendingDapRequest::Resume,
+        );
+    }
+
+    /// Pause the running program (its first thread)
+    pub(super) fn debug_pause(&mut self) {
+        let Some(session) = self.debug_session.as_mut() else {
+            self.set_status_message("No debug session".to_string());
+            return;
+        };
+        if session.stopped_thread.is_some() {
+            self.set_status_message("The program is already paused".to_string());
+            return;
+        }
+        session.request("threads", Value::Null, PendingDapRequest::Threads);
+    }
+
+    /// Set or remove a breakpoint on the cursor line of the active buffer
+    pub(super) fn toggle_breakpoint(&mut self) {
+        let buffer_id = self.active_buffer();
+        if self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|metadata| metadata.file_path())
+            .is_none()
+        {
+            self.set_status_message("Breakpoints can only be set in files".to_string());
+            return;
+        }
+        let color = self.theme.diagnostic_error_fg;
+        let Some(state) = self.buffers.get_mut(&buffer_id) else {
+            return;
+        };
+        let line = state
+            .buffer
+            .get_line_number(state.cursors.primary().position);
+        let existing = state
+            .margins
+            .line_indicator_positions(BREAKPOINT_NAMESPACE)
+            .into_iter()
+            .find(|&(_, position)| state.buffer.get_line_number(position) == line);
+        let message = match existing {
+            Some((marker_id, _)) => {
+                state
+                    .margins
+                    .remove_line_indicator(marker_id, BREAKPOINT_NAMESPACE);
+                format!("Breakpoint removed from line {}", line + 1)
+            }
+            None => {
+                let Some(line_start) = state.buffer.line_start_offset(line) else {
+                    return;
+                };
+                state.margins.set_line_indicator(
+                    line_start,
+                    BREAKPOINT_NAMESPACE.to_string(),
+                    LineIndicator::new(BREAKPOINT_SYMBOL, color, BREAKPOINT_PRIORITY),
+                );
+                format!("Breakpoint set on line {}", line + 1)
+            }
+        };
+        self.set_status_message(message);
+
+        if self
+            .debug_session
+            .as_ref()
+            .is_some_and(|session| session.configured)
+        {
+            self.send_breakpoints(buffer_id);
+        }
+    }
+
+    /// Lines (0-based) of a buffer's breakpoints, where edits have moved them
+    pub(super) fn breakpoint_lines(&self, buffer_id: BufferId) -> Vec<usize> {
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return Vec::new();
+        };
+        let mut lines: Vec<usize> = state
+            .margins
+            .line_indicator_positions(BREAKPOINT_NAMESPACE)
+            .into_iter()
+            .map(|(_, position)| state.buffer.get_line_number(position))
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        lines
+    }
+
+    /// Replace the adapter's breakpoints for a buffer's file
+    fn send_breakpoints(&mut self, buffer_id: BufferId) {
+        let Some(path) = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|metadata| metadata.file_path())
+            .cloned()
+        else {
+            return;
+        };
+        let lines = self.breakpoint_lines(buffer_id);
+        if let Some(session) = self.debug_session.as_mut() {
+            session.request(
+                "setBreakpoints",
+                set_breakpoints_arguments(&path.to_string_lossy(), &lines),
+                PendingDapRequest::SetBreakpoints { path },
+            );
+        }
+    }
+
+    /// Handle the response to a request of the current session
+    pub(super) fn handle_dap_response(
+        &mut self,
+        session_id: u64,
+        request_id: u64,
+        command: String,
+        result: Result<Value, String>,
+    ) {
+        let Some(session) = self
+            .debug_session
+            .as_mut()
+            .filter(|session| session.id == session_id)
+        else {
+            return;
+        };
+        let Some(pending) = session.pending.remove(&request_id) else {
+            return;
+        };
+
+        let body = match result {
+            Ok(body) => body,
+            Err(e) => {
+                match pending {
+                    PendingDapRequest::Initialize | PendingDapRequest::Start => {
+                        self.end_debug_session(format!("Debugging failed: {}", e));
+                    }
+                    PendingDapRequest::Disconnect => {
+                        self.end_debug_session("Debug session ended".to_string());
+                    }
+                    PendingDapRequest::Evaluate { index, .. } => {
+                        if let Some(watch) = self.debug_watches.get_mut(index) {
+                            watch.value = Some(Err(e));
+                        }
+                        self.render_debug_panel(DebugPanel::Watches);
+                    }
+                    _ => self.set_status_message(format!("Debug {} failed: {}", command, e)),
+                }
+                return;
+            }
+        };
+
+        match pending {
+            PendingDapRequest::Initialize => {
+                session.capabilities = body;
+                let command = session.config.request.command();
+                let arguments = session.arguments.clone();
+                session.request(command, arguments, PendingDapRequest::Start);
+            }
+            PendingDapRequest::Start => {
+                let message = format!("Debugging {}", session.language);
+                self.set_status_message(message);
+            }
+            PendingDapRequest::SetBreakpoints { path } => {
+                let breakpoints: Vec<Breakpoint> =
+                    body_field(&body, "breakpoints").unwrap_or_default();
+                let unverified = breakpoints.iter().filter(|bp| !bp.verified).count();
+                if unverified > 0 {
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    self.set_status_message(format!(
+                        "{} breakpoint(s) in {} could not be set",
+                        unverified, name
+                    ));
+                }
+            }
+            PendingDapRequest::Configure | PendingDapRequest::Pause => {}
+            PendingDapRequest::Threads => {
+                let thread = body
+                    .get("threads")
+                    .and_then(|threads| threads.get(0))
+                    .and_then(|thread| thread.get("id"))
+                    .and_then(Value::as_i64);
+                match thread {
+                    Some(thread_id) => session.request(
+                        "pause",
+                        json!({ "threadId": thread_id }),
+                        PendingDapRequest::Pause,
+                    ),
+                    None => self.set_status_message("No thread to pause".to_string()),
+                }
+            }
+            PendingDapRequest::StackTrace => {
+                session.frames = body_field(&body, "stackFrames").unwrap_or_default();
+                session.current_frame = 0;
+                self.show_current_frame();
+            }
+            PendingDapRequest::Scopes { frame_id } => {
+                if session.current_frame_id() != Some(frame_id) {
+                    return;
+                }
+                let scopes: Vec<Scope> = body_field(&body, "scopes").unwrap_or_default();
+                session.variables = scopes.into_iter().map(VariableNode::scope).collect();
+                let references: Vec<i64> = session
+                    .variables
+                    .iter()
+                    .filter(|node| node.expanded && node.reference > 0)
+                    .map(|node| node.reference)
+                    .collect();
+                for reference in references {
+                    session.request(
+                        "variables",
+                        json!({ "variablesReference": reference }),
+                        PendingDapRequest::Variables { reference },
+                    );
+                }
+                self.render_debug_panel(DebugPanel::Variables);
+            }
+            PendingDapRequest::Variables { reference } => {
+                let variables: Vec<Variable> = body_field(&body, "variables").unwrap_or_default();
+                let Some(index) = session
+                    .variables
+                    .iter()
+                    .position(|node| node.reference == reference && node.expanded)
+                else {
+                    return;
+                };
+                let depth = session.variables[index].depth + 1;
+                let children = variables
+                    .into_iter()
+                    .map(|variable| VariableNode::variable(variable, depth));
+                session.variables.splice(index + 1..index + 1, children);
+                self.render_debug_panel(DebugPanel::Variables);
+            }
+            PendingDapRequest::Evaluate { index, frame_id } => {
+                if session.current_frame_id() != Some(frame_id) {
+                    return;
+                }
+                let value = body
+                    .get("result")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                if let Some(watch) = self.debug_watches.get_mut(index) {
+                    watch.value = Some(Ok(value));
+                }
+                self.render_debug_panel(DebugPanel::Watches);
+            }
+            PendingDapRequest::Resume => {
+                // Adapters don't send `continued` for requests that resume the program
+                session.resume();
+                self.clear_debug_frame();
+                self.render_debug_panels();
+            }
+            PendingDapRequest::Disconnect => {
+                self.end_debug_session("Debug session ended".to_string());
+            }
+        }
+    }
+
+    /// Handle an event of the current session
+    pub(super) fn handle_dap_event(&mut self, session_id: u64, event: String, body: Value) {
+        let Some(session) = self
+            .debug_session
+            .as_mut()
+            .filter(|session| session.id == session_id)
+        else {
+            return;
+        };
+
+        match event.as_str() {
+            "initialized" => {
+                session.configured = true;
+                session.request(
+                    "setExceptionBreakpoints",
+                    json!({ "filters": [] }),
+                    PendingDapRequest::Configure,
+                );
+                let configuration_done = session.capability("supportsConfigurationDoneRequest");
+                let buffers: Vec<BufferId> = self
+                    .buffers
+                    .keys()
+                    .copied()
+                    .filter(|&buffer_id| !self.breakpoint_lines(buffer_id).is_empty())
+                    .collect();
+                for buffer_id in buffers {
+                    self.send_breakpoints(buffer_id);
+                }
+                if configuration_done {
+                    if let Some(session) = self.debug_session.as_mut() {
+                        session.request(
+                            "configurationDone",
+                            Value::Null,
+                            PendingDapRequest::Configure,
+                        );
+                    }
+                }
+            }
+            "stopped" => {
+                let Ok(stopped) = serde_json::from_value::<StoppedEvent>(body) else {
+                    return;
+                };
+                let thread_id = stopped.thread_id.unwrap_or(1);
+                session.stopped_thread = Some(thread_id);
+                session.request(
+                    "stackTrace",
+                    json!({ "threadId": thread_id, "startFrame": 0, "levels": STACK_TRACE_LEVELS }),
+                    PendingDapRequest::StackTrace,
+                );
+                let detail = stopped.description.or(stopped.text);
+                self.set_status_message(match detail {
+                    Some(detail) => format!("Paused on {}: {}", stopped.reason, detail),
+                    None => format!("Paused on {}", stopped.reason),
+                });
+            }
+            "continued" => {
+                session.resume();
+                self.clear_debug_frame();
+                self.render_debug_panels();
+            }
+            "exited" => {
+                let code = body.get("exitCode").and_then(Value::as_i64).unwrap_or(0);
+                self.set_status_message(format!("Program exited with code {}", code));
+            }
+            "terminated" => {
+                self.end_debug_session("Debug session ended".to_string());
+            }
+            "output" => {
+                let output = body.get("output").and_then(Value::as_str).unwrap_or("");
+                tracing::debug!("Debug adapter output: {}", output.trim_end());
+            }
+            _ => tracing::trace!("Ignoring debug adapter event: {}", event),
+        }
+    }
+
+    /// Handle the exit of a session's adapter
+    pub(super) fn handle_dap_exited(&mut self, session_id: u64, error: String) {
+        if self
+            .debug_session
+            .as_ref()
+            .is_some_and(|session| session.id == session_id)
+        {
+            tracing::info!("Debug adapter exited: {}", error);
+            self.end_debug_session("Debug adapter exited".to_string());
+        }
+    }
+
+    /// Drop the session (killing the adapter) and clear what it showed
+    fn end_debug_session(&mut self, message: String) {
+        self.debug_session = None;
+        self.clear_debug_frame();
+        self.render_debug_panels();
+        self.set_status_message(message);
+    }
+
+    /// Open the current frame's file at its line and highlight the line
+    fn show_current_frame(&mut self) {
+        self.clear_debug_frame();
+        let Some(session) = self.debug_session.as_mut() else {
+            return;
+        };
+        let Some(frame) = session.frames.get(session.current_frame).cloned() else {
+            self.render_debug_panels();
+            return;
+        };
+        session.variables.clear();
+        session.request(
+            "scopes",
+            json!({ "frameId": frame.id }),
+            PendingDapRequest::Scopes { frame_id: frame.id },
+        );
+        for (index, watch) in self.debug_watches.iter_mut().enumerate() {
+            watch.value = None;
+            session.request(
+                "evaluate",
+                json!({ "expression": watch.expression, "frameId": frame.id, "context": "watch" }),
+                PendingDapRequest::Evaluate {
+                    index,
+                    frame_id: frame.id,
+                },
+            );
+        }
+        self.render_debug_panels();
+
+        let Some(path) = frame.source.and_then(|source| source.path) else {
+            return;
+        };
+        let buffer_id = match self.open_file(Path::new(&path)) {
+            Ok(buffer_id) => buffer_id,
+            Err(e) => {
+                self.set_status_message(format!("Failed to open {}: {}", path, e));
+                return;
+            }
+        };
+        let line = (frame.line - 1).max(0) as usize;
+        let column = (frame.column - 1).max(0) as usize;
+        let background = self.theme.diagnostic_warning_bg;
+        let marker = self.theme.diagnostic_warning_fg;
+        let Some(state) = self.buffers.get_mut(&buffer_id) else {
+            return;
+        };
+        let Some(line_start) = state.buffer.line_start_offset(line) else {
+            return;
+        };
+        let line_end = state
+            .buffer
+            .line_start_offset(line + 1)
+            .unwrap_or_else(|| state.buffer.len());
+        let overlay = Overlay::with_namespace(
+            &mut state.marker_list,
+            line_start..line_end,
+            OverlayFace::Background { color: background },
+            OverlayNamespace::from_string(FRAME_NAMESPACE.to_string()),
+        );
+        state.overlays.add(overlay);
+        state.margins.set_line_indicator(
+            line_start,
+            FRAME_NAMESPACE.to_string(),
+            LineIndicator::new(FRAME_SYMBOL, marker, FRAME_PRIORITY),
+        );
+
+        let position = state.buffer.line_col_to_position(line, column);
+        let cursor = *state.cursors.primary();
+        state.apply(&Event::MoveCursor {
+            cursor_id: state.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: position,
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        });
+    }
+
+    /// Remove the current frame's highlight from every buffer
+    fn clear_debug_frame(&mut self) {
+        let namespace = OverlayNamespace::from_string(FRAME_NAMESPACE.to_string());
+        for state in self.buffers.values_mut() {
+            state
+                .overlays
+                .clear_namespace(&namespace, &mut state.marker_list);
+            state
+                .margins
+                .clear_line_indicators_for_namespace(FRAME_NAMESPACE);
+        }
+    }
+
+    /// Open a debug panel, reusing its buffer if it is open
+    pub(super) fn show_debug_panel(&mut self, panel: DebugPanel) {
+        let existing = self
+            .debug_panels
+            .iter()
+            .find(|(_, &shown)| shown == panel)
+            .map(|(&buffer_id, _)| buffer_id);
+        let buffer_id = match existing {
+            Some(buffer_id) => buffer_id,
+            None => {
+                let buffer_id = self.create_virtual_buffer(
+                    panel.buffer_name().to_string(),
+                    DEBUG_MODE.to_string(),
+                    true,
+                );
+                if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                    state.editing_disabled = true;
+                    state.margins.set_line_numbers(false);
+                }
+                self.debug_panels.insert(buffer_id, panel);
+                buffer_id
+            }
+        };
+        self.render_debug_panel(panel);
+        self.set_active_buffer(buffer_id);
+    }
+
+    fn render_debug_panels(&mut self) {
+        for panel in [
+            DebugPanel::Variables,
+            DebugPanel::CallStack,
+            DebugPanel::Watches,
+        ] {
+            self.render_debug_panel(panel);
+        }
+    }
+
+    /// Rewrite a debug panel's buffer, if it is open
+    fn render_debug_panel(&mut self, panel: DebugPanel) {
+        let Some(buffer_id) = self
+            .debug_panels
+            .iter()
+            .find(|(_, &shown)| shown == panel)
+            .map(|(&buffer_id, _)| buffer_id)
+        else {
+            return;
+        };
+
+        let mut entries = vec![TextPropertyEntry::text(format!("{}\n\n", panel.title()))];
+        let session = self.debug_session.as_ref();
+        let paused = session.is_some_and(|session| session.stopped_thread.is_some());
+        match panel {
+            DebugPanel::Watches => {}
+            _ if session.is_none() => entries.push(TextPropertyEntry::text("Not debugging\n")),
+            _ if !paused => entries.push(TextPropertyEntry::text("Running\n")),
+            _ => {}
+        }
+
+        match (panel, session) {
+            (DebugPanel::Variables, Some(session)) if paused => {
+                for (index, node) in session.variables.iter().enumerate() {
+                    entries.push(
+                        TextPropertyEntry::text(format_variable(node))
+                            .with_property(DEBUG_ENTRY_PROPERTY, json!(index)),
+                    );
+                }
+            }
+            (DebugPanel::CallStack, Some(session)) if paused => {
+                for (index, frame) in session.frames.iter().enumerate() {
+                    let current = index == session.current_frame;
+                    entries.push(
+                        TextPropertyEntry::text(format_frame(frame, current, &self.working_dir))
+                            .with_property(DEBUG_ENTRY_PROPERTY, json!(index)),
+                    );
+                }
+            }
+            (DebugPanel::Watches, _) => {
+                for (index, watch) in self.debug_watches.iter().enumerate() {
+                    let value = match &watch.value {
+                        Some(Ok(value)) => value.clone(),
+                        Some(Err(e)) => format!("<{}>", e),
+                        None => "<not available>".to_string(),
+                    };
+                    entries.push(
+                        TextPropertyEntry::text(format!("{} = {}\n", watch.expression, value))
+                            .with_property(DEBUG_ENTRY_PROPERTY, json!(index)),
+                    );
+                }
+                entries.push(TextPropertyEntry::text(
+                    "\na: add watch, Delete: remove watch\n",
+                ));
+            }
+            _ => {}
+        }
+
+        if let Err(e) = self.set_virtual_buffer_content(buffer_id, entries) {
+            tracing::warn!("Failed to update debug panel: {}", e);
+        }
+    }
+
+    /// Add a watch expression, evaluating it right away if the program is paused
+    pub(super) fn add_debug_watch(&mut self, expression: &str) {
+        if expression.is_empty() {
+            return;
+        }
+        self.debug_watches.push(DebugWatch {
+            expression: expression.to_string(),
+            value: None,
+        });
+        let index = self.debug_watches.len() - 1;
+        if let Some(session) = self.debug_session.as_mut() {
+            if let Some(frame_id) = session
+                .stopped_thread
+                .and_then(|_| session.current_frame_id())
+            {
+                session.request(
+                    "evaluate",
+                    json!({ "expression": expression, "frameId": frame_id, "context": "watch" }),
+                    PendingDapRequest::Evaluate { index, frame_id },
+                );
+            }
+        }
+        self.render_debug_panel(DebugPanel::Watches);
+    }
+
+    /// Run a command bound in the debug buffer mode ("debug-<command>")
+    pub(super) fn handle_debug_panel_command(&mut self, command: &str) {
+        let buffer_id = self.active_buffer();
+        let Some(&panel) = self.debug_panels.get(&buffer_id) else {
+            return;
+        };
+        let entry = self.get_text_properties_at_cursor().and_then(|properties| {
+            properties
+                .iter()
+                .find_map(|property| property.get_as::<usize>(DEBUG_ENTRY_PROPERTY))
+        });
+
+        match (command, panel, entry) {
+            ("add-watch", _, _) => {
+                self.start_prompt("Watch expression: ".to_string(), PromptType::AddWatch)
+            }
+            ("remove-watch", DebugPanel::Watches, Some(index)) => {
+                if index < self.debug_watches.len() {
+                    self.debug_watches.remove(index);
+                }
+                self.render_debug_panel(DebugPanel::Watches);
+            }
+            ("select", DebugPanel::Variables, Some(index)) => self.toggle_debug_variable(index),
+            ("select", DebugPanel::CallStack, Some(index)) => {
+                if let Some(session) = self.debug_session.as_mut() {
+                    if index < session.frames.len() {
+                        session.current_frame = index;
+                        self.show_current_frame();
+                    }
+                }
+            }
+            ("select" | "remove-watch", _, _) => {}
+            _ => tracing::warn!("Unknown debug panel command: {}", command),
+        }
+    }
+
+    /// Expand or collapse a structured variable
+    fn toggle_debug_variable(&mut self, index: usize) {
+        let Some(session) = self.debug_session.as_mut() else {
+            return;
+        };
+        let Some(node) = session.variables.get_mut(index) else {
+            return;
+        };
+        if node.reference == 0 {
+            return;
+        }
+        node.expanded = !node.expanded;
+        let (expanded, depth, reference) = (node.expanded, node.depth, node.reference);
+        if expanded {
+            session.request(
+                "variables",
+                json!({ "variablesReference": reference }),
+                PendingDapRequest::Variables { reference },
+            );
+        } else {
+            let end = session.variables[index + 1..]
+                .iter()
+                .position(|child| child.depth <= depth)
+                .map_or(session.variables.len(), |offset| index + 1 + offset);
+            session.variables.drain(index + 1..end);
+        }
+        self.render_debug_panel(DebugPanel::Variables);
+    }
+}
+
+/// Line of the variables panel for a scope or variable
+fn format_variable(node: &VariableNode) -> String {
+    let indent = "  ".repeat(node.depth);
+    let marker = match (node.reference > 0, node.expanded) {
+        (false, _) => "  ",
+        (true, true) => "▾ ",
+        (true, false) => "▸ ",
+    };
+    let type_name = node
+        .type_name
+        .as_ref()
+        .map(|type_name| format!(": {}", type_name))
+        .unwrap_or_default();
+    match &node.value {
+        Some(value) => format!(
+            "{}{}{}{} = {}\n",
+            indent, marker, node.name, type_name, value
+        ),
+        None => format!("{}{}{}\n", indent, marker, node.name),
+    }
+}
+
+/// Line of the call stack panel for a frame, with its location relative to `working_dir`
+fn format_frame(frame: &StackFrame, current: bool, working_dir: &Path) -> String {
+    let marker = if current { "▶ " } else { "  " };
+    let location = frame
+        .source
+        .as_ref()
+        .and_then(|source| source.path.as_deref().or(source.name.as_deref()))
+        .map(|path| {
+            let path = Path::new(path);
+            let path = path.strip_prefix(working_dir).unwrap_or(path);
+            format!("  {}:{}", path.display(), frame.line)
+        })
+        .unwrap_or_default();
+    format!("{}{}{}\n", marker, frame.name, location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::dap::protocol::Source;
+
+    #[test]
+    fn test_format_variable() {
+        let scope = VariableNode::scope(Scope {
+            name: "Locals".to_string(),
+            variables_reference: 1,
+            expensive: false,
+        });
+        assert_eq!(format_variable(&scope), "▾ Locals\n");
+
+        let variable = VariableNode::variable(
+            Variable {
+                name: "count".to_string(),
+                value: "42".to_string(),
+                type_name: Some("i32".to_string()),
+                variables_reference: 0,
+            },
+            1,
+        );
+        assert_eq!(format_variable(&variable), "    count: i32 = 42\n");
+    }
+
+    #[test]
+    fn test_format_frame_relative_to_working_dir() {
+        let frame = StackFrame {
+            id: 1,
+            name: "main".to_string(),
+            source: Some(Source {
+                name: Some("main.rs".to_string()),
+                path: Some("/work/demo/src/main.rs".to_string()),
+            }),
+            line: 3,
+            column: 5,
+        };
+        assert_eq!(
+            format_frame(&frame, true, Path::new("/work/demo")),
+            "▶ main  src/main.rs:3\n"
+        );
+    }
+}
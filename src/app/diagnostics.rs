@@ -0,0 +1,389 @@
+//! Inline diagnostics, the diagnostics popup and quick fix indicators
+//!
+//! Diagnostics at or above `editor.inline_diagnostics` are shown at the end of their
+//! line. Hovering a diagnostic lists it in a popup together with its related
+//! information; choosing a related entry jumps to its location. For every line with
+//! diagnostics, servers are asked for quick fixes, and lines that have some are
+//! marked in the gutter.
+
+use std::collections::BTreeMap;
+
+use lsp_types::{CodeActionKind, CodeActionOrCommand, Diagnostic, DiagnosticSeverity};
+use ratatui::style::Style;
+
+use super::{uri_to_path, Editor};
+use crate::model::event::BufferId;
+use crate::services::lsp::client::LspFeature;
+use crate::services::lsp::manager::detect_language;
+use crate::view::margin::LineIndicator;
+use crate::view::popup::{Popup, PopupListItem, PopupPosition};
+
+/// Title of the diagnostics popup, used to recognize it when an item is confirmed
+pub(super) const DIAGNOSTICS_POPUP_TITLE: &str = "Diagnostics";
+
+/// Namespace of the quick fix gutter indicators
+const QUICK_FIX_NAMESPACE: &str = "lsp-quick-fix";
+
+/// Gutter symbol of lines with quick fixes
+const QUICK_FIX_SYMBOL: &str = "◆";
+
+/// Above git and modification markers
+const QUICK_FIX_PRIORITY: i32 = 20;
+
+/// Lines asked for quick fixes each time a buffer's diagnostics change
+const QUICK_FIX_PROBE_LIMIT: usize = 50;
+
+/// A quick fix request for a line with diagnostics
+#[derive(Debug, Clone)]
+pub(super) struct QuickFixProbe {
+    buffer_id: BufferId,
+    line: usize,
+    /// Servers yet to answer
+    responses_remaining: usize,
+}
+
+impl Editor {
+    /// Show a buffer's diagnostics at the end of their line
+    pub(super) fn apply_inline_diagnostics(
+        &mut self,
+        buffer_id: BufferId,
+        diagnostics: &[Diagnostic],
+    ) {
+        let shown = self.config.editor.inline_diagnostics;
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            crate::services::lsp::diagnostics::apply_inline_diagnostics_to_state(
+                state,
+                diagnostics,
+                shown,
+                &self.theme,
+            );
+        }
+    }
+
+    /// Ask the servers for quick fixes on each line with diagnostics
+    ///
+    /// The indicators of the previous diagnostics are cleared right away; lines are
+    /// marked again as the answers arrive.
+    pub(super) fn request_quick_fixes(
+        &mut self,
+        buffer_id: BufferId,
+        uri: &str,
+        diagnostics: &[Diagnostic],
+    ) {
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state
+                .margins
+                .clear_line_indicators_for_namespace(QUICK_FIX_NAMESPACE);
+        }
+        self.quick_fix_probes
+            .retain(|_, probe| probe.buffer_id != buffer_id);
+        if diagnostics.is_empty() {
+            return;
+        }
+
+        let Ok(parsed_uri) = uri.parse::<lsp_types::Uri>() else {
+            return;
+        };
+        let Some(language) = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|metadata| metadata.file_path())
+            .and_then(|path| detect_language(path, &self.config.languages))
+        else {
+            return;
+        };
+
+        // The diagnostics of each line, asked about together
+        let mut by_line: BTreeMap<usize, Vec<Diagnostic>> = BTreeMap::new();
+        for diagnostic in diagnostics {
+            by_line
+                .entry(diagnostic.range.start.line as usize)
+                .or_default()
+                .push(diagnostic.clone());
+        }
+
+        for (line, line_diagnostics) in by_line.into_iter().take(QUICK_FIX_PROBE_LIMIT) {
+            let range = lsp_types::Range {
+                start: line_diagnostics
+                    .iter()
+                    .map(|d| d.range.start)
+                    .min()
+                    .unwrap_or_default(),
+                end: line_diagnostics
+                    .iter()
+                    .map(|d| d.range.end)
+                    .max()
+                    .unwrap_or_default(),
+            };
+            let request_id = self.next_lsp_request_id;
+            let Some(lsp) = self.lsp.as_mut() else {
+                return;
+            };
+            let handles = lsp.handles_for_feature(&language, LspFeature::CodeAction);
+            if handles.is_empty() {
+                return;
+            }
+            let responses_remaining = handles.len();
+            for handle in handles {
+                let _ = handle.quick_fixes(
+                    request_id,
+                    parsed_uri.clone(),
+                    range,
+                    line_diagnostics.clone(),
+                );
+            }
+            self.next_lsp_request_id += 1;
+            self.quick_fix_probes.insert(
+                request_id,
+                QuickFixProbe {
+                    buffer_id,
+                    line,
+                    responses_remaining,
+                },
+            );
+        }
+    }
+
+    /// Whether a code actions response answers a quick fix request
+    pub(super) fn is_quick_fix_probe(&self, request_id: u64) -> bool {
+        self.quick_fix_probes.contains_key(&request_id)
+    }
+
+    /// Mark the line of a quick fix request if a server offered a fix
+    pub(super) fn handle_quick_fix_probe_response(
+        &mut self,
+        request_id: u64,
+        actions: Vec<CodeActionOrCommand>,
+    ) {
+        let Some(probe) = self.quick_fix_probes.get_mut(&request_id) else {
+            return;
+        };
+        probe.responses_remaining = probe.responses_remaining.saturating_sub(1);
+        let probe = probe.clone();
+        if probe.responses_remaining == 0 {
+            self.quick_fix_probes.remove(&request_id);
+        }
+        if !actions.iter().any(is_quick_fix) {
+            return;
+        }
+
+        let color = self.theme.diagnostic_warning_fg;
+        let Some(state) = self.buffers.get_mut(&probe.buffer_id) else {
+            return;
+        };
+        let Some(line_start) = state.buffer.line_start_offset(probe.line) else {
+            return;
+        };
+        let already_marked = state
+            .margins
+            .get_line_indicator(probe.line, |byte| state.buffer.get_line_number(byte))
+            .is_some_and(|indicator| indicator.symbol == QUICK_FIX_SYMBOL);
+        if !already_marked {
+            state.margins.set_line_indicator(
+                line_start,
+                QUICK_FIX_NAMESPACE.to_string(),
+                LineIndicator::new(QUICK_FIX_SYMBOL, color, QUICK_FIX_PRIORITY),
+            );
+        }
+    }
+
+    /// Diagnostics of the active buffer on some lines, sent as context with code actions
+    pub(super) fn diagnostics_on_lines(
+        &self,
+        uri: &lsp_types::Uri,
+        lines: std::ops::RangeInclusive<u32>,
+    ) -> Vec<Diagnostic> {
+        self.stored_diagnostics
+            .get(uri.as_str())
+            .map(|diagnostics| {
+                diagnostics
+                    .iter()
+                    .filter(|d| {
+                        d.range.start.line <= *lines.end() && d.range.end.line >= *lines.start()
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Diagnostics of the active buffer whose range contains a byte position
+    pub(super) fn diagnostics_at(&self, position: usize) -> Vec<Diagnostic> {
+        let Some(uri) = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|metadata| metadata.file_uri())
+        else {
+            return Vec::new();
+        };
+        let Some(diagnostics) = self.stored_diagnostics.get(uri.as_str()) else {
+            return Vec::new();
+        };
+        let buffer = &self.active_state().buffer;
+        diagnostics
+            .iter()
+            .filter(|d| {
+                let start = buffer.lsp_position_to_byte(
+                    d.range.start.line as usize,
+                    d.range.start.character as usize,
+                );
+                let end = buffer.lsp_position_to_byte(
+                    d.range.end.line as usize,
+                    d.range.end.character as usize,
+                );
+                (start..=end).contains(&position)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// List diagnostics and their related information in a popup
+    ///
+    /// The hover documentation, if any, is shown beside the list.
+    pub(super) fn show_diagnostics_popup(
+        &mut self,
+        diagnostics: Vec<Diagnostic>,
+        position: PopupPosition,
+        hover: Option<Popup>,
+    ) {
+        let mut items = Vec::new();
+        let mut locations = Vec::new();
+        for diagnostic in &diagnostics {
+            let mut lines = diagnostic.message.lines();
+            let mut item = PopupListItem::new(lines.next().unwrap_or_default().to_string())
+                .with_icon(severity_icon(diagnostic.severity).to_string());
+            if let Some(detail) = diagnostic_source(diagnostic) {
+                item = item.with_detail(detail);
+            }
+            items.push(item);
+            items.extend(lines.map(|line| PopupListItem::new(format!("  {}", line))));
+
+            for related in diagnostic.related_information.iter().flatten() {
+                let place = match uri_to_path(&related.location.uri) {
+                    Ok(path) => format!(
+                        "{}:{}",
+                        self.display_path(&path),
+                        related.location.range.start.line + 1
+                    ),
+                    Err(_) => related.location.uri.as_str().to_string(),
+                };
+                let text = format!("  ↳ {}: {}", place, related.message);
+                items.push(PopupListItem::new(text).with_data(locations.len().to_string()));
+                locations.push(related.location.clone());
+            }
+        }
+
+        let width = items
+            .iter()
+            .map(|item| {
+                item.text.chars().count()
+                    + item.detail.as_ref().map_or(0, |d| d.chars().count() + 2)
+            })
+            .max()
+            .unwrap_or(0)
+            .saturating_add(6)
+            .clamp(30, 80) as u16;
+        let mut popup = Popup::list(items, &self.theme)
+            .with_title(DIAGNOSTICS_POPUP_TITLE.to_string())
+            .with_position(position)
+            .with_width(width)
+            .with_max_height(15);
+        popup.border_style = Style::default().fg(self.theme.popup_border_fg);
+        popup.side_popup = hover.map(Box::new);
+
+        self.diagnostic_popup_locations = locations;
+        self.active_state_mut().popups.show(popup);
+    }
+
+    /// Jump to the related location chosen in the diagnostics popup (`data` is its index)
+    pub(super) fn confirm_diagnostic_location(&mut self, data: &str) -> std::io::Result<()> {
+        let Some(location) = data
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| self.diagnostic_popup_locations.get(index).cloned())
+        else {
+            return Ok(());
+        };
+        match self.open_location(&location)? {
+            Some(path) => self.set_status_message(format!(
+                "Jumped to {}:{}",
+                self.display_path(&path),
+                location.range.start.line + 1
+            )),
+            None => self.set_status_message(format!("Could not open {}", location.uri.as_str())),
+        }
+        Ok(())
+    }
+}
+
+/// Whether a code action is an applicable quick fix
+fn is_quick_fix(action: &CodeActionOrCommand) -> bool {
+    match action {
+        CodeActionOrCommand::CodeAction(action) => {
+            action.disabled.is_none()
+                && action.kind.as_ref().is_some_and(|kind| {
+                    kind.as_str().starts_with(CodeActionKind::QUICKFIX.as_str())
+                })
+        }
+        CodeActionOrCommand::Command(_) => false,
+    }
+}
+
+fn severity_icon(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::WARNING) => "W",
+        Some(DiagnosticSeverity::INFORMATION) => "I",
+        Some(DiagnosticSeverity::HINT) => "H",
+        _ => "E",
+    }
+}
+
+/// The source and code of a diagnostic, e.g. "rustc E0308"
+fn diagnostic_source(diagnostic: &Diagnostic) -> Option<String> {
+    let code = diagnostic.code.as_ref().map(|code| match code {
+        lsp_types::NumberOrString::Number(number) => number.to_string(),
+        lsp_types::NumberOrString::String(text) => text.clone(),
+    });
+    match (&diagnostic.source, code) {
+        (Some(source), Some(code)) => Some(format!("{} {}", source, code)),
+        (Some(source), None) => Some(source.clone()),
+        (None, code) => code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::CodeAction;
+
+    #[test]
+    fn test_is_quick_fix() {
+        let action = |kind: Option<CodeActionKind>| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Fix".to_string(),
+                kind,
+                ..Default::default()
+            })
+        };
+        assert!(is_quick_fix(&action(Some(CodeActionKind::QUICKFIX))));
+        assert!(is_quick_fix(&action(Some(CodeActionKind::from(
+            "quickfix.import".to_string()
+        )))));
+        assert!(!is_quick_fix(&action(Some(CodeActionKind::REFACTOR))));
+        assert!(!is_quick_fix(&action(None)));
+    }
+
+    #[test]
+    fn test_diagnostic_source() {
+        let diagnostic = Diagnostic {
+            source: Some("rustc".to_string()),
+            code: Some(lsp_types::NumberOrString::String("E0308".to_string())),
+            ..Default::default()
+        };
+        assert_eq!(
+            diagnostic_source(&diagnostic).as_deref(),
+            Some("rustc E0308")
+        );
+        assert_eq!(diagnostic_source(&Diagnostic::default()), None);
+    }
+}
@@ -521,12 +521,20 @@ impl Editor {
             .map(|p| p.join(&new_name))
             .unwrap_or_else(|| original_path.clone());
 
+        // Language servers may want to update references to the file first
+        self.lsp_will_rename(&original_path, &new_path);
+
         if let Some(runtime) = &self.tokio_runtime {
             let result =
                 runtime.block_on(async { tokio::fs::rename(&original_path, &new_path).await });
 
             match result {
                 Ok(_) => {
+                    self.move_buffers_after_rename(&original_path, &new_path);
+                    self.lsp_did_rename(&original_path, &new_path);
+                    let Some(runtime) = &self.tokio_runtime else {
+                        return;
+                    };
                     // Refresh the parent directory and select the renamed item
                     if let Some(explorer) = &mut self.file_explorer {
                         if let Some(selected_id) = explorer.get_selected() {
@@ -0,0 +1,254 @@
+//! Code folding: keeping each buffer's foldable ranges current, and the fold commands
+//!
+//! Ranges come from `textDocument/foldingRange` when the buffer's language server is
+//! running and supports it. Otherwise they are computed right away with the
+//! language's tree-sitter `folds.scm` query, or from indentation. Edits schedule a
+//! debounced refresh.
+
+use std::time::{Duration, Instant};
+
+use lsp_types::{FoldingRange, FoldingRangeProviderCapability};
+
+use super::Editor;
+use crate::model::event::{BufferId, Event};
+use crate::primitives::folding::{from_lsp_folding_ranges, indent_fold_ranges, FoldRange};
+use crate::services::lsp::client::LspFeature;
+use crate::services::lsp::manager::detect_language;
+use crate::view::folding::skip_hidden;
+
+/// How long to wait after the last edit before recomputing foldable ranges
+pub(super) const FOLDING_DEBOUNCE: Duration = Duration::from_millis(300);
+
+impl Editor {
+    /// Recompute a buffer's foldable ranges once `delay` has passed
+    pub(super) fn schedule_folding_refresh(&mut self, buffer_id: BufferId, delay: Duration) {
+        let is_virtual = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .is_some_and(|metadata| metadata.is_virtual() || metadata.binary);
+        if !is_virtual {
+            self.folding_refresh
+                .insert(buffer_id, Instant::now() + delay);
+        }
+    }
+
+    /// Recompute foldable ranges now for every open buffer of a language
+    pub(super) fn schedule_folding_for_language(&mut self, language: &str) {
+        for buffer_id in self.buffer_ids_for_language(language) {
+            self.schedule_folding_refresh(buffer_id, Duration::ZERO);
+        }
+    }
+
+    /// Send folding requests and recompute local ranges that are due
+    pub(super) fn check_folding_refresh(&mut self) {
+        let now = Instant::now();
+        let due: Vec<BufferId> = self
+            .folding_refresh
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(buffer_id, _)| *buffer_id)
+            .collect();
+
+        for buffer_id in due {
+            // Wait for the in-flight request; its response may already be outdated
+            if self
+                .pending_folding_requests
+                .values()
+                .any(|pending| *pending == buffer_id)
+            {
+                continue;
+            }
+            self.folding_refresh.remove(&buffer_id);
+            if !self.request_folding_ranges(buffer_id) {
+                self.compute_local_fold_ranges(buffer_id);
+            }
+        }
+    }
+
+    /// Send a folding range request, if the buffer's server is ready and supports it
+    fn request_folding_ranges(&mut self, buffer_id: BufferId) -> bool {
+        let Some(metadata) = self.buffer_metadata.get(&buffer_id) else {
+            return false;
+        };
+        if !metadata.lsp_enabled {
+            return false;
+        }
+        let (Some(uri), Some(path)) = (metadata.file_uri(), metadata.file_path()) else {
+            return false;
+        };
+        let uri = uri.clone();
+        let Some(language) = detect_language(path, &self.config.languages) else {
+            return false;
+        };
+
+        let Some(lsp) = self.lsp.as_mut() else {
+            return false;
+        };
+        // Local ranges are used until the server is running
+        if !lsp.is_server_ready(&language) {
+            return false;
+        }
+        let Some(handle) = lsp.handle_for_feature(&language, LspFeature::FoldingRange) else {
+            return false;
+        };
+        let supported = handle
+            .capabilities()
+            .and_then(|caps| caps.folding_range_provider)
+            .is_some_and(|provider| {
+                !matches!(provider, FoldingRangeProviderCapability::Simple(false))
+            });
+        if !supported {
+            return false;
+        }
+
+        let request_id = self.next_lsp_request_id;
+        if let Err(e) = handle.folding_ranges(request_id, uri) {
+            tracing::warn!("Failed to send folding range request: {}", e);
+            return false;
+        }
+        self.next_lsp_request_id += 1;
+        self.pending_folding_requests.insert(request_id, buffer_id);
+        true
+    }
+
+    /// Compute foldable ranges with tree-sitter, or from indentation
+    fn compute_local_fold_ranges(&mut self, buffer_id: BufferId) {
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return;
+        };
+        // Large files aren't loaded in full
+        let Some(text) = state.buffer.to_string() else {
+            return;
+        };
+        let ranges = state
+            .highlighter
+            .language()
+            .and_then(|language| self.fold_range_extractor.ranges(&text, language))
+            .unwrap_or_else(|| indent_fold_ranges(&text, self.config.editor.tab_size));
+        self.set_fold_ranges(buffer_id, ranges);
+    }
+
+    /// Store a folding range response, falling back to local ranges on failure
+    pub(super) fn handle_folding_ranges_response(
+        &mut self,
+        request_id: u64,
+        result: Result<Vec<FoldingRange>, String>,
+    ) {
+        let Some(buffer_id) = self.pending_folding_requests.remove(&request_id) else {
+            tracing::debug!("Ignoring stale folding range response: {}", request_id);
+            return;
+        };
+        match result {
+            Ok(ranges) => self.set_fold_ranges(buffer_id, from_lsp_folding_ranges(ranges)),
+            Err(e) => {
+                tracing::debug!("Folding range request failed: {}", e);
+                self.compute_local_fold_ranges(buffer_id);
+            }
+        }
+    }
+
+    fn set_fold_ranges(&mut self, buffer_id: BufferId, ranges: Vec<FoldRange>) {
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.margins.show_fold_indicators = self.config.editor.show_fold_indicators;
+            state.folds.set_ranges(ranges);
+        }
+    }
+
+    // =========================================================================
+    // Fold commands (active buffer, primary cursor)
+    // =========================================================================
+
+    fn primary_cursor_line(&self) -> usize {
+        let state = self.active_state();
+        state
+            .buffer
+            .get_line_number(state.cursors.primary().position)
+    }
+
+    /// Fold the innermost region containing the cursor
+    pub fn fold_at_cursor(&mut self) {
+        let line = self.primary_cursor_line();
+        let state = self.active_state_mut();
+        if state.folds.fold_at_line(&state.buffer, line) {
+            self.after_fold_change();
+        } else {
+            self.set_status_message("No foldable region at cursor".to_string());
+        }
+    }
+
+    /// Unfold the regions containing the cursor
+    pub fn unfold_at_cursor(&mut self) {
+        let line = self.primary_cursor_line();
+        let state = self.active_state_mut();
+        if state.folds.unfold_at_line(&state.buffer, line) {
+            self.after_fold_change();
+        }
+    }
+
+    /// Unfold the region headed by the cursor line, or fold the innermost one containing it
+    pub fn toggle_fold_at_cursor(&mut self) {
+        let line = self.primary_cursor_line();
+        let state = self.active_state_mut();
+        if state.folds.toggle_at_line(&state.buffer, line) {
+            self.after_fold_change();
+        } else {
+            self.set_status_message("No foldable region at cursor".to_string());
+        }
+    }
+
+    /// Fold every region in the buffer
+    pub fn fold_all(&mut self) {
+        let state = self.active_state_mut();
+        state.folds.fold_all(&state.buffer);
+        self.after_fold_change();
+    }
+
+    /// Unfold every region in the buffer
+    pub fn unfold_all(&mut self) {
+        self.active_state_mut().folds.unfold_all();
+        self.after_fold_change();
+    }
+
+    /// Fold the regions nested at `level` (1 = top level), except around the cursor
+    pub fn fold_level(&mut self, level: usize) {
+        if level == 0 {
+            self.set_status_message("Fold level must be at least 1".to_string());
+            return;
+        }
+        let line = self.primary_cursor_line();
+        let state = self.active_state_mut();
+        state.folds.fold_level(&state.buffer, level, line);
+        self.after_fold_change();
+    }
+
+    /// Move cursors out of newly folded regions and redraw the buffer
+    fn after_fold_change(&mut self) {
+        let buffer_id = self.active_buffer();
+        let state = self.active_state();
+        let hidden = state.folds.hidden_ranges(&state.buffer);
+        let moves: Vec<Event> = state
+            .cursors
+            .iter()
+            .filter(|(_, cursor)| hidden.iter().any(|r| r.contains(&cursor.position)))
+            .map(|(cursor_id, cursor)| Event::MoveCursor {
+                cursor_id,
+                old_position: cursor.position,
+                // Moving backward out of a fold lands on its header line
+                new_position: skip_hidden(
+                    &state.buffer,
+                    &hidden,
+                    cursor.position + 1,
+                    cursor.position,
+                ),
+                old_anchor: cursor.anchor,
+                new_anchor: None,
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            })
+            .collect();
+        for event in moves {
+            self.apply_event_to_active_buffer(&event);
+        }
+        self.invalidate_layouts_for_buffer(buffer_id);
+    }
+}
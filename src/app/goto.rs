@@ -0,0 +1,333 @@
+//! Go to definition, declaration, type definition and implementation
+//!
+//! A single location is jumped to directly; when the server returns several (typically
+//! the implementations of a trait) they are offered in a picker instead. Peeking shows
+//! the lines around the location in a popup without leaving the current buffer; Enter
+//! in the peek popup jumps there.
+
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use lsp_types::Location;
+use ratatui::style::{Modifier, Style};
+
+use super::{uri_to_path, Editor};
+use crate::model::event::Event;
+use crate::services::lsp::async_handler::GotoTarget;
+use crate::services::lsp::manager::detect_language;
+use crate::view::popup::{Popup, PopupListItem, PopupPosition, StyledLine};
+
+/// Lines shown above the target line when peeking
+const PEEK_LINES_BEFORE: usize = 2;
+
+/// Lines shown when peeking, including the target line
+const PEEK_LINES: usize = 12;
+
+/// A goto request waiting for its response
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PendingGoto {
+    pub(super) request_id: u64,
+    target: GotoTarget,
+    peek: bool,
+}
+
+/// Locations offered by the goto picker or peek popup
+#[derive(Debug, Clone)]
+pub(super) struct GotoPopup {
+    /// Title of the popup, which identifies it
+    title: String,
+    target: GotoTarget,
+    locations: Vec<Location>,
+    /// Whether choosing a location peeks at it rather than jumping there
+    peek: bool,
+}
+
+impl Editor {
+    /// Ask the language server where the symbol at the cursor is defined, declared, etc.
+    ///
+    /// With `peek`, the location is shown in a popup instead of jumped to.
+    pub(super) fn request_goto(&mut self, target: GotoTarget, peek: bool) -> io::Result<()> {
+        let state = self.active_state();
+        let (line, character) = state
+            .buffer
+            .position_to_lsp_position(state.cursors.primary().position);
+
+        let Some(metadata) = self.buffer_metadata.get(&self.active_buffer()) else {
+            return Ok(());
+        };
+        let (Some(uri), Some(path)) = (metadata.file_uri().cloned(), metadata.file_path()) else {
+            return Ok(());
+        };
+        let Some(language) = detect_language(path, &self.config.languages) else {
+            return Ok(());
+        };
+        let Some(lsp) = self.lsp.as_mut() else {
+            return Ok(());
+        };
+        let Some(handle) = lsp.handle_for_feature(&language, target.feature()) else {
+            self.set_status_message(format!(
+                "Go to {} is not supported by the language server",
+                target.name()
+            ));
+            return Ok(());
+        };
+
+        let request_id = self.next_lsp_request_id;
+        self.next_lsp_request_id += 1;
+        self.pending_goto_request = Some(PendingGoto {
+            request_id,
+            target,
+            peek,
+        });
+        let _ = handle.goto(
+            request_id,
+            target,
+            uri.clone(),
+            line as u32,
+            character as u32,
+        );
+        tracing::info!(
+            "Requested go-to-{} at {}:{}:{}",
+            target.name(),
+            uri.as_str(),
+            line,
+            character
+        );
+        Ok(())
+    }
+
+    /// Handle the locations returned for a goto request
+    pub(super) fn handle_goto_response(
+        &mut self,
+        request_id: u64,
+        mut locations: Vec<Location>,
+    ) -> io::Result<()> {
+        let Some(pending) = self
+            .pending_goto_request
+            .filter(|pending| pending.request_id == request_id)
+        else {
+            tracing::debug!("Ignoring goto response for outdated request {}", request_id);
+            return Ok(());
+        };
+        self.pending_goto_request = None;
+
+        // Servers may report the same location more than once
+        let mut seen = Vec::new();
+        locations.retain(|location| {
+            let key = (location.uri.clone(), location.range.start);
+            let new = !seen.contains(&key);
+            seen.push(key);
+            new
+        });
+
+        match locations.len() {
+            0 => self.set_status_message(format!("No {} found", pending.target.name())),
+            1 if pending.peek => self.peek_location(pending.target, locations.remove(0)),
+            1 => self.jump_to_location(pending.target, &locations[0])?,
+            _ => self.show_goto_picker(pending.target, pending.peek, locations),
+        }
+        Ok(())
+    }
+
+    fn show_goto_picker(&mut self, target: GotoTarget, peek: bool, locations: Vec<Location>) {
+        let items = locations
+            .iter()
+            .enumerate()
+            .map(|(index, location)| {
+                let line = location.range.start.line as usize;
+                let text = uri_to_path(&location.uri)
+                    .map(|path| format!("{}:{}", self.display_path(&path), line + 1))
+                    .unwrap_or_else(|_| location.uri.as_str().to_string());
+                let source = self.location_lines(location, line..line + 1);
+                let item = PopupListItem::new(text).with_data(index.to_string());
+                match source.first() {
+                    Some(source) => item.with_detail(source.trim().to_string()),
+                    None => item,
+                }
+            })
+            .collect();
+
+        let title = format!("{} ({})", target.title(), locations.len());
+        let popup = Popup::list(items, &self.theme)
+            .with_title(title.clone())
+            .with_position(PopupPosition::BelowCursor)
+            .with_width(80)
+            .with_max_height(12);
+        self.active_state_mut().popups.show(popup);
+        self.goto_popup = Some(GotoPopup {
+            title,
+            target,
+            locations,
+            peek,
+        });
+    }
+
+    /// Show the lines around a location in a popup
+    fn peek_location(&mut self, target: GotoTarget, location: Location) {
+        let target_line = location.range.start.line as usize;
+        let first_line = target_line.saturating_sub(PEEK_LINES_BEFORE);
+        let source = self.location_lines(&location, first_line..first_line + PEEK_LINES);
+        if source.is_empty() {
+            self.set_status_message(format!("Could not read {} location", target.name()));
+            return;
+        }
+
+        let gutter_style = Style::default().fg(self.theme.line_number_fg);
+        let text_style = Style::default().fg(self.theme.popup_text_fg);
+        let lines = source
+            .into_iter()
+            .enumerate()
+            .map(|(offset, text)| {
+                let line = first_line + offset;
+                let mut styled = StyledLine::new();
+                styled.push(format!("{:>4} │ ", line + 1), gutter_style);
+                let style = if line == target_line {
+                    text_style.add_modifier(Modifier::BOLD)
+                } else {
+                    text_style
+                };
+                styled.push(text, style);
+                styled
+            })
+            .collect();
+
+        let title = match uri_to_path(&location.uri) {
+            Ok(path) => format!("{}:{}", self.display_path(&path), target_line + 1),
+            Err(_) => location.uri.as_str().to_string(),
+        };
+        let popup = Popup::styled(lines, &self.theme)
+            .with_title(title.clone())
+            .with_position(PopupPosition::BelowCursor)
+            .with_width(80)
+            .with_max_height(PEEK_LINES as u16 + 2);
+        self.active_state_mut().popups.show(popup);
+        self.goto_popup = Some(GotoPopup {
+            title,
+            target,
+            locations: vec![location],
+            peek: false,
+        });
+    }
+
+    /// Index of the location chosen in the goto popup, if it is the top popup
+    pub(super) fn goto_popup_selection(&self) -> Option<usize> {
+        let goto = self.goto_popup.as_ref()?;
+        let popup = self.active_state().popups.top()?;
+        if popup.title.as_deref() != Some(goto.title.as_str()) {
+            return None;
+        }
+        match popup.selected_item() {
+            Some(item) => item.data.as_ref()?.parse().ok(),
+            // The peek popup holds a single location
+            None => Some(0),
+        }
+    }
+
+    /// Go to (or peek at) the location chosen in the goto popup
+    pub(super) fn confirm_goto_popup(&mut self, index: usize) -> io::Result<()> {
+        let Some(mut goto) = self.goto_popup.take() else {
+            return Ok(());
+        };
+        if index >= goto.locations.len() {
+            return Ok(());
+        }
+        let location = goto.locations.swap_remove(index);
+        if goto.peek {
+            self.peek_location(goto.target, location);
+            Ok(())
+        } else {
+            self.jump_to_location(goto.target, &location)
+        }
+    }
+
+    fn jump_to_location(&mut self, target: GotoTarget, location: &Location) -> io::Result<()> {
+        let Some(path) = self.open_location(location)? else {
+            self.set_status_message(format!("Could not open {} location", target.name()));
+            return Ok(());
+        };
+
+        self.set_status_message(format!(
+            "Jumped to {} at {}:{}",
+            target.name(),
+            path.display(),
+            location.range.start.line + 1
+        ));
+        Ok(())
+    }
+
+    /// Open the file of a location with the cursor at its start
+    ///
+    /// Returns the file's path, or `None` if the location isn't a file.
+    pub(super) fn open_location(&mut self, location: &Location) -> io::Result<Option<PathBuf>> {
+        let Ok(path) = uri_to_path(&location.uri) else {
+            return Ok(None);
+        };
+        let buffer_id = self.open_file(&path)?;
+
+        let line = location.range.start.line as usize;
+        let character = location.range.start.character as usize;
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            let position = state.buffer.line_col_to_position(line, character);
+            let cursor = *state.cursors.primary();
+            state.apply(&Event::MoveCursor {
+                cursor_id: state.cursors.primary_id(),
+                old_position: cursor.position,
+                new_position: position,
+                old_anchor: cursor.anchor,
+                new_anchor: None,
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            });
+        }
+        Ok(Some(path))
+    }
+
+    /// Text of some lines of a location's file, from its buffer if it is open
+    ///
+    /// Lines past the end of the file are left out.
+    fn location_lines(&mut self, location: &Location, lines: Range<usize>) -> Vec<String> {
+        let text: Vec<String> = match self.find_buffer_by_uri(location.uri.as_str()) {
+            Some(buffer_id) => {
+                let Some(state) = self.buffers.get_mut(&buffer_id) else {
+                    return Vec::new();
+                };
+                lines
+                    .clone()
+                    .map_while(|line| {
+                        let start = state.buffer.line_start_offset(line)?;
+                        let end = state
+                            .buffer
+                            .line_start_offset(line + 1)
+                            .unwrap_or_else(|| state.buffer.len());
+                        (start < end || line == 0).then(|| state.get_text_range(start, end))
+                    })
+                    .collect()
+            }
+            None => uri_to_path(&location.uri)
+                .ok()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .map(|content| {
+                    content
+                        .lines()
+                        .skip(lines.start)
+                        .take(lines.len())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        let tab = " ".repeat(self.config.editor.tab_size);
+        text.into_iter()
+            .map(|line| line.trim_end_matches(['\r', '\n']).replace('\t', &tab))
+            .collect()
+    }
+
+    /// A path relative to the working directory, if it is inside it
+    pub(super) fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.working_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+}
@@ -0,0 +1,323 @@
+//! Call and type hierarchy explorer
+//!
+//! "Show Incoming Calls" (and the outgoing call, supertype and subtype variants)
+//! prepares the hierarchy item at the cursor and opens it as a tree in a read-only
+//! virtual buffer. The first item is expanded right away; Tab, Right and Left expand
+//! and collapse nodes, asking the server for each level as it is opened. Enter jumps
+//! to the call site (or to the declaration, for roots and types).
+
+use lsp_types::{CallHierarchyServerCapability, Position, Uri};
+
+use super::{uri_to_path, Editor};
+use crate::model::event::BufferId;
+use crate::services::lsp::async_handler::{HierarchyDirection, HierarchyEntry};
+use crate::services::lsp::manager::detect_language;
+use crate::view::file_tree::{NodeId, NodeState};
+use crate::view::hierarchy::{HierarchyTree, HIERARCHY_NODE_PROPERTY};
+
+/// Buffer mode of hierarchy buffers (see `ModeRegistry::new`)
+const HIERARCHY_MODE: &str = "hierarchy";
+
+/// What a pending hierarchy request was sent for
+#[derive(Debug, Clone)]
+pub(super) enum PendingHierarchyRequest {
+    /// Items at the cursor, to open a new tree
+    Prepare {
+        direction: HierarchyDirection,
+        language: String,
+    },
+    /// Children of a node in an open tree
+    Expand { buffer_id: BufferId, node: NodeId },
+}
+
+impl Editor {
+    /// Open the call or type hierarchy of the symbol at the cursor
+    pub fn show_hierarchy(&mut self, direction: HierarchyDirection) {
+        let kind = if direction.is_call() { "call" } else { "type" };
+        let state = self.active_state();
+        let (line, character) = state
+            .buffer
+            .position_to_lsp_position(state.cursors.primary().position);
+
+        let metadata = self.buffer_metadata.get(&self.active_buffer());
+        let (Some(uri), Some(path)) = (
+            metadata.and_then(|meta| meta.file_uri()).cloned(),
+            metadata.and_then(|meta| meta.file_path()),
+        ) else {
+            self.set_status_message(format!("No {} hierarchy for this buffer", kind));
+            return;
+        };
+        let Some(language) = detect_language(path, &self.config.languages) else {
+            self.set_status_message(format!("No {} hierarchy for this buffer", kind));
+            return;
+        };
+
+        let request_id = self.next_lsp_request_id;
+        let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.handle_for_feature(&language, direction.feature()))
+        else {
+            self.set_status_message(format!("No language server for {}", language));
+            return;
+        };
+        // lsp-types has no type hierarchy server capability, so only calls are checked
+        let unsupported = direction.is_call()
+            && handle.capabilities().is_some_and(|caps| {
+                matches!(
+                    caps.call_hierarchy_provider,
+                    None | Some(CallHierarchyServerCapability::Simple(false))
+                )
+            });
+        if unsupported {
+            self.set_status_message(format!(
+                "Language server for {} doesn't support call hierarchy",
+                language
+            ));
+            return;
+        }
+
+        if let Err(e) =
+            handle.prepare_hierarchy(request_id, uri, line as u32, character as u32, direction)
+        {
+            tracing::warn!("Failed to send prepare hierarchy request: {}", e);
+            return;
+        }
+        self.next_lsp_request_id += 1;
+        self.pending_hierarchy_requests.insert(
+            request_id,
+            PendingHierarchyRequest::Prepare {
+                direction,
+                language,
+            },
+        );
+        self.set_status_message(format!("Finding {}...", direction.title().to_lowercase()));
+    }
+
+    /// Open a tree for prepared items, or store the children of an expanded node
+    pub(super) fn handle_hierarchy_response(
+        &mut self,
+        request_id: u64,
+        result: Result<Vec<HierarchyEntry>, String>,
+    ) {
+        let Some(pending) = self.pending_hierarchy_requests.remove(&request_id) else {
+            tracing::debug!("Ignoring stale hierarchy response: {}", request_id);
+            return;
+        };
+
+        match pending {
+            PendingHierarchyRequest::Prepare {
+                direction,
+                language,
+            } => {
+                let entries = match result {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        self.set_status_message(format!("{} failed: {}", direction.title(), e));
+                        return;
+                    }
+                };
+                if entries.is_empty() {
+                    let kind = if direction.is_call() { "call" } else { "type" };
+                    self.set_status_message(format!("No {} hierarchy at cursor", kind));
+                    return;
+                }
+                let items = entries.into_iter().map(|entry| entry.item).collect();
+                let tree = HierarchyTree::new(direction, language, items);
+                self.open_hierarchy_buffer(tree);
+            }
+            PendingHierarchyRequest::Expand { buffer_id, node } => {
+                let Some(tree) = self.hierarchy_views.get_mut(&buffer_id) else {
+                    return;
+                };
+                match result {
+                    Ok(entries) => tree.set_children(node, entries),
+                    Err(e) => tree.set_error(node, e),
+                }
+                self.render_hierarchy(buffer_id);
+            }
+        }
+    }
+
+    /// Show a new tree, reusing the buffer of an earlier tree in the same direction
+    fn open_hierarchy_buffer(&mut self, tree: HierarchyTree) {
+        let name = format!("*{}*", tree.direction().title());
+        let first_root = tree.roots().first().copied();
+
+        let existing = self
+            .buffer_metadata
+            .iter()
+            .find(|(id, meta)| meta.display_name == name && self.hierarchy_views.contains_key(id))
+            .map(|(id, _)| *id);
+        let buffer_id = match existing {
+            Some(buffer_id) => buffer_id,
+            None => {
+                let buffer_id = self.create_virtual_buffer(name, HIERARCHY_MODE.to_string(), true);
+                if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                    state.editing_disabled = true;
+                    state.margins.set_line_numbers(false);
+                }
+                buffer_id
+            }
+        };
+
+        self.hierarchy_views.insert(buffer_id, tree);
+        self.set_active_buffer(buffer_id);
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.cursors.primary_mut().position = 0;
+        }
+        self.render_hierarchy(buffer_id);
+
+        if let Some(root) = first_root {
+            self.expand_hierarchy_node(buffer_id, root);
+            self.move_cursor_to_hierarchy_node(buffer_id, root);
+        }
+    }
+
+    /// Rewrite a hierarchy buffer from its tree
+    fn render_hierarchy(&mut self, buffer_id: BufferId) {
+        let Some(tree) = self.hierarchy_views.get(&buffer_id) else {
+            return;
+        };
+        let working_dir = self.working_dir.clone();
+        let entries = tree.render(|uri: &Uri, position: Position| {
+            let path = match uri_to_path(uri) {
+                Ok(path) => path
+                    .strip_prefix(&working_dir)
+                    .map(|relative| relative.to_path_buf())
+                    .unwrap_or(path),
+                Err(_) => return uri.as_str().to_string(),
+            };
+            format!("{}:{}", path.display(), position.line + 1)
+        });
+        if let Err(e) = self.set_virtual_buffer_content(buffer_id, entries) {
+            tracing::warn!("Failed to update hierarchy buffer: {}", e);
+        }
+    }
+
+    /// Request the children of a node
+    fn expand_hierarchy_node(&mut self, buffer_id: BufferId, node: NodeId) {
+        let Some(tree) = self.hierarchy_views.get_mut(&buffer_id) else {
+            return;
+        };
+        if !tree.start_loading(node) {
+            return;
+        }
+        let direction = tree.direction();
+        let language = tree.language().to_string();
+        let Some(item) = tree.get_node(node).map(|node| node.item.clone()) else {
+            return;
+        };
+
+        let request_id = self.next_lsp_request_id;
+        let sent = match self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.handle_for_feature(&language, direction.feature()))
+        {
+            Some(handle) => handle.expand_hierarchy(request_id, item, direction),
+            None => Err(format!("No language server for {}", language)),
+        };
+        match sent {
+            Ok(()) => {
+                self.next_lsp_request_id += 1;
+                self.pending_hierarchy_requests.insert(
+                    request_id,
+                    PendingHierarchyRequest::Expand { buffer_id, node },
+                );
+            }
+            Err(e) => {
+                if let Some(tree) = self.hierarchy_views.get_mut(&buffer_id) {
+                    tree.set_error(node, e);
+                }
+            }
+        }
+        self.render_hierarchy(buffer_id);
+    }
+
+    /// Node on the cursor line of the active hierarchy buffer
+    fn hierarchy_node_at_cursor(&self) -> Option<(BufferId, NodeId)> {
+        let buffer_id = self.active_buffer();
+        if !self.hierarchy_views.contains_key(&buffer_id) {
+            return None;
+        }
+        let node = self
+            .get_text_properties_at_cursor()?
+            .iter()
+            .find_map(|property| property.get_as::<usize>(HIERARCHY_NODE_PROPERTY))?;
+        Some((buffer_id, NodeId(node)))
+    }
+
+    fn move_cursor_to_hierarchy_node(&mut self, buffer_id: BufferId, node: NodeId) {
+        let Some(state) = self.buffers.get_mut(&buffer_id) else {
+            return;
+        };
+        let start = state
+            .text_properties
+            .all()
+            .iter()
+            .find(|property| property.get_as::<usize>(HIERARCHY_NODE_PROPERTY) == Some(node.0))
+            .map(|property| property.start);
+        if let Some(start) = start {
+            state.cursors.primary_mut().position = start;
+            state.cursors.primary_mut().anchor = None;
+        }
+    }
+
+    /// Run a command bound in the hierarchy buffer mode ("hierarchy-<command>")
+    pub(super) fn handle_hierarchy_command(&mut self, command: &str) {
+        let Some((buffer_id, node_id)) = self.hierarchy_node_at_cursor() else {
+            return;
+        };
+        let Some(node) = self
+            .hierarchy_views
+            .get(&buffer_id)
+            .and_then(|tree| tree.get_node(node_id))
+        else {
+            return;
+        };
+        let state = node.state.clone();
+        let parent = node.parent;
+        let target = node.target.clone();
+
+        match command {
+            "goto" => self.goto_hierarchy_target(target),
+            "toggle" if state == NodeState::Expanded => {
+                self.collapse_hierarchy_node(buffer_id, node_id)
+            }
+            "toggle" | "expand" => self.expand_hierarchy_node(buffer_id, node_id),
+            "collapse" if state == NodeState::Expanded => {
+                self.collapse_hierarchy_node(buffer_id, node_id)
+            }
+            "collapse" => {
+                if let Some(parent) = parent {
+                    self.move_cursor_to_hierarchy_node(buffer_id, parent);
+                }
+            }
+            _ => tracing::warn!("Unknown hierarchy command: {}", command),
+        }
+    }
+
+    fn collapse_hierarchy_node(&mut self, buffer_id: BufferId, node: NodeId) {
+        if let Some(tree) = self.hierarchy_views.get_mut(&buffer_id) {
+            tree.collapse(node);
+        }
+        self.render_hierarchy(buffer_id);
+        self.move_cursor_to_hierarchy_node(buffer_id, node);
+    }
+
+    fn goto_hierarchy_target(&mut self, (uri, position): (Uri, Position)) {
+        let path = match uri_to_path(&uri) {
+            Ok(path) => path,
+            Err(e) => {
+                self.set_status_message(format!("Cannot open {}: {}", uri.as_str(), e));
+                return;
+            }
+        };
+        let line = position.line as usize + 1;
+        let column = position.character as usize + 1;
+        if let Err(e) = self.handle_open_file_at_location(path.clone(), Some(line), Some(column)) {
+            self.set_status_message(format!("Failed to open {}: {}", path.display(), e));
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use super::normalize_path;
 use super::*;
 use crate::services::plugins::hooks::HookArgs;
+use crate::services::shell_command::ShellOutputMode;
 impl Editor {
     /// Determine the current keybinding context based on UI state
     pub(super) fn get_key_context(&self) -> crate::input::keybindings::KeyContext {
@@ -177,6 +178,23 @@ impl Editor {
             }
         }
 
+        // Typing while the completion popup is open edits the buffer and refilters it
+        if matches!(context, crate::input::keybindings::KeyContext::Popup)
+            && self.handle_completion_popup_key(code, modifiers)?
+        {
+            return Ok(());
+        }
+
+        // Vim modal editing in file buffers (virtual buffers keep their own modes)
+        if matches!(context, crate::input::keybindings::KeyContext::Normal)
+            && self.keybindings.vim_emulation()
+            && self.chord_state.is_empty()
+            && self.active_buffer_mode().is_none()
+            && self.handle_vim_key(code, modifiers)?
+        {
+            return Ok(());
+        }
+
         // Only check buffer mode keybindings if we're not in a higher-priority context
         // (Menu, Prompt, Popup should take precedence over mode bindings)
         let should_check_mode_bindings = matches!(
@@ -199,6 +217,15 @@ impl Editor {
                     // Handle built-in mode commands
                     let buffer_id = self.active_buffer();
                     return self.close_buffer(buffer_id);
+                } else if let Some(command) = command_name.strip_prefix("hierarchy-") {
+                    self.handle_hierarchy_command(command);
+                    return Ok(());
+                } else if let Some(command) = command_name.strip_prefix("debug-") {
+                    self.handle_debug_panel_command(command);
+                    return Ok(());
+                } else if let Some(command) = command_name.strip_prefix("undo-tree-") {
+                    self.handle_undo_tree_command(command);
+                    return Ok(());
                 } else if command_name == "revert-buffer" {
                     // Refresh the buffer (for virtual buffers, this would re-query data)
                     self.set_status_message("Refreshing buffer...".to_string());
@@ -250,6 +277,12 @@ impl Editor {
         match action {
             Action::LspCompletion
             | Action::LspGotoDefinition
+            | Action::LspGotoDeclaration
+            | Action::LspGotoTypeDefinition
+            | Action::LspGotoImplementation
+            | Action::LspPeekDefinition
+            | Action::LspPeekTypeDefinition
+            | Action::LspPeekImplementation
             | Action::LspReferences
             | Action::LspHover
             | Action::None => {
@@ -659,15 +692,19 @@ impl Editor {
             // Popup mode actions
             Action::PopupSelectNext => {
                 self.popup_select_next();
+                self.update_completion_documentation();
             }
             Action::PopupSelectPrev => {
                 self.popup_select_prev();
+                self.update_completion_documentation();
             }
             Action::PopupPageUp => {
                 self.popup_page_up();
+                self.update_completion_documentation();
             }
             Action::PopupPageDown => {
                 self.popup_page_down();
+                self.update_completion_documentation();
             }
             Action::PopupConfirm => {
                 return self.handle_action(action);
@@ -700,6 +737,25 @@ impl Editor {
         // Record action to macro if recording
         self.record_macro_action(&action);
 
+        // Only the action right after a kill or yank continues it
+        let kill_chain = std::mem::take(&mut self.kill_chain);
+        // Kills in a row only update the kill ring; the system clipboard gets
+        // their combined text once another action ends them
+        if !matches!(
+            action,
+            Action::KillLine
+                | Action::KillWordForward
+                | Action::KillWordBackward
+                | Action::KillRegion
+        ) {
+            self.clipboard.sync_system();
+        }
+
+        // Tab and Shift+Tab move between the tabstops of an inserted snippet
+        if self.handle_snippet_action(&action)? {
+            return Ok(());
+        }
+
         match action {
             Action::Quit => self.quit(),
             Action::Save => {
@@ -717,7 +773,7 @@ impl Editor {
                         PromptType::ConfirmSaveConflict,
                     );
                 } else {
-                    self.save()?;
+                    self.format_and_save()?;
                 }
             }
             Action::SaveAs => {
@@ -803,6 +859,48 @@ impl Editor {
                 }
                 self.paste()
             }
+            Action::PasteFromHistory => self.start_paste_from_history_prompt(),
+            Action::KillLine => self.kill(Action::DeleteToLineEnd, kill_chain),
+            Action::KillWordForward => self.kill(Action::DeleteWordForward, kill_chain),
+            Action::KillWordBackward => self.kill(Action::DeleteWordBackward, kill_chain),
+            Action::KillRegion => self.kill_region(kill_chain),
+            Action::Yank => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                    return Ok(());
+                }
+                self.yank()
+            }
+            Action::YankPop => self.yank_pop(kill_chain),
+            Action::CopyToRegister(name) => self.copy_to_register(name),
+            Action::InsertRegister(name) => self.insert_register(name),
+            Action::PromptCopyToRegister => {
+                self.start_prompt(
+                    "Copy to register (a-z, A-Z appends): ".to_string(),
+                    PromptType::CopyToRegister,
+                );
+            }
+            Action::PromptInsertRegister => {
+                self.start_prompt(
+                    "Insert register (a-z): ".to_string(),
+                    PromptType::InsertRegister,
+                );
+            }
+            Action::RectangleKill => self.copy_rectangle(true),
+            Action::RectangleCopy => self.copy_rectangle(false),
+            Action::RectangleYank => self.yank_rectangle(),
+            Action::RectangleOpen => self.open_rectangle(),
+            Action::RectangleReplace => self.start_rectangle_text_prompt(true),
+            Action::RectangleInsert => self.start_rectangle_text_prompt(false),
+            Action::RectangleNumberLines => self.start_rectangle_number_prompt(),
+            Action::RectangleToCursors => self.rectangle_to_cursors(),
+            Action::ShellCommandReplace => {
+                self.start_shell_command_prompt(ShellOutputMode::Replace)
+            }
+            Action::ShellCommandInsert => self.start_shell_command_prompt(ShellOutputMode::Insert),
+            Action::ShellCommandToBuffer => {
+                self.start_shell_command_prompt(ShellOutputMode::Buffer)
+            }
             Action::Undo => {
                 if self.is_editing_disabled() {
                     self.set_status_message("Editing disabled in this buffer".to_string());
@@ -841,6 +939,21 @@ impl Editor {
                 // Update modified status based on event log position
                 self.update_modified_from_event_log();
             }
+            Action::ShowUndoTree => {
+                self.show_undo_tree();
+            }
+            Action::UndoToEarlierTime => {
+                self.start_prompt(
+                    "Undo to earlier (e.g. 30s, 5m, 2h): ".to_string(),
+                    PromptType::UndoToTime { later: false },
+                );
+            }
+            Action::RedoToLaterTime => {
+                self.start_prompt(
+                    "Redo to later (e.g. 30s, 5m, 2h): ".to_string(),
+                    PromptType::UndoToTime { later: true },
+                );
+            }
             Action::ShowHelp => {
                 self.open_help_manual();
             }
@@ -991,7 +1104,25 @@ impl Editor {
                 self.request_completion()?;
             }
             Action::LspGotoDefinition => {
-                self.request_goto_definition()?;
+                self.request_goto(GotoTarget::Definition, false)?;
+            }
+            Action::LspGotoDeclaration => {
+                self.request_goto(GotoTarget::Declaration, false)?;
+            }
+            Action::LspGotoTypeDefinition => {
+                self.request_goto(GotoTarget::TypeDefinition, false)?;
+            }
+            Action::LspGotoImplementation => {
+                self.request_goto(GotoTarget::Implementation, false)?;
+            }
+            Action::LspPeekDefinition => {
+                self.request_goto(GotoTarget::Definition, true)?;
+            }
+            Action::LspPeekTypeDefinition => {
+                self.request_goto(GotoTarget::TypeDefinition, true)?;
+            }
+            Action::LspPeekImplementation => {
+                self.request_goto(GotoTarget::Implementation, true)?;
             }
             Action::LspRename => {
                 self.start_rename()?;
@@ -1008,6 +1139,30 @@ impl Editor {
             Action::LspCodeActions => {
                 self.request_code_actions()?;
             }
+            Action::LspCodeLens => {
+                self.run_code_lens_at_cursor();
+            }
+            Action::LspFormatDocument => {
+                self.request_formatting(false)?;
+            }
+            Action::LspFormatSelection => {
+                self.request_formatting(true)?;
+            }
+            Action::LspWorkspaceSymbol => {
+                self.start_workspace_symbol_prompt();
+            }
+            Action::LspIncomingCalls => {
+                self.show_hierarchy(HierarchyDirection::IncomingCalls);
+            }
+            Action::LspOutgoingCalls => {
+                self.show_hierarchy(HierarchyDirection::OutgoingCalls);
+            }
+            Action::LspSupertypes => {
+                self.show_hierarchy(HierarchyDirection::Supertypes);
+            }
+            Action::LspSubtypes => {
+                self.show_hierarchy(HierarchyDirection::Subtypes);
+            }
             Action::LspRestart => {
                 // Get the language for the current buffer
                 if let Some(metadata) = self.buffer_metadata.get(&self.active_buffer()) {
@@ -1064,11 +1219,16 @@ impl Editor {
                                                     )
                                                 {
                                                     if let Some(lsp) = self.lsp.as_mut() {
-                                                        if let Some(handle) =
-                                                            lsp.get_or_spawn(&lang_id)
+                                                        lsp.add_workspace_folder(
+                                                            &lang_id, &buf_path,
+                                                        );
+                                                        for handle in lsp.language_handles(&lang_id)
                                                         {
-                                                            let _ = handle
-                                                                .did_open(uri, content, lang_id);
+                                                            let _ = handle.did_open(
+                                                                uri.clone(),
+                                                                content.clone(),
+                                                                lang_id.clone(),
+                                                            );
                                                         }
                                                     }
                                                 }
@@ -1145,6 +1305,15 @@ impl Editor {
                     }
                 }
             }
+            Action::LspShowLog => {
+                self.start_show_lsp_log_prompt();
+            }
+            Action::LspInfo => {
+                self.show_lsp_info();
+            }
+            Action::LspToggleTrace => {
+                self.toggle_lsp_trace();
+            }
             Action::ToggleInlayHints => {
                 self.toggle_inlay_hints();
             }
@@ -1245,6 +1414,20 @@ impl Editor {
             Action::ToggleLineNumbers => self.toggle_line_numbers(),
             Action::ToggleMouseCapture => self.toggle_mouse_capture(),
             Action::ToggleMouseHover => self.toggle_mouse_hover(),
+            Action::DebugStart => self.start_debugging(),
+            Action::DebugStop => self.stop_debugging(),
+            Action::DebugContinue => self.debug_continue(),
+            Action::DebugPause => self.debug_pause(),
+            Action::DebugStepOver => self.debug_step("next"),
+            Action::DebugStepInto => self.debug_step("stepIn"),
+            Action::DebugStepOut => self.debug_step("stepOut"),
+            Action::DebugToggleBreakpoint => self.toggle_breakpoint(),
+            Action::DebugShowVariables => self.show_debug_panel(DebugPanel::Variables),
+            Action::DebugShowCallStack => self.show_debug_panel(DebugPanel::CallStack),
+            Action::DebugShowWatches => self.show_debug_panel(DebugPanel::Watches),
+            Action::DebugAddWatch => {
+                self.start_prompt("Watch expression: ".to_string(), PromptType::AddWatch)
+            }
             Action::FocusFileExplorer => self.focus_file_explorer(),
             Action::FocusEditor => self.focus_editor(),
             Action::FileExplorerUp => self.file_explorer_navigate_up(),
@@ -1414,7 +1597,8 @@ impl Editor {
 
             Action::SwitchKeybindingMap(map_name) => {
                 // Check if the map exists (either built-in or user-defined)
-                let is_builtin = matches!(map_name.as_str(), "default" | "emacs" | "vscode");
+                let is_builtin =
+                    matches!(map_name.as_str(), "default" | "emacs" | "vscode" | "vim");
                 let is_user_defined = self.config.keybinding_maps.contains_key(&map_name);
 
                 if is_builtin || is_user_defined {
@@ -1443,9 +1627,57 @@ impl Editor {
             Action::ToggleComment => {
                 self.toggle_comment();
             }
+            Action::Fold => {
+                self.fold_at_cursor();
+            }
+            Action::Unfold => {
+                self.unfold_at_cursor();
+            }
+            Action::ToggleFold => {
+                self.toggle_fold_at_cursor();
+            }
+            Action::FoldAll => {
+                self.fold_all();
+            }
+            Action::UnfoldAll => {
+                self.unfold_all();
+            }
+            Action::FoldLevel(level) => {
+                self.fold_level(level);
+            }
+            Action::PromptFoldLevel => {
+                self.start_prompt("Fold to level: ".to_string(), PromptType::FoldLevel);
+            }
             Action::GoToMatchingBracket => {
                 self.goto_matching_bracket();
             }
+            Action::ExpandSelection => {
+                self.expand_selection();
+            }
+            Action::ShrinkSelection => {
+                self.shrink_selection();
+            }
+            Action::SelectTextObject(kind) => {
+                self.select_text_object(kind);
+            }
+            Action::DeleteTextObject(kind) => {
+                self.delete_text_object(kind, false);
+            }
+            Action::ChangeTextObject(kind) => {
+                self.delete_text_object(kind, true);
+            }
+            Action::NextFunction => {
+                self.goto_function(true);
+            }
+            Action::PreviousFunction => {
+                self.goto_function(false);
+            }
+            Action::SwapArgumentNext => {
+                self.swap_argument(true);
+            }
+            Action::SwapArgumentPrevious => {
+                self.swap_argument(false);
+            }
             Action::JumpToNextError => {
                 self.jump_to_next_error();
             }
@@ -1888,6 +2120,20 @@ impl Editor {
                                 self.set_status_message(format!("Invalid line number: {}", input));
                             }
                         },
+                        PromptType::FoldLevel => match input.trim().parse::<usize>() {
+                            Ok(level) => self.fold_level(level),
+                            Err(_) => {
+                                self.set_status_message(format!("Invalid fold level: {}", input));
+                            }
+                        },
+                        PromptType::UndoToTime { later } => {
+                            match crate::model::event::parse_time_offset(&input) {
+                                Some(millis) => self.undo_by_time(millis, later),
+                                None => {
+                                    self.set_status_message(format!("Invalid time: {}", input));
+                                }
+                            }
+                        }
                         PromptType::SetBackgroundFile => {
                             if let Err(e) = self.load_ansi_background(&input) {
                                 self.set_status_message(format!(
@@ -1993,6 +2239,20 @@ impl Editor {
                                 self.set_status_message("No register specified".to_string());
                             }
                         }
+                        PromptType::CopyToRegister => match input.trim().chars().next() {
+                            Some(name) => self.copy_to_register(name),
+                            None => self.set_status_message("No register specified".to_string()),
+                        },
+                        PromptType::InsertRegister => match input.trim().chars().next() {
+                            Some(name) => self.insert_register(name),
+                            None => self.set_status_message("No register specified".to_string()),
+                        },
+                        PromptType::PasteFromHistory => self.paste_from_history(&input),
+                        PromptType::RectangleText { replace } => {
+                            self.rectangle_insert_text(&input, replace)
+                        }
+                        PromptType::RectangleNumberLines => self.rectangle_number_lines(&input),
+                        PromptType::ShellCommand { mode } => self.run_shell_command(&input, mode),
                         PromptType::JumpToBookmark => {
                             if let Some(c) = input.trim().chars().next() {
                                 if c.is_ascii_digit() {
@@ -2030,7 +2290,7 @@ impl Editor {
                             let input_lower = input.trim().to_lowercase();
                             if input_lower == "o" || input_lower == "overwrite" {
                                 // Force save despite conflict
-                                if let Err(e) = self.save() {
+                                if let Err(e) = self.format_and_save() {
                                     self.set_status_message(format!("Failed to save: {}", e));
                                 }
                             } else {
@@ -2140,7 +2400,9 @@ impl Editor {
                                         // Update config to disable auto-start for this language
                                         if let Some(lsp_config) = self.config.lsp.get_mut(language)
                                         {
-                                            lsp_config.auto_start = false;
+                                            for server in lsp_config.servers_mut() {
+                                                server.auto_start = false;
+                                            }
                                             if let Err(e) = self.save_config() {
                                                 tracing::warn!(
                                                     "Failed to save config after disabling LSP auto-start: {}",
@@ -2176,12 +2438,26 @@ impl Editor {
                         PromptType::SelectKeybindingMap => {
                             self.apply_keybinding_map(input.trim());
                         }
+                        PromptType::ShowLspLog => {
+                            // input is "language/server"
+                            self.show_lsp_log(&input);
+                        }
+                        PromptType::AddWatch => {
+                            self.add_debug_watch(input.trim());
+                        }
+                        PromptType::VimCommand => {
+                            return self.run_vim_ex_command(&input);
+                        }
                         PromptType::SwitchToTab => {
                             // input is the buffer id as a string
                             if let Ok(id) = input.trim().parse::<usize>() {
                                 self.switch_to_tab(BufferId(id));
                             }
                         }
+                        PromptType::WorkspaceSymbol => {
+                            // input is the index of the chosen search result
+                            self.confirm_workspace_symbol(&input);
+                        }
                         PromptType::QueryReplaceConfirm => {
                             // This is handled by InsertChar, not PromptConfirm
                             // But if somehow Enter is pressed, treat it as skip (n)
@@ -2220,75 +2496,82 @@ impl Editor {
                     return Ok(());
                 }
 
-                // If it's a completion popup, insert the selected item
-                let completion_text = if let Some(popup) = self.active_state().popups.top() {
-                    if let Some(title) = &popup.title {
-                        if title == "Completion" {
-                            if let Some(item) = popup.selected_item() {
-                                item.data.clone()
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
+                // If it's the code actions popup, apply the selected action
+                let code_action = self.active_state().popups.top().and_then(|popup| {
+                    if popup.title.as_deref() == Some(super::code_actions::CODE_ACTIONS_POPUP_TITLE)
+                    {
+                        popup.selected_item().and_then(|item| item.data.clone())
                     } else {
                         None
                     }
-                } else {
-                    None
-                };
-
-                // Now perform the completion if we have text
-                if let Some(text) = completion_text {
-                    use crate::primitives::word_navigation::find_completion_word_start;
-
-                    let (cursor_id, cursor_pos, word_start) = {
-                        let state = self.active_state();
-                        let cursor_id = state.cursors.primary_id();
-                        let cursor_pos = state.cursors.primary().position;
-                        let word_start = find_completion_word_start(&state.buffer, cursor_pos);
-                        (cursor_id, cursor_pos, word_start)
-                    };
+                });
+                if let Some(data) = code_action {
+                    self.hide_popup();
+                    self.confirm_code_action(&data);
+                    return Ok(());
+                }
 
-                    let deleted_text = if word_start < cursor_pos {
-                        self.active_state_mut()
-                            .get_text_range(word_start, cursor_pos)
+                // If it's the code lens popup, run the selected lens
+                let code_lens = self.active_state().popups.top().and_then(|popup| {
+                    if popup.title.as_deref() == Some(super::code_lens::CODE_LENS_POPUP_TITLE) {
+                        popup.selected_item().and_then(|item| item.data.clone())
                     } else {
-                        String::new()
-                    };
-
-                    if word_start < cursor_pos {
-                        let delete_event = crate::model::event::Event::Delete {
-                            range: word_start..cursor_pos,
-                            deleted_text,
-                            cursor_id,
-                        };
-
-                        self.active_event_log_mut().append(delete_event.clone());
-                        self.apply_event_to_active_buffer(&delete_event);
+                        None
+                    }
+                });
+                if let Some(data) = code_lens {
+                    self.hide_popup();
+                    self.confirm_code_lens(&data);
+                    return Ok(());
+                }
 
-                        let buffer_len = self.active_state().buffer.len();
-                        let insert_pos = word_start.min(buffer_len);
+                // If it's a completion popup, accept the selected item
+                let completion = self.active_state().popups.top().and_then(|popup| {
+                    if popup.title.as_deref() == Some(super::completion::COMPLETION_POPUP_TITLE) {
+                        popup.selected_item().and_then(|item| item.data.clone())
+                    } else {
+                        None
+                    }
+                });
+                if let Some(data) = completion {
+                    self.hide_popup();
+                    self.confirm_completion(&data)?;
+                    return Ok(());
+                }
 
-                        let insert_event = crate::model::event::Event::Insert {
-                            position: insert_pos,
-                            text,
-                            cursor_id,
-                        };
+                // If it's the goto picker or a peek popup, go to the chosen location
+                if let Some(index) = self.goto_popup_selection() {
+                    self.hide_popup();
+                    self.confirm_goto_popup(index)?;
+                    return Ok(());
+                }
 
-                        self.active_event_log_mut().append(insert_event.clone());
-                        self.apply_event_to_active_buffer(&insert_event);
+                // If it's the diagnostics popup, go to the chosen related location
+                let related = self.active_state().popups.top().and_then(|popup| {
+                    if popup.title.as_deref() == Some(super::diagnostics::DIAGNOSTICS_POPUP_TITLE) {
+                        popup.selected_item().and_then(|item| item.data.clone())
                     } else {
-                        let insert_event = crate::model::event::Event::Insert {
-                            position: cursor_pos,
-                            text,
-                            cursor_id,
-                        };
+                        None
+                    }
+                });
+                if let Some(data) = related {
+                    self.hide_popup();
+                    self.confirm_diagnostic_location(&data)?;
+                    return Ok(());
+                }
 
-                        self.active_event_log_mut().append(insert_event.clone());
-                        self.apply_event_to_active_buffer(&insert_event);
+                // If it's a snippet choice popup, fill in the chosen value
+                let choice = self.active_state().popups.top().and_then(|popup| {
+                    if popup.title.as_deref() == Some(super::snippets::SNIPPET_CHOICE_POPUP_TITLE) {
+                        popup.selected_item().and_then(|item| item.data.clone())
+                    } else {
+                        None
                     }
+                });
+                if let Some(data) = choice {
+                    self.hide_popup();
+                    self.confirm_snippet_choice(&data);
+                    return Ok(());
                 }
 
                 self.hide_popup();
@@ -2366,6 +2649,8 @@ impl Editor {
                     if c == '(' || c == ',' {
                         let _ = self.request_signature_help();
                     }
+
+                    self.maybe_request_on_type_formatting(c);
                 }
             }
             _ => {
@@ -2385,6 +2670,19 @@ impl Editor {
                         | Action::IndentSelection
                         | Action::DedentSelection
                         | Action::ToggleComment
+                        | Action::MoveLineUp
+                        | Action::MoveLineDown
+                        | Action::DuplicateLine
+                        | Action::JoinLines
+                        | Action::SortLines
+                        | Action::SortLinesNatural
+                        | Action::SortLinesNumeric
+                        | Action::SortLinesCaseInsensitive
+                        | Action::SortLinesDescending
+                        | Action::UniqueLines
+                        | Action::ReverseLines
+                        | Action::ShuffleLines
+                        | Action::DeleteBlankLines
                 );
 
                 if is_editing_action && self.is_editing_disabled() {
@@ -4009,6 +4307,11 @@ impl Editor {
         // Focus this split (handles terminal mode exit, tab state, etc.)
         self.focus_split(split_id, buffer_id);
 
+        // Clicking a code lens title runs it instead of moving the cursor
+        if self.handle_code_lens_click(col, row, split_id, buffer_id, content_rect) {
+            return Ok(());
+        }
+
         // Get cached view line mappings for this split (before mutable borrow of buffers)
         let cached_mappings = self
             .cached_layout
@@ -4089,6 +4392,16 @@ impl Editor {
                     .record_movement(buffer_id, target_position, None);
             }
 
+            // A click in the indicator column toggles a breakpoint on the line
+            let is_file = self
+                .buffer_metadata
+                .get(&buffer_id)
+                .is_some_and(|metadata| metadata.file_path().is_some());
+            if is_file && col == content_rect.x && state.margins.left_config.enabled {
+                self.toggle_breakpoint();
+                return Ok(());
+            }
+
             // Set up drag selection state for potential text selection
             self.mouse_state.dragging_text_selection = true;
             self.mouse_state.drag_selection_split = Some(split_id);
@@ -4434,7 +4747,7 @@ impl Editor {
     /// Start the keybinding map selection prompt with available maps
     fn start_select_keybinding_map_prompt(&mut self) {
         // Built-in keybinding maps
-        let builtin_maps = vec!["default", "emacs", "vscode"];
+        let builtin_maps = vec!["default", "emacs", "vscode", "vim"];
 
         // Collect user-defined keybinding maps from config
         let user_maps: Vec<&str> = self
@@ -4502,7 +4815,7 @@ impl Editor {
         }
 
         // Check if the map exists (either built-in or user-defined)
-        let is_builtin = matches!(map_name, "default" | "emacs" | "vscode");
+        let is_builtin = matches!(map_name, "default" | "emacs" | "vscode" | "vim");
         let is_user_defined = self.config.keybinding_maps.contains_key(map_name);
 
         if is_builtin || is_user_defined {
@@ -0,0 +1,321 @@
+//! Kill ring, yank-pop, named registers and clipboard history
+//!
+//! Every copy and cut goes to the front of the clipboard's kill ring. The kill
+//! commands (`kill_line`, `kill_word_forward`...) delete text into the ring;
+//! kills in a row add to the same entry, so `C-k C-k` yanks back as one piece.
+//! Right after a yank, yank-pop replaces the yanked text with the next older
+//! entry. "Paste from History" picks an entry from a list. Named registers hold
+//! text apart from the ring.
+
+use std::ops::Range;
+
+use super::Editor;
+use crate::input::commands::Suggestion;
+use crate::input::keybindings::Action;
+use crate::model::event::{BufferId, Event};
+use crate::view::prompt::{Prompt, PromptType};
+
+/// Longest entry text shown in the clipboard history
+const MAX_HISTORY_PREVIEW_CHARS: usize = 60;
+
+/// What the previous action left for the next one to continue
+#[derive(Debug, Clone, Default)]
+pub(super) enum KillChain {
+    #[default]
+    None,
+    /// Text was killed; another kill adds to the same entry
+    Kill,
+    /// Text was yanked; yank-pop replaces it with an older entry
+    Yank {
+        buffer_id: BufferId,
+        range: Range<usize>,
+        ring_index: usize,
+    },
+}
+
+impl Editor {
+    /// Kill the text the delete action `delete` removes
+    ///
+    /// `chain` is what the previous action left: a kill right after a kill adds
+    /// to its entry (before it, for backward kills).
+    pub(super) fn kill(&mut self, delete: Action, chain: KillChain) {
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        let backward = matches!(delete, Action::DeleteWordBackward);
+        let Some(events) = self.action_to_events(delete) else {
+            return;
+        };
+        let mut killed: Vec<(usize, String)> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Delete {
+                    range,
+                    deleted_text,
+                    ..
+                } => Some((range.start, deleted_text.clone())),
+                _ => None,
+            })
+            .collect();
+        if killed.is_empty() {
+            return;
+        }
+        killed.sort_by_key(|(start, _)| *start);
+        let pieces: Vec<String> = killed.into_iter().map(|(_, text)| text).collect();
+        if matches!(chain, KillChain::Kill) && pieces.len() == 1 {
+            self.clipboard.append_kill(&pieces[0], backward);
+        } else {
+            self.clipboard.copy_pieces(pieces);
+        }
+
+        let event = if events.len() == 1 {
+            events.into_iter().next().unwrap()
+        } else {
+            Event::Batch {
+                events,
+                description: "Kill".to_string(),
+            }
+        };
+        self.active_event_log_mut().append(event.clone());
+        self.apply_event_to_active_buffer(&event);
+        self.kill_chain = KillChain::Kill;
+    }
+
+    /// Kill the selection of every cursor
+    pub(super) fn kill_region(&mut self, chain: KillChain) {
+        let all_selected = self
+            .active_state()
+            .cursors
+            .iter()
+            .all(|(_, cursor)| cursor.selection_range().is_some_and(|r| !r.is_empty()));
+        if !all_selected {
+            self.set_status_message("No selection".to_string());
+            return;
+        }
+        // Deleting backward removes the selection when there is one
+        self.kill(Action::DeleteBackward, chain);
+    }
+
+    /// Insert text at the primary cursor as one undo step, returning where it went
+    ///
+    /// Like typing, the text replaces the selection when there is one.
+    fn insert_at_primary_cursor(&mut self, text: String, description: &str) -> Range<usize> {
+        let state = self.active_state_mut();
+        let cursor_id = state.cursors.primary_id();
+        let cursor = state.cursors.primary();
+        let selection = cursor.selection_range().filter(|range| !range.is_empty());
+        let cursor_position = cursor.position;
+        let mut events = Vec::new();
+        let position = match selection {
+            Some(selection) => {
+                let deleted_text = state.get_text_range(selection.start, selection.end);
+                let start = selection.start;
+                events.push(Event::Delete {
+                    range: selection,
+                    deleted_text,
+                    cursor_id,
+                });
+                start
+            }
+            None => cursor_position,
+        };
+        let range = position..position + text.len();
+        events.push(Event::Insert {
+            position,
+            text,
+            cursor_id,
+        });
+        events.push(Event::ClearAnchor { cursor_id });
+        let batch = Event::Batch {
+            events,
+            description: description.to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+        range
+    }
+
+    /// Paste the most recent kill, remembering it for yank-pop
+    pub(super) fn yank(&mut self) {
+        if self.active_state().cursors.count() > 1 {
+            self.paste();
+            return;
+        }
+        let Some(text) = self.clipboard.paste() else {
+            self.set_status_message("Kill ring is empty".to_string());
+            return;
+        };
+        let range = self.insert_at_primary_cursor(text, "Yank");
+        self.kill_chain = KillChain::Yank {
+            buffer_id: self.active_buffer(),
+            range,
+            ring_index: 0,
+        };
+    }
+
+    /// Replace the text just yanked with the next older kill ring entry
+    pub(super) fn yank_pop(&mut self, chain: KillChain) {
+        let KillChain::Yank {
+            buffer_id,
+            range,
+            ring_index,
+        } = chain
+        else {
+            self.set_status_message("Previous command was not a yank".to_string());
+            return;
+        };
+        let len = self.clipboard.ring_len();
+        if buffer_id != self.active_buffer() || range.end > self.active_state().buffer.len() {
+            self.set_status_message("Previous command was not a yank".to_string());
+            return;
+        }
+        if len < 2 {
+            self.set_status_message("No older entry in the kill ring".to_string());
+            self.kill_chain = KillChain::Yank {
+                buffer_id,
+                range,
+                ring_index,
+            };
+            return;
+        }
+
+        let ring_index = (ring_index + 1) % len;
+        let text = self
+            .clipboard
+            .ring_entry(ring_index)
+            .unwrap_or_default()
+            .to_string();
+        let cursor_id = self.active_state().cursors.primary_id();
+        let deleted_text = self
+            .active_state_mut()
+            .get_text_range(range.start, range.end);
+        let start = range.start;
+        let new_range = start..start + text.len();
+        let batch = Event::Batch {
+            events: vec![
+                Event::Delete {
+                    range,
+                    deleted_text,
+                    cursor_id,
+                },
+                Event::Insert {
+                    position: start,
+                    text,
+                    cursor_id,
+                },
+            ],
+            description: "Yank pop".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+
+        self.kill_chain = KillChain::Yank {
+            buffer_id,
+            range: new_range,
+            ring_index,
+        };
+        self.set_status_message(format!("Kill ring entry {} of {}", ring_index + 1, len));
+    }
+
+    /// Open a list of recent clipboard entries to paste from
+    pub(super) fn start_paste_from_history_prompt(&mut self) {
+        if self.clipboard.ring_len() == 0 {
+            self.set_status_message("Clipboard history is empty".to_string());
+            return;
+        }
+        let suggestions: Vec<Suggestion> = (0..self.clipboard.ring_len())
+            .filter_map(|index| {
+                let text = self.clipboard.ring_entry(index)?;
+                let lines = text.lines().count();
+                Some(Suggestion {
+                    text: history_preview(text),
+                    description: (lines > 1).then(|| format!("{} lines", lines)),
+                    value: Some(index.to_string()),
+                    disabled: false,
+                    keybinding: None,
+                    source: None,
+                })
+            })
+            .collect();
+
+        self.prompt = Some(Prompt::with_suggestions(
+            "Paste from history: ".to_string(),
+            PromptType::PasteFromHistory,
+            suggestions,
+        ));
+    }
+
+    /// Paste the clipboard history entry chosen in the prompt (its index)
+    pub(super) fn paste_from_history(&mut self, input: &str) {
+        let Some(text) = input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| self.clipboard.ring_entry(index))
+            .map(str::to_string)
+        else {
+            self.set_status_message("No such clipboard entry".to_string());
+            return;
+        };
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        // The chosen entry becomes the most recent one
+        self.clipboard.copy(text);
+        self.paste();
+    }
+
+    /// Store the selected text in a named register
+    pub(super) fn copy_to_register(&mut self, name: char) {
+        let mut ranges: Vec<Range<usize>> = self
+            .active_state()
+            .cursors
+            .iter()
+            .filter_map(|(_, cursor)| cursor.selection_range())
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        let state = self.active_state_mut();
+        let text = ranges
+            .into_iter()
+            .map(|range| state.get_text_range(range.start, range.end))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.is_empty() {
+            self.set_status_message("No selection".to_string());
+            return;
+        }
+        if self.clipboard.set_register(name, &text) {
+            self.set_status_message(format!("Copied to register {}", name));
+        } else {
+            self.set_status_message("Register must be a letter".to_string());
+        }
+    }
+
+    /// Insert the text of a named register at the cursor
+    pub(super) fn insert_register(&mut self, name: char) {
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        let Some(text) = self.clipboard.register(name).map(str::to_string) else {
+            self.set_status_message(format!("Register {} is empty", name));
+            return;
+        };
+        self.insert_at_primary_cursor(text, "Insert register");
+    }
+}
+
+/// One-line preview of a clipboard entry
+fn history_preview(text: &str) -> String {
+    let single_line = text.trim().replace('\n', " ⏎ ");
+    let mut preview: String = single_line
+        .chars()
+        .take(MAX_HISTORY_PREVIEW_CHARS)
+        .collect();
+    if preview.len() < single_line.len() {
+        preview.push('…');
+    }
+    preview
+}
@@ -0,0 +1,310 @@
+//! Document lifecycle and file notifications for language servers
+//!
+//! Closing a buffer sends `didClose`. Saving sends `willSave`, and when a server asks
+//! for `willSaveWaitUntil` the save waits for its edits like format-on-save does.
+//!
+//! Servers that register file watchers get `didChangeWatchedFiles` for changes anywhere
+//! in the working directory. The auto-revert watcher only covers the directories of
+//! open files, so the working directory is watched recursively while any server has
+//! watchers registered. Changes are batched until the async messages of a tick have
+//! all been processed.
+//!
+//! Renames in the file explorer are announced with `willRenameFiles`, whose edits are
+//! applied before the rename, and `didRenameFiles`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use lsp_types::{FileChangeType, FileRename, TextDocumentSaveReason, Uri};
+
+use super::types::{FormatTrigger, PendingFormatRequest};
+use super::Editor;
+use crate::model::event::BufferId;
+use crate::services::async_bridge::AsyncMessage;
+use crate::services::lsp::file_watch::file_operation_matches;
+use crate::services::lsp::manager::detect_language;
+
+/// How long a rename in the file explorer waits for servers' `willRenameFiles` edits
+const WILL_RENAME_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl Editor {
+    /// URI and language of a buffer whose file is synchronized with language servers
+    fn lsp_document(&self, buffer_id: BufferId) -> Option<(Uri, String)> {
+        let metadata = self.buffer_metadata.get(&buffer_id)?;
+        if !metadata.lsp_enabled {
+            return None;
+        }
+        let uri = metadata.file_uri()?.clone();
+        let language = detect_language(metadata.file_path()?, &self.config.languages)?;
+        Some((uri, language))
+    }
+
+    /// Tell language servers a buffer's file is no longer open
+    ///
+    /// Servers that never opened the file ignore it.
+    pub(super) fn notify_lsp_file_closed(&mut self, buffer_id: BufferId) {
+        let Some((uri, _)) = self.lsp_document(buffer_id) else {
+            return;
+        };
+        let Some(lsp) = &self.lsp else {
+            return;
+        };
+        for handle in lsp.all_handles() {
+            if let Err(e) = handle.did_close(uri.clone()) {
+                tracing::warn!("Failed to send didClose: {}", e);
+            }
+        }
+    }
+
+    /// Save a buffer once its language servers have had their say
+    ///
+    /// Servers that asked for `willSave` are told the buffer is about to be saved. If a
+    /// server asked for `willSaveWaitUntil`, its edits are requested and the save happens
+    /// when they arrive (or after `FORMAT_ON_SAVE_TIMEOUT`); otherwise it happens now.
+    pub(super) fn save_after_will_save(&mut self, buffer_id: BufferId) -> io::Result<()> {
+        let Some((uri, language)) = self.lsp_document(buffer_id) else {
+            return self.save_formatted_buffer(buffer_id);
+        };
+
+        let reason = TextDocumentSaveReason::MANUAL;
+        let request_id = self.next_lsp_request_id;
+        let mut sent = false;
+        if let Some(lsp) = self.lsp.as_mut() {
+            // Saving must not wait on a server that is still starting up
+            let ready = lsp.is_server_ready(&language);
+            for handle in lsp.language_handles(&language) {
+                if handle.wants_will_save() {
+                    if let Err(e) = handle.will_save(uri.clone(), reason) {
+                        tracing::warn!("Failed to send willSave: {}", e);
+                    }
+                }
+                if !sent && ready && handle.wants_will_save_wait_until() {
+                    sent = handle
+                        .will_save_wait_until(request_id, uri.clone(), reason)
+                        .is_ok();
+                }
+            }
+        }
+        if !sent {
+            return self.save_formatted_buffer(buffer_id);
+        }
+
+        self.next_lsp_request_id += 1;
+        let log_position = self
+            .event_logs
+            .get(&buffer_id)
+            .and_then(|log| log.position());
+        self.pending_format_request = Some(PendingFormatRequest {
+            request_id,
+            buffer_id,
+            log_position,
+            trigger: FormatTrigger::WillSave,
+            started: Instant::now(),
+        });
+        tracing::info!("Requested willSaveWaitUntil edits for {}", uri.as_str());
+        Ok(())
+    }
+
+    /// Record file watchers a server registered, and start watching if needed
+    pub(super) fn handle_watched_files_registered(
+        &mut self,
+        language: String,
+        server: String,
+        id: String,
+        watchers: Vec<lsp_types::FileSystemWatcher>,
+    ) {
+        if let Some(lsp) = self.lsp.as_mut() {
+            lsp.register_watched_files(&language, &server, id, &watchers);
+        }
+        self.update_lsp_file_watcher();
+    }
+
+    /// Drop file watchers a server unregistered, and stop watching if none are left
+    pub(super) fn handle_watched_files_unregistered(
+        &mut self,
+        language: String,
+        server: String,
+        id: String,
+    ) {
+        if let Some(lsp) = self.lsp.as_mut() {
+            lsp.unregister_watched_files(&language, &server, &id);
+        }
+        self.update_lsp_file_watcher();
+    }
+
+    /// Watch the working directory recursively while servers have file watchers
+    fn update_lsp_file_watcher(&mut self) {
+        use notify::{RecursiveMode, Watcher};
+
+        let wanted = self.lsp.as_ref().is_some_and(|lsp| lsp.has_watched_files());
+        if !wanted {
+            if self.lsp_file_watcher.take().is_some() {
+                tracing::info!("Stopped watching files for language servers");
+            }
+            self.watched_file_changes.clear();
+            return;
+        }
+        if self.lsp_file_watcher.is_some() {
+            return;
+        }
+
+        let Some(sender) = self.async_bridge.as_ref().map(|bridge| bridge.sender()) else {
+            return;
+        };
+        let watcher_result = notify::recommended_watcher(
+            move |res: Result<notify::Event, notify::Error>| match res {
+                Ok(event) => {
+                    for (path, change) in watched_file_changes(event) {
+                        let _ = sender.send(AsyncMessage::WatchedFileChanged { path, change });
+                    }
+                }
+                Err(e) => tracing::error!("LSP file watcher error: {}", e),
+            },
+        );
+        match watcher_result {
+            Ok(mut watcher) => {
+                if let Err(e) = watcher.watch(&self.working_dir, RecursiveMode::Recursive) {
+                    tracing::warn!("Failed to watch {:?}: {}", self.working_dir, e);
+                    return;
+                }
+                self.lsp_file_watcher = Some(watcher);
+                tracing::info!("Watching {:?} for language servers", self.working_dir);
+            }
+            Err(e) => tracing::error!("Failed to create LSP file watcher: {}", e),
+        }
+    }
+
+    /// Send the file changes seen since the last call to the servers watching them
+    pub(super) fn flush_watched_file_changes(&mut self) {
+        if self.watched_file_changes.is_empty() {
+            return;
+        }
+        let mut changes = std::mem::take(&mut self.watched_file_changes);
+        // Writing a file usually reports several identical changes
+        let mut seen = Vec::new();
+        changes.retain(|change| {
+            let new = !seen.contains(change);
+            seen.push(change.clone());
+            new
+        });
+        if let Some(lsp) = &self.lsp {
+            lsp.notify_watched_files(&changes, &self.working_dir);
+        }
+    }
+
+    /// Apply the edits servers want made before `old_path` is renamed to `new_path`
+    ///
+    /// Servers get `WILL_RENAME_TIMEOUT` in total to answer; the rename goes ahead
+    /// without the edits of those that don't.
+    pub(super) fn lsp_will_rename(&mut self, old_path: &Path, new_path: &Path) {
+        let Some(files) = file_renames(old_path, new_path) else {
+            return;
+        };
+        let is_dir = old_path.is_dir();
+        let receivers: Vec<_> = self
+            .lsp
+            .iter()
+            .flat_map(|lsp| lsp.all_handles())
+            .filter(|handle| {
+                handle.is_initialized()
+                    && handle.file_operations().is_some_and(|operations| {
+                        file_operation_matches(operations.will_rename.as_ref(), old_path, is_dir)
+                    })
+            })
+            .filter_map(|handle| handle.will_rename_files(files.clone()).ok())
+            .collect();
+        if receivers.is_empty() {
+            return;
+        }
+        let Some(runtime) = &self.tokio_runtime else {
+            return;
+        };
+
+        let deadline = Instant::now() + WILL_RENAME_TIMEOUT;
+        let edits: Vec<_> = receivers
+            .into_iter()
+            .filter_map(|rx| {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match runtime.block_on(async { tokio::time::timeout(timeout, rx).await }) {
+                    Ok(Ok(Ok(edit))) => edit,
+                    Ok(Ok(Err(e))) => {
+                        tracing::warn!("willRenameFiles failed: {}", e);
+                        None
+                    }
+                    Ok(Err(_)) => None,
+                    Err(_) => {
+                        tracing::warn!("willRenameFiles timed out");
+                        None
+                    }
+                }
+            })
+            .collect();
+        for edit in edits {
+            if let Err(e) = self.apply_workspace_edit(edit) {
+                tracing::warn!("Failed to apply willRenameFiles edit: {}", e);
+            }
+        }
+    }
+
+    /// Tell servers interested in it that `old_path` was renamed to `new_path`
+    pub(super) fn lsp_did_rename(&mut self, old_path: &Path, new_path: &Path) {
+        let Some(files) = file_renames(old_path, new_path) else {
+            return;
+        };
+        let is_dir = new_path.is_dir();
+        let Some(lsp) = &self.lsp else {
+            return;
+        };
+        for handle in lsp.all_handles() {
+            let interested = handle.file_operations().is_some_and(|operations| {
+                file_operation_matches(operations.did_rename.as_ref(), old_path, is_dir)
+            });
+            if interested {
+                if let Err(e) = handle.did_rename_files(files.clone()) {
+                    tracing::warn!("Failed to send didRenameFiles: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// The `FileRename` of a rename, if both paths have file URIs
+fn file_renames(old_path: &Path, new_path: &Path) -> Option<Vec<FileRename>> {
+    let old_uri = url::Url::from_file_path(old_path).ok()?;
+    let new_uri = url::Url::from_file_path(new_path).ok()?;
+    Some(vec![FileRename {
+        old_uri: old_uri.to_string(),
+        new_uri: new_uri.to_string(),
+    }])
+}
+
+/// The LSP file changes a file system event stands for
+fn watched_file_changes(event: notify::Event) -> Vec<(PathBuf, FileChangeType)> {
+    use notify::event::{EventKind, ModifyKind};
+
+    let change = match event.kind {
+        EventKind::Create(_) => FileChangeType::CREATED,
+        EventKind::Remove(_) => FileChangeType::DELETED,
+        // A rename reports either end, or both: whichever path exists was created
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            return event
+                .paths
+                .into_iter()
+                .map(|path| {
+                    let change = if path.exists() {
+                        FileChangeType::CREATED
+                    } else {
+                        FileChangeType::DELETED
+                    };
+                    (path, change)
+                })
+                .collect();
+        }
+        // Access times and permissions don't change what servers see
+        EventKind::Modify(ModifyKind::Metadata(_)) => return Vec::new(),
+        EventKind::Modify(_) => FileChangeType::CHANGED,
+        _ => return Vec::new(),
+    };
+    event.paths.into_iter().map(|path| (path, change)).collect()
+}
@@ -0,0 +1,373 @@
+//! LSP server logs and the LSP Info buffer
+//!
+//! Log messages, shown messages and progress notifications are kept per server (the
+//! last `LSP_LOG_LIMIT` entries), so there is somewhere to look when a server
+//! misbehaves. "Show LSP Log" opens a server's log in a read-only buffer that is
+//! updated as entries arrive. With "Toggle LSP Trace" on, the JSON-RPC messages
+//! exchanged with servers are logged as well, and servers are asked for `$/logTrace`.
+//!
+//! "LSP Info" lists each running server's command, state, PID, root, restart count
+//! and capabilities.
+
+use super::types::{LspLogEntry, LspLogKind};
+use super::Editor;
+use crate::input::commands::Suggestion;
+use crate::services::async_bridge::LspMessageType;
+use crate::services::lsp::manager::LspServerInfo;
+use crate::view::prompt::{Prompt, PromptType};
+
+/// Entries kept in each server's log
+const LSP_LOG_LIMIT: usize = 1000;
+
+/// Name of the buffer the LSP Info command opens
+const LSP_INFO_BUFFER_NAME: &str = "*LSP Info*";
+
+impl Editor {
+    /// Append an entry to a server's log
+    pub(super) fn record_lsp_log(
+        &mut self,
+        language: String,
+        server: String,
+        kind: LspLogKind,
+        message: String,
+    ) {
+        let log = self.lsp_logs.entry((language, server)).or_default();
+        if log.entries.len() == LSP_LOG_LIMIT {
+            log.entries.pop_front();
+        }
+        log.entries.push_back(LspLogEntry {
+            kind,
+            message,
+            time: chrono::Local::now(),
+        });
+        log.dirty = true;
+    }
+
+    /// Handle a JSON-RPC message traced while tracing is on
+    pub(super) fn handle_lsp_trace(
+        &mut self,
+        language: String,
+        server: String,
+        outgoing: bool,
+        message: String,
+    ) {
+        self.record_lsp_log(language, server, LspLogKind::Trace { outgoing }, message);
+    }
+
+    /// Ask which server's log to show
+    ///
+    /// Servers that have stopped or crashed are listed too, as long as the editor
+    /// heard from them.
+    pub(super) fn start_show_lsp_log_prompt(&mut self) {
+        let mut servers: Vec<(String, String)> = self
+            .lsp_server_statuses
+            .keys()
+            .chain(self.lsp_logs.keys())
+            .cloned()
+            .collect();
+        servers.sort();
+        servers.dedup();
+        if servers.is_empty() {
+            self.set_status_message("No LSP server has been started".to_string());
+            return;
+        }
+
+        let suggestions: Vec<Suggestion> = servers
+            .iter()
+            .map(|(language, server)| {
+                let entries = self
+                    .lsp_logs
+                    .get(&(language.clone(), server.clone()))
+                    .map_or(0, |log| log.entries.len());
+                Suggestion {
+                    text: self.lsp_server_label(language, server),
+                    description: Some(format!("{} entries", entries)),
+                    value: Some(format!("{}/{}", language, server)),
+                    disabled: false,
+                    keybinding: None,
+                    source: None,
+                }
+            })
+            .collect();
+
+        // Start with the server of the current buffer selected
+        let current_language = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|metadata| metadata.file_path())
+            .and_then(|path| {
+                crate::services::lsp::manager::detect_language(path, &self.config.languages)
+            });
+        let selected = current_language
+            .and_then(|language| servers.iter().position(|(l, _)| *l == language))
+            .unwrap_or(0);
+
+        self.prompt = Some(Prompt::with_suggestions(
+            "Show LSP log: ".to_string(),
+            PromptType::ShowLspLog,
+            suggestions,
+        ));
+        if let Some(prompt) = self.prompt.as_mut() {
+            prompt.selected_suggestion = Some(selected);
+        }
+    }
+
+    /// Open the log of a server, given as "language/server"
+    pub(super) fn show_lsp_log(&mut self, server_key: &str) {
+        let Some((language, server)) = server_key.trim().split_once('/') else {
+            self.set_status_message(format!("No LSP server matches '{}'", server_key));
+            return;
+        };
+        let key = (language.to_string(), server.to_string());
+
+        let existing = self
+            .lsp_logs
+            .get(&key)
+            .and_then(|log| log.buffer)
+            .filter(|buffer_id| self.buffers.contains_key(buffer_id));
+        if let Some(buffer_id) = existing {
+            self.set_active_buffer(buffer_id);
+            return;
+        }
+
+        let name = format!("*LSP Log: {}*", self.lsp_server_label(language, server));
+        let buffer_id = self.create_virtual_buffer(name, "special".to_string(), true);
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.editing_disabled = true;
+            state.margins.set_line_numbers(false);
+        }
+        let log = self.lsp_logs.entry(key).or_default();
+        log.buffer = Some(buffer_id);
+        log.dirty = true;
+        self.update_lsp_log_buffers();
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            let end = state.buffer.len();
+            state.cursors.primary_mut().position = end;
+        }
+        self.set_active_buffer(buffer_id);
+    }
+
+    /// Show new log entries in the open log buffers
+    ///
+    /// A buffer whose cursor is at the end keeps following the log.
+    pub(super) fn update_lsp_log_buffers(&mut self) {
+        for log in self.lsp_logs.values_mut() {
+            let Some(buffer_id) = log.buffer else {
+                continue;
+            };
+            let Some(state) = self.buffers.get_mut(&buffer_id) else {
+                // The buffer was closed
+                log.buffer = None;
+                continue;
+            };
+            if !log.dirty {
+                continue;
+            }
+            log.dirty = false;
+
+            let text: String = log.entries.iter().map(format_log_entry).collect();
+            let old_len = state.buffer.len();
+            let position = state.cursors.primary().position;
+            if old_len > 0 {
+                state.buffer.delete_bytes(0, old_len);
+            }
+            state.buffer.insert(0, &text);
+            state.buffer.clear_modified();
+
+            let new_len = state.buffer.len();
+            let position = if position >= old_len {
+                new_len
+            } else {
+                state.buffer.snap_to_char_boundary(position.min(new_len))
+            };
+            state.cursors.primary_mut().position = position;
+            state.cursors.primary_mut().anchor = None;
+        }
+    }
+
+    /// Open a buffer describing each running server
+    pub(super) fn show_lsp_info(&mut self) {
+        let infos = self
+            .lsp
+            .as_ref()
+            .map(|lsp| lsp.server_infos())
+            .unwrap_or_default();
+
+        let mut content = String::from("LSP Servers\n===========\n\n");
+        if infos.is_empty() {
+            content.push_str("No LSP servers are running.\n\n");
+        }
+        for info in &infos {
+            content.push_str(&self.format_server_info(info));
+            content.push('\n');
+        }
+        content.push_str("Press 'q' to close this buffer.\n");
+
+        let existing = self
+            .buffer_metadata
+            .iter()
+            .find(|(_, metadata)| metadata.display_name == LSP_INFO_BUFFER_NAME)
+            .map(|(id, _)| *id);
+        let buffer_id = existing.unwrap_or_else(|| {
+            self.create_virtual_buffer(
+                LSP_INFO_BUFFER_NAME.to_string(),
+                "special".to_string(),
+                true,
+            )
+        });
+
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            let len = state.buffer.len();
+            if len > 0 {
+                state.buffer.delete_bytes(0, len);
+            }
+            state.buffer.insert(0, &content);
+            state.buffer.clear_modified();
+            state.editing_disabled = true;
+            state.margins.set_line_numbers(false);
+            state.cursors.primary_mut().position = 0;
+            state.cursors.primary_mut().anchor = None;
+        }
+        self.set_active_buffer(buffer_id);
+    }
+
+    fn format_server_info(&self, info: &LspServerInfo) -> String {
+        let command = std::iter::once(info.command.as_str())
+            .chain(info.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let capabilities = info
+            .capabilities
+            .as_ref()
+            .map(capability_names)
+            .map_or("(not initialized)".to_string(), |names| names.join(", "));
+
+        let mut text = format!("{}\n", self.lsp_server_label(&info.language, &info.server));
+        let mut field = |name: &str, value: String| {
+            text.push_str(&format!("  {:<14}{}\n", format!("{}:", name), value));
+        };
+        field("Command", command);
+        field("State", format!("{:?}", info.state));
+        field(
+            "PID",
+            info.pid
+                .map_or("(unknown)".to_string(), |pid| pid.to_string()),
+        );
+        field(
+            "Root",
+            info.root_uri
+                .as_ref()
+                .map_or("(none)".to_string(), |uri| uri.as_str().to_string()),
+        );
+        if !info.workspace_folders.is_empty() {
+            let folders: Vec<&str> = info
+                .workspace_folders
+                .iter()
+                .map(|uri| uri.as_str())
+                .collect();
+            field("Folders", folders.join(", "));
+        }
+        field("Restarts", info.restarts.to_string());
+        field("Trace", if info.tracing { "on" } else { "off" }.to_string());
+        field("Stderr log", info.stderr_log_path.display().to_string());
+        field("Capabilities", capabilities);
+        text
+    }
+
+    /// Turn JSON-RPC tracing on or off for all servers
+    pub(super) fn toggle_lsp_trace(&mut self) {
+        let Some(lsp) = self.lsp.as_mut() else {
+            self.set_status_message("No LSP manager available".to_string());
+            return;
+        };
+        let tracing = !lsp.is_tracing();
+        lsp.set_trace(tracing);
+        self.set_status_message(if tracing {
+            "LSP trace on: messages are recorded in server logs".to_string()
+        } else {
+            "LSP trace off".to_string()
+        });
+    }
+
+    /// Name a server for display: the language alone when it has a single server
+    fn lsp_server_label(&self, language: &str, server: &str) -> String {
+        match &self.lsp {
+            Some(lsp) => lsp.server_label(language, server),
+            None => language.to_string(),
+        }
+    }
+}
+
+/// A log entry as a line (or lines) of a log buffer
+fn format_log_entry(entry: &LspLogEntry) -> String {
+    let level = |message_type: LspMessageType| match message_type {
+        LspMessageType::Error => "error",
+        LspMessageType::Warning => "warning",
+        LspMessageType::Info => "info",
+        LspMessageType::Log => "log",
+    };
+    let tag = match entry.kind {
+        LspLogKind::Log(message_type) => level(message_type).to_string(),
+        LspLogKind::Message(message_type) => format!("shown {}", level(message_type)),
+        LspLogKind::Progress => "progress".to_string(),
+        LspLogKind::Trace { outgoing: true } => "sent".to_string(),
+        LspLogKind::Trace { outgoing: false } => "received".to_string(),
+    };
+    format!(
+        "{} [{}] {}\n",
+        entry.time.format("%H:%M:%S%.3f"),
+        tag,
+        entry.message.trim_end()
+    )
+}
+
+/// Names of the capabilities a server advertises, as in the initialize response
+fn capability_names(capabilities: &lsp_types::ServerCapabilities) -> Vec<String> {
+    let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(capabilities) else {
+        return Vec::new();
+    };
+    fields
+        .into_iter()
+        .filter(|(_, value)| {
+            !matches!(
+                value,
+                serde_json::Value::Null | serde_json::Value::Bool(false)
+            )
+        })
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{OneOf, ServerCapabilities};
+
+    #[test]
+    fn test_format_log_entry() {
+        let entry = LspLogEntry {
+            kind: LspLogKind::Message(LspMessageType::Warning),
+            message: "Failed to load workspace\n".to_string(),
+            time: chrono::Local::now(),
+        };
+        let line = format_log_entry(&entry);
+        assert!(line.ends_with(" [shown warning] Failed to load workspace\n"));
+
+        let entry = LspLogEntry {
+            kind: LspLogKind::Trace { outgoing: true },
+            message: "{}".to_string(),
+            time: chrono::Local::now(),
+        };
+        assert!(format_log_entry(&entry).ends_with(" [sent] {}\n"));
+    }
+
+    #[test]
+    fn test_capability_names() {
+        let capabilities = ServerCapabilities {
+            hover_provider: Some(lsp_types::HoverProviderCapability::Simple(true)),
+            rename_provider: Some(OneOf::Left(false)),
+            ..Default::default()
+        };
+        assert_eq!(capability_names(&capabilities), vec!["hoverProvider"]);
+    }
+}
@@ -1,15 +1,35 @@
 mod async_messages;
+mod code_actions;
+mod code_lens;
+mod completion;
+mod debugger;
+mod diagnostics;
 mod file_explorer;
 pub mod file_open;
 mod file_open_input;
+mod folding;
+mod goto;
 mod help;
+mod hierarchy;
 mod input;
+mod kill_ring;
+mod lsp_files;
+mod lsp_log;
 mod plugin_commands;
+mod rectangle;
 mod render;
 pub mod script_control;
+mod semantic_tokens;
 pub mod session;
+mod shell_command;
+mod snippets;
 mod terminal;
+mod textobjects;
 pub mod types;
+mod undo_history;
+mod undo_tree;
+mod vim;
+mod workspace_symbols;
 
 use std::path::Component;
 
@@ -45,9 +65,11 @@ pub(crate) fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
     }
 }
 
+use self::debugger::DebugPanel;
 use self::types::{
-    Bookmark, CachedLayout, EventLineInfo, InteractiveReplaceState, LspMessageEntry,
-    LspProgressInfo, MacroRecordingState, MouseState, SearchState, DEFAULT_BACKGROUND_FILE,
+    Bookmark, CachedLayout, EventLineInfo, FormatTrigger, InteractiveReplaceState, LspProgressInfo,
+    LspServerLog, MacroRecordingState, MouseState, PendingFormatRequest, SearchState,
+    DEFAULT_BACKGROUND_FILE,
 };
 use crate::config::{Config, DirectoryContext};
 use crate::input::actions::action_to_events as convert_action_to_events;
@@ -60,13 +82,19 @@ use crate::input::multi_cursor::{
 };
 use crate::input::position_history::PositionHistory;
 use crate::model::event::{CursorId, Event, EventLog, SplitDirection, SplitId};
+use crate::primitives::folding::FoldRangeExtractor;
+use crate::primitives::outline::{flatten_document_symbols, OutlineExtractor};
+use crate::primitives::textobjects::TextObjectExtractor;
 use crate::services::async_bridge::{AsyncBridge, AsyncMessage};
 use crate::services::fs::{FsBackend, FsManager, LocalFsBackend};
-use crate::services::lsp::client::LspServerConfig;
+use crate::services::lsp::async_handler::{FormattingRequest, GotoTarget, HierarchyDirection};
+use crate::services::lsp::client::{LspFeature, LspLanguageConfig};
+use crate::services::lsp::diagnostics::DiagnosticsBySource;
 use crate::services::lsp::manager::{detect_language, LspManager, LspSpawnResult};
 use crate::services::plugins::api::{BufferSavedDiff, PluginCommand};
 use crate::services::plugins::PluginManager;
 use crate::services::recovery::{RecoveryConfig, RecoveryService};
+use crate::services::undo_history::UndoHistoryStore;
 use crate::state::EditorState;
 use crate::view::file_tree::{FileTree, FileTreeView};
 use crate::view::prompt::{Prompt, PromptType};
@@ -74,6 +102,7 @@ use crate::view::split::{SplitManager, SplitViewState};
 use crate::view::ui::{
     FileExplorerRenderer, SplitRenderer, StatusBarRenderer, SuggestionsRenderer,
 };
+use crate::view::virtual_text::VirtualTextNamespace;
 use crossterm::event::{KeyCode, KeyModifiers};
 use lsp_types::{Position, Range as LspRange, TextDocumentContentChangeEvent};
 use ratatui::{
@@ -99,6 +128,34 @@ fn uri_to_path(uri: &lsp_types::Uri) -> Result<PathBuf, String> {
         .map_err(|_| "URI is not a file path".to_string())
 }
 
+/// Virtual text namespace of LSP inlay hints
+const INLAY_HINTS_NAMESPACE: &str = "lsp-inlay-hints";
+
+/// How long a save waits for the language server to answer a format-on-save request
+const FORMAT_ON_SAVE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Shrink a replacement to the part that actually changes
+///
+/// Formatters often replace a large span (or the whole document) with mostly identical
+/// text. Returns the byte lengths of the common prefix and suffix of `old` and `new`
+/// plus the remaining slice of `new`, so cursors in unchanged text stay put.
+fn trim_unchanged_edit<'a>(old: &str, new: &'a str) -> (usize, usize, &'a str) {
+    let prefix: usize = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    let suffix: usize = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    (prefix, suffix, &new[prefix..new.len() - suffix])
+}
+
 /// The main editor struct - manages multiple buffers, clipboard, and rendering
 pub struct Editor {
     /// All open buffers
@@ -232,12 +289,36 @@ pub struct Editor {
     /// Pending LSP completion request ID (if any)
     pending_completion_request: Option<u64>,
 
-    /// Pending LSP go-to-definition request ID (if any)
-    pending_goto_definition_request: Option<u64>,
+    /// Number of language servers yet to answer the pending completion request
+    completion_responses_remaining: usize,
+
+    /// Completion items received so far for the pending request, from all servers
+    completion_items_received: Vec<completion::CompletionCandidate>,
+
+    /// Whether any server answered the pending completion request with an incomplete list
+    completion_list_incomplete: bool,
+
+    /// Completion items offered at the cursor, while the completion popup is up
+    completion_session: Option<completion::CompletionSession>,
+
+    /// Pending completionItem/resolve requests
+    pending_completion_resolves: HashMap<u64, completion::PendingCompletionResolve>,
+
+    /// User snippets by language, read from the snippets directory on first use
+    user_snippets: HashMap<String, Vec<crate::primitives::snippet::SnippetDefinition>>,
+
+    /// Pending LSP go-to-definition/declaration/type definition/implementation request
+    pending_goto_request: Option<goto::PendingGoto>,
+
+    /// Locations offered by the goto picker or peek popup
+    goto_popup: Option<goto::GotoPopup>,
 
     /// Pending LSP hover request ID (if any)
     pending_hover_request: Option<u64>,
 
+    /// Byte position of the pending hover request, whose diagnostics are listed with it
+    hover_position: Option<usize>,
+
     /// Pending LSP find references request ID (if any)
     pending_references_request: Option<u64>,
 
@@ -250,9 +331,111 @@ pub struct Editor {
     /// Pending LSP code actions request ID (if any)
     pending_code_actions_request: Option<u64>,
 
+    /// Number of language servers yet to answer the pending code actions request
+    code_actions_responses_remaining: usize,
+
+    /// Code actions received so far for the pending request, from all servers
+    code_actions_received: Vec<code_actions::OfferedCodeAction>,
+
+    /// Code actions listed in the code actions popup, by item index
+    code_actions_offered: Vec<code_actions::OfferedCodeAction>,
+
+    /// Pending code action resolve and execute command requests
+    pending_code_action_requests: HashMap<u64, code_actions::PendingCodeActionRequest>,
+
+    /// Pending quick fix requests for lines with diagnostics
+    quick_fix_probes: HashMap<u64, diagnostics::QuickFixProbe>,
+
+    /// Related locations listed in the diagnostics popup, by item index
+    diagnostic_popup_locations: Vec<lsp_types::Location>,
+
+    /// Code lenses of each buffer
+    code_lenses: HashMap<BufferId, code_lens::BufferCodeLenses>,
+
+    /// Buffers whose code lenses should be re-requested, and when
+    code_lens_refresh: HashMap<BufferId, std::time::Instant>,
+
+    /// Pending code lens requests: request ID -> buffer
+    pending_code_lens_requests: HashMap<u64, BufferId>,
+
+    /// Pending code lens resolve requests
+    pending_code_lens_resolves: HashMap<u64, code_lens::PendingCodeLensResolve>,
+
+    /// Lens commands listed in the code lens popup, by item index
+    code_lens_offered: Vec<code_lens::OfferedCodeLens>,
+
     /// Pending LSP inlay hints request ID (if any)
     pending_inlay_hints_request: Option<u64>,
 
+    /// Pending LSP formatting request (if any)
+    pending_format_request: Option<PendingFormatRequest>,
+
+    /// Pending LSP document symbols request ID and the buffer it was made for
+    pending_document_symbols_request: Option<(u64, BufferId)>,
+
+    /// Tree-sitter outline extractor, used when no language server provides symbols
+    outline_extractor: OutlineExtractor,
+
+    /// Open "Go to Symbol in Workspace" search (queries in flight and merged results)
+    workspace_symbol_search: Option<workspace_symbols::WorkspaceSymbolSearch>,
+
+    /// Pending semantic tokens requests: request ID -> (buffer, requested lines for range requests)
+    pending_semantic_tokens_requests: HashMap<u64, (BufferId, Option<std::ops::Range<u32>>)>,
+
+    /// Buffers whose semantic tokens should be re-requested, and when
+    semantic_tokens_refresh: HashMap<BufferId, std::time::Instant>,
+
+    /// Tree-sitter fold range extractor, used when no language server provides ranges
+    fold_range_extractor: FoldRangeExtractor,
+
+    /// Tree-sitter text objects for structural selection, editing and navigation
+    text_object_extractor: TextObjectExtractor,
+
+    /// Selection expansions of the active buffer, undone one at a time by shrinking
+    selection_expansions: Vec<textobjects::SelectionExpansion>,
+
+    /// Pending folding range requests: request ID -> buffer
+    pending_folding_requests: HashMap<u64, BufferId>,
+
+    /// Buffers whose foldable ranges should be recomputed, and when
+    folding_refresh: HashMap<BufferId, std::time::Instant>,
+
+    /// Call and type hierarchy trees shown in virtual buffers
+    hierarchy_views: HashMap<BufferId, crate::view::hierarchy::HierarchyTree>,
+
+    /// Undo tree visualizers shown in virtual buffers
+    undo_tree_views: HashMap<BufferId, undo_tree::UndoTreeView>,
+
+    /// Kill or yank of the previous action, continued by the next kill or yank-pop
+    kill_chain: kill_ring::KillChain,
+
+    /// Shell command running on the selection or buffer, if any
+    shell_command: Option<shell_command::PendingShellCommand>,
+
+    /// Id for the next shell command
+    next_shell_command_id: u64,
+
+    /// Scratch buffer showing shell command output
+    shell_output_buffer: Option<BufferId>,
+
+    /// Pending call/type hierarchy requests (prepare or expand)
+    pending_hierarchy_requests: HashMap<u64, hierarchy::PendingHierarchyRequest>,
+
+    /// The running debug session, if any
+    debug_session: Option<debugger::DebugSession>,
+
+    /// ID of the last debug session, to ignore messages of earlier sessions
+    next_debug_session_id: u64,
+
+    /// Watch expressions of the debugger (kept across sessions)
+    debug_watches: Vec<debugger::DebugWatch>,
+
+    /// Open debugger panels (variables, call stack, watches)
+    debug_panels: HashMap<BufferId, DebugPanel>,
+
+    /// Parser state and unnamed register of the vim emulation
+    vim: crate::input::vim::VimState,
+
     /// Hover symbol range (byte offsets) - for highlighting the symbol under hover
     /// Format: (start_byte_offset, end_byte_offset)
     hover_symbol_range: Option<(usize, usize)>,
@@ -312,15 +495,13 @@ pub struct Editor {
     /// LSP progress tracking (token -> progress info)
     lsp_progress: std::collections::HashMap<String, LspProgressInfo>,
 
-    /// LSP server statuses (language -> status)
+    /// LSP server statuses ((language, server name) -> status)
     lsp_server_statuses:
-        std::collections::HashMap<String, crate::services::async_bridge::LspServerStatus>,
-
-    /// LSP window messages (recent messages from window/showMessage)
-    lsp_window_messages: Vec<LspMessageEntry>,
+        std::collections::HashMap<(String, String), crate::services::async_bridge::LspServerStatus>,
 
-    /// LSP log messages (recent messages from window/logMessage)
-    lsp_log_messages: Vec<LspMessageEntry>,
+    /// Recent log messages, notifications and progress of each LSP server
+    /// ((language, server name) -> log)
+    lsp_logs: HashMap<(String, String), LspServerLog>,
 
     /// Diagnostic result IDs per URI (for incremental pull diagnostics)
     /// Maps URI string to last result_id received from server
@@ -330,6 +511,10 @@ pub struct Editor {
     /// Maps file URI string to Vec of diagnostics for that file
     stored_diagnostics: HashMap<String, Vec<lsp_types::Diagnostic>>,
 
+    /// LSP diagnostics per URI, kept per server so each server's publish replaces only
+    /// its own; `stored_diagnostics` holds the merged result
+    diagnostics_by_source: HashMap<String, DiagnosticsBySource>,
+
     /// Event broadcaster for control events (observable by external systems)
     event_broadcaster: crate::model::control_event::EventBroadcaster,
 
@@ -385,6 +570,13 @@ pub struct Editor {
     /// We watch directories instead of files to handle atomic saves (temp+rename)
     watched_dirs: HashSet<PathBuf>,
 
+    /// Recursive watcher of the working directory, while language servers have
+    /// registered file watchers
+    lsp_file_watcher: Option<notify::RecommendedWatcher>,
+
+    /// File changes not yet sent to the language servers watching them
+    watched_file_changes: Vec<(PathBuf, lsp_types::FileChangeType)>,
+
     /// Last known modification times for watched files (for conflict detection)
     /// Maps file path to last known modification time
     file_mod_times: HashMap<PathBuf, std::time::SystemTime>,
@@ -402,6 +594,9 @@ pub struct Editor {
     /// Recovery service for auto-save and crash recovery
     recovery_service: RecoveryService,
 
+    /// Undo histories of closed files
+    undo_history: UndoHistoryStore,
+
     /// Last auto-save time for rate limiting
     last_auto_save: std::time::Instant,
 
@@ -660,6 +855,7 @@ impl Editor {
         let file_explorer_width = config.file_explorer.width;
         let recovery_enabled = config.editor.recovery_enabled;
         let auto_save_interval_secs = config.editor.auto_save_interval_secs;
+        let persistent_undo_max_bytes = config.editor.persistent_undo_max_bytes;
         let check_for_updates = config.check_for_updates;
 
         // Start periodic update checker if enabled
@@ -717,13 +913,55 @@ impl Editor {
             in_navigation: false,
             next_lsp_request_id: 0,
             pending_completion_request: None,
-            pending_goto_definition_request: None,
+            completion_responses_remaining: 0,
+            completion_items_received: Vec::new(),
+            completion_list_incomplete: false,
+            completion_session: None,
+            pending_completion_resolves: HashMap::new(),
+            user_snippets: HashMap::new(),
+            pending_goto_request: None,
+            goto_popup: None,
             pending_hover_request: None,
+            hover_position: None,
             pending_references_request: None,
             pending_references_symbol: String::new(),
             pending_signature_help_request: None,
             pending_code_actions_request: None,
+            code_actions_responses_remaining: 0,
+            code_actions_received: Vec::new(),
+            code_actions_offered: Vec::new(),
+            pending_code_action_requests: HashMap::new(),
+            quick_fix_probes: HashMap::new(),
+            diagnostic_popup_locations: Vec::new(),
+            code_lenses: HashMap::new(),
+            code_lens_refresh: HashMap::new(),
+            pending_code_lens_requests: HashMap::new(),
+            pending_code_lens_resolves: HashMap::new(),
+            code_lens_offered: Vec::new(),
             pending_inlay_hints_request: None,
+            pending_format_request: None,
+            pending_document_symbols_request: None,
+            outline_extractor: OutlineExtractor::new(),
+            workspace_symbol_search: None,
+            pending_semantic_tokens_requests: HashMap::new(),
+            semantic_tokens_refresh: HashMap::new(),
+            fold_range_extractor: FoldRangeExtractor::new(),
+            text_object_extractor: TextObjectExtractor::new(),
+            selection_expansions: Vec::new(),
+            pending_folding_requests: HashMap::new(),
+            folding_refresh: HashMap::new(),
+            hierarchy_views: HashMap::new(),
+            undo_tree_views: HashMap::new(),
+            kill_chain: kill_ring::KillChain::None,
+            shell_command: None,
+            next_shell_command_id: 0,
+            shell_output_buffer: None,
+            pending_hierarchy_requests: HashMap::new(),
+            debug_session: None,
+            next_debug_session_id: 0,
+            debug_watches: Vec::new(),
+            debug_panels: HashMap::new(),
+            vim: crate::input::vim::VimState::new(),
             hover_symbol_range: None,
             hover_symbol_overlay: None,
             mouse_hover_screen_position: None,
@@ -765,10 +1003,10 @@ impl Editor {
             },
             lsp_progress: std::collections::HashMap::new(),
             lsp_server_statuses: std::collections::HashMap::new(),
-            lsp_window_messages: Vec::new(),
-            lsp_log_messages: Vec::new(),
+            lsp_logs: HashMap::new(),
             diagnostic_result_ids: HashMap::new(),
             stored_diagnostics: HashMap::new(),
+            diagnostics_by_source: HashMap::new(),
             event_broadcaster: crate::model::control_event::EventBroadcaster::default(),
             bookmarks: HashMap::new(),
             search_case_sensitive: true,
@@ -788,6 +1026,8 @@ impl Editor {
             auto_revert_enabled: true,
             file_watcher: None,
             watched_dirs: HashSet::new(),
+            lsp_file_watcher: None,
+            watched_file_changes: Vec::new(),
             file_mod_times: HashMap::new(),
             file_rapid_change_counts: HashMap::new(),
             file_open_state: None,
@@ -800,6 +1040,10 @@ impl Editor {
                 };
                 RecoveryService::with_config_and_dir(recovery_config, dir_context.recovery_dir())
             },
+            undo_history: UndoHistoryStore::new(
+                dir_context.undo_history_dir(),
+                persistent_undo_max_bytes,
+            ),
             last_auto_save: std::time::Instant::now(),
             active_custom_contexts: HashSet::new(),
             warning_log: None,
@@ -919,9 +1163,8 @@ impl Editor {
     pub fn is_lsp_server_ready(&self, language: &str) -> bool {
         use crate::services::async_bridge::LspServerStatus;
         self.lsp_server_statuses
-            .get(language)
-            .map(|status| matches!(status, LspServerStatus::Running))
-            .unwrap_or(false)
+            .iter()
+            .any(|((lang, _), status)| lang == language && *status == LspServerStatus::Running)
     }
 
     /// Get the LSP status string (displayed in status bar)
@@ -972,7 +1215,7 @@ impl Editor {
     }
 
     /// Configure LSP server for a specific language
-    pub fn set_lsp_config(&mut self, language: String, config: LspServerConfig) {
+    pub fn set_lsp_config(&mut self, language: String, config: LspLanguageConfig) {
         if let Some(ref mut lsp) = self.lsp {
             lsp.set_language_config(language, config);
         }
@@ -1292,6 +1535,15 @@ impl Editor {
         // Store metadata for this buffer
         self.buffer_metadata.insert(buffer_id, metadata);
 
+        // Pick up the undo history from the last time the file was closed
+        self.restore_undo_history(buffer_id);
+        if let Some(log) = self.event_logs.get_mut(&buffer_id) {
+            log.set_snapshot_interval(self.config.editor.snapshot_interval);
+        }
+
+        // Local ranges right away; the server's replace them once it's running
+        self.schedule_folding_refresh(buffer_id, std::time::Duration::ZERO);
+
         // Add buffer to the active split's tabs (but don't switch to it)
         let active_split = self.split_manager.active_split();
         if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
@@ -1667,10 +1919,20 @@ impl Editor {
                 .set_split_buffer(split_id, replacement_buffer);
         }
 
+        self.notify_lsp_file_closed(id);
+
+        self.persist_undo_history(id);
+        self.cleanup_undo_histories();
+
         self.buffers.remove(&id);
         self.event_logs.remove(&id);
         self.seen_byte_ranges.remove(&id);
         self.buffer_metadata.remove(&id);
+        self.hierarchy_views.remove(&id);
+        self.undo_tree_views.remove(&id);
+        self.debug_panels.remove(&id);
+        self.code_lenses.remove(&id);
+        self.code_lens_refresh.remove(&id);
 
         // Remove buffer from panel_ids mapping if it was a panel buffer
         // This prevents stale entries when the same panel_id is reused later
@@ -2389,8 +2651,11 @@ impl Editor {
             self.set_status_message("Inlay hints enabled".to_string());
         } else {
             // Clear inlay hints from all buffers
+            let namespace = VirtualTextNamespace::from_string(INLAY_HINTS_NAMESPACE.to_string());
             for state in self.buffers.values_mut() {
-                state.virtual_texts.clear(&mut state.marker_list);
+                state
+                    .virtual_texts
+                    .clear_namespace(&mut state.marker_list, &namespace);
             }
             self.set_status_message("Inlay hints disabled".to_string());
         }
@@ -2433,7 +2698,7 @@ impl Editor {
 
         // Get LSP client for this language
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
+            if let Some(client) = lsp.handle_for_feature(&language, LspFeature::InlayHints) {
                 let request_id = self.next_lsp_request_id;
                 self.next_lsp_request_id += 1;
                 self.pending_inlay_hints_request = Some(request_id);
@@ -2742,6 +3007,7 @@ impl Editor {
         match event {
             Event::Insert { .. } | Event::Delete { .. } => {
                 self.invalidate_layouts_for_buffer(self.active_buffer());
+                self.schedule_folding_refresh(self.active_buffer(), folding::FOLDING_DEBOUNCE);
             }
             Event::Batch { events, .. } => {
                 let has_edits = events
@@ -2749,6 +3015,7 @@ impl Editor {
                     .any(|e| matches!(e, Event::Insert { .. } | Event::Delete { .. }));
                 if has_edits {
                     self.invalidate_layouts_for_buffer(self.active_buffer());
+                    self.schedule_folding_refresh(self.active_buffer(), folding::FOLDING_DEBOUNCE);
                 }
             }
             _ => {}
@@ -3029,6 +3296,19 @@ impl Editor {
         }
     }
 
+    /// Open file buffers whose language is `language`
+    pub(super) fn buffer_ids_for_language(&self, language: &str) -> Vec<BufferId> {
+        self.buffer_metadata
+            .iter()
+            .filter(|(_, metadata)| {
+                metadata.file_path().is_some_and(|path| {
+                    detect_language(path, &self.config.languages).as_deref() == Some(language)
+                })
+            })
+            .map(|(buffer_id, _)| *buffer_id)
+            .collect()
+    }
+
     /// Invalidate layouts for all splits viewing a specific buffer
     ///
     /// Called after buffer content changes (Insert/Delete) to mark
@@ -3105,9 +3385,12 @@ impl Editor {
     // works across buffer editing and prompt input.
 
     /// Copy the current selection to clipboard
+    ///
+    /// With several cursors, each cursor's text is kept so that a paste with the
+    /// same number of cursors puts one back at each.
     pub fn copy_selection(&mut self) {
-        // Collect ranges first
-        let ranges: Vec<_> = {
+        // Collect ranges first, in buffer order
+        let mut ranges: Vec<_> = {
             let state = self.active_state();
             state
                 .cursors
@@ -3115,19 +3398,16 @@ impl Editor {
                 .filter_map(|(_, cursor)| cursor.selection_range())
                 .collect()
         };
+        ranges.sort_by_key(|range| range.start);
 
-        let mut text = String::new();
         let state = self.active_state_mut();
-        for range in ranges {
-            if !text.is_empty() {
-                text.push('\n');
-            }
-            let range_text = state.get_text_range(range.start, range.end);
-            text.push_str(&range_text);
-        }
+        let pieces: Vec<String> = ranges
+            .into_iter()
+            .map(|range| state.get_text_range(range.start, range.end))
+            .collect();
 
-        if !text.is_empty() {
-            self.clipboard.copy(text);
+        if pieces.iter().any(|piece| !piece.is_empty()) {
+            self.clipboard.copy_pieces(pieces);
             self.status_message = Some("Copied".to_string());
         }
     }
@@ -3175,6 +3455,14 @@ impl Editor {
 
     /// Paste the clipboard content
     pub fn paste(&mut self) {
+        let cursor_count = self.active_state().cursors.count();
+        if cursor_count > 1 {
+            if let Some(pieces) = self.clipboard.paste_pieces(cursor_count) {
+                self.paste_pieces(pieces);
+                return;
+            }
+        }
+
         // Get content from clipboard (tries system first, falls back to internal)
         let paste_text = match self.clipboard.paste() {
             Some(text) => text,
@@ -3197,6 +3485,37 @@ impl Editor {
         self.status_message = Some("Pasted".to_string());
     }
 
+    /// Paste one piece of a multi-cursor copy at each cursor, in buffer order
+    fn paste_pieces(&mut self, pieces: Vec<String>) {
+        let mut cursors: Vec<(CursorId, usize)> = self
+            .active_state()
+            .cursors
+            .iter()
+            .map(|(cursor_id, cursor)| (cursor_id, cursor.position))
+            .collect();
+        cursors.sort_by_key(|&(_, position)| position);
+
+        // Insert at the last cursor first so the earlier positions stay valid
+        let events = cursors
+            .into_iter()
+            .zip(pieces)
+            .rev()
+            .map(|((cursor_id, position), text)| Event::Insert {
+                position,
+                text,
+                cursor_id,
+            })
+            .collect();
+        let batch = Event::Batch {
+            events,
+            description: "Paste".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+
+        self.status_message = Some("Pasted".to_string());
+    }
+
     /// Add a cursor at the next occurrence of the selected text
     /// If no selection, does nothing
     pub fn add_cursor_at_next_match(&mut self) {
@@ -3552,6 +3871,10 @@ impl Editor {
             }
         };
 
+        // Sent once the server is ready (right away if it's already running)
+        self.schedule_semantic_tokens_refresh(buffer_id, std::time::Duration::ZERO);
+        self.schedule_code_lens_refresh(buffer_id, std::time::Duration::ZERO);
+
         let enable_inlay_hints = self.config.editor.enable_inlay_hints;
         let previous_result_id = self.diagnostic_result_ids.get(uri.as_str()).cloned();
 
@@ -3580,18 +3903,21 @@ impl Editor {
         tracing::debug!("Using URI from metadata: {}", uri.as_str());
         tracing::debug!("Attempting to spawn LSP client for language: {}", language);
 
-        match lsp.try_spawn(&language) {
+        match lsp.try_spawn(&language, Some(path)) {
             LspSpawnResult::Spawned => {
-                if let Some(client) = lsp.get_or_spawn(&language) {
-                    // Send didOpen
-                    tracing::info!("Sending didOpen to LSP for: {}", uri.as_str());
-                    if let Err(e) = client.did_open(uri.clone(), text, language.clone()) {
+                // Send didOpen to every server of the language, in the file's project
+                tracing::info!("Sending didOpen to LSP for: {}", uri.as_str());
+                lsp.add_workspace_folder(&language, path);
+                for client in lsp.language_handles(&language) {
+                    if let Err(e) = client.did_open(uri.clone(), text.clone(), language.clone()) {
                         tracing::warn!("Failed to send didOpen to LSP: {}", e);
                         return;
                     }
-                    tracing::info!("Successfully sent didOpen to LSP");
+                }
+                tracing::info!("Successfully sent didOpen to LSP");
 
-                    // Request pull diagnostics
+                // Request pull diagnostics
+                if let Some(client) = lsp.handle_for_feature(&language, LspFeature::Diagnostics) {
                     let request_id = self.next_lsp_request_id;
                     self.next_lsp_request_id += 1;
                     if let Err(e) =
@@ -3608,9 +3934,12 @@ impl Editor {
                             request_id
                         );
                     }
+                }
 
-                    // Request inlay hints
-                    if enable_inlay_hints {
+                // Request inlay hints
+                if enable_inlay_hints {
+                    if let Some(client) = lsp.handle_for_feature(&language, LspFeature::InlayHints)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_inlay_hints_request = Some(request_id);
@@ -3695,13 +4024,15 @@ impl Editor {
 
                         // Use full document sync - send the entire new content
                         if let Some(content) = content {
-                            if let Some(client) = lsp.get_or_spawn(&language) {
+                            for client in lsp.language_handles(&language) {
                                 let content_change = TextDocumentContentChangeEvent {
                                     range: None, // None means full document replacement
                                     range_length: None,
-                                    text: content,
+                                    text: content.clone(),
                                 };
-                                if let Err(e) = client.did_change(lsp_uri, vec![content_change]) {
+                                if let Err(e) =
+                                    client.did_change(lsp_uri.clone(), vec![content_change])
+                                {
                                     tracing::warn!("Failed to notify LSP of file change: {}", e);
                                 }
                             }
@@ -4499,6 +4830,10 @@ impl Editor {
                     self.file_open_state = None;
                     self.file_browser_layout = None;
                 }
+                PromptType::WorkspaceSymbol => {
+                    // Cancel queries still in flight
+                    self.cancel_workspace_symbol_search();
+                }
                 _ => {}
             }
         }
@@ -4523,8 +4858,11 @@ impl Editor {
                     | PromptType::SwitchProject
                     | PromptType::SaveFileAs
                     | PromptType::StopLspServer
+                    | PromptType::ShowLspLog
                     | PromptType::SelectTheme
                     | PromptType::SwitchToTab
+                    | PromptType::WorkspaceSymbol
+                    | PromptType::PasteFromHistory
             ) {
                 // Use the selected suggestion if any
                 if let Some(selected_idx) = prompt.selected_suggestion {
@@ -4690,7 +5028,15 @@ impl Editor {
                     },
                 );
             }
-            PromptType::SwitchToTab | PromptType::SelectTheme | PromptType::StopLspServer => {
+            PromptType::WorkspaceSymbol => {
+                // Query the language servers; results arrive asynchronously
+                self.update_workspace_symbol_query(input);
+            }
+            PromptType::SwitchToTab
+            | PromptType::SelectTheme
+            | PromptType::StopLspServer
+            | PromptType::ShowLspLog
+            | PromptType::PasteFromHistory => {
                 // Filter suggestions using fuzzy matching
                 use crate::input::fuzzy::fuzzy_match;
 
@@ -4742,8 +5088,12 @@ impl Editor {
 
         for message in messages {
             match message {
-                AsyncMessage::LspDiagnostics { uri, diagnostics } => {
-                    self.handle_lsp_diagnostics(uri, diagnostics);
+                AsyncMessage::LspDiagnostics {
+                    uri,
+                    server,
+                    diagnostics,
+                } => {
+                    self.handle_lsp_diagnostics(uri, server, diagnostics);
                 }
                 AsyncMessage::LspInitialized { language } => {
                     tracing::info!("LSP server initialized for language: {}", language);
@@ -4751,6 +5101,9 @@ impl Editor {
 
                     // Send didOpen for all open buffers of this language
                     self.resend_did_open_for_language(&language);
+                    self.schedule_semantic_tokens_for_language(&language);
+                    self.schedule_folding_for_language(&language);
+                    self.schedule_code_lens_for_language(&language);
                 }
                 AsyncMessage::LspError {
                     language,
@@ -4788,17 +5141,29 @@ impl Editor {
                         }
                     }
                 }
-                AsyncMessage::LspCompletion { request_id, items } => {
-                    if let Err(e) = self.handle_completion_response(request_id, items) {
+                AsyncMessage::LspCompletion {
+                    request_id,
+                    server,
+                    items,
+                    is_incomplete,
+                } => {
+                    if let Err(e) =
+                        self.handle_completion_response(request_id, server, items, is_incomplete)
+                    {
                         tracing::error!("Error handling completion response: {}", e);
                     }
                 }
-                AsyncMessage::LspGotoDefinition {
+                AsyncMessage::LspCompletionItemResolved { request_id, result } => {
+                    if let Err(e) = self.handle_completion_item_resolved(request_id, result) {
+                        tracing::error!("Error applying resolved completion item: {}", e);
+                    }
+                }
+                AsyncMessage::LspGotoLocations {
                     request_id,
                     locations,
                 } => {
-                    if let Err(e) = self.handle_goto_definition_response(request_id, locations) {
-                        tracing::error!("Error handling goto definition response: {}", e);
+                    if let Err(e) = self.handle_goto_response(request_id, locations) {
+                        tracing::error!("Error handling goto response: {}", e);
                     }
                 }
                 AsyncMessage::LspRename { request_id, result } => {
@@ -4830,18 +5195,76 @@ impl Editor {
                 }
                 AsyncMessage::LspCodeActions {
                     request_id,
+                    language,
+                    server,
                     actions,
                 } => {
-                    self.handle_code_actions_response(request_id, actions);
+                    if self.is_quick_fix_probe(request_id) {
+                        self.handle_quick_fix_probe_response(request_id, actions);
+                    } else {
+                        self.handle_code_actions_response(request_id, language, server, actions);
+                    }
+                }
+                AsyncMessage::LspCodeActionResolved { request_id, result } => {
+                    self.handle_code_action_resolved(request_id, result);
+                }
+                AsyncMessage::LspCommandExecuted { request_id, result } => {
+                    self.handle_command_executed(request_id, result);
+                }
+                AsyncMessage::LspApplyEdit {
+                    language,
+                    server,
+                    label,
+                    edit,
+                    response,
+                } => {
+                    let result = self.handle_lsp_apply_edit(language, server, label, edit);
+                    let _ = response.send(result);
+                }
+                AsyncMessage::LspWatchedFilesRegistered {
+                    language,
+                    server,
+                    id,
+                    watchers,
+                } => {
+                    self.handle_watched_files_registered(language, server, id, watchers);
+                }
+                AsyncMessage::LspWatchedFilesUnregistered {
+                    language,
+                    server,
+                    id,
+                } => {
+                    self.handle_watched_files_unregistered(language, server, id);
+                }
+                AsyncMessage::LspCodeLens {
+                    request_id,
+                    language,
+                    server,
+                    result,
+                } => {
+                    self.handle_code_lens_response(request_id, language, server, result);
+                }
+                AsyncMessage::LspCodeLensResolved { request_id, result } => {
+                    self.handle_code_lens_resolved(request_id, result);
+                }
+                AsyncMessage::LspCodeLensRefresh { language } => {
+                    self.schedule_code_lens_for_language(&language);
                 }
                 AsyncMessage::LspPulledDiagnostics {
                     request_id: _,
                     uri,
+                    server,
                     result_id,
                     diagnostics,
                     unchanged,
                 } => {
-                    self.handle_lsp_pulled_diagnostics(uri, result_id, diagnostics, unchanged);
+                    self.handle_lsp_pulled_diagnostics(
+                        uri,
+                        server,
+                        result_id,
+                        diagnostics,
+                        unchanged,
+                    );
                 }
                 AsyncMessage::LspInlayHints {
                     request_id,
@@ -4850,12 +5273,73 @@ impl Editor {
                 } => {
                     self.handle_lsp_inlay_hints(request_id, uri, hints);
                 }
+                AsyncMessage::LspFormatting {
+                    request_id,
+                    uri: _,
+                    result,
+                } => {
+                    if let Err(e) = self.handle_formatting_response(request_id, result) {
+                        tracing::error!("Error handling formatting response: {}", e);
+                    }
+                }
+                AsyncMessage::LspDocumentSymbols {
+                    request_id,
+                    uri: _,
+                    result,
+                } => {
+                    self.handle_document_symbols_response(request_id, result);
+                }
+                AsyncMessage::LspWorkspaceSymbols {
+                    request_id,
+                    language,
+                    result,
+                } => {
+                    self.handle_workspace_symbols_response(request_id, language, result);
+                }
+                AsyncMessage::LspSemanticTokens {
+                    request_id,
+                    uri: _,
+                    result,
+                } => {
+                    self.handle_semantic_tokens_response(request_id, result);
+                }
+                AsyncMessage::LspHierarchy { request_id, result } => {
+                    self.handle_hierarchy_response(request_id, result);
+                }
+                AsyncMessage::DapResponse {
+                    session,
+                    request_id,
+                    command,
+                    result,
+                } => {
+                    self.handle_dap_response(session, request_id, command, result);
+                }
+                AsyncMessage::DapEvent {
+                    session,
+                    event,
+                    body,
+                } => {
+                    self.handle_dap_event(session, event, body);
+                }
+                AsyncMessage::DapExited { session, error } => {
+                    self.handle_dap_exited(session, error);
+                }
+                AsyncMessage::LspFoldingRanges {
+                    request_id,
+                    uri: _,
+                    result,
+                } => {
+                    self.handle_folding_ranges_response(request_id, result);
+                }
                 AsyncMessage::LspServerQuiescent { language } => {
                     self.handle_lsp_server_quiescent(language);
                 }
                 AsyncMessage::FileChanged { path } => {
                     self.handle_async_file_changed(path);
                 }
+                AsyncMessage::WatchedFileChanged { path, change } => {
+                    self.watched_file_changes.push((path, change));
+                }
                 AsyncMessage::GitStatusChanged { status } => {
                     tracing::info!("Git status changed: {}", status);
                     // TODO: Handle git status changes
@@ -4904,31 +5388,49 @@ impl Editor {
                 }
                 AsyncMessage::LspProgress {
                     language,
+                    server,
                     token,
                     value,
                 } => {
-                    self.handle_lsp_progress(language, token, value);
+                    self.handle_lsp_progress(language, server, token, value);
                 }
                 AsyncMessage::LspWindowMessage {
                     language,
+                    server,
                     message_type,
                     message,
                 } => {
-                    self.handle_lsp_window_message(language, message_type, message);
+                    self.handle_lsp_window_message(language, server, message_type, message);
                 }
                 AsyncMessage::LspLogMessage {
                     language,
+                    server,
                     message_type,
                     message,
                 } => {
-                    self.handle_lsp_log_message(language, message_type, message);
+                    self.handle_lsp_log_message(language, server, message_type, message);
                 }
-                AsyncMessage::LspStatusUpdate { language, status } => {
-                    self.handle_lsp_status_update(language, status);
+                AsyncMessage::LspTrace {
+                    language,
+                    server,
+                    outgoing,
+                    message,
+                } => {
+                    self.handle_lsp_trace(language, server, outgoing, message);
+                }
+                AsyncMessage::LspStatusUpdate {
+                    language,
+                    server,
+                    status,
+                } => {
+                    self.handle_lsp_status_update(language, server, status);
                 }
                 AsyncMessage::FileOpenDirectoryLoaded(result) => {
                     self.handle_file_open_directory_loaded(result);
                 }
+                AsyncMessage::ShellCommandFinished { id, outputs } => {
+                    self.handle_shell_command_finished(id, outputs);
+                }
                 AsyncMessage::TerminalOutput { terminal_id } => {
                     // Terminal output received - check if we should auto-jump back to terminal mode
                     tracing::trace!("Terminal output received for {:?}", terminal_id);
@@ -4986,6 +5488,21 @@ impl Editor {
         // Process pending LSP server restarts (with exponential backoff)
         self.process_pending_lsp_restarts();
 
+        // Save unformatted if a format-on-save request is taking too long
+        let format_timed_out = self.check_format_on_save_timeout();
+
+        // Send semantic tokens requests that are due (debounced after edits)
+        self.check_semantic_tokens_refresh();
+        self.check_folding_refresh();
+        self.check_code_lens_refresh();
+        self.store_undo_snapshots();
+
+        // Send the file changes of this tick to the servers watching them
+        self.flush_watched_file_changes();
+
+        // Show the log entries of this tick in open LSP log buffers
+        self.update_lsp_log_buffers();
+
         // Check and clear the plugin render request flag
         #[cfg(feature = "plugins")]
         let plugin_render = {
@@ -5003,7 +5520,7 @@ impl Editor {
         }
 
         // Trigger render if any async messages, plugin commands were processed, or plugin requested render
-        needs_render || processed_any_commands || plugin_render
+        needs_render || processed_any_commands || plugin_render || format_timed_out
     }
 
     /// Update LSP status bar string from active progress operations
@@ -5035,7 +5552,13 @@ impl Editor {
         let mut statuses: Vec<(String, LspServerStatus)> = self
             .lsp_server_statuses
             .iter()
-            .map(|(lang, status)| (lang.clone(), *status))
+            .map(|((language, server), status)| {
+                let label = match &self.lsp {
+                    Some(lsp) => lsp.server_label(language, server),
+                    None => language.clone(),
+                };
+                (label, *status)
+            })
             .collect();
 
         if statuses.is_empty() {
@@ -5470,6 +5993,10 @@ impl Editor {
                 self.handle_send_lsp_request(language, method, params, request_id);
             }
 
+            PluginCommand::RequestDocumentSymbols { buffer_id } => {
+                self.request_document_symbols(buffer_id);
+            }
+
             // ==================== Clipboard Commands ====================
             PluginCommand::SetClipboard { text } => {
                 self.handle_set_clipboard(text);
@@ -5816,385 +6343,85 @@ impl Editor {
         Ok(())
     }
 
-    /// Handle LSP completion response
-    fn handle_completion_response(
-        &mut self,
-        request_id: u64,
-        items: Vec<lsp_types::CompletionItem>,
-    ) -> io::Result<()> {
-        // Check if this is the pending completion request
-        if self.pending_completion_request != Some(request_id) {
-            tracing::debug!(
-                "Ignoring completion response for outdated request {}",
-                request_id
-            );
-            return Ok(());
+    /// Check if there are any pending LSP requests
+    pub fn has_pending_lsp_requests(&self) -> bool {
+        self.pending_completion_request.is_some() || self.pending_goto_request.is_some()
+    }
+
+    /// Cancel any pending LSP requests
+    /// This should be called when the user performs an action that would make
+    /// the pending request's results stale (e.g., cursor movement, text editing)
+    fn cancel_pending_lsp_requests(&mut self) {
+        if let Some(request_id) = self.pending_completion_request.take() {
+            tracing::debug!("Canceling pending LSP completion request {}", request_id);
+            // Send cancellation to the LSP server
+            self.send_lsp_cancel_request(request_id);
+            self.lsp_status.clear();
+        }
+        if let Some(pending) = self.pending_goto_request.take() {
+            tracing::debug!("Canceling pending LSP goto request {}", pending.request_id);
+            // Send cancellation to the LSP server
+            self.send_lsp_cancel_request(pending.request_id);
+            self.lsp_status.clear();
         }
+    }
 
-        self.pending_completion_request = None;
-        self.lsp_status.clear();
+    /// Send a cancel request to the LSP server for a specific request ID
+    fn send_lsp_cancel_request(&mut self, request_id: u64) {
+        // Get the current file path to determine language
+        let metadata = self.buffer_metadata.get(&self.active_buffer());
+        let file_path = metadata.and_then(|meta| meta.file_path());
 
-        if items.is_empty() {
-            tracing::debug!("No completion items received");
-            return Ok(());
+        if let Some(path) = file_path {
+            if let Some(language) = detect_language(path, &self.config.languages) {
+                if let Some(lsp) = self.lsp.as_mut() {
+                    // The request may have gone to any of the language's servers
+                    for handle in lsp.language_handles(&language) {
+                        if let Err(e) = handle.cancel_request(request_id) {
+                            tracing::warn!("Failed to send LSP cancel request: {}", e);
+                        } else {
+                            tracing::debug!("Sent $/cancelRequest for request_id={}", request_id);
+                        }
+                    }
+                }
+            }
         }
+    }
 
-        // Get the partial word at cursor to filter completions
-        use crate::primitives::word_navigation::find_completion_word_start;
-        let (word_start, cursor_pos) = {
-            let state = self.active_state();
-            let cursor_pos = state.cursors.primary().position;
-            let word_start = find_completion_word_start(&state.buffer, cursor_pos);
-            (word_start, cursor_pos)
-        };
-        let prefix = if word_start < cursor_pos {
-            self.active_state_mut()
-                .get_text_range(word_start, cursor_pos)
-                .to_lowercase()
-        } else {
-            String::new()
-        };
+    /// Request LSP hover documentation at current cursor position
+    fn request_hover(&mut self) -> io::Result<()> {
+        // Get the current buffer and cursor position
+        let state = self.active_state();
+        let cursor_pos = state.cursors.primary().position;
 
-        // Filter completions to match the typed prefix
-        let filtered_items: Vec<&lsp_types::CompletionItem> = if prefix.is_empty() {
-            // No prefix - show all completions
-            items.iter().collect()
-        } else {
-            // Filter to items that start with the prefix (case-insensitive)
-            items
-                .iter()
-                .filter(|item| {
-                    item.label.to_lowercase().starts_with(&prefix)
-                        || item
-                            .filter_text
-                            .as_ref()
-                            .map(|ft| ft.to_lowercase().starts_with(&prefix))
-                            .unwrap_or(false)
-                })
-                .collect()
-        };
+        // Convert byte position to LSP position (line, UTF-16 code units)
+        let (line, character) = state.buffer.position_to_lsp_position(cursor_pos);
 
-        if filtered_items.is_empty() {
-            tracing::debug!("No completion items match prefix '{}'", prefix);
-            return Ok(());
+        // Debug: Log the position conversion details
+        if let Some(pos) = state.buffer.offset_to_position(cursor_pos) {
+            tracing::debug!(
+                "Hover request: cursor_byte={}, line={}, byte_col={}, utf16_col={}",
+                cursor_pos,
+                pos.line,
+                pos.column,
+                character
+            );
         }
 
-        // Convert CompletionItem to PopupListItem
-        use crate::view::popup::PopupListItem;
-
-        let popup_items: Vec<PopupListItem> = filtered_items
-            .iter()
-            .map(|item| {
-                let text = item.label.clone();
-                let detail = item.detail.clone();
-                let icon = match item.kind {
-                    Some(lsp_types::CompletionItemKind::FUNCTION)
-                    | Some(lsp_types::CompletionItemKind::METHOD) => Some("λ".to_string()),
-                    Some(lsp_types::CompletionItemKind::VARIABLE) => Some("v".to_string()),
-                    Some(lsp_types::CompletionItemKind::STRUCT)
-                    | Some(lsp_types::CompletionItemKind::CLASS) => Some("S".to_string()),
-                    Some(lsp_types::CompletionItemKind::CONSTANT) => Some("c".to_string()),
-                    Some(lsp_types::CompletionItemKind::KEYWORD) => Some("k".to_string()),
-                    _ => None,
-                };
-
-                let mut list_item = PopupListItem::new(text);
-                if let Some(detail) = detail {
-                    list_item = list_item.with_detail(detail);
-                }
-                if let Some(icon) = icon {
-                    list_item = list_item.with_icon(icon);
-                }
-                // Store the insert_text or label as data
-                let data = item
-                    .insert_text
-                    .clone()
-                    .or_else(|| Some(item.label.clone()));
-                if let Some(data) = data {
-                    list_item = list_item.with_data(data);
-                }
-                list_item
-            })
-            .collect();
-
-        // Show the popup
-        use crate::model::event::{
-            PopupContentData, PopupData, PopupListItemData, PopupPositionData,
-        };
-        let popup_data = PopupData {
-            title: Some("Completion".to_string()),
-            content: PopupContentData::List {
-                items: popup_items
-                    .into_iter()
-                    .map(|item| PopupListItemData {
-                        text: item.text,
-                        detail: item.detail,
-                        icon: item.icon,
-                        data: item.data,
-                    })
-                    .collect(),
-                selected: 0,
-            },
-            position: PopupPositionData::BelowCursor,
-            width: 50,
-            max_height: 15,
-            bordered: true,
-        };
-
-        self.active_state_mut()
-            .apply(&crate::model::event::Event::ShowPopup { popup: popup_data });
-
-        tracing::info!("Showing completion popup with {} items", items.len());
-
-        Ok(())
-    }
-
-    /// Handle LSP go-to-definition response
-    fn handle_goto_definition_response(
-        &mut self,
-        request_id: u64,
-        locations: Vec<lsp_types::Location>,
-    ) -> io::Result<()> {
-        // Check if this is the pending request
-        if self.pending_goto_definition_request != Some(request_id) {
-            tracing::debug!(
-                "Ignoring go-to-definition response for outdated request {}",
-                request_id
-            );
-            return Ok(());
-        }
-
-        self.pending_goto_definition_request = None;
-
-        if locations.is_empty() {
-            self.status_message = Some("No definition found".to_string());
-            return Ok(());
-        }
-
-        // For now, just jump to the first location
-        let location = &locations[0];
-
-        // Convert URI to file path
-        if let Ok(path) = uri_to_path(&location.uri) {
-            // Open the file
-            let buffer_id = self.open_file(&path)?;
-
-            // Move cursor to the definition position
-            let line = location.range.start.line as usize;
-            let character = location.range.start.character as usize;
-
-            // Calculate byte position from line and character
-            if let Some(state) = self.buffers.get(&buffer_id) {
-                let position = state.buffer.line_col_to_position(line, character);
-
-                // Move cursor
-                let cursor_id = state.cursors.primary_id();
-                let old_position = state.cursors.primary().position;
-                let old_anchor = state.cursors.primary().anchor;
-                let old_sticky_column = state.cursors.primary().sticky_column;
-                let event = crate::model::event::Event::MoveCursor {
-                    cursor_id,
-                    old_position,
-                    new_position: position,
-                    old_anchor,
-                    new_anchor: None,
-                    old_sticky_column,
-                    new_sticky_column: 0, // Reset sticky column for goto definition
-                };
-
-                if let Some(state) = self.buffers.get_mut(&buffer_id) {
-                    state.apply(&event);
-                }
-            }
-
-            self.status_message = Some(format!(
-                "Jumped to definition at {}:{}",
-                path.display(),
-                line + 1
-            ));
-        } else {
-            self.status_message = Some("Could not open definition location".to_string());
-        }
-
-        Ok(())
-    }
-
-    /// Check if there are any pending LSP requests
-    pub fn has_pending_lsp_requests(&self) -> bool {
-        self.pending_completion_request.is_some() || self.pending_goto_definition_request.is_some()
-    }
-
-    /// Cancel any pending LSP requests
-    /// This should be called when the user performs an action that would make
-    /// the pending request's results stale (e.g., cursor movement, text editing)
-    fn cancel_pending_lsp_requests(&mut self) {
-        if let Some(request_id) = self.pending_completion_request.take() {
-            tracing::debug!("Canceling pending LSP completion request {}", request_id);
-            // Send cancellation to the LSP server
-            self.send_lsp_cancel_request(request_id);
-            self.lsp_status.clear();
-        }
-        if let Some(request_id) = self.pending_goto_definition_request.take() {
-            tracing::debug!(
-                "Canceling pending LSP goto-definition request {}",
-                request_id
-            );
-            // Send cancellation to the LSP server
-            self.send_lsp_cancel_request(request_id);
-            self.lsp_status.clear();
-        }
-    }
-
-    /// Send a cancel request to the LSP server for a specific request ID
-    fn send_lsp_cancel_request(&mut self, request_id: u64) {
-        // Get the current file path to determine language
-        let metadata = self.buffer_metadata.get(&self.active_buffer());
-        let file_path = metadata.and_then(|meta| meta.file_path());
-
-        if let Some(path) = file_path {
-            if let Some(language) = detect_language(path, &self.config.languages) {
-                if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
-                        if let Err(e) = handle.cancel_request(request_id) {
-                            tracing::warn!("Failed to send LSP cancel request: {}", e);
-                        } else {
-                            tracing::debug!("Sent $/cancelRequest for request_id={}", request_id);
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    /// Request LSP completion at current cursor position
-    fn request_completion(&mut self) -> io::Result<()> {
-        // Get the current buffer and cursor position
-        let state = self.active_state();
-        let cursor_pos = state.cursors.primary().position;
-
-        // Convert byte position to LSP position (line, UTF-16 code units)
-        let (line, character) = state.buffer.position_to_lsp_position(cursor_pos);
-
-        // Get the current file URI and path
-        let metadata = self.buffer_metadata.get(&self.active_buffer());
-        let (uri, file_path) = if let Some(meta) = metadata {
-            (meta.file_uri(), meta.file_path())
-        } else {
-            (None, None)
-        };
-
-        if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
-                // Get LSP handle
-                if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
-                        let request_id = self.next_lsp_request_id;
-                        self.next_lsp_request_id += 1;
-                        self.pending_completion_request = Some(request_id);
-                        self.lsp_status = "LSP: completion...".to_string();
-
-                        let _ = handle.completion(
-                            request_id,
-                            uri.clone(),
-                            line as u32,
-                            character as u32,
-                        );
-                        tracing::info!(
-                            "Requested completion at {}:{}:{}",
-                            uri.as_str(),
-                            line,
-                            character
-                        );
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Request LSP go-to-definition at current cursor position
-    fn request_goto_definition(&mut self) -> io::Result<()> {
-        // Get the current buffer and cursor position
-        let state = self.active_state();
-        let cursor_pos = state.cursors.primary().position;
-
-        // Convert byte position to LSP position (line, UTF-16 code units)
-        let (line, character) = state.buffer.position_to_lsp_position(cursor_pos);
-
-        // Get the current file URI and path
-        let metadata = self.buffer_metadata.get(&self.active_buffer());
-        let (uri, file_path) = if let Some(meta) = metadata {
-            (meta.file_uri(), meta.file_path())
-        } else {
-            (None, None)
-        };
-
-        if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
-                // Get LSP handle
-                if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
-                        let request_id = self.next_lsp_request_id;
-                        self.next_lsp_request_id += 1;
-                        self.pending_goto_definition_request = Some(request_id);
-
-                        let _ = handle.goto_definition(
-                            request_id,
-                            uri.clone(),
-                            line as u32,
-                            character as u32,
-                        );
-                        tracing::info!(
-                            "Requested go-to-definition at {}:{}:{}",
-                            uri.as_str(),
-                            line,
-                            character
-                        );
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Request LSP hover documentation at current cursor position
-    fn request_hover(&mut self) -> io::Result<()> {
-        // Get the current buffer and cursor position
-        let state = self.active_state();
-        let cursor_pos = state.cursors.primary().position;
-
-        // Convert byte position to LSP position (line, UTF-16 code units)
-        let (line, character) = state.buffer.position_to_lsp_position(cursor_pos);
-
-        // Debug: Log the position conversion details
-        if let Some(pos) = state.buffer.offset_to_position(cursor_pos) {
-            tracing::debug!(
-                "Hover request: cursor_byte={}, line={}, byte_col={}, utf16_col={}",
-                cursor_pos,
-                pos.line,
-                pos.column,
-                character
-            );
-        }
-
-        // Get the current file URI and path
-        let metadata = self.buffer_metadata.get(&self.active_buffer());
-        let (uri, file_path) = if let Some(meta) = metadata {
-            (meta.file_uri(), meta.file_path())
-        } else {
-            (None, None)
-        };
+        // Get the current file URI and path
+        let metadata = self.buffer_metadata.get(&self.active_buffer());
+        let (uri, file_path) = if let Some(meta) = metadata {
+            (meta.file_uri(), meta.file_path())
+        } else {
+            (None, None)
+        };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
             // Detect language from file extension
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.handle_for_feature(&language, LspFeature::Hover) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_hover_request = Some(request_id);
@@ -6209,11 +6436,23 @@ impl Editor {
                             character,
                             cursor_pos
                         );
+                        self.hover_position = Some(cursor_pos);
+                        return Ok(());
                     }
                 }
             }
         }
 
+        // No server to ask: list the diagnostics at the cursor, if any
+        let diagnostics = self.diagnostics_at(cursor_pos);
+        if !diagnostics.is_empty() {
+            self.show_diagnostics_popup(
+                diagnostics,
+                crate::view::popup::PopupPosition::BelowCursor,
+                None,
+            );
+        }
+
         Ok(())
     }
 
@@ -6250,7 +6489,7 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.handle_for_feature(&language, LspFeature::Hover) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_hover_request = Some(request_id);
@@ -6265,11 +6504,20 @@ impl Editor {
                             character,
                             byte_pos
                         );
+                        self.hover_position = Some(byte_pos);
+                        return Ok(());
                     }
                 }
             }
         }
 
+        // No server to ask: list the diagnostics under the mouse, if any
+        let diagnostics = self.diagnostics_at(byte_pos);
+        if !diagnostics.is_empty() {
+            let position = self.hover_popup_position();
+            self.show_diagnostics_popup(diagnostics, position, None);
+        }
+
         Ok(())
     }
 
@@ -6290,9 +6538,21 @@ impl Editor {
         self.pending_hover_request = None;
         self.lsp_status.clear();
 
+        // Diagnostics at the hovered position are listed, with the documentation beside them
+        let diagnostics = self
+            .hover_position
+            .take()
+            .map(|position| self.diagnostics_at(position))
+            .unwrap_or_default();
+
         if contents.is_empty() {
-            self.set_status_message("No hover information available".to_string());
             self.hover_symbol_range = None;
+            if diagnostics.is_empty() {
+                self.set_status_message("No hover information available".to_string());
+            } else {
+                let position = self.hover_popup_position();
+                self.show_diagnostics_popup(diagnostics, position, None);
+            }
             return;
         }
 
@@ -6343,7 +6603,7 @@ impl Editor {
         }
 
         // Create a popup with the hover contents
-        use crate::view::popup::{Popup, PopupPosition};
+        use crate::view::popup::Popup;
         use ratatui::style::Style;
 
         // Use markdown rendering if the content is markdown
@@ -6357,18 +6617,18 @@ impl Editor {
 
         // Configure popup properties
         popup.title = Some("Hover".to_string());
-        // Use mouse position if this was a mouse-triggered hover, otherwise use cursor position
-        popup.position = if let Some((x, y)) = self.mouse_hover_screen_position.take() {
-            // Position below the mouse, offset by 1 row
-            PopupPosition::Fixed { x, y: y + 1 }
-        } else {
-            PopupPosition::BelowCursor
-        };
+        popup.position = self.hover_popup_position();
         popup.width = 80;
         popup.max_height = 20;
         popup.border_style = Style::default().fg(self.theme.popup_border_fg);
         popup.background_style = Style::default().bg(self.theme.popup_bg);
 
+        if !diagnostics.is_empty() {
+            let position = popup.position;
+            self.show_diagnostics_popup(diagnostics, position, Some(popup));
+            return;
+        }
+
         // Show the popup
         if let Some(state) = self.buffers.get_mut(&self.active_buffer()) {
             state.popups.show(popup);
@@ -6376,6 +6636,17 @@ impl Editor {
         }
     }
 
+    /// Where a hover popup goes: below the mouse for a mouse-triggered hover, otherwise
+    /// below the cursor
+    fn hover_popup_position(&mut self) -> crate::view::popup::PopupPosition {
+        use crate::view::popup::PopupPosition;
+        match self.mouse_hover_screen_position.take() {
+            // Position below the mouse, offset by 1 row
+            Some((x, y)) => PopupPosition::Fixed { x, y: y + 1 },
+            None => PopupPosition::BelowCursor,
+        }
+    }
+
     /// Apply inlay hints to editor state as virtual text
     fn apply_inlay_hints_to_state(
         state: &mut crate::state::EditorState,
@@ -6385,7 +6656,10 @@ impl Editor {
         use ratatui::style::{Color, Style};
 
         // Clear existing inlay hints
-        state.virtual_texts.clear(&mut state.marker_list);
+        let namespace = VirtualTextNamespace::from_string(INLAY_HINTS_NAMESPACE.to_string());
+        state
+            .virtual_texts
+            .clear_namespace(&mut state.marker_list, &namespace);
 
         if hints.is_empty() {
             return;
@@ -6420,12 +6694,13 @@ impl Editor {
             // Use the hint text as-is - spacing is handled during rendering
             let display_text = text;
 
-            state.virtual_texts.add(
+            state.virtual_texts.add_inline(
                 &mut state.marker_list,
                 byte_offset,
                 display_text,
                 hint_style,
                 position,
+                namespace.clone(),
                 0, // Default priority
             );
         }
@@ -6515,7 +6790,8 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.handle_for_feature(&language, LspFeature::References)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_references_request = Some(request_id);
@@ -6565,7 +6841,9 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.handle_for_feature(&language, LspFeature::SignatureHelp)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_signature_help_request = Some(request_id);
@@ -6668,173 +6946,625 @@ impl Editor {
                     }
                 }
             }
-        }
-
-        // Add function documentation if available
-        if let Some(doc) = &signature.documentation {
-            let doc_text = match doc {
-                lsp_types::Documentation::String(s) => s.clone(),
-                lsp_types::Documentation::MarkupContent(m) => m.value.clone(),
-            };
-            if !doc_text.is_empty() {
-                if lines.len() > 1 {
-                    lines.push(String::new());
-                    lines.push("---".to_string());
+        }
+
+        // Add function documentation if available
+        if let Some(doc) = &signature.documentation {
+            let doc_text = match doc {
+                lsp_types::Documentation::String(s) => s.clone(),
+                lsp_types::Documentation::MarkupContent(m) => m.value.clone(),
+            };
+            if !doc_text.is_empty() {
+                if lines.len() > 1 {
+                    lines.push(String::new());
+                    lines.push("---".to_string());
+                }
+                lines.push(doc_text);
+            }
+        }
+
+        // Create a popup with the signature help
+        use crate::view::popup::{Popup, PopupPosition};
+        use ratatui::style::Style;
+
+        let mut popup = Popup::text(lines, &self.theme);
+        popup.title = Some("Signature Help".to_string());
+        popup.position = PopupPosition::BelowCursor;
+        popup.width = 60;
+        popup.max_height = 10;
+        popup.border_style = Style::default().fg(self.theme.popup_border_fg);
+        popup.background_style = Style::default().bg(self.theme.popup_bg);
+
+        // Show the popup
+        if let Some(state) = self.buffers.get_mut(&self.active_buffer()) {
+            state.popups.show(popup);
+            tracing::info!(
+                "Showing signature help popup for {} signatures",
+                signature_help.signatures.len()
+            );
+        }
+    }
+
+    /// Request LSP code actions at current cursor position
+    fn request_code_actions(&mut self) -> io::Result<()> {
+        // Get the current buffer and cursor position
+        let state = self.active_state();
+        let cursor_pos = state.cursors.primary().position;
+
+        // Convert byte position to LSP position (line, UTF-16 code units)
+        let (line, character) = state.buffer.position_to_lsp_position(cursor_pos);
+
+        // Get selection range (if any) or use cursor position
+        let (start_line, start_char, end_line, end_char) =
+            if let Some(range) = state.cursors.primary().selection_range() {
+                let (s_line, s_char) = state.buffer.position_to_lsp_position(range.start);
+                let (e_line, e_char) = state.buffer.position_to_lsp_position(range.end);
+                (s_line as u32, s_char as u32, e_line as u32, e_char as u32)
+            } else {
+                (line as u32, character as u32, line as u32, character as u32)
+            };
+
+        // Get the current file URI and path
+        let metadata = self.buffer_metadata.get(&self.active_buffer());
+        let (uri, file_path) = if let Some(meta) = metadata {
+            (meta.file_uri(), meta.file_path())
+        } else {
+            (None, None)
+        };
+
+        if let (Some(uri), Some(path)) = (uri, file_path) {
+            // Diagnostics on the requested lines, which servers need to offer quick fixes
+            let diagnostics = self.diagnostics_on_lines(uri, start_line..=end_line);
+
+            // Detect language from file extension
+            if let Some(language) = detect_language(path, &self.config.languages) {
+                // Get LSP handle
+                if let Some(lsp) = self.lsp.as_mut() {
+                    // Every server that offers code actions is asked; the lists are merged
+                    let handles = lsp.handles_for_feature(&language, LspFeature::CodeAction);
+                    if !handles.is_empty() {
+                        let request_id = self.next_lsp_request_id;
+                        self.next_lsp_request_id += 1;
+                        self.pending_code_actions_request = Some(request_id);
+                        self.code_actions_responses_remaining = handles.len();
+                        self.code_actions_received.clear();
+                        self.lsp_status = "LSP: code actions...".to_string();
+
+                        for handle in handles {
+                            let _ = handle.code_actions(
+                                request_id,
+                                uri.clone(),
+                                start_line,
+                                start_char,
+                                end_line,
+                                end_char,
+                                diagnostics.clone(),
+                            );
+                        }
+                        tracing::info!(
+                            "Requested code actions at {}:{}:{}-{}:{} (byte_pos={})",
+                            uri.as_str(),
+                            start_line,
+                            start_char,
+                            end_line,
+                            end_char,
+                            cursor_pos
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Request LSP formatting for the active buffer
+    ///
+    /// With `selection_only`, formats the primary selection (or the cursor's line
+    /// when nothing is selected) using `textDocument/rangeFormatting`.
+    fn request_formatting(&mut self, selection_only: bool) -> io::Result<()> {
+        let request = if selection_only {
+            let state = self.active_state();
+            let cursor = state.cursors.primary();
+            let range = match cursor.selection_range() {
+                Some(range) => {
+                    let (s_line, s_char) = state.buffer.position_to_lsp_position(range.start);
+                    let (e_line, e_char) = state.buffer.position_to_lsp_position(range.end);
+                    LspRange::new(
+                        Position::new(s_line as u32, s_char as u32),
+                        Position::new(e_line as u32, e_char as u32),
+                    )
+                }
+                None => {
+                    let (line, _) = state.buffer.position_to_lsp_position(cursor.position);
+                    LspRange::new(
+                        Position::new(line as u32, 0),
+                        Position::new(line as u32 + 1, 0),
+                    )
+                }
+            };
+            FormattingRequest::Range(range)
+        } else {
+            FormattingRequest::Document
+        };
+
+        if !self.send_format_request(request, FormatTrigger::Manual) {
+            self.set_status_message("Formatting not available for this buffer".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Save the active buffer, formatting it first if the language has `format_on_save`
+    ///
+    /// When a formatting request is sent, the save happens once the language server
+    /// responds (or after `FORMAT_ON_SAVE_TIMEOUT`), so the written file includes the edits.
+    /// Servers that asked for `willSaveWaitUntil` get to edit the buffer the same way.
+    pub fn format_and_save(&mut self) -> io::Result<()> {
+        let format_on_save = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|meta| meta.file_path())
+            .and_then(|path| detect_language(path, &self.config.languages))
+            .and_then(|language| self.config.languages.get(&language))
+            .is_some_and(|lang_config| lang_config.format_on_save);
+
+        if format_on_save
+            && self.send_format_request(FormattingRequest::Document, FormatTrigger::Save)
+        {
+            return Ok(());
+        }
+
+        self.save_after_will_save(self.active_buffer())
+    }
+
+    /// Request on-type formatting after `c` was inserted, if the language has
+    /// `format_on_type` and the server lists `c` as a trigger character
+    fn maybe_request_on_type_formatting(&mut self, c: char) {
+        let Some(path) = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|meta| meta.file_path())
+        else {
+            return;
+        };
+        let Some(language) = detect_language(path, &self.config.languages) else {
+            return;
+        };
+        let enabled = self
+            .config
+            .languages
+            .get(&language)
+            .is_some_and(|lang_config| lang_config.format_on_type);
+        if !enabled {
+            return;
+        }
+
+        let is_trigger = self
+            .lsp
+            .as_mut()
+            .filter(|lsp| lsp.is_server_ready(&language))
+            .and_then(|lsp| lsp.handle_for_feature(&language, LspFeature::Formatting))
+            .and_then(|handle| handle.capabilities())
+            .and_then(|caps| caps.document_on_type_formatting_provider)
+            .is_some_and(|provider| {
+                let ch = c.to_string();
+                provider.first_trigger_character == ch
+                    || provider
+                        .more_trigger_character
+                        .is_some_and(|more| more.contains(&ch))
+            });
+
+        if is_trigger {
+            let state = self.active_state();
+            let (line, character) = state
+                .buffer
+                .position_to_lsp_position(state.cursors.primary().position);
+            let request = FormattingRequest::OnType {
+                position: Position::new(line as u32, character as u32),
+                ch: c.to_string(),
+            };
+            self.send_format_request(request, FormatTrigger::OnType);
+        }
+    }
+
+    /// Send a formatting request for the active buffer
+    ///
+    /// Returns false if no request could be sent.
+    fn send_format_request(&mut self, request: FormattingRequest, trigger: FormatTrigger) -> bool {
+        // Never replace a pending format-on-save: its response triggers the save
+        if trigger != FormatTrigger::Save
+            && self
+                .pending_format_request
+                .as_ref()
+                .is_some_and(|pending| pending.trigger.saves())
+        {
+            return false;
+        }
+
+        let buffer_id = self.active_buffer();
+        let Some(metadata) = self.buffer_metadata.get(&buffer_id) else {
+            return false;
+        };
+        if !metadata.lsp_enabled {
+            return false;
+        }
+        let (Some(uri), Some(path)) = (metadata.file_uri().cloned(), metadata.file_path()) else {
+            return false;
+        };
+        let Some(language) = detect_language(path, &self.config.languages) else {
+            return false;
+        };
+
+        let log_position = self
+            .event_logs
+            .get(&buffer_id)
+            .and_then(|log| log.position());
+        let tab_size = self.config.editor.tab_size as u32;
+        let request_id = self.next_lsp_request_id;
+
+        let Some(lsp) = self.lsp.as_mut() else {
+            return false;
+        };
+        // Saving must not wait on a server that is still starting up
+        if trigger == FormatTrigger::Save && !lsp.is_server_ready(&language) {
+            return false;
+        }
+        let Some(handle) = lsp.handle_for_feature(&language, LspFeature::Formatting) else {
+            return false;
+        };
+
+        if let Err(e) = handle.formatting(request_id, uri, request, tab_size, true) {
+            tracing::warn!("Failed to send formatting request: {}", e);
+            return false;
+        }
+
+        self.next_lsp_request_id += 1;
+        self.pending_format_request = Some(PendingFormatRequest {
+            request_id,
+            buffer_id,
+            log_position,
+            trigger,
+            started: std::time::Instant::now(),
+        });
+        if trigger != FormatTrigger::OnType {
+            self.lsp_status = "LSP: formatting...".to_string();
+        }
+        tracing::info!("Requested formatting ({:?}) for {}", trigger, language);
+
+        true
+    }
+
+    /// Handle a formatting response from LSP
+    pub fn handle_formatting_response(
+        &mut self,
+        request_id: u64,
+        result: Result<Vec<lsp_types::TextEdit>, String>,
+    ) -> io::Result<()> {
+        let pending = match self.pending_format_request.take() {
+            Some(pending) if pending.request_id == request_id => pending,
+            other => {
+                tracing::debug!("Ignoring stale formatting response: {}", request_id);
+                self.pending_format_request = other;
+                return Ok(());
+            }
+        };
+
+        if pending.trigger != FormatTrigger::OnType {
+            self.lsp_status.clear();
+        }
+
+        // Edits are computed against the text at request time; drop them if the
+        // buffer was edited (or undone past that point) while waiting
+        let buffer_changed = match self.event_logs.get(&pending.buffer_id) {
+            Some(log) => log.changed_since(pending.log_position),
+            None => true,
+        };
+
+        let outcome = match result {
+            Ok(_) if buffer_changed => Err("buffer changed while formatting".to_string()),
+            Ok(edits) => Ok(self.apply_lsp_format_edits(pending.buffer_id, edits)?),
+            Err(e) => Err(e),
+        };
+
+        match pending.trigger {
+            FormatTrigger::Manual => match outcome {
+                Ok(0) => self.set_status_message("Already formatted".to_string()),
+                Ok(changes) => self.set_status_message(format!("Formatted ({} edits)", changes)),
+                Err(e) => self.set_status_message(format!("Formatting failed: {}", e)),
+            },
+            FormatTrigger::Save => {
+                self.save_after_will_save(pending.buffer_id)?;
+                if let Err(e) = outcome {
+                    self.set_status_message(format!("Saved without formatting: {}", e));
+                }
+            }
+            FormatTrigger::WillSave => {
+                self.save_formatted_buffer(pending.buffer_id)?;
+                if let Err(e) = outcome {
+                    self.set_status_message(format!("Saved without language server edits: {}", e));
+                }
+            }
+            FormatTrigger::OnType => {
+                if let Err(e) = outcome {
+                    tracing::debug!("On-type formatting skipped: {}", e);
                 }
-                lines.push(doc_text);
             }
         }
 
-        // Create a popup with the signature help
-        use crate::view::popup::{Popup, PopupPosition};
-        use ratatui::style::Style;
+        Ok(())
+    }
 
-        let mut popup = Popup::text(lines, &self.theme);
-        popup.title = Some("Signature Help".to_string());
-        popup.position = PopupPosition::BelowCursor;
-        popup.width = 60;
-        popup.max_height = 10;
-        popup.border_style = Style::default().fg(self.theme.popup_border_fg);
-        popup.background_style = Style::default().bg(self.theme.popup_bg);
+    /// Save a buffer after its format-on-save or `willSaveWaitUntil` request completed
+    fn save_formatted_buffer(&mut self, buffer_id: BufferId) -> io::Result<()> {
+        if !self.buffers.contains_key(&buffer_id) {
+            return Ok(());
+        }
 
-        // Show the popup
-        if let Some(state) = self.buffers.get_mut(&self.active_buffer()) {
-            state.popups.show(popup);
-            tracing::info!(
-                "Showing signature help popup for {} signatures",
-                signature_help.signatures.len()
+        // save() works on the active buffer; the user may have switched while waiting
+        let original_active = self.active_buffer();
+        if original_active == buffer_id {
+            return self.save();
+        }
+        self.split_manager.set_active_buffer_id(buffer_id);
+        let result = self.save();
+        self.split_manager.set_active_buffer_id(original_active);
+        result
+    }
+
+    /// Give up on a format-on-save or `willSaveWaitUntil` request that the server hasn't
+    /// answered in time and save the buffer without its edits. Returns true if it timed out.
+    fn check_format_on_save_timeout(&mut self) -> bool {
+        let timed_out = self.pending_format_request.as_ref().is_some_and(|pending| {
+            pending.trigger.saves() && pending.started.elapsed() >= FORMAT_ON_SAVE_TIMEOUT
+        });
+        if !timed_out {
+            return false;
+        }
+
+        if let Some(pending) = self.pending_format_request.take() {
+            tracing::warn!(
+                "{:?} request {} timed out, saving without its edits",
+                pending.trigger,
+                pending.request_id
             );
+            self.lsp_status.clear();
+            let skipped = match pending.trigger {
+                FormatTrigger::WillSave => "language server edits",
+                _ => "formatting",
+            };
+            match self.save_formatted_buffer(pending.buffer_id) {
+                Ok(()) => self.set_status_message(format!(
+                    "Saved without {}: language server timed out",
+                    skipped
+                )),
+                Err(e) => self.set_status_message(format!("Failed to save: {}", e)),
+            }
         }
+        true
     }
 
-    /// Request LSP code actions at current cursor position
-    fn request_code_actions(&mut self) -> io::Result<()> {
-        // Get the current buffer and cursor position
-        let state = self.active_state();
-        let cursor_pos = state.cursors.primary().position;
+    /// Apply formatting edits to a buffer as a single undoable batch
+    ///
+    /// Unlike rename edits, formatting edits never move cursors to the edit
+    /// location: cursors only shift to stay on the same text. Returns the
+    /// number of edits that changed the buffer.
+    fn apply_lsp_format_edits(
+        &mut self,
+        buffer_id: BufferId,
+        edits: Vec<lsp_types::TextEdit>,
+    ) -> io::Result<usize> {
+        let state = self
+            .buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Buffer not found"))?;
 
-        // Convert byte position to LSP position (line, UTF-16 code units)
-        let (line, character) = state.buffer.position_to_lsp_position(cursor_pos);
+        // Convert every edit to byte offsets against the unmodified text first.
+        // Keep the original index so inserts at the same position stay in order.
+        let mut byte_edits: Vec<(usize, usize, usize, String)> = edits
+            .into_iter()
+            .enumerate()
+            .map(|(index, edit)| {
+                let start = state.buffer.lsp_position_to_byte(
+                    edit.range.start.line as usize,
+                    edit.range.start.character as usize,
+                );
+                let end = state.buffer.lsp_position_to_byte(
+                    edit.range.end.line as usize,
+                    edit.range.end.character as usize,
+                );
+                (start, end.max(start), index, edit.new_text)
+            })
+            .collect();
 
-        // Get selection range (if any) or use cursor position
-        let (start_line, start_char, end_line, end_char) =
-            if let Some(range) = state.cursors.primary().selection_range() {
-                let (s_line, s_char) = state.buffer.position_to_lsp_position(range.start);
-                let (e_line, e_char) = state.buffer.position_to_lsp_position(range.end);
-                (s_line as u32, s_char as u32, e_line as u32, e_char as u32)
-            } else {
-                (line as u32, character as u32, line as u32, character as u32)
-            };
+        // Apply back to front so earlier offsets stay valid
+        byte_edits.sort_by_key(|(start, _, index, _)| std::cmp::Reverse((*start, *index)));
 
-        // Get diagnostics at cursor position for context
-        // TODO: Implement diagnostic retrieval when needed
-        let diagnostics = Vec::new();
+        let mut events = Vec::new();
+        let mut changes = 0;
+        for (start, end, _, new_text) in byte_edits {
+            let old_text = state.get_text_range(start, end);
+            let (prefix, suffix, new_text) = trim_unchanged_edit(&old_text, &new_text);
+            let start = start + prefix;
+            let end = end - suffix;
+            if start == end && new_text.is_empty() {
+                continue;
+            }
 
-        // Get the current file URI and path
-        let metadata = self.buffer_metadata.get(&self.active_buffer());
-        let (uri, file_path) = if let Some(meta) = metadata {
-            (meta.file_uri(), meta.file_path())
-        } else {
-            (None, None)
+            // UNDO_SENTINEL: shift cursors with the text instead of moving one to the edit
+            if start < end {
+                events.push(Event::Delete {
+                    range: start..end,
+                    deleted_text: old_text[prefix..old_text.len() - suffix].to_string(),
+                    cursor_id: CursorId::UNDO_SENTINEL,
+                });
+            }
+            if !new_text.is_empty() {
+                events.push(Event::Insert {
+                    position: start,
+                    text: new_text.to_string(),
+                    cursor_id: CursorId::UNDO_SENTINEL,
+                });
+            }
+            changes += 1;
+        }
+
+        if events.is_empty() {
+            return Ok(0);
+        }
+
+        let batch = Event::Batch {
+            events,
+            description: "Format Document".to_string(),
         };
 
-        if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
-                // Get LSP handle
-                if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
-                        let request_id = self.next_lsp_request_id;
-                        self.next_lsp_request_id += 1;
-                        self.pending_code_actions_request = Some(request_id);
-                        self.lsp_status = "LSP: code actions...".to_string();
+        if buffer_id == self.active_buffer() {
+            self.active_event_log_mut().append(batch.clone());
+            self.apply_event_to_active_buffer(&batch);
+        } else {
+            if let Some(event_log) = self.event_logs.get_mut(&buffer_id) {
+                event_log.append(batch.clone());
+            }
+            // Positions must be converted before the buffer changes (see apply_rename_batch_to_buffer)
+            let original_active = self.active_buffer();
+            self.split_manager.set_active_buffer_id(buffer_id);
+            let lsp_changes = self.collect_lsp_changes(&batch);
+            self.split_manager.set_active_buffer_id(original_active);
 
-                        let _ = handle.code_actions(
-                            request_id,
-                            uri.clone(),
-                            start_line,
-                            start_char,
-                            end_line,
-                            end_char,
-                            diagnostics,
-                        );
-                        tracing::info!(
-                            "Requested code actions at {}:{}:{}-{}:{} (byte_pos={})",
-                            uri.as_str(),
-                            start_line,
-                            start_char,
-                            end_line,
-                            end_char,
-                            cursor_pos
-                        );
-                    }
-                }
+            if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                state.apply(&batch);
             }
+            self.invalidate_layouts_for_buffer(buffer_id);
+            self.send_lsp_changes_for_buffer(buffer_id, lsp_changes);
         }
 
-        Ok(())
-    }
-
-    /// Handle code actions response from LSP
-    fn handle_code_actions_response(
-        &mut self,
-        request_id: u64,
-        actions: Vec<lsp_types::CodeActionOrCommand>,
-    ) {
-        // Check if this response is for the current pending request
-        if self.pending_code_actions_request != Some(request_id) {
-            tracing::debug!("Ignoring stale code actions response: {}", request_id);
-            return;
+        // Sentinel edits don't update the cached primary cursor line
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            let position = state.cursors.primary().position;
+            if let Some(pos) = state.buffer.offset_to_position(position) {
+                state.primary_cursor_line_number =
+                    crate::model::buffer::LineNumber::Absolute(pos.line);
+            }
         }
 
-        self.pending_code_actions_request = None;
-        self.lsp_status.clear();
+        Ok(changes)
+    }
 
-        if actions.is_empty() {
-            self.set_status_message("No code actions available".to_string());
+    /// Request the symbol outline of a buffer
+    ///
+    /// Asks the language server when one is ready and provides document symbols;
+    /// otherwise extracts symbols with tree-sitter. The result is delivered to
+    /// plugins through the `document_symbols` hook.
+    pub(crate) fn request_document_symbols(&mut self, buffer_id: BufferId) {
+        if let Some(request_id) = self.send_document_symbols_request(buffer_id) {
+            self.pending_document_symbols_request = Some((request_id, buffer_id));
             return;
         }
+        self.emit_tree_sitter_outline(buffer_id);
+    }
 
-        // Build the display content
-        let mut lines: Vec<String> = Vec::new();
-        lines.push(format!("Code Actions ({}):", actions.len()));
-        lines.push(String::new());
-
-        for (i, action) in actions.iter().enumerate() {
-            let title = match action {
-                lsp_types::CodeActionOrCommand::Command(cmd) => &cmd.title,
-                lsp_types::CodeActionOrCommand::CodeAction(ca) => &ca.title,
-            };
-            lines.push(format!("  {}. {}", i + 1, title));
+    /// Send a documentSymbol request, returning its ID if one was sent
+    fn send_document_symbols_request(&mut self, buffer_id: BufferId) -> Option<u64> {
+        let metadata = self.buffer_metadata.get(&buffer_id)?;
+        if !metadata.lsp_enabled {
+            return None;
+        }
+        let uri = metadata.file_uri()?.clone();
+        let language = detect_language(metadata.file_path()?, &self.config.languages)?;
+        let request_id = self.next_lsp_request_id;
+
+        let lsp = self.lsp.as_mut()?;
+        // Don't start a server just to build the outline
+        if !lsp.is_server_ready(&language) {
+            return None;
+        }
+        let handle = lsp.handle_for_feature(&language, LspFeature::DocumentSymbols)?;
+        let supported = handle.capabilities().is_some_and(|caps| {
+            !matches!(
+                caps.document_symbol_provider,
+                None | Some(lsp_types::OneOf::Left(false))
+            )
+        });
+        if !supported {
+            return None;
+        }
+        if let Err(e) = handle.document_symbols(request_id, uri) {
+            tracing::warn!("Failed to send document symbols request: {}", e);
+            return None;
         }
 
-        lines.push(String::new());
-        lines.push("Press number to select, Esc to cancel".to_string());
+        self.next_lsp_request_id += 1;
+        Some(request_id)
+    }
 
-        // Create a popup with the code actions
-        use crate::view::popup::{Popup, PopupPosition};
-        use ratatui::style::Style;
+    /// Handle a document symbols response from LSP
+    fn handle_document_symbols_response(
+        &mut self,
+        request_id: u64,
+        result: Result<lsp_types::DocumentSymbolResponse, String>,
+    ) {
+        let buffer_id = match self.pending_document_symbols_request {
+            Some((pending_id, buffer_id)) if pending_id == request_id => buffer_id,
+            _ => {
+                tracing::debug!("Ignoring stale document symbols response: {}", request_id);
+                return;
+            }
+        };
+        self.pending_document_symbols_request = None;
 
-        let mut popup = Popup::text(lines, &self.theme);
-        popup.title = Some("Code Actions".to_string());
-        popup.position = PopupPosition::BelowCursor;
-        popup.width = 60;
-        popup.max_height = 15;
-        popup.border_style = Style::default().fg(self.theme.popup_border_fg);
-        popup.background_style = Style::default().bg(self.theme.popup_bg);
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("Document symbols request failed, using tree-sitter: {}", e);
+                self.emit_tree_sitter_outline(buffer_id);
+                return;
+            }
+        };
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return;
+        };
+        let symbols = flatten_document_symbols(response, |pos| {
+            state
+                .buffer
+                .lsp_position_to_byte(pos.line as usize, pos.character as usize)
+        });
+        self.emit_document_symbols(buffer_id, "lsp", symbols);
+    }
 
-        // Show the popup
-        if let Some(state) = self.buffers.get_mut(&self.active_buffer()) {
-            state.popups.show(popup);
-            tracing::info!("Showing code actions popup with {} actions", actions.len());
-        }
+    /// Build the outline with tree-sitter and deliver it to plugins
+    fn emit_tree_sitter_outline(&mut self, buffer_id: BufferId) {
+        let symbols = self
+            .buffers
+            .get(&buffer_id)
+            .and_then(|state| {
+                let language = *state.highlighter.language()?;
+                let text = state.buffer.to_string()?;
+                Some(self.outline_extractor.symbols(&text, &language))
+            })
+            .unwrap_or_default();
+        self.emit_document_symbols(buffer_id, "tree-sitter", symbols);
+    }
 
-        // Note: Executing code actions would require storing the actions and handling
-        // key presses to select and apply them. This is left for future enhancement.
-        self.set_status_message(format!(
-            "Found {} code action(s) - selection not yet implemented",
-            actions.len()
-        ));
+    fn emit_document_symbols(
+        &mut self,
+        buffer_id: BufferId,
+        source: &str,
+        symbols: Vec<crate::primitives::outline::OutlineSymbol>,
+    ) {
+        tracing::debug!(
+            "Outline for buffer {:?}: {} symbols from {}",
+            buffer_id,
+            symbols.len(),
+            source
+        );
+        self.plugin_manager.run_hook(
+            "document_symbols",
+            crate::services::plugins::hooks::HookArgs::DocumentSymbols {
+                buffer_id,
+                source: source.to_string(),
+                symbols,
+            },
+        );
     }
 
     /// Handle find references response from LSP
@@ -6858,9 +7588,16 @@ impl Editor {
         self.pending_references_request = None;
         self.lsp_status.clear();
 
+        let symbol = std::mem::take(&mut self.pending_references_symbol);
+        self.show_references(symbol, locations);
+        Ok(())
+    }
+
+    /// Hand reference locations to plugins for display
+    pub(super) fn show_references(&mut self, symbol: String, locations: Vec<lsp_types::Location>) {
         if locations.is_empty() {
             self.set_status_message("No references found".to_string());
-            return Ok(());
+            return;
         }
 
         // Convert locations to hook args format
@@ -6884,7 +7621,6 @@ impl Editor {
             .collect();
 
         let count = lsp_locations.len();
-        let symbol = std::mem::take(&mut self.pending_references_symbol);
         self.set_status_message(format!("Found {} reference(s) for '{}'", count, symbol));
 
         // Fire the lsp_references hook so plugins can display the results
@@ -6901,8 +7637,6 @@ impl Editor {
             count,
             symbol
         );
-
-        Ok(())
     }
 
     /// Apply LSP text edits to a buffer and return the number of changes made.
@@ -7013,90 +7747,7 @@ impl Editor {
 
         match result {
             Ok(workspace_edit) => {
-                // Log the full workspace edit for debugging
-                tracing::debug!(
-                    "Received WorkspaceEdit: changes={:?}, document_changes={:?}",
-                    workspace_edit.changes.as_ref().map(|c| c.len()),
-                    workspace_edit.document_changes.as_ref().map(|dc| match dc {
-                        lsp_types::DocumentChanges::Edits(e) => format!("{} edits", e.len()),
-                        lsp_types::DocumentChanges::Operations(o) =>
-                            format!("{} operations", o.len()),
-                    })
-                );
-
-                // Apply the workspace edit
-                let mut total_changes = 0;
-
-                // Handle changes (map of URI -> Vec<TextEdit>)
-                if let Some(changes) = workspace_edit.changes {
-                    for (uri, edits) in changes {
-                        if let Ok(path) = uri_to_path(&uri) {
-                            let buffer_id = self.open_file(&path)?;
-                            total_changes += self.apply_lsp_text_edits(buffer_id, edits)?;
-                        }
-                    }
-                }
-
-                // Handle document_changes (TextDocumentEdit[])
-                // This is what rust-analyzer sends instead of changes
-                if let Some(document_changes) = workspace_edit.document_changes {
-                    use lsp_types::DocumentChanges;
-
-                    let text_edits = match document_changes {
-                        DocumentChanges::Edits(edits) => edits,
-                        DocumentChanges::Operations(ops) => {
-                            // Extract TextDocumentEdit from operations
-                            ops.into_iter()
-                                .filter_map(|op| {
-                                    if let lsp_types::DocumentChangeOperation::Edit(edit) = op {
-                                        Some(edit)
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect()
-                        }
-                    };
-
-                    for text_doc_edit in text_edits {
-                        let uri = text_doc_edit.text_document.uri;
-
-                        if let Ok(path) = uri_to_path(&uri) {
-                            let buffer_id = self.open_file(&path)?;
-
-                            // Extract TextEdit from OneOf<TextEdit, AnnotatedTextEdit>
-                            let edits: Vec<lsp_types::TextEdit> = text_doc_edit
-                                .edits
-                                .into_iter()
-                                .map(|one_of| match one_of {
-                                    lsp_types::OneOf::Left(text_edit) => text_edit,
-                                    lsp_types::OneOf::Right(annotated) => annotated.text_edit,
-                                })
-                                .collect();
-
-                            // Log the edits for debugging
-                            tracing::info!(
-                                "Applying {} edits from rust-analyzer for {:?}:",
-                                edits.len(),
-                                path
-                            );
-                            for (i, edit) in edits.iter().enumerate() {
-                                tracing::info!(
-                                    "  Edit {}: line {}:{}-{}:{} -> {:?}",
-                                    i,
-                                    edit.range.start.line,
-                                    edit.range.start.character,
-                                    edit.range.end.line,
-                                    edit.range.end.character,
-                                    edit.new_text
-                                );
-                            }
-
-                            total_changes += self.apply_lsp_text_edits(buffer_id, edits)?;
-                        }
-                    }
-                }
-
+                let total_changes = self.apply_workspace_edit(workspace_edit)?;
                 self.status_message =
                     Some(format!("Renamed successfully ({} changes)", total_changes));
             }
@@ -7274,12 +7925,18 @@ impl Editor {
             uri.as_str()
         );
 
+        self.adjust_semantic_tokens_for_changes(buffer_id, &changes);
+        self.schedule_code_lens_refresh(buffer_id, code_lens::CODE_LENS_DEBOUNCE);
+
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
-                if let Err(e) = client.did_change(uri, changes) {
-                    tracing::warn!("Failed to send didChange to LSP: {}", e);
-                } else {
-                    tracing::trace!("Successfully sent batched didChange to LSP");
+            let clients = lsp.language_handles(&language);
+            if !clients.is_empty() {
+                for client in clients {
+                    if let Err(e) = client.did_change(uri.clone(), changes.clone()) {
+                        tracing::warn!("Failed to send didChange to LSP: {}", e);
+                    } else {
+                        tracing::trace!("Successfully sent batched didChange to LSP");
+                    }
                 }
             } else {
                 tracing::warn!(
@@ -7390,7 +8047,7 @@ impl Editor {
             if let Some(language) = detect_language(path, &self.config.languages) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) = lsp.handle_for_feature(&language, LspFeature::Rename) {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.lsp_status = "LSP: rename...".to_string();
@@ -8977,4 +9634,51 @@ mod tests {
             .sum();
         assert!(view_state.tab_scroll_offset <= total_width);
     }
+
+    #[test]
+    fn test_trim_unchanged_edit() {
+        assert_eq!(trim_unchanged_edit("let x=1;", "let x = 1;"), (5, 2, " = "));
+        assert_eq!(trim_unchanged_edit("same", "same"), (4, 0, ""));
+        assert_eq!(trim_unchanged_edit("a  b", "a b"), (2, 1, ""));
+        assert_eq!(trim_unchanged_edit("é=1", "é = 1"), (2, 1, " = "));
+    }
+
+    #[test]
+    fn test_lsp_format_edits_keep_cursor_and_undo_in_one_step() {
+        use crate::model::buffer::Buffer;
+
+        let config = Config::default();
+        let (dir_context, _temp) = test_dir_context();
+        let mut editor = Editor::new(config, 80, 24, dir_context).unwrap();
+
+        let initial = "fn main(){\nlet x=1;\n}\n";
+        editor.active_state_mut().buffer = Buffer::from_str(initial, 1024 * 1024);
+        // Cursor on the '1' of line 1
+        editor.active_state_mut().cursors.primary_mut().position = 17;
+
+        // Formatters commonly replace the whole document
+        let formatted = "fn main() {\n    let x = 1;\n}\n";
+        let edits = vec![lsp_types::TextEdit {
+            range: lsp_types::Range::new(
+                lsp_types::Position::new(0, 0),
+                lsp_types::Position::new(3, 0),
+            ),
+            new_text: formatted.to_string(),
+        }];
+        let buffer_id = editor.active_buffer();
+        let changes = editor.apply_lsp_format_edits(buffer_id, edits).unwrap();
+        assert_eq!(changes, 1);
+
+        let content = editor.active_state().buffer.to_string().unwrap();
+        assert_eq!(content, formatted);
+        let cursor = editor.active_state().cursors.primary().position;
+        assert_eq!(
+            &content[cursor..cursor + 1],
+            "1",
+            "Cursor should stay on '1'"
+        );
+
+        editor.handle_action(Action::Undo).unwrap();
+        assert_eq!(editor.active_state().buffer.to_string().unwrap(), initial);
+    }
 }
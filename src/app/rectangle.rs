@@ -0,0 +1,333 @@
+//! Rectangle commands
+//!
+//! The rectangle is the primary cursor's block selection or, as in Emacs, the
+//! columns between its selection anchor and the cursor. "Kill Rectangle" and
+//! "Copy Rectangle" put its lines in the clipboard and "Yank Rectangle" pastes
+//! the lines of the clipboard as a column at the cursor. The
+//! other commands insert or replace text on every line of the rectangle, or turn
+//! it into one cursor per line.
+
+use super::Editor;
+use crate::input::actions::clear_block_selection_if_active;
+use crate::model::event::{CursorId, Event};
+use crate::primitives::rectangle::{self, Edit, Rectangle};
+use crate::view::prompt::PromptType;
+
+/// Line length assumed when reading lines of large files
+const LINE_LENGTH_ESTIMATE: usize = 80;
+
+/// The lines a rectangle command works on, read from the buffer
+struct RectangleText {
+    /// The lines, from the start of the first one
+    text: String,
+    /// Buffer offset of the text
+    offset: usize,
+}
+
+impl RectangleText {
+    /// Edits of the text as edits of the buffer
+    fn global(&self, edits: Vec<Edit>) -> Vec<Edit> {
+        edits
+            .into_iter()
+            .map(|(range, text)| (range.start + self.offset..range.end + self.offset, text))
+            .collect()
+    }
+}
+
+impl Editor {
+    /// Column of `pos` on its line
+    fn rectangle_column(&mut self, pos: usize) -> usize {
+        let line_start = self
+            .active_state_mut()
+            .buffer
+            .line_iterator(pos, LINE_LENGTH_ESTIMATE)
+            .current_position();
+        pos - line_start
+    }
+
+    /// `count` lines starting `lines_up` lines above the one containing `pos`
+    fn rectangle_lines(&mut self, pos: usize, lines_up: usize, count: usize) -> RectangleText {
+        let buffer = &mut self.active_state_mut().buffer;
+        let mut lines = buffer.line_iterator(pos, LINE_LENGTH_ESTIMATE);
+        for _ in 0..lines_up {
+            if lines.prev().is_none() {
+                break;
+            }
+        }
+        let offset = lines.current_position();
+
+        let mut lines = buffer.line_iterator(offset, LINE_LENGTH_ESTIMATE);
+        let mut text = String::new();
+        for _ in 0..count {
+            match lines.next() {
+                Some((_, line)) => text.push_str(&line),
+                None => break,
+            }
+        }
+        RectangleText { text, offset }
+    }
+
+    /// The lines and the rectangle (with its top on the first of them) of the
+    /// primary cursor's selection
+    fn selected_rectangle(&mut self) -> Option<(RectangleText, Rectangle)> {
+        let cursor = *self.active_state().cursors.primary();
+        let Some(anchor) = cursor.anchor else {
+            self.set_status_message("No rectangle selected".to_string());
+            return None;
+        };
+        let anchor_column = match (cursor.has_block_selection(), cursor.block_anchor) {
+            (true, Some(block_anchor)) => block_anchor.column,
+            _ => self.rectangle_column(anchor),
+        };
+        let cursor_column = self.rectangle_column(cursor.position);
+
+        // Line numbers are only estimated in large files, so both corners are
+        // placed by counting the line breaks between them instead
+        let start = anchor.min(cursor.position);
+        let end = anchor.max(cursor.position);
+        let lines_between = self
+            .active_state_mut()
+            .get_text_range(start, end)
+            .matches('\n')
+            .count();
+        let (anchor_line, cursor_line) = if anchor <= cursor.position {
+            (0, lines_between)
+        } else {
+            (lines_between, 0)
+        };
+        let rect = Rectangle::new((anchor_line, anchor_column), (cursor_line, cursor_column));
+        let lines = self.rectangle_lines(start, 0, lines_between + 1);
+        Some((lines, rect))
+    }
+
+    fn check_rectangle_editable(&mut self) -> bool {
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return false;
+        }
+        true
+    }
+
+    /// Apply the edits of a rectangle command as one undo step, then put the
+    /// cursor at `column` of the line starting at `line_start` without a selection
+    ///
+    /// The edits don't touch text before `line_start`, so it stays a line start.
+    fn apply_rectangle_edits(
+        &mut self,
+        edits: Vec<Edit>,
+        description: &str,
+        line_start: usize,
+        column: usize,
+    ) {
+        let cursor_id = self.active_state().cursors.primary_id();
+        // Later edits first, so that the ranges of earlier ones stay valid
+        let mut events = Vec::new();
+        for (range, text) in edits.into_iter().rev() {
+            if !range.is_empty() {
+                let deleted_text = self
+                    .active_state_mut()
+                    .get_text_range(range.start, range.end);
+                events.push(Event::Delete {
+                    range: range.clone(),
+                    deleted_text,
+                    cursor_id,
+                });
+            }
+            if !text.is_empty() {
+                events.push(Event::Insert {
+                    position: range.start,
+                    text,
+                    cursor_id,
+                });
+            }
+        }
+        if !events.is_empty() {
+            let batch = Event::Batch {
+                events,
+                description: description.to_string(),
+            };
+            self.active_event_log_mut().append(batch.clone());
+            self.apply_event_to_active_buffer(&batch);
+        }
+
+        let line = self.rectangle_lines(line_start, 0, 1);
+        let state = self.active_state_mut();
+        clear_block_selection_if_active(state);
+        let cursor = *state.cursors.primary();
+        let event = Event::MoveCursor {
+            cursor_id,
+            old_position: cursor.position,
+            new_position: line.offset + rectangle::offset(&line.text, 0, column),
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: column,
+        };
+        self.active_event_log_mut().append(event.clone());
+        self.apply_event_to_active_buffer(&event);
+    }
+
+    /// Put the lines of the rectangle in the clipboard, deleting them with `kill`
+    pub(super) fn copy_rectangle(&mut self, kill: bool) {
+        if kill && !self.check_rectangle_editable() {
+            return;
+        }
+        let Some((text, rect)) = self.selected_rectangle() else {
+            return;
+        };
+        let pieces = rect.extract(&text.text);
+        let lines = pieces.len();
+        self.clipboard.copy_pieces(pieces);
+        if kill {
+            let edits = text.global(rect.delete(&text.text));
+            self.apply_rectangle_edits(edits, "Kill rectangle", text.offset, rect.left);
+            self.set_status_message(format!("Killed rectangle of {} lines", lines));
+        } else {
+            self.set_status_message(format!("Copied rectangle of {} lines", lines));
+        }
+    }
+
+    /// Paste the lines of the clipboard as a column starting at the cursor
+    pub(super) fn yank_rectangle(&mut self) {
+        if !self.check_rectangle_editable() {
+            return;
+        }
+        let Some(clipboard) = self.clipboard.paste() else {
+            self.set_status_message("Clipboard is empty".to_string());
+            return;
+        };
+        let pieces: Vec<String> = clipboard.lines().map(str::to_string).collect();
+        let position = self.active_state().cursors.primary().position;
+        let text = self.rectangle_lines(position, 0, pieces.len());
+        let column = position - text.offset;
+        let edits = text.global(rectangle::yank(&text.text, column, &pieces));
+        self.apply_rectangle_edits(edits, "Yank rectangle", text.offset, column);
+    }
+
+    /// Shift the text in and right of the rectangle right by its width
+    pub(super) fn open_rectangle(&mut self) {
+        if !self.check_rectangle_editable() {
+            return;
+        }
+        let Some((text, rect)) = self.selected_rectangle() else {
+            return;
+        };
+        if rect.width() == 0 {
+            self.set_status_message("The rectangle is empty".to_string());
+            return;
+        }
+        let edits = text.global(rect.open(&text.text));
+        self.apply_rectangle_edits(edits, "Open rectangle", text.offset, rect.left);
+    }
+
+    /// Prompt for the text of "Replace Rectangle" (`replace`) or "Insert in Rectangle"
+    pub(super) fn start_rectangle_text_prompt(&mut self, replace: bool) {
+        if !self.check_rectangle_editable() || self.selected_rectangle().is_none() {
+            return;
+        }
+        let message = if replace {
+            "Replace rectangle with: "
+        } else {
+            "Insert on every line: "
+        };
+        self.start_prompt(message.to_string(), PromptType::RectangleText { replace });
+    }
+
+    /// Put `input` on every line of the rectangle, replacing its text with `replace`
+    /// and at its left edge otherwise
+    pub(super) fn rectangle_insert_text(&mut self, input: &str, replace: bool) {
+        let Some((text, mut rect)) = self.selected_rectangle() else {
+            return;
+        };
+        if !replace {
+            rect.right = rect.left;
+        }
+        let edits = text.global(rect.replace(&text.text, std::iter::repeat(input.to_string())));
+        self.apply_rectangle_edits(edits, "Replace rectangle", text.offset, rect.left);
+    }
+
+    pub(super) fn start_rectangle_number_prompt(&mut self) {
+        if !self.check_rectangle_editable() || self.selected_rectangle().is_none() {
+            return;
+        }
+        self.start_prompt_with_initial_text(
+            "Number lines from (start [step]): ".to_string(),
+            PromptType::RectangleNumberLines,
+            "1".to_string(),
+        );
+    }
+
+    /// Insert numbers counting up from the start in `input` at the rectangle's left
+    /// edge
+    pub(super) fn rectangle_number_lines(&mut self, input: &str) {
+        let Some((text, mut rect)) = self.selected_rectangle() else {
+            return;
+        };
+        rect.right = rect.left;
+        let lines = rect.ranges(&text.text).len();
+        let Some(numbers) = rectangle::number_sequence(input, lines) else {
+            self.set_status_message("Expected a start number and an optional step".to_string());
+            return;
+        };
+        let edits = text.global(rect.replace(&text.text, numbers));
+        self.apply_rectangle_edits(edits, "Number lines", text.offset, rect.left);
+    }
+
+    /// Replace the rectangle and any other cursors with a cursor on each of its
+    /// lines, selecting the rectangle's text on that line
+    pub(super) fn rectangle_to_cursors(&mut self) {
+        let Some((text, rect)) = self.selected_rectangle() else {
+            return;
+        };
+        let ranges: Vec<std::ops::Range<usize>> = rect
+            .ranges(&text.text)
+            .into_iter()
+            .map(|range| range.start + text.offset..range.end + text.offset)
+            .collect();
+        let Some((first, rest)) = ranges.split_first() else {
+            return;
+        };
+        let anchor = |range: &std::ops::Range<usize>| (!range.is_empty()).then_some(range.start);
+
+        let state = self.active_state_mut();
+        clear_block_selection_if_active(state);
+        let primary_id = state.cursors.primary_id();
+        let primary = *state.cursors.primary();
+        let next_id = state.cursors.ids().iter().map(|id| id.0).max().unwrap_or(0) + 1;
+        let mut events: Vec<Event> = state
+            .cursors
+            .iter()
+            .filter(|(id, _)| *id != primary_id)
+            .map(|(id, cursor)| Event::RemoveCursor {
+                cursor_id: id,
+                position: cursor.position,
+                anchor: cursor.anchor,
+            })
+            .collect();
+        events.push(Event::MoveCursor {
+            cursor_id: primary_id,
+            old_position: primary.position,
+            new_position: first.end,
+            old_anchor: primary.anchor,
+            new_anchor: anchor(first),
+            old_sticky_column: primary.sticky_column,
+            new_sticky_column: rect.right,
+        });
+        events.extend(
+            rest.iter()
+                .zip(next_id..)
+                .map(|(range, id)| Event::AddCursor {
+                    cursor_id: CursorId(id),
+                    position: range.end,
+                    anchor: anchor(range),
+                }),
+        );
+        let batch = Event::Batch {
+            events,
+            description: "Rectangle to cursors".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+        self.set_status_message(format!("{} cursors", ranges.len()));
+    }
+}
@@ -179,6 +179,7 @@ impl Editor {
                     // This allows plugins to transform the view (e.g., soft breaks for markdown)
                     let visible_count = split_area.height as usize;
                     let is_binary = state.buffer.is_binary();
+                    let hidden = state.folds.hidden_ranges(&state.buffer);
                     let base_tokens =
                         crate::view::ui::split_rendering::SplitRenderer::build_base_tokens_for_hook(
                             &mut state.buffer,
@@ -186,6 +187,7 @@ impl Editor {
                             self.config.editor.estimated_line_length,
                             visible_count,
                             is_binary,
+                            &hidden,
                         );
                     let viewport_start = viewport_top_byte;
                     let viewport_end = base_tokens
@@ -272,8 +274,8 @@ impl Editor {
         }
 
         // Render editor content (same for both layouts)
-        let lsp_waiting = self.pending_completion_request.is_some()
-            || self.pending_goto_definition_request.is_some();
+        let lsp_waiting =
+            self.pending_completion_request.is_some() || self.pending_goto_request.is_some();
 
         // Hide the hardware cursor when menu is open, file explorer is focused, or in terminal mode
         // (the file explorer will set its own cursor position when focused)
@@ -581,6 +583,12 @@ impl Editor {
                         &theme_clone,
                         hover_target.as_ref(),
                     );
+                    if let (Some(side_popup), Some(side_area)) = (
+                        popup.side_popup.as_ref(),
+                        popup.side_popup_area(*popup_area, size),
+                    ) {
+                        side_popup.render(frame, side_area, &theme_clone);
+                    }
                 }
             }
         }
@@ -1182,45 +1190,54 @@ impl Editor {
 
         // Send didOpen to LSP
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(language) {
-                tracing::info!("Sending didOpen to newly started LSP for: {}", uri.as_str());
-                if let Err(e) = client.did_open(uri.clone(), text, file_language) {
+            lsp.add_workspace_folder(language, path);
+            let clients = lsp.language_handles(language);
+            if clients.is_empty() {
+                return;
+            }
+            tracing::info!("Sending didOpen to newly started LSP for: {}", uri.as_str());
+            for client in clients {
+                if let Err(e) = client.did_open(uri.clone(), text.clone(), file_language.clone()) {
                     tracing::warn!("Failed to send didOpen to LSP: {}", e);
-                } else {
-                    tracing::info!("Successfully sent didOpen to LSP after confirmation");
+                    return;
+                }
+            }
+            tracing::info!("Successfully sent didOpen to LSP after confirmation");
 
-                    // Request pull diagnostics
-                    let previous_result_id = self.diagnostic_result_ids.get(uri.as_str()).cloned();
+            // Request pull diagnostics
+            if let Some(client) = lsp.handle_for_feature(language, LspFeature::Diagnostics) {
+                let previous_result_id = self.diagnostic_result_ids.get(uri.as_str()).cloned();
+                let request_id = self.next_lsp_request_id;
+                self.next_lsp_request_id += 1;
+
+                if let Err(e) =
+                    client.document_diagnostic(request_id, uri.clone(), previous_result_id)
+                {
+                    tracing::debug!(
+                        "Failed to request pull diagnostics (server may not support): {}",
+                        e
+                    );
+                }
+            }
+
+            // Request inlay hints if enabled
+            if self.config.editor.enable_inlay_hints {
+                if let Some(client) = lsp.handle_for_feature(language, LspFeature::InlayHints) {
                     let request_id = self.next_lsp_request_id;
                     self.next_lsp_request_id += 1;
+                    self.pending_inlay_hints_request = Some(request_id);
+
+                    let last_line = line_count.saturating_sub(1) as u32;
+                    let last_char = 10000u32;
 
                     if let Err(e) =
-                        client.document_diagnostic(request_id, uri.clone(), previous_result_id)
+                        client.inlay_hints(request_id, uri.clone(), 0, 0, last_line, last_char)
                     {
                         tracing::debug!(
-                            "Failed to request pull diagnostics (server may not support): {}",
+                            "Failed to request inlay hints (server may not support): {}",
                             e
                         );
-                    }
-
-                    // Request inlay hints if enabled
-                    if self.config.editor.enable_inlay_hints {
-                        let request_id = self.next_lsp_request_id;
-                        self.next_lsp_request_id += 1;
-                        self.pending_inlay_hints_request = Some(request_id);
-
-                        let last_line = line_count.saturating_sub(1) as u32;
-                        let last_char = 10000u32;
-
-                        if let Err(e) =
-                            client.inlay_hints(request_id, uri.clone(), 0, 0, last_line, last_char)
-                        {
-                            tracing::debug!(
-                                "Failed to request inlay hints (server may not support): {}",
-                                e
-                            );
-                            self.pending_inlay_hints_request = None;
-                        }
+                        self.pending_inlay_hints_request = None;
                     }
                 }
             }
@@ -1246,7 +1263,7 @@ impl Editor {
 
         let result = {
             let lsp = self.lsp.as_mut()?;
-            lsp.try_spawn(language)
+            lsp.try_spawn(language, None)
         };
 
         match result {
@@ -1348,17 +1365,31 @@ impl Editor {
             uri.as_str()
         );
 
+        self.adjust_semantic_tokens_for_changes(self.active_buffer(), &changes);
+        self.schedule_code_lens_refresh(self.active_buffer(), super::code_lens::CODE_LENS_DEBOUNCE);
+
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
-                // Send all changes in a single didChange notification
+            let clients = lsp.language_handles(&language);
+            if !clients.is_empty() {
+                // Send all changes in a single didChange notification to every server
                 // This is much more efficient for batch operations like LSP rename
-                if let Err(e) = client.did_change(uri.clone(), changes) {
-                    tracing::warn!("Failed to send didChange to LSP: {}", e);
-                } else {
+                let mut sent = true;
+                for client in clients {
+                    if let Err(e) = client.did_change(uri.clone(), changes.clone()) {
+                        tracing::warn!("Failed to send didChange to LSP: {}", e);
+                        sent = false;
+                    }
+                }
+                if sent {
                     tracing::info!("Successfully sent batched didChange to LSP");
+                }
 
-                    // Request pull diagnostics after the change
-                    // TODO: Consider debouncing this to avoid excessive requests during rapid typing
+                // Request pull diagnostics after the change
+                // TODO: Consider debouncing this to avoid excessive requests during rapid typing
+                if let Some(client) = lsp
+                    .handle_for_feature(&language, LspFeature::Diagnostics)
+                    .filter(|_| sent)
+                {
                     let previous_result_id = self.diagnostic_result_ids.get(uri.as_str()).cloned();
                     let request_id = self.next_lsp_request_id;
                     self.next_lsp_request_id += 1;
@@ -1591,12 +1622,15 @@ impl Editor {
         );
 
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
+            let clients = lsp.language_handles(&language);
+            if !clients.is_empty() {
                 // Send didSave with the full text content
-                if let Err(e) = client.did_save(uri, Some(full_text)) {
-                    tracing::warn!("Failed to send didSave to LSP: {}", e);
-                } else {
-                    tracing::info!("Successfully sent didSave to LSP");
+                for client in clients {
+                    if let Err(e) = client.did_save(uri.clone(), Some(full_text.clone())) {
+                        tracing::warn!("Failed to send didSave to LSP: {}", e);
+                    } else {
+                        tracing::info!("Successfully sent didSave to LSP");
+                    }
                 }
             } else {
                 tracing::warn!(
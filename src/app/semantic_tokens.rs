@@ -0,0 +1,253 @@
+//! LSP semantic tokens: requesting, refreshing and storing tokens per buffer
+//!
+//! Tokens are requested with `full/delta` when the server supports it, `full`
+//! otherwise, and `range` (for the visible lines) as a last resort. Edits shift the
+//! stored tokens immediately and schedule a debounced refresh.
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use lsp_types::{Position, SemanticTokensFullDeltaResult, TextDocumentContentChangeEvent};
+
+use super::Editor;
+use crate::model::event::BufferId;
+use crate::primitives::semantic_tokens::semantic_tokens_capabilities;
+use crate::services::lsp::async_handler::SemanticTokensRequest;
+use crate::services::lsp::client::LspFeature;
+use crate::services::lsp::manager::detect_language;
+
+/// How long to wait after the last edit before re-requesting tokens
+pub(super) const SEMANTIC_TOKENS_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Extra lines requested above and below the viewport in range mode
+const RANGE_MARGIN_LINES: u32 = 50;
+
+impl Editor {
+    /// Request fresh tokens for a buffer once `delay` has passed
+    pub(super) fn schedule_semantic_tokens_refresh(
+        &mut self,
+        buffer_id: BufferId,
+        delay: Duration,
+    ) {
+        if !self.config.editor.enable_semantic_tokens {
+            return;
+        }
+        self.semantic_tokens_refresh
+            .insert(buffer_id, Instant::now() + delay);
+    }
+
+    /// Schedule an immediate refresh for every open buffer of a language
+    pub(super) fn schedule_semantic_tokens_for_language(&mut self, language: &str) {
+        for buffer_id in self.buffer_ids_for_language(language) {
+            self.schedule_semantic_tokens_refresh(buffer_id, Duration::ZERO);
+        }
+    }
+
+    /// Shift stored tokens for edits sent to the server and schedule a refresh
+    pub(super) fn adjust_semantic_tokens_for_changes(
+        &mut self,
+        buffer_id: BufferId,
+        changes: &[TextDocumentContentChangeEvent],
+    ) {
+        if !self.config.editor.enable_semantic_tokens {
+            return;
+        }
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            for change in changes {
+                match change.range {
+                    Some(range) => {
+                        let new_end_line =
+                            range.start.line + change.text.matches('\n').count() as u32;
+                        state.semantic_tokens.adjust_for_edit(
+                            range.start.line,
+                            range.end.line,
+                            new_end_line,
+                        );
+                    }
+                    None => state.semantic_tokens.adjust_for_edit(0, u32::MAX, 0),
+                }
+            }
+        }
+        self.schedule_semantic_tokens_refresh(buffer_id, SEMANTIC_TOKENS_DEBOUNCE);
+    }
+
+    /// Send token requests that are due
+    pub(super) fn check_semantic_tokens_refresh(&mut self) {
+        if !self.config.editor.enable_semantic_tokens {
+            return;
+        }
+
+        // In range mode, follow the viewport of the active buffer
+        let active = self.active_buffer();
+        if !self.semantic_tokens_refresh.contains_key(&active) {
+            let visible = self.visible_lines();
+            let needs_range = self.buffers.get(&active).is_some_and(|state| {
+                state
+                    .semantic_tokens
+                    .covered_lines()
+                    .is_some_and(|covered| {
+                        visible.start < covered.start || visible.end > covered.end
+                    })
+            });
+            if needs_range {
+                self.semantic_tokens_refresh.insert(active, Instant::now());
+            }
+        }
+
+        let now = Instant::now();
+        let due: Vec<BufferId> = self
+            .semantic_tokens_refresh
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(buffer_id, _)| *buffer_id)
+            .collect();
+
+        for buffer_id in due {
+            // Wait for the in-flight request; its response may already be outdated
+            if self.has_pending_semantic_tokens_request(buffer_id) {
+                continue;
+            }
+            // A server that isn't ready yet schedules a refresh once it initializes
+            self.semantic_tokens_refresh.remove(&buffer_id);
+            self.request_semantic_tokens(buffer_id);
+        }
+    }
+
+    fn has_pending_semantic_tokens_request(&self, buffer_id: BufferId) -> bool {
+        self.pending_semantic_tokens_requests
+            .values()
+            .any(|(pending, _)| *pending == buffer_id)
+    }
+
+    /// Lines of the active buffer shown in the active split
+    pub(super) fn visible_lines(&self) -> Range<u32> {
+        let viewport = self.active_viewport();
+        let top = self
+            .active_state()
+            .buffer
+            .get_line_number(viewport.top_byte) as u32;
+        top..top + u32::from(viewport.height)
+    }
+
+    /// Send a semantic tokens request for a buffer, if its server is ready and supports it
+    fn request_semantic_tokens(&mut self, buffer_id: BufferId) -> Option<()> {
+        if !self.buffers.contains_key(&buffer_id) {
+            return None;
+        }
+        let metadata = self.buffer_metadata.get(&buffer_id)?;
+        if !metadata.lsp_enabled {
+            return None;
+        }
+        let uri = metadata.file_uri()?.clone();
+        let language = detect_language(metadata.file_path()?, &self.config.languages)?;
+
+        let range_lines = if buffer_id == self.active_buffer() {
+            let visible = self.visible_lines();
+            visible.start.saturating_sub(RANGE_MARGIN_LINES)..visible.end + RANGE_MARGIN_LINES
+        } else {
+            0..2 * RANGE_MARGIN_LINES
+        };
+        let previous_result_id = self
+            .buffers
+            .get(&buffer_id)
+            .and_then(|state| state.semantic_tokens.result_id().map(String::from));
+
+        let lsp = self.lsp.as_mut()?;
+        // Don't start a server just for highlighting
+        if !lsp.is_server_ready(&language) {
+            return None;
+        }
+        let handle = lsp.handle_for_feature(&language, LspFeature::SemanticTokens)?;
+        let (_, support) = semantic_tokens_capabilities(&handle.capabilities()?)?;
+
+        let (request, lines) = match previous_result_id {
+            Some(previous_result_id) if support.delta => (
+                SemanticTokensRequest::FullDelta { previous_result_id },
+                None,
+            ),
+            _ if support.full => (SemanticTokensRequest::Full, None),
+            _ if support.range => {
+                let range = lsp_types::Range::new(
+                    Position::new(range_lines.start, 0),
+                    Position::new(range_lines.end, 0),
+                );
+                (SemanticTokensRequest::Range(range), Some(range_lines))
+            }
+            _ => return None,
+        };
+
+        let request_id = self.next_lsp_request_id;
+        if let Err(e) = handle.semantic_tokens(request_id, uri, request) {
+            tracing::warn!("Failed to send semantic tokens request: {}", e);
+            return None;
+        }
+        self.next_lsp_request_id += 1;
+        self.pending_semantic_tokens_requests
+            .insert(request_id, (buffer_id, lines));
+        Some(())
+    }
+
+    /// Store a semantic tokens response in its buffer
+    pub(super) fn handle_semantic_tokens_response(
+        &mut self,
+        request_id: u64,
+        result: Result<SemanticTokensFullDeltaResult, String>,
+    ) {
+        let Some((buffer_id, lines)) = self.pending_semantic_tokens_requests.remove(&request_id)
+        else {
+            tracing::debug!("Ignoring stale semantic tokens response: {}", request_id);
+            return;
+        };
+        if !self.config.editor.enable_semantic_tokens {
+            return;
+        }
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::debug!("Semantic tokens request failed: {}", e);
+                return;
+            }
+        };
+
+        let legend = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|metadata| detect_language(metadata.file_path()?, &self.config.languages))
+            .and_then(|language| {
+                self.lsp
+                    .as_mut()?
+                    .handle_for_feature(&language, LspFeature::SemanticTokens)?
+                    .capabilities()
+            })
+            .as_ref()
+            .and_then(semantic_tokens_capabilities)
+            .map(|(legend, _)| legend);
+        let (Some(legend), Some(state)) = (legend, self.buffers.get_mut(&buffer_id)) else {
+            return;
+        };
+
+        match result {
+            SemanticTokensFullDeltaResult::Tokens(tokens) => match lines {
+                Some(lines) => state.semantic_tokens.set_range(lines, tokens, &legend),
+                None => state.semantic_tokens.set_full(tokens, &legend),
+            },
+            SemanticTokensFullDeltaResult::TokensDelta(delta) => {
+                if let Err(e) = state.semantic_tokens.apply_delta(delta, &legend) {
+                    // The result ID is cleared, so this asks for a full result
+                    tracing::warn!("Failed to apply semantic tokens delta: {}", e);
+                    self.schedule_semantic_tokens_refresh(buffer_id, Duration::ZERO);
+                }
+            }
+            SemanticTokensFullDeltaResult::PartialTokensDelta { edits } => {
+                let delta = lsp_types::SemanticTokensDelta {
+                    result_id: None,
+                    edits,
+                };
+                if let Err(e) = state.semantic_tokens.apply_delta(delta, &legend) {
+                    tracing::warn!("Failed to apply semantic tokens delta: {}", e);
+                    self.schedule_semantic_tokens_refresh(buffer_id, Duration::ZERO);
+                }
+            }
+        }
+    }
+}
@@ -29,12 +29,13 @@ use std::time::Instant;
 use crate::state::EditorState;
 
 use crate::model::event::{BufferId, SplitDirection, SplitId};
+use crate::primitives::folding::FoldRange;
 use crate::services::terminal::TerminalId;
 use crate::session::{
     FileExplorerState, SearchOptions, SerializedBookmark, SerializedCursor, SerializedFileState,
-    SerializedScroll, SerializedSplitDirection, SerializedSplitNode, SerializedSplitViewState,
-    SerializedTabRef, SerializedTerminalSession, SerializedViewMode, Session,
-    SessionConfigOverrides, SessionError, SessionHistories, SESSION_VERSION,
+    SerializedFold, SerializedScroll, SerializedSplitDirection, SerializedSplitNode,
+    SerializedSplitViewState, SerializedTabRef, SerializedTerminalSession, SerializedViewMode,
+    Session, SessionConfigOverrides, SessionError, SessionHistories, SESSION_VERSION,
 };
 use crate::state::ViewMode;
 use crate::view::split::{SplitNode, SplitViewState};
@@ -247,6 +248,9 @@ impl Editor {
         let bookmarks =
             serialize_bookmarks(&self.bookmarks, &self.buffer_metadata, &self.working_dir);
 
+        // Capture folded regions
+        let folds = serialize_folds(&self.buffers, &self.buffer_metadata, &self.working_dir);
+
         Session {
             version: SESSION_VERSION,
             working_dir: self.working_dir.clone(),
@@ -258,6 +262,7 @@ impl Editor {
             histories,
             search_options,
             bookmarks,
+            folds,
             terminals,
             saved_at: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
@@ -477,6 +482,19 @@ impl Editor {
             }
         }
 
+        // 8. Restore folds
+        for (rel_path, folds) in &session.folds {
+            if let Some(state) = path_to_buffer
+                .get(rel_path)
+                .and_then(|buffer_id| self.buffers.get_mut(buffer_id))
+            {
+                for fold in folds {
+                    let range = FoldRange::new(fold.start_line, fold.end_line);
+                    state.folds.fold(&state.buffer, range);
+                }
+            }
+        }
+
         tracing::debug!(
             "Session restore complete: {} splits, {} buffers",
             self.split_view_states.len(),
@@ -1098,6 +1116,33 @@ fn serialize_bookmarks(
         .collect()
 }
 
+fn serialize_folds(
+    buffers: &HashMap<BufferId, EditorState>,
+    buffer_metadata: &HashMap<BufferId, super::types::BufferMetadata>,
+    working_dir: &Path,
+) -> HashMap<PathBuf, Vec<SerializedFold>> {
+    buffers
+        .iter()
+        .filter_map(|(buffer_id, state)| {
+            let folds: Vec<SerializedFold> = state
+                .folds
+                .folded_lines(&state.buffer)
+                .into_iter()
+                .map(|range| SerializedFold {
+                    start_line: range.start_line,
+                    end_line: range.end_line,
+                })
+                .collect();
+            if folds.is_empty() {
+                return None;
+            }
+            let abs_path = buffer_metadata.get(buffer_id)?.file_path()?;
+            let rel_path = abs_path.strip_prefix(working_dir).ok()?;
+            Some((rel_path.to_path_buf(), folds))
+        })
+        .collect()
+}
+
 /// Collect all unique file paths from split_states
 fn collect_file_paths_from_states(
     split_states: &HashMap<usize, SerializedSplitViewState>,
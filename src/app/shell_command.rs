@@ -0,0 +1,393 @@
+//! Shell command on the selection
+//!
+//! Pipes each cursor's selection, or the whole buffer when nothing is selected,
+//! through a shell command such as `sort -u` or `jq .`, like `M-|` in Emacs and
+//! `:%!` in Vim. The output replaces the text, is inserted after it, or is shown
+//! in a scratch buffer. Commands run in the background under
+//! `editor.shell_command_limits` and are stopped after
+//! `editor.shell_command_timeout_secs`; if one fails, the text is left alone and
+//! its exit status is shown as a warning.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use super::Editor;
+use crate::model::event::{BufferId, CursorId, Event};
+use crate::primitives::line_edit;
+use crate::primitives::rectangle::Edit;
+use crate::primitives::text_property::TextPropertyEntry;
+use crate::services::async_bridge::AsyncMessage;
+use crate::services::shell_command::{self, ShellOutput, ShellOutputMode};
+use crate::view::prompt::PromptType;
+
+const OUTPUT_BUFFER_NAME: &str = "*Shell Command Output*";
+
+/// A piece of text a shell command reads
+#[derive(Debug)]
+pub(super) struct ShellRegion {
+    /// Cursor whose selection (or position) this is; `None` for the whole buffer
+    cursor_id: Option<CursorId>,
+    range: Range<usize>,
+    text: String,
+}
+
+/// A shell command running in the background
+#[derive(Debug)]
+pub(super) struct PendingShellCommand {
+    id: u64,
+    command: String,
+    buffer_id: BufferId,
+    mode: ShellOutputMode,
+    /// In buffer order, one run of the command each
+    regions: Vec<ShellRegion>,
+}
+
+impl Editor {
+    pub(super) fn start_shell_command_prompt(&mut self, mode: ShellOutputMode) {
+        if mode != ShellOutputMode::Buffer && self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        let message = match mode {
+            ShellOutputMode::Replace => "Pipe through command: ",
+            ShellOutputMode::Insert => "Insert output of command: ",
+            ShellOutputMode::Buffer => "Show output of command: ",
+        };
+        self.start_prompt(message.to_string(), PromptType::ShellCommand { mode });
+    }
+
+    /// Run `command` on the selection of every cursor, or on the whole buffer when
+    /// nothing is selected
+    ///
+    /// To insert output without a selection, the command reads nothing and its
+    /// output goes at every cursor.
+    pub(super) fn run_shell_command(&mut self, command: &str, mode: ShellOutputMode) {
+        let state = self.active_state_mut();
+        let mut selections: Vec<(CursorId, Range<usize>)> = state
+            .cursors
+            .iter()
+            .filter_map(|(cursor_id, cursor)| {
+                let range = cursor.selection_range().filter(|range| !range.is_empty())?;
+                Some((cursor_id, range))
+            })
+            .collect();
+        selections.sort_by_key(|(_, range)| range.start);
+
+        let regions = if !selections.is_empty() {
+            selections
+                .into_iter()
+                .map(|(cursor_id, range)| ShellRegion {
+                    cursor_id: Some(cursor_id),
+                    text: state.get_text_range(range.start, range.end),
+                    range,
+                })
+                .collect()
+        } else if mode == ShellOutputMode::Insert {
+            let mut positions: Vec<(CursorId, usize)> = state
+                .cursors
+                .iter()
+                .map(|(cursor_id, cursor)| (cursor_id, cursor.position))
+                .collect();
+            positions.sort_by_key(|(_, position)| *position);
+            positions
+                .into_iter()
+                .map(|(cursor_id, position)| ShellRegion {
+                    cursor_id: Some(cursor_id),
+                    range: position..position,
+                    text: String::new(),
+                })
+                .collect()
+        } else {
+            let len = state.buffer.len();
+            vec![ShellRegion {
+                cursor_id: None,
+                range: 0..len,
+                text: state.get_text_range(0, len),
+            }]
+        };
+        self.start_shell_command(command, mode, regions);
+    }
+
+    /// Replace `range` (whole lines) with the output of `command` run on it, for
+    /// Vim's `:{range}!command`
+    pub(super) fn filter_through_shell_command(&mut self, command: &str, range: Range<usize>) {
+        let text = self
+            .active_state_mut()
+            .get_text_range(range.start, range.end);
+        let region = ShellRegion {
+            cursor_id: None,
+            range,
+            text,
+        };
+        self.start_shell_command(command, ShellOutputMode::Replace, vec![region]);
+    }
+
+    /// Show the output of `command`, which reads nothing, for Vim's `:!command`
+    pub(super) fn show_shell_command_output(&mut self, command: &str) {
+        let region = ShellRegion {
+            cursor_id: None,
+            range: 0..0,
+            text: String::new(),
+        };
+        self.start_shell_command(command, ShellOutputMode::Buffer, vec![region]);
+    }
+
+    /// Run `command` on each region in the background; a command already running
+    /// is superseded and its output ignored
+    fn start_shell_command(
+        &mut self,
+        command: &str,
+        mode: ShellOutputMode,
+        regions: Vec<ShellRegion>,
+    ) {
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        if mode != ShellOutputMode::Buffer && self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        let (Some(runtime), Some(sender)) = (
+            self.tokio_runtime.as_ref(),
+            self.async_bridge.as_ref().map(|bridge| bridge.sender()),
+        ) else {
+            self.set_status_message("Async runtime not available".to_string());
+            return;
+        };
+
+        let id = self.next_shell_command_id;
+        let inputs: Vec<String> = regions.iter().map(|region| region.text.clone()).collect();
+        let run_command = command.to_string();
+        let cwd = Some(self.working_dir.clone());
+        let limits = self.config.editor.shell_command_limits.clone();
+        let timeout = Duration::from_secs(self.config.editor.shell_command_timeout_secs);
+        runtime.spawn(async move {
+            let mut outputs = Vec::with_capacity(inputs.len());
+            for input in inputs {
+                outputs.push(
+                    shell_command::run(
+                        run_command.clone(),
+                        input,
+                        cwd.clone(),
+                        limits.clone(),
+                        timeout,
+                    )
+                    .await,
+                );
+            }
+            let _ = sender.send(AsyncMessage::ShellCommandFinished { id, outputs });
+        });
+
+        self.next_shell_command_id += 1;
+        self.shell_command = Some(PendingShellCommand {
+            id,
+            command: command.to_string(),
+            buffer_id: self.active_buffer(),
+            mode,
+            regions,
+        });
+        self.set_status_message(format!("Running {}...", command));
+    }
+
+    /// Use the output of a finished shell command
+    pub(super) fn handle_shell_command_finished(&mut self, id: u64, outputs: Vec<ShellOutput>) {
+        let pending = match self.shell_command.take() {
+            Some(pending) if pending.id == id => pending,
+            other => {
+                self.shell_command = other;
+                return;
+            }
+        };
+        if let Some(failed) = outputs.iter().find(|output| !output.success()) {
+            let status = match failed.exit_code {
+                Some(code) => format!("exited with status {}", code),
+                None => "failed".to_string(),
+            };
+            let detail = failed
+                .stderr
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(|line| format!(": {}", line))
+                .unwrap_or_default();
+            self.set_status_message(format!("Warning: {} {}{}", pending.command, status, detail));
+            return;
+        }
+
+        match pending.mode {
+            ShellOutputMode::Buffer => self.show_shell_output(&pending.command, &outputs),
+            ShellOutputMode::Replace | ShellOutputMode::Insert => {
+                self.apply_shell_output(pending, &outputs)
+            }
+        }
+    }
+
+    fn show_shell_output(&mut self, command: &str, outputs: &[ShellOutput]) {
+        let text: String = outputs
+            .iter()
+            .map(|output| output.stdout.as_str())
+            .collect();
+        let existing = self
+            .shell_output_buffer
+            .filter(|buffer_id| self.buffers.contains_key(buffer_id));
+        let buffer_id = match existing {
+            Some(buffer_id) => buffer_id,
+            None => {
+                let buffer_id = self.create_virtual_buffer(
+                    OUTPUT_BUFFER_NAME.to_string(),
+                    "special".to_string(),
+                    true,
+                );
+                if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                    state.editing_disabled = true;
+                }
+                self.shell_output_buffer = Some(buffer_id);
+                buffer_id
+            }
+        };
+        let empty = text.is_empty();
+        if let Err(e) =
+            self.set_virtual_buffer_content(buffer_id, vec![TextPropertyEntry::text(text)])
+        {
+            tracing::warn!("Failed to update shell command output buffer: {}", e);
+        }
+        self.set_active_buffer(buffer_id);
+        if empty {
+            self.set_status_message(format!("{} produced no output", command));
+        }
+    }
+
+    /// Put the output of each run in place of (or after) the text it read, as one
+    /// undo step
+    fn apply_shell_output(&mut self, pending: PendingShellCommand, outputs: &[ShellOutput]) {
+        if self.active_buffer() != pending.buffer_id {
+            self.set_status_message(format!(
+                "Switched buffers while {} ran; output not used",
+                pending.command
+            ));
+            return;
+        }
+        let state = self.active_state_mut();
+        let len = state.buffer.len();
+        let unchanged = pending.regions.iter().all(|region| {
+            region.range.end <= len
+                && state.get_text_range(region.range.start, region.range.end) == region.text
+        });
+        if !unchanged {
+            self.set_status_message(format!(
+                "The text changed while {} ran; output not used",
+                pending.command
+            ));
+            return;
+        }
+
+        let edits: Vec<Edit> = pending
+            .regions
+            .iter()
+            .zip(outputs)
+            .map(|(region, output)| {
+                let text = output_text(&region.text, &output.stdout);
+                match pending.mode {
+                    ShellOutputMode::Replace => (region.range.clone(), text),
+                    _ => (region.range.end..region.range.end, text),
+                }
+            })
+            .collect();
+        if edits
+            .iter()
+            .all(|(range, text)| range.is_empty() && text.is_empty())
+        {
+            self.set_status_message(format!("{} produced no output", pending.command));
+            return;
+        }
+
+        let state = self.active_state_mut();
+        let cursor_id = state.cursors.primary_id();
+        let mut events = Vec::new();
+        for (range, text) in edits.iter().rev() {
+            if !range.is_empty() {
+                events.push(Event::Delete {
+                    range: range.clone(),
+                    deleted_text: state.get_text_range(range.start, range.end),
+                    cursor_id,
+                });
+            }
+            if !text.is_empty() {
+                events.push(Event::Insert {
+                    position: range.start,
+                    text: text.clone(),
+                    cursor_id,
+                });
+            }
+        }
+
+        // A cursor whose selection was replaced selects the output; after an
+        // insertion it goes to the end of the output
+        let outputs_placed: Vec<(Option<CursorId>, Range<usize>)> = pending
+            .regions
+            .iter()
+            .zip(new_ranges(&edits))
+            .map(|(region, range)| (region.cursor_id, range))
+            .collect();
+        for (id, cursor) in state.cursors.iter() {
+            let placed = outputs_placed
+                .iter()
+                .find(|(cursor_id, _)| *cursor_id == Some(id))
+                .map(|(_, range)| range.clone());
+            let (new_position, new_anchor) = match placed {
+                Some(range) if pending.mode == ShellOutputMode::Insert => (range.end, None),
+                Some(range) if cursor.anchor.is_some_and(|anchor| anchor > cursor.position) => {
+                    (range.start, Some(range.end))
+                }
+                Some(range) => (range.end, Some(range.start)),
+                None => (
+                    line_edit::map_position(&edits, cursor.position),
+                    cursor
+                        .anchor
+                        .map(|anchor| line_edit::map_position(&edits, anchor)),
+                ),
+            };
+            events.push(Event::MoveCursor {
+                cursor_id: id,
+                old_position: cursor.position,
+                new_position,
+                old_anchor: cursor.anchor,
+                new_anchor,
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            });
+        }
+
+        let batch = Event::Batch {
+            events,
+            description: format!("Shell command: {}", pending.command),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+        self.set_status_message(format!("Ran {}", pending.command));
+    }
+}
+
+/// The output put in place of `input`: a final line break that the input doesn't
+/// end with is dropped
+fn output_text(input: &str, stdout: &str) -> String {
+    if input.ends_with('\n') {
+        return stdout.to_string();
+    }
+    let stdout = stdout.strip_suffix('\n').unwrap_or(stdout);
+    stdout.strip_suffix('\r').unwrap_or(stdout).to_string()
+}
+
+/// Where the new text of each edit (in buffer order) is once all are made
+fn new_ranges(edits: &[Edit]) -> Vec<Range<usize>> {
+    let mut shift = 0isize;
+    edits
+        .iter()
+        .map(|(range, text)| {
+            let start = (range.start as isize + shift) as usize;
+            shift += text.len() as isize - range.len() as isize;
+            start..start + text.len()
+        })
+        .collect()
+}
@@ -0,0 +1,391 @@
+//! Snippets: inserting them and moving between their tabstops
+//!
+//! Snippets come from LSP completion items with `insertTextFormat = Snippet` and from
+//! the user's snippet files (`<config dir>/snippets/<language>.json`, in VSCode
+//! format). Typing a user snippet's prefix and pressing Tab expands it, and user
+//! snippets are offered alongside LSP completions.
+//!
+//! Once inserted, Tab and Shift+Tab move between the tabstops until the final one is
+//! reached. Every occurrence of the current tabstop gets a cursor, so mirrored
+//! placeholders change together; at a choice tabstop the choices are offered in a
+//! popup.
+
+use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Documentation, InsertTextFormat, MarkupContent, MarkupKind,
+};
+
+use super::Editor;
+use crate::input::keybindings::Action;
+use crate::model::event::{
+    CursorId, Event, PopupContentData, PopupData, PopupListItemData, PopupPositionData,
+};
+use crate::model::snippet::SnippetSession;
+use crate::primitives::snippet::{load_snippets, parse_snippet, SnippetDefinition};
+use crate::primitives::word_navigation::find_completion_word_start;
+use crate::services::lsp::manager::detect_language;
+
+/// Title of the popup offering the values of a choice tabstop
+pub(super) const SNIPPET_CHOICE_POPUP_TITLE: &str = "Snippet Choice";
+
+impl Editor {
+    /// Replace `range` of the active buffer with a snippet and go to its first tabstop
+    pub(super) fn insert_snippet(&mut self, range: Range<usize>, body: &str) {
+        let variables = self.snippet_variables(range.clone());
+
+        // Later lines keep the indentation of the line the snippet starts on
+        let indent = variables
+            .get("TM_CURRENT_LINE")
+            .map(|line| {
+                line.chars()
+                    .take_while(|c| *c == ' ' || *c == '\t')
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+        let body = body
+            .replace('\t', &" ".repeat(self.config.editor.tab_size))
+            .replace('\n', &format!("\n{}", indent));
+        let snippet = parse_snippet(&body, |name| variables.get(name).cloned());
+
+        let cursor_id = self.active_state().cursors.primary_id();
+        let mut events = Vec::new();
+        if !range.is_empty() {
+            let deleted_text = self
+                .active_state_mut()
+                .get_text_range(range.start, range.end);
+            events.push(Event::Delete {
+                range: range.clone(),
+                deleted_text,
+                cursor_id,
+            });
+        }
+        events.push(Event::Insert {
+            position: range.start,
+            text: snippet.text.clone(),
+            cursor_id,
+        });
+        let batch = Event::Batch {
+            events,
+            description: "Insert snippet".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+
+        self.active_state_mut().snippet = Some(SnippetSession::new(range.start, &snippet));
+        self.select_snippet_tabstop();
+    }
+
+    /// Values of the snippet variables (`$TM_FILENAME` etc.) for a snippet replacing
+    /// `range`
+    fn snippet_variables(&mut self, range: Range<usize>) -> HashMap<&'static str, String> {
+        let mut variables = HashMap::new();
+
+        let state = self.active_state_mut();
+        let line = state.buffer.get_line_number(range.start);
+        let line_start = state.buffer.line_start_offset(line).unwrap_or(0);
+        let line_end = state
+            .buffer
+            .line_start_offset(line + 1)
+            .unwrap_or_else(|| state.buffer.len());
+        let current_line = state.get_text_range(line_start, line_end);
+        variables.insert(
+            "TM_CURRENT_LINE",
+            current_line.trim_end_matches(['\r', '\n']).to_string(),
+        );
+        let word_start = find_completion_word_start(&state.buffer, range.start);
+        variables.insert(
+            "TM_CURRENT_WORD",
+            state.get_text_range(word_start, range.start),
+        );
+        if let Some(selection) = state.cursors.primary().selection_range() {
+            variables.insert(
+                "TM_SELECTED_TEXT",
+                state.get_text_range(selection.start, selection.end),
+            );
+        }
+        variables.insert("TM_LINE_INDEX", line.to_string());
+        variables.insert("TM_LINE_NUMBER", (line + 1).to_string());
+
+        if let Some(path) = state.buffer.file_path().map(|path| path.to_path_buf()) {
+            variables.insert("TM_FILEPATH", path.to_string_lossy().into_owned());
+            if let Some(name) = path.file_name() {
+                variables.insert("TM_FILENAME", name.to_string_lossy().into_owned());
+            }
+            if let Some(stem) = path.file_stem() {
+                variables.insert("TM_FILENAME_BASE", stem.to_string_lossy().into_owned());
+            }
+            if let Some(dir) = path.parent() {
+                variables.insert("TM_DIRECTORY", dir.to_string_lossy().into_owned());
+            }
+        }
+        variables.insert("CLIPBOARD", self.clipboard.get_internal().to_string());
+
+        let now = chrono::Local::now();
+        for (name, format) in [
+            ("CURRENT_YEAR", "%Y"),
+            ("CURRENT_YEAR_SHORT", "%y"),
+            ("CURRENT_MONTH", "%m"),
+            ("CURRENT_MONTH_NAME", "%B"),
+            ("CURRENT_MONTH_NAME_SHORT", "%b"),
+            ("CURRENT_DATE", "%d"),
+            ("CURRENT_DAY_NAME", "%A"),
+            ("CURRENT_DAY_NAME_SHORT", "%a"),
+            ("CURRENT_HOUR", "%H"),
+            ("CURRENT_MINUTE", "%M"),
+            ("CURRENT_SECOND", "%S"),
+        ] {
+            variables.insert(name, now.format(format).to_string());
+        }
+        variables
+    }
+
+    /// Put a cursor on every occurrence of the current tabstop, selecting its text
+    ///
+    /// Reaching the final tabstop ends the snippet; reaching a choice offers the choices.
+    fn select_snippet_tabstop(&mut self) {
+        let Some(session) = self.active_state().snippet.as_ref() else {
+            return;
+        };
+        let at_end = session.is_at_end();
+        let choices = session.current_choices().to_vec();
+        self.place_snippet_cursors();
+
+        if at_end {
+            self.active_state_mut().snippet = None;
+        } else if !choices.is_empty() {
+            self.show_snippet_choices(choices);
+        }
+    }
+
+    fn place_snippet_cursors(&mut self) {
+        let Some(ranges) = self
+            .active_state()
+            .snippet
+            .as_ref()
+            .map(|session| session.current_ranges())
+        else {
+            return;
+        };
+        let Some((first, mirrors)) = ranges.split_first() else {
+            return;
+        };
+
+        let state = self.active_state();
+        let primary_id = state.cursors.primary_id();
+        let primary = *state.cursors.primary();
+        let next_id = state.cursors.ids().iter().map(|id| id.0).max().unwrap_or(0) + 1;
+        let anchor = |range: &Range<usize>| (!range.is_empty()).then_some(range.start);
+
+        // Cursors left on the previous tabstop's occurrences go away
+        let mut events: Vec<Event> = state
+            .cursors
+            .iter()
+            .filter(|(id, _)| *id != primary_id)
+            .map(|(id, cursor)| Event::RemoveCursor {
+                cursor_id: id,
+                position: cursor.position,
+                anchor: cursor.anchor,
+            })
+            .collect();
+        events.push(Event::MoveCursor {
+            cursor_id: primary_id,
+            old_position: primary.position,
+            new_position: first.end,
+            old_anchor: primary.anchor,
+            new_anchor: anchor(first),
+            old_sticky_column: primary.sticky_column,
+            new_sticky_column: 0,
+        });
+        events.extend(
+            mirrors
+                .iter()
+                .zip(next_id..)
+                .map(|(range, id)| Event::AddCursor {
+                    cursor_id: CursorId(id),
+                    position: range.end,
+                    anchor: anchor(range),
+                }),
+        );
+
+        let batch = Event::Batch {
+            events,
+            description: "Snippet tabstop".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+    }
+
+    fn show_snippet_choices(&mut self, choices: Vec<String>) {
+        let popup = PopupData {
+            title: Some(SNIPPET_CHOICE_POPUP_TITLE.to_string()),
+            content: PopupContentData::List {
+                items: choices
+                    .into_iter()
+                    .map(|choice| PopupListItemData {
+                        text: choice.clone(),
+                        detail: None,
+                        icon: None,
+                        data: Some(choice),
+                    })
+                    .collect(),
+                selected: 0,
+            },
+            position: PopupPositionData::BelowCursor,
+            width: 40,
+            max_height: 10,
+            bordered: true,
+        };
+        self.active_state_mut().apply(&Event::ShowPopup { popup });
+    }
+
+    /// Put the value picked for a choice tabstop into all its occurrences
+    pub(super) fn confirm_snippet_choice(&mut self, choice: &str) {
+        let Some(mut ranges) = self
+            .active_state()
+            .snippet
+            .as_ref()
+            .map(|session| session.current_ranges())
+        else {
+            return;
+        };
+        // Back to front, so earlier ranges stay valid
+        ranges.sort_by_key(|range| std::cmp::Reverse(range.start));
+
+        let cursor_id = self.active_state().cursors.primary_id();
+        let mut events = Vec::new();
+        for range in ranges {
+            if !range.is_empty() {
+                let deleted_text = self
+                    .active_state_mut()
+                    .get_text_range(range.start, range.end);
+                events.push(Event::Delete {
+                    range: range.clone(),
+                    deleted_text,
+                    cursor_id,
+                });
+            }
+            events.push(Event::Insert {
+                position: range.start,
+                text: choice.to_string(),
+                cursor_id,
+            });
+        }
+        let batch = Event::Batch {
+            events,
+            description: "Snippet choice".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+        self.place_snippet_cursors();
+    }
+
+    /// Handle Tab (next tabstop, or expanding a snippet prefix) and Shift+Tab
+    /// (previous tabstop); returns false when the action should run as usual
+    pub(super) fn handle_snippet_action(&mut self, action: &Action) -> io::Result<bool> {
+        if self.is_prompting()
+            || self.is_editing_disabled()
+            || !matches!(action, Action::InsertTab | Action::DedentSelection)
+        {
+            return Ok(false);
+        }
+
+        // Moving away from the snippet ends it
+        let cursor_pos = self.active_state().cursors.primary().position;
+        if self
+            .active_state()
+            .snippet
+            .as_ref()
+            .is_some_and(|session| !session.contains(cursor_pos))
+        {
+            self.active_state_mut().snippet = None;
+        }
+
+        let Some(session) = self.active_state_mut().snippet.as_mut() else {
+            return Ok(matches!(action, Action::InsertTab) && self.expand_snippet_prefix());
+        };
+        match action {
+            Action::InsertTab => session.next(),
+            _ => {
+                if !session.prev() {
+                    return Ok(true);
+                }
+            }
+        }
+        self.select_snippet_tabstop();
+        Ok(true)
+    }
+
+    /// Expand the user snippet whose prefix is the word before the cursor
+    fn expand_snippet_prefix(&mut self) -> bool {
+        let state = self.active_state();
+        if state.cursors.count() > 1 || state.cursors.primary().selection_range().is_some() {
+            return false;
+        }
+        let cursor_pos = state.cursors.primary().position;
+        let word_start = find_completion_word_start(&state.buffer, cursor_pos);
+        if word_start == cursor_pos {
+            return false;
+        }
+        let word = self
+            .active_state_mut()
+            .get_text_range(word_start, cursor_pos);
+        let Some(language) = self.active_language() else {
+            return false;
+        };
+        let Some(body) = self
+            .user_snippets(&language)
+            .iter()
+            .find(|snippet| snippet.prefixes.contains(&word))
+            .map(|snippet| snippet.body.clone())
+        else {
+            return false;
+        };
+        self.insert_snippet(word_start..cursor_pos, &body);
+        true
+    }
+
+    pub(super) fn active_language(&self) -> Option<String> {
+        let path = self
+            .buffer_metadata
+            .get(&self.active_buffer())?
+            .file_path()?;
+        detect_language(path, &self.config.languages)
+    }
+
+    /// The user's snippets for a language, read from its snippet file on first use
+    fn user_snippets(&mut self, language: &str) -> &[SnippetDefinition] {
+        let dir = self.dir_context.snippets_dir();
+        self.user_snippets
+            .entry(language.to_string())
+            .or_insert_with(|| load_snippets(&dir, language))
+    }
+
+    /// The user's snippets for a language as completion items
+    pub(super) fn snippet_completion_items(&mut self, language: &str) -> Vec<CompletionItem> {
+        self.user_snippets(language)
+            .iter()
+            .flat_map(|snippet| {
+                snippet.prefixes.iter().map(|prefix| CompletionItem {
+                    label: prefix.clone(),
+                    kind: Some(CompletionItemKind::SNIPPET),
+                    detail: Some(
+                        snippet
+                            .description
+                            .clone()
+                            .unwrap_or_else(|| snippet.name.clone()),
+                    ),
+                    documentation: Some(Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: format!("```\n{}\n```", snippet.body),
+                    })),
+                    insert_text: Some(snippet.body.clone()),
+                    insert_text_format: Some(InsertTextFormat::SNIPPET),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,415 @@
+//! Syntax text objects, structural navigation, and expanding/shrinking the selection
+//!
+//! Objects come from the language's tree-sitter `textobjects.scm` query. Expanding
+//! the selection climbs the syntax tree; buffers without a grammar keep growing it
+//! word by word. Each expansion is remembered so that shrinking walks back down
+//! through the same selections.
+
+use std::ops::Range;
+
+use super::Editor;
+use crate::input::keybindings::Action;
+use crate::model::event::{BufferId, CursorId, Event};
+use crate::primitives::textobjects::{
+    next_object_start, object_around, swap_target, TextObject, TextObjectExtractor, TextObjectKind,
+};
+
+/// Position and anchor of every cursor in a buffer
+type CursorSnapshot = Vec<(CursorId, usize, Option<usize>)>;
+
+/// One step of selection expansion, undone by shrinking
+#[derive(Debug, Clone)]
+pub(super) struct SelectionExpansion {
+    buffer_id: BufferId,
+    before: CursorSnapshot,
+    /// Shrinking restores `before` only while the cursors are still here
+    after: CursorSnapshot,
+}
+
+impl Editor {
+    fn cursor_snapshot(&self) -> CursorSnapshot {
+        self.active_state()
+            .cursors
+            .iter()
+            .map(|(id, cursor)| (id, cursor.position, cursor.anchor))
+            .collect()
+    }
+
+    /// Buffer text and its objects of the given kinds, if the language has text objects
+    fn active_text_objects(
+        &mut self,
+        kinds: &[TextObjectKind],
+    ) -> Option<(String, Vec<TextObject>)> {
+        let state = self.active_state();
+        let language = *state.highlighter.language()?;
+        let text = state.buffer.to_string()?;
+        let mut objects = Vec::new();
+        for kind in kinds {
+            objects.extend(
+                self.text_object_extractor
+                    .objects(&text, &language, *kind)?,
+            );
+        }
+        Some((text, objects))
+    }
+
+    /// Log and apply cursor movements as one step
+    fn apply_selection_events(&mut self, events: Vec<Event>, description: &str) {
+        let event = if events.len() == 1 {
+            events.into_iter().next().unwrap()
+        } else {
+            Event::Batch {
+                events,
+                description: description.to_string(),
+            }
+        };
+        self.active_event_log_mut().append(event.clone());
+        self.apply_event_to_active_buffer(&event);
+    }
+
+    /// Select the object around each cursor, or the one enclosing its selection
+    pub(super) fn select_text_object(&mut self, kind: TextObjectKind) {
+        let Some((_, objects)) = self.active_text_objects(&[kind]) else {
+            self.set_status_message("No syntax text objects for this buffer".to_string());
+            return;
+        };
+        let mut events = Vec::new();
+        for (cursor_id, cursor) in self.active_state().cursors.iter() {
+            let selection = cursor
+                .selection_range()
+                .unwrap_or(cursor.position..cursor.position);
+            if let Some(object) = object_around(&objects, selection) {
+                events.push(Event::MoveCursor {
+                    cursor_id,
+                    old_position: cursor.position,
+                    new_position: object.outer.end,
+                    old_anchor: cursor.anchor,
+                    new_anchor: Some(object.outer.start),
+                    old_sticky_column: cursor.sticky_column,
+                    new_sticky_column: 0,
+                });
+            }
+        }
+        if events.is_empty() {
+            self.set_status_message(format!("No {} at cursor", kind.name()));
+            return;
+        }
+        self.apply_selection_events(events, "SelectTextObject");
+    }
+
+    /// Delete the object around each cursor (`inner`: only its contents)
+    pub(super) fn delete_text_object(&mut self, kind: TextObjectKind, inner: bool) {
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        let Some((text, objects)) = self.active_text_objects(&[kind]) else {
+            self.set_status_message("No syntax text objects for this buffer".to_string());
+            return;
+        };
+
+        let mut ranges: Vec<(CursorId, Range<usize>)> = Vec::new();
+        for (cursor_id, cursor) in self.active_state().cursors.iter() {
+            let selection = cursor
+                .selection_range()
+                .unwrap_or(cursor.position..cursor.position);
+            if let Some(object) = object_around(&objects, selection) {
+                let range = if inner {
+                    object.inner.clone()
+                } else {
+                    whole_lines(&text, object.outer.clone())
+                };
+                ranges.push((cursor_id, range));
+            }
+        }
+        if ranges.is_empty() {
+            self.set_status_message(format!("No {} at cursor", kind.name()));
+            return;
+        }
+
+        // Several cursors in the same object delete it once
+        ranges.sort_by_key(|(_, range)| std::cmp::Reverse(range.start));
+        let mut events = Vec::new();
+        let mut last_start = usize::MAX;
+        for (cursor_id, range) in ranges {
+            if range.is_empty() || range.end > last_start {
+                continue;
+            }
+            last_start = range.start;
+            events.push(Event::Delete {
+                deleted_text: text[range.clone()].to_string(),
+                range,
+                cursor_id,
+            });
+        }
+        if events.is_empty() {
+            return;
+        }
+        let description = if inner {
+            "ChangeTextObject"
+        } else {
+            "DeleteTextObject"
+        };
+        let batch = Event::Batch {
+            events,
+            description: description.to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+    }
+
+    /// Range of the object around `pos`, and whether it is whole lines
+    ///
+    /// Arguments include parameters. An outer object alone on its lines takes the
+    /// whole lines.
+    pub(super) fn syntax_object_range(
+        &mut self,
+        kind: TextObjectKind,
+        inner: bool,
+        pos: usize,
+    ) -> Option<(Range<usize>, bool)> {
+        let kinds = if kind == TextObjectKind::Argument {
+            vec![TextObjectKind::Argument, TextObjectKind::Parameter]
+        } else {
+            vec![kind]
+        };
+        let (text, objects) = self.active_text_objects(&kinds)?;
+        let object = object_around(&objects, pos..pos)?;
+        if inner {
+            return Some((object.inner.clone(), false));
+        }
+        let range = whole_lines(&text, object.outer.clone());
+        let linewise = range != object.outer;
+        Some((range, linewise))
+    }
+
+    /// Move the cursor to the start of the next (or previous) function
+    pub(super) fn goto_function(&mut self, forward: bool) {
+        let Some((_, objects)) = self.active_text_objects(&[TextObjectKind::Function]) else {
+            self.set_status_message("No syntax text objects for this buffer".to_string());
+            return;
+        };
+        let state = self.active_state();
+        let cursor = *state.cursors.primary();
+        let Some(target) = next_object_start(&objects, cursor.position, forward) else {
+            let which = if forward { "next" } else { "previous" };
+            self.set_status_message(format!("No {} function", which));
+            return;
+        };
+        let event = Event::MoveCursor {
+            cursor_id: state.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: target,
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        };
+        self.apply_selection_events(vec![event], "GotoFunction");
+    }
+
+    /// Swap the argument (or parameter) at the cursor with its next or previous sibling
+    pub(super) fn swap_argument(&mut self, forward: bool) {
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        let kinds = [TextObjectKind::Argument, TextObjectKind::Parameter];
+        let Some((text, objects)) = self.active_text_objects(&kinds) else {
+            self.set_status_message("No syntax text objects for this buffer".to_string());
+            return;
+        };
+        let state = self.active_state();
+        let cursor_id = state.cursors.primary_id();
+        let pos = state.cursors.primary().position;
+        let Some((current, neighbour)) = swap_target(&objects, pos, forward) else {
+            let which = if forward { "next" } else { "previous" };
+            self.set_status_message(format!("No {} argument to swap with", which));
+            return;
+        };
+
+        let (first, second) = if forward {
+            (current.clone(), neighbour.clone())
+        } else {
+            (neighbour.clone(), current.clone())
+        };
+        let mut events = Vec::new();
+        for (range, replacement) in [(&second, &first), (&first, &second)] {
+            events.push(Event::Delete {
+                range: range.clone(),
+                deleted_text: text[range.clone()].to_string(),
+                cursor_id,
+            });
+            events.push(Event::Insert {
+                position: range.start,
+                text: text[replacement.clone()].to_string(),
+                cursor_id,
+            });
+        }
+        let batch = Event::Batch {
+            events,
+            description: "SwapArgument".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+
+        // Keep the cursor on the item it was on
+        let offset = pos - current.start;
+        let new_start = if forward {
+            neighbour.end - current.len()
+        } else {
+            neighbour.start
+        };
+        let cursor = *self.active_state().cursors.primary();
+        let event = Event::MoveCursor {
+            cursor_id,
+            old_position: cursor.position,
+            new_position: new_start + offset,
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        };
+        self.apply_selection_events(vec![event], "SwapArgument");
+    }
+
+    /// Grow each selection to the enclosing syntax node
+    ///
+    /// Buffers without text objects fall back to growing the selection by a word.
+    pub(super) fn expand_selection(&mut self) {
+        let before = self.cursor_snapshot();
+        let events = match self.syntax_selection_events(true) {
+            Some(events) => events,
+            None => self
+                .action_to_events(Action::ExpandSelection)
+                .unwrap_or_default(),
+        };
+        if events.is_empty() {
+            return;
+        }
+        self.apply_selection_events(events, "ExpandSelection");
+
+        let buffer_id = self.active_buffer();
+        let after = self.cursor_snapshot();
+        let continues = self
+            .selection_expansions
+            .last()
+            .is_some_and(|last| last.buffer_id == buffer_id && last.after == before);
+        if !continues {
+            self.selection_expansions.clear();
+        }
+        self.selection_expansions.push(SelectionExpansion {
+            buffer_id,
+            before,
+            after,
+        });
+    }
+
+    /// Undo the last expansion, or step into the syntax node that is selected
+    pub(super) fn shrink_selection(&mut self) {
+        let buffer_id = self.active_buffer();
+        let current = self.cursor_snapshot();
+        let expanded = self
+            .selection_expansions
+            .last()
+            .is_some_and(|last| last.buffer_id == buffer_id && last.after == current);
+        if !expanded {
+            self.selection_expansions.clear();
+            if let Some(events) = self.syntax_selection_events(false) {
+                if !events.is_empty() {
+                    self.apply_selection_events(events, "ShrinkSelection");
+                }
+            }
+            return;
+        }
+
+        let Some(step) = self.selection_expansions.pop() else {
+            return;
+        };
+        let state = self.active_state();
+        let events: Vec<Event> = step
+            .before
+            .into_iter()
+            .filter_map(|(cursor_id, position, anchor)| {
+                let cursor = state.cursors.get(cursor_id)?;
+                Some(Event::MoveCursor {
+                    cursor_id,
+                    old_position: cursor.position,
+                    new_position: position,
+                    old_anchor: cursor.anchor,
+                    new_anchor: anchor,
+                    old_sticky_column: cursor.sticky_column,
+                    new_sticky_column: 0,
+                })
+            })
+            .collect();
+        if !events.is_empty() {
+            self.apply_selection_events(events, "ShrinkSelection");
+        }
+    }
+
+    /// Selection changes that expand (or shrink) along the syntax tree
+    ///
+    /// Returns `None` when the buffer's language has no text objects.
+    fn syntax_selection_events(&mut self, expand: bool) -> Option<Vec<Event>> {
+        let state = self.active_state();
+        let language = *state.highlighter.language()?;
+        if !TextObjectExtractor::supports(&language) {
+            return None;
+        }
+        let text = state.buffer.to_string()?;
+        let cursors: Vec<_> = state
+            .cursors
+            .iter()
+            .map(|(id, cursor)| (id, *cursor))
+            .collect();
+
+        let mut events = Vec::new();
+        for (cursor_id, cursor) in cursors {
+            let range = match cursor.selection_range() {
+                Some(range) => range,
+                None if expand => cursor.position..cursor.position,
+                None => continue,
+            };
+            let next = if expand {
+                self.text_object_extractor
+                    .expand_selection(&text, &language, range)
+            } else {
+                self.text_object_extractor.shrink_selection(
+                    &text,
+                    &language,
+                    range,
+                    cursor.position,
+                )
+            };
+            let Some(next) = next else {
+                continue;
+            };
+            events.push(Event::MoveCursor {
+                cursor_id,
+                old_position: cursor.position,
+                new_position: next.end,
+                old_anchor: cursor.anchor,
+                new_anchor: Some(next.start),
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            });
+        }
+        Some(events)
+    }
+}
+
+/// Extend a range over the whole lines it is on, when nothing else shares them
+fn whole_lines(text: &str, range: Range<usize>) -> Range<usize> {
+    let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[range.end..]
+        .find('\n')
+        .map_or(text.len(), |i| range.end + i);
+    if text[line_start..range.start].trim().is_empty()
+        && text[range.end..line_end].trim().is_empty()
+    {
+        line_start..(line_end + 1).min(text.len())
+    } else {
+        range
+    }
+}
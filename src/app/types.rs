@@ -254,14 +254,70 @@ pub(super) struct LspProgressInfo {
     pub percentage: Option<u32>,
 }
 
-/// LSP message entry (for window messages and logs)
+/// What an entry of a language server's log records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum LspLogKind {
+    /// `window/logMessage` or `$/logTrace`
+    Log(LspMessageType),
+    /// `window/showMessage`
+    Message(LspMessageType),
+    /// `$/progress`
+    Progress,
+    /// A JSON-RPC message, recorded while tracing is on
+    Trace { outgoing: bool },
+}
+
+/// An entry of a language server's log
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub(super) struct LspMessageEntry {
-    pub language: String,
-    pub message_type: LspMessageType,
+pub(super) struct LspLogEntry {
+    pub kind: LspLogKind,
     pub message: String,
-    pub timestamp: std::time::Instant,
+    pub time: chrono::DateTime<chrono::Local>,
+}
+
+/// The recent log of a language server, and the buffer showing it
+#[derive(Debug, Default)]
+pub(super) struct LspServerLog {
+    pub entries: std::collections::VecDeque<LspLogEntry>,
+    /// Virtual buffer showing the log, while one is open
+    pub buffer: Option<BufferId>,
+    /// Entries were added since the buffer was last updated
+    pub dirty: bool,
+}
+
+/// What caused an LSP formatting request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum FormatTrigger {
+    /// Format Document / Format Selection actions
+    Manual,
+    /// `format_on_save` - the buffer is saved once the edits are applied
+    Save,
+    /// `format_on_type` - a server trigger character was typed
+    OnType,
+    /// `willSaveWaitUntil` - the buffer is saved once the server's edits are applied
+    WillSave,
+}
+
+impl FormatTrigger {
+    /// Whether the buffer is saved once the request completes
+    pub fn saves(self) -> bool {
+        matches!(self, Self::Save | Self::WillSave)
+    }
+}
+
+/// An in-flight LSP formatting request
+#[derive(Debug, Clone)]
+pub(super) struct PendingFormatRequest {
+    /// Editor request ID used to match the response
+    pub request_id: u64,
+    /// Buffer the edits apply to
+    pub buffer_id: BufferId,
+    /// Event log state when the request was sent (edits are stale if the buffer changed since)
+    pub log_position: crate::model::event::UndoPosition,
+    /// What caused the request
+    pub trigger: FormatTrigger,
+    /// When the request was sent (format-on-save gives up waiting after a timeout)
+    pub started: std::time::Instant,
 }
 
 /// Types of UI elements that can be hovered over
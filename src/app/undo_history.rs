@@ -0,0 +1,80 @@
+//! Persistent undo history
+//!
+//! A file buffer's event log is stored when the buffer is closed and when the
+//! editor exits, and restored when the file is opened again while its content
+//! still matches. Buffers with unsaved changes are not stored: their history
+//! would not match the file, and the history stored at the last clean close
+//! still does. Large and binary files are skipped.
+
+use super::Editor;
+use crate::model::event::BufferId;
+
+impl Editor {
+    /// Text of a buffer whose undo history can be persisted
+    fn persistable_content(&self, buffer_id: BufferId) -> Option<(std::path::PathBuf, String)> {
+        if !self.config.editor.persistent_undo {
+            return None;
+        }
+        let metadata = self.buffer_metadata.get(&buffer_id)?;
+        if metadata.is_virtual() || metadata.binary {
+            return None;
+        }
+        let state = self.buffers.get(&buffer_id)?;
+        if state.buffer.is_large_file() {
+            return None;
+        }
+        let path = state.buffer.file_path()?.to_path_buf();
+        let content = state.buffer.to_string()?;
+        Some((path, content))
+    }
+
+    /// Replace a freshly opened buffer's event log with its stored history
+    pub(super) fn restore_undo_history(&mut self, buffer_id: BufferId) {
+        let Some((path, content)) = self.persistable_content(buffer_id) else {
+            return;
+        };
+        match self.undo_history.load(&path, content.as_bytes()) {
+            Ok(Some(log)) => {
+                tracing::info!("Restored {} undo events for {}", log.len(), path.display());
+                self.event_logs.insert(buffer_id, log);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to restore undo history: {}", e),
+        }
+    }
+
+    /// Store a buffer's event log so it survives closing the buffer
+    pub(super) fn persist_undo_history(&mut self, buffer_id: BufferId) {
+        let Some((path, content)) = self.persistable_content(buffer_id) else {
+            return;
+        };
+        if self.buffers[&buffer_id].buffer.is_modified() {
+            return;
+        }
+        let Some(log) = self.event_logs.get(&buffer_id) else {
+            return;
+        };
+        if let Err(e) = self.undo_history.save(&path, content.as_bytes(), log) {
+            tracing::warn!("Failed to persist undo history: {}", e);
+        }
+    }
+
+    /// Store the undo history of every open file (call on shutdown)
+    pub fn persist_undo_histories(&mut self) {
+        let buffer_ids: Vec<BufferId> = self.buffers.keys().copied().collect();
+        for buffer_id in buffer_ids {
+            self.persist_undo_history(buffer_id);
+        }
+        self.cleanup_undo_histories();
+    }
+
+    /// Remove stored histories beyond the configured size limit
+    pub(super) fn cleanup_undo_histories(&self) {
+        if !self.config.editor.persistent_undo {
+            return;
+        }
+        if let Err(e) = self.undo_history.cleanup() {
+            tracing::warn!("Failed to clean up undo histories: {}", e);
+        }
+    }
+}
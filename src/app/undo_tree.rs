@@ -0,0 +1,314 @@
+//! Undo tree visualizer and time-based undo
+//!
+//! "Show Undo Tree" lists every state of the active buffer's undo tree in a
+//! read-only virtual buffer. Up and Down select a state and preview its text
+//! around the edit; Enter restores it in the source buffer. "Undo to Earlier
+//! Time" and "Redo to Later Time" (`:earlier` and `:later` in vim) jump to the
+//! state the buffer was in some time before or after the current one.
+//!
+//! The editor tick stores a snapshot of a buffer's content every
+//! `snapshot_interval` edits, so jumps between distant states start from the
+//! nearest snapshot instead of replaying every edit in between. Each log keeps
+//! its snapshots within a byte budget, and large files get none.
+
+use super::Editor;
+use crate::model::event::{BufferId, Event, UndoJump, UndoPosition};
+use crate::view::undo_tree::{self, UNDO_TREE_NODE_PROPERTY};
+
+/// Buffer mode of undo tree buffers (see `ModeRegistry::new`)
+const UNDO_TREE_MODE: &str = "undo-tree";
+
+/// An undo tree shown in a virtual buffer
+#[derive(Debug, Clone)]
+pub(super) struct UndoTreeView {
+    /// Buffer whose history is shown
+    source: BufferId,
+    /// State previewed below the tree
+    selected: UndoPosition,
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl Editor {
+    /// Open the undo tree of the active buffer
+    pub fn show_undo_tree(&mut self) {
+        let source = self.active_buffer();
+        if self.undo_tree_views.contains_key(&source) {
+            return;
+        }
+        let is_virtual = self
+            .buffer_metadata
+            .get(&source)
+            .is_some_and(|meta| meta.is_virtual());
+        if is_virtual || self.is_editing_disabled() {
+            self.set_status_message("No undo history for this buffer".to_string());
+            return;
+        }
+
+        let existing = self
+            .undo_tree_views
+            .iter()
+            .find(|(_, view)| view.source == source)
+            .map(|(id, _)| *id);
+        let buffer_id = match existing {
+            Some(buffer_id) => buffer_id,
+            None => {
+                let title = self.undo_tree_title(source);
+                let buffer_id = self.create_virtual_buffer(
+                    format!("*Undo Tree: {}*", title),
+                    UNDO_TREE_MODE.to_string(),
+                    true,
+                );
+                if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                    state.editing_disabled = true;
+                    state.margins.set_line_numbers(false);
+                }
+                buffer_id
+            }
+        };
+
+        let log = self.active_event_log();
+        let current = log.state_of(log.position());
+        self.undo_tree_views.insert(
+            buffer_id,
+            UndoTreeView {
+                source,
+                selected: current,
+            },
+        );
+        self.set_active_buffer(buffer_id);
+        self.render_undo_tree(buffer_id);
+        self.move_cursor_to_undo_state(buffer_id, current);
+    }
+
+    fn undo_tree_title(&self, source: BufferId) -> String {
+        self.buffer_metadata
+            .get(&source)
+            .map(|meta| meta.display_name.clone())
+            .unwrap_or_default()
+    }
+
+    /// Rewrite an undo tree buffer from its source's event log
+    fn render_undo_tree(&mut self, buffer_id: BufferId) {
+        let Some(view) = self.undo_tree_views.get(&buffer_id) else {
+            return;
+        };
+        let (source, selected) = (view.source, view.selected);
+        let (Some(log), Some(state)) = (self.event_logs.get(&source), self.buffers.get(&source))
+        else {
+            return;
+        };
+
+        // Materialize the selected state on a copy; the source buffer is untouched
+        let preview = state.buffer.to_string().map(|mut text| {
+            let jump = log.jump_events(selected);
+            if let Some(base) = jump.base {
+                text = (*base).clone();
+            }
+            for event in &jump.events {
+                undo_tree::apply_to_text(&mut text, event);
+            }
+            text
+        });
+        let entries = undo_tree::render(
+            log,
+            &self.undo_tree_title(source),
+            now_ms(),
+            selected,
+            preview.as_deref(),
+        );
+        if let Err(e) = self.set_virtual_buffer_content(buffer_id, entries) {
+            tracing::warn!("Failed to update undo tree buffer: {}", e);
+        }
+    }
+
+    /// Start offsets of the state lines of an undo tree buffer
+    fn undo_state_lines(&self, buffer_id: BufferId) -> Vec<(usize, UndoPosition)> {
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return Vec::new();
+        };
+        state
+            .text_properties
+            .all()
+            .iter()
+            .filter_map(|property| {
+                property
+                    .get_as::<UndoPosition>(UNDO_TREE_NODE_PROPERTY)
+                    .map(|node| (property.start, node))
+            })
+            .collect()
+    }
+
+    fn move_cursor_to_undo_state(&mut self, buffer_id: BufferId, target: UndoPosition) {
+        let start = self
+            .undo_state_lines(buffer_id)
+            .into_iter()
+            .find(|&(_, state)| state == target)
+            .map(|(start, _)| start);
+        if let (Some(start), Some(state)) = (start, self.buffers.get_mut(&buffer_id)) {
+            state.cursors.primary_mut().position = start;
+            state.cursors.primary_mut().anchor = None;
+        }
+    }
+
+    /// Run a command bound in the undo tree buffer mode ("undo-tree-<command>")
+    pub(super) fn handle_undo_tree_command(&mut self, command: &str) {
+        let buffer_id = self.active_buffer();
+        if !self.undo_tree_views.contains_key(&buffer_id) {
+            return;
+        }
+        match command {
+            "next" => self.select_adjacent_undo_state(buffer_id, true),
+            "previous" => self.select_adjacent_undo_state(buffer_id, false),
+            "restore" => self.restore_selected_undo_state(buffer_id),
+            _ => tracing::warn!("Unknown undo tree command: {}", command),
+        }
+    }
+
+    fn select_adjacent_undo_state(&mut self, buffer_id: BufferId, forward: bool) {
+        let lines = self.undo_state_lines(buffer_id);
+        let cursor = self.buffers[&buffer_id].cursors.primary().position;
+        let target = match lines.iter().rposition(|&(start, _)| start <= cursor) {
+            Some(line) if forward => lines.get(line + 1),
+            Some(line) => line.checked_sub(1).and_then(|line| lines.get(line)),
+            None => lines.first(),
+        };
+        let Some(&(_, target)) = target else {
+            return;
+        };
+        if let Some(view) = self.undo_tree_views.get_mut(&buffer_id) {
+            view.selected = target;
+        }
+        self.render_undo_tree(buffer_id);
+        self.move_cursor_to_undo_state(buffer_id, target);
+    }
+
+    fn restore_selected_undo_state(&mut self, buffer_id: BufferId) {
+        let Some(source) = self.undo_tree_views.get(&buffer_id).map(|view| view.source) else {
+            return;
+        };
+        // The cursor may have been moved without selecting (e.g. with the mouse)
+        let lines = self.undo_state_lines(buffer_id);
+        let cursor = self.buffers[&buffer_id].cursors.primary().position;
+        let Some(&(_, target)) = lines.iter().rev().find(|&&(start, _)| start <= cursor) else {
+            return;
+        };
+        if !self.buffers.contains_key(&source) {
+            self.set_status_message("The buffer of this undo tree was closed".to_string());
+            return;
+        }
+        self.set_active_buffer(source);
+        self.jump_to_undo_state(target);
+        if let Err(e) = self.close_buffer(buffer_id) {
+            tracing::warn!("Failed to close undo tree buffer: {}", e);
+        }
+    }
+
+    /// Move the active buffer to another state of its undo tree
+    fn jump_to_undo_state(&mut self, target: UndoPosition) {
+        let jump = self.active_event_log_mut().jump_to(target);
+        self.apply_undo_jump(jump);
+
+        // Put the cursor where the restored edit was made
+        let edit = target.and_then(|index| {
+            undo_tree::edit_position(&self.active_event_log().entries()[index].event)
+        });
+        if let Some(position) = edit {
+            let state = self.active_state();
+            let cursor = state.cursors.primary();
+            let event = Event::MoveCursor {
+                cursor_id: state.cursors.primary_id(),
+                old_position: cursor.position,
+                new_position: position.min(state.buffer.len()),
+                old_anchor: cursor.anchor,
+                new_anchor: None,
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            };
+            self.apply_event_to_active_buffer(&event);
+        }
+        self.update_modified_from_event_log();
+    }
+
+    fn apply_undo_jump(&mut self, jump: UndoJump) {
+        if let Some(base) = jump.base {
+            let cursor_id = self.active_state().cursors.primary_id();
+            let len = self.active_state().buffer.len();
+            let deleted_text = self.active_state_mut().get_text_range(0, len);
+            let load = Event::Batch {
+                events: vec![
+                    Event::Delete {
+                        range: 0..len,
+                        deleted_text,
+                        cursor_id,
+                    },
+                    Event::Insert {
+                        position: 0,
+                        text: (*base).clone(),
+                        cursor_id,
+                    },
+                ],
+                description: "Load undo snapshot".to_string(),
+            };
+            self.apply_event_to_active_buffer(&load);
+        }
+        for event in &jump.events {
+            self.apply_event_to_active_buffer(event);
+        }
+    }
+
+    /// Jump `millis` back (or forward, with `later`) in the active buffer's history
+    pub(super) fn undo_by_time(&mut self, millis: u64, later: bool) {
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        let offset = i64::try_from(millis).unwrap_or(i64::MAX);
+        let log = self.active_event_log();
+        let current = log.state_of(log.position());
+        let target = log.position_by_time(if later { offset } else { -offset });
+        if target == current {
+            self.set_status_message(if later {
+                "Already at newest change".to_string()
+            } else {
+                "Already at oldest change".to_string()
+            });
+            return;
+        }
+
+        self.jump_to_undo_state(target);
+        let message = match self.active_event_log().timestamp(target) {
+            Some(time) => format!(
+                "Restored change from {}",
+                undo_tree::format_age(now_ms().saturating_sub(time))
+            ),
+            None => "Restored original content".to_string(),
+        };
+        self.set_status_message(message);
+    }
+
+    /// Store snapshots for buffers that made enough edits since their last one
+    pub(super) fn store_undo_snapshots(&mut self) {
+        for (buffer_id, log) in self.event_logs.iter_mut() {
+            if !log.snapshot_due() {
+                continue;
+            }
+            // Large files are loaded lazily: copying them would load them whole
+            let content = self
+                .buffers
+                .get(buffer_id)
+                .filter(|state| {
+                    !state.buffer.is_large_file() && log.snapshot_fits(state.buffer.len())
+                })
+                .and_then(|state| state.buffer.to_string());
+            if let Some(content) = content {
+                log.store_snapshot(content);
+            }
+        }
+    }
+}
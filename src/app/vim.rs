@@ -0,0 +1,1543 @@
+//! Vim modal editing: running the commands parsed by `input::vim`
+//!
+//! Keys of file buffers reach `handle_vim_key` while the vim keymap is active. The
+//! buffer's vim mode picks the buffer mode whose bindings are tried first (`i`, `p`,
+//! `u`, `:`...); other keys go through the operator/motion grammar of
+//! [`VimState`](crate::input::vim::VimState). In insert mode only Escape is handled
+//! here, every other key is edited by the regular keymap.
+//!
+//! Motions and operators work on a [`VimText`]: the lines around the cursor that the
+//! command can reach, read with the buffer's line iterator, so that a key costs the
+//! same in a large file as in a small one.
+
+use std::io;
+use std::ops::Range;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::Editor;
+use crate::input::keybindings::Action;
+use crate::input::vim::{
+    block_ranges, first_non_blank, last_line, line_end, line_index, line_range, line_start,
+    line_start_of, motion_target, next_char_pos, normal_position, operator_range, parse_ex_command,
+    prev_char_pos, text_object_range, visual_range, ExAddress, ExCommand, ExRange, Motion,
+    ObjectKind, Operator, RegisterKind, Target, TextObject, VimAction, VimKeyResult, VimMode,
+    VimRange, VimRegister,
+};
+use crate::model::event::{CursorId, Event};
+use crate::view::overlay::{Overlay, OverlayFace, OverlayNamespace};
+use crate::view::prompt::PromptType;
+
+/// Lines read on each side of the lines a command works on
+const CONTEXT_LINES: usize = 1;
+
+/// Lines a word motion may move over per word (runs of blank lines)
+const WORD_LINES: usize = 20;
+
+/// Lines searched on each side of the cursor by `%`, `{`, `}` and the block and
+/// paragraph text objects
+const SEARCH_LINES: usize = 2000;
+
+/// Estimated line length given to the buffer's line iterator
+const LINE_LENGTH_ESTIMATE: usize = 80;
+
+/// Overlay namespace of the visual selection
+fn visual_namespace() -> OverlayNamespace {
+    OverlayNamespace::from_string("vim-visual".to_string())
+}
+
+/// "1 line" or "3 lines"
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Whole lines of the active buffer that a command works on
+///
+/// The helpers of `input::vim` take positions in `text`; `local` and `global`
+/// convert from and to buffer positions. The text ends where the buffer does only
+/// when it reaches the end of the buffer.
+struct VimText {
+    text: String,
+    /// Buffer position of the start of `text`
+    offset: usize,
+}
+
+impl VimText {
+    /// Position in `text` of the buffer position `pos`
+    fn local(&self, pos: usize) -> usize {
+        pos.saturating_sub(self.offset).min(self.text.len())
+    }
+
+    /// Buffer position of the position `pos` in `text`
+    fn global(&self, pos: usize) -> usize {
+        self.offset + pos
+    }
+
+    fn global_range(&self, range: Range<usize>) -> Range<usize> {
+        self.global(range.start)..self.global(range.end)
+    }
+
+    fn global_edits(&self, edits: Vec<(Range<usize>, String)>) -> Vec<(Range<usize>, String)> {
+        edits
+            .into_iter()
+            .map(|(range, text)| (self.global_range(range), text))
+            .collect()
+    }
+}
+
+/// Lines on each side of the cursor that `motion` repeated `count` times can reach
+fn motion_context(motion: Motion, count: Option<usize>) -> usize {
+    let n = count.unwrap_or(1).max(1);
+    match motion {
+        Motion::Left
+        | Motion::Right
+        | Motion::LineStart
+        | Motion::FirstNonBlank
+        | Motion::FindChar { .. }
+        | Motion::FileStart
+        | Motion::FileEnd => CONTEXT_LINES,
+        Motion::Up
+        | Motion::Down
+        | Motion::LineEnd
+        | Motion::NextLineStart
+        | Motion::PrevLineStart => n.saturating_add(CONTEXT_LINES),
+        Motion::WordForward { .. } | Motion::WordBackward { .. } | Motion::WordEnd { .. } => {
+            n.saturating_mul(WORD_LINES).min(SEARCH_LINES)
+        }
+        Motion::ParagraphForward | Motion::ParagraphBackward | Motion::MatchPair => SEARCH_LINES,
+    }
+}
+
+/// Lines on each side of the cursor that `object` can span
+fn object_context(object: TextObject) -> usize {
+    match object.kind {
+        ObjectKind::Word { .. } | ObjectKind::Quote(_) => CONTEXT_LINES,
+        _ => SEARCH_LINES,
+    }
+}
+
+impl Editor {
+    /// Vim mode of the active buffer
+    pub(super) fn vim_mode(&self) -> VimMode {
+        VimMode::from_mode_name(&self.active_state().mode)
+    }
+
+    fn set_vim_mode(&mut self, mode: VimMode) {
+        let current = self.vim_mode();
+        if current.is_visual() && !mode.is_visual() {
+            // Remember the selection for `'<,'>`
+            let first = self.vim_line_number(self.vim.visual_start);
+            let last = self.vim_line_number(self.vim_cursor());
+            self.vim.visual_lines = Some((first.min(last), first.max(last)));
+        }
+        if current != mode {
+            self.apply_event_to_active_buffer(&Event::ChangeMode {
+                mode: mode.mode_name().to_string(),
+            });
+        }
+    }
+
+    /// The lines from `context` lines before the one containing `from` to `context`
+    /// lines after the one containing `to`
+    fn vim_window(&mut self, from: usize, to: usize, context: usize) -> VimText {
+        let (from, to) = (from.min(to), from.max(to));
+        let buffer = &mut self.active_state_mut().buffer;
+        let mut lines = buffer.line_iterator(from, LINE_LENGTH_ESTIMATE);
+        for _ in 0..context {
+            if lines.prev().is_none() {
+                break;
+            }
+        }
+        let offset = lines.current_position();
+
+        let mut lines = buffer.line_iterator(offset, LINE_LENGTH_ESTIMATE);
+        let mut text = String::new();
+        let mut after = 0;
+        while let Some((start, line)) = lines.next() {
+            if start > to {
+                if after == context {
+                    break;
+                }
+                after += 1;
+            }
+            text.push_str(&line);
+        }
+        VimText { text, offset }
+    }
+
+    /// Zero-based number of the line containing `pos` (estimated in large files)
+    fn vim_line_number(&self, pos: usize) -> usize {
+        self.active_state().buffer.get_line_number(pos)
+    }
+
+    /// Zero-based number of the last line (a final newline doesn't start a line)
+    fn vim_last_line(&mut self) -> usize {
+        let state = self.active_state_mut();
+        let len = state.buffer.len();
+        let newlines = state.buffer.get_line_number(len);
+        if len > 0 && state.get_text_range(len - 1, len) == "\n" {
+            newlines.saturating_sub(1)
+        } else {
+            newlines
+        }
+    }
+
+    /// Start of the zero-based line `line`, or the end of the buffer past its last
+    /// line (estimated in large files)
+    fn vim_line_start(&mut self, line: usize) -> usize {
+        let buffer = &mut self.active_state_mut().buffer;
+        match buffer.line_count() {
+            Some(count) if line < count => buffer.line_start_offset(line).unwrap_or(0),
+            Some(_) => buffer.len(),
+            None => {
+                let estimate = line.saturating_mul(LINE_LENGTH_ESTIMATE).min(buffer.len());
+                buffer
+                    .line_iterator(estimate, LINE_LENGTH_ESTIMATE)
+                    .current_position()
+            }
+        }
+    }
+
+    /// First non-blank character of the line containing `pos`
+    fn vim_first_non_blank(&mut self, pos: usize) -> usize {
+        let window = self.vim_window(pos, pos, 0);
+        window.global(first_non_blank(&window.text, window.local(pos)))
+    }
+
+    /// Nearest position to `pos` the cursor may take in normal mode
+    fn vim_normal_position(&mut self, pos: usize) -> usize {
+        let window = self.vim_window(pos, pos, CONTEXT_LINES);
+        window.global(normal_position(&window.text, window.local(pos)))
+    }
+
+    /// Whole lines from the line containing `from` to the one containing `to`,
+    /// reading only those two lines
+    fn vim_line_range(&mut self, from: usize, to: usize) -> Range<usize> {
+        let (from, to) = (from.min(to), from.max(to));
+        let first = self.vim_window(from, from, 0);
+        let last = self.vim_window(to, to, 0);
+        let start = line_start(&first.text, first.local(from));
+        let end = line_range(&last.text, last.local(to), last.local(to)).end;
+        first.global(start)..last.global(end)
+    }
+
+    /// Characters from `from` to `to`, reading only the line of the later one
+    fn vim_visual_range(&mut self, from: usize, to: usize) -> Range<usize> {
+        let (start, end) = (from.min(to), from.max(to));
+        let window = self.vim_window(end, end, 0);
+        start..window.global(next_char_pos(&window.text, window.local(end)))
+    }
+
+    fn vim_cursor(&self) -> usize {
+        self.active_state().cursors.primary().position
+    }
+
+    /// Handle a key while the vim keymap is active
+    ///
+    /// Returns false for keys that should go through the regular keymap.
+    pub(super) fn handle_vim_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> io::Result<bool> {
+        // Shifted characters arrive as the character itself
+        let modifiers = if matches!(code, KeyCode::Char(_)) {
+            modifiers - KeyModifiers::SHIFT
+        } else {
+            modifiers
+        };
+        let key = KeyEvent::new(code, modifiers);
+        let mode = self.vim_mode();
+
+        if mode == VimMode::Insert {
+            if self.vim.is_recording_insert() {
+                self.vim.record_key(key);
+            }
+            return match self
+                .mode_registry
+                .resolve_keybinding(mode.mode_name(), code, modifiers)
+            {
+                Some(command) => {
+                    self.run_vim_command(&command, None)?;
+                    Ok(true)
+                }
+                None => Ok(false),
+            };
+        }
+
+        self.vim.record_key(key);
+        if !self.vim.is_awaiting_key() {
+            if let Some(command) =
+                self.mode_registry
+                    .resolve_keybinding(mode.mode_name(), code, modifiers)
+            {
+                let count = self.vim.take_count();
+                self.vim.reset();
+                self.run_vim_command(&command, count)?;
+                self.update_vim_visual_highlight();
+                return Ok(true);
+            }
+        }
+
+        let handled = match self.vim.handle_key(key, mode.is_visual()) {
+            VimKeyResult::Pending => {
+                if mode == VimMode::Normal && self.vim.is_operator_pending() {
+                    self.set_vim_mode(VimMode::OperatorPending);
+                }
+                true
+            }
+            VimKeyResult::Action(action) => {
+                if mode == VimMode::OperatorPending {
+                    self.set_vim_mode(VimMode::Normal);
+                }
+                let change = self.run_vim_action(action)?;
+                // A change that entered insert mode ends with the insert
+                if !self.vim.is_recording_insert() {
+                    self.vim.finish_command(change && !mode.is_visual());
+                }
+                true
+            }
+            VimKeyResult::Cancelled => {
+                self.vim.finish_command(false);
+                if mode == VimMode::OperatorPending {
+                    self.set_vim_mode(VimMode::Normal);
+                }
+                true
+            }
+            VimKeyResult::Unbound => {
+                self.vim.finish_command(false);
+                if mode == VimMode::OperatorPending {
+                    self.set_vim_mode(VimMode::Normal);
+                }
+                // Text must not be typed into the buffer outside insert mode
+                matches!(code, KeyCode::Char(_) | KeyCode::Tab)
+                    && !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            }
+        };
+        self.update_vim_visual_highlight();
+        Ok(handled)
+    }
+
+    /// Run a command bound in one of the vim buffer modes
+    fn run_vim_command(&mut self, command: &str, count: Option<usize>) -> io::Result<()> {
+        let mut change = false;
+        match command {
+            "vim-insert" => self.enter_vim_insert(self.vim_cursor()),
+            "vim-append" => {
+                let cursor = self.vim_cursor();
+                let window = self.vim_window(cursor, cursor, 0);
+                let text = &window.text;
+                let pos = window.local(cursor);
+                let pos = if pos < line_end(text, pos) {
+                    next_char_pos(text, pos)
+                } else {
+                    pos
+                };
+                self.enter_vim_insert(window.global(pos));
+            }
+            "vim-insert-line-start" => {
+                let pos = self.vim_first_non_blank(self.vim_cursor());
+                self.enter_vim_insert(pos);
+            }
+            "vim-append-line-end" => {
+                let cursor = self.vim_cursor();
+                let window = self.vim_window(cursor, cursor, 0);
+                self.enter_vim_insert(window.global(line_end(&window.text, window.local(cursor))));
+            }
+            "vim-open-below" | "vim-open-above" => {
+                let cursor = self.vim_cursor();
+                let window = self.vim_window(cursor, cursor, 0);
+                let text = &window.text;
+                let pos = window.local(cursor);
+                let indent_end = first_non_blank(text, pos);
+                let indent = text[line_start(text, pos)..indent_end].to_string();
+                let (at, inserted, cursor) = if command == "vim-open-below" {
+                    let end = window.global(line_end(text, pos));
+                    (end, format!("\n{}", indent), end + 1 + indent.len())
+                } else {
+                    let start = window.global(line_start(text, pos));
+                    (start, format!("{}\n", indent), start + indent.len())
+                };
+                self.vim_edit(vec![(at..at, inserted)], "Open line");
+                self.vim.start_insert();
+                self.set_vim_mode(VimMode::Insert);
+                self.vim_move_cursor(cursor, false);
+            }
+            "vim-visual" | "vim-visual-line" | "vim-visual-block" => {
+                let target = match command {
+                    "vim-visual" => VimMode::Visual,
+                    "vim-visual-line" => VimMode::VisualLine,
+                    _ => VimMode::VisualBlock,
+                };
+                let mode = self.vim_mode();
+                if mode == target {
+                    self.set_vim_mode(VimMode::Normal);
+                } else {
+                    if !mode.is_visual() {
+                        self.vim.visual_start = self.vim_cursor();
+                    }
+                    self.set_vim_mode(target);
+                }
+            }
+            "vim-visual-swap" => {
+                let cursor = self.vim_cursor();
+                let start = std::mem::replace(&mut self.vim.visual_start, cursor);
+                self.vim_move_cursor(start, false);
+            }
+            "vim-block-insert" | "vim-block-append" => {
+                self.vim_block_insert(command == "vim-block-append");
+            }
+            "vim-normal-mode" => self.leave_vim_mode(),
+            "vim-undo" | "vim-redo" => {
+                let action = if command == "vim-undo" {
+                    Action::Undo
+                } else {
+                    Action::Redo
+                };
+                for _ in 0..count.unwrap_or(1) {
+                    self.handle_action(action.clone())?;
+                }
+                self.clamp_vim_cursor();
+            }
+            "vim-put-after" | "vim-put-before" => {
+                change = self.vim_put(command == "vim-put-after", count.unwrap_or(1));
+            }
+            "vim-repeat" => {
+                self.vim.finish_command(false);
+                return self.vim_repeat(count);
+            }
+            "vim-ex" => {
+                let initial = if self.vim_mode().is_visual() {
+                    self.set_vim_mode(VimMode::Normal);
+                    "'<,'>".to_string()
+                } else {
+                    String::new()
+                };
+                self.start_prompt_with_initial_text(
+                    ":".to_string(),
+                    PromptType::VimCommand,
+                    initial,
+                );
+            }
+            _ => {
+                // Commands added to the vim modes by name (e.g. by plugins)
+                let commands = self.command_registry.read().unwrap().get_all();
+                let action = commands
+                    .iter()
+                    .find(|c| c.name == command)
+                    .map(|c| c.action.clone())
+                    .unwrap_or_else(|| Action::PluginAction(command.to_string()));
+                drop(commands);
+                self.vim.finish_command(false);
+                return self.handle_action(action);
+            }
+        }
+        if !self.vim.is_recording_insert() {
+            self.vim.finish_command(change);
+        }
+        Ok(())
+    }
+
+    fn enter_vim_insert(&mut self, pos: usize) {
+        self.vim_move_cursor(pos, false);
+        self.vim.start_insert();
+        self.set_vim_mode(VimMode::Insert);
+    }
+
+    /// Escape: back to normal mode from insert, visual or operator-pending mode
+    fn leave_vim_mode(&mut self) {
+        self.vim.reset();
+        if self.vim_mode() == VimMode::Insert {
+            self.vim.finish_insert();
+            if self.active_state().cursors.count() > 1 {
+                let _ = self.handle_action(Action::RemoveSecondaryCursors);
+            }
+            self.set_vim_mode(VimMode::Normal);
+            // The cursor goes back onto the last inserted character
+            let cursor = self.vim_cursor();
+            let window = self.vim_window(cursor, cursor, 0);
+            let pos = window.local(cursor);
+            if pos > line_start(&window.text, pos) {
+                self.vim_move_cursor(window.global(prev_char_pos(&window.text, pos)), false);
+            }
+        } else {
+            self.set_vim_mode(VimMode::Normal);
+        }
+        self.clamp_vim_cursor();
+    }
+
+    /// Move the primary cursor, dropping any selection
+    ///
+    /// `sticky_column` is the column `j` and `k` aim for (0 when unset).
+    fn vim_move_cursor_sticky(&mut self, pos: usize, sticky_column: usize) {
+        let state = self.active_state();
+        let cursor = *state.cursors.primary();
+        let event = Event::MoveCursor {
+            cursor_id: state.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: pos,
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: sticky_column,
+        };
+        self.active_event_log_mut().append(event.clone());
+        self.apply_event_to_active_buffer(&event);
+    }
+
+    fn vim_move_cursor(&mut self, pos: usize, keep_sticky_column: bool) {
+        let sticky_column = if keep_sticky_column {
+            self.active_state().cursors.primary().sticky_column
+        } else {
+            0
+        };
+        self.vim_move_cursor_sticky(pos, sticky_column);
+    }
+
+    /// Keep the cursor on a character outside insert mode
+    fn clamp_vim_cursor(&mut self) {
+        if self.vim_mode() == VimMode::Insert {
+            return;
+        }
+        let pos = self.vim_cursor();
+        let clamped = self.vim_normal_position(pos);
+        if clamped != pos {
+            self.vim_move_cursor(clamped, true);
+        }
+    }
+
+    /// Replace ranges of the active buffer (given in the current text) as one undo step
+    ///
+    /// Text inserted at the same position ends up in the order of `edits`.
+    fn vim_edit(&mut self, mut edits: Vec<(Range<usize>, String)>, description: &str) {
+        edits.reverse();
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        let cursor_id = self.active_state().cursors.primary_id();
+        let mut events = Vec::new();
+        for (range, text) in edits {
+            if !range.is_empty() {
+                let deleted_text = self
+                    .active_state_mut()
+                    .get_text_range(range.start, range.end);
+                events.push(Event::Delete {
+                    range: range.clone(),
+                    deleted_text,
+                    cursor_id,
+                });
+            }
+            if !text.is_empty() {
+                events.push(Event::Insert {
+                    position: range.start,
+                    text,
+                    cursor_id,
+                });
+            }
+        }
+        if events.is_empty() {
+            return;
+        }
+        let batch = Event::Batch {
+            events,
+            description: description.to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+    }
+
+    /// Range of a text object around `pos`, with the lines it is in; `if`, `ac`,
+    /// `ia`... are looked up in the syntax tree
+    fn vim_text_object_range(
+        &mut self,
+        pos: usize,
+        object: TextObject,
+        count: Option<usize>,
+    ) -> Option<(VimText, VimRange)> {
+        match object.kind {
+            ObjectKind::Syntax(kind) => {
+                let (range, linewise) = self.syntax_object_range(kind, object.inner, pos)?;
+                let window = self.vim_window(range.start, range.end, CONTEXT_LINES);
+                let range = window.local(range.start)..window.local(range.end);
+                Some((window, VimRange { range, linewise }))
+            }
+            _ => {
+                let window = self.vim_window(pos, pos, object_context(object));
+                let range = text_object_range(&window.text, window.local(pos), object, count)?;
+                Some((window, range))
+            }
+        }
+    }
+
+    /// The lines an operator with `motion` from `pos` works on, with the motion and
+    /// count to apply to them
+    ///
+    /// `gg` and `G` become a line number in the lines read.
+    fn vim_motion_window(
+        &mut self,
+        pos: usize,
+        motion: Motion,
+        count: Option<usize>,
+    ) -> (VimText, Motion, Option<usize>) {
+        match motion {
+            Motion::FileStart | Motion::FileEnd => {
+                let target = self.vim_file_line_start(motion, count);
+                let window = self.vim_window(pos, target, CONTEXT_LINES);
+                let line = line_index(&window.text, window.local(target));
+                (window, Motion::FileStart, Some(line + 1))
+            }
+            _ => (
+                self.vim_window(pos, pos, motion_context(motion, count)),
+                motion,
+                count,
+            ),
+        }
+    }
+
+    /// Start of the line `gg` or `G` goes to
+    fn vim_file_line_start(&mut self, motion: Motion, count: Option<usize>) -> usize {
+        let last = self.vim_last_line();
+        let line = match count {
+            Some(count) => count.saturating_sub(1),
+            None if motion == Motion::FileStart => 0,
+            None => last,
+        };
+        self.vim_line_start(line.min(last))
+    }
+
+    /// Run a command completed by the grammar; returns whether it changed the buffer
+    fn run_vim_action(&mut self, action: VimAction) -> io::Result<bool> {
+        let cursor = self.vim_cursor();
+        match action {
+            VimAction::Move { motion, count } => {
+                self.vim_motion(cursor, motion, count);
+                Ok(false)
+            }
+            VimAction::Operate {
+                operator,
+                target,
+                count,
+            } => {
+                if operator != Operator::Yank && self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                    return Ok(false);
+                }
+                let range = match target {
+                    Target::TextObject(object) => self.vim_text_object_range(cursor, object, count),
+                    Target::Motion(motion) => {
+                        let (window, motion, count) = self.vim_motion_window(cursor, motion, count);
+                        let pos = window.local(cursor);
+                        operator_range(&window.text, pos, operator, Target::Motion(motion), count)
+                            .map(|range| (window, range))
+                    }
+                    Target::Line => {
+                        let lines = count.unwrap_or(1).max(1).saturating_add(CONTEXT_LINES);
+                        let window = self.vim_window(cursor, cursor, lines);
+                        let pos = window.local(cursor);
+                        operator_range(&window.text, pos, operator, target, count)
+                            .map(|range| (window, range))
+                    }
+                };
+                let Some((window, range)) = range else {
+                    return Ok(false);
+                };
+                if range.range.is_empty() && !range.linewise {
+                    return Ok(false);
+                }
+                let end = window.global(range.range.end);
+                self.vim_operate(&window, operator, range);
+                if operator == Operator::ToggleCase && target == Target::Motion(Motion::Right) {
+                    // `~` steps over the characters it toggled
+                    let target = self.vim_normal_position(end);
+                    self.vim_move_cursor(target, false);
+                }
+                Ok(operator != Operator::Yank)
+            }
+            VimAction::OperateSelection { operator } => {
+                if operator != Operator::Yank && self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                    return Ok(false);
+                }
+                let mode = self.vim_mode();
+                let start = self.vim.visual_start;
+                self.set_vim_mode(VimMode::Normal);
+                let window = self.vim_window(start, cursor, CONTEXT_LINES);
+                let (start, pos) = (window.local(start), window.local(cursor));
+                let text = &window.text;
+                if mode == VimMode::VisualBlock {
+                    let ranges = block_ranges(text, start, pos);
+                    self.vim_operate_block(&window, operator, ranges);
+                } else {
+                    let range = if mode == VimMode::VisualLine {
+                        VimRange {
+                            range: line_range(text, start, pos),
+                            linewise: true,
+                        }
+                    } else {
+                        VimRange {
+                            range: visual_range(text, start, pos),
+                            linewise: false,
+                        }
+                    };
+                    self.vim_operate(&window, operator, range);
+                }
+                Ok(operator != Operator::Yank)
+            }
+            VimAction::Select { object, count } => {
+                // Extend the selection over the object around the cursor
+                if let Some((window, range)) = self.vim_text_object_range(cursor, object, count) {
+                    if !range.range.is_empty() {
+                        self.vim.visual_start = window.global(range.range.start);
+                        if range.linewise && self.vim_mode() == VimMode::Visual {
+                            self.set_vim_mode(VimMode::VisualLine);
+                        }
+                        let last = prev_char_pos(&window.text, range.range.end);
+                        self.vim_move_cursor(window.global(last), false);
+                    }
+                }
+                Ok(false)
+            }
+            VimAction::Replace { ch, count } => {
+                let window = self.vim_window(cursor, cursor, 0);
+                let text = &window.text;
+                let pos = window.local(cursor);
+                let end = line_end(text, pos);
+                let chars = text[pos..end].chars().count();
+                if count > chars || self.is_editing_disabled() {
+                    return Ok(false);
+                }
+                let replaced_end = text[pos..end]
+                    .char_indices()
+                    .nth(count)
+                    .map_or(end, |(i, _)| pos + i);
+                let replacement = ch.to_string().repeat(count);
+                let last = cursor + replacement.len() - ch.len_utf8();
+                self.vim_edit(
+                    vec![(cursor..window.global(replaced_end), replacement)],
+                    "Replace",
+                );
+                self.vim_move_cursor(last, false);
+                Ok(true)
+            }
+            VimAction::Join { count } => Ok(self.vim_join(cursor, count.max(2))),
+        }
+    }
+
+    fn vim_motion(&mut self, cursor: usize, motion: Motion, count: Option<usize>) {
+        let visual = self.vim_mode().is_visual();
+        match motion {
+            Motion::Up | Motion::Down => {
+                // Like the regular up/down keys: aim for the sticky byte column
+                let n = count.unwrap_or(1).max(1);
+                let window = self.vim_window(cursor, cursor, motion_context(motion, count));
+                let text = &window.text;
+                let pos = window.local(cursor);
+                let line = line_index(text, pos);
+                let target_line = if motion == Motion::Up {
+                    if line == 0 {
+                        return;
+                    }
+                    line.saturating_sub(n)
+                } else {
+                    if line >= last_line(text) {
+                        return;
+                    }
+                    (line + n).min(last_line(text))
+                };
+                let sticky = self.active_state().cursors.primary().sticky_column;
+                let goal = if sticky > 0 {
+                    sticky
+                } else {
+                    pos - line_start(text, pos)
+                };
+                let Some(start) = line_start_of(text, target_line) else {
+                    return;
+                };
+                let mut target = start + goal.min(line_end(text, start) - start);
+                while !text.is_char_boundary(target) {
+                    target -= 1;
+                }
+                let target = if visual {
+                    target
+                } else {
+                    normal_position(text, target)
+                };
+                self.vim_move_cursor_sticky(window.global(target), goal);
+            }
+            Motion::FileStart | Motion::FileEnd => {
+                let start = self.vim_file_line_start(motion, count);
+                let target = self.vim_first_non_blank(start);
+                let target = if visual {
+                    target
+                } else {
+                    self.vim_normal_position(target)
+                };
+                self.vim_move_cursor_sticky(target, 0);
+            }
+            _ => {
+                let window = self.vim_window(cursor, cursor, motion_context(motion, count));
+                let text = &window.text;
+                let Some(target) = motion_target(text, window.local(cursor), motion, count) else {
+                    return;
+                };
+                let target = if visual {
+                    target.min(text.len())
+                } else {
+                    normal_position(text, target)
+                };
+                // After `$`, `j` and `k` stay at the end of the line
+                let sticky = if motion == Motion::LineEnd {
+                    usize::MAX
+                } else {
+                    0
+                };
+                self.vim_move_cursor_sticky(window.global(target), sticky);
+            }
+        }
+    }
+
+    /// Apply an operator to a range of `window`
+    fn vim_operate(&mut self, window: &VimText, operator: Operator, range: VimRange) {
+        let text = &window.text;
+        let VimRange {
+            mut range,
+            linewise,
+        } = range;
+        let lines = line_index(text, range.end.saturating_sub(1).max(range.start))
+            - line_index(text, range.start)
+            + 1;
+        let mut yanked = text[range.clone()].to_string();
+        if linewise && !yanked.ends_with('\n') {
+            yanked.push('\n');
+        }
+        let kind = if linewise {
+            RegisterKind::Linewise
+        } else {
+            RegisterKind::Charwise
+        };
+
+        match operator {
+            Operator::Yank => {
+                self.vim_set_register(VimRegister { text: yanked, kind });
+                if lines > 2 {
+                    self.set_status_message(format!("{} yanked", plural(lines, "line")));
+                }
+                // `yiw` and `yb` leave the cursor at the start of the text; `yy` keeps it
+                if !linewise {
+                    let target = normal_position(text, range.start);
+                    self.vim_move_cursor(window.global(target), false);
+                }
+            }
+            Operator::Delete => {
+                self.vim_set_register(VimRegister { text: yanked, kind });
+                // Deleting the last line, which has no newline, deletes the one before it
+                // (`text` only ends without a newline at the end of the buffer)
+                if linewise && range.end == text.len() && range.start > 0 && !text.ends_with('\n') {
+                    range.start -= 1;
+                }
+                self.vim_edit(
+                    vec![(window.global_range(range.clone()), String::new())],
+                    "Delete",
+                );
+                let start = window.global(range.start);
+                let target = if linewise {
+                    let start = self.vim_normal_position(start);
+                    self.vim_first_non_blank(start)
+                } else {
+                    start
+                };
+                let target = self.vim_normal_position(target);
+                self.vim_move_cursor(target, false);
+                if linewise && lines > 2 {
+                    self.set_status_message(format!("{} fewer lines", lines));
+                }
+            }
+            Operator::Change => {
+                self.vim_set_register(VimRegister { text: yanked, kind });
+                let cursor = if linewise {
+                    // Keep the indentation of the first line, on a line of its own
+                    let indent_end = first_non_blank(text, range.start);
+                    let end = if text[range.clone()].ends_with('\n') {
+                        range.end - 1
+                    } else {
+                        range.end
+                    };
+                    self.vim_edit(
+                        vec![(window.global_range(indent_end..end), String::new())],
+                        "Change",
+                    );
+                    indent_end
+                } else {
+                    self.vim_edit(
+                        vec![(window.global_range(range.clone()), String::new())],
+                        "Change",
+                    );
+                    range.start
+                };
+                self.enter_vim_insert(window.global(cursor));
+            }
+            Operator::Indent | Operator::Outdent => {
+                let first = line_index(text, range.start);
+                let last = first + lines - 1;
+                let edits = self.vim_shift_lines(text, first, last, operator == Operator::Indent);
+                self.vim_edit(window.global_edits(edits), "Indent");
+                // Indenting doesn't move the start of the first line
+                let start = window.global(line_start_of(text, first).unwrap_or(0));
+                let target = self.vim_first_non_blank(start);
+                self.vim_move_cursor(target, false);
+                if lines > 2 {
+                    let sign = if operator == Operator::Indent {
+                        '>'
+                    } else {
+                        '<'
+                    };
+                    self.set_status_message(format!("{} {}ed 1 time", lines, sign));
+                }
+            }
+            Operator::Lowercase | Operator::Uppercase | Operator::ToggleCase => {
+                let changed = change_case(&text[range.clone()], operator);
+                self.vim_edit(
+                    vec![(window.global_range(range.clone()), changed)],
+                    "Change case",
+                );
+                let start = window.global(range.start);
+                let target = if linewise {
+                    self.vim_cursor().max(start)
+                } else {
+                    start
+                };
+                let target = self.vim_normal_position(target);
+                self.vim_move_cursor(target, false);
+            }
+        }
+    }
+
+    /// Apply an operator to a visual block (ranges of `window`)
+    fn vim_operate_block(
+        &mut self,
+        window: &VimText,
+        operator: Operator,
+        ranges: Vec<Range<usize>>,
+    ) {
+        let text = &window.text;
+        let Some(first) = ranges.first().cloned() else {
+            return;
+        };
+        let first_start = window.global(first.start);
+        let pieces: Vec<&str> = ranges.iter().map(|range| &text[range.clone()]).collect();
+        let register = VimRegister {
+            text: pieces.join("\n"),
+            kind: RegisterKind::Blockwise,
+        };
+        match operator {
+            Operator::Yank => {
+                self.vim_set_register(register);
+                self.vim_move_cursor(first_start, false);
+            }
+            Operator::Delete | Operator::Change => {
+                self.vim_set_register(register);
+                let edits = ranges
+                    .iter()
+                    .map(|range| (window.global_range(range.clone()), String::new()))
+                    .collect();
+                self.vim_edit(edits, "Delete block");
+                if operator == Operator::Change {
+                    // Type into every line of the block at once
+                    let mut removed = 0;
+                    let positions: Vec<usize> = ranges
+                        .iter()
+                        .map(|range| {
+                            let pos = window.global(range.start) - removed;
+                            removed += range.len();
+                            pos
+                        })
+                        .collect();
+                    self.vim_insert_at(&positions);
+                } else {
+                    let target = self.vim_normal_position(first_start);
+                    self.vim_move_cursor(target, false);
+                }
+            }
+            Operator::Indent | Operator::Outdent => {
+                let first_line = line_index(text, first.start);
+                let last_line = line_index(text, ranges.last().map_or(0, |r| r.start));
+                let edits =
+                    self.vim_shift_lines(text, first_line, last_line, operator == Operator::Indent);
+                self.vim_edit(window.global_edits(edits), "Indent");
+                let start = window.global(line_start_of(text, first_line).unwrap_or(0));
+                let target = self.vim_first_non_blank(start);
+                self.vim_move_cursor(target, false);
+            }
+            Operator::Lowercase | Operator::Uppercase | Operator::ToggleCase => {
+                let edits = ranges
+                    .iter()
+                    .map(|range| {
+                        (
+                            window.global_range(range.clone()),
+                            change_case(&text[range.clone()], operator),
+                        )
+                    })
+                    .collect();
+                self.vim_edit(edits, "Change case");
+                self.vim_move_cursor(first_start, false);
+            }
+        }
+    }
+
+    /// `I` and `A` in visual block mode: insert on every line of the block
+    fn vim_block_insert(&mut self, append: bool) {
+        let cursor = self.vim_cursor();
+        let visual_start = self.vim.visual_start;
+        self.set_vim_mode(VimMode::Normal);
+        let window = self.vim_window(visual_start, cursor, 0);
+        let text = &window.text;
+        let (start, pos) = (window.local(visual_start), window.local(cursor));
+        let column = |p: usize| text[line_start(text, p)..p].chars().count();
+        let (left, right) = {
+            let (a, b) = (column(start), column(pos));
+            (a.min(b), a.max(b) + 1)
+        };
+        let first = line_index(text, start.min(pos));
+        let last = line_index(text, start.max(pos));
+
+        // `A` pads short lines with spaces up to the block's right edge; `I` skips them
+        let mut padding = Vec::new();
+        let mut positions = Vec::new();
+        let mut added = 0;
+        for line in first..=last {
+            let Some(start) = line_start_of(text, line) else {
+                continue;
+            };
+            let end = line_end(text, start);
+            let chars = text[start..end].chars().count();
+            let target_column = if append { right } else { left };
+            if chars < target_column {
+                if !append {
+                    continue;
+                }
+                let pad = " ".repeat(target_column - chars);
+                padding.push((window.global(end)..window.global(end), pad.clone()));
+                added += pad.len();
+                positions.push(window.global(end) + added);
+            } else {
+                let at = text[start..end]
+                    .char_indices()
+                    .nth(target_column)
+                    .map_or(end, |(i, _)| start + i);
+                positions.push(window.global(at) + added);
+            }
+        }
+        self.vim_edit(padding, "Pad block");
+        self.vim_insert_at(&positions);
+    }
+
+    /// Insert mode with a cursor at each position (in the current text)
+    fn vim_insert_at(&mut self, positions: &[usize]) {
+        let Some((&first, rest)) = positions.split_first() else {
+            return;
+        };
+        self.enter_vim_insert(first);
+        let next_id = self
+            .active_state()
+            .cursors
+            .ids()
+            .iter()
+            .map(|id| id.0)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let events: Vec<Event> = rest
+            .iter()
+            .zip(next_id..)
+            .map(|(&position, id)| Event::AddCursor {
+                cursor_id: CursorId(id),
+                position,
+                anchor: None,
+            })
+            .collect();
+        if !events.is_empty() {
+            let batch = Event::Batch {
+                events,
+                description: "Block insert".to_string(),
+            };
+            self.active_event_log_mut().append(batch.clone());
+            self.apply_event_to_active_buffer(&batch);
+        }
+    }
+
+    /// Edits indenting or outdenting lines `first..=last` of `text` by one level
+    fn vim_shift_lines(
+        &self,
+        text: &str,
+        first: usize,
+        last: usize,
+        indent: bool,
+    ) -> Vec<(Range<usize>, String)> {
+        let tab_size = self.config.editor.tab_size;
+        (first..=last)
+            .filter_map(|line| {
+                let start = line_start_of(text, line)?;
+                let end = line_end(text, start);
+                if indent {
+                    (end > start).then(|| (start..start, " ".repeat(tab_size)))
+                } else if text[start..end].starts_with('\t') {
+                    Some((start..start + 1, String::new()))
+                } else {
+                    let spaces =
+                        text[start..end].len() - text[start..end].trim_start_matches(' ').len();
+                    (spaces > 0).then(|| (start..start + spaces.min(tab_size), String::new()))
+                }
+            })
+            .collect()
+    }
+
+    /// Store cut or yanked text in the unnamed register, and in the one chosen
+    /// with `"` (`+` and `*` are the clipboard)
+    fn vim_set_register(&mut self, register: VimRegister) {
+        match self.vim.take_register_name() {
+            Some('+' | '*') => self.clipboard.copy(register.text.clone()),
+            Some(name) => {
+                self.clipboard.set_register(name, &register.text);
+            }
+            None => {}
+        }
+        self.vim.register = Some(register);
+    }
+
+    /// `p` and `P`; returns whether anything was put
+    fn vim_put(&mut self, after: bool, count: usize) -> bool {
+        let register = match self.vim.take_register_name() {
+            Some('+' | '*') => self.clipboard.paste().map(VimRegister::from_text),
+            Some(name) => self
+                .clipboard
+                .register(name)
+                .map(|text| VimRegister::from_text(text.to_string())),
+            None => self.vim.register.clone(),
+        };
+        let Some(register) = register else {
+            self.set_status_message("Nothing in register".to_string());
+            return false;
+        };
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return false;
+        }
+        let cursor = self.vim_cursor();
+        match register.kind {
+            RegisterKind::Charwise => {
+                let window = self.vim_window(cursor, cursor, 0);
+                let (text, pos) = (&window.text, window.local(cursor));
+                let at = if after && pos < line_end(text, pos) {
+                    next_char_pos(text, pos)
+                } else {
+                    pos
+                };
+                let at = window.global(at);
+                let inserted = register.text.repeat(count);
+                let end = at + inserted.len();
+                self.vim_edit(vec![(at..at, inserted)], "Put");
+                let window = self.vim_window(end, end, CONTEXT_LINES);
+                let last = prev_char_pos(&window.text, window.local(end));
+                let target = normal_position(&window.text, last);
+                self.vim_move_cursor(window.global(target), false);
+            }
+            RegisterKind::Linewise => {
+                let window = self.vim_window(cursor, cursor, 0);
+                let (text, pos) = (&window.text, window.local(cursor));
+                let lines = register.text.repeat(count);
+                let (at, inserted, first) = if !after {
+                    let start = window.global(line_start(text, pos));
+                    (start, lines, start)
+                } else if line_end(text, pos) == text.len() && !text.ends_with('\n') {
+                    // After the last line, which has no newline of its own
+                    let end = window.global(text.len());
+                    let body = lines.strip_suffix('\n').unwrap_or(&lines);
+                    (end, format!("\n{}", body), end + 1)
+                } else {
+                    let next = window.global(line_end(text, pos) + 1);
+                    (next, lines, next)
+                };
+                self.vim_edit(vec![(at..at, inserted)], "Put");
+                let target = self.vim_first_non_blank(first);
+                self.vim_move_cursor(target, false);
+            }
+            RegisterKind::Blockwise => {
+                // The lines the pieces go on, and whether the buffer ends among them
+                let pieces: Vec<&str> = register.text.split('\n').collect();
+                let window = self.vim_window(cursor, cursor, pieces.len());
+                let (text, pos) = (&window.text, window.local(cursor));
+                let column = text[line_start(text, pos)..pos].chars().count()
+                    + usize::from(after && pos < line_end(text, pos));
+                let first_line = line_index(text, pos);
+                let mut edits = Vec::new();
+                // Lines past the end of the buffer are added
+                let mut added = String::new();
+                for (i, piece) in pieces.iter().enumerate() {
+                    let piece = piece.repeat(count);
+                    match line_start_of(text, first_line + i) {
+                        Some(start) if start < text.len() || i == 0 => {
+                            let end = line_end(text, start);
+                            let chars = text[start..end].chars().count();
+                            if chars < column {
+                                let pad = " ".repeat(column - chars);
+                                edits.push((end..end, format!("{}{}", pad, piece)));
+                            } else {
+                                let at = text[start..end]
+                                    .char_indices()
+                                    .nth(column)
+                                    .map_or(end, |(i, _)| start + i);
+                                edits.push((at..at, piece));
+                            }
+                        }
+                        _ => {
+                            if added.is_empty() && !text.ends_with('\n') {
+                                added.push('\n');
+                            }
+                            added.push_str(&format!("{}{}\n", " ".repeat(column), piece));
+                        }
+                    }
+                }
+                if !added.is_empty() {
+                    edits.push((text.len()..text.len(), added));
+                }
+                // The pieces go after the start of the first line, which stays put
+                let line_begin = window.global(line_start(text, pos));
+                self.vim_edit(window.global_edits(edits), "Put block");
+                let window = self.vim_window(line_begin, line_begin, 0);
+                let text = &window.text;
+                let start = window.local(line_begin);
+                let at = text[start..line_end(text, start)]
+                    .char_indices()
+                    .nth(column)
+                    .map_or(line_end(text, start), |(i, _)| start + i);
+                let target = self.vim_normal_position(window.global(at));
+                self.vim_move_cursor(target, false);
+            }
+        }
+        true
+    }
+
+    /// `J`: join `count` lines; returns whether any were joined
+    fn vim_join(&mut self, cursor: usize, count: usize) -> bool {
+        let window = self.vim_window(cursor, cursor, count);
+        let text = &window.text;
+        let pos = window.local(cursor);
+        let first = line_index(text, pos);
+        let last = (first + count - 1).min(last_line(text));
+        if last == first || self.is_editing_disabled() {
+            return false;
+        }
+        let mut edits = Vec::new();
+        let mut cursor = pos;
+        let mut removed = 0;
+        for line in first..last {
+            let Some(start) = line_start_of(text, line) else {
+                break;
+            };
+            let end = line_end(text, start);
+            let next = end + 1;
+            let next_content = first_non_blank(text, next);
+            let current = text[start..end].trim_end_matches([' ', '\t']);
+            let trailing = start + current.len();
+            let next_text = &text[next_content..line_end(text, next)];
+            let separator =
+                if current.is_empty() || next_text.is_empty() || next_text.starts_with(')') {
+                    ""
+                } else {
+                    " "
+                };
+            edits.push((trailing..next_content, separator.to_string()));
+            cursor = trailing - removed;
+            removed += next_content - trailing - separator.len();
+        }
+        self.vim_edit(window.global_edits(edits), "Join lines");
+        let target = self.vim_normal_position(window.global(cursor));
+        self.vim_move_cursor(target, false);
+        true
+    }
+
+    /// `.`: replay the keys of the last change
+    fn vim_repeat(&mut self, count: Option<usize>) -> io::Result<()> {
+        let keys = self.vim.repeat_keys(count);
+        self.vim.replaying = true;
+        let result = keys
+            .into_iter()
+            .try_for_each(|key| self.handle_key(key.code, key.modifiers));
+        self.vim.replaying = false;
+        result
+    }
+
+    /// Highlight the visual selection
+    fn update_vim_visual_highlight(&mut self) {
+        let ns = visual_namespace();
+        let mode = self.vim_mode();
+        let ranges = if mode.is_visual() {
+            let start = self.vim.visual_start.min(self.active_state().buffer.len());
+            let pos = self.vim_cursor();
+            match mode {
+                VimMode::VisualLine => vec![self.vim_line_range(start, pos)],
+                VimMode::VisualBlock => {
+                    let window = self.vim_window(start, pos, 0);
+                    block_ranges(&window.text, window.local(start), window.local(pos))
+                        .into_iter()
+                        .map(|range| window.global_range(range))
+                        .collect()
+                }
+                _ => vec![self.vim_visual_range(start, pos)],
+            }
+        } else {
+            Vec::new()
+        };
+        let style = ratatui::style::Style::default().bg(self.theme.selection_bg);
+        let state = self.active_state_mut();
+        state.overlays.clear_namespace(&ns, &mut state.marker_list);
+        for range in ranges {
+            let overlay = Overlay::with_namespace(
+                &mut state.marker_list,
+                range,
+                OverlayFace::Style { style },
+                ns.clone(),
+            )
+            .with_priority_value(20);
+            state.overlays.add(overlay);
+        }
+    }
+
+    /// Zero-based line of an ex address
+    fn vim_ex_line(&mut self, address: ExAddress) -> Result<usize, String> {
+        let last = self.vim_last_line();
+        let line = match address {
+            ExAddress::Current => self.vim_line_number(self.vim_cursor()),
+            ExAddress::Last => last,
+            ExAddress::Line(n) => n.saturating_sub(1),
+            ExAddress::VisualStart | ExAddress::VisualEnd => {
+                let (first, last) = self.vim.visual_lines.ok_or("Mark not set")?;
+                if address == ExAddress::VisualStart {
+                    first
+                } else {
+                    last
+                }
+            }
+        };
+        Ok(line.min(last))
+    }
+
+    fn vim_ex_range(&mut self, range: ExRange) -> Result<(usize, usize), String> {
+        let start = self.vim_ex_line(range.start)?;
+        let end = self.vim_ex_line(range.end)?;
+        Ok((start.min(end), start.max(end)))
+    }
+
+    /// Run a `:` command
+    pub(super) fn run_vim_ex_command(&mut self, input: &str) -> io::Result<()> {
+        let command = match parse_ex_command(input) {
+            Ok(command) => command,
+            Err(e) => {
+                self.set_status_message(e);
+                return Ok(());
+            }
+        };
+        match command {
+            ExCommand::Write => self.handle_action(Action::Save)?,
+            ExCommand::Quit => self.handle_action(Action::Quit)?,
+            ExCommand::WriteQuit => {
+                self.handle_action(Action::Save)?;
+                if !self.active_state().buffer.is_modified() {
+                    self.handle_action(Action::Quit)?;
+                }
+            }
+            ExCommand::GotoLine(address) => match self.vim_ex_line(address) {
+                Ok(line) => {
+                    let start = self.vim_line_start(line);
+                    let target = self.vim_first_non_blank(start);
+                    self.vim_move_cursor(target, false);
+                }
+                Err(e) => self.set_status_message(e),
+            },
+            ExCommand::Substitute {
+                range,
+                pattern,
+                replacement,
+                global,
+                ignore_case,
+            } => {
+                let (first, last) = match self.vim_ex_range(range) {
+                    Ok(lines) => lines,
+                    Err(e) => {
+                        self.set_status_message(e);
+                        return Ok(());
+                    }
+                };
+                self.vim_substitute(first, last, &pattern, &replacement, global, ignore_case);
+            }
+            ExCommand::Normal { range, keys } => {
+                let (first, last) = match self.vim_ex_range(range) {
+                    Ok(lines) => lines,
+                    Err(e) => {
+                        self.set_status_message(e);
+                        return Ok(());
+                    }
+                };
+                self.vim_normal_on_lines(first, last, &keys)?;
+            }
+            ExCommand::Earlier(millis) => self.undo_by_time(millis, false),
+            ExCommand::Later(millis) => self.undo_by_time(millis, true),
+            ExCommand::Filter {
+                range: None,
+                command,
+            } => self.show_shell_command_output(&command),
+            ExCommand::Filter {
+                range: Some(range),
+                command,
+            } => {
+                let (first, last) = match self.vim_ex_range(range) {
+                    Ok(lines) => lines,
+                    Err(e) => {
+                        self.set_status_message(e);
+                        return Ok(());
+                    }
+                };
+                let start = self.vim_line_start(first);
+                let end = self.vim_line_start(last + 1);
+                self.filter_through_shell_command(&command, start..end);
+            }
+        }
+        Ok(())
+    }
+
+    /// `:s` on lines `first..=last`
+    fn vim_substitute(
+        &mut self,
+        first: usize,
+        last: usize,
+        pattern: &str,
+        replacement: &str,
+        global: bool,
+        ignore_case: bool,
+    ) {
+        if self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+        let regex = match regex::RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(e) => {
+                self.set_status_message(format!("Invalid pattern: {}", e));
+                return;
+            }
+        };
+
+        // Only the lines substituted in are read; line `first` is line 0 of them
+        let (start, end) = (self.vim_line_start(first), self.vim_line_start(last));
+        let window = self.vim_window(start, end, 0);
+        let text = &window.text;
+        let mut edits = Vec::new();
+        let mut substitutions = 0;
+        let mut last_changed = None;
+        for line in 0..=last - first {
+            let Some(start) = line_start_of(text, line) else {
+                break;
+            };
+            let end = line_end(text, start);
+            let content = &text[start..end];
+            let matches = if global {
+                regex.find_iter(content).count()
+            } else {
+                usize::from(regex.is_match(content))
+            };
+            if matches == 0 {
+                continue;
+            }
+            let replaced = if global {
+                regex.replace_all(content, replacement)
+            } else {
+                regex.replace(content, replacement)
+            };
+            substitutions += matches;
+            last_changed = Some(edits.len());
+            edits.push((start..end, replaced.into_owned()));
+        }
+
+        let Some(last_index) = last_changed else {
+            self.set_status_message(format!("Pattern not found: {}", pattern));
+            return;
+        };
+        let changed_lines = edits.len();
+        // The cursor goes to the last line substituted, moved down by the line breaks
+        // substituted before it
+        let line = first
+            + line_index(text, edits[last_index].0.start)
+            + edits[..last_index]
+                .iter()
+                .map(|(_, replaced)| replaced.matches('\n').count())
+                .sum::<usize>();
+        self.vim_edit(window.global_edits(edits), "Substitute");
+        let start = self.vim_line_start(line);
+        let target = self.vim_first_non_blank(start);
+        self.vim_move_cursor(target, false);
+        self.set_status_message(format!(
+            "{} on {}",
+            plural(substitutions, "substitution"),
+            plural(changed_lines, "line")
+        ));
+    }
+
+    /// `:norm`: run normal mode keys at the start of each line
+    fn vim_normal_on_lines(&mut self, first: usize, last: usize, keys: &str) -> io::Result<()> {
+        let keys: Vec<KeyEvent> = keys
+            .chars()
+            .map(|c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+            .collect();
+        let mut line = first;
+        let mut remaining = last - first + 1;
+        let replaying = std::mem::replace(&mut self.vim.replaying, true);
+        while remaining > 0 {
+            let start = self.vim_line_start(line);
+            if start >= self.active_state().buffer.len() && line > 0 {
+                break;
+            }
+            self.set_vim_mode(VimMode::Normal);
+            self.vim.reset();
+            self.vim_move_cursor(start, false);
+            let lines_before = self.vim_last_line();
+            for key in &keys {
+                if let Err(e) = self.handle_key(key.code, key.modifiers) {
+                    self.vim.replaying = replaying;
+                    return Err(e);
+                }
+            }
+            // Unfinished commands end like Escape
+            self.leave_vim_mode();
+            // Lines added or removed by the keys shift the following lines
+            let delta = self.vim_last_line() as isize - lines_before as isize;
+            line = (line as isize + 1 + delta).max(0) as usize;
+            remaining -= 1;
+        }
+        self.vim.replaying = replaying;
+        Ok(())
+    }
+}
+
+/// Text with its case changed by a case operator
+fn change_case(text: &str, operator: Operator) -> String {
+    match operator {
+        Operator::Lowercase => text.to_lowercase(),
+        Operator::Uppercase => text.to_uppercase(),
+        _ => text
+            .chars()
+            .flat_map(|c| {
+                let toggled: Vec<char> = if c.is_uppercase() {
+                    c.to_lowercase().collect()
+                } else {
+                    c.to_uppercase().collect()
+                };
+                toggled
+            })
+            .collect(),
+    }
+}
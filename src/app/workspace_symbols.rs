@@ -0,0 +1,405 @@
+//! Workspace symbol search ("Go to Symbol in Workspace")
+//!
+//! Every edit of the prompt input sends a `workspace/symbol` query to each running
+//! language server that advertises `workspaceSymbolProvider`. Queries still in flight
+//! when the input changes again are cancelled with `$/cancelRequest`. Results from all
+//! servers are merged, ranked with the fuzzy matcher and shown as prompt suggestions;
+//! the chosen symbol is opened through the same path as the plugin `openFile` API.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use lsp_types::{OneOf, SymbolKind, WorkspaceSymbolResponse};
+
+use super::{uri_to_path, Editor};
+use crate::input::commands::Suggestion;
+use crate::input::fuzzy::fuzzy_match;
+use crate::primitives::outline::symbol_kind_name;
+use crate::services::lsp::client::LspFeature;
+use crate::view::prompt::{Prompt, PromptType};
+
+/// Upper bound on suggestions shown for a query; large workspaces return thousands
+const MAX_WORKSPACE_SYMBOL_SUGGESTIONS: usize = 200;
+
+/// A symbol returned by `workspace/symbol`, resolved to a file location
+#[derive(Debug, Clone)]
+pub(crate) struct WorkspaceSymbolEntry {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub container: Option<String>,
+    pub path: PathBuf,
+    /// 1-based line (None when the server only reported the file)
+    pub line: Option<usize>,
+    /// 1-based column
+    pub column: Option<usize>,
+}
+
+/// State of an open workspace symbol prompt
+#[derive(Debug, Default)]
+pub(crate) struct WorkspaceSymbolSearch {
+    /// Query the pending requests were sent for
+    query: String,
+    /// In-flight requests: editor request ID -> server language
+    pending: HashMap<u64, String>,
+    /// Merged results; may still belong to the previous query until a response arrives
+    entries: Vec<WorkspaceSymbolEntry>,
+    /// Whether `entries` were produced for an older query
+    stale: bool,
+}
+
+/// Convert a `workspace/symbol` response into entries with file locations
+pub(crate) fn workspace_symbol_entries(
+    response: WorkspaceSymbolResponse,
+) -> Vec<WorkspaceSymbolEntry> {
+    match response {
+        WorkspaceSymbolResponse::Flat(symbols) => symbols
+            .into_iter()
+            .filter_map(|symbol| {
+                let path = uri_to_path(&symbol.location.uri).ok()?;
+                let start = symbol.location.range.start;
+                Some(WorkspaceSymbolEntry {
+                    name: symbol.name,
+                    kind: symbol.kind,
+                    container: symbol.container_name,
+                    path,
+                    line: Some(start.line as usize + 1),
+                    column: Some(start.character as usize + 1),
+                })
+            })
+            .collect(),
+        WorkspaceSymbolResponse::Nested(symbols) => symbols
+            .into_iter()
+            .filter_map(|symbol| {
+                let (path, line, column) = match symbol.location {
+                    OneOf::Left(location) => {
+                        let start = location.range.start;
+                        (
+                            uri_to_path(&location.uri).ok()?,
+                            Some(start.line as usize + 1),
+                            Some(start.character as usize + 1),
+                        )
+                    }
+                    OneOf::Right(location) => (uri_to_path(&location.uri).ok()?, None, None),
+                };
+                Some(WorkspaceSymbolEntry {
+                    name: symbol.name,
+                    kind: symbol.kind,
+                    container: symbol.container_name,
+                    path,
+                    line,
+                    column,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Rank entries against the query, best first, returning indices into `entries`
+///
+/// Servers already filter by the query, but each uses its own matching rules, so
+/// merged results are re-ranked with the same fuzzy matcher as the command palette.
+pub(crate) fn rank_workspace_symbols(query: &str, entries: &[WorkspaceSymbolEntry]) -> Vec<usize> {
+    let mut scored: Vec<(usize, i32)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let result = fuzzy_match(query, &entry.name);
+            result.matched.then_some((index, result.score))
+        })
+        .collect();
+
+    // Prefer shorter names on equal scores so an exact match beats longer ones;
+    // the stable sort keeps server order otherwise
+    scored.sort_by_key(|&(index, score)| (std::cmp::Reverse(score), entries[index].name.len()));
+    scored.truncate(MAX_WORKSPACE_SYMBOL_SUGGESTIONS);
+    scored.into_iter().map(|(index, _)| index).collect()
+}
+
+impl Editor {
+    /// Open the "Go to Symbol in Workspace" prompt
+    pub(super) fn start_workspace_symbol_prompt(&mut self) {
+        if self.workspace_symbol_languages().is_empty() {
+            self.set_status_message(
+                "No running language server supports workspace symbols".to_string(),
+            );
+            return;
+        }
+
+        self.workspace_symbol_search = Some(WorkspaceSymbolSearch::default());
+        self.prompt = Some(Prompt::new(
+            "Go to symbol in workspace: ".to_string(),
+            PromptType::WorkspaceSymbol,
+        ));
+    }
+
+    /// Languages of ready servers that advertise `workspaceSymbolProvider`
+    fn workspace_symbol_languages(&mut self) -> Vec<String> {
+        let Some(lsp) = self.lsp.as_mut() else {
+            return Vec::new();
+        };
+        let mut languages = lsp.running_servers();
+        languages.sort();
+        languages.retain(|language| {
+            lsp.is_server_ready(language)
+                && lsp
+                    .handle_for_feature(language, LspFeature::WorkspaceSymbols)
+                    .and_then(|handle| handle.capabilities())
+                    .is_some_and(|caps| {
+                        !matches!(
+                            caps.workspace_symbol_provider,
+                            None | Some(OneOf::Left(false))
+                        )
+                    })
+        });
+        languages
+    }
+
+    /// Send the current prompt input to every workspace symbol server
+    ///
+    /// Requests still pending for the previous input are cancelled first.
+    pub(super) fn update_workspace_symbol_query(&mut self, query: String) {
+        let Some(search) = self.workspace_symbol_search.as_mut() else {
+            return;
+        };
+        if search.query == query && !search.pending.is_empty() {
+            return;
+        }
+        let stale_requests: Vec<(u64, String)> = search.pending.drain().collect();
+        search.query = query.clone();
+        search.stale = true;
+
+        for (request_id, language) in stale_requests {
+            self.cancel_workspace_symbol_request(request_id, &language);
+        }
+
+        if query.trim().is_empty() {
+            if let Some(search) = self.workspace_symbol_search.as_mut() {
+                search.entries.clear();
+                search.stale = false;
+            }
+            self.refresh_workspace_symbol_suggestions();
+            return;
+        }
+
+        let mut sent = HashMap::new();
+        for language in self.workspace_symbol_languages() {
+            let request_id = self.next_lsp_request_id;
+            let Some(handle) = self
+                .lsp
+                .as_mut()
+                .and_then(|lsp| lsp.handle_for_feature(&language, LspFeature::WorkspaceSymbols))
+            else {
+                continue;
+            };
+            match handle.workspace_symbols(request_id, query.clone()) {
+                Ok(()) => {
+                    self.next_lsp_request_id += 1;
+                    sent.insert(request_id, language);
+                }
+                Err(e) => tracing::warn!("Failed to send workspace symbols request: {}", e),
+            }
+        }
+
+        if let Some(search) = self.workspace_symbol_search.as_mut() {
+            search.pending = sent;
+        }
+        self.refresh_workspace_symbol_suggestions();
+    }
+
+    /// Handle a `workspace/symbol` response from one server
+    pub(super) fn handle_workspace_symbols_response(
+        &mut self,
+        request_id: u64,
+        language: String,
+        result: Result<WorkspaceSymbolResponse, String>,
+    ) {
+        let Some(search) = self.workspace_symbol_search.as_mut() else {
+            return;
+        };
+        if search.pending.remove(&request_id).is_none() {
+            tracing::debug!("Ignoring stale workspace symbols response: {}", request_id);
+            return;
+        }
+
+        // The first answer for a new query replaces the previous query's results
+        if search.stale {
+            search.entries.clear();
+            search.stale = false;
+        }
+        match result {
+            Ok(response) => search.entries.extend(workspace_symbol_entries(response)),
+            Err(e) => tracing::warn!("Workspace symbols request to {} failed: {}", language, e),
+        }
+        self.refresh_workspace_symbol_suggestions();
+    }
+
+    /// Rebuild the prompt suggestions from the merged results
+    fn refresh_workspace_symbol_suggestions(&mut self) {
+        let Some(search) = self.workspace_symbol_search.as_ref() else {
+            return;
+        };
+        let Some(prompt) = self.prompt.as_mut() else {
+            return;
+        };
+        if prompt.prompt_type != PromptType::WorkspaceSymbol {
+            return;
+        }
+
+        let root = self.working_dir.as_path();
+        prompt.suggestions = rank_workspace_symbols(&prompt.input, &search.entries)
+            .into_iter()
+            .map(|index| {
+                let entry = &search.entries[index];
+                Suggestion {
+                    text: entry.name.clone(),
+                    description: Some(describe_location(entry, root)),
+                    value: Some(index.to_string()),
+                    disabled: false,
+                    keybinding: None,
+                    source: None,
+                }
+            })
+            .collect();
+        prompt.selected_suggestion = if prompt.suggestions.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+    }
+
+    /// Open the symbol chosen in the prompt (`input` is the index of the entry)
+    pub(super) fn confirm_workspace_symbol(&mut self, input: &str) {
+        let Some(search) = self.workspace_symbol_search.take() else {
+            return;
+        };
+        for (request_id, language) in &search.pending {
+            self.cancel_workspace_symbol_request(*request_id, language);
+        }
+
+        let Some(entry) = input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| search.entries.get(index))
+        else {
+            self.set_status_message("No matching symbol".to_string());
+            return;
+        };
+
+        if let Err(e) =
+            self.handle_open_file_at_location(entry.path.clone(), entry.line, entry.column)
+        {
+            self.set_status_message(format!("Failed to open {}: {}", entry.path.display(), e));
+        }
+    }
+
+    /// Drop the search state when the prompt is cancelled
+    pub(super) fn cancel_workspace_symbol_search(&mut self) {
+        if let Some(search) = self.workspace_symbol_search.take() {
+            for (request_id, language) in &search.pending {
+                self.cancel_workspace_symbol_request(*request_id, language);
+            }
+        }
+    }
+
+    fn cancel_workspace_symbol_request(&mut self, request_id: u64, language: &str) {
+        if let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.handle_for_feature(language, LspFeature::WorkspaceSymbols))
+        {
+            if let Err(e) = handle.cancel_request(request_id) {
+                tracing::warn!("Failed to cancel workspace symbols request: {}", e);
+            }
+        }
+    }
+}
+
+/// Suggestion description: project-relative location, then kind and container
+fn describe_location(entry: &WorkspaceSymbolEntry, root: &Path) -> String {
+    let path = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+    let location = match entry.line {
+        Some(line) => format!("{}:{}", path.display(), line),
+        None => path.display().to_string(),
+    };
+    let kind = symbol_kind_name(entry.kind);
+    match &entry.container {
+        Some(container) if !container.is_empty() => {
+            format!("{} ({} in {})", location, kind, container)
+        }
+        _ => format!("{} ({})", location, kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> WorkspaceSymbolEntry {
+        WorkspaceSymbolEntry {
+            name: name.to_string(),
+            kind: SymbolKind::STRUCT,
+            container: None,
+            path: PathBuf::from("/project/src/lib.rs"),
+            line: Some(1),
+            column: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_rank_workspace_symbols_merges_and_orders_by_score() {
+        // Entries as merged from two servers, in arrival order
+        let entries = vec![
+            entry("WidgetFactoryBuilder"),
+            entry("unrelated"),
+            entry("Widget"),
+            entry("SomeWidget"),
+        ];
+
+        let ranked = rank_workspace_symbols("Widget", &entries);
+        let names: Vec<&str> = ranked.iter().map(|&i| entries[i].name.as_str()).collect();
+        assert_eq!(names[0], "Widget");
+        assert!(!names.contains(&"unrelated"));
+        assert_eq!(names.len(), 3);
+    }
+
+    #[test]
+    fn test_workspace_symbol_entries_flat_and_nested() {
+        let json = serde_json::json!([
+            {
+                "name": "Widget",
+                "kind": 23,
+                "containerName": "shapes",
+                "location": {
+                    "uri": "file:///project/src/lib.rs",
+                    "range": {
+                        "start": { "line": 4, "character": 11 },
+                        "end": { "line": 4, "character": 17 }
+                    }
+                }
+            }
+        ]);
+        let flat: Vec<lsp_types::SymbolInformation> = serde_json::from_value(json).unwrap();
+        let entries = workspace_symbol_entries(WorkspaceSymbolResponse::Flat(flat));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, PathBuf::from("/project/src/lib.rs"));
+        assert_eq!(entries[0].line, Some(5));
+        assert_eq!(entries[0].column, Some(12));
+        assert_eq!(
+            describe_location(&entries[0], Path::new("/project")),
+            "src/lib.rs:5 (struct in shapes)"
+        );
+
+        // Servers may omit the range and only report the file
+        let json = serde_json::json!([
+            { "name": "main", "kind": 12, "location": { "uri": "file:///project/main.go" } }
+        ]);
+        let nested: Vec<lsp_types::WorkspaceSymbol> = serde_json::from_value(json).unwrap();
+        let entries = workspace_symbol_entries(WorkspaceSymbolResponse::Nested(nested));
+        assert_eq!(entries[0].line, None);
+        assert_eq!(
+            describe_location(&entries[0], Path::new("/project")),
+            "main.go (function)"
+        );
+    }
+}
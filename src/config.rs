@@ -1,4 +1,5 @@
-use crate::services::lsp::client::LspServerConfig;
+use crate::services::dap::client::{DebugAdapterConfig, DebugRequest};
+use crate::services::lsp::client::{LspLanguageConfig, LspServerConfig};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -36,7 +37,7 @@ pub struct Config {
     #[serde(default)]
     pub keybinding_maps: HashMap<String, KeymapConfig>,
 
-    /// Active keybinding map name (e.g., "default", "emacs", "vscode", or a custom name)
+    /// Active keybinding map name (e.g., "default", "emacs", "vscode", "vim", or a custom name)
     #[serde(default = "default_keybinding_map_name")]
     pub active_keybinding_map: String,
 
@@ -44,9 +45,13 @@ pub struct Config {
     #[serde(default)]
     pub languages: HashMap<String, LanguageConfig>,
 
-    /// LSP server configurations by language
+    /// LSP server configurations by language (one server, or a list of servers)
     #[serde(default)]
-    pub lsp: HashMap<String, LspServerConfig>,
+    pub lsp: HashMap<String, LspLanguageConfig>,
+
+    /// Debug adapter configurations by language
+    #[serde(default)]
+    pub debug: HashMap<String, DebugAdapterConfig>,
 
     /// Menu bar configuration
     #[serde(default)]
@@ -80,6 +85,10 @@ pub struct EditorConfig {
     #[serde(default = "default_false")]
     pub relative_line_numbers: bool,
 
+    /// Mark foldable lines in the gutter (folded lines are always marked)
+    #[serde(default = "default_true")]
+    pub show_fold_indicators: bool,
+
     /// Minimum lines to keep visible above/below cursor when scrolling
     #[serde(default = "default_scroll_offset")]
     pub scroll_offset: usize,
@@ -118,6 +127,18 @@ pub struct EditorConfig {
     #[serde(default = "default_true")]
     pub enable_inlay_hints: bool,
 
+    /// Whether to enable LSP semantic tokens (highlighting layered over syntax colors)
+    #[serde(default = "default_true")]
+    pub enable_semantic_tokens: bool,
+
+    /// Whether to show LSP code lenses (e.g. "Run test", reference counts) above lines
+    #[serde(default = "default_true")]
+    pub enable_code_lens: bool,
+
+    /// Lowest severity of LSP diagnostics shown at the end of their line ("off" to show none)
+    #[serde(default)]
+    pub inline_diagnostics: InlineDiagnostics,
+
     /// Whether to enable file recovery (Emacs-style auto-save)
     /// When enabled, buffers are periodically saved to recovery files
     /// so they can be recovered if the editor crashes.
@@ -131,6 +152,28 @@ pub struct EditorConfig {
     #[serde(default = "default_auto_save_interval")]
     pub auto_save_interval_secs: u32,
 
+    /// Whether to keep undo history across restarts
+    /// When enabled, a file's undo history is saved when its buffer is closed
+    /// and restored when the file is reopened, as long as the file is unchanged.
+    /// Default: false
+    #[serde(default = "default_false")]
+    pub persistent_undo: bool,
+
+    /// Maximum total size in bytes of stored undo histories
+    /// The oldest histories are removed once this is exceeded.
+    /// Default: 50MB
+    #[serde(default = "default_persistent_undo_max_bytes")]
+    pub persistent_undo_max_bytes: u64,
+
+    /// Seconds a shell command run on the selection may take before it is stopped
+    /// Default: 30 seconds
+    #[serde(default = "default_shell_command_timeout")]
+    pub shell_command_timeout_secs: u64,
+
+    /// Resource limits (memory and CPU) for shell commands run on the selection
+    #[serde(default)]
+    pub shell_command_limits: crate::services::process_limits::ProcessLimits,
+
     /// Number of bytes to look back/forward from the viewport for syntax highlighting context.
     /// Larger values improve accuracy for multi-line constructs (strings, comments, nested blocks)
     /// but may slow down highlighting for very large files.
@@ -192,6 +235,14 @@ fn default_auto_save_interval() -> u32 {
     2 // Auto-save every 2 seconds for fast recovery
 }
 
+fn default_persistent_undo_max_bytes() -> u64 {
+    50 * 1024 * 1024
+}
+
+fn default_shell_command_timeout() -> u64 {
+    30
+}
+
 fn default_highlight_context_bytes() -> usize {
     10_000 // 10KB context for accurate syntax highlighting
 }
@@ -207,6 +258,7 @@ impl Default for EditorConfig {
             auto_indent: true,
             line_numbers: true,
             relative_line_numbers: false,
+            show_fold_indicators: true,
             scroll_offset: default_scroll_offset(),
             syntax_highlighting: true,
             line_wrap: true,
@@ -215,8 +267,15 @@ impl Default for EditorConfig {
             large_file_threshold_bytes: default_large_file_threshold(),
             estimated_line_length: default_estimated_line_length(),
             enable_inlay_hints: true,
+            enable_semantic_tokens: true,
+            enable_code_lens: true,
+            inline_diagnostics: InlineDiagnostics::default(),
             recovery_enabled: true,
             auto_save_interval_secs: default_auto_save_interval(),
+            persistent_undo: false,
+            persistent_undo_max_bytes: default_persistent_undo_max_bytes(),
+            shell_command_timeout_secs: default_shell_command_timeout(),
+            shell_command_limits: crate::services::process_limits::ProcessLimits::default(),
             highlight_context_bytes: default_highlight_context_bytes(),
             mouse_hover_enabled: true,
             mouse_hover_delay_ms: default_mouse_hover_delay(),
@@ -224,6 +283,38 @@ impl Default for EditorConfig {
     }
 }
 
+/// Which diagnostics are shown at the end of their line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum InlineDiagnostics {
+    /// No inline diagnostics
+    Off,
+    /// Errors only
+    Error,
+    /// Errors and warnings
+    #[default]
+    Warning,
+    /// Errors, warnings and information
+    Info,
+    /// All diagnostics, including hints
+    Hint,
+}
+
+impl InlineDiagnostics {
+    /// Whether diagnostics of a severity are shown (no severity counts as an error)
+    pub fn shows(self, severity: Option<lsp_types::DiagnosticSeverity>) -> bool {
+        use lsp_types::DiagnosticSeverity;
+        let threshold = match self {
+            Self::Off => return false,
+            Self::Error => DiagnosticSeverity::ERROR,
+            Self::Warning => DiagnosticSeverity::WARNING,
+            Self::Info => DiagnosticSeverity::INFORMATION,
+            Self::Hint => DiagnosticSeverity::HINT,
+        };
+        severity.unwrap_or(DiagnosticSeverity::ERROR) <= threshold
+    }
+}
+
 /// File explorer configuration
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FileExplorerConfig {
@@ -358,6 +449,14 @@ pub struct LanguageConfig {
     /// If specified, this grammar will be used when highlighter is "textmate"
     #[serde(default)]
     pub textmate_grammar: Option<std::path::PathBuf>,
+
+    /// Format the buffer with the language server before saving
+    #[serde(default)]
+    pub format_on_save: bool,
+
+    /// Ask the language server to format after typing one of its trigger characters
+    #[serde(default)]
+    pub format_on_type: bool,
 }
 
 /// Preference for which syntax highlighting backend to use
@@ -427,6 +526,7 @@ impl Default for Config {
             active_keybinding_map: default_keybinding_map_name(),
             languages: Self::default_languages(),
             lsp: Self::default_lsp_config(),
+            debug: Self::default_debug_config(),
             menu: MenuConfig::default(),
         }
     }
@@ -505,6 +605,7 @@ impl Config {
     ///
     /// This is called after deserializing user config to ensure that:
     /// - Default LSP servers are present even if user only customizes one
+    /// - Default debug adapters are present even if user only customizes one
     /// - Default language configs are present even if user only customizes one
     ///
     /// User entries override defaults when keys collide.
@@ -518,6 +619,13 @@ impl Config {
             self.lsp.insert(key, value);
         }
 
+        // Merge debug adapter configs: start with defaults, overlay user entries
+        let user_debug = std::mem::take(&mut self.debug);
+        self.debug = defaults.debug;
+        for (key, value) in user_debug {
+            self.debug.insert(key, value);
+        }
+
         // Merge language configs: start with defaults, overlay user entries
         let user_languages = std::mem::take(&mut self.languages);
         self.languages = defaults.languages;
@@ -546,6 +654,7 @@ impl Config {
             "default" => include_str!("../keymaps/default.json"),
             "emacs" => include_str!("../keymaps/emacs.json"),
             "vscode" => include_str!("../keymaps/vscode.json"),
+            "vim" => include_str!("../keymaps/vim.json"),
             _ => return None,
         };
 
@@ -598,6 +707,28 @@ impl Config {
 
         all_bindings
     }
+
+    /// Whether a keymap is `base` or inherits from it
+    pub fn keymap_extends(&self, map_name: &str, base: &str) -> bool {
+        let mut visited = std::collections::HashSet::new();
+        let mut current = Some(map_name.to_string());
+        while let Some(name) = current {
+            if name == base {
+                return true;
+            }
+            if !visited.insert(name.clone()) {
+                return false;
+            }
+            current = self
+                .keybinding_maps
+                .get(&name)
+                .cloned()
+                .or_else(|| Self::load_builtin_keymap(&name))
+                .and_then(|keymap| keymap.inherits);
+        }
+        false
+    }
+
     /// Create default language configurations
     fn default_languages() -> HashMap<String, LanguageConfig> {
         let mut languages = HashMap::new();
@@ -611,6 +742,8 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
 
@@ -623,6 +756,8 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
 
@@ -635,6 +770,8 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
 
@@ -647,6 +784,8 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
 
@@ -659,6 +798,8 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
 
@@ -678,6 +819,8 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
 
@@ -690,6 +833,8 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
 
@@ -697,7 +842,7 @@ impl Config {
     }
 
     /// Create default LSP configurations
-    fn default_lsp_config() -> HashMap<String, LspServerConfig> {
+    fn default_lsp_config() -> HashMap<String, LspLanguageConfig> {
         let mut lsp = HashMap::new();
 
         // rust-analyzer (installed via rustup or package manager)
@@ -717,7 +862,23 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                // Inlay hints are shown, so ask for all of them
+                settings: Some(serde_json::json!({
+                    "rust-analyzer": {
+                        "inlayHints": {
+                            "typeHints": { "enable": true },
+                            "parameterHints": { "enable": true },
+                            "chainingHints": { "enable": true },
+                            "closureReturnTypeHints": { "enable": "always" }
+                        }
+                    }
+                })),
+                root_markers: vec!["Cargo.toml".to_string()],
+            }
+            .into(),
         );
 
         // pylsp (installed via pip)
@@ -730,7 +891,13 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec!["pyproject.toml".to_string(), "setup.py".to_string()],
+            }
+            .into(),
         );
 
         // typescript-language-server (installed via npm)
@@ -742,9 +909,14 @@ impl Config {
             auto_start: false,
             process_limits: crate::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec!["package.json".to_string(), "tsconfig.json".to_string()],
         };
-        lsp.insert("javascript".to_string(), ts_lsp.clone());
-        lsp.insert("typescript".to_string(), ts_lsp);
+        lsp.insert("javascript".to_string(), ts_lsp.clone().into());
+        lsp.insert("typescript".to_string(), ts_lsp.into());
 
         // vscode-html-languageserver-bin (installed via npm)
         lsp.insert(
@@ -756,7 +928,13 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec![],
+            }
+            .into(),
         );
 
         // vscode-css-languageserver-bin (installed via npm)
@@ -769,7 +947,13 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec![],
+            }
+            .into(),
         );
 
         // clangd (installed via package manager)
@@ -782,7 +966,13 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec![],
+            }
+            .into(),
         );
         lsp.insert(
             "cpp".to_string(),
@@ -793,7 +983,13 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec![],
+            }
+            .into(),
         );
 
         // gopls (installed via go install)
@@ -806,7 +1002,13 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec!["go.mod".to_string()],
+            }
+            .into(),
         );
 
         // vscode-json-languageserver (installed via npm)
@@ -819,7 +1021,13 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec![],
+            }
+            .into(),
         );
 
         // csharp-language-server (installed via dotnet tool install -g csharp-ls)
@@ -832,12 +1040,56 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec![],
+            }
+            .into(),
         );
 
         lsp
     }
 
+    /// Create default debug adapter configurations
+    fn default_debug_config() -> HashMap<String, DebugAdapterConfig> {
+        let mut debug = HashMap::new();
+
+        // lldb-dap (shipped with LLVM), debugging the package's binary
+        debug.insert(
+            "rust".to_string(),
+            DebugAdapterConfig {
+                command: "lldb-dap".to_string(),
+                args: vec![],
+                request: DebugRequest::Launch,
+                configuration: serde_json::json!({
+                    "program": "${workspaceFolder}/target/debug/${workspaceFolderBasename}",
+                    "cwd": "${workspaceFolder}",
+                }),
+                adapter_id: None,
+            },
+        );
+
+        // debugpy (installed via pip install debugpy), debugging the current file
+        debug.insert(
+            "python".to_string(),
+            DebugAdapterConfig {
+                command: "python3".to_string(),
+                args: vec!["-m".to_string(), "debugpy.adapter".to_string()],
+                request: DebugRequest::Launch,
+                configuration: serde_json::json!({
+                    "program": "${file}",
+                    "cwd": "${workspaceFolder}",
+                    "console": "internalConsole",
+                }),
+                adapter_id: Some("debugpy".to_string()),
+            },
+        );
+
+        debug
+    }
+
     /// Create default menu bar configuration
     fn default_menus() -> Vec<Menu> {
         vec![
@@ -924,6 +1176,13 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Undo Tree...".to_string(),
+                        action: "undo_tree".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Cut".to_string(),
@@ -1108,6 +1367,40 @@ impl Config {
                         checkbox: None,
                     },
                     MenuItem::Separator { separator: true },
+                    MenuItem::Submenu {
+                        label: "Folding".to_string(),
+                        items: vec![
+                            MenuItem::Action {
+                                label: "Toggle Fold".to_string(),
+                                action: "toggle_fold".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                            MenuItem::Action {
+                                label: "Fold All".to_string(),
+                                action: "fold_all".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                            MenuItem::Action {
+                                label: "Unfold All".to_string(),
+                                action: "unfold_all".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                            MenuItem::Action {
+                                label: "Fold to Level...".to_string(),
+                                action: "prompt_fold_level".to_string(),
+                                args: HashMap::new(),
+                                when: None,
+                                checkbox: None,
+                            },
+                        ],
+                    },
+                    MenuItem::Separator { separator: true },
                     MenuItem::Submenu {
                         label: "Terminal".to_string(),
                         items: vec![
@@ -1172,6 +1465,17 @@ impl Config {
                                 when: None,
                                 checkbox: None,
                             },
+                            MenuItem::Action {
+                                label: "Vim".to_string(),
+                                action: "switch_keybinding_map".to_string(),
+                                args: {
+                                    let mut map = HashMap::new();
+                                    map.insert("map".to_string(), serde_json::json!("vim"));
+                                    map
+                                },
+                                when: None,
+                                checkbox: None,
+                            },
                         ],
                     },
                 ],
@@ -1208,6 +1512,13 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Shrink Selection".to_string(),
+                        action: "shrink_selection".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Add Cursor Above".to_string(),
@@ -1257,6 +1568,20 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Go to Type Definition".to_string(),
+                        action: "lsp_goto_type_definition".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Go to Implementation".to_string(),
+                        action: "lsp_goto_implementation".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     MenuItem::Action {
                         label: "Find References".to_string(),
                         action: "lsp_references".to_string(),
@@ -1307,6 +1632,41 @@ impl Config {
                         when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Go to Declaration".to_string(),
+                        action: "lsp_goto_declaration".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Go to Type Definition".to_string(),
+                        action: "lsp_goto_type_definition".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Go to Implementation".to_string(),
+                        action: "lsp_goto_implementation".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Peek Definition".to_string(),
+                        action: "lsp_peek_definition".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Peek Implementation".to_string(),
+                        action: "lsp_peek_implementation".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
                     MenuItem::Action {
                         label: "Find References".to_string(),
                         action: "lsp_references".to_string(),
@@ -1343,6 +1703,62 @@ impl Config {
                         when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Run Code Lens".to_string(),
+                        action: "lsp_code_lens".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Format Document".to_string(),
+                        action: "lsp_format_document".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Format Selection".to_string(),
+                        action: "lsp_format_selection".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Go to Symbol in Workspace...".to_string(),
+                        action: "lsp_workspace_symbol".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Incoming Calls".to_string(),
+                        action: "lsp_incoming_calls".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Outgoing Calls".to_string(),
+                        action: "lsp_outgoing_calls".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Supertypes".to_string(),
+                        action: "lsp_supertypes".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Subtypes".to_string(),
+                        action: "lsp_subtypes".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Toggle Inlay Hints".to_string(),
@@ -1373,6 +1789,20 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Server Log".to_string(),
+                        action: "lsp_show_log".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Server Info".to_string(),
+                        action: "lsp_info".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                 ],
             },
             // Explorer menu (file explorer operations)
@@ -1623,6 +2053,11 @@ impl DirectoryContext {
         self.data_dir.join("recovery")
     }
 
+    /// Get the persistent undo history directory path
+    pub fn undo_history_dir(&self) -> std::path::PathBuf {
+        self.data_dir.join("undo")
+    }
+
     /// Get the sessions directory path
     pub fn sessions_dir(&self) -> std::path::PathBuf {
         self.data_dir.join("sessions")
@@ -1668,6 +2103,11 @@ impl DirectoryContext {
     pub fn plugins_dir(&self) -> std::path::PathBuf {
         self.config_dir.join("plugins")
     }
+
+    /// Get the user snippets directory path
+    pub fn snippets_dir(&self) -> std::path::PathBuf {
+        self.config_dir.join("snippets")
+    }
 }
 
 #[cfg(test)]
@@ -1756,7 +2196,10 @@ mod tests {
 
         // User's rust override should be present
         assert!(loaded.lsp.contains_key("rust"));
-        assert_eq!(loaded.lsp["rust"].command, "custom-rust-analyzer");
+        assert_eq!(
+            loaded.lsp["rust"].servers()[0].command,
+            "custom-rust-analyzer"
+        );
 
         // Default LSP servers should also be present (merged from defaults)
         assert!(
@@ -2,12 +2,15 @@
 
 use crate::input::keybindings::Action;
 use crate::model::buffer::Buffer;
-use crate::model::cursor::{Position2D, SelectionMode};
+use crate::model::cursor::{Cursor, Position2D, SelectionMode};
 use crate::model::event::{CursorId, Event};
+use crate::primitives::line_edit::{self, LineTransform, Lines};
+use crate::primitives::rectangle::Edit;
 use crate::primitives::word_navigation::{
     find_word_end, find_word_start, find_word_start_left, find_word_start_right,
 };
 use crate::state::EditorState;
+use crate::view::folding::skip_hidden;
 use std::ops::Range;
 
 /// Direction for block selection movement
@@ -181,6 +184,353 @@ fn max_cursor_position(buffer: &Buffer) -> usize {
     buffer.len()
 }
 
+/// Whole lines of the buffer read for the line commands
+struct LineWindow {
+    text: String,
+    /// Buffer offset of the text
+    offset: usize,
+}
+
+impl LineWindow {
+    /// Position in the text of a buffer position
+    fn local(&self, pos: usize) -> usize {
+        pos.saturating_sub(self.offset).min(self.text.len())
+    }
+
+    fn local_range(&self, range: Range<usize>) -> Range<usize> {
+        self.local(range.start)..self.local(range.end)
+    }
+}
+
+/// The buffer's whole text, for line commands on all lines
+fn whole_buffer(state: &mut EditorState) -> LineWindow {
+    let len = state.buffer.len();
+    LineWindow {
+        text: state.get_text_range(0, len),
+        offset: 0,
+    }
+}
+
+/// The lines of every cursor and selection, with the line above and the line
+/// below them, which the commands moving and joining lines reach
+fn cursor_lines_window(state: &mut EditorState, estimated_line_length: usize) -> LineWindow {
+    let (from, to) = state
+        .cursors
+        .iter()
+        .fold((usize::MAX, 0), |(from, to), (_, cursor)| {
+            let anchor = cursor.anchor.unwrap_or(cursor.position);
+            (
+                from.min(cursor.position.min(anchor)),
+                to.max(cursor.position.max(anchor)),
+            )
+        });
+    let mut lines = state.buffer.line_iterator(from, estimated_line_length);
+    lines.prev();
+    let offset = lines.current_position();
+
+    let mut lines = state.buffer.line_iterator(offset, estimated_line_length);
+    let mut text = String::new();
+    let mut below = false;
+    while let Some((start, line)) = lines.next() {
+        if start > to {
+            if below {
+                break;
+            }
+            below = true;
+        }
+        text.push_str(&line);
+    }
+    LineWindow { text, offset }
+}
+
+/// Events for edits of `window` in text order that don't overlap, later ones
+/// first so the ranges of earlier ones stay valid
+fn push_edits(state: &EditorState, window: &LineWindow, edits: &[Edit], events: &mut Vec<Event>) {
+    let cursor_id = state.cursors.primary_id();
+    for (range, new_text) in edits.iter().rev() {
+        let start = window.offset + range.start;
+        if !range.is_empty() {
+            events.push(Event::Delete {
+                range: start..window.offset + range.end,
+                deleted_text: window.text[range.clone()].to_string(),
+                cursor_id,
+            });
+        }
+        if !new_text.is_empty() {
+            events.push(Event::Insert {
+                position: start,
+                text: new_text.clone(),
+                cursor_id,
+            });
+        }
+    }
+}
+
+/// Move every cursor to the position and anchor `place` gives it
+fn push_cursor_moves(
+    state: &EditorState,
+    events: &mut Vec<Event>,
+    place: impl Fn(&Cursor) -> (usize, Option<usize>),
+) {
+    for (cursor_id, cursor) in state.cursors.iter() {
+        let (new_position, new_anchor) = place(cursor);
+        events.push(Event::MoveCursor {
+            cursor_id,
+            old_position: cursor.position,
+            new_position,
+            old_anchor: cursor.anchor,
+            new_anchor,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        });
+    }
+}
+
+/// Where a buffer position goes after edits of `window`
+fn map_buffer_position(window: &LineWindow, edits: &[Edit], pos: usize) -> usize {
+    window.offset + line_edit::map_position(edits, window.local(pos))
+}
+
+fn non_empty_selection(cursor: &Cursor) -> Option<Range<usize>> {
+    cursor.selection_range().filter(|range| !range.is_empty())
+}
+
+/// Lines of a cursor: those its selection touches, or the cursor's line
+fn cursor_lines(lines: &Lines, window: &LineWindow, cursor: &Cursor) -> (usize, usize) {
+    lines.span(
+        window.local_range(
+            cursor
+                .selection_range()
+                .unwrap_or(cursor.position..cursor.position),
+        ),
+    )
+}
+
+/// Swap the lines of every cursor with the line above (`up`) or below
+fn move_lines(
+    state: &mut EditorState,
+    events: &mut Vec<Event>,
+    up: bool,
+    estimated_line_length: usize,
+) {
+    let window = cursor_lines_window(state, estimated_line_length);
+    let lines = Lines::new(&window.text);
+    let newline_len = lines.newline().len() as isize;
+    let blocks = line_edit::merge_blocks(
+        state
+            .cursors
+            .iter()
+            .map(|(_, cursor)| cursor_lines(&lines, &window, cursor))
+            .collect(),
+        true,
+    );
+
+    let mut edits = Vec::new();
+    // Positions in each moved block and how far they move
+    let mut moved = Vec::new();
+    for (first, last) in blocks {
+        let mut block: Vec<String> = lines
+            .texts(first, last)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let positions = lines.start(first)..=lines.end_with_newline(last);
+        if up && first > 0 {
+            let above = lines.text(first - 1);
+            block.push(above.to_string());
+            edits.push(lines.replace(first - 1, last, &block));
+            moved.push((positions, -(above.len() as isize + newline_len)));
+        } else if !up && last + 1 < lines.count() {
+            let below = lines.text(last + 1);
+            block.insert(0, below.to_string());
+            edits.push(lines.replace(first, last + 1, &block));
+            moved.push((positions, below.len() as isize + newline_len));
+        }
+    }
+    if edits.is_empty() {
+        return;
+    }
+
+    push_edits(state, &window, &edits, events);
+    // Swapping lines keeps the text's length
+    let shift = |pos: usize| {
+        let local = window.local(pos);
+        match moved.iter().find(|(range, _)| range.contains(&local)) {
+            Some((_, delta)) => {
+                window.offset
+                    + (local as isize + delta).clamp(0, window.text.len() as isize) as usize
+            }
+            None => pos,
+        }
+    };
+    push_cursor_moves(state, events, |cursor| {
+        (shift(cursor.position), cursor.anchor.map(shift))
+    });
+}
+
+/// Insert a copy of every cursor's selection after it and select the copy, or
+/// copy the cursor's line below it and move onto the copy
+fn duplicate_lines(state: &mut EditorState, events: &mut Vec<Event>, estimated_line_length: usize) {
+    let window = cursor_lines_window(state, estimated_line_length);
+    let lines = Lines::new(&window.text);
+    let copies: Vec<(usize, String)> = state
+        .cursors
+        .iter()
+        .map(|(_, cursor)| match non_empty_selection(cursor) {
+            Some(range) => {
+                let range = window.local_range(range);
+                (range.end, window.text[range].to_string())
+            }
+            None => {
+                let line = lines.line_of(window.local(cursor.position));
+                (
+                    lines.end(line),
+                    format!("{}{}", lines.newline(), lines.text(line)),
+                )
+            }
+        })
+        .collect();
+
+    let mut edits: Vec<Edit> = copies
+        .iter()
+        .map(|(at, copy)| (*at..*at, copy.clone()))
+        .collect();
+    edits.sort_by_key(|(range, _)| range.start);
+    edits.dedup();
+    push_edits(state, &window, &edits, events);
+
+    // Each cursor moves past the copies before it, and onto its own copy
+    let shift = |pos: usize, own: &(usize, String)| {
+        let local = window.local(pos);
+        let before: usize = edits
+            .iter()
+            .filter(|(range, copy)| range.start < local && (range.start, copy) != (own.0, &own.1))
+            .map(|(_, copy)| copy.len())
+            .sum();
+        pos + before + own.1.len()
+    };
+    for ((cursor_id, cursor), own) in state.cursors.iter().zip(&copies) {
+        events.push(Event::MoveCursor {
+            cursor_id,
+            old_position: cursor.position,
+            new_position: shift(cursor.position, own),
+            old_anchor: cursor.anchor,
+            new_anchor: cursor.anchor.map(|anchor| shift(anchor, own)),
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        });
+    }
+}
+
+/// Join the lines of every cursor's selection, or its line and the next one
+fn join_lines(state: &mut EditorState, events: &mut Vec<Event>, estimated_line_length: usize) {
+    let window = cursor_lines_window(state, estimated_line_length);
+    let lines = Lines::new(&window.text);
+    let blocks = line_edit::merge_blocks(
+        state
+            .cursors
+            .iter()
+            .map(|(_, cursor)| {
+                let (first, last) = cursor_lines(&lines, &window, cursor);
+                (first, last.max(first + 1))
+            })
+            .filter(|&(_, last)| last < lines.count())
+            .collect(),
+        false,
+    );
+
+    let mut edits = Vec::new();
+    // Lines of each block and where its last join ends up
+    let mut joins = Vec::new();
+    for (first, last) in blocks {
+        let (joined, join_point) = line_edit::join(&lines.texts(first, last));
+        let edit = lines.replace(first, last, &[joined]);
+        joins.push((
+            lines.start(first)..=lines.end_with_newline(last),
+            join_point,
+        ));
+        edits.push(edit);
+    }
+    if edits.is_empty() {
+        return;
+    }
+
+    push_edits(state, &window, &edits, events);
+    push_cursor_moves(state, events, |cursor| {
+        // A cursor at the start of a block's first line isn't in the block before
+        let position = window.local(cursor.position);
+        let join = joins
+            .iter()
+            .rev()
+            .find(|(range, _)| range.contains(&position));
+        match join {
+            Some((range, join_point)) => (
+                window.offset + line_edit::map_position(&edits, *range.start()) + join_point,
+                None,
+            ),
+            None => (
+                map_buffer_position(&window, &edits, cursor.position),
+                cursor
+                    .anchor
+                    .map(|anchor| map_buffer_position(&window, &edits, anchor)),
+            ),
+        }
+    });
+}
+
+/// Rewrite the lines of every selection with `transform`, or all lines when
+/// nothing is selected
+fn transform_lines(
+    state: &mut EditorState,
+    events: &mut Vec<Event>,
+    transform: LineTransform,
+    estimated_line_length: usize,
+) {
+    let selected = state
+        .cursors
+        .iter()
+        .any(|(_, cursor)| non_empty_selection(cursor).is_some());
+    let window = if selected {
+        cursor_lines_window(state, estimated_line_length)
+    } else {
+        whole_buffer(state)
+    };
+    let lines = Lines::new(&window.text);
+    let selections: Vec<(usize, usize)> = state
+        .cursors
+        .iter()
+        .filter_map(|(_, cursor)| non_empty_selection(cursor))
+        .map(|range| lines.span(window.local_range(range)))
+        .collect();
+    let blocks = if selections.is_empty() {
+        vec![(0, lines.count() - 1)]
+    } else {
+        line_edit::merge_blocks(selections, false)
+    };
+
+    let mut edits = Vec::new();
+    for (first, last) in blocks {
+        let old = lines.texts(first, last);
+        let new = transform.apply(&old);
+        if new != old {
+            edits.push(lines.replace(first, last, &new));
+        }
+    }
+    if edits.is_empty() {
+        return;
+    }
+
+    push_edits(state, &window, &edits, events);
+    push_cursor_moves(state, events, |cursor| {
+        (
+            map_buffer_position(&window, &edits, cursor.position),
+            cursor
+                .anchor
+                .map(|anchor| map_buffer_position(&window, &edits, anchor)),
+        )
+    });
+}
+
 /// Convert an action into a sequence of events that can be applied to the editor state
 ///
 /// # Parameters
@@ -1538,6 +1888,65 @@ pub fn action_to_events(
             }
         }
 
+        Action::MoveLineUp => move_lines(state, &mut events, true, estimated_line_length),
+        Action::MoveLineDown => move_lines(state, &mut events, false, estimated_line_length),
+        Action::DuplicateLine => duplicate_lines(state, &mut events, estimated_line_length),
+        Action::JoinLines => join_lines(state, &mut events, estimated_line_length),
+        Action::SortLines => transform_lines(
+            state,
+            &mut events,
+            LineTransform::Sort,
+            estimated_line_length,
+        ),
+        Action::SortLinesNatural => transform_lines(
+            state,
+            &mut events,
+            LineTransform::SortNatural,
+            estimated_line_length,
+        ),
+        Action::SortLinesNumeric => transform_lines(
+            state,
+            &mut events,
+            LineTransform::SortNumeric,
+            estimated_line_length,
+        ),
+        Action::SortLinesCaseInsensitive => transform_lines(
+            state,
+            &mut events,
+            LineTransform::SortCaseInsensitive,
+            estimated_line_length,
+        ),
+        Action::SortLinesDescending => transform_lines(
+            state,
+            &mut events,
+            LineTransform::SortDescending,
+            estimated_line_length,
+        ),
+        Action::UniqueLines => transform_lines(
+            state,
+            &mut events,
+            LineTransform::Unique,
+            estimated_line_length,
+        ),
+        Action::ReverseLines => transform_lines(
+            state,
+            &mut events,
+            LineTransform::Reverse,
+            estimated_line_length,
+        ),
+        Action::ShuffleLines => transform_lines(
+            state,
+            &mut events,
+            LineTransform::Shuffle,
+            estimated_line_length,
+        ),
+        Action::DeleteBlankLines => transform_lines(
+            state,
+            &mut events,
+            LineTransform::DeleteBlank,
+            estimated_line_length,
+        ),
+
         Action::Recenter => {
             // Scroll so that the cursor is centered in the view
             // This is handled specially - we emit a Recenter event
@@ -1611,6 +2020,28 @@ pub fn action_to_events(
         | Action::Copy
         | Action::Cut
         | Action::Paste
+        | Action::PasteFromHistory
+        | Action::KillLine
+        | Action::KillWordForward
+        | Action::KillWordBackward
+        | Action::KillRegion
+        | Action::Yank
+        | Action::YankPop
+        | Action::CopyToRegister(_)
+        | Action::InsertRegister(_)
+        | Action::PromptCopyToRegister
+        | Action::PromptInsertRegister
+        | Action::RectangleKill
+        | Action::RectangleCopy
+        | Action::RectangleYank
+        | Action::RectangleOpen
+        | Action::RectangleReplace
+        | Action::RectangleInsert
+        | Action::RectangleNumberLines
+        | Action::RectangleToCursors
+        | Action::ShellCommandReplace
+        | Action::ShellCommandInsert
+        | Action::ShellCommandToBuffer
         | Action::AddCursorNextMatch
         | Action::AddCursorAbove
         | Action::AddCursorBelow
@@ -1624,6 +2055,9 @@ pub fn action_to_events(
         | Action::ToggleMaximizeSplit
         | Action::Undo
         | Action::Redo
+        | Action::ShowUndoTree
+        | Action::UndoToEarlierTime
+        | Action::RedoToLaterTime
         | Action::GoToMatchingBracket
         | Action::JumpToNextError
         | Action::JumpToPreviousError
@@ -1632,6 +2066,21 @@ pub fn action_to_events(
         | Action::IndentSelection
         | Action::DedentSelection
         | Action::ToggleComment
+        | Action::Fold
+        | Action::Unfold
+        | Action::ToggleFold
+        | Action::FoldAll
+        | Action::UnfoldAll
+        | Action::FoldLevel(_)
+        | Action::ShrinkSelection
+        | Action::SelectTextObject(_)
+        | Action::DeleteTextObject(_)
+        | Action::ChangeTextObject(_)
+        | Action::NextFunction
+        | Action::PreviousFunction
+        | Action::SwapArgumentNext
+        | Action::SwapArgumentPrevious
+        | Action::PromptFoldLevel
         | Action::SetBookmark(_)
         | Action::JumpToBookmark(_)
         | Action::ClearBookmark(_)
@@ -1706,15 +2155,44 @@ pub fn action_to_events(
         | Action::FileExplorerToggleGitignored
         | Action::LspCompletion
         | Action::LspGotoDefinition
+        | Action::LspGotoDeclaration
+        | Action::LspGotoTypeDefinition
+        | Action::LspGotoImplementation
+        | Action::LspPeekDefinition
+        | Action::LspPeekTypeDefinition
+        | Action::LspPeekImplementation
         | Action::LspReferences
         | Action::LspRename
         | Action::LspHover
         | Action::LspSignatureHelp
         | Action::LspCodeActions
+        | Action::LspCodeLens
+        | Action::LspFormatDocument
+        | Action::LspFormatSelection
+        | Action::LspWorkspaceSymbol
+        | Action::LspIncomingCalls
+        | Action::LspOutgoingCalls
+        | Action::LspSupertypes
+        | Action::LspSubtypes
         | Action::LspRestart
         | Action::LspStop
+        | Action::LspShowLog
+        | Action::LspInfo
+        | Action::LspToggleTrace
         | Action::ToggleInlayHints
         | Action::ToggleMouseHover
+        | Action::DebugStart
+        | Action::DebugStop
+        | Action::DebugContinue
+        | Action::DebugPause
+        | Action::DebugStepOver
+        | Action::DebugStepInto
+        | Action::DebugStepOut
+        | Action::DebugToggleBreakpoint
+        | Action::DebugShowVariables
+        | Action::DebugShowCallStack
+        | Action::DebugShowWatches
+        | Action::DebugAddWatch
         | Action::ToggleLineNumbers
         | Action::ToggleMouseCapture
         | Action::DumpConfig
@@ -1839,6 +2317,25 @@ pub fn action_to_events(
         }
     }
 
+    // Cursor movement steps over folded regions instead of landing inside them
+    if !state.folds.is_empty()
+        && !events
+            .iter()
+            .any(|e| matches!(e, Event::Insert { .. } | Event::Delete { .. }))
+    {
+        let hidden = state.folds.hidden_ranges(&state.buffer);
+        for event in &mut events {
+            if let Event::MoveCursor {
+                old_position,
+                new_position,
+                ..
+            } = event
+            {
+                *new_position = skip_hidden(&state.buffer, &hidden, *old_position, *new_position);
+            }
+        }
+    }
+
     Some(events)
 }
 
@@ -3046,4 +3543,104 @@ mod tests {
 
         assert_eq!(state.buffer.to_string().unwrap(), "(bc)");
     }
+
+    fn state_with_text(text: &str) -> EditorState {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        state.apply(&Event::Insert {
+            position: 0,
+            text: text.to_string(),
+            cursor_id: CursorId(0),
+        });
+        state
+    }
+
+    fn set_cursor(
+        state: &mut EditorState,
+        cursor_id: CursorId,
+        position: usize,
+        anchor: Option<usize>,
+    ) {
+        state.apply(&Event::MoveCursor {
+            cursor_id,
+            old_position: 0,
+            new_position: position,
+            old_anchor: None,
+            new_anchor: anchor,
+            old_sticky_column: 0,
+            new_sticky_column: 0,
+        });
+    }
+
+    fn apply_action(state: &mut EditorState, action: Action) {
+        for event in action_to_events(state, action, 4, false, 80, 24).unwrap() {
+            state.apply(&event);
+        }
+    }
+
+    #[test]
+    fn test_move_lines_with_multiple_cursors() {
+        let mut state = state_with_text("a\nb\nc\nd\n");
+        set_cursor(&mut state, CursorId(0), 2, None);
+        state.apply(&Event::AddCursor {
+            position: 6,
+            cursor_id: CursorId(1),
+            anchor: None,
+        });
+
+        apply_action(&mut state, Action::MoveLineUp);
+        assert_eq!(state.buffer.to_string().unwrap(), "b\na\nd\nc\n");
+        assert_eq!(state.cursors.get(CursorId(0)).unwrap().position, 0);
+        assert_eq!(state.cursors.get(CursorId(1)).unwrap().position, 4);
+
+        // The first line can't move up; the other cursor's line still does
+        apply_action(&mut state, Action::MoveLineUp);
+        assert_eq!(state.buffer.to_string().unwrap(), "b\nd\na\nc\n");
+    }
+
+    #[test]
+    fn test_duplicate_selection() {
+        let mut state = state_with_text("foo bar");
+        set_cursor(&mut state, CursorId(0), 3, Some(0));
+
+        apply_action(&mut state, Action::DuplicateLine);
+        assert_eq!(state.buffer.to_string().unwrap(), "foofoo bar");
+        let cursor = state.cursors.primary();
+        assert_eq!((cursor.anchor, cursor.position), (Some(3), 6));
+    }
+
+    #[test]
+    fn test_join_lines_of_comment() {
+        let mut state = state_with_text("    // first\n    // second\n    //   third\nx");
+        set_cursor(&mut state, CursorId(0), 30, Some(0));
+
+        apply_action(&mut state, Action::JoinLines);
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "    // first second third\nx"
+        );
+        assert_eq!(state.cursors.primary().position, 19);
+        assert_eq!(state.cursors.primary().anchor, None);
+    }
+
+    #[test]
+    fn test_sort_lines_in_selection() {
+        let mut state = state_with_text("z\nc\nb\na\n");
+        // From the start of "c" to the start of "a": "a" isn't sorted
+        set_cursor(&mut state, CursorId(0), 6, Some(2));
+
+        apply_action(&mut state, Action::SortLines);
+        assert_eq!(state.buffer.to_string().unwrap(), "z\nb\nc\na\n");
+
+        apply_action(&mut state, Action::SortLinesDescending);
+        assert_eq!(state.buffer.to_string().unwrap(), "z\nc\nb\na\n");
+    }
+
+    #[test]
+    fn test_delete_blank_lines_in_whole_buffer() {
+        let mut state = state_with_text("a\n\n  \nb\n\n");
+
+        apply_action(&mut state, Action::DeleteBlankLines);
+        assert_eq!(state.buffer.to_string().unwrap(), "a\nb\n");
+    }
 }
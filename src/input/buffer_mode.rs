@@ -91,6 +91,99 @@ impl ModeRegistry {
 
         registry.register(special_mode);
 
+        // Built-in mode for call and type hierarchy trees
+        let hierarchy_mode = BufferMode::new("hierarchy")
+            .with_parent("special")
+            .with_binding(KeyCode::Enter, KeyModifiers::NONE, "hierarchy-goto")
+            .with_binding(KeyCode::Tab, KeyModifiers::NONE, "hierarchy-toggle")
+            .with_binding(KeyCode::Right, KeyModifiers::NONE, "hierarchy-expand")
+            .with_binding(KeyCode::Left, KeyModifiers::NONE, "hierarchy-collapse");
+
+        registry.register(hierarchy_mode);
+
+        // Built-in mode for the debugger's variables, call stack and watch panels
+        let debug_mode = BufferMode::new("debug")
+            .with_parent("special")
+            .with_binding(KeyCode::Enter, KeyModifiers::NONE, "debug-select")
+            .with_binding(KeyCode::Tab, KeyModifiers::NONE, "debug-select")
+            .with_binding(KeyCode::Char('a'), KeyModifiers::NONE, "debug-add-watch")
+            .with_binding(KeyCode::Delete, KeyModifiers::NONE, "debug-remove-watch");
+
+        registry.register(debug_mode);
+
+        // Built-in mode for the undo tree visualizer
+        let undo_tree_mode = BufferMode::new("undo-tree")
+            .with_parent("special")
+            .with_binding(KeyCode::Enter, KeyModifiers::NONE, "undo-tree-restore")
+            .with_binding(KeyCode::Down, KeyModifiers::NONE, "undo-tree-next")
+            .with_binding(KeyCode::Char('j'), KeyModifiers::NONE, "undo-tree-next")
+            .with_binding(KeyCode::Up, KeyModifiers::NONE, "undo-tree-previous")
+            .with_binding(KeyCode::Char('k'), KeyModifiers::NONE, "undo-tree-previous")
+            .with_binding(KeyCode::Esc, KeyModifiers::NONE, "close-buffer");
+
+        registry.register(undo_tree_mode);
+
+        // Built-in modes for the vim keymap. They hold the commands that stand on
+        // their own; operators, motions and counts are parsed by `input::vim`.
+        let none = KeyModifiers::NONE;
+        let vim_normal = BufferMode::new("vim-normal")
+            .with_binding(KeyCode::Char('i'), none, "vim-insert")
+            .with_binding(KeyCode::Char('a'), none, "vim-append")
+            .with_binding(KeyCode::Char('I'), none, "vim-insert-line-start")
+            .with_binding(KeyCode::Char('A'), none, "vim-append-line-end")
+            .with_binding(KeyCode::Char('o'), none, "vim-open-below")
+            .with_binding(KeyCode::Char('O'), none, "vim-open-above")
+            .with_binding(KeyCode::Char('v'), none, "vim-visual")
+            .with_binding(KeyCode::Char('V'), none, "vim-visual-line")
+            .with_binding(
+                KeyCode::Char('v'),
+                KeyModifiers::CONTROL,
+                "vim-visual-block",
+            )
+            .with_binding(KeyCode::Char('u'), none, "vim-undo")
+            .with_binding(KeyCode::Char('r'), KeyModifiers::CONTROL, "vim-redo")
+            .with_binding(KeyCode::Char('p'), none, "vim-put-after")
+            .with_binding(KeyCode::Char('P'), none, "vim-put-before")
+            .with_binding(KeyCode::Char('.'), none, "vim-repeat")
+            .with_binding(KeyCode::Char(':'), none, "vim-ex");
+
+        registry.register(vim_normal);
+
+        let vim_insert =
+            BufferMode::new("vim-insert").with_binding(KeyCode::Esc, none, "vim-normal-mode");
+
+        registry.register(vim_insert);
+
+        let vim_visual = BufferMode::new("vim-visual")
+            .with_binding(KeyCode::Esc, none, "vim-normal-mode")
+            .with_binding(KeyCode::Char('v'), none, "vim-visual")
+            .with_binding(KeyCode::Char('V'), none, "vim-visual-line")
+            .with_binding(
+                KeyCode::Char('v'),
+                KeyModifiers::CONTROL,
+                "vim-visual-block",
+            )
+            .with_binding(KeyCode::Char('o'), none, "vim-visual-swap")
+            .with_binding(KeyCode::Char(':'), none, "vim-ex");
+
+        registry.register(vim_visual);
+        registry.register(BufferMode::new("vim-visual-line").with_parent("vim-visual"));
+
+        let vim_visual_block = BufferMode::new("vim-visual-block")
+            .with_parent("vim-visual")
+            .with_binding(KeyCode::Char('I'), none, "vim-block-insert")
+            .with_binding(KeyCode::Char('A'), none, "vim-block-append");
+
+        registry.register(vim_visual_block);
+
+        let vim_operator_pending = BufferMode::new("vim-operator-pending").with_binding(
+            KeyCode::Esc,
+            none,
+            "vim-normal-mode",
+        );
+
+        registry.register(vim_operator_pending);
+
         registry
     }
 
@@ -1,6 +1,7 @@
 //! Command palette system for executing editor actions by name
 
 use crate::input::keybindings::{Action, KeyContext};
+use crate::primitives::textobjects::TextObjectKind;
 
 /// Source of a command (builtin or from a plugin)
 #[derive(Debug, Clone, PartialEq)]
@@ -124,7 +125,7 @@ impl Suggestion {
 
 /// Get all available commands for the command palette
 pub fn get_all_commands() -> Vec<Command> {
-    vec![
+    let mut commands = vec![
         // File operations
         Command {
             name: "Open File".to_string(),
@@ -223,6 +224,31 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Show Undo Tree".to_string(),
+            description: "Browse every branch of the undo history and restore a state".to_string(),
+            action: Action::ShowUndoTree,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Undo to Earlier Time".to_string(),
+            description: "Return to the state the buffer was in some time earlier (e.g. 5m)"
+                .to_string(),
+            action: Action::UndoToEarlierTime,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Redo to Later Time".to_string(),
+            description: "Go to the state the buffer was in some time later (e.g. 5m)".to_string(),
+            action: Action::RedoToLaterTime,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Copy".to_string(),
             description: "Copy selection to clipboard".to_string(),
@@ -247,6 +273,30 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Paste from History".to_string(),
+            description: "Paste a recent clipboard entry".to_string(),
+            action: Action::PasteFromHistory,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Copy to Register".to_string(),
+            description: "Copy the selection to a named register (a-z, A-Z appends)".to_string(),
+            action: Action::PromptCopyToRegister,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Insert Register".to_string(),
+            description: "Insert the text of a named register (a-z)".to_string(),
+            action: Action::PromptInsertRegister,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Delete Line".to_string(),
             description: "Delete the current line".to_string(),
@@ -295,6 +345,134 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Move Line Up".to_string(),
+            description: "Move the current line or selected lines up".to_string(),
+            action: Action::MoveLineUp,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Move Line Down".to_string(),
+            description: "Move the current line or selected lines down".to_string(),
+            action: Action::MoveLineDown,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Duplicate Line".to_string(),
+            description: "Duplicate the selection, or the current line".to_string(),
+            action: Action::DuplicateLine,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Join Lines".to_string(),
+            description: "Join the selected lines, or the current line with the next".to_string(),
+            action: Action::JoinLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines".to_string(),
+            description: "Sort the selected lines, or all lines".to_string(),
+            action: Action::SortLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines Natural".to_string(),
+            description: "Sort lines, comparing numbers by value (a2 before a10)".to_string(),
+            action: Action::SortLinesNatural,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines Numeric".to_string(),
+            description: "Sort lines by the number they start with".to_string(),
+            action: Action::SortLinesNumeric,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines Case-Insensitive".to_string(),
+            description: "Sort lines ignoring case".to_string(),
+            action: Action::SortLinesCaseInsensitive,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines Descending".to_string(),
+            description: "Sort lines in reverse order".to_string(),
+            action: Action::SortLinesDescending,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unique Lines".to_string(),
+            description: "Remove lines that repeat an earlier line".to_string(),
+            action: Action::UniqueLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Reverse Lines".to_string(),
+            description: "Reverse the order of the selected lines, or all lines".to_string(),
+            action: Action::ReverseLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Shuffle Lines".to_string(),
+            description: "Put the selected lines, or all lines, in random order".to_string(),
+            action: Action::ShuffleLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Delete Blank Lines".to_string(),
+            description: "Delete empty and whitespace-only lines".to_string(),
+            action: Action::DeleteBlankLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Pipe Through Shell Command".to_string(),
+            description: "Replace the selection (or buffer) with the output of a shell command run on it".to_string(),
+            action: Action::ShellCommandReplace,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Insert Shell Command Output".to_string(),
+            description: "Insert the output of a shell command run on the selection, after it".to_string(),
+            action: Action::ShellCommandInsert,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Show Shell Command Output".to_string(),
+            description: "Show the output of a shell command run on the selection (or buffer) in a scratch buffer".to_string(),
+            action: Action::ShellCommandToBuffer,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Recenter".to_string(),
             description: "Center the view on the cursor".to_string(),
@@ -338,12 +516,125 @@ pub fn get_all_commands() -> Vec<Command> {
         },
         Command {
             name: "Expand Selection".to_string(),
-            description: "Expand the current selection by one word".to_string(),
+            description: "Expand the selection to the enclosing syntax node (or by one word)"
+                .to_string(),
             action: Action::ExpandSelection,
             contexts: vec![KeyContext::Normal],
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Shrink Selection".to_string(),
+            description: "Undo the last selection expansion".to_string(),
+            action: Action::ShrinkSelection,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        // Structural navigation
+        Command {
+            name: "Go to Next Function".to_string(),
+            description: "Move to the start of the next function".to_string(),
+            action: Action::NextFunction,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Previous Function".to_string(),
+            description: "Move to the start of the previous function".to_string(),
+            action: Action::PreviousFunction,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Swap Argument with Next".to_string(),
+            description: "Swap the argument or parameter at the cursor with the next one"
+                .to_string(),
+            action: Action::SwapArgumentNext,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Swap Argument with Previous".to_string(),
+            description: "Swap the argument or parameter at the cursor with the previous one"
+                .to_string(),
+            action: Action::SwapArgumentPrevious,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        // Rectangle editing
+        Command {
+            name: "Kill Rectangle".to_string(),
+            description:
+                "Cut the block selection (or the columns of the selection) into the clipboard"
+                    .to_string(),
+            action: Action::RectangleKill,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Copy Rectangle".to_string(),
+            description:
+                "Copy the block selection (or the columns of the selection) to the clipboard"
+                    .to_string(),
+            action: Action::RectangleCopy,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Yank Rectangle".to_string(),
+            description: "Paste the clipboard's lines as a column at the cursor".to_string(),
+            action: Action::RectangleYank,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Open Rectangle".to_string(),
+            description: "Shift the text of the rectangle right by its width".to_string(),
+            action: Action::RectangleOpen,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Replace Rectangle".to_string(),
+            description: "Replace the text of the rectangle on every line".to_string(),
+            action: Action::RectangleReplace,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Insert in Rectangle".to_string(),
+            description: "Insert text at the left edge of the rectangle on every line".to_string(),
+            action: Action::RectangleInsert,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Number Rectangle Lines".to_string(),
+            description: "Insert a number sequence at the left edge of the rectangle".to_string(),
+            action: Action::RectangleNumberLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Rectangle to Cursors".to_string(),
+            description: "Put a cursor on every line of the rectangle".to_string(),
+            action: Action::RectangleToCursors,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // Multi-cursor
         Command {
             name: "Add Cursor Above".to_string(),
@@ -720,6 +1011,60 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Go to Declaration".to_string(),
+            description: "Jump to the declaration of the symbol under cursor".to_string(),
+            action: Action::LspGotoDeclaration,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Type Definition".to_string(),
+            description: "Jump to the definition of the type of the symbol under cursor"
+                .to_string(),
+            action: Action::LspGotoTypeDefinition,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Implementation".to_string(),
+            description:
+                "Jump to the implementations of the trait, interface or method under cursor"
+                    .to_string(),
+            action: Action::LspGotoImplementation,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Peek Definition".to_string(),
+            description: "Show the definition of the symbol under cursor in a popup".to_string(),
+            action: Action::LspPeekDefinition,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Peek Type Definition".to_string(),
+            description: "Show the definition of the type of the symbol under cursor in a popup"
+                .to_string(),
+            action: Action::LspPeekTypeDefinition,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Peek Implementation".to_string(),
+            description:
+                "Show the implementations of the trait, interface or method under cursor in a popup"
+                    .to_string(),
+            action: Action::LspPeekImplementation,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Show Hover Info".to_string(),
             description: "Show documentation for the symbol under cursor".to_string(),
@@ -752,6 +1097,76 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Run Code Lens".to_string(),
+            description: "Run a code lens shown above the cursor line".to_string(),
+            action: Action::LspCodeLens,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Format Document".to_string(),
+            description: "Format the whole buffer using the language server".to_string(),
+            action: Action::LspFormatDocument,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Format Selection".to_string(),
+            description: "Format the selection (or current line) using the language server"
+                .to_string(),
+            action: Action::LspFormatSelection,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Symbol in Workspace".to_string(),
+            description: "Search symbols across the project using the language servers".to_string(),
+            action: Action::LspWorkspaceSymbol,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Show Incoming Calls".to_string(),
+            description:
+                "Show the functions that call the symbol at the cursor, as an expandable tree"
+                    .to_string(),
+            action: Action::LspIncomingCalls,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Show Outgoing Calls".to_string(),
+            description:
+                "Show the functions called by the symbol at the cursor, as an expandable tree"
+                    .to_string(),
+            action: Action::LspOutgoingCalls,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Show Supertypes".to_string(),
+            description: "Show the types the type at the cursor extends or implements".to_string(),
+            action: Action::LspSupertypes,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Show Subtypes".to_string(),
+            description: "Show the types that extend or implement the type at the cursor"
+                .to_string(),
+            action: Action::LspSubtypes,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Start/Restart LSP Server".to_string(),
             description: "Start or restart the LSP server for the current language".to_string(),
@@ -768,6 +1183,31 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Show LSP Log".to_string(),
+            description: "Show a server's log messages, notifications and progress".to_string(),
+            action: Action::LspShowLog,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "LSP Info".to_string(),
+            description: "Show each running server's state, capabilities, root and PID".to_string(),
+            action: Action::LspInfo,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Toggle LSP Trace".to_string(),
+            description: "Record the JSON-RPC messages exchanged with servers in their logs"
+                .to_string(),
+            action: Action::LspToggleTrace,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Toggle Mouse Hover".to_string(),
             description: "Toggle LSP hover info on mouse hover".to_string(),
@@ -776,6 +1216,103 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Start Debugging".to_string(),
+            description: "Start a debug session with the current language's debug adapter"
+                .to_string(),
+            action: Action::DebugStart,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Stop Debugging".to_string(),
+            description: "End the debug session and its program".to_string(),
+            action: Action::DebugStop,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Continue".to_string(),
+            description: "Resume the paused program, or start debugging".to_string(),
+            action: Action::DebugContinue,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Pause".to_string(),
+            description: "Pause the running program".to_string(),
+            action: Action::DebugPause,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Step Over".to_string(),
+            description: "Run to the next line of the current function".to_string(),
+            action: Action::DebugStepOver,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Step Into".to_string(),
+            description: "Step into the call on the current line".to_string(),
+            action: Action::DebugStepInto,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Step Out".to_string(),
+            description: "Run until the current function returns".to_string(),
+            action: Action::DebugStepOut,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Toggle Breakpoint".to_string(),
+            description: "Set or remove a breakpoint on the cursor line".to_string(),
+            action: Action::DebugToggleBreakpoint,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Show Variables".to_string(),
+            description: "Show the variables of the current stack frame".to_string(),
+            action: Action::DebugShowVariables,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Show Call Stack".to_string(),
+            description: "Show the call stack of the paused thread".to_string(),
+            action: Action::DebugShowCallStack,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Show Watches".to_string(),
+            description: "Show the watch expressions and their values".to_string(),
+            action: Action::DebugShowWatches,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Debug: Add Watch".to_string(),
+            description: "Evaluate an expression whenever the program pauses".to_string(),
+            action: Action::DebugAddWatch,
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Navigate Back".to_string(),
             description: "Go back in navigation history".to_string(),
@@ -825,6 +1362,55 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        // Folding
+        Command {
+            name: "Fold".to_string(),
+            description: "Fold the region at the cursor".to_string(),
+            action: Action::Fold,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unfold".to_string(),
+            description: "Unfold the region at the cursor".to_string(),
+            action: Action::Unfold,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Toggle Fold".to_string(),
+            description: "Fold or unfold the region at the cursor".to_string(),
+            action: Action::ToggleFold,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Fold All".to_string(),
+            description: "Fold every region in the buffer".to_string(),
+            action: Action::FoldAll,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unfold All".to_string(),
+            description: "Unfold every region in the buffer".to_string(),
+            action: Action::UnfoldAll,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Fold to Level".to_string(),
+            description: "Fold every region nested at a given level".to_string(),
+            action: Action::PromptFoldLevel,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // Error navigation
         Command {
             name: "Jump to Next Error".to_string(),
@@ -962,7 +1548,7 @@ pub fn get_all_commands() -> Vec<Command> {
         // Keybinding map selection
         Command {
             name: "Select Keybinding Map".to_string(),
-            description: "Choose a keybinding map (default, emacs, vscode)".to_string(),
+            description: "Choose a keybinding map (default, emacs, vscode, vim)".to_string(),
             action: Action::SelectKeybindingMap,
             contexts: vec![],
             custom_contexts: vec![],
@@ -1002,7 +1588,43 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
-    ]
+    ];
+    commands.extend(text_object_commands());
+    commands
+}
+
+/// Select, delete and change commands for each syntax text object
+fn text_object_commands() -> Vec<Command> {
+    let mut commands = Vec::new();
+    for kind in TextObjectKind::ALL {
+        let label = kind.label();
+        let name = kind.name();
+        commands.push(Command {
+            name: format!("Select {}", label),
+            description: format!("Select the {} around the cursor", name),
+            action: Action::SelectTextObject(kind),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        });
+        commands.push(Command {
+            name: format!("Delete {}", label),
+            description: format!("Delete the {} around the cursor", name),
+            action: Action::DeleteTextObject(kind),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        });
+        commands.push(Command {
+            name: format!("Change {}", label),
+            description: format!("Delete the contents of the {} around the cursor", name),
+            action: Action::ChangeTextObject(kind),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        });
+    }
+    commands
 }
 
 /// Filter commands by fuzzy matching the query, with context awareness
@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::primitives::textobjects::TextObjectKind;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -238,6 +239,16 @@ pub enum Action {
     SelectWord,
     SelectLine,
     ExpandSelection,
+    ShrinkSelection,
+
+    // Syntax text objects and structural navigation
+    SelectTextObject(TextObjectKind),
+    DeleteTextObject(TextObjectKind),
+    ChangeTextObject(TextObjectKind),
+    NextFunction,
+    PreviousFunction,
+    SwapArgumentNext,
+    SwapArgumentPrevious,
 
     // Block/rectangular selection (column-wise)
     BlockSelectLeft,
@@ -245,6 +256,17 @@ pub enum Action {
     BlockSelectUp,
     BlockSelectDown,
 
+    // Rectangle editing (the block selection, or the columns between the
+    // selection's anchor and the cursor)
+    RectangleKill,
+    RectangleCopy,
+    RectangleYank,
+    RectangleOpen,
+    RectangleReplace,
+    RectangleInsert,
+    RectangleNumberLines,
+    RectangleToCursors,
+
     // Editing
     DeleteBackward,
     DeleteForward,
@@ -255,6 +277,27 @@ pub enum Action {
     TransposeChars,
     OpenLine,
 
+    // Line editing (the lines of every cursor; sorting and filtering work on
+    // the whole buffer when nothing is selected)
+    MoveLineUp,
+    MoveLineDown,
+    DuplicateLine,
+    JoinLines,
+    SortLines,
+    SortLinesNatural,
+    SortLinesNumeric,
+    SortLinesCaseInsensitive,
+    SortLinesDescending,
+    UniqueLines,
+    ReverseLines,
+    ShuffleLines,
+    DeleteBlankLines,
+
+    // Shell commands on the selection of every cursor, or the whole buffer
+    ShellCommandReplace,
+    ShellCommandInsert,
+    ShellCommandToBuffer,
+
     // View
     Recenter,
 
@@ -265,6 +308,21 @@ pub enum Action {
     Copy,
     Cut,
     Paste,
+    PasteFromHistory,
+
+    // Kill ring
+    KillLine,
+    KillWordForward,
+    KillWordBackward,
+    KillRegion,
+    Yank,
+    YankPop,
+
+    // Named registers
+    CopyToRegister(char),
+    InsertRegister(char),
+    PromptCopyToRegister,
+    PromptInsertRegister,
 
     // Multi-cursor
     AddCursorAbove,
@@ -296,6 +354,15 @@ pub enum Action {
     DedentSelection,
     ToggleComment,
 
+    // Folding
+    Fold,
+    Unfold,
+    ToggleFold,
+    FoldAll,
+    UnfoldAll,
+    FoldLevel(usize),
+    PromptFoldLevel,
+
     // Bookmarks
     SetBookmark(char),
     JumpToBookmark(char),
@@ -326,6 +393,9 @@ pub enum Action {
     // Undo/redo
     Undo,
     Redo,
+    ShowUndoTree,
+    UndoToEarlierTime,
+    RedoToLaterTime,
 
     // View
     ScrollUp,
@@ -425,16 +495,47 @@ pub enum Action {
     // LSP operations
     LspCompletion,
     LspGotoDefinition,
+    LspGotoDeclaration,
+    LspGotoTypeDefinition,
+    LspGotoImplementation,
+    LspPeekDefinition,
+    LspPeekTypeDefinition,
+    LspPeekImplementation,
     LspReferences,
     LspRename,
     LspHover,
     LspSignatureHelp,
     LspCodeActions,
+    LspCodeLens,
+    LspFormatDocument,
+    LspFormatSelection,
+    LspWorkspaceSymbol,
+    LspIncomingCalls,
+    LspOutgoingCalls,
+    LspSupertypes,
+    LspSubtypes,
     LspRestart,
     LspStop,
+    LspShowLog,
+    LspInfo,
+    LspToggleTrace,
     ToggleInlayHints,
     ToggleMouseHover,
 
+    // Debugging
+    DebugStart,
+    DebugStop,
+    DebugContinue,
+    DebugPause,
+    DebugStepOver,
+    DebugStepInto,
+    DebugStepOut,
+    DebugToggleBreakpoint,
+    DebugShowVariables,
+    DebugShowCallStack,
+    DebugShowWatches,
+    DebugAddWatch,
+
     // View toggles
     ToggleLineNumbers,
     ToggleMouseCapture,
@@ -463,7 +564,7 @@ pub enum Action {
     MenuOpen(String), // Open a specific menu by name (e.g., "File", "Edit")
 
     // Keybinding map switching
-    SwitchKeybindingMap(String), // Switch to a named keybinding map (e.g., "default", "emacs", "vscode")
+    SwitchKeybindingMap(String), // Switch to a named keybinding map (e.g., "default", "emacs", "vscode", "vim")
 
     // Plugin custom actions
     PluginAction(String),
@@ -523,12 +624,38 @@ impl Action {
             "select_word" => Some(Action::SelectWord),
             "select_line" => Some(Action::SelectLine),
             "expand_selection" => Some(Action::ExpandSelection),
+            "shrink_selection" => Some(Action::ShrinkSelection),
+
+            "select_text_object" => {
+                let kind = TextObjectKind::from_name(args.get("object")?.as_str()?)?;
+                Some(Action::SelectTextObject(kind))
+            }
+            "delete_text_object" => {
+                let kind = TextObjectKind::from_name(args.get("object")?.as_str()?)?;
+                Some(Action::DeleteTextObject(kind))
+            }
+            "change_text_object" => {
+                let kind = TextObjectKind::from_name(args.get("object")?.as_str()?)?;
+                Some(Action::ChangeTextObject(kind))
+            }
+            "next_function" => Some(Action::NextFunction),
+            "previous_function" => Some(Action::PreviousFunction),
+            "swap_argument_next" => Some(Action::SwapArgumentNext),
+            "swap_argument_previous" => Some(Action::SwapArgumentPrevious),
 
             // Block/rectangular selection
             "block_select_left" => Some(Action::BlockSelectLeft),
             "block_select_right" => Some(Action::BlockSelectRight),
             "block_select_up" => Some(Action::BlockSelectUp),
             "block_select_down" => Some(Action::BlockSelectDown),
+            "rectangle_kill" => Some(Action::RectangleKill),
+            "rectangle_copy" => Some(Action::RectangleCopy),
+            "rectangle_yank" => Some(Action::RectangleYank),
+            "rectangle_open" => Some(Action::RectangleOpen),
+            "rectangle_replace" => Some(Action::RectangleReplace),
+            "rectangle_insert" => Some(Action::RectangleInsert),
+            "rectangle_number_lines" => Some(Action::RectangleNumberLines),
+            "rectangle_to_cursors" => Some(Action::RectangleToCursors),
 
             "delete_backward" => Some(Action::DeleteBackward),
             "delete_forward" => Some(Action::DeleteForward),
@@ -538,12 +665,53 @@ impl Action {
             "delete_to_line_end" => Some(Action::DeleteToLineEnd),
             "transpose_chars" => Some(Action::TransposeChars),
             "open_line" => Some(Action::OpenLine),
+            "move_line_up" => Some(Action::MoveLineUp),
+            "move_line_down" => Some(Action::MoveLineDown),
+            "duplicate_line" => Some(Action::DuplicateLine),
+            "join_lines" => Some(Action::JoinLines),
+            "sort_lines" => Some(Action::SortLines),
+            "sort_lines_natural" => Some(Action::SortLinesNatural),
+            "sort_lines_numeric" => Some(Action::SortLinesNumeric),
+            "sort_lines_case_insensitive" => Some(Action::SortLinesCaseInsensitive),
+            "sort_lines_descending" => Some(Action::SortLinesDescending),
+            "unique_lines" => Some(Action::UniqueLines),
+            "reverse_lines" => Some(Action::ReverseLines),
+            "shuffle_lines" => Some(Action::ShuffleLines),
+            "delete_blank_lines" => Some(Action::DeleteBlankLines),
+            "shell_command_replace" => Some(Action::ShellCommandReplace),
+            "shell_command_insert" => Some(Action::ShellCommandInsert),
+            "shell_command_to_buffer" => Some(Action::ShellCommandToBuffer),
             "recenter" => Some(Action::Recenter),
             "set_mark" => Some(Action::SetMark),
 
             "copy" => Some(Action::Copy),
             "cut" => Some(Action::Cut),
             "paste" => Some(Action::Paste),
+            "paste_from_history" => Some(Action::PasteFromHistory),
+
+            "kill_line" => Some(Action::KillLine),
+            "kill_word_forward" => Some(Action::KillWordForward),
+            "kill_word_backward" => Some(Action::KillWordBackward),
+            "kill_region" => Some(Action::KillRegion),
+            "yank" => Some(Action::Yank),
+            "yank_pop" => Some(Action::YankPop),
+
+            "copy_to_register" => {
+                if let Some(serde_json::Value::String(c)) = args.get("char") {
+                    c.chars().next().map(Action::CopyToRegister)
+                } else {
+                    None
+                }
+            }
+            "insert_register" => {
+                if let Some(serde_json::Value::String(c)) = args.get("char") {
+                    c.chars().next().map(Action::InsertRegister)
+                } else {
+                    None
+                }
+            }
+            "prompt_copy_to_register" => Some(Action::PromptCopyToRegister),
+            "prompt_insert_register" => Some(Action::PromptInsertRegister),
 
             "add_cursor_above" => Some(Action::AddCursorAbove),
             "add_cursor_below" => Some(Action::AddCursorBelow),
@@ -570,6 +738,17 @@ impl Action {
             "dedent_selection" => Some(Action::DedentSelection),
             "toggle_comment" => Some(Action::ToggleComment),
 
+            "fold" => Some(Action::Fold),
+            "unfold" => Some(Action::Unfold),
+            "toggle_fold" => Some(Action::ToggleFold),
+            "fold_all" => Some(Action::FoldAll),
+            "unfold_all" => Some(Action::UnfoldAll),
+            "fold_level" => {
+                let level = args.get("level")?.as_u64()?;
+                Some(Action::FoldLevel(level as usize))
+            }
+            "prompt_fold_level" => Some(Action::PromptFoldLevel),
+
             "set_bookmark" => {
                 if let Some(serde_json::Value::String(c)) = args.get("char") {
                     c.chars().next().map(Action::SetBookmark)
@@ -630,6 +809,9 @@ impl Action {
 
             "undo" => Some(Action::Undo),
             "redo" => Some(Action::Redo),
+            "undo_tree" => Some(Action::ShowUndoTree),
+            "undo_earlier" => Some(Action::UndoToEarlierTime),
+            "redo_later" => Some(Action::RedoToLaterTime),
 
             "scroll_up" => Some(Action::ScrollUp),
             "scroll_down" => Some(Action::ScrollDown),
@@ -711,16 +893,46 @@ impl Action {
 
             "lsp_completion" => Some(Action::LspCompletion),
             "lsp_goto_definition" => Some(Action::LspGotoDefinition),
+            "lsp_goto_declaration" => Some(Action::LspGotoDeclaration),
+            "lsp_goto_type_definition" => Some(Action::LspGotoTypeDefinition),
+            "lsp_goto_implementation" => Some(Action::LspGotoImplementation),
+            "lsp_peek_definition" => Some(Action::LspPeekDefinition),
+            "lsp_peek_type_definition" => Some(Action::LspPeekTypeDefinition),
+            "lsp_peek_implementation" => Some(Action::LspPeekImplementation),
             "lsp_references" => Some(Action::LspReferences),
             "lsp_rename" => Some(Action::LspRename),
             "lsp_hover" => Some(Action::LspHover),
             "lsp_signature_help" => Some(Action::LspSignatureHelp),
             "lsp_code_actions" => Some(Action::LspCodeActions),
+            "lsp_code_lens" => Some(Action::LspCodeLens),
+            "lsp_format_document" => Some(Action::LspFormatDocument),
+            "lsp_format_selection" => Some(Action::LspFormatSelection),
+            "lsp_workspace_symbol" => Some(Action::LspWorkspaceSymbol),
+            "lsp_incoming_calls" => Some(Action::LspIncomingCalls),
+            "lsp_outgoing_calls" => Some(Action::LspOutgoingCalls),
+            "lsp_supertypes" => Some(Action::LspSupertypes),
+            "lsp_subtypes" => Some(Action::LspSubtypes),
             "lsp_restart" => Some(Action::LspRestart),
             "lsp_stop" => Some(Action::LspStop),
+            "lsp_show_log" => Some(Action::LspShowLog),
+            "lsp_info" => Some(Action::LspInfo),
+            "lsp_toggle_trace" => Some(Action::LspToggleTrace),
             "toggle_inlay_hints" => Some(Action::ToggleInlayHints),
             "toggle_mouse_hover" => Some(Action::ToggleMouseHover),
 
+            "debug_start" => Some(Action::DebugStart),
+            "debug_stop" => Some(Action::DebugStop),
+            "debug_continue" => Some(Action::DebugContinue),
+            "debug_pause" => Some(Action::DebugPause),
+            "debug_step_over" => Some(Action::DebugStepOver),
+            "debug_step_into" => Some(Action::DebugStepInto),
+            "debug_step_out" => Some(Action::DebugStepOut),
+            "debug_toggle_breakpoint" => Some(Action::DebugToggleBreakpoint),
+            "debug_show_variables" => Some(Action::DebugShowVariables),
+            "debug_show_call_stack" => Some(Action::DebugShowCallStack),
+            "debug_show_watches" => Some(Action::DebugShowWatches),
+            "debug_add_watch" => Some(Action::DebugAddWatch),
+
             "toggle_line_numbers" => Some(Action::ToggleLineNumbers),
             "toggle_mouse_capture" => Some(Action::ToggleMouseCapture),
             "set_background" => Some(Action::SetBackground),
@@ -794,6 +1006,9 @@ pub struct KeybindingResolver {
 
     /// Default chord bindings for each context
     default_chord_bindings: HashMap<KeyContext, HashMap<Vec<(KeyCode, KeyModifiers)>, Action>>,
+
+    /// Whether the active keymap is (or inherits from) the vim keymap
+    vim_emulation: bool,
 }
 
 impl KeybindingResolver {
@@ -804,6 +1019,7 @@ impl KeybindingResolver {
             default_bindings: HashMap::new(),
             chord_bindings: HashMap::new(),
             default_chord_bindings: HashMap::new(),
+            vim_emulation: config.keymap_extends(&config.active_keybinding_map, "vim"),
         };
 
         // Load bindings from the active keymap (with inheritance resolution) into default_bindings
@@ -816,6 +1032,11 @@ impl KeybindingResolver {
         resolver
     }
 
+    /// Whether vim modal editing is enabled (see `input::vim`)
+    pub fn vim_emulation(&self) -> bool {
+        self.vim_emulation
+    }
+
     /// Load default bindings from a vector of keybinding definitions (into default_bindings/default_chord_bindings)
     fn load_default_bindings_from_vec(&mut self, bindings: &[crate::config::Keybinding]) {
         for binding in bindings {
@@ -1488,10 +1709,32 @@ impl KeybindingResolver {
             Action::SelectWord => "Select word under cursor".to_string(),
             Action::SelectLine => "Select current line".to_string(),
             Action::ExpandSelection => "Expand selection".to_string(),
+            Action::ShrinkSelection => "Shrink selection".to_string(),
+            Action::SelectTextObject(kind) => {
+                format!("Select {}", kind.name())
+            }
+            Action::DeleteTextObject(kind) => {
+                format!("Delete {}", kind.name())
+            }
+            Action::ChangeTextObject(kind) => {
+                format!("Change the contents of the {}", kind.name())
+            }
+            Action::NextFunction => "Go to next function".to_string(),
+            Action::PreviousFunction => "Go to previous function".to_string(),
+            Action::SwapArgumentNext => "Swap argument with the next one".to_string(),
+            Action::SwapArgumentPrevious => "Swap argument with the previous one".to_string(),
             Action::BlockSelectLeft => "Block select left".to_string(),
             Action::BlockSelectRight => "Block select right".to_string(),
             Action::BlockSelectUp => "Block select up".to_string(),
             Action::BlockSelectDown => "Block select down".to_string(),
+            Action::RectangleKill => "Kill rectangle".to_string(),
+            Action::RectangleCopy => "Copy rectangle".to_string(),
+            Action::RectangleYank => "Yank clipboard lines as a rectangle".to_string(),
+            Action::RectangleOpen => "Open rectangle (shift text right)".to_string(),
+            Action::RectangleReplace => "Replace rectangle with text".to_string(),
+            Action::RectangleInsert => "Insert text on every line of the rectangle".to_string(),
+            Action::RectangleNumberLines => "Number the lines of the rectangle".to_string(),
+            Action::RectangleToCursors => "Cursor on every line of the rectangle".to_string(),
             Action::DeleteBackward => "Delete backward".to_string(),
             Action::DeleteForward => "Delete forward".to_string(),
             Action::DeleteWordBackward => "Delete word backward".to_string(),
@@ -1500,11 +1743,40 @@ impl KeybindingResolver {
             Action::DeleteToLineEnd => "Delete to end of line".to_string(),
             Action::TransposeChars => "Transpose characters".to_string(),
             Action::OpenLine => "Open line below".to_string(),
+            Action::MoveLineUp => "Move line up".to_string(),
+            Action::MoveLineDown => "Move line down".to_string(),
+            Action::DuplicateLine => "Duplicate line or selection".to_string(),
+            Action::JoinLines => "Join lines".to_string(),
+            Action::SortLines => "Sort lines".to_string(),
+            Action::SortLinesNatural => "Sort lines (natural order)".to_string(),
+            Action::SortLinesNumeric => "Sort lines by number".to_string(),
+            Action::SortLinesCaseInsensitive => "Sort lines (case-insensitive)".to_string(),
+            Action::SortLinesDescending => "Sort lines in reverse order".to_string(),
+            Action::UniqueLines => "Remove duplicate lines".to_string(),
+            Action::ReverseLines => "Reverse lines".to_string(),
+            Action::ShuffleLines => "Shuffle lines".to_string(),
+            Action::DeleteBlankLines => "Delete blank lines".to_string(),
+            Action::ShellCommandReplace => "Pipe through shell command".to_string(),
+            Action::ShellCommandInsert => "Insert shell command output".to_string(),
+            Action::ShellCommandToBuffer => "Show shell command output".to_string(),
             Action::Recenter => "Recenter view on cursor".to_string(),
             Action::SetMark => "Set mark (start selection)".to_string(),
             Action::Copy => "Copy".to_string(),
             Action::Cut => "Cut".to_string(),
             Action::Paste => "Paste".to_string(),
+            Action::PasteFromHistory => "Paste from clipboard history".to_string(),
+            Action::KillLine => "Kill to end of line".to_string(),
+            Action::KillWordForward => "Kill word forward".to_string(),
+            Action::KillWordBackward => "Kill word backward".to_string(),
+            Action::KillRegion => "Kill selection".to_string(),
+            Action::Yank => "Yank (paste last kill)".to_string(),
+            Action::YankPop => "Replace yanked text with older kill".to_string(),
+            Action::CopyToRegister(c) => format!("Copy selection to register '{}'", c),
+            Action::InsertRegister(c) => format!("Insert register '{}'", c),
+            Action::PromptCopyToRegister => {
+                "Copy selection to register (prompts for register)".to_string()
+            }
+            Action::PromptInsertRegister => "Insert register (prompts for register)".to_string(),
             Action::AddCursorAbove => "Add cursor above".to_string(),
             Action::AddCursorBelow => "Add cursor below".to_string(),
             Action::AddCursorNextMatch => "Add cursor at next match".to_string(),
@@ -1529,6 +1801,13 @@ impl KeybindingResolver {
             Action::IndentSelection => "Indent selection".to_string(),
             Action::DedentSelection => "Dedent selection".to_string(),
             Action::ToggleComment => "Toggle comment".to_string(),
+            Action::Fold => "Fold region at cursor".to_string(),
+            Action::Unfold => "Unfold region at cursor".to_string(),
+            Action::ToggleFold => "Toggle fold at cursor".to_string(),
+            Action::FoldAll => "Fold all regions".to_string(),
+            Action::UnfoldAll => "Unfold all regions".to_string(),
+            Action::FoldLevel(level) => format!("Fold regions at level {}", level),
+            Action::PromptFoldLevel => "Fold to level...".to_string(),
             Action::SetBookmark(c) => format!("Set bookmark '{}'", c),
             Action::JumpToBookmark(c) => format!("Jump to bookmark '{}'", c),
             Action::ClearBookmark(c) => format!("Clear bookmark '{}'", c),
@@ -1550,6 +1829,9 @@ impl KeybindingResolver {
             Action::PromptJumpToBookmark => "Jump to bookmark (prompts for register)".to_string(),
             Action::Undo => "Undo".to_string(),
             Action::Redo => "Redo".to_string(),
+            Action::ShowUndoTree => "Show undo tree".to_string(),
+            Action::UndoToEarlierTime => "Undo to earlier time".to_string(),
+            Action::RedoToLaterTime => "Redo to later time".to_string(),
             Action::ScrollUp => "Scroll up".to_string(),
             Action::ScrollDown => "Scroll down".to_string(),
             Action::ShowHelp => "Show manual".to_string(),
@@ -1625,15 +1907,44 @@ impl KeybindingResolver {
             }
             Action::LspCompletion => "LSP: Show completion suggestions".to_string(),
             Action::LspGotoDefinition => "LSP: Go to definition".to_string(),
+            Action::LspGotoDeclaration => "LSP: Go to declaration".to_string(),
+            Action::LspGotoTypeDefinition => "LSP: Go to type definition".to_string(),
+            Action::LspGotoImplementation => "LSP: Go to implementation".to_string(),
+            Action::LspPeekDefinition => "LSP: Peek definition".to_string(),
+            Action::LspPeekTypeDefinition => "LSP: Peek type definition".to_string(),
+            Action::LspPeekImplementation => "LSP: Peek implementation".to_string(),
             Action::LspReferences => "LSP: Find references".to_string(),
             Action::LspRename => "LSP: Rename symbol".to_string(),
             Action::LspHover => "LSP: Show hover documentation".to_string(),
             Action::LspSignatureHelp => "LSP: Show signature help".to_string(),
             Action::LspCodeActions => "LSP: Show code actions".to_string(),
+            Action::LspCodeLens => "LSP: Run code lens".to_string(),
+            Action::LspFormatDocument => "LSP: Format document".to_string(),
+            Action::LspFormatSelection => "LSP: Format selection".to_string(),
+            Action::LspWorkspaceSymbol => "LSP: Go to symbol in workspace".to_string(),
+            Action::LspIncomingCalls => "LSP: Show incoming calls".to_string(),
+            Action::LspOutgoingCalls => "LSP: Show outgoing calls".to_string(),
+            Action::LspSupertypes => "LSP: Show supertypes".to_string(),
+            Action::LspSubtypes => "LSP: Show subtypes".to_string(),
             Action::LspRestart => "LSP: Start/restart server for current language".to_string(),
             Action::LspStop => "LSP: Stop a running server".to_string(),
+            Action::LspShowLog => "LSP: Show a server's log".to_string(),
+            Action::LspInfo => "LSP: Show running servers".to_string(),
+            Action::LspToggleTrace => "LSP: Toggle JSON-RPC trace".to_string(),
             Action::ToggleInlayHints => "Toggle inlay hints".to_string(),
             Action::ToggleMouseHover => "Toggle LSP hover on mouse".to_string(),
+            Action::DebugStart => "Debug: Start a session for the current file".to_string(),
+            Action::DebugStop => "Debug: Stop the session".to_string(),
+            Action::DebugContinue => "Debug: Continue (or start a session)".to_string(),
+            Action::DebugPause => "Debug: Pause".to_string(),
+            Action::DebugStepOver => "Debug: Step over".to_string(),
+            Action::DebugStepInto => "Debug: Step into".to_string(),
+            Action::DebugStepOut => "Debug: Step out".to_string(),
+            Action::DebugToggleBreakpoint => "Debug: Toggle breakpoint".to_string(),
+            Action::DebugShowVariables => "Debug: Show variables".to_string(),
+            Action::DebugShowCallStack => "Debug: Show call stack".to_string(),
+            Action::DebugShowWatches => "Debug: Show watches".to_string(),
+            Action::DebugAddWatch => "Debug: Add a watch expression".to_string(),
             Action::ToggleLineNumbers => "Toggle line numbers".to_string(),
             Action::ToggleMouseCapture => "Toggle mouse support".to_string(),
             Action::SetBackground => "Set ANSI background file".to_string(),
@@ -11,3 +11,4 @@ pub mod input_history;
 pub mod keybindings;
 pub mod multi_cursor;
 pub mod position_history;
+pub mod vim;
@@ -0,0 +1,1981 @@
+//! Vim emulation: modes, the command grammar, motions and text objects
+//!
+//! The `vim` keymap turns on modal editing. A buffer's mode is kept in its
+//! `EditorState::mode` (changed with `Event::ChangeMode`), and each mode is a buffer
+//! mode in the [`ModeRegistry`](super::buffer_mode::ModeRegistry) holding the commands
+//! that stand on their own (`i`, `p`, `u`, `:`...). [`VimState`] parses everything
+//! else: counts, operators, motions and text objects, so that `d3w`, `ci"` and `>ip`
+//! compose as they do in Vim. It also remembers the keys of the last change for `.`,
+//! and holds the unnamed register.
+//!
+//! Motions, text objects and ex commands are computed by the pure functions in this
+//! module; the editor applies the results (see `app/vim.rs`).
+
+use crate::model::event::parse_time_offset;
+use crate::primitives::textobjects::TextObjectKind;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::ops::Range;
+
+/// Vim mode of a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimMode {
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+    VisualBlock,
+    /// An operator was typed and waits for its motion or text object
+    OperatorPending,
+}
+
+impl VimMode {
+    /// Name of the buffer mode holding this mode's commands
+    ///
+    /// This is also what `EditorState::mode` holds while in the mode.
+    pub fn mode_name(self) -> &'static str {
+        match self {
+            VimMode::Normal => "vim-normal",
+            VimMode::Insert => "vim-insert",
+            VimMode::Visual => "vim-visual",
+            VimMode::VisualLine => "vim-visual-line",
+            VimMode::VisualBlock => "vim-visual-block",
+            VimMode::OperatorPending => "vim-operator-pending",
+        }
+    }
+
+    /// Mode from a buffer's `EditorState::mode`
+    ///
+    /// Buffers that never entered a vim mode are in normal mode.
+    pub fn from_mode_name(name: &str) -> Self {
+        match name {
+            "vim-insert" => VimMode::Insert,
+            "vim-visual" => VimMode::Visual,
+            "vim-visual-line" => VimMode::VisualLine,
+            "vim-visual-block" => VimMode::VisualBlock,
+            "vim-operator-pending" => VimMode::OperatorPending,
+            _ => VimMode::Normal,
+        }
+    }
+
+    /// Label shown in the status bar
+    pub fn label(self) -> &'static str {
+        match self {
+            VimMode::Normal => "NORMAL",
+            VimMode::Insert => "INSERT",
+            VimMode::Visual => "VISUAL",
+            VimMode::VisualLine => "VISUAL LINE",
+            VimMode::VisualBlock => "VISUAL BLOCK",
+            VimMode::OperatorPending => "OPERATOR",
+        }
+    }
+
+    /// Whether this is one of the visual modes
+    pub fn is_visual(self) -> bool {
+        matches!(
+            self,
+            VimMode::Visual | VimMode::VisualLine | VimMode::VisualBlock
+        )
+    }
+}
+
+/// An operator (`d`, `c`, `y`, `>`, `<`, `gu`, `gU`, `g~`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+    Lowercase,
+    Uppercase,
+    ToggleCase,
+}
+
+/// A cursor motion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordForward {
+        big: bool,
+    },
+    WordBackward {
+        big: bool,
+    },
+    WordEnd {
+        big: bool,
+    },
+    /// `0`
+    LineStart,
+    /// `^`
+    FirstNonBlank,
+    /// `$`
+    LineEnd,
+    /// `+` and Enter
+    NextLineStart,
+    /// `-`
+    PrevLineStart,
+    /// `gg`, or the line given by the count
+    FileStart,
+    /// `G`, or the line given by the count
+    FileEnd,
+    /// `f`, `F`, `t` and `T`
+    FindChar {
+        ch: char,
+        forward: bool,
+        till: bool,
+    },
+    /// `}`
+    ParagraphForward,
+    /// `{`
+    ParagraphBackward,
+    /// `%`
+    MatchPair,
+}
+
+/// How an operator treats the text a motion moves over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotionKind {
+    /// The character at the end of the motion is not included
+    Exclusive,
+    /// The character at the end of the motion is included
+    Inclusive,
+    /// Whole lines are included
+    Linewise,
+}
+
+impl Motion {
+    /// How an operator treats this motion
+    pub fn kind(self) -> MotionKind {
+        match self {
+            Motion::Up
+            | Motion::Down
+            | Motion::NextLineStart
+            | Motion::PrevLineStart
+            | Motion::FileStart
+            | Motion::FileEnd => MotionKind::Linewise,
+            Motion::WordEnd { .. } | Motion::LineEnd | Motion::MatchPair => MotionKind::Inclusive,
+            Motion::FindChar { forward, .. } if forward => MotionKind::Inclusive,
+            _ => MotionKind::Exclusive,
+        }
+    }
+}
+
+/// Kind of text object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectKind {
+    /// `w` and `W`
+    Word { big: bool },
+    /// `"`, `'` and `` ` ``
+    Quote(char),
+    /// `(`, `[`, `{` and `<`, with their closing characters and `b`/`B`
+    Block(char, char),
+    /// `p`
+    Paragraph,
+    /// `f` (function), `c` (class) and `a` (argument), found in the syntax tree
+    Syntax(TextObjectKind),
+}
+
+/// A text object (`iw`, `a"`, `i{`, `ap`...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextObject {
+    pub kind: ObjectKind,
+    /// `i` (inner) rather than `a` (around)
+    pub inner: bool,
+}
+
+/// What an operator acts on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Motion(Motion),
+    TextObject(TextObject),
+    /// The operator was doubled (`dd`, `>>`), acting on whole lines
+    Line,
+}
+
+/// A complete command parsed by [`VimState`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimAction {
+    /// Move the cursor
+    Move {
+        motion: Motion,
+        count: Option<usize>,
+    },
+    /// Apply an operator in normal mode
+    Operate {
+        operator: Operator,
+        target: Target,
+        count: Option<usize>,
+    },
+    /// Apply an operator to the visual selection
+    OperateSelection { operator: Operator },
+    /// Extend the visual selection over a text object
+    Select {
+        object: TextObject,
+        count: Option<usize>,
+    },
+    /// `r`: replace characters under the cursor
+    Replace { ch: char, count: usize },
+    /// `J`: join lines
+    Join { count: usize },
+}
+
+/// Result of feeding a key to [`VimState`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimKeyResult {
+    /// The key was consumed and more keys are needed
+    Pending,
+    /// The key completed a command
+    Action(VimAction),
+    /// The pending command was cancelled (Escape or an invalid key)
+    Cancelled,
+    /// The key is not part of the grammar
+    Unbound,
+}
+
+/// A key that must be followed by another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    /// `g` prefix
+    G,
+    /// `f`, `F`, `t` or `T`, waiting for the character
+    Find { forward: bool, till: bool },
+    /// `r`, waiting for the replacement character
+    Replace,
+    /// `i` or `a` after an operator or in visual mode, waiting for the object
+    Object { inner: bool },
+    /// `"`, waiting for the register name
+    Register,
+}
+
+/// How the text of a register was cut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterKind {
+    Charwise,
+    Linewise,
+    Blockwise,
+}
+
+/// Contents of the unnamed register
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VimRegister {
+    pub text: String,
+    pub kind: RegisterKind,
+}
+
+impl VimRegister {
+    /// Text that didn't come from vim (a named register or the clipboard); whole
+    /// lines are put as lines
+    pub fn from_text(text: String) -> Self {
+        let kind = if text.ends_with('\n') {
+            RegisterKind::Linewise
+        } else {
+            RegisterKind::Charwise
+        };
+        Self { text, kind }
+    }
+}
+
+/// Parser state of the vim emulation, shared by all buffers
+#[derive(Debug, Default)]
+pub struct VimState {
+    /// Count typed so far
+    count: Option<usize>,
+    /// Operator waiting for its target, with the count typed before it
+    operator: Option<(Operator, Option<usize>)>,
+    /// Key waiting for the next one
+    pending: Option<Pending>,
+    /// Last `f`/`F`/`t`/`T` search, for `;` and `,`
+    last_find: Option<(char, bool, bool)>,
+    /// Keys of the command being typed, and of the insert it started
+    keys: Vec<KeyEvent>,
+    /// Whether the keys typed in insert mode belong to the current change
+    recording_insert: bool,
+    /// Keys of the last change, replayed by `.`
+    last_change: Vec<KeyEvent>,
+    /// Set while `.` replays the last change, so that it isn't recorded again
+    pub replaying: bool,
+    /// The unnamed register
+    pub register: Option<VimRegister>,
+    /// Register chosen with `"` for the command being typed (`a`-`z`, `A`-`Z`,
+    /// `+` or `*`)
+    register_name: Option<char>,
+    /// Where the visual selection started
+    pub visual_start: usize,
+    /// First and last line of the last visual selection (`'<` and `'>`)
+    pub visual_lines: Option<(usize, usize)>,
+}
+
+impl VimState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the next key completes a `g`, `f`, `r`, `i`, `a` or `"` prefix
+    pub fn is_awaiting_key(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Whether an operator waits for its target
+    pub fn is_operator_pending(&self) -> bool {
+        self.operator.is_some()
+    }
+
+    /// Take the count typed so far
+    pub fn take_count(&mut self) -> Option<usize> {
+        self.count.take()
+    }
+
+    /// Take the register chosen with `"` for the command being run
+    pub fn take_register_name(&mut self) -> Option<char> {
+        self.register_name.take()
+    }
+
+    /// Forget the command being typed
+    pub fn reset(&mut self) {
+        self.count = None;
+        self.operator = None;
+        self.pending = None;
+    }
+
+    /// Feed a key typed in normal, operator-pending or visual mode
+    pub fn handle_key(&mut self, key: KeyEvent, visual: bool) -> VimKeyResult {
+        let c = match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                c
+            }
+            KeyCode::Esc => return self.cancel(),
+            KeyCode::Left | KeyCode::Backspace if self.pending.is_none() => {
+                return self.motion(Motion::Left)
+            }
+            KeyCode::Right if self.pending.is_none() => return self.motion(Motion::Right),
+            KeyCode::Up if self.pending.is_none() => return self.motion(Motion::Up),
+            KeyCode::Down if self.pending.is_none() => return self.motion(Motion::Down),
+            KeyCode::Home if self.pending.is_none() => return self.motion(Motion::LineStart),
+            KeyCode::End if self.pending.is_none() => return self.motion(Motion::LineEnd),
+            KeyCode::Enter if self.pending.is_none() => return self.motion(Motion::NextLineStart),
+            KeyCode::Delete if self.pending.is_none() && self.operator.is_none() => {
+                return self.delete_chars(visual, Motion::Right)
+            }
+            _ => {
+                self.reset();
+                return VimKeyResult::Unbound;
+            }
+        };
+
+        if let Some(pending) = self.pending.take() {
+            return match pending {
+                Pending::G => {
+                    let operator = match c {
+                        'g' => return self.motion(Motion::FileStart),
+                        'u' => Operator::Lowercase,
+                        'U' => Operator::Uppercase,
+                        '~' => Operator::ToggleCase,
+                        _ => return self.cancel(),
+                    };
+                    match self.operator {
+                        // `gugu` is the same as `guu`
+                        Some((pending, _)) if pending == operator => self.operate(Target::Line),
+                        Some(_) => self.cancel(),
+                        None => self.operator(operator, visual),
+                    }
+                }
+                Pending::Find { forward, till } => {
+                    self.last_find = Some((c, forward, till));
+                    self.motion(Motion::FindChar {
+                        ch: c,
+                        forward,
+                        till,
+                    })
+                }
+                Pending::Replace => {
+                    let count = self.count.take().unwrap_or(1);
+                    self.reset();
+                    VimKeyResult::Action(VimAction::Replace { ch: c, count })
+                }
+                Pending::Object { inner } => match object_kind(c) {
+                    Some(kind) => self.text_object(TextObject { kind, inner }, visual),
+                    None => self.cancel(),
+                },
+                Pending::Register => match c {
+                    '"' => {
+                        self.register_name = None;
+                        VimKeyResult::Pending
+                    }
+                    'a'..='z' | 'A'..='Z' | '+' | '*' => {
+                        self.register_name = Some(c);
+                        VimKeyResult::Pending
+                    }
+                    _ => self.cancel(),
+                },
+            };
+        }
+
+        // Counts; a leading `0` is the motion to the start of the line
+        if let Some(digit) = c.to_digit(10) {
+            if digit != 0 || self.count.is_some() {
+                let count = self.count.unwrap_or(0);
+                self.count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+                return VimKeyResult::Pending;
+            }
+        }
+
+        if let Some((operator, _)) = self.operator {
+            // Doubling the operator (`dd`, `>>`, `gUU`) acts on lines
+            let doubled = match operator {
+                Operator::Lowercase => c == 'u',
+                Operator::Uppercase => c == 'U',
+                Operator::ToggleCase => c == '~',
+                _ => operator_for_key(c) == Some(operator),
+            };
+            if doubled {
+                return self.operate(Target::Line);
+            }
+            if c == 'i' || c == 'a' {
+                self.pending = Some(Pending::Object { inner: c == 'i' });
+                return VimKeyResult::Pending;
+            }
+        }
+
+        if let Some(motion) = motion_for_key(c) {
+            return self.motion(motion);
+        }
+        if let Some(operator) = operator_for_key(c) {
+            if self.operator.is_some() {
+                return self.cancel();
+            }
+            return self.operator(operator, visual);
+        }
+
+        match c {
+            'f' | 'F' | 't' | 'T' => {
+                self.pending = Some(Pending::Find {
+                    forward: c == 'f' || c == 't',
+                    till: c == 't' || c == 'T',
+                });
+                VimKeyResult::Pending
+            }
+            ';' | ',' => match self.last_find {
+                Some((ch, forward, till)) => self.motion(Motion::FindChar {
+                    ch,
+                    forward: forward == (c == ';'),
+                    till,
+                }),
+                None => self.cancel(),
+            },
+            'g' => {
+                self.pending = Some(Pending::G);
+                VimKeyResult::Pending
+            }
+            _ if self.operator.is_some() => self.cancel(),
+            '"' => {
+                self.pending = Some(Pending::Register);
+                VimKeyResult::Pending
+            }
+            'i' | 'a' if visual => {
+                self.pending = Some(Pending::Object { inner: c == 'i' });
+                VimKeyResult::Pending
+            }
+            'x' => self.delete_chars(visual, Motion::Right),
+            'X' if !visual => self.delete_chars(visual, Motion::Left),
+            's' if visual => self.operator(Operator::Change, visual),
+            'u' if visual => self.operator(Operator::Lowercase, visual),
+            'U' if visual => self.operator(Operator::Uppercase, visual),
+            '~' => {
+                if visual {
+                    self.operator(Operator::ToggleCase, visual)
+                } else {
+                    self.shortcut(Operator::ToggleCase, Target::Motion(Motion::Right))
+                }
+            }
+            's' => self.shortcut(Operator::Change, Target::Motion(Motion::Right)),
+            'S' => self.shortcut(Operator::Change, Target::Line),
+            'C' => self.shortcut(Operator::Change, Target::Motion(Motion::LineEnd)),
+            'D' => self.shortcut(Operator::Delete, Target::Motion(Motion::LineEnd)),
+            'Y' => self.shortcut(Operator::Yank, Target::Line),
+            'r' if !visual => {
+                self.pending = Some(Pending::Replace);
+                VimKeyResult::Pending
+            }
+            'J' if !visual => {
+                let count = self.count.take().unwrap_or(2);
+                self.reset();
+                VimKeyResult::Action(VimAction::Join { count })
+            }
+            _ => {
+                self.reset();
+                VimKeyResult::Unbound
+            }
+        }
+    }
+
+    fn cancel(&mut self) -> VimKeyResult {
+        self.reset();
+        self.register_name = None;
+        VimKeyResult::Cancelled
+    }
+
+    fn motion(&mut self, motion: Motion) -> VimKeyResult {
+        if self.operator.is_some() {
+            return self.operate(Target::Motion(motion));
+        }
+        let count = self.count.take();
+        self.reset();
+        VimKeyResult::Action(VimAction::Move { motion, count })
+    }
+
+    fn operator(&mut self, operator: Operator, visual: bool) -> VimKeyResult {
+        if visual {
+            self.reset();
+            return VimKeyResult::Action(VimAction::OperateSelection { operator });
+        }
+        self.operator = Some((operator, self.count.take()));
+        VimKeyResult::Pending
+    }
+
+    /// Complete the pending operator; `2d3w` deletes six words
+    fn operate(&mut self, target: Target) -> VimKeyResult {
+        let Some((operator, operator_count)) = self.operator.take() else {
+            return self.cancel();
+        };
+        let count = match (operator_count, self.count.take()) {
+            (Some(a), Some(b)) => Some(a.saturating_mul(b)),
+            (a, b) => a.or(b),
+        };
+        self.reset();
+        VimKeyResult::Action(VimAction::Operate {
+            operator,
+            target,
+            count,
+        })
+    }
+
+    /// A key standing for an operator and its target (`x` is `dl`, `C` is `c$`)
+    fn shortcut(&mut self, operator: Operator, target: Target) -> VimKeyResult {
+        self.operator = Some((operator, None));
+        self.operate(target)
+    }
+
+    fn delete_chars(&mut self, visual: bool, motion: Motion) -> VimKeyResult {
+        if visual {
+            return self.operator(Operator::Delete, visual);
+        }
+        self.shortcut(Operator::Delete, Target::Motion(motion))
+    }
+
+    fn text_object(&mut self, object: TextObject, visual: bool) -> VimKeyResult {
+        if self.operator.is_some() {
+            return self.operate(Target::TextObject(object));
+        }
+        if !visual {
+            return self.cancel();
+        }
+        let count = self.count.take();
+        self.reset();
+        VimKeyResult::Action(VimAction::Select { object, count })
+    }
+
+    /// Remember a key of the command being typed, or of the insert it started
+    pub fn record_key(&mut self, key: KeyEvent) {
+        if !self.replaying {
+            self.keys.push(key);
+        }
+    }
+
+    /// The command being typed is complete; keep it for `.` if it was a change
+    pub fn finish_command(&mut self, change: bool) {
+        self.register_name = None;
+        if self.replaying {
+            return;
+        }
+        if change {
+            self.last_change = std::mem::take(&mut self.keys);
+        } else {
+            self.keys.clear();
+        }
+    }
+
+    /// The command being typed entered insert mode; the keys typed until the insert
+    /// ends belong to the same change
+    pub fn start_insert(&mut self) {
+        self.recording_insert = !self.replaying;
+    }
+
+    /// Whether keys typed in insert mode are being recorded
+    pub fn is_recording_insert(&self) -> bool {
+        self.recording_insert
+    }
+
+    /// The insert started by the last command ended
+    pub fn finish_insert(&mut self) {
+        if self.recording_insert {
+            self.recording_insert = false;
+            self.finish_command(true);
+        }
+    }
+
+    /// Keys replaying the last change, with `count` replacing its own count
+    pub fn repeat_keys(&self, count: Option<usize>) -> Vec<KeyEvent> {
+        let mut keys = self.last_change.clone();
+        if let Some(count) = count {
+            let digits = keys
+                .iter()
+                .take_while(|key| matches!(key.code, KeyCode::Char(c) if c.is_ascii_digit()))
+                .count();
+            keys.splice(
+                0..digits,
+                count
+                    .to_string()
+                    .chars()
+                    .map(|c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)),
+            );
+        }
+        keys
+    }
+}
+
+fn motion_for_key(c: char) -> Option<Motion> {
+    Some(match c {
+        'h' => Motion::Left,
+        'l' | ' ' => Motion::Right,
+        'j' => Motion::Down,
+        'k' => Motion::Up,
+        'w' => Motion::WordForward { big: false },
+        'W' => Motion::WordForward { big: true },
+        'b' => Motion::WordBackward { big: false },
+        'B' => Motion::WordBackward { big: true },
+        'e' => Motion::WordEnd { big: false },
+        'E' => Motion::WordEnd { big: true },
+        '0' => Motion::LineStart,
+        '^' => Motion::FirstNonBlank,
+        '$' => Motion::LineEnd,
+        '+' => Motion::NextLineStart,
+        '-' => Motion::PrevLineStart,
+        'G' => Motion::FileEnd,
+        '}' => Motion::ParagraphForward,
+        '{' => Motion::ParagraphBackward,
+        '%' => Motion::MatchPair,
+        _ => return None,
+    })
+}
+
+fn operator_for_key(c: char) -> Option<Operator> {
+    Some(match c {
+        'd' => Operator::Delete,
+        'c' => Operator::Change,
+        'y' => Operator::Yank,
+        '>' => Operator::Indent,
+        '<' => Operator::Outdent,
+        _ => return None,
+    })
+}
+
+fn object_kind(c: char) -> Option<ObjectKind> {
+    Some(match c {
+        'w' => ObjectKind::Word { big: false },
+        'W' => ObjectKind::Word { big: true },
+        '"' | '\'' | '`' => ObjectKind::Quote(c),
+        '(' | ')' | 'b' => ObjectKind::Block('(', ')'),
+        '[' | ']' => ObjectKind::Block('[', ']'),
+        '{' | '}' | 'B' => ObjectKind::Block('{', '}'),
+        '<' | '>' => ObjectKind::Block('<', '>'),
+        'p' => ObjectKind::Paragraph,
+        'f' => ObjectKind::Syntax(TextObjectKind::Function),
+        'c' => ObjectKind::Syntax(TextObjectKind::Class),
+        'a' => ObjectKind::Syntax(TextObjectKind::Argument),
+        _ => return None,
+    })
+}
+
+// Text helpers. Positions are byte offsets at character boundaries.
+
+fn char_at(text: &str, pos: usize) -> Option<char> {
+    text.get(pos..)?.chars().next()
+}
+
+/// Position of the character after the one at `pos`
+pub fn next_char_pos(text: &str, pos: usize) -> usize {
+    char_at(text, pos).map_or(text.len(), |c| pos + c.len_utf8())
+}
+
+/// Position of the character before `pos`
+pub fn prev_char_pos(text: &str, pos: usize) -> usize {
+    text[..pos]
+        .chars()
+        .next_back()
+        .map_or(0, |c| pos - c.len_utf8())
+}
+
+/// Start of the line containing `pos`
+pub fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// End of the line containing `pos` (the position of its newline)
+pub fn line_end(text: &str, pos: usize) -> usize {
+    text[pos..].find('\n').map_or(text.len(), |i| pos + i)
+}
+
+/// Start of the line after the one containing `pos`, or the end of the text
+fn next_line_start(text: &str, pos: usize) -> usize {
+    (line_end(text, pos) + 1).min(text.len())
+}
+
+/// First non-blank character of the line containing `pos`
+pub fn first_non_blank(text: &str, pos: usize) -> usize {
+    let start = line_start(text, pos);
+    let line = &text[start..line_end(text, pos)];
+    start + line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// Nearest position the cursor may take in normal mode: on a character, not on the
+/// newline ending a non-empty line nor on the empty line after a final newline
+pub fn normal_position(text: &str, pos: usize) -> usize {
+    let mut pos = pos.min(text.len());
+    if pos == text.len() && pos > 0 && text.ends_with('\n') {
+        pos -= 1;
+    }
+    if pos >= line_end(text, pos) && pos > line_start(text, pos) {
+        pos = prev_char_pos(text, pos);
+    }
+    pos
+}
+
+/// Zero-based number of the line containing `pos`
+pub fn line_index(text: &str, pos: usize) -> usize {
+    text.as_bytes()[..pos]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count()
+}
+
+/// Zero-based number of the last line (a final newline doesn't start a line)
+pub fn last_line(text: &str) -> usize {
+    let newlines = line_index(text, text.len());
+    if text.ends_with('\n') {
+        newlines - 1
+    } else {
+        newlines
+    }
+}
+
+/// Start of the zero-based line `line`
+pub fn line_start_of(text: &str, line: usize) -> Option<usize> {
+    if line == 0 {
+        return Some(0);
+    }
+    text.match_indices('\n').nth(line - 1).map(|(i, _)| i + 1)
+}
+
+/// Column of `pos` in characters
+fn char_column(text: &str, pos: usize) -> usize {
+    text[line_start(text, pos)..pos].chars().count()
+}
+
+/// Position of character column `column` on the line starting at `start`, clamped
+/// to the end of the line
+fn column_pos(text: &str, start: usize, column: usize) -> usize {
+    let end = line_end(text, start);
+    text[start..end]
+        .char_indices()
+        .nth(column)
+        .map_or(end, |(i, _)| start + i)
+}
+
+fn is_blank_line(text: &str, pos: usize) -> bool {
+    text[line_start(text, pos)..line_end(text, pos)]
+        .trim()
+        .is_empty()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Space,
+    Word,
+    Punctuation,
+}
+
+fn char_class(c: char, big: bool) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Space
+    } else if big || c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// `w`: start of the next word, stopping at empty lines
+fn word_forward(text: &str, pos: usize, big: bool) -> usize {
+    let mut p = pos;
+    if let Some(c) = char_at(text, p) {
+        let class = char_class(c, big);
+        if class != CharClass::Space {
+            while let Some(c) = char_at(text, p) {
+                if char_class(c, big) != class {
+                    break;
+                }
+                p += c.len_utf8();
+            }
+        }
+    }
+    while let Some(c) = char_at(text, p) {
+        if c == '\n' {
+            p += 1;
+            if char_at(text, p) == Some('\n') {
+                return p;
+            }
+        } else if c.is_whitespace() {
+            p += c.len_utf8();
+        } else {
+            break;
+        }
+    }
+    p
+}
+
+/// `e`: end of the word after `pos`
+fn word_end(text: &str, pos: usize, big: bool) -> usize {
+    let mut p = next_char_pos(text, pos);
+    while let Some(c) = char_at(text, p) {
+        if !c.is_whitespace() {
+            break;
+        }
+        p += c.len_utf8();
+    }
+    match char_at(text, p) {
+        Some(c) => word_end_from(text, p, char_class(c, big), big),
+        None => prev_char_pos(text, text.len()).max(pos),
+    }
+}
+
+/// Last character of the run of `class` characters containing `pos`
+fn word_end_from(text: &str, pos: usize, class: CharClass, big: bool) -> usize {
+    let mut p = pos;
+    loop {
+        let next = next_char_pos(text, p);
+        match char_at(text, next) {
+            Some(c) if c != '\n' && char_class(c, big) == class => p = next,
+            _ => return p,
+        }
+    }
+}
+
+/// `b`: start of the word before `pos`
+fn word_backward(text: &str, pos: usize, big: bool) -> usize {
+    let mut p = pos;
+    loop {
+        if p == 0 {
+            return 0;
+        }
+        p = prev_char_pos(text, p);
+        if !char_at(text, p).is_some_and(char::is_whitespace) {
+            break;
+        }
+    }
+    let class = char_at(text, p).map_or(CharClass::Space, |c| char_class(c, big));
+    while p > 0 {
+        let prev = prev_char_pos(text, p);
+        if char_at(text, prev).map(|c| char_class(c, big)) != Some(class) {
+            break;
+        }
+        p = prev;
+    }
+    p
+}
+
+fn find_char(
+    text: &str,
+    pos: usize,
+    ch: char,
+    forward: bool,
+    till: bool,
+    count: usize,
+) -> Option<usize> {
+    let start = line_start(text, pos);
+    let end = line_end(text, pos);
+    if forward {
+        if pos >= end {
+            return None;
+        }
+        let from = next_char_pos(text, pos);
+        let (i, _) = text[from..end]
+            .char_indices()
+            .filter(|(_, c)| *c == ch)
+            .nth(count - 1)?;
+        let found = from + i;
+        Some(if till {
+            prev_char_pos(text, found)
+        } else {
+            found
+        })
+    } else {
+        let (i, _) = text[start..pos]
+            .char_indices()
+            .rev()
+            .filter(|(_, c)| *c == ch)
+            .nth(count - 1)?;
+        let found = start + i;
+        Some(if till {
+            next_char_pos(text, found)
+        } else {
+            found
+        })
+    }
+}
+
+fn paragraph_forward(text: &str, pos: usize) -> usize {
+    let start = line_start(text, pos);
+    let on_blank = is_blank_line(text, start);
+    let mut p = next_line_start(text, start);
+    if on_blank {
+        while p < text.len() && is_blank_line(text, p) {
+            p = next_line_start(text, p);
+        }
+    }
+    while p < text.len() && !is_blank_line(text, p) {
+        p = next_line_start(text, p);
+    }
+    p
+}
+
+fn paragraph_backward(text: &str, pos: usize) -> usize {
+    let start = line_start(text, pos);
+    if start == 0 {
+        return 0;
+    }
+    let on_blank = is_blank_line(text, start);
+    let mut p = line_start(text, start - 1);
+    if on_blank {
+        while p > 0 && is_blank_line(text, p) {
+            p = line_start(text, p - 1);
+        }
+    }
+    while p > 0 && !is_blank_line(text, p) {
+        p = line_start(text, p - 1);
+    }
+    p
+}
+
+fn match_pair(text: &str, pos: usize) -> Option<usize> {
+    let end = line_end(text, pos);
+    let (offset, c) = text[pos..end]
+        .char_indices()
+        .find(|(_, c)| "()[]{}".contains(*c))?;
+    let at = pos + offset;
+    let (open, close, forward) = match c {
+        '(' => ('(', ')', true),
+        ')' => ('(', ')', false),
+        '[' => ('[', ']', true),
+        ']' => ('[', ']', false),
+        '{' => ('{', '}', true),
+        _ => ('{', '}', false),
+    };
+    let mut depth = 0usize;
+    if forward {
+        for (i, ch) in text[at..].char_indices() {
+            if ch == open {
+                depth += 1;
+            } else if ch == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(at + i);
+                }
+            }
+        }
+    } else {
+        for (i, ch) in text[..=at].char_indices().rev() {
+            if ch == close {
+                depth += 1;
+            } else if ch == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Where `motion` repeated `count` times moves the cursor from `pos`
+///
+/// Returns `None` when the motion fails, like `j` on the last line.
+pub fn motion_target(
+    text: &str,
+    pos: usize,
+    motion: Motion,
+    count: Option<usize>,
+) -> Option<usize> {
+    let n = count.unwrap_or(1).max(1);
+    let line = line_index(text, pos);
+    let last = last_line(text);
+    let first_non_blank_of =
+        |line: usize| line_start_of(text, line).map(|s| first_non_blank(text, s));
+
+    let target = match motion {
+        Motion::Left => {
+            let start = line_start(text, pos);
+            let mut p = pos;
+            for _ in 0..n {
+                if p <= start {
+                    break;
+                }
+                p = prev_char_pos(text, p);
+            }
+            p
+        }
+        Motion::Right => {
+            let end = line_end(text, pos);
+            let mut p = pos;
+            for _ in 0..n {
+                if p >= end {
+                    break;
+                }
+                p = next_char_pos(text, p);
+            }
+            p
+        }
+        Motion::Up | Motion::Down => {
+            let target_line = if motion == Motion::Up {
+                if line == 0 {
+                    return None;
+                }
+                line.saturating_sub(n)
+            } else if line < last {
+                (line + n).min(last)
+            } else {
+                return None;
+            };
+            column_pos(
+                text,
+                line_start_of(text, target_line)?,
+                char_column(text, pos),
+            )
+        }
+        Motion::WordForward { big } => (0..n).fold(pos, |p, _| word_forward(text, p, big)),
+        Motion::WordBackward { big } => (0..n).fold(pos, |p, _| word_backward(text, p, big)),
+        Motion::WordEnd { big } => (0..n).fold(pos, |p, _| word_end(text, p, big)),
+        Motion::LineStart => line_start(text, pos),
+        Motion::FirstNonBlank => first_non_blank(text, pos),
+        Motion::LineEnd => line_end(text, line_start_of(text, (line + n - 1).min(last))?),
+        Motion::NextLineStart => {
+            if line >= last {
+                return None;
+            }
+            first_non_blank_of((line + n).min(last))?
+        }
+        Motion::PrevLineStart => {
+            if line == 0 {
+                return None;
+            }
+            first_non_blank_of(line.saturating_sub(n))?
+        }
+        Motion::FileStart => first_non_blank_of(count.map_or(0, |c| c - 1).min(last))?,
+        Motion::FileEnd => first_non_blank_of(count.map_or(last, |c| c - 1).min(last))?,
+        Motion::FindChar { ch, forward, till } => find_char(text, pos, ch, forward, till, n)?,
+        Motion::ParagraphForward => (0..n).fold(pos, |p, _| paragraph_forward(text, p)),
+        Motion::ParagraphBackward => (0..n).fold(pos, |p, _| paragraph_backward(text, p)),
+        Motion::MatchPair => match_pair(text, pos)?,
+    };
+    Some(target)
+}
+
+/// Text an operator acts on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VimRange {
+    pub range: Range<usize>,
+    /// Whole lines, including the final newline when there is one
+    pub linewise: bool,
+}
+
+/// Whole lines from the line containing `from` to the one containing `to`
+pub fn line_range(text: &str, from: usize, to: usize) -> Range<usize> {
+    let (from, to) = (from.min(to), from.max(to));
+    line_start(text, from)..next_line_start(text, to)
+}
+
+/// The text `operator` acts on when applied to `target` from `pos`
+pub fn operator_range(
+    text: &str,
+    pos: usize,
+    operator: Operator,
+    target: Target,
+    count: Option<usize>,
+) -> Option<VimRange> {
+    let n = count.unwrap_or(1).max(1);
+    match target {
+        Target::Line => {
+            let last = line_start_of(text, (line_index(text, pos) + n - 1).min(last_line(text)))?;
+            Some(VimRange {
+                range: line_range(text, pos, last),
+                linewise: true,
+            })
+        }
+        Target::TextObject(object) => text_object_range(text, pos, object, count),
+        // `cw` on a word changes up to its end, like `ce`
+        Target::Motion(Motion::WordForward { big })
+            if operator == Operator::Change
+                && char_at(text, pos).is_some_and(|c| !c.is_whitespace()) =>
+        {
+            let class = char_class(char_at(text, pos)?, big);
+            let end = (1..n).fold(word_end_from(text, pos, class, big), |p, _| {
+                word_end(text, p, big)
+            });
+            Some(VimRange {
+                range: pos..next_char_pos(text, end),
+                linewise: false,
+            })
+        }
+        Target::Motion(motion) => {
+            let target = motion_target(text, pos, motion, count)?;
+            let (start, end) = (pos.min(target), pos.max(target));
+            let range = match motion.kind() {
+                MotionKind::Linewise => {
+                    return Some(VimRange {
+                        range: line_range(text, start, end),
+                        linewise: true,
+                    })
+                }
+                MotionKind::Inclusive => match char_at(text, end) {
+                    Some(c) if c != '\n' => start..end + c.len_utf8(),
+                    _ => start..end,
+                },
+                MotionKind::Exclusive => {
+                    if end > start && end == line_start(text, end) {
+                        if matches!(motion, Motion::WordForward { .. }) {
+                            // The last word moved over ends the text, not the next line
+                            start..end - 1
+                        } else if start <= first_non_blank(text, start) {
+                            // `d}` from the start of a paragraph deletes whole lines
+                            return Some(VimRange {
+                                range: line_range(text, start, end - 1),
+                                linewise: true,
+                            });
+                        } else {
+                            start..end - 1
+                        }
+                    } else {
+                        start..end
+                    }
+                }
+            };
+            Some(VimRange {
+                range,
+                linewise: false,
+            })
+        }
+    }
+}
+
+/// Range of a text object around `pos`
+pub fn text_object_range(
+    text: &str,
+    pos: usize,
+    object: TextObject,
+    count: Option<usize>,
+) -> Option<VimRange> {
+    let n = count.unwrap_or(1).max(1);
+    let range = match object.kind {
+        ObjectKind::Word { big } => word_object(text, pos, object.inner, big, n)?,
+        ObjectKind::Quote(quote) => quote_object(text, pos, quote, object.inner)?,
+        ObjectKind::Block(open, close) => block_object(text, pos, open, close, object.inner, n)?,
+        ObjectKind::Paragraph => {
+            return Some(VimRange {
+                range: paragraph_object(text, pos, object.inner, n),
+                linewise: true,
+            })
+        }
+        // Needs the buffer's syntax tree; resolved by the editor
+        ObjectKind::Syntax(_) => return None,
+    };
+    Some(VimRange {
+        range,
+        linewise: false,
+    })
+}
+
+fn word_object(
+    text: &str,
+    pos: usize,
+    inner: bool,
+    big: bool,
+    count: usize,
+) -> Option<Range<usize>> {
+    let start = line_start(text, pos);
+    let end = line_end(text, pos);
+    if pos >= end {
+        return None;
+    }
+    // The run of characters of one class containing `p`
+    let run = |p: usize| {
+        let class = char_class(char_at(text, p).unwrap_or(' '), big);
+        let same = |q: usize| char_at(text, q).is_some_and(|c| char_class(c, big) == class);
+        let mut s = p;
+        while s > start && same(prev_char_pos(text, s)) {
+            s = prev_char_pos(text, s);
+        }
+        let mut e = p;
+        while e < end && same(e) {
+            e = next_char_pos(text, e);
+        }
+        s..e
+    };
+    let on_space = char_at(text, pos).is_some_and(char::is_whitespace);
+    let mut range = run(pos);
+    if inner {
+        for _ in 1..count {
+            if range.end >= end {
+                break;
+            }
+            range.end = run(range.end).end;
+        }
+    } else if on_space {
+        if range.end < end {
+            range.end = run(range.end).end;
+        }
+    } else if range.end < end && char_at(text, range.end).is_some_and(char::is_whitespace) {
+        range.end = run(range.end).end;
+    } else if range.start > start
+        && char_at(text, prev_char_pos(text, range.start)).is_some_and(char::is_whitespace)
+    {
+        range.start = run(prev_char_pos(text, range.start)).start;
+    }
+    Some(range)
+}
+
+fn quote_object(text: &str, pos: usize, quote: char, inner: bool) -> Option<Range<usize>> {
+    let start = line_start(text, pos);
+    let end = line_end(text, pos);
+    let line = &text[start..end];
+    let mut quotes = Vec::new();
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if c == quote && !escaped {
+            quotes.push(start + i);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    let (open, close) = quotes
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|&(_, close)| pos <= close)?;
+    if inner {
+        return Some(open + quote.len_utf8()..close);
+    }
+    let mut range = open..close + quote.len_utf8();
+    let trailing = text[range.end..end].len() - text[range.end..end].trim_start().len();
+    if trailing > 0 {
+        range.end += trailing;
+    } else {
+        let leading = text[start..open].len() - text[start..open].trim_end().len();
+        range.start -= leading;
+    }
+    Some(range)
+}
+
+fn block_object(
+    text: &str,
+    pos: usize,
+    open: char,
+    close: char,
+    inner: bool,
+    count: usize,
+) -> Option<Range<usize>> {
+    // Find the unmatched opening character before the cursor, `count` levels out; the
+    // cursor on an opening character counts as the first level
+    let on_open = char_at(text, pos) == Some(open);
+    let mut open_pos = on_open.then_some(pos);
+    let mut search_end = pos;
+    for _ in usize::from(on_open)..count {
+        let mut depth = 0usize;
+        let mut found = None;
+        for (i, c) in text[..search_end].char_indices().rev() {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                if depth == 0 {
+                    found = Some(i);
+                    break;
+                }
+                depth -= 1;
+            }
+        }
+        let found = found?;
+        open_pos = Some(found);
+        search_end = found;
+    }
+    let open_pos = open_pos?;
+
+    let mut depth = 0usize;
+    let mut close_pos = None;
+    let after_open = open_pos + open.len_utf8();
+    for (i, c) in text[after_open..].char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                close_pos = Some(after_open + i);
+                break;
+            }
+            depth -= 1;
+        }
+    }
+    let close_pos = close_pos?;
+
+    if !inner {
+        return Some(open_pos..close_pos + close.len_utf8());
+    }
+    let mut start = after_open;
+    if text[start..close_pos].starts_with('\n') {
+        start += 1;
+    }
+    let mut end = close_pos;
+    // Keep the line of the closing character when it is on a line of its own
+    let close_line = line_start(text, close_pos);
+    if close_line > start && text[close_line..close_pos].trim().is_empty() {
+        end = close_line;
+    }
+    Some(start..end.max(start))
+}
+
+fn paragraph_object(text: &str, pos: usize, inner: bool, count: usize) -> Range<usize> {
+    let run_start = |p: usize| {
+        let blank = is_blank_line(text, p);
+        let mut s = line_start(text, p);
+        while s > 0 && is_blank_line(text, s - 1) == blank {
+            s = line_start(text, s - 1);
+        }
+        s
+    };
+    let run_end = |p: usize| {
+        let blank = is_blank_line(text, p);
+        let mut e = line_start(text, p);
+        while e < text.len() && is_blank_line(text, e) == blank {
+            e = next_line_start(text, e);
+        }
+        e
+    };
+    let mut start = run_start(pos);
+    let mut end = run_end(pos);
+    for _ in 1..count {
+        if end < text.len() {
+            end = run_end(end);
+        }
+    }
+    if !inner {
+        if end < text.len() {
+            end = run_end(end);
+        } else if start > 0 {
+            start = run_start(start - 1);
+        }
+    }
+    start..end
+}
+
+/// Characters selected in visual block mode, one range per line
+pub fn block_ranges(text: &str, from: usize, to: usize) -> Vec<Range<usize>> {
+    let (a, b) = (char_column(text, from), char_column(text, to));
+    let (left, right) = (a.min(b), a.max(b));
+    let (first, last) = (
+        line_index(text, from.min(to)),
+        line_index(text, from.max(to)),
+    );
+    (first..=last)
+        .filter_map(|line| {
+            let start = line_start_of(text, line)?;
+            let begin = column_pos(text, start, left);
+            let end = column_pos(text, start, right + 1);
+            (begin < end).then_some(begin..end)
+        })
+        .collect()
+}
+
+/// Characters selected in characterwise visual mode
+pub fn visual_range(text: &str, from: usize, to: usize) -> Range<usize> {
+    let (start, end) = (from.min(to), from.max(to));
+    start..next_char_pos(text, end)
+}
+
+/// A line address of an ex command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExAddress {
+    /// `.`
+    Current,
+    /// `$`
+    Last,
+    /// A one-based line number
+    Line(usize),
+    /// `'<`
+    VisualStart,
+    /// `'>`
+    VisualEnd,
+}
+
+/// Lines an ex command applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExRange {
+    pub start: ExAddress,
+    pub end: ExAddress,
+}
+
+impl ExRange {
+    fn current() -> Self {
+        Self {
+            start: ExAddress::Current,
+            end: ExAddress::Current,
+        }
+    }
+}
+
+/// A parsed `:` command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExCommand {
+    /// `:w`
+    Write,
+    /// `:q`
+    Quit,
+    /// `:wq` and `:x`
+    WriteQuit,
+    /// `:<line>`
+    GotoLine(ExAddress),
+    /// `:s/pattern/replacement/flags`
+    Substitute {
+        range: ExRange,
+        /// Regular expression (already translated from Vim's syntax)
+        pattern: String,
+        /// Replacement in the `regex` crate's syntax
+        replacement: String,
+        global: bool,
+        ignore_case: bool,
+    },
+    /// `:norm keys`
+    Normal { range: ExRange, keys: String },
+    /// `:earlier 5m`, in milliseconds
+    Earlier(u64),
+    /// `:later 5m`, in milliseconds
+    Later(u64),
+    /// `:!cmd` shows the output of `cmd`; `:{range}!cmd` filters the lines of
+    /// `range` through it
+    Filter {
+        range: Option<ExRange>,
+        command: String,
+    },
+}
+
+fn parse_address(input: &str) -> (Option<ExAddress>, &str) {
+    if let Some(rest) = input.strip_prefix('.') {
+        (Some(ExAddress::Current), rest)
+    } else if let Some(rest) = input.strip_prefix('$') {
+        (Some(ExAddress::Last), rest)
+    } else if let Some(rest) = input.strip_prefix("'<") {
+        (Some(ExAddress::VisualStart), rest)
+    } else if let Some(rest) = input.strip_prefix("'>") {
+        (Some(ExAddress::VisualEnd), rest)
+    } else {
+        let digits = input.len() - input.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        match input[..digits].parse() {
+            Ok(line) => (Some(ExAddress::Line(line)), &input[digits..]),
+            Err(_) => (None, input),
+        }
+    }
+}
+
+/// Parse a `:` command line
+pub fn parse_ex_command(input: &str) -> Result<ExCommand, String> {
+    let input = input.trim();
+    let (range, rest) = if let Some(rest) = input.strip_prefix('%') {
+        (
+            Some(ExRange {
+                start: ExAddress::Line(1),
+                end: ExAddress::Last,
+            }),
+            rest,
+        )
+    } else {
+        match parse_address(input) {
+            (Some(start), rest) => match rest.strip_prefix(',').map(parse_address) {
+                Some((Some(end), rest)) => (Some(ExRange { start, end }), rest),
+                Some((None, _)) => return Err(format!("Invalid range: {}", input)),
+                None => (Some(ExRange { start, end: start }), rest),
+            },
+            (None, rest) => (None, rest),
+        }
+    };
+    let rest = rest.trim_start();
+    if let Some(command) = rest.strip_prefix('!') {
+        let command = command.trim();
+        if command.is_empty() {
+            return Err("Usage: [range]!command".to_string());
+        }
+        return Ok(ExCommand::Filter {
+            range,
+            command: command.to_string(),
+        });
+    }
+    let name_len = rest.len()
+        - rest
+            .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+            .len();
+    let (name, args) = rest.split_at(name_len);
+    let args = args.strip_prefix('!').unwrap_or(args);
+
+    match name {
+        "" if args.is_empty() => match range {
+            Some(range) => Ok(ExCommand::GotoLine(range.end)),
+            None => Err("Empty command".to_string()),
+        },
+        "w" | "write" if args.is_empty() => Ok(ExCommand::Write),
+        "q" | "quit" if args.is_empty() => Ok(ExCommand::Quit),
+        "wq" | "x" | "xit" if args.is_empty() => Ok(ExCommand::WriteQuit),
+        "s" | "substitute" => parse_substitute(range.unwrap_or_else(ExRange::current), args),
+        "norm" | "normal" if !args.is_empty() => Ok(ExCommand::Normal {
+            range: range.unwrap_or_else(ExRange::current),
+            keys: args.strip_prefix(' ').unwrap_or(args).to_string(),
+        }),
+        "ea" | "earlier" => parse_time_offset(args)
+            .map(ExCommand::Earlier)
+            .ok_or_else(|| "Usage: earlier {N}s|m|h|d".to_string()),
+        "lat" | "later" => parse_time_offset(args)
+            .map(ExCommand::Later)
+            .ok_or_else(|| "Usage: later {N}s|m|h|d".to_string()),
+        _ => Err(format!("Not an editor command: {}", input)),
+    }
+}
+
+fn parse_substitute(range: ExRange, args: &str) -> Result<ExCommand, String> {
+    let mut chars = args.chars();
+    let delimiter = chars
+        .next()
+        .filter(|c| !c.is_alphanumeric() && !c.is_whitespace() && *c != '\\' && *c != '"')
+        .ok_or_else(|| "Usage: s/pattern/replacement/flags".to_string())?;
+
+    // Split on unescaped delimiters; `\<delimiter>` stands for the delimiter itself
+    let mut parts = vec![String::new()];
+    let mut chars = chars.peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                Some(next) => {
+                    let part = parts.last_mut().unwrap();
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => parts.last_mut().unwrap().push('\\'),
+            }
+        } else if c == delimiter && parts.len() < 3 {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+    let pattern = &parts[0];
+    if pattern.is_empty() {
+        return Err("Empty search pattern".to_string());
+    }
+    let replacement = parts.get(1).map(String::as_str).unwrap_or("");
+    let mut global = false;
+    let mut ignore_case = false;
+    for flag in parts
+        .get(2)
+        .map(String::as_str)
+        .unwrap_or("")
+        .trim()
+        .chars()
+    {
+        match flag {
+            'g' => global = true,
+            'i' => ignore_case = true,
+            'I' => ignore_case = false,
+            _ => return Err(format!("Invalid substitute flag: {}", flag)),
+        }
+    }
+    Ok(ExCommand::Substitute {
+        range,
+        pattern: vim_pattern_to_regex(pattern),
+        replacement: vim_replacement(replacement),
+        global,
+        ignore_case,
+    })
+}
+
+/// Translate a Vim ("magic") pattern to the `regex` crate's syntax
+///
+/// In Vim `(`, `|`, `+`, `?` and `{` are literal and their backslashed forms are
+/// special; `\<` and `\>` are word boundaries.
+pub fn vim_pattern_to_regex(pattern: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('(' | ')' | '|' | '+' | '?' | '{' | '}')) => regex.push(c),
+                Some('<' | '>') => regex.push_str("\\b"),
+                Some(c) => {
+                    regex.push('\\');
+                    regex.push(c);
+                }
+                None => regex.push_str("\\\\"),
+            },
+            '(' | ')' | '|' | '+' | '?' | '{' | '}' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+    }
+    regex
+}
+
+/// Translate a Vim substitute replacement to the `regex` crate's syntax
+///
+/// `&` and `\0` are the whole match, `\1`-`\9` the groups and `\r`/`\n` a line break.
+pub fn vim_replacement(replacement: &str) -> String {
+    let mut result = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => result.push_str("${0}"),
+            '$' => result.push_str("$$"),
+            '\\' => match chars.next() {
+                Some(digit @ '0'..='9') => {
+                    result.push_str("${");
+                    result.push(digit);
+                    result.push('}');
+                }
+                Some('r' | 'n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some(c) => result.push(c),
+                None => result.push('\\'),
+            },
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(state: &mut VimState, keys: &str, visual: bool) -> VimKeyResult {
+        let mut result = VimKeyResult::Unbound;
+        for c in keys.chars() {
+            result = state.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), visual);
+        }
+        result
+    }
+
+    fn apply(text: &str, pos: usize, keys: &str) -> Option<VimRange> {
+        match feed(&mut VimState::new(), keys, false) {
+            VimKeyResult::Action(VimAction::Operate {
+                operator,
+                target,
+                count,
+            }) => operator_range(text, pos, operator, target, count),
+            other => panic!("{} did not parse to an operator: {:?}", keys, other),
+        }
+    }
+
+    #[test]
+    fn test_grammar_composes_operators_counts_and_targets() {
+        let mut state = VimState::new();
+        assert_eq!(
+            feed(&mut state, "2d3w", false),
+            VimKeyResult::Action(VimAction::Operate {
+                operator: Operator::Delete,
+                target: Target::Motion(Motion::WordForward { big: false }),
+                count: Some(6),
+            })
+        );
+        assert_eq!(
+            feed(&mut state, "ci\"", false),
+            VimKeyResult::Action(VimAction::Operate {
+                operator: Operator::Change,
+                target: Target::TextObject(TextObject {
+                    kind: ObjectKind::Quote('"'),
+                    inner: true
+                }),
+                count: None,
+            })
+        );
+        assert_eq!(
+            feed(&mut state, ">ip", false),
+            VimKeyResult::Action(VimAction::Operate {
+                operator: Operator::Indent,
+                target: Target::TextObject(TextObject {
+                    kind: ObjectKind::Paragraph,
+                    inner: true
+                }),
+                count: None,
+            })
+        );
+        assert_eq!(
+            feed(&mut state, "daa", false),
+            VimKeyResult::Action(VimAction::Operate {
+                operator: Operator::Delete,
+                target: Target::TextObject(TextObject {
+                    kind: ObjectKind::Syntax(TextObjectKind::Argument),
+                    inner: false
+                }),
+                count: None,
+            })
+        );
+        assert_eq!(
+            feed(&mut state, "gUU", false),
+            VimKeyResult::Action(VimAction::Operate {
+                operator: Operator::Uppercase,
+                target: Target::Line,
+                count: None,
+            })
+        );
+        assert_eq!(
+            feed(&mut state, "0", false),
+            VimKeyResult::Action(VimAction::Move {
+                motion: Motion::LineStart,
+                count: None
+            })
+        );
+        // A different operator cancels the pending one
+        assert_eq!(feed(&mut state, "dy", false), VimKeyResult::Cancelled);
+        assert!(!state.is_operator_pending());
+        // In visual mode operators apply to the selection
+        assert_eq!(
+            feed(&mut state, "d", true),
+            VimKeyResult::Action(VimAction::OperateSelection {
+                operator: Operator::Delete
+            })
+        );
+    }
+
+    #[test]
+    fn test_repeat_keys_replace_count() {
+        let mut state = VimState::new();
+        for c in "3x".chars() {
+            state.record_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        state.finish_command(true);
+        let keys: String = state
+            .repeat_keys(Some(12))
+            .iter()
+            .filter_map(|key| match key.code {
+                KeyCode::Char(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(keys, "12x");
+        assert_eq!(state.repeat_keys(None).len(), 2);
+    }
+
+    #[test]
+    fn test_register_prefix() {
+        let mut state = VimState::new();
+        assert_eq!(
+            feed(&mut state, "\"a2dd", false),
+            VimKeyResult::Action(VimAction::Operate {
+                operator: Operator::Delete,
+                target: Target::Line,
+                count: Some(2),
+            })
+        );
+        assert_eq!(state.take_register_name(), Some('a'));
+
+        // The name is kept for a command bound outside the parser (`"+p`)
+        assert_eq!(feed(&mut state, "\"+", false), VimKeyResult::Pending);
+        assert!(!state.is_awaiting_key());
+        state.reset();
+        assert_eq!(state.take_register_name(), Some('+'));
+
+        assert_eq!(
+            feed(&mut state, "\"Ax", false),
+            VimKeyResult::Action(VimAction::Operate {
+                operator: Operator::Delete,
+                target: Target::Motion(Motion::Right),
+                count: None,
+            })
+        );
+        state.finish_command(true);
+        assert_eq!(state.take_register_name(), None);
+        assert_eq!(feed(&mut state, "\"%", false), VimKeyResult::Cancelled);
+        assert_eq!(
+            VimRegister::from_text("line\n".to_string()).kind,
+            RegisterKind::Linewise
+        );
+    }
+
+    #[test]
+    fn test_word_motions() {
+        let text = "foo.bar  baz\n\nqux";
+        let w = |pos, big| motion_target(text, pos, Motion::WordForward { big }, None);
+        assert_eq!(w(0, false), Some(3));
+        assert_eq!(w(3, false), Some(4));
+        assert_eq!(w(0, true), Some(9));
+        // Empty lines are words
+        assert_eq!(w(9, false), Some(13));
+        assert_eq!(
+            motion_target(text, 9, Motion::WordEnd { big: false }, None),
+            Some(11)
+        );
+        assert_eq!(
+            motion_target(text, 9, Motion::WordBackward { big: false }, Some(2)),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_operator_ranges() {
+        let text = "let x = \"hello world\";\nfoo bar\n";
+        // `dw` on the last word of a line stops at the end of the line
+        assert_eq!(
+            apply(text, 27, "dw"),
+            Some(VimRange {
+                range: 27..30,
+                linewise: false
+            })
+        );
+        // `cw` changes to the end of the word
+        assert_eq!(apply(text, 23, "cw").unwrap().range, 23..26);
+        assert_eq!(apply(text, 12, "ci\"").unwrap().range, 9..20);
+        // Without trailing white space, `a"` takes the leading white space
+        assert_eq!(apply(text, 12, "da\"").unwrap().range, 7..21);
+        assert_eq!(apply(text, 0, "dt;").unwrap().range, 0..21);
+        assert_eq!(
+            apply(text, 3, "2dd"),
+            Some(VimRange {
+                range: 0..31,
+                linewise: true
+            })
+        );
+        assert_eq!(apply(text, 0, "d$").unwrap().range, 0..22);
+    }
+
+    #[test]
+    fn test_block_and_paragraph_objects() {
+        let text = "fn f() {\n    g(a, (b));\n}\n\nnext\n";
+        let inner_brace = TextObject {
+            kind: ObjectKind::Block('{', '}'),
+            inner: true,
+        };
+        // The lines between the braces, keeping the closing brace's line
+        assert_eq!(
+            text_object_range(text, 15, inner_brace, None)
+                .unwrap()
+                .range,
+            9..24
+        );
+        let around_paren = TextObject {
+            kind: ObjectKind::Block('(', ')'),
+            inner: false,
+        };
+        assert_eq!(
+            text_object_range(text, 19, around_paren, None)
+                .unwrap()
+                .range,
+            18..21
+        );
+        assert_eq!(
+            text_object_range(text, 19, around_paren, Some(2))
+                .unwrap()
+                .range,
+            14..22
+        );
+        let paragraph = |inner| TextObject {
+            kind: ObjectKind::Paragraph,
+            inner,
+        };
+        assert_eq!(
+            text_object_range(text, 15, paragraph(true), None),
+            Some(VimRange {
+                range: 0..26,
+                linewise: true
+            })
+        );
+        assert_eq!(
+            text_object_range(text, 15, paragraph(false), None)
+                .unwrap()
+                .range,
+            0..27
+        );
+    }
+
+    #[test]
+    fn test_block_ranges() {
+        let text = "abcd\nab\nabcdef\n";
+        assert_eq!(block_ranges(text, 1, 10), vec![1..3, 6..7, 9..11]);
+    }
+
+    #[test]
+    fn test_parse_ex_commands() {
+        assert_eq!(parse_ex_command("w"), Ok(ExCommand::Write));
+        assert_eq!(parse_ex_command("x"), Ok(ExCommand::WriteQuit));
+        assert_eq!(
+            parse_ex_command("12"),
+            Ok(ExCommand::GotoLine(ExAddress::Line(12)))
+        );
+        assert_eq!(
+            parse_ex_command("%s/foo\\(\\d\\+\\)/bar&\\1/g"),
+            Ok(ExCommand::Substitute {
+                range: ExRange {
+                    start: ExAddress::Line(1),
+                    end: ExAddress::Last
+                },
+                pattern: "foo(\\d+)".to_string(),
+                replacement: "bar${0}${1}".to_string(),
+                global: true,
+                ignore_case: false,
+            })
+        );
+        assert_eq!(
+            parse_ex_command("'<,'>norm A;"),
+            Ok(ExCommand::Normal {
+                range: ExRange {
+                    start: ExAddress::VisualStart,
+                    end: ExAddress::VisualEnd
+                },
+                keys: "A;".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_ex_command("s#a/b#c#"),
+            Ok(ExCommand::Substitute {
+                range: ExRange::current(),
+                pattern: "a/b".to_string(),
+                replacement: "c".to_string(),
+                global: false,
+                ignore_case: false,
+            })
+        );
+        assert_eq!(
+            parse_ex_command("earlier 5m"),
+            Ok(ExCommand::Earlier(300_000))
+        );
+        assert_eq!(parse_ex_command("lat 10s"), Ok(ExCommand::Later(10_000)));
+        assert_eq!(
+            parse_ex_command("%!jq ."),
+            Ok(ExCommand::Filter {
+                range: Some(ExRange {
+                    start: ExAddress::Line(1),
+                    end: ExAddress::Last
+                }),
+                command: "jq .".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_ex_command("!ls -l"),
+            Ok(ExCommand::Filter {
+                range: None,
+                command: "ls -l".to_string(),
+            })
+        );
+        assert!(parse_ex_command("!").is_err());
+        assert!(parse_ex_command("earlier").is_err());
+        assert!(parse_ex_command("frobnicate").is_err());
+    }
+}
@@ -440,6 +440,9 @@ fn main() -> io::Result<()> {
         #[cfg(not(target_os = "linux"))]
         let loop_result = run_event_loop(&mut editor, &mut terminal, session_enabled);
 
+        // Keep undo history of the files still open
+        editor.persist_undo_histories();
+
         // End recovery session (clean shutdown)
         if let Err(e) = editor.end_recovery_session() {
             tracing::warn!("Failed to end recovery session: {}", e);
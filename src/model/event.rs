@@ -1,6 +1,8 @@
 use crate::view::overlay::{OverlayHandle, OverlayNamespace};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
+use std::sync::Arc;
 
 /// Unique identifier for a cursor
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -473,6 +475,11 @@ pub struct LogEntry {
 
     /// Optional description for debugging
     pub description: Option<String>,
+
+    /// Entry this one was logged after (`None` for the first edit on a branch
+    /// from the initial state)
+    #[serde(default)]
+    pub parent: Option<usize>,
 }
 
 impl LogEntry {
@@ -484,6 +491,7 @@ impl LogEntry {
                 .unwrap()
                 .as_millis() as u64,
             description: None,
+            parent: None,
         }
     }
 
@@ -493,40 +501,81 @@ impl LogEntry {
     }
 }
 
-/// Snapshot of editor state for fast undo/redo
+/// Buffer content at an entry of the undo tree, for cheap jumps between distant states
 #[derive(Debug, Clone)]
 pub struct Snapshot {
-    /// Index in the event log where this snapshot was taken
+    /// Index of the entry the content was taken after
     pub log_index: usize,
 
-    /// Buffer content at this point (stored as ChunkTree reference)
-    /// For now we'll use a placeholder - will be filled in when we implement Buffer
-    pub buffer_state: (),
+    /// Buffer content at this point
+    pub content: Arc<String>,
+}
+
+/// A state in the undo tree: the index of the last applied entry, or `None`
+/// before the first one
+pub type UndoPosition = Option<usize>;
 
-    /// Cursor positions at this point
-    pub cursor_positions: Vec<(CursorId, usize, Option<usize>)>,
+/// What to apply to a buffer to move it to another state of the undo tree
+#[derive(Debug, Clone, Default)]
+pub struct UndoJump {
+    /// Content to replace the buffer with first, when starting from a snapshot
+    /// is cheaper than walking the tree
+    pub base: Option<Arc<String>>,
+
+    /// Events to apply (after loading `base`, if any)
+    pub events: Vec<Event>,
 }
 
-/// The event log - append-only log of all events
+/// Parse a time such as `30s`, `5m`, `2h` or `1d` into milliseconds
+/// (for moving through the undo history by time)
+pub fn parse_time_offset(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let digits = input.len() - input.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let amount: u64 = input[..digits].parse().ok()?;
+    let unit_millis = match input[digits..].trim() {
+        "s" | "sec" | "secs" => 1_000,
+        "m" | "min" | "mins" => 60_000,
+        "h" | "hour" | "hours" => 3_600_000,
+        "d" | "day" | "days" => 86_400_000,
+        _ => return None,
+    };
+    amount.checked_mul(unit_millis)
+}
+
+/// Default for the most bytes of buffer content an event log keeps in snapshots
+pub const DEFAULT_SNAPSHOT_BUDGET: usize = 16 * 1024 * 1024;
+
+/// The event log - an undo tree of all events
+///
+/// Entries are never removed: editing after undo starts a new branch and the
+/// undone entries stay reachable through `jump_to`. Undo and redo move along
+/// the active branch.
 pub struct EventLog {
-    /// All logged events
+    /// All logged events, in the order they were logged; `LogEntry::parent`
+    /// links them into a tree
     entries: Vec<LogEntry>,
 
-    /// Current position in the log (for undo/redo)
+    /// The active branch: entry indices from the root to its tip
+    path: Vec<usize>,
+
+    /// Number of entries of `path` that are applied (for undo/redo)
     current_index: usize,
 
-    /// Periodic snapshots for fast seeking
-    snapshots: Vec<Snapshot>,
+    /// Periodic snapshots for fast seeking, keyed by entry index
+    snapshots: HashMap<usize, Snapshot>,
 
     /// How often to create snapshots (every N events)
     snapshot_interval: usize,
 
+    /// Most bytes of content the snapshots may hold together
+    snapshot_budget: usize,
+
     /// Optional file for streaming events to disk
     stream_file: Option<std::fs::File>,
 
-    /// Index at which the buffer was last saved (for tracking modified status)
-    /// When current_index equals saved_at_index, the buffer is not modified
-    saved_at_index: Option<usize>,
+    /// State at which the buffer was last saved (for tracking modified status)
+    /// When the current state equals it, the buffer is not modified
+    saved_at: Option<UndoPosition>,
 }
 
 impl EventLog {
@@ -534,44 +583,49 @@ impl EventLog {
     pub fn new() -> Self {
         Self {
             entries: Vec::new(),
+            path: Vec::new(),
             current_index: 0,
-            snapshots: Vec::new(),
+            snapshots: HashMap::new(),
             snapshot_interval: 100,
+            snapshot_budget: DEFAULT_SNAPSHOT_BUDGET,
             stream_file: None,
-            saved_at_index: Some(0), // New buffer starts at "saved" state (index 0)
+            saved_at: Some(None), // New buffer starts at "saved" state (before any event)
         }
     }
 
     /// Mark the current position as the saved point
     /// Call this when the buffer is saved to disk
     pub fn mark_saved(&mut self) {
-        self.saved_at_index = Some(self.current_index);
+        self.saved_at = Some(self.position());
     }
 
     /// Check if the buffer is at the saved position (not modified)
     /// Returns true if we're at the saved position OR if all events between
-    /// saved_at_index and current_index are readonly (don't modify buffer content)
+    /// the saved state and the current state are readonly (don't modify buffer content)
     pub fn is_at_saved_position(&self) -> bool {
-        match self.saved_at_index {
+        match self.saved_at {
             None => false,
-            Some(saved_idx) if saved_idx == self.current_index => true,
-            Some(saved_idx) => {
-                // Check if all events between saved position and current position
-                // are readonly (don't modify buffer content)
-                let (start, end) = if saved_idx < self.current_index {
-                    (saved_idx, self.current_index)
-                } else {
-                    (self.current_index, saved_idx)
-                };
-
-                // All events in range [start, end) must be readonly
-                self.entries[start..end]
-                    .iter()
-                    .all(|entry| !entry.event.modifies_buffer())
-            }
+            Some(saved) => !self.modifies_between(saved, self.position()),
         }
     }
 
+    /// State at which the buffer was last saved, if any
+    pub fn saved_position(&self) -> Option<UndoPosition> {
+        self.saved_at
+    }
+
+    /// Check whether the buffer content changed since the log was at `position`
+    pub fn changed_since(&self, position: UndoPosition) -> bool {
+        self.modifies_between(position, self.position())
+    }
+
+    fn modifies_between(&self, from: UndoPosition, to: UndoPosition) -> bool {
+        let (up, down) = self.route(from, to);
+        up.iter()
+            .chain(&down)
+            .any(|&index| self.entries[index].event.modifies_buffer())
+    }
+
     /// Enable streaming events to a file
     pub fn enable_streaming<P: AsRef<std::path::Path>>(&mut self, path: P) -> std::io::Result<()> {
         use std::io::Write;
@@ -591,7 +645,6 @@ impl EventLog {
         self.stream_file = Some(file);
         Ok(())
     }
-
     /// Disable streaming
     pub fn disable_streaming(&mut self) {
         self.stream_file = None;
@@ -647,11 +700,10 @@ impl EventLog {
     }
 
     /// Append an event to the log
+    /// Appending after undo starts a new branch; the undone events stay in the tree
     pub fn append(&mut self, event: Event) -> usize {
-        // If we're not at the end, truncate future events
-        if self.current_index < self.entries.len() {
-            self.entries.truncate(self.current_index);
-        }
+        self.path.truncate(self.current_index);
+        let parent = self.position();
 
         // Stream event to file if enabled
         if let Some(ref mut file) = self.stream_file {
@@ -659,6 +711,7 @@ impl EventLog {
 
             let stream_entry = serde_json::json!({
                 "index": self.entries.len(),
+                "parent": parent,
                 "timestamp": chrono::Local::now().to_rfc3339(),
                 "event": event,
             });
@@ -672,25 +725,27 @@ impl EventLog {
             }
         }
 
-        let entry = LogEntry::new(event);
+        let mut entry = LogEntry::new(event);
+        entry.parent = parent;
+        let index = self.entries.len();
         self.entries.push(entry);
-        self.current_index = self.entries.len();
-
-        // Check if we should create a snapshot
-        if self.entries.len() % self.snapshot_interval == 0 {
-            // Snapshot creation will be implemented when we have Buffer
-            // For now, just track that we'd create one here
-        }
+        self.path.push(index);
+        self.current_index = self.path.len();
 
-        self.current_index - 1
+        index
     }
 
-    /// Get the current event index
+    /// Get the current event index (the number of applied events on the active branch)
     pub fn current_index(&self) -> usize {
         self.current_index
     }
 
-    /// Get the number of events in the log
+    /// Get the current state in the undo tree
+    pub fn position(&self) -> UndoPosition {
+        self.current_index.checked_sub(1).map(|i| self.path[i])
+    }
+
+    /// Get the number of events in the log (across all branches)
     pub fn len(&self) -> usize {
         self.entries.len()
     }
@@ -702,7 +757,7 @@ impl EventLog {
 
     /// Can we redo?
     pub fn can_redo(&self) -> bool {
-        self.current_index < self.entries.len()
+        self.current_index < self.path.len()
     }
 
     /// Move back through events (for undo)
@@ -715,7 +770,7 @@ impl EventLog {
         // Keep moving backward until we find a write action
         while self.can_undo() && !found_write_action {
             self.current_index -= 1;
-            let event = &self.entries[self.current_index].event;
+            let event = &self.entries[self.path[self.current_index]].event;
 
             // Check if this is a write action - we'll stop after processing it
             if event.is_write_action() {
@@ -741,7 +796,7 @@ impl EventLog {
 
         // Keep moving forward to collect write action and subsequent readonly events
         while self.can_redo() {
-            let event = self.entries[self.current_index].event.clone();
+            let event = self.entries[self.path[self.current_index]].event.clone();
 
             // If we've already found a write action and this is another write action, stop
             if found_write_action && event.is_write_action() {
@@ -762,28 +817,218 @@ impl EventLog {
         events
     }
 
-    /// Get all events from the log
+    /// Get all events from the log, in the order they were logged
     pub fn entries(&self) -> &[LogEntry] {
         &self.entries
     }
 
-    /// Get events in a range
-    pub fn range(&self, range: Range<usize>) -> &[LogEntry] {
-        &self.entries[range]
-    }
-
     /// Get the most recent event
     pub fn last_event(&self) -> Option<&Event> {
-        if self.current_index > 0 {
-            Some(&self.entries[self.current_index - 1].event)
-        } else {
-            None
+        self.position().map(|index| &self.entries[index].event)
+    }
+
+    /// Entry indices from `position` up to the root
+    pub fn ancestors(&self, position: UndoPosition) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(position, |&index| self.entries[index].parent)
+    }
+
+    /// Entries to undo (walking up from `from`) and to apply (walking down to `to`)
+    /// to get from one state to the other
+    fn route(&self, from: UndoPosition, to: UndoPosition) -> (Vec<usize>, Vec<usize>) {
+        let from_ancestors: HashSet<usize> = self.ancestors(from).collect();
+        let mut down = Vec::new();
+        let mut common = None;
+        for index in self.ancestors(to) {
+            if from_ancestors.contains(&index) {
+                common = Some(index);
+                break;
+            }
+            down.push(index);
+        }
+        down.reverse();
+        let up = self
+            .ancestors(from)
+            .take_while(|&index| Some(index) != common)
+            .collect();
+        (up, down)
+    }
+
+    /// Move to another state of the undo tree, returning what to apply to the buffer
+    ///
+    /// The active branch becomes the one through `target`, continuing with the
+    /// most recent edits made after it.
+    pub fn jump_to(&mut self, target: UndoPosition) -> UndoJump {
+        let jump = self.jump_events(target);
+        self.set_position(target);
+        jump
+    }
+
+    /// What to apply to the buffer to get from the current state to `target`
+    pub fn jump_events(&self, target: UndoPosition) -> UndoJump {
+        let (up, down) = self.route(self.position(), target);
+        let walk_cost = up.len() + down.len();
+
+        // Loading a snapshot costs about as much as replaying `snapshot_interval` events
+        let from_snapshot = self
+            .ancestors(target)
+            .enumerate()
+            .take_while(|&(steps, _)| steps + self.snapshot_interval < walk_cost)
+            .find_map(|(steps, index)| Some((steps, self.snapshots.get(&index)?)));
+
+        match from_snapshot {
+            Some((steps, snapshot)) => {
+                let mut replay: Vec<usize> = self.ancestors(target).take(steps).collect();
+                replay.reverse();
+                UndoJump {
+                    base: Some(snapshot.content.clone()),
+                    events: replay
+                        .iter()
+                        .map(|&index| self.entries[index].event.clone())
+                        .collect(),
+                }
+            }
+            None => UndoJump {
+                base: None,
+                events: up
+                    .iter()
+                    .filter_map(|&index| self.entries[index].event.inverse())
+                    .chain(down.iter().map(|&index| self.entries[index].event.clone()))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Make `position` the current state without producing events
+    fn set_position(&mut self, position: UndoPosition) {
+        // Staying on the active branch keeps its redo tail
+        if let Some(depth) = self.path.iter().position(|&index| Some(index) == position) {
+            self.current_index = depth + 1;
+            return;
+        }
+
+        self.path = self.ancestors(position).collect();
+        self.path.reverse();
+        self.current_index = self.path.len();
+
+        // Continue the branch with the most recent edit made at each point
+        let mut latest_child: HashMap<UndoPosition, usize> = HashMap::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            latest_child.insert(entry.parent, index);
+        }
+        let mut tip = position;
+        while let Some(&child) = latest_child.get(&tip) {
+            self.path.push(child);
+            tip = Some(child);
+        }
+    }
+
+    /// Move to `position` after loading a log and treat it as the saved point
+    pub fn restore_position(&mut self, position: UndoPosition) {
+        let position = position.filter(|&index| index < self.entries.len());
+        self.set_position(position);
+        self.saved_at = Some(position);
+    }
+
+    /// Nearest state at or above `position` that an undo step can stop at
+    /// (undo steps end at write actions)
+    pub fn state_of(&self, position: UndoPosition) -> UndoPosition {
+        self.ancestors(position)
+            .find(|&index| self.entries[index].event.is_write_action())
+    }
+
+    /// Time (milliseconds since epoch) the edit leading to a state was made
+    pub fn timestamp(&self, position: UndoPosition) -> Option<u64> {
+        position.map(|index| self.entries[index].timestamp)
+    }
+
+    /// The state the buffer was in at `time` (milliseconds since epoch): the one
+    /// left by the last edit made at or before then
+    pub fn position_at(&self, time: u64) -> UndoPosition {
+        let logged = self
+            .entries
+            .partition_point(|entry| entry.timestamp <= time);
+        self.state_of(logged.checked_sub(1))
+    }
+
+    /// The state `offset_ms` before (negative) or after (positive) the time the
+    /// current state was reached
+    pub fn position_by_time(&self, offset_ms: i64) -> UndoPosition {
+        let current = self.state_of(self.position());
+        let base = match self.timestamp(current) {
+            Some(time) => time,
+            // Before any edit, measure from just before the first one
+            None => match self.entries.first() {
+                Some(entry) => entry.timestamp.saturating_sub(1),
+                None => return None,
+            },
+        };
+        self.position_at(base.saturating_add_signed(offset_ms))
+    }
+
+    /// Check whether a snapshot of the buffer should be stored for the current state
+    pub fn snapshot_due(&self) -> bool {
+        if self.snapshot_interval == 0 {
+            return false;
+        }
+        let recent: Vec<usize> = self
+            .ancestors(self.position())
+            .take(self.snapshot_interval)
+            .collect();
+        recent.len() == self.snapshot_interval
+            && !recent
+                .iter()
+                .any(|index| self.snapshots.contains_key(index))
+    }
+
+    /// Check whether a buffer of `len` bytes is small enough to snapshot
+    pub fn snapshot_fits(&self, len: usize) -> bool {
+        len <= self.snapshot_budget
+    }
+
+    /// Total bytes of content held by snapshots
+    pub fn snapshot_bytes(&self) -> usize {
+        self.snapshots
+            .values()
+            .map(|snapshot| snapshot.content.len())
+            .sum()
+    }
+
+    /// Store the buffer content of the current state as a snapshot
+    ///
+    /// Snapshots farthest from the new one are dropped to stay within the budget.
+    pub fn store_snapshot(&mut self, content: String) {
+        let Some(log_index) = self.position() else {
+            return;
+        };
+        if !self.snapshot_fits(content.len()) {
+            return;
+        }
+        self.snapshots.insert(
+            log_index,
+            Snapshot {
+                log_index,
+                content: Arc::new(content),
+            },
+        );
+
+        let mut total = self.snapshot_bytes();
+        while total > self.snapshot_budget {
+            let farthest = self
+                .snapshots
+                .keys()
+                .copied()
+                .max_by_key(|&index| index.abs_diff(log_index));
+            match farthest.and_then(|index| self.snapshots.remove(&index)) {
+                Some(snapshot) => total -= snapshot.content.len(),
+                None => break,
+            }
         }
     }
 
     /// Clear all events (for testing or reset)
     pub fn clear(&mut self) {
         self.entries.clear();
+        self.path.clear();
         self.current_index = 0;
         self.snapshots.clear();
     }
@@ -803,6 +1048,7 @@ impl EventLog {
     }
 
     /// Load event log from JSON Lines format
+    /// The current state is the last logged event
     pub fn load_from_file(path: &std::path::Path) -> std::io::Result<Self> {
         use std::io::BufRead;
         let file = std::fs::File::open(path)?;
@@ -816,10 +1062,19 @@ impl EventLog {
                 continue;
             }
             let entry: LogEntry = serde_json::from_str(&line)?;
+            if entry
+                .parent
+                .is_some_and(|parent| parent >= log.entries.len())
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "event log entry refers to a later parent",
+                ));
+            }
             log.entries.push(entry);
         }
 
-        log.current_index = log.entries.len();
+        log.set_position(log.entries.len().checked_sub(1));
 
         Ok(log)
     }
@@ -828,6 +1083,11 @@ impl EventLog {
     pub fn set_snapshot_interval(&mut self, interval: usize) {
         self.snapshot_interval = interval;
     }
+
+    /// Set the most bytes of content the snapshots may hold together
+    pub fn set_snapshot_budget(&mut self, budget: usize) {
+        self.snapshot_budget = budget;
+    }
 }
 
 impl Default for EventLog {
@@ -922,9 +1182,9 @@ mod tests {
                 assert_eq!(redo_count, events.len());
             }
 
-            /// Appending after undo should truncate redo history
+            /// Appending after undo starts a new branch with nothing to redo
             #[test]
-            fn append_after_undo_truncates(
+            fn append_after_undo_branches(
                 initial_events in prop::collection::vec(arb_event(), 2..10),
                 new_event in arb_event()
             ) {
@@ -1019,33 +1279,165 @@ mod tests {
         }
     }
 
+    fn insert(position: usize, text: &str) -> Event {
+        Event::Insert {
+            position,
+            text: text.to_string(),
+            cursor_id: CursorId(0),
+        }
+    }
+
     #[test]
-    fn test_truncate_on_new_event_after_undo() {
+    fn test_new_event_after_undo_keeps_branch() {
         let mut log = EventLog::new();
 
-        log.append(Event::Insert {
-            position: 0,
-            text: "a".to_string(),
-            cursor_id: CursorId(0),
-        });
-
-        log.append(Event::Insert {
-            position: 1,
-            text: "b".to_string(),
-            cursor_id: CursorId(0),
-        });
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
 
         log.undo();
         assert_eq!(log.entries().len(), 2);
 
-        // Adding new event should truncate the future
-        log.append(Event::Insert {
-            position: 1,
-            text: "c".to_string(),
-            cursor_id: CursorId(0),
-        });
+        // Adding new event starts a branch; "b" stays in the tree
+        log.append(insert(1, "c"));
 
-        assert_eq!(log.entries().len(), 2);
+        assert_eq!(log.entries().len(), 3);
         assert_eq!(log.current_index(), 2);
+        assert_eq!(log.position(), Some(2));
+        assert!(!log.can_redo());
+        assert_eq!(log.entries()[1].parent, Some(0));
+        assert_eq!(log.entries()[2].parent, Some(0));
+    }
+
+    #[test]
+    fn test_jump_between_branches() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        log.append(insert(1, "c"));
+
+        // From "ac" to "ab": remove "c", insert "b"
+        let jump = log.jump_to(Some(1));
+        assert!(jump.base.is_none());
+        assert_eq!(jump.events.len(), 2);
+        assert!(matches!(
+            &jump.events[0],
+            Event::Delete { deleted_text, .. } if deleted_text == "c"
+        ));
+        assert!(matches!(&jump.events[1], Event::Insert { text, .. } if text == "b"));
+        assert_eq!(log.position(), Some(1));
+
+        // Undo and redo now follow the "b" branch
+        log.undo();
+        assert_eq!(log.position(), Some(0));
+        log.redo();
+        assert_eq!(log.position(), Some(1));
+    }
+
+    #[test]
+    fn test_saved_position_on_other_branch() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.mark_saved();
+        log.undo();
+        log.append(insert(0, "b"));
+        assert!(!log.is_at_saved_position());
+
+        log.jump_to(Some(0));
+        assert!(log.is_at_saved_position());
+        assert!(log.changed_since(Some(1)));
+        assert!(!log.changed_since(Some(0)));
+    }
+
+    #[test]
+    fn test_position_at_time() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.append(insert(2, "c"));
+        for (entry, timestamp) in log.entries.iter_mut().zip([1_000, 2_000, 3_000]) {
+            entry.timestamp = timestamp;
+        }
+
+        assert_eq!(log.position_at(500), None);
+        assert_eq!(log.position_at(2_500), Some(1));
+        assert_eq!(log.position_by_time(-1_000), Some(1));
+
+        log.jump_to(Some(0));
+        assert_eq!(log.position_by_time(2_000), Some(2));
+    }
+
+    #[test]
+    fn test_parse_time_offset() {
+        assert_eq!(parse_time_offset("30s"), Some(30_000));
+        assert_eq!(parse_time_offset(" 5m "), Some(300_000));
+        assert_eq!(parse_time_offset("2 hours"), Some(7_200_000));
+        assert_eq!(parse_time_offset("5"), None);
+        assert_eq!(parse_time_offset("m"), None);
+    }
+
+    #[test]
+    fn test_jump_uses_snapshot() {
+        let mut log = EventLog::new();
+        log.set_snapshot_interval(2);
+        let mut text = String::new();
+        for i in 0..10 {
+            log.append(insert(i, "x"));
+            text.push('x');
+            if log.snapshot_due() {
+                log.store_snapshot(text.clone());
+            }
+        }
+
+        // Snapshots were stored after every second event (entries 1, 3, 5, ...)
+        log.jump_to(None);
+        let jump = log.jump_to(Some(8));
+        assert_eq!(jump.base.as_deref().map(String::as_str), Some("xxxxxxxx"));
+        assert_eq!(jump.events.len(), 1);
+
+        // Nearby states are reached by walking the tree
+        let jump = log.jump_to(Some(6));
+        assert!(jump.base.is_none());
+        assert_eq!(jump.events.len(), 2);
+    }
+
+    #[test]
+    fn test_snapshots_stay_within_budget() {
+        let mut log = EventLog::new();
+        log.set_snapshot_interval(2);
+        log.set_snapshot_budget(20);
+        let mut text = String::new();
+        for i in 0..20 {
+            log.append(insert(i, "x"));
+            text.push('x');
+            if log.snapshot_due() {
+                log.store_snapshot(text.clone());
+            }
+            assert!(log.snapshot_bytes() <= 20);
+        }
+
+        // The most recent snapshots are kept; too large ones aren't stored
+        assert!(log.snapshots.contains_key(&19));
+        assert!(!log.snapshots.contains_key(&1));
+        log.append(insert(20, &"x".repeat(30)));
+        log.store_snapshot("x".repeat(51));
+        assert!(!log.snapshots.contains_key(&20));
+    }
+
+    #[test]
+    fn test_save_and_load_tree() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        log.append(insert(1, "c"));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("log.jsonl");
+        log.save_to_file(&path).unwrap();
+
+        let mut loaded = EventLog::load_from_file(&path).unwrap();
+        assert_eq!(loaded.position(), Some(2));
+        assert_eq!(loaded.jump_to(Some(1)).events.len(), 2);
     }
 }
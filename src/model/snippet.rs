@@ -0,0 +1,194 @@
+//! Tabstops of an inserted snippet
+//!
+//! Once a snippet is inserted its tabstops stay live until the final one is reached.
+//! Every occurrence of a tabstop is anchored by a start and an end marker, so edits
+//! elsewhere move them with the text, while text typed into the current tabstop
+//! grows its ranges.
+
+use crate::model::marker::{MarkerId, MarkerList};
+use crate::primitives::snippet::Snippet;
+use std::ops::Range;
+
+/// The live tabstops of a snippet and which one the cursor is at
+#[derive(Debug)]
+pub struct SnippetSession {
+    markers: MarkerList,
+    /// Tabstops in visiting order; the last one is `$0`
+    tabstops: Vec<LiveTabstop>,
+    current: usize,
+}
+
+#[derive(Debug)]
+struct LiveTabstop {
+    /// (start, end) markers of each occurrence
+    ranges: Vec<(MarkerId, MarkerId)>,
+    choices: Vec<String>,
+}
+
+impl SnippetSession {
+    /// Track the tabstops of `snippet`, inserted at `position`; starts at the first one
+    pub fn new(position: usize, snippet: &Snippet) -> Self {
+        let mut markers = MarkerList::new();
+        let tabstops = snippet
+            .tabstops
+            .iter()
+            .map(|tabstop| LiveTabstop {
+                ranges: tabstop
+                    .ranges
+                    .iter()
+                    .map(|range| {
+                        (
+                            markers.create(position + range.start, true),
+                            markers.create(position + range.end, false),
+                        )
+                    })
+                    .collect(),
+                choices: tabstop.choices.clone(),
+            })
+            .collect();
+        Self {
+            markers,
+            tabstops,
+            current: 0,
+        }
+    }
+
+    /// Byte ranges of the current tabstop's occurrences
+    pub fn current_ranges(&self) -> Vec<Range<usize>> {
+        self.ranges(self.current)
+    }
+
+    /// Choices offered at the current tabstop
+    pub fn current_choices(&self) -> &[String] {
+        &self.tabstops[self.current].choices
+    }
+
+    /// Whether the cursor reached the final tabstop (`$0`), which ends the snippet
+    pub fn is_at_end(&self) -> bool {
+        self.current + 1 == self.tabstops.len()
+    }
+
+    /// Move to the next tabstop
+    pub fn next(&mut self) {
+        self.current = (self.current + 1).min(self.tabstops.len() - 1);
+    }
+
+    /// Move to the previous tabstop; returns false at the first one
+    pub fn prev(&mut self) -> bool {
+        if self.current == 0 {
+            return false;
+        }
+        self.current -= 1;
+        true
+    }
+
+    /// Whether a position lies within the snippet's text
+    pub fn contains(&self, position: usize) -> bool {
+        let ranges: Vec<Range<usize>> = (0..self.tabstops.len())
+            .flat_map(|index| self.ranges(index))
+            .collect();
+        let start = ranges.iter().map(|range| range.start).min();
+        let end = ranges.iter().map(|range| range.end).max();
+        start
+            .zip(end)
+            .is_some_and(|(start, end)| (start..=end).contains(&position))
+    }
+
+    fn ranges(&self, index: usize) -> Vec<Range<usize>> {
+        self.tabstops[index]
+            .ranges
+            .iter()
+            .filter_map(|&(start, end)| {
+                let start = self.markers.get_position(start)?;
+                let end = self.markers.get_position(end)?;
+                Some(start..end.max(start))
+            })
+            .collect()
+    }
+
+    /// Adjust the tabstops after an insertion
+    ///
+    /// Text inserted at the start or end of the current tabstop becomes part of it;
+    /// text inserted right after another tabstop doesn't.
+    pub fn adjust_for_insert(&mut self, position: usize, length: usize) {
+        // Markers only move past an insertion at their position, so remember the ones
+        // that must stay and put them back afterwards
+        let mut pinned = Vec::new();
+        for (index, tabstop) in self.tabstops.iter().enumerate() {
+            for (occurrence, &(start, end)) in tabstop.ranges.iter().enumerate() {
+                let (Some(start_pos), Some(end_pos)) = (
+                    self.markers.get_position(start),
+                    self.markers.get_position(end),
+                ) else {
+                    continue;
+                };
+                if index == self.current && start_pos == position {
+                    pinned.push((index, occurrence, true));
+                } else if index != self.current && start_pos < position && end_pos == position {
+                    pinned.push((index, occurrence, false));
+                }
+            }
+        }
+
+        self.markers.adjust_for_insert(position, length);
+
+        for (index, occurrence, is_start) in pinned {
+            let (start, end) = &mut self.tabstops[index].ranges[occurrence];
+            let marker = if is_start { start } else { end };
+            self.markers.delete(*marker);
+            *marker = self.markers.create(position, is_start);
+        }
+    }
+
+    /// Adjust the tabstops after a deletion
+    pub fn adjust_for_delete(&mut self, position: usize, length: usize) {
+        self.markers.adjust_for_delete(position, length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::snippet::parse_snippet;
+
+    #[test]
+    fn test_typing_grows_current_tabstop_only() {
+        // "(a, b)" inserted at 10: $1 = "a" at 11..12, $2 = "b" at 14..15
+        let snippet = parse_snippet("(${1:a}, ${2:b})", |_| None);
+        let mut session = SnippetSession::new(10, &snippet);
+        assert_eq!(session.current_ranges(), vec![11..12]);
+
+        // Replace "a" with "xy": delete it, then type at its start
+        session.adjust_for_delete(11, 1);
+        session.adjust_for_insert(11, 2);
+        assert_eq!(session.current_ranges(), vec![11..13]);
+
+        // Edits before the snippet shift it
+        session.adjust_for_insert(0, 5);
+        assert_eq!(session.current_ranges(), vec![16..18]);
+
+        session.next();
+        assert_eq!(session.current_ranges(), vec![20..21]);
+        // Typing at the end of $2 grows it
+        session.adjust_for_insert(21, 1);
+        assert_eq!(session.current_ranges(), vec![20..22]);
+        assert!(!session.is_at_end());
+
+        session.next();
+        assert!(session.is_at_end());
+        assert_eq!(session.current_ranges(), vec![23..23]);
+        assert!(session.contains(16));
+        assert!(!session.contains(24));
+    }
+
+    #[test]
+    fn test_typing_after_previous_tabstop_stays_out_of_it() {
+        let snippet = parse_snippet("${1:a}${2}", |_| None);
+        let mut session = SnippetSession::new(0, &snippet);
+        session.next();
+        session.adjust_for_insert(1, 3);
+        assert_eq!(session.current_ranges(), vec![1..4]);
+        assert!(session.prev());
+        assert_eq!(session.current_ranges(), vec![0..1]);
+    }
+}
@@ -0,0 +1,385 @@
+//! Foldable regions of a document
+//!
+//! Ranges come from the language server (`textDocument/foldingRange`) when one is
+//! running. Otherwise they are computed with tree-sitter using the language's
+//! `folds.scm` query under `queries/<lang>`, and from indentation for everything else.
+//!
+//! # Query Captures
+//! - `@fold`: A node whose lines can be folded
+//!
+//! A folded range keeps its first line visible and hides the rest. When a node's
+//! closing delimiter (`}`, `]`, `end`, `</div>`) sits on a line of its own, that
+//! line stays visible too.
+
+use crate::primitives::highlighter::Language;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use tree_sitter::{Node, Parser, Query, QueryCursor, StreamingIterator};
+
+/// A foldable range of lines (0-indexed, inclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FoldRange {
+    /// Header line that stays visible when folded
+    pub start_line: usize,
+    /// Last line hidden when folded
+    pub end_line: usize,
+}
+
+impl FoldRange {
+    pub fn new(start_line: usize, end_line: usize) -> Self {
+        Self {
+            start_line,
+            end_line,
+        }
+    }
+
+    /// Whether a line is the header or one of the hidden lines
+    pub fn contains_line(&self, line: usize) -> bool {
+        self.start_line <= line && line <= self.end_line
+    }
+}
+
+/// Fold range extractor using tree-sitter fold queries
+pub struct FoldRangeExtractor {
+    /// Map of language to (parser, query)
+    configs: HashMap<&'static str, (Parser, Query)>,
+}
+
+impl FoldRangeExtractor {
+    /// Create a new fold range extractor
+    pub fn new() -> Self {
+        Self {
+            configs: HashMap::new(),
+        }
+    }
+
+    /// Whether a folds query exists for this language
+    pub fn supports(language: &Language) -> bool {
+        Self::query_source(language).is_some()
+    }
+
+    /// Folds query for a language, if it has one
+    fn query_source(
+        language: &Language,
+    ) -> Option<(&'static str, tree_sitter::Language, &'static str)> {
+        let config = match language {
+            Language::Rust => (
+                "rust",
+                tree_sitter_rust::LANGUAGE.into(),
+                include_str!("../../queries/rust/folds.scm"),
+            ),
+            Language::Python => (
+                "python",
+                tree_sitter_python::LANGUAGE.into(),
+                include_str!("../../queries/python/folds.scm"),
+            ),
+            Language::JavaScript => (
+                "javascript",
+                tree_sitter_javascript::LANGUAGE.into(),
+                include_str!("../../queries/javascript/folds.scm"),
+            ),
+            Language::TypeScript => (
+                "typescript",
+                tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+                include_str!("../../queries/typescript/folds.scm"),
+            ),
+            Language::C => (
+                "c",
+                tree_sitter_c::LANGUAGE.into(),
+                include_str!("../../queries/c/folds.scm"),
+            ),
+            Language::Cpp => (
+                "cpp",
+                tree_sitter_cpp::LANGUAGE.into(),
+                include_str!("../../queries/cpp/folds.scm"),
+            ),
+            Language::Go => (
+                "go",
+                tree_sitter_go::LANGUAGE.into(),
+                include_str!("../../queries/go/folds.scm"),
+            ),
+            Language::Java => (
+                "java",
+                tree_sitter_java::LANGUAGE.into(),
+                include_str!("../../queries/java/folds.scm"),
+            ),
+            Language::CSharp => (
+                "csharp",
+                tree_sitter_c_sharp::LANGUAGE.into(),
+                include_str!("../../queries/csharp/folds.scm"),
+            ),
+            Language::Ruby => (
+                "ruby",
+                tree_sitter_ruby::LANGUAGE.into(),
+                include_str!("../../queries/ruby/folds.scm"),
+            ),
+            Language::Php => (
+                "php",
+                tree_sitter_php::LANGUAGE_PHP.into(),
+                include_str!("../../queries/php/folds.scm"),
+            ),
+            Language::Bash => (
+                "bash",
+                tree_sitter_bash::LANGUAGE.into(),
+                include_str!("../../queries/bash/folds.scm"),
+            ),
+            Language::Json => (
+                "json",
+                tree_sitter_json::LANGUAGE.into(),
+                include_str!("../../queries/json/folds.scm"),
+            ),
+            Language::HTML => (
+                "html",
+                tree_sitter_html::LANGUAGE.into(),
+                include_str!("../../queries/html/folds.scm"),
+            ),
+            Language::CSS => (
+                "css",
+                tree_sitter_css::LANGUAGE.into(),
+                include_str!("../../queries/css/folds.scm"),
+            ),
+            // Lua falls back to indentation
+            Language::Lua => return None,
+        };
+        Some(config)
+    }
+
+    /// Get or create parser and query for a language
+    fn get_config(&mut self, language: &Language) -> Option<(&mut Parser, &Query)> {
+        let (lang_name, ts_language, query_str) = Self::query_source(language)?;
+
+        if !self.configs.contains_key(lang_name) {
+            let mut parser = Parser::new();
+            if parser.set_language(&ts_language).is_err() {
+                tracing::error!("Failed to set language for {}", lang_name);
+                return None;
+            }
+
+            let query = match Query::new(&ts_language, query_str) {
+                Ok(q) => q,
+                Err(e) => {
+                    tracing::error!("Failed to create folds query for {}: {:?}", lang_name, e);
+                    return None;
+                }
+            };
+
+            self.configs.insert(lang_name, (parser, query));
+        }
+
+        let (parser, query) = self.configs.get_mut(lang_name)?;
+        Some((parser, query))
+    }
+
+    /// Extract fold ranges from source text
+    ///
+    /// Returns `None` if the language has no folds query or parsing fails.
+    pub fn ranges(&mut self, source: &str, language: &Language) -> Option<Vec<FoldRange>> {
+        let (parser, query) = self.get_config(language)?;
+        let tree = parser.parse(source, None)?;
+        let lines: Vec<&str> = source.split('\n').collect();
+
+        let mut ranges = Vec::new();
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+        while let Some(m) = matches.next() {
+            for capture in m.captures {
+                if let Some(range) = node_fold_range(capture.node, &lines) {
+                    ranges.push(range);
+                }
+            }
+        }
+        Some(normalize_fold_ranges(ranges))
+    }
+}
+
+impl Default for FoldRangeExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fold range for a node, keeping a closing line of its own visible
+fn node_fold_range(node: Node, lines: &[&str]) -> Option<FoldRange> {
+    let start_line = node.start_position().row;
+    let end = node.end_position();
+    // A node that swallows its trailing newline ends on the previous line
+    let mut end_line = if end.column == 0 && end.row > start_line {
+        end.row - 1
+    } else {
+        end.row
+    };
+
+    let closing_line = lines.get(end_line).copied().unwrap_or("");
+    let closing = closing_line.trim_start();
+    let indent = closing_line.len() - closing.len();
+    let closes_with_delimiter = ["}", "]", ")", "</", "*/", "-->"]
+        .iter()
+        .any(|delimiter| closing.starts_with(delimiter));
+    let closes_with_keyword = node
+        .child(node.child_count().saturating_sub(1))
+        .is_some_and(|last| {
+            !last.is_named()
+                && last.start_position().row == end_line
+                && last.start_position().column == indent
+        });
+    if closes_with_delimiter || closes_with_keyword {
+        end_line = end_line.saturating_sub(1);
+    }
+
+    (end_line > start_line).then(|| FoldRange::new(start_line, end_line))
+}
+
+/// Compute fold ranges from indentation
+///
+/// A line folds everything below it that is indented further, up to the last
+/// such non-blank line. `tab_size` is the width a tab counts for.
+pub fn indent_fold_ranges(source: &str, tab_size: usize) -> Vec<FoldRange> {
+    let mut ranges = Vec::new();
+    // Lines that may still start a fold: (line, indent)
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut last_non_blank = 0;
+
+    for (line, text) in source.lines().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let indent = indent_width(text, tab_size);
+        while let Some(&(start, start_indent)) = open.last() {
+            if start_indent < indent {
+                break;
+            }
+            open.pop();
+            if last_non_blank > start {
+                ranges.push(FoldRange::new(start, last_non_blank));
+            }
+        }
+        open.push((line, indent));
+        last_non_blank = line;
+    }
+    for (start, _) in open {
+        if last_non_blank > start {
+            ranges.push(FoldRange::new(start, last_non_blank));
+        }
+    }
+
+    normalize_fold_ranges(ranges)
+}
+
+fn indent_width(text: &str, tab_size: usize) -> usize {
+    text.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { tab_size.max(1) } else { 1 })
+        .sum()
+}
+
+/// Convert a `textDocument/foldingRange` response
+pub fn from_lsp_folding_ranges(ranges: Vec<lsp_types::FoldingRange>) -> Vec<FoldRange> {
+    normalize_fold_ranges(
+        ranges
+            .into_iter()
+            .filter(|range| range.end_line > range.start_line)
+            .map(|range| FoldRange::new(range.start_line as usize, range.end_line as usize))
+            .collect(),
+    )
+}
+
+/// Sort ranges by start line, keeping the largest range for each start line
+pub fn normalize_fold_ranges(ranges: Vec<FoldRange>) -> Vec<FoldRange> {
+    let mut by_start: BTreeMap<usize, usize> = BTreeMap::new();
+    for range in ranges {
+        if range.end_line <= range.start_line {
+            continue;
+        }
+        let end = by_start.entry(range.start_line).or_insert(range.end_line);
+        *end = (*end).max(range.end_line);
+    }
+    by_start
+        .into_iter()
+        .map(|(start, end)| FoldRange::new(start, end))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(ranges: &[FoldRange]) -> Vec<(usize, usize)> {
+        ranges.iter().map(|r| (r.start_line, r.end_line)).collect()
+    }
+
+    #[test]
+    fn test_all_folds_queries_compile() {
+        let languages = [
+            Language::Rust,
+            Language::Python,
+            Language::JavaScript,
+            Language::TypeScript,
+            Language::C,
+            Language::Cpp,
+            Language::Go,
+            Language::Java,
+            Language::CSharp,
+            Language::Ruby,
+            Language::Php,
+            Language::Bash,
+            Language::Json,
+            Language::HTML,
+            Language::CSS,
+        ];
+        let mut extractor = FoldRangeExtractor::new();
+        for language in &languages {
+            assert!(
+                extractor.get_config(language).is_some(),
+                "folds query for {:?} should compile",
+                language
+            );
+        }
+    }
+
+    #[test]
+    fn test_rust_closing_brace_stays_visible() {
+        let source = "impl Point {\n    fn new() -> Self {\n        Point { x: 0 }\n    }\n\n    fn x(&self) -> i32 { self.x }\n}\n";
+        let mut extractor = FoldRangeExtractor::new();
+        let ranges = extractor.ranges(source, &Language::Rust).unwrap();
+
+        assert_eq!(pairs(&ranges), vec![(0, 5), (1, 2)]);
+    }
+
+    #[test]
+    fn test_json_nested_objects() {
+        let source = "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": { \"c\": 1 }\n}";
+        let mut extractor = FoldRangeExtractor::new();
+        let ranges = extractor.ranges(source, &Language::Json).unwrap();
+
+        assert_eq!(pairs(&ranges), vec![(0, 5), (1, 3)]);
+    }
+
+    #[test]
+    fn test_python_block_without_closing_line() {
+        let source = "def f():\n    x = 1\n    return x\n\nprint(f())\n";
+        let mut extractor = FoldRangeExtractor::new();
+        let ranges = extractor.ranges(source, &Language::Python).unwrap();
+
+        assert_eq!(pairs(&ranges), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn test_indent_fold_ranges() {
+        let source = "a:\n  b:\n    c\n\n  d\ne\n  f\n";
+        assert_eq!(
+            pairs(&indent_fold_ranges(source, 4)),
+            vec![(0, 4), (1, 2), (5, 6)]
+        );
+    }
+
+    #[test]
+    fn test_lsp_ranges_drop_single_lines_and_merge_starts() {
+        let range = |start_line, end_line| lsp_types::FoldingRange {
+            start_line,
+            end_line,
+            ..Default::default()
+        };
+        let ranges = from_lsp_folding_ranges(vec![range(4, 9), range(2, 2), range(4, 6)]);
+        assert_eq!(pairs(&ranges), vec![(4, 9)]);
+    }
+}
@@ -0,0 +1,391 @@
+//! Line commands: moving, duplicating, joining, sorting and filtering lines
+//!
+//! The functions here work on the buffer text: they find its lines and compute
+//! the new text of a block of lines. `input/actions.rs` turns that into edits for
+//! the lines of every cursor.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use super::rectangle::Edit;
+
+/// Comment prefixes removed from lines joined onto a comment line
+const COMMENT_PREFIXES: &[&str] = &["///", "//!", "//", "#", "--", ";"];
+
+/// The lines of a text
+///
+/// The empty line after a final line break isn't counted, so that moving or
+/// sorting lines keeps the text ending with a line break.
+pub struct Lines<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+    /// `\r\n` if the text uses it, `\n` otherwise
+    newline: &'static str,
+}
+
+impl<'a> Lines<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        if starts.len() > 1 && starts.last() == Some(&text.len()) {
+            starts.pop();
+        }
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        Self {
+            text,
+            starts,
+            newline,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn newline(&self) -> &'static str {
+        self.newline
+    }
+
+    /// Line containing `pos`
+    pub fn line_of(&self, pos: usize) -> usize {
+        self.starts
+            .partition_point(|&start| start <= pos)
+            .saturating_sub(1)
+    }
+
+    pub fn start(&self, line: usize) -> usize {
+        self.starts[line]
+    }
+
+    /// End of a line, before its line break
+    pub fn end(&self, line: usize) -> usize {
+        let end = match self.starts.get(line + 1) {
+            Some(&next) => next - 1,
+            None => self
+                .text
+                .strip_suffix('\n')
+                .map_or(self.text.len(), str::len),
+        };
+        if end > self.start(line) && self.text.as_bytes()[end - 1] == b'\r' {
+            end - 1
+        } else {
+            end
+        }
+    }
+
+    /// End of a line after its line break
+    pub fn end_with_newline(&self, line: usize) -> usize {
+        self.starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.text.len())
+    }
+
+    /// Text of a line without its line break
+    pub fn text(&self, line: usize) -> &'a str {
+        &self.text[self.start(line)..self.end(line)]
+    }
+
+    /// Texts of lines `first..=last`
+    pub fn texts(&self, first: usize, last: usize) -> Vec<&'a str> {
+        (first..=last).map(|line| self.text(line)).collect()
+    }
+
+    /// Lines `first..=last` touched by a range, leaving out the last line if the
+    /// range only reaches its start
+    pub fn span(&self, range: Range<usize>) -> (usize, usize) {
+        let first = self.line_of(range.start);
+        let last = self.line_of(range.end);
+        if last > first && self.start(last) == range.end {
+            (first, last - 1)
+        } else {
+            (first, last)
+        }
+    }
+
+    /// Edit replacing lines `first..=last` with `lines`, or deleting them along
+    /// with a line break if `lines` is empty
+    pub fn replace(&self, first: usize, last: usize, lines: &[String]) -> Edit {
+        let range = if !lines.is_empty() {
+            self.start(first)..self.end(last)
+        } else if last + 1 < self.count() {
+            self.start(first)..self.start(last + 1)
+        } else if first > 0 {
+            self.end(first - 1)..self.end(last)
+        } else {
+            self.start(first)..self.end(last)
+        };
+        (range, lines.join(self.newline))
+    }
+}
+
+/// Sort blocks of lines `(first, last)` and merge those that overlap, or are
+/// next to each other with `adjacent`
+pub fn merge_blocks(mut blocks: Vec<(usize, usize)>, adjacent: bool) -> Vec<(usize, usize)> {
+    blocks.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(blocks.len());
+    for (first, last) in blocks {
+        match merged.last_mut() {
+            Some(prev) if first <= prev.1 + usize::from(adjacent) => prev.1 = prev.1.max(last),
+            _ => merged.push((first, last)),
+        }
+    }
+    merged
+}
+
+/// Where `pos` ends up after `edits`, which are in buffer order and don't overlap
+///
+/// Positions inside a replaced range keep their offset into it, cut short at the
+/// end of the new text. Positions at an insertion point end up after it.
+pub fn map_position(edits: &[Edit], pos: usize) -> usize {
+    let mut shift = 0isize;
+    for (range, text) in edits {
+        if pos < range.start {
+            break;
+        }
+        if !range.is_empty() && pos <= range.end {
+            let start = (range.start as isize + shift) as usize;
+            return start + (pos - range.start).min(text.len());
+        }
+        shift += text.len() as isize - range.len() as isize;
+    }
+    (pos as isize + shift) as usize
+}
+
+/// A command rewriting a block of lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineTransform {
+    Sort,
+    /// Sort with runs of digits compared by value (`a2` before `a10`)
+    SortNatural,
+    /// Sort by the number each line starts with
+    SortNumeric,
+    SortCaseInsensitive,
+    SortDescending,
+    /// Remove lines that repeat an earlier line
+    Unique,
+    Reverse,
+    Shuffle,
+    DeleteBlank,
+}
+
+impl LineTransform {
+    pub fn apply(self, lines: &[&str]) -> Vec<String> {
+        let mut lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        match self {
+            LineTransform::Sort => lines.sort(),
+            LineTransform::SortNatural => lines.sort_by(|a, b| natural_cmp(a, b)),
+            LineTransform::SortNumeric => {
+                lines.sort_by(|a, b| match (leading_number(a), leading_number(b)) {
+                    (Some(x), Some(y)) => x.total_cmp(&y),
+                    (x, y) => x.is_some().cmp(&y.is_some()),
+                })
+            }
+            LineTransform::SortCaseInsensitive => lines.sort_by_cached_key(|l| l.to_lowercase()),
+            LineTransform::SortDescending => lines.sort_by(|a, b| b.cmp(a)),
+            LineTransform::Unique => {
+                let mut seen = HashSet::new();
+                lines.retain(|line| seen.insert(line.clone()));
+            }
+            LineTransform::Reverse => lines.reverse(),
+            LineTransform::Shuffle => shuffle(&mut lines),
+            LineTransform::DeleteBlank => lines.retain(|line| !line.trim().is_empty()),
+        }
+        lines
+    }
+}
+
+/// Compare with runs of digits compared by their value
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let a_end = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+            let b_end = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+            let a_digits = a[..a_end].trim_start_matches('0');
+            let b_digits = b[..b_end].trim_start_matches('0');
+            let order = a_digits
+                .len()
+                .cmp(&b_digits.len())
+                .then_with(|| a_digits.cmp(b_digits));
+            if order != Ordering::Equal {
+                return order;
+            }
+            a = &a[a_end..];
+            b = &b[b_end..];
+        } else if x != y {
+            return x.cmp(&y);
+        } else {
+            a = &a[x.len_utf8()..];
+            b = &b[y.len_utf8()..];
+        }
+    }
+}
+
+/// The number a line starts with, after any indentation
+fn leading_number(line: &str) -> Option<f64> {
+    let line = line.trim_start();
+    let end = line
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && matches!(c, '-' | '+'))))
+        .map_or(line.len(), |(i, _)| i);
+    line[..end].parse().ok()
+}
+
+/// Fisher-Yates shuffle seeded from the hasher's random keys
+fn shuffle(lines: &mut [String]) {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut state = RandomState::new().build_hasher().finish() | 1;
+    for i in (1..lines.len()).rev() {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        lines.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+/// The comment marker continuation lines of a comment line start with
+fn comment_prefix(line: &str) -> Option<&'static str> {
+    let line = line.trim_start();
+    if line.starts_with("/*") || (line.starts_with('*') && !line.starts_with("*/")) {
+        return Some("*");
+    }
+    COMMENT_PREFIXES
+        .iter()
+        .find(|prefix| line.starts_with(*prefix))
+        .copied()
+}
+
+/// Join lines into one, returning it and the offset of the last join
+///
+/// The indentation of the joined lines is dropped, and so is their comment marker
+/// when the first line is a comment. They are separated by a space, except around
+/// empty lines, before a closing bracket and after an opening one.
+pub fn join(lines: &[&str]) -> (String, usize) {
+    let Some((first, rest)) = lines.split_first() else {
+        return (String::new(), 0);
+    };
+    let prefix = comment_prefix(first);
+    let mut joined = first.to_string();
+    let mut join_point = joined.len();
+    for line in rest {
+        let mut next = line.trim_start();
+        if let Some(prefix) = prefix {
+            if !next.starts_with("*/") {
+                if let Some(stripped) = next.strip_prefix(prefix) {
+                    next = stripped.trim_start();
+                }
+            }
+        }
+        joined.truncate(joined.trim_end().len());
+        join_point = joined.len();
+        let space = !joined.is_empty()
+            && !next.is_empty()
+            && !next.starts_with([')', ']'])
+            && !joined.ends_with(['(', '[']);
+        if space {
+            joined.push(' ');
+        }
+        joined.push_str(next);
+    }
+    (joined, join_point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_and_replace() {
+        let lines = Lines::new("one\r\ntwo\r\nthree\r\n");
+        assert_eq!(lines.count(), 3);
+        assert_eq!(lines.newline(), "\r\n");
+        assert_eq!(lines.texts(0, 2), vec!["one", "two", "three"]);
+        assert_eq!(lines.line_of(5), 1);
+        assert_eq!(lines.span(0..5), (0, 0));
+        assert_eq!(lines.span(0..6), (0, 1));
+
+        let lines = Lines::new("a\nb\nc");
+        assert_eq!(lines.replace(1, 1, &[]), (2..4, String::new()));
+        assert_eq!(lines.replace(2, 2, &[]), (3..5, String::new()));
+        assert_eq!(
+            lines.replace(0, 1, &["b".to_string(), "a".to_string()]),
+            (0..3, "b\na".to_string())
+        );
+    }
+
+    #[test]
+    fn test_map_position() {
+        let edits = vec![(2..4, "xyz1".to_string()), (6..6, "!".to_string())];
+        assert_eq!(map_position(&edits, 1), 1);
+        assert_eq!(map_position(&edits, 3), 3);
+        assert_eq!(map_position(&edits, 5), 7);
+        assert_eq!(map_position(&edits, 6), 9);
+        assert_eq!(
+            merge_blocks(vec![(4, 5), (0, 1), (2, 2)], false),
+            vec![(0, 1), (2, 2), (4, 5)]
+        );
+        assert_eq!(
+            merge_blocks(vec![(4, 5), (0, 1), (2, 2)], true),
+            vec![(0, 2), (4, 5)]
+        );
+    }
+
+    #[test]
+    fn test_sorts() {
+        let lines = ["b10", "B2", "a", "b2"];
+        assert_eq!(LineTransform::Sort.apply(&lines), ["B2", "a", "b10", "b2"]);
+        assert_eq!(
+            LineTransform::SortNatural.apply(&lines),
+            ["B2", "a", "b2", "b10"]
+        );
+        assert_eq!(
+            LineTransform::SortCaseInsensitive.apply(&lines),
+            ["a", "b10", "B2", "b2"]
+        );
+        assert_eq!(
+            LineTransform::SortDescending.apply(&lines),
+            ["b2", "b10", "a", "B2"]
+        );
+        assert_eq!(
+            LineTransform::SortNumeric.apply(&["10 x", "x", " -1.5", "9"]),
+            ["x", " -1.5", "9", "10 x"]
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let lines = ["a", "", "b", "a", "  "];
+        assert_eq!(LineTransform::Unique.apply(&lines), ["a", "", "b", "  "]);
+        assert_eq!(LineTransform::DeleteBlank.apply(&lines), ["a", "b", "a"]);
+        assert_eq!(
+            LineTransform::Reverse.apply(&lines),
+            ["  ", "a", "b", "", "a"]
+        );
+        let mut shuffled = LineTransform::Shuffle.apply(&lines);
+        shuffled.sort();
+        assert_eq!(shuffled, LineTransform::Sort.apply(&lines));
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(
+            join(&["let x = foo(  ", "    a,", "  b", ")"]),
+            ("let x = foo(a, b)".to_string(), 16)
+        );
+        assert_eq!(join(&["// one", "  // two"]), ("// one two".to_string(), 6));
+        assert_eq!(
+            join(&["/* one", " * two", " */"]),
+            ("/* one two */".to_string(), 10)
+        );
+        assert_eq!(join(&["a", ""]), ("a".to_string(), 1));
+        assert_eq!(join(&["# a", "b"]), ("# a b".to_string(), 3));
+    }
+}
@@ -5,13 +5,20 @@
 
 pub mod ansi;
 pub mod ansi_background;
+pub mod folding;
 pub mod grammar_registry;
 pub mod highlight_engine;
 pub mod highlighter;
 pub mod indent;
+pub mod line_edit;
 pub mod line_iterator;
 pub mod line_wrapping;
+pub mod outline;
+pub mod rectangle;
 pub mod semantic_highlight;
+pub mod semantic_tokens;
+pub mod snippet;
 pub mod text_property;
 pub mod textmate_highlighter;
+pub mod textobjects;
 pub mod word_navigation;
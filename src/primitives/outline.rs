@@ -0,0 +1,480 @@
+//! Document outline (symbol tree) for the active buffer
+//!
+//! Symbols come from the language server (`textDocument/documentSymbol`) when one is
+//! running. Otherwise they are extracted with tree-sitter using the language's
+//! `tags.scm` query under `queries/<lang>`.
+//!
+//! # Query Captures
+//! - `@name`: The symbol's name
+//! - `@definition.<kind>`: The whole definition, where `<kind>` is a lowercase LSP
+//!   symbol kind (e.g. `function`, `method`, `struct`, `enum_member`)
+//!
+//! When several patterns match the same node, the first pattern in the file wins,
+//! so more specific patterns (e.g. methods inside an impl block) go first.
+
+use crate::primitives::highlighter::Language;
+use lsp_types::{DocumentSymbol, DocumentSymbolResponse, Position, SymbolKind};
+use std::collections::HashMap;
+use std::ops::Range;
+use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
+
+/// A single entry in the document outline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineSymbol {
+    /// Symbol name as displayed in the outline
+    pub name: String,
+    /// Extra information such as a signature (LSP only)
+    pub detail: Option<String>,
+    /// Lowercase LSP symbol kind (e.g. "function", "struct")
+    pub kind: String,
+    /// Nesting depth (0 = top level)
+    pub depth: usize,
+    /// Line of the symbol's name (1-based)
+    pub line: usize,
+    /// Byte range of the whole definition
+    pub range: Range<usize>,
+    /// Byte offset of the symbol's name (where navigation should land)
+    pub selection_start: usize,
+}
+
+/// Outline extractor using tree-sitter tag queries
+pub struct OutlineExtractor {
+    /// Map of language to (parser, query)
+    configs: HashMap<&'static str, (Parser, Query)>,
+}
+
+impl OutlineExtractor {
+    /// Create a new outline extractor
+    pub fn new() -> Self {
+        Self {
+            configs: HashMap::new(),
+        }
+    }
+
+    /// Whether a tags query exists for this language
+    pub fn supports(language: &Language) -> bool {
+        Self::query_source(language).is_some()
+    }
+
+    /// Tags query for a language, if it has one
+    fn query_source(
+        language: &Language,
+    ) -> Option<(&'static str, tree_sitter::Language, &'static str)> {
+        let config = match language {
+            Language::Rust => (
+                "rust",
+                tree_sitter_rust::LANGUAGE.into(),
+                include_str!("../../queries/rust/tags.scm"),
+            ),
+            Language::Python => (
+                "python",
+                tree_sitter_python::LANGUAGE.into(),
+                include_str!("../../queries/python/tags.scm"),
+            ),
+            Language::JavaScript => (
+                "javascript",
+                tree_sitter_javascript::LANGUAGE.into(),
+                include_str!("../../queries/javascript/tags.scm"),
+            ),
+            Language::TypeScript => (
+                "typescript",
+                tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+                include_str!("../../queries/typescript/tags.scm"),
+            ),
+            Language::C => (
+                "c",
+                tree_sitter_c::LANGUAGE.into(),
+                include_str!("../../queries/c/tags.scm"),
+            ),
+            Language::Cpp => (
+                "cpp",
+                tree_sitter_cpp::LANGUAGE.into(),
+                include_str!("../../queries/cpp/tags.scm"),
+            ),
+            Language::Go => (
+                "go",
+                tree_sitter_go::LANGUAGE.into(),
+                include_str!("../../queries/go/tags.scm"),
+            ),
+            Language::Java => (
+                "java",
+                tree_sitter_java::LANGUAGE.into(),
+                include_str!("../../queries/java/tags.scm"),
+            ),
+            Language::CSharp => (
+                "csharp",
+                tree_sitter_c_sharp::LANGUAGE.into(),
+                include_str!("../../queries/csharp/tags.scm"),
+            ),
+            Language::Ruby => (
+                "ruby",
+                tree_sitter_ruby::LANGUAGE.into(),
+                include_str!("../../queries/ruby/tags.scm"),
+            ),
+            Language::Php => (
+                "php",
+                tree_sitter_php::LANGUAGE_PHP.into(),
+                include_str!("../../queries/php/tags.scm"),
+            ),
+            Language::Bash => (
+                "bash",
+                tree_sitter_bash::LANGUAGE.into(),
+                include_str!("../../queries/bash/tags.scm"),
+            ),
+            // Markup/data languages have no meaningful symbol outline
+            Language::HTML | Language::CSS | Language::Json | Language::Lua => return None,
+        };
+        Some(config)
+    }
+
+    /// Get or create parser and query for a language
+    fn get_config(&mut self, language: &Language) -> Option<(&mut Parser, &Query)> {
+        let (lang_name, ts_language, query_str) = Self::query_source(language)?;
+
+        if !self.configs.contains_key(lang_name) {
+            let mut parser = Parser::new();
+            if parser.set_language(&ts_language).is_err() {
+                tracing::error!("Failed to set language for {}", lang_name);
+                return None;
+            }
+
+            let query = match Query::new(&ts_language, query_str) {
+                Ok(q) => q,
+                Err(e) => {
+                    tracing::error!("Failed to create tags query for {}: {:?}", lang_name, e);
+                    return None;
+                }
+            };
+
+            self.configs.insert(lang_name, (parser, query));
+        }
+
+        let (parser, query) = self.configs.get_mut(lang_name)?;
+        Some((parser, query))
+    }
+
+    /// Extract outline symbols from source text
+    ///
+    /// Returns an empty list if the language has no tags query or parsing fails.
+    pub fn symbols(&mut self, source: &str, language: &Language) -> Vec<OutlineSymbol> {
+        let Some((parser, query)) = self.get_config(language) else {
+            return Vec::new();
+        };
+        let Some(tree) = parser.parse(source, None) else {
+            return Vec::new();
+        };
+
+        let name_index = query.capture_index_for_name("name");
+        let capture_names = query.capture_names();
+
+        // Node range -> (pattern index, symbol); the earliest pattern wins
+        let mut by_range: HashMap<(usize, usize), (usize, OutlineSymbol)> = HashMap::new();
+
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+        while let Some(m) = matches.next() {
+            let mut name_node = None;
+            let mut definition = None;
+            for capture in m.captures {
+                if Some(capture.index) == name_index {
+                    name_node = Some(capture.node);
+                } else if let Some(kind) =
+                    capture_names[capture.index as usize].strip_prefix("definition.")
+                {
+                    definition = Some((capture.node, kind));
+                }
+            }
+            let (Some(name_node), Some((node, kind))) = (name_node, definition) else {
+                continue;
+            };
+
+            let name = source[name_node.byte_range()]
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let symbol = OutlineSymbol {
+                name,
+                detail: None,
+                kind: kind.to_string(),
+                depth: 0,
+                line: name_node.start_position().row + 1,
+                range: node.byte_range(),
+                selection_start: name_node.start_byte(),
+            };
+
+            let key = (node.start_byte(), node.end_byte());
+            match by_range.get(&key) {
+                Some((pattern, _)) if *pattern <= m.pattern_index => {}
+                _ => {
+                    by_range.insert(key, (m.pattern_index, symbol));
+                }
+            }
+        }
+
+        let mut symbols: Vec<OutlineSymbol> = by_range.into_values().map(|(_, s)| s).collect();
+        assign_depths(&mut symbols);
+        symbols
+    }
+}
+
+impl Default for OutlineExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sort symbols by position and set each depth from range containment
+fn assign_depths(symbols: &mut [OutlineSymbol]) {
+    symbols.sort_by_key(|s| (s.range.start, std::cmp::Reverse(s.range.end)));
+
+    // Ends of the ranges enclosing the current symbol
+    let mut enclosing: Vec<usize> = Vec::new();
+    for symbol in symbols.iter_mut() {
+        while enclosing
+            .last()
+            .is_some_and(|&end| symbol.range.start >= end)
+        {
+            enclosing.pop();
+        }
+        symbol.depth = enclosing.len();
+        enclosing.push(symbol.range.end);
+    }
+}
+
+/// Flatten a `textDocument/documentSymbol` response into outline order
+///
+/// `to_byte` converts an LSP position to a byte offset in the buffer.
+pub fn flatten_document_symbols(
+    response: DocumentSymbolResponse,
+    to_byte: impl Fn(Position) -> usize,
+) -> Vec<OutlineSymbol> {
+    match response {
+        DocumentSymbolResponse::Nested(symbols) => {
+            let mut out = Vec::new();
+            let mut sorted = symbols;
+            sorted.sort_by_key(|s| s.range.start);
+            for symbol in sorted {
+                push_nested(&mut out, symbol, 0, &to_byte);
+            }
+            out
+        }
+        DocumentSymbolResponse::Flat(infos) => {
+            let mut out: Vec<OutlineSymbol> = infos
+                .into_iter()
+                .map(|info| {
+                    let range = info.location.range;
+                    let start = to_byte(range.start);
+                    OutlineSymbol {
+                        name: info.name,
+                        detail: info.container_name,
+                        kind: symbol_kind_name(info.kind).to_string(),
+                        depth: 0,
+                        line: range.start.line as usize + 1,
+                        range: start..to_byte(range.end).max(start),
+                        selection_start: start,
+                    }
+                })
+                .collect();
+            assign_depths(&mut out);
+            out
+        }
+    }
+}
+
+fn push_nested(
+    out: &mut Vec<OutlineSymbol>,
+    symbol: DocumentSymbol,
+    depth: usize,
+    to_byte: &impl Fn(Position) -> usize,
+) {
+    let start = to_byte(symbol.range.start);
+    out.push(OutlineSymbol {
+        name: symbol.name,
+        detail: symbol.detail,
+        kind: symbol_kind_name(symbol.kind).to_string(),
+        depth,
+        line: symbol.selection_range.start.line as usize + 1,
+        range: start..to_byte(symbol.range.end).max(start),
+        selection_start: to_byte(symbol.selection_range.start),
+    });
+
+    let mut children = symbol.children.unwrap_or_default();
+    children.sort_by_key(|s| s.range.start);
+    for child in children {
+        push_nested(out, child, depth + 1, to_byte);
+    }
+}
+
+/// Lowercase name for an LSP symbol kind, matching the `@definition.<kind>` captures
+pub fn symbol_kind_name(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::FILE => "file",
+        SymbolKind::MODULE => "module",
+        SymbolKind::NAMESPACE => "namespace",
+        SymbolKind::PACKAGE => "package",
+        SymbolKind::CLASS => "class",
+        SymbolKind::METHOD => "method",
+        SymbolKind::PROPERTY => "property",
+        SymbolKind::FIELD => "field",
+        SymbolKind::CONSTRUCTOR => "constructor",
+        SymbolKind::ENUM => "enum",
+        SymbolKind::INTERFACE => "interface",
+        SymbolKind::FUNCTION => "function",
+        SymbolKind::VARIABLE => "variable",
+        SymbolKind::CONSTANT => "constant",
+        SymbolKind::STRING => "string",
+        SymbolKind::NUMBER => "number",
+        SymbolKind::BOOLEAN => "boolean",
+        SymbolKind::ARRAY => "array",
+        SymbolKind::OBJECT => "object",
+        SymbolKind::KEY => "key",
+        SymbolKind::NULL => "null",
+        SymbolKind::ENUM_MEMBER => "enum_member",
+        SymbolKind::STRUCT => "struct",
+        SymbolKind::EVENT => "event",
+        SymbolKind::OPERATOR => "operator",
+        SymbolKind::TYPE_PARAMETER => "type_parameter",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(symbols: &[OutlineSymbol]) -> Vec<(usize, &str, &str)> {
+        symbols
+            .iter()
+            .map(|s| (s.depth, s.kind.as_str(), s.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_all_tags_queries_compile() {
+        let languages = [
+            Language::Rust,
+            Language::Python,
+            Language::JavaScript,
+            Language::TypeScript,
+            Language::C,
+            Language::Cpp,
+            Language::Go,
+            Language::Java,
+            Language::CSharp,
+            Language::Ruby,
+            Language::Php,
+            Language::Bash,
+        ];
+        let mut extractor = OutlineExtractor::new();
+        for language in &languages {
+            assert!(
+                extractor.get_config(language).is_some(),
+                "tags query for {:?} should compile",
+                language
+            );
+        }
+    }
+
+    #[test]
+    fn test_rust_outline_nesting() {
+        let source = "mod util {\n    pub fn helper() {}\n}\n\nstruct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn new() -> Self { Point { x: 0 } }\n}\n\nfn main() {}\n";
+        let mut extractor = OutlineExtractor::new();
+        let symbols = extractor.symbols(source, &Language::Rust);
+
+        assert_eq!(
+            names(&symbols),
+            vec![
+                (0, "module", "util"),
+                (1, "function", "helper"),
+                (0, "struct", "Point"),
+                (1, "field", "x"),
+                (0, "object", "Point"),
+                (1, "method", "new"),
+                (0, "function", "main"),
+            ]
+        );
+
+        let main = symbols.last().unwrap();
+        assert_eq!(main.line, 13);
+        assert_eq!(
+            &source[main.selection_start..main.selection_start + 4],
+            "main"
+        );
+    }
+
+    #[test]
+    fn test_python_outline_methods() {
+        let source =
+            "class Greeter:\n    def greet(self):\n        pass\n\ndef main():\n    pass\n";
+        let mut extractor = OutlineExtractor::new();
+        let symbols = extractor.symbols(source, &Language::Python);
+
+        assert_eq!(
+            names(&symbols),
+            vec![
+                (0, "class", "Greeter"),
+                (1, "method", "greet"),
+                (0, "function", "main"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_language_is_empty() {
+        let mut extractor = OutlineExtractor::new();
+        assert!(extractor.symbols("{\"a\": 1}", &Language::Json).is_empty());
+        assert!(!OutlineExtractor::supports(&Language::Json));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_flatten_nested_document_symbols() {
+        use lsp_types::Range as LspRange;
+
+        let pos = |line, character| Position { line, character };
+        let symbol = |name: &str, kind, range: LspRange, children| DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range: LspRange::new(range.start, range.start),
+            children,
+        };
+        let response = DocumentSymbolResponse::Nested(vec![
+            symbol(
+                "main",
+                SymbolKind::FUNCTION,
+                LspRange::new(pos(5, 0), pos(6, 1)),
+                None,
+            ),
+            symbol(
+                "Point",
+                SymbolKind::STRUCT,
+                LspRange::new(pos(0, 0), pos(3, 1)),
+                Some(vec![symbol(
+                    "x",
+                    SymbolKind::FIELD,
+                    LspRange::new(pos(1, 4), pos(1, 10)),
+                    None,
+                )]),
+            ),
+        ]);
+
+        // One byte per character, 100 bytes per line
+        let symbols =
+            flatten_document_symbols(response, |p| p.line as usize * 100 + p.character as usize);
+
+        assert_eq!(
+            names(&symbols),
+            vec![
+                (0, "struct", "Point"),
+                (1, "field", "x"),
+                (0, "function", "main"),
+            ]
+        );
+        assert_eq!(symbols[1].range, 104..110);
+        assert_eq!(symbols[2].line, 6);
+    }
+}
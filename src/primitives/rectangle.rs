@@ -0,0 +1,304 @@
+//! Rectangles: the same columns on a range of lines
+//!
+//! A rectangle is given by two corners, like a block selection or, in Emacs, the
+//! mark and the cursor. Columns are byte offsets into their line, as in block
+//! selections; lines that end inside the rectangle only contribute what they have.
+//!
+//! The functions here compute the edits of the rectangle commands from the buffer
+//! text; the editor applies them (see `app/rectangle.rs`).
+
+use std::ops::Range;
+
+/// Text replacing a range of the buffer
+pub type Edit = (Range<usize>, String);
+
+/// Lines `top..=bottom`, columns `left..right`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rectangle {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+/// Start offset and text (without the line break) of every line
+fn lines(text: &str) -> Vec<(usize, &str)> {
+    let mut start = 0;
+    text.split('\n')
+        .map(|line| {
+            let entry = (start, line.strip_suffix('\r').unwrap_or(line));
+            start += line.len() + 1;
+            entry
+        })
+        .collect()
+}
+
+/// Column `column` of `line`, moved back onto a character boundary and cut short
+/// at the end of the line
+fn floor_column(line: &str, column: usize) -> usize {
+    let mut column = column.min(line.len());
+    while !line.is_char_boundary(column) {
+        column -= 1;
+    }
+    column
+}
+
+/// Line and column of a byte offset
+pub fn line_column(text: &str, pos: usize) -> (usize, usize) {
+    let before = &text.as_bytes()[..pos.min(text.len())];
+    let line = before.iter().filter(|&&b| b == b'\n').count();
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    (line, before.len() - line_start)
+}
+
+/// Byte offset of a line and column, clamped to the text
+pub fn offset(text: &str, line: usize, column: usize) -> usize {
+    match lines(text).get(line) {
+        Some(&(start, line)) => start + floor_column(line, column),
+        None => text.len(),
+    }
+}
+
+impl Rectangle {
+    /// Rectangle between two corners, each a line and a column
+    pub fn new(a: (usize, usize), b: (usize, usize)) -> Self {
+        Self {
+            top: a.0.min(b.0),
+            bottom: a.0.max(b.0),
+            left: a.1.min(b.1),
+            right: a.1.max(b.1),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.right - self.left
+    }
+
+    /// The rectangle's lines that exist in `text`
+    fn lines<'a>(&self, text: &'a str) -> Vec<(usize, &'a str)> {
+        lines(text)
+            .into_iter()
+            .skip(self.top)
+            .take(self.bottom - self.top + 1)
+            .collect()
+    }
+
+    /// Range of the rectangle on each of its lines
+    pub fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.lines(text)
+            .into_iter()
+            .map(|(start, line)| {
+                start + floor_column(line, self.left)..start + floor_column(line, self.right)
+            })
+            .collect()
+    }
+
+    /// Text of each line, padded with spaces to the rectangle's width
+    pub fn extract(&self, text: &str) -> Vec<String> {
+        self.ranges(text)
+            .into_iter()
+            .map(|range| {
+                let mut piece = text[range].to_string();
+                if piece.len() < self.width() {
+                    piece.push_str(&" ".repeat(self.width() - piece.len()));
+                }
+                piece
+            })
+            .collect()
+    }
+
+    /// Edits deleting the rectangle
+    pub fn delete(&self, text: &str) -> Vec<Edit> {
+        self.ranges(text)
+            .into_iter()
+            .filter(|range| !range.is_empty())
+            .map(|range| (range, String::new()))
+            .collect()
+    }
+
+    /// Edits replacing the rectangle's text on each line with the next of `pieces`
+    ///
+    /// Lines that end before the left edge are padded with spaces up to it.
+    pub fn replace(&self, text: &str, pieces: impl IntoIterator<Item = String>) -> Vec<Edit> {
+        self.lines(text)
+            .into_iter()
+            .zip(pieces)
+            .map(|((start, line), piece)| {
+                let range =
+                    start + floor_column(line, self.left)..start + floor_column(line, self.right);
+                let pad = " ".repeat(self.left.saturating_sub(line.len()));
+                (range, pad + piece.as_str())
+            })
+            .filter(|(range, piece)| !range.is_empty() || !piece.is_empty())
+            .collect()
+    }
+
+    /// Edits shifting the text from the left edge right by the rectangle's width
+    ///
+    /// Lines that end before the left edge have nothing to shift and are left alone.
+    pub fn open(&self, text: &str) -> Vec<Edit> {
+        if self.width() == 0 {
+            return Vec::new();
+        }
+        self.lines(text)
+            .into_iter()
+            .filter(|(_, line)| line.len() > self.left)
+            .map(|(start, line)| {
+                let at = start + floor_column(line, self.left);
+                (at..at, " ".repeat(self.width()))
+            })
+            .collect()
+    }
+}
+
+/// Edits inserting `pieces` as a column at `pos`, one on each line from its line down
+///
+/// Lines that end before the column are padded with spaces, and lines are added at
+/// the end of the text when there are more pieces than lines.
+pub fn yank(text: &str, pos: usize, pieces: &[String]) -> Vec<Edit> {
+    let (first_line, column) = line_column(text, pos);
+    let lines = lines(text);
+    let mut edits: Vec<Edit> = Vec::new();
+    let mut added = String::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        match lines.get(first_line + i) {
+            Some(&(start, line)) => {
+                let at = start + floor_column(line, column);
+                let pad = " ".repeat(column.saturating_sub(line.len()));
+                edits.push((at..at, pad + piece.as_str()));
+            }
+            None => {
+                added.push('\n');
+                added.push_str(&" ".repeat(column));
+                added.push_str(piece);
+            }
+        }
+    }
+    if !added.is_empty() {
+        // The last line ends at the end of the text: its piece goes first
+        match edits.last_mut() {
+            Some((range, piece)) if range.start == text.len() => piece.push_str(&added),
+            _ => edits.push((text.len()..text.len(), added)),
+        }
+    }
+    edits
+}
+
+/// `count` numbers from input such as `1`, `10 5` (start and step) or `001`
+/// (zero-padded to three digits), right-aligned to the same width
+///
+/// Returns `None` if the input isn't a start number and an optional step.
+pub fn number_sequence(input: &str, count: usize) -> Option<Vec<String>> {
+    let mut parts = input.split_whitespace();
+    let start_text = parts.next().unwrap_or("1");
+    let start: i64 = start_text.parse().ok()?;
+    let step: i64 = match parts.next() {
+        Some(step) => step.parse().ok()?,
+        None => 1,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let digits = start_text.trim_start_matches(['-', '+']);
+    let zero_pad = digits.len() > 1 && digits.starts_with('0');
+    let numbers: Vec<i64> = (0..count as i64)
+        .map(|i| start.saturating_add(step.saturating_mul(i)))
+        .collect();
+    let mut width = numbers
+        .iter()
+        .map(|n| n.to_string().len())
+        .max()
+        .unwrap_or(0);
+    if zero_pad {
+        width = width.max(start_text.len());
+    }
+    Some(
+        numbers
+            .iter()
+            .map(|n| {
+                if zero_pad {
+                    format!("{:0width$}", n)
+                } else {
+                    format!("{:>width$}", n)
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply edits, which are in buffer order and don't overlap
+    fn apply(text: &str, edits: Vec<Edit>) -> String {
+        let mut text = text.to_string();
+        for (range, piece) in edits.into_iter().rev() {
+            text.replace_range(range, &piece);
+        }
+        text
+    }
+
+    const TABLE: &str = "name  age\nalice 30\nbob\ncarol 41\n";
+
+    #[test]
+    fn test_extract_and_delete() {
+        let rect = Rectangle::new((3, 8), (0, 6));
+        assert_eq!(rect, Rectangle::new((0, 6), (3, 8)));
+        assert_eq!(rect.extract(TABLE), vec!["ag", "30", "  ", "41"]);
+        assert_eq!(
+            apply(TABLE, rect.delete(TABLE)),
+            "name  e\nalice \nbob\ncarol \n"
+        );
+    }
+
+    #[test]
+    fn test_yank_pads_and_adds_lines() {
+        let pieces: Vec<String> = ["x", "y", "z"].iter().map(|s| s.to_string()).collect();
+        // At the end of "bob": the pieces go on "bob", "carol" and a new line
+        let pos = offset(TABLE, 2, 3);
+        assert_eq!(line_column(TABLE, pos), (2, 3));
+        assert_eq!(
+            apply(TABLE, yank(TABLE, pos, &pieces)),
+            "name  age\nalice 30\nbobx\ncaryol 41\n   z"
+        );
+        assert_eq!(apply("ab", yank("ab", 1, &pieces)), "axb\n y\n z");
+    }
+
+    #[test]
+    fn test_replace_and_open() {
+        let rect = Rectangle::new((1, 6), (3, 6));
+        let text = apply(
+            TABLE,
+            rect.replace(TABLE, std::iter::repeat("| ".to_string())),
+        );
+        assert_eq!(text, "name  age\nalice | 30\nbob   | \ncarol | 41\n");
+
+        let rect = Rectangle::new((0, 5), (3, 7));
+        assert_eq!(
+            apply(TABLE, rect.open(TABLE)),
+            "name    age\nalice   30\nbob\ncarol   41\n"
+        );
+    }
+
+    #[test]
+    fn test_number_sequence() {
+        assert_eq!(
+            number_sequence("", 3),
+            Some(vec!["1".to_string(), "2".to_string(), "3".to_string()])
+        );
+        assert_eq!(
+            number_sequence("8 1", 3),
+            Some(vec![" 8".to_string(), " 9".to_string(), "10".to_string()])
+        );
+        assert_eq!(
+            number_sequence("001 5", 2),
+            Some(vec!["001".to_string(), "006".to_string()])
+        );
+        assert_eq!(number_sequence("one", 2), None);
+    }
+}
@@ -0,0 +1,560 @@
+//! LSP semantic tokens as a highlighting layer
+//!
+//! # Design
+//! - **Legend decoding**: token type and modifier indices are resolved once per response
+//!   using the legend the server advertised in its capabilities
+//! - **Position-based storage**: tokens keep their LSP position (line, UTF-16 column) and
+//!   are converted to byte ranges only for the visible viewport at render time
+//! - **Delta support**: the raw token array is kept so `full/delta` edits can be applied
+//! - **Edit tolerance**: edits drop tokens on the touched lines and shift the ones below,
+//!   so colors stay in place until the next response replaces them
+//!
+//! Tokens are painted above tree-sitter/TextMate colors; token types this module does not
+//! know keep the syntax color and only contribute their modifiers.
+
+use crate::view::theme::Theme;
+use lsp_types::{
+    SemanticToken, SemanticTokens, SemanticTokensDelta, SemanticTokensEdit,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensServerCapabilities,
+    ServerCapabilities,
+};
+use ratatui::style::{Color, Modifier, Style};
+use std::ops::Range;
+
+/// Token types announced to servers (standard types plus common server extensions)
+pub const TOKEN_TYPES: &[&str] = &[
+    "namespace",
+    "type",
+    "class",
+    "enum",
+    "interface",
+    "struct",
+    "typeParameter",
+    "parameter",
+    "variable",
+    "property",
+    "enumMember",
+    "event",
+    "function",
+    "method",
+    "macro",
+    "keyword",
+    "modifier",
+    "comment",
+    "string",
+    "number",
+    "regexp",
+    "operator",
+    "decorator",
+    // rust-analyzer and clangd extensions
+    "attribute",
+    "boolean",
+    "builtinType",
+    "character",
+    "escapeSequence",
+    "formatSpecifier",
+    "lifetime",
+    "selfKeyword",
+    "selfTypeKeyword",
+    "typeAlias",
+];
+
+/// Token modifiers announced to servers
+pub const TOKEN_MODIFIERS: &[&str] = &[
+    "declaration",
+    "definition",
+    "readonly",
+    "static",
+    "deprecated",
+    "abstract",
+    "async",
+    "modification",
+    "documentation",
+    "defaultLibrary",
+    // rust-analyzer extensions
+    "mutable",
+    "unsafe",
+];
+
+/// Highlighting category of a semantic token type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticTokenKind {
+    Namespace,
+    Type,
+    Parameter,
+    Variable,
+    Property,
+    Constant,
+    Function,
+    Macro,
+    Keyword,
+    Comment,
+    String,
+    Operator,
+}
+
+impl SemanticTokenKind {
+    /// Map an LSP token type name to a category
+    pub fn from_type_name(name: &str) -> Option<Self> {
+        match name {
+            "namespace" => Some(Self::Namespace),
+            "type" | "class" | "enum" | "interface" | "struct" | "typeParameter"
+            | "builtinType" | "typeAlias" | "selfTypeKeyword" => Some(Self::Type),
+            "parameter" => Some(Self::Parameter),
+            "variable" | "event" => Some(Self::Variable),
+            "property" => Some(Self::Property),
+            "enumMember" | "number" | "boolean" | "decorator" | "attribute" | "escapeSequence"
+            | "formatSpecifier" => Some(Self::Constant),
+            "function" | "method" => Some(Self::Function),
+            "macro" => Some(Self::Macro),
+            "keyword" | "modifier" | "selfKeyword" | "lifetime" => Some(Self::Keyword),
+            "comment" => Some(Self::Comment),
+            "string" | "regexp" | "character" => Some(Self::String),
+            "operator" => Some(Self::Operator),
+            _ => None,
+        }
+    }
+
+    /// Get the color for this category from the theme
+    pub fn color(&self, theme: &Theme) -> Color {
+        match self {
+            Self::Namespace => theme.syntax_namespace,
+            Self::Type => theme.syntax_type,
+            Self::Parameter => theme.syntax_parameter,
+            Self::Variable => theme.syntax_variable,
+            Self::Property => theme.syntax_variable, // Properties are like variables
+            Self::Constant => theme.syntax_constant,
+            Self::Function => theme.syntax_function,
+            Self::Macro => theme.syntax_macro,
+            Self::Keyword => theme.syntax_keyword,
+            Self::Comment => theme.syntax_comment,
+            Self::String => theme.syntax_string,
+            Self::Operator => theme.syntax_operator,
+        }
+    }
+}
+
+/// Resolved styling of one token: its category plus the modifiers we render
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SemanticTokenStyle {
+    pub kind: Option<SemanticTokenKind>,
+    /// `mutable` modifier (rendered underlined)
+    pub mutable: bool,
+    /// `deprecated` modifier (rendered crossed out)
+    pub deprecated: bool,
+    /// `unsafe` modifier (rendered in the theme's unsafe color)
+    pub is_unsafe: bool,
+}
+
+impl SemanticTokenStyle {
+    /// Style to patch over the syntax highlighting, or None if the token changes nothing
+    pub fn style(&self, theme: &Theme) -> Option<Style> {
+        let mut style = Style::default();
+        if self.is_unsafe {
+            style = style.fg(theme.syntax_unsafe);
+        } else if let Some(kind) = self.kind {
+            style = style.fg(kind.color(theme));
+        }
+        if self.mutable {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        if self.deprecated {
+            style = style.add_modifier(Modifier::CROSSED_OUT);
+        }
+        (style != Style::default()).then_some(style)
+    }
+}
+
+/// A server's token legend, pre-resolved to styles
+#[derive(Debug, Clone, Default)]
+pub struct SemanticTokenLegend {
+    kinds: Vec<Option<SemanticTokenKind>>,
+    mutable_bit: Option<u32>,
+    deprecated_bit: Option<u32>,
+    unsafe_bit: Option<u32>,
+}
+
+impl SemanticTokenLegend {
+    pub fn new(legend: &SemanticTokensLegend) -> Self {
+        let modifier_bit = |name: &str| {
+            legend
+                .token_modifiers
+                .iter()
+                .position(|m| m.as_str() == name)
+                .filter(|&index| index < 32)
+                .map(|index| 1u32 << index)
+        };
+        Self {
+            kinds: legend
+                .token_types
+                .iter()
+                .map(|t| SemanticTokenKind::from_type_name(t.as_str()))
+                .collect(),
+            mutable_bit: modifier_bit("mutable"),
+            deprecated_bit: modifier_bit("deprecated"),
+            unsafe_bit: modifier_bit("unsafe"),
+        }
+    }
+
+    /// Resolve a token's type index and modifier bitset
+    pub fn resolve(&self, token_type: u32, modifiers: u32) -> SemanticTokenStyle {
+        let has = |bit: Option<u32>| bit.is_some_and(|bit| modifiers & bit != 0);
+        SemanticTokenStyle {
+            kind: self.kinds.get(token_type as usize).copied().flatten(),
+            mutable: has(self.mutable_bit),
+            deprecated: has(self.deprecated_bit),
+            is_unsafe: has(self.unsafe_bit),
+        }
+    }
+}
+
+/// Which semantic token requests a server supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SemanticTokensSupport {
+    pub full: bool,
+    pub delta: bool,
+    pub range: bool,
+}
+
+/// Read the legend and supported requests from server capabilities
+pub fn semantic_tokens_capabilities(
+    capabilities: &ServerCapabilities,
+) -> Option<(SemanticTokenLegend, SemanticTokensSupport)> {
+    let options = match capabilities.semantic_tokens_provider.as_ref()? {
+        SemanticTokensServerCapabilities::SemanticTokensOptions(options) => options,
+        SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+            &options.semantic_tokens_options
+        }
+    };
+    let (full, delta) = match &options.full {
+        Some(SemanticTokensFullOptions::Bool(full)) => (*full, false),
+        Some(SemanticTokensFullOptions::Delta { delta }) => (true, delta.unwrap_or(false)),
+        None => (false, false),
+    };
+    let support = SemanticTokensSupport {
+        full,
+        delta,
+        range: options.range.unwrap_or(false),
+    };
+    Some((SemanticTokenLegend::new(&options.legend), support))
+}
+
+/// A decoded token at an absolute LSP position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionedToken {
+    /// 0-based line
+    pub line: u32,
+    /// Start column in UTF-16 code units
+    pub start: u32,
+    /// Length in UTF-16 code units
+    pub length: u32,
+    pub style: SemanticTokenStyle,
+}
+
+/// Decode relative (delta-encoded) tokens into absolute positions
+pub fn decode_semantic_tokens(
+    data: &[SemanticToken],
+    legend: &SemanticTokenLegend,
+) -> Vec<PositionedToken> {
+    let mut line = 0u32;
+    let mut start = 0u32;
+    data.iter()
+        .map(|token| {
+            if token.delta_line > 0 {
+                line += token.delta_line;
+                start = token.delta_start;
+            } else {
+                start += token.delta_start;
+            }
+            PositionedToken {
+                line,
+                start,
+                length: token.length,
+                style: legend.resolve(token.token_type, token.token_modifiers_bitset),
+            }
+        })
+        .collect()
+}
+
+/// Apply `full/delta` edits to a previous token array
+///
+/// Edit offsets count integers of the flat encoding (five per token), so they must fall
+/// on token boundaries.
+pub fn apply_semantic_token_edits(
+    data: &mut Vec<SemanticToken>,
+    mut edits: Vec<SemanticTokensEdit>,
+) -> Result<(), String> {
+    // Apply back to front so earlier offsets stay valid
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    for edit in edits {
+        if edit.start % 5 != 0 || edit.delete_count % 5 != 0 {
+            return Err("Semantic token edit does not align with token boundaries".to_string());
+        }
+        let start = edit.start as usize / 5;
+        let end = start + edit.delete_count as usize / 5;
+        if end > data.len() {
+            return Err("Semantic token edit is out of range".to_string());
+        }
+        data.splice(start..end, edit.data.unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// Semantic tokens of one buffer
+#[derive(Debug, Clone, Default)]
+pub struct SemanticTokenStore {
+    /// Result ID of the last full result (for `full/delta`)
+    result_id: Option<String>,
+    /// Raw tokens of the last full result, as the server knows them
+    data: Vec<SemanticToken>,
+    /// Decoded tokens sorted by position
+    tokens: Vec<PositionedToken>,
+    /// Lines covered by range results (None when tokens come from full results)
+    covered_lines: Option<Range<u32>>,
+}
+
+impl SemanticTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Result ID to send with the next `full/delta` request
+    pub fn result_id(&self) -> Option<&str> {
+        self.result_id.as_deref()
+    }
+
+    /// Lines covered by range results, if the tokens came from `range` requests
+    pub fn covered_lines(&self) -> Option<&Range<u32>> {
+        self.covered_lines.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Replace all tokens with a full result
+    pub fn set_full(&mut self, tokens: SemanticTokens, legend: &SemanticTokenLegend) {
+        self.tokens = decode_semantic_tokens(&tokens.data, legend);
+        self.data = tokens.data;
+        self.result_id = tokens.result_id;
+        self.covered_lines = None;
+    }
+
+    /// Apply a `full/delta` result to the previous full result
+    pub fn apply_delta(
+        &mut self,
+        delta: SemanticTokensDelta,
+        legend: &SemanticTokenLegend,
+    ) -> Result<(), String> {
+        if let Err(e) = apply_semantic_token_edits(&mut self.data, delta.edits) {
+            // The server will send a full result for a request without a result ID
+            self.result_id = None;
+            return Err(e);
+        }
+        self.tokens = decode_semantic_tokens(&self.data, legend);
+        self.result_id = delta.result_id;
+        self.covered_lines = None;
+        Ok(())
+    }
+
+    /// Replace the tokens on `lines` with a range result
+    pub fn set_range(
+        &mut self,
+        lines: Range<u32>,
+        tokens: SemanticTokens,
+        legend: &SemanticTokenLegend,
+    ) {
+        let decoded = decode_semantic_tokens(&tokens.data, legend);
+        self.tokens.retain(|t| !lines.contains(&t.line));
+        self.tokens.extend(decoded);
+        self.tokens.sort_by_key(|t| (t.line, t.start));
+        // Range results can't seed a delta request
+        self.result_id = None;
+        self.data.clear();
+        self.covered_lines = Some(match self.covered_lines.take() {
+            Some(covered) if covered.start <= lines.end && lines.start <= covered.end => {
+                covered.start.min(lines.start)..covered.end.max(lines.end)
+            }
+            _ => lines,
+        });
+    }
+
+    /// Keep tokens roughly in place after an edit until fresh tokens arrive
+    ///
+    /// Tokens on lines `start_line..=old_end_line` are dropped and tokens below are
+    /// shifted by the change in line count.
+    pub fn adjust_for_edit(&mut self, start_line: u32, old_end_line: u32, new_end_line: u32) {
+        let shift = i64::from(new_end_line) - i64::from(old_end_line);
+        self.tokens
+            .retain(|t| t.line < start_line || t.line > old_end_line);
+        if shift != 0 {
+            for token in &mut self.tokens {
+                if token.line > old_end_line {
+                    token.line = (i64::from(token.line) + shift) as u32;
+                }
+            }
+            // Range coverage no longer lines up with the buffer
+            self.covered_lines = None;
+        }
+    }
+
+    /// Drop all tokens (e.g. when the feature is turned off)
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Tokens on `lines`, in position order
+    pub fn tokens_in_lines(&self, lines: Range<u32>) -> &[PositionedToken] {
+        let start = self.tokens.partition_point(|t| t.line < lines.start);
+        let end = self.tokens.partition_point(|t| t.line < lines.end);
+        &self.tokens[start..end.max(start)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{SemanticTokenModifier, SemanticTokenType};
+
+    fn legend() -> SemanticTokenLegend {
+        SemanticTokenLegend::new(&SemanticTokensLegend {
+            token_types: vec![
+                SemanticTokenType::new("function"),
+                SemanticTokenType::new("variable"),
+                SemanticTokenType::new("unknownThing"),
+            ],
+            token_modifiers: vec![
+                SemanticTokenModifier::new("declaration"),
+                SemanticTokenModifier::new("mutable"),
+                SemanticTokenModifier::new("unsafe"),
+            ],
+        })
+    }
+
+    fn token(
+        delta_line: u32,
+        delta_start: u32,
+        length: u32,
+        token_type: u32,
+        modifiers: u32,
+    ) -> SemanticToken {
+        SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type,
+            token_modifiers_bitset: modifiers,
+        }
+    }
+
+    #[test]
+    fn test_decode_relative_positions_and_legend() {
+        // fn main() { let mut x = 1; unsafe_call(); }
+        let data = vec![
+            token(0, 3, 4, 0, 0b001), // main: function, declaration
+            token(1, 8, 1, 1, 0b010), // x: variable, mutable
+            token(0, 4, 3, 2, 0b100), // unknown type, unsafe
+        ];
+        let tokens = decode_semantic_tokens(&data, &legend());
+
+        assert_eq!((tokens[0].line, tokens[0].start), (0, 3));
+        assert_eq!(tokens[0].style.kind, Some(SemanticTokenKind::Function));
+        assert!(!tokens[0].style.mutable);
+
+        assert_eq!((tokens[1].line, tokens[1].start), (1, 8));
+        assert!(tokens[1].style.mutable);
+
+        // Same line: start is relative to the previous token
+        assert_eq!((tokens[2].line, tokens[2].start), (1, 12));
+        assert_eq!(tokens[2].style.kind, None);
+        assert!(tokens[2].style.is_unsafe);
+    }
+
+    #[test]
+    fn test_style_layers_modifiers_over_kind() {
+        let theme = Theme::dark();
+        let style = SemanticTokenStyle {
+            kind: Some(SemanticTokenKind::Variable),
+            mutable: true,
+            ..Default::default()
+        }
+        .style(&theme)
+        .unwrap();
+        assert_eq!(style.fg, Some(theme.syntax_variable));
+        assert!(style.add_modifier.contains(Modifier::UNDERLINED));
+
+        // Unknown type without modifiers leaves the syntax color alone
+        assert_eq!(SemanticTokenStyle::default().style(&theme), None);
+    }
+
+    #[test]
+    fn test_apply_delta_edits() {
+        let mut data = vec![
+            token(0, 0, 1, 0, 0),
+            token(1, 0, 1, 1, 0),
+            token(1, 0, 1, 0, 0),
+        ];
+        // Replace the middle token with two tokens
+        let edits = vec![SemanticTokensEdit {
+            start: 5,
+            delete_count: 5,
+            data: Some(vec![token(1, 0, 2, 1, 0), token(0, 4, 2, 1, 0)]),
+        }];
+        apply_semantic_token_edits(&mut data, edits).unwrap();
+        assert_eq!(data.len(), 4);
+        assert_eq!(data[1].length, 2);
+        assert_eq!(data[2].delta_start, 4);
+
+        let misaligned = vec![SemanticTokensEdit {
+            start: 3,
+            delete_count: 0,
+            data: None,
+        }];
+        assert!(apply_semantic_token_edits(&mut data, misaligned).is_err());
+    }
+
+    #[test]
+    fn test_store_adjusts_for_edits_and_ranges() {
+        let legend = legend();
+        let mut store = SemanticTokenStore::new();
+        store.set_full(
+            SemanticTokens {
+                result_id: Some("1".to_string()),
+                data: vec![
+                    token(0, 0, 1, 0, 0),
+                    token(2, 0, 1, 1, 0),
+                    token(2, 0, 1, 1, 0),
+                ],
+            },
+            &legend,
+        );
+        assert_eq!(store.result_id(), Some("1"));
+
+        // Newline typed on line 2: its tokens are dropped, the ones below move down
+        store.adjust_for_edit(2, 2, 3);
+        let lines: Vec<u32> = store
+            .tokens_in_lines(0..10)
+            .iter()
+            .map(|t| t.line)
+            .collect();
+        assert_eq!(lines, vec![0, 5]);
+
+        // A range result only replaces the lines it covers
+        store.set_range(
+            4..6,
+            SemanticTokens {
+                result_id: None,
+                data: vec![token(4, 2, 3, 0, 0)],
+            },
+            &legend,
+        );
+        let lines: Vec<u32> = store
+            .tokens_in_lines(0..10)
+            .iter()
+            .map(|t| t.line)
+            .collect();
+        assert_eq!(lines, vec![0, 4]);
+        assert_eq!(store.covered_lines(), Some(&(4..6)));
+        assert_eq!(store.result_id(), None);
+    }
+}
@@ -0,0 +1,547 @@
+//! Snippet syntax and user snippet files
+//!
+//! Snippet bodies use the LSP / TextMate syntax: `$1` and `${1:default}` tabstops,
+//! `${1|one,two|}` choices, `$0` for the final cursor position and `$NAME` /
+//! `${NAME:default}` variables. Parsing expands the variables and placeholders into
+//! the text to insert and records where each tabstop ended up in it.
+//!
+//! User snippets are read from VSCode-style JSON files, one per language.
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::Path;
+
+/// A parsed snippet: the text to insert and its tabstops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub text: String,
+    /// Tabstops in the order Tab visits them, ending with `$0`
+    pub tabstops: Vec<Tabstop>,
+}
+
+/// A tabstop and the places it appears (more than one when it's mirrored)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tabstop {
+    pub index: u32,
+    /// Byte ranges of its occurrences in the snippet text
+    pub ranges: Vec<Range<usize>>,
+    /// Values offered for a choice tabstop (`${1|a,b|}`)
+    pub choices: Vec<String>,
+}
+
+impl Snippet {
+    /// Whether the snippet has tabstops to visit besides the final cursor position
+    pub fn has_tabstops(&self) -> bool {
+        self.tabstops.iter().any(|tabstop| tabstop.index != 0)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Tabstop {
+        index: u32,
+        placeholder: Option<Vec<Node>>,
+        choices: Vec<String>,
+    },
+    Variable {
+        name: String,
+        default: Option<Vec<Node>>,
+    },
+}
+
+/// Parse a snippet body, expanding variables with `variable`
+///
+/// Variables `variable` doesn't know expand to their default, or to their name when
+/// they have none. Malformed constructs are inserted literally.
+pub fn parse_snippet(body: &str, variable: impl Fn(&str) -> Option<String>) -> Snippet {
+    let mut parser = Parser {
+        chars: body.chars().collect(),
+        pos: 0,
+    };
+    let nodes = parser.parse_nodes(false);
+
+    // Mirrors without a placeholder of their own show the tabstop's placeholder
+    let mut placeholders = HashMap::new();
+    collect_placeholders(&nodes, &mut placeholders);
+
+    let mut renderer = Renderer {
+        variable: &variable,
+        placeholders: &placeholders,
+        text: String::new(),
+        tabstops: BTreeMap::new(),
+        expanding: Vec::new(),
+    };
+    renderer.render(&nodes);
+
+    let end = renderer.text.len();
+    let mut tabstops: Vec<Tabstop> = renderer
+        .tabstops
+        .into_iter()
+        .map(|(index, (ranges, choices))| Tabstop {
+            index,
+            ranges,
+            choices,
+        })
+        .collect();
+    // `$0` comes last, and is the end of the snippet when it isn't given
+    let final_stop = match tabstops.iter().position(|tabstop| tabstop.index == 0) {
+        Some(position) => tabstops.remove(position),
+        None => Tabstop {
+            index: 0,
+            ranges: std::iter::once(end..end).collect(),
+            choices: Vec::new(),
+        },
+    };
+    tabstops.push(final_stop);
+
+    Snippet {
+        text: renderer.text,
+        tabstops,
+    }
+}
+
+fn collect_placeholders<'a>(nodes: &'a [Node], placeholders: &mut HashMap<u32, &'a [Node]>) {
+    for node in nodes {
+        match node {
+            Node::Tabstop {
+                index,
+                placeholder: Some(children),
+                ..
+            } => {
+                placeholders.entry(*index).or_insert(children);
+                collect_placeholders(children, placeholders);
+            }
+            Node::Variable {
+                default: Some(children),
+                ..
+            } => collect_placeholders(children, placeholders),
+            _ => {}
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Parse text and constructs up to the end, or to the `}` closing a placeholder
+    fn parse_nodes(&mut self, in_placeholder: bool) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '}' if in_placeholder => break,
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(escaped @ ('$' | '}' | '\\')) => {
+                            self.pos += 1;
+                            text.push(escaped);
+                        }
+                        _ => text.push('\\'),
+                    }
+                }
+                '$' => match self.parse_dollar() {
+                    Some(node) => {
+                        if !text.is_empty() {
+                            nodes.push(Node::Text(std::mem::take(&mut text)));
+                        }
+                        nodes.push(node);
+                    }
+                    None => {
+                        self.pos += 1;
+                        text.push('$');
+                    }
+                },
+                _ => {
+                    self.pos += 1;
+                    text.push(c);
+                }
+            }
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        nodes
+    }
+
+    /// Parse a construct starting at `$`, leaving the position unchanged if malformed
+    fn parse_dollar(&mut self) -> Option<Node> {
+        let start = self.pos;
+        let node = self.parse_dollar_inner();
+        if node.is_none() {
+            self.pos = start;
+        }
+        node
+    }
+
+    fn parse_dollar_inner(&mut self) -> Option<Node> {
+        self.pos += 1;
+        if let Some(index) = self.parse_int() {
+            return Some(Node::Tabstop {
+                index,
+                placeholder: None,
+                choices: Vec::new(),
+            });
+        }
+        if let Some(name) = self.parse_name() {
+            return Some(Node::Variable {
+                name,
+                default: None,
+            });
+        }
+        if self.peek() != Some('{') {
+            return None;
+        }
+        self.pos += 1;
+
+        if let Some(index) = self.parse_int() {
+            let (placeholder, choices) = match self.peek()? {
+                '}' => (None, Vec::new()),
+                ':' => {
+                    self.pos += 1;
+                    let children = self.parse_nodes(true);
+                    (Some(children), Vec::new())
+                }
+                '|' => {
+                    self.pos += 1;
+                    (None, self.parse_choices()?)
+                }
+                _ => return None,
+            };
+            self.expect('}')?;
+            return Some(Node::Tabstop {
+                index,
+                placeholder,
+                choices,
+            });
+        }
+
+        let name = self.parse_name()?;
+        let default = match self.peek()? {
+            '}' => None,
+            ':' => {
+                self.pos += 1;
+                Some(self.parse_nodes(true))
+            }
+            // Transforms (`${NAME/regex/format/}`) aren't applied
+            '/' => {
+                self.skip_transform()?;
+                None
+            }
+            _ => return None,
+        };
+        self.expect('}')?;
+        Some(Node::Variable { name, default })
+    }
+
+    fn expect(&mut self, c: char) -> Option<()> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn parse_int(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return None;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().ok()
+    }
+
+    fn parse_name(&mut self) -> Option<String> {
+        let start = self.pos;
+        if !self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            return None;
+        }
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        Some(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Parse `a,b|` after the opening `|` of a choice
+    fn parse_choices(&mut self) -> Option<Vec<String>> {
+        let mut choices = Vec::new();
+        let mut choice = String::new();
+        loop {
+            match self.peek()? {
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek()? {
+                        escaped @ ('$' | '}' | '\\' | ',' | '|') => {
+                            self.pos += 1;
+                            choice.push(escaped);
+                        }
+                        _ => choice.push('\\'),
+                    }
+                }
+                ',' => {
+                    self.pos += 1;
+                    choices.push(std::mem::take(&mut choice));
+                }
+                '|' => {
+                    self.pos += 1;
+                    choices.push(choice);
+                    return Some(choices);
+                }
+                c => {
+                    self.pos += 1;
+                    choice.push(c);
+                }
+            }
+        }
+    }
+
+    /// Skip `/regex/format/options` up to (not including) the closing `}`
+    fn skip_transform(&mut self) -> Option<()> {
+        let mut depth = 0usize;
+        loop {
+            match self.peek()? {
+                '\\' => self.pos += 2,
+                '{' => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                '}' if depth == 0 => return Some(()),
+                '}' => {
+                    depth -= 1;
+                    self.pos += 1;
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+}
+
+struct Renderer<'a, F: Fn(&str) -> Option<String>> {
+    variable: &'a F,
+    placeholders: &'a HashMap<u32, &'a [Node]>,
+    text: String,
+    /// Ranges and choices of each tabstop, by index
+    tabstops: BTreeMap<u32, (Vec<Range<usize>>, Vec<String>)>,
+    /// Tabstops whose placeholder is being rendered into a mirror, to stop cycles
+    expanding: Vec<u32>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Renderer<'_, F> {
+    fn render(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.text.push_str(text),
+                Node::Tabstop {
+                    index,
+                    placeholder,
+                    choices,
+                } => {
+                    let start = self.text.len();
+                    if let Some(children) = placeholder {
+                        self.render(children);
+                    } else if let Some(choice) = choices.first() {
+                        self.text.push_str(choice);
+                    } else if let Some(children) = self.placeholders.get(index) {
+                        if !self.expanding.contains(index) {
+                            self.expanding.push(*index);
+                            self.render(children);
+                            self.expanding.pop();
+                        }
+                    }
+                    let end = self.text.len();
+                    let entry = self.tabstops.entry(*index).or_default();
+                    entry.0.push(start..end);
+                    if entry.1.is_empty() {
+                        entry.1 = choices.clone();
+                    }
+                }
+                Node::Variable { name, default } => match (self.variable)(name) {
+                    Some(value) => self.text.push_str(&value),
+                    None => match default {
+                        Some(children) => self.render(children),
+                        None => self.text.push_str(name),
+                    },
+                },
+            }
+        }
+    }
+}
+
+/// A snippet defined in a user snippet file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetDefinition {
+    pub name: String,
+    /// Words that expand to the snippet
+    pub prefixes: Vec<String>,
+    pub body: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct RawSnippetDefinition {
+    #[serde(default)]
+    prefix: Option<OneOrMany>,
+    body: OneOrMany,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// Parse a VSCode snippet file: an object mapping names to `prefix`, `body` and
+/// `description`, where `prefix` and `body` may be arrays (body lines are joined)
+pub fn parse_snippet_file(json: &str) -> Result<Vec<SnippetDefinition>, serde_json::Error> {
+    let raw: BTreeMap<String, RawSnippetDefinition> = serde_json::from_str(json)?;
+    Ok(raw
+        .into_iter()
+        .map(|(name, snippet)| SnippetDefinition {
+            prefixes: match snippet.prefix {
+                Some(OneOrMany::One(prefix)) => vec![prefix],
+                Some(OneOrMany::Many(prefixes)) => prefixes,
+                None => Vec::new(),
+            },
+            body: match snippet.body {
+                OneOrMany::One(body) => body,
+                OneOrMany::Many(lines) => lines.join("\n"),
+            },
+            description: snippet.description,
+            name,
+        })
+        .collect())
+}
+
+/// Load the user snippets for a language from `<dir>/<language>.json`
+pub fn load_snippets(dir: &Path, language: &str) -> Vec<SnippetDefinition> {
+    let path = dir.join(format!("{}.json", language));
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    match parse_snippet_file(&json) {
+        Ok(snippets) => snippets,
+        Err(e) => {
+            tracing::warn!("Failed to parse snippet file {}: {}", path.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(body: &str) -> Snippet {
+        parse_snippet(body, |name| match name {
+            "TM_FILENAME" => Some("main.rs".to_string()),
+            _ => None,
+        })
+    }
+
+    fn stop(index: u32, ranges: Vec<Range<usize>>) -> Tabstop {
+        Tabstop {
+            index,
+            ranges,
+            choices: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_tabstops_and_placeholders() {
+        let snippet = parse("fn ${1:name}($2) {\n    $0\n}");
+        assert_eq!(snippet.text, "fn name() {\n    \n}");
+        assert_eq!(
+            snippet.tabstops,
+            vec![
+                stop(1, vec![3..7]),
+                stop(2, vec![8..8]),
+                stop(0, vec![16..16])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_final_tabstop_defaults_to_end() {
+        let snippet = parse("$2 and $1");
+        assert_eq!(snippet.text, " and ");
+        assert_eq!(
+            snippet.tabstops,
+            vec![
+                stop(1, vec![5..5]),
+                stop(2, vec![0..0]),
+                stop(0, vec![5..5])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mirrors_take_the_placeholder() {
+        let snippet = parse("let $1 = ${1:value}; ${2:${1}}");
+        assert_eq!(snippet.text, "let value = value; value");
+        assert_eq!(snippet.tabstops[0], stop(1, vec![4..9, 12..17, 19..24]));
+        assert_eq!(snippet.tabstops[1], stop(2, vec![19..24]));
+    }
+
+    #[test]
+    fn test_choices() {
+        let snippet = parse("${1|pub,pub(crate)\\, x|} fn");
+        assert_eq!(snippet.text, "pub fn");
+        assert_eq!(
+            snippet.tabstops[0].choices,
+            vec!["pub".to_string(), "pub(crate), x".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let snippet = parse("// $TM_FILENAME ${UNKNOWN:fallback} $UNKNOWN ${TM_FILENAME/(.*)/$1/}");
+        assert_eq!(snippet.text, "// main.rs fallback UNKNOWN main.rs");
+    }
+
+    #[test]
+    fn test_escapes_and_malformed_constructs_are_literal() {
+        let snippet = parse("\\$1 costs $ and \\} ${1:a\\}b} ${2");
+        assert_eq!(snippet.text, "$1 costs $ and } a}b ${2");
+        assert_eq!(snippet.tabstops[0], stop(1, vec![17..20]));
+    }
+
+    #[test]
+    fn test_parse_snippet_file() {
+        let json = r##"{
+            "Test function": {
+                "prefix": ["test", "tfn"],
+                "body": ["#[test]", "fn ${1:name}() {", "    $0", "}"],
+                "description": "A unit test"
+            },
+            "Print": { "prefix": "p", "body": "println!(\"$1\");" }
+        }"##;
+        let snippets = parse_snippet_file(json).unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].name, "Print");
+        assert_eq!(snippets[0].prefixes, vec!["p".to_string()]);
+        assert_eq!(snippets[1].body, "#[test]\nfn ${1:name}() {\n    $0\n}");
+        assert_eq!(snippets[1].description.as_deref(), Some("A unit test"));
+    }
+}
@@ -0,0 +1,601 @@
+//! Syntax-aware text objects and structural selection
+//!
+//! Functions, classes, parameters, arguments, comments and blocks are found with
+//! tree-sitter using the language's `textobjects.scm` query under `queries/<lang>`.
+//! Expanding and shrinking the selection walks the syntax tree itself.
+//!
+//! # Query Captures
+//! - `@<kind>.outer`: The whole object (e.g. `@function.outer`)
+//! - `@<kind>.inner`: Its contents, such as a function's body
+//!
+//! A bracketed inner node (`{ ... }`, `( ... )`) stands for what is between the
+//! brackets. Objects captured without an inner node use their outer range the
+//! same way. Parameters and arguments only capture `.inner`; their outer range
+//! adds the separator to the next (or previous) item, so deleting one leaves a
+//! well-formed list.
+
+use crate::primitives::highlighter::Language;
+use std::collections::HashMap;
+use std::ops::Range;
+use tree_sitter::{Node, Parser, Query, QueryCursor, StreamingIterator, Tree};
+
+/// Kind of syntax text object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextObjectKind {
+    Function,
+    Class,
+    Parameter,
+    Argument,
+    Comment,
+    Block,
+}
+
+impl TextObjectKind {
+    pub const ALL: [TextObjectKind; 6] = [
+        TextObjectKind::Function,
+        TextObjectKind::Class,
+        TextObjectKind::Parameter,
+        TextObjectKind::Argument,
+        TextObjectKind::Comment,
+        TextObjectKind::Block,
+    ];
+
+    /// Capture name prefix and action argument (e.g. "function")
+    pub fn name(self) -> &'static str {
+        match self {
+            TextObjectKind::Function => "function",
+            TextObjectKind::Class => "class",
+            TextObjectKind::Parameter => "parameter",
+            TextObjectKind::Argument => "argument",
+            TextObjectKind::Comment => "comment",
+            TextObjectKind::Block => "block",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Capitalized name for commands and messages
+    pub fn label(self) -> &'static str {
+        match self {
+            TextObjectKind::Function => "Function",
+            TextObjectKind::Class => "Class",
+            TextObjectKind::Parameter => "Parameter",
+            TextObjectKind::Argument => "Argument",
+            TextObjectKind::Comment => "Comment",
+            TextObjectKind::Block => "Block",
+        }
+    }
+
+    /// Parameters and arguments are list items joined by separators
+    fn is_list_item(self) -> bool {
+        matches!(self, TextObjectKind::Parameter | TextObjectKind::Argument)
+    }
+}
+
+/// A text object found in the document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextObject {
+    pub kind: TextObjectKind,
+    /// Byte range of the whole object
+    pub outer: Range<usize>,
+    /// Byte range of its contents
+    pub inner: Range<usize>,
+    /// Byte range of the enclosing syntax node; list items sharing it are siblings
+    pub parent: Option<Range<usize>>,
+}
+
+/// Text object extractor using tree-sitter textobjects queries
+pub struct TextObjectExtractor {
+    /// Map of language to (parser, query)
+    configs: HashMap<&'static str, (Parser, Query)>,
+}
+
+impl TextObjectExtractor {
+    /// Create a new text object extractor
+    pub fn new() -> Self {
+        Self {
+            configs: HashMap::new(),
+        }
+    }
+
+    /// Whether a textobjects query exists for this language
+    pub fn supports(language: &Language) -> bool {
+        Self::query_source(language).is_some()
+    }
+
+    /// Textobjects query for a language, if it has one
+    fn query_source(
+        language: &Language,
+    ) -> Option<(&'static str, tree_sitter::Language, &'static str)> {
+        let config = match language {
+            Language::Rust => (
+                "rust",
+                tree_sitter_rust::LANGUAGE.into(),
+                include_str!("../../queries/rust/textobjects.scm"),
+            ),
+            Language::Python => (
+                "python",
+                tree_sitter_python::LANGUAGE.into(),
+                include_str!("../../queries/python/textobjects.scm"),
+            ),
+            Language::JavaScript => (
+                "javascript",
+                tree_sitter_javascript::LANGUAGE.into(),
+                include_str!("../../queries/javascript/textobjects.scm"),
+            ),
+            Language::TypeScript => (
+                "typescript",
+                tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+                include_str!("../../queries/typescript/textobjects.scm"),
+            ),
+            Language::C => (
+                "c",
+                tree_sitter_c::LANGUAGE.into(),
+                include_str!("../../queries/c/textobjects.scm"),
+            ),
+            Language::Cpp => (
+                "cpp",
+                tree_sitter_cpp::LANGUAGE.into(),
+                include_str!("../../queries/cpp/textobjects.scm"),
+            ),
+            Language::Go => (
+                "go",
+                tree_sitter_go::LANGUAGE.into(),
+                include_str!("../../queries/go/textobjects.scm"),
+            ),
+            Language::Java => (
+                "java",
+                tree_sitter_java::LANGUAGE.into(),
+                include_str!("../../queries/java/textobjects.scm"),
+            ),
+            Language::CSharp => (
+                "csharp",
+                tree_sitter_c_sharp::LANGUAGE.into(),
+                include_str!("../../queries/csharp/textobjects.scm"),
+            ),
+            Language::Ruby => (
+                "ruby",
+                tree_sitter_ruby::LANGUAGE.into(),
+                include_str!("../../queries/ruby/textobjects.scm"),
+            ),
+            Language::Php => (
+                "php",
+                tree_sitter_php::LANGUAGE_PHP.into(),
+                include_str!("../../queries/php/textobjects.scm"),
+            ),
+            Language::Bash => (
+                "bash",
+                tree_sitter_bash::LANGUAGE.into(),
+                include_str!("../../queries/bash/textobjects.scm"),
+            ),
+            // Markup/data languages have no functions or classes
+            Language::HTML | Language::CSS | Language::Json | Language::Lua => return None,
+        };
+        Some(config)
+    }
+
+    /// Get or create parser and query for a language
+    fn get_config(&mut self, language: &Language) -> Option<(&mut Parser, &Query)> {
+        let (lang_name, ts_language, query_str) = Self::query_source(language)?;
+
+        if !self.configs.contains_key(lang_name) {
+            let mut parser = Parser::new();
+            if parser.set_language(&ts_language).is_err() {
+                tracing::error!("Failed to set language for {}", lang_name);
+                return None;
+            }
+
+            let query = match Query::new(&ts_language, query_str) {
+                Ok(q) => q,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to create textobjects query for {}: {:?}",
+                        lang_name,
+                        e
+                    );
+                    return None;
+                }
+            };
+
+            self.configs.insert(lang_name, (parser, query));
+        }
+
+        let (parser, query) = self.configs.get_mut(lang_name)?;
+        Some((parser, query))
+    }
+
+    fn parse(&mut self, source: &str, language: &Language) -> Option<Tree> {
+        let (parser, _) = self.get_config(language)?;
+        parser.parse(source, None)
+    }
+
+    /// All text objects of a kind, ordered by position
+    ///
+    /// Returns `None` if the language has no textobjects query or parsing fails.
+    pub fn objects(
+        &mut self,
+        source: &str,
+        language: &Language,
+        kind: TextObjectKind,
+    ) -> Option<Vec<TextObject>> {
+        let (parser, query) = self.get_config(language)?;
+        let tree = parser.parse(source, None)?;
+        let outer_name = format!("{}.outer", kind.name());
+        let inner_name = format!("{}.inner", kind.name());
+        let outer_index = query.capture_index_for_name(&outer_name);
+        let inner_index = query.capture_index_for_name(&inner_name);
+
+        // Outer range -> object; a match with an inner capture wins over one without
+        let mut by_range: HashMap<(usize, usize), (bool, TextObject)> = HashMap::new();
+
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+        while let Some(m) = matches.next() {
+            let mut outer = None;
+            let mut inner = None;
+            for capture in m.captures {
+                if Some(capture.index) == outer_index {
+                    outer = Some(capture.node);
+                } else if Some(capture.index) == inner_index {
+                    inner = Some(capture.node);
+                }
+            }
+
+            let object = match (outer, inner) {
+                (_, Some(node)) if kind.is_list_item() => TextObject {
+                    kind,
+                    outer: list_item_range(source, node.byte_range()),
+                    inner: node.byte_range(),
+                    parent: node.parent().map(|parent| parent.byte_range()),
+                },
+                (Some(node), inner) => TextObject {
+                    kind,
+                    outer: node.byte_range(),
+                    inner: contents(source, inner.unwrap_or(node).byte_range()),
+                    parent: node.parent().map(|parent| parent.byte_range()),
+                },
+                _ => continue,
+            };
+
+            let has_inner = inner.is_some();
+            let key = (object.outer.start, object.outer.end);
+            match by_range.get(&key) {
+                Some((true, _)) => {}
+                Some((false, _)) if !has_inner => {}
+                _ => {
+                    by_range.insert(key, (has_inner, object));
+                }
+            }
+        }
+
+        let mut objects: Vec<TextObject> = by_range.into_values().map(|(_, o)| o).collect();
+        objects.sort_by_key(|o| (o.outer.start, std::cmp::Reverse(o.outer.end)));
+        Some(objects)
+    }
+
+    /// The next larger syntax node around `range`, for expanding a selection
+    ///
+    /// Between a bracketed node and its brackets, the contents are selected first.
+    pub fn expand_selection(
+        &mut self,
+        source: &str,
+        language: &Language,
+        range: Range<usize>,
+    ) -> Option<Range<usize>> {
+        let tree = self.parse(source, language)?;
+        let mut node = tree
+            .root_node()
+            .named_descendant_for_byte_range(range.start, range.end)?;
+        loop {
+            let inside = contents(source, node.byte_range());
+            for candidate in [inside, node.byte_range()] {
+                if candidate.start <= range.start
+                    && range.end <= candidate.end
+                    && candidate != range
+                {
+                    return Some(candidate);
+                }
+            }
+            node = node.parent()?;
+        }
+    }
+
+    /// The syntax node one level inside `range` that contains `pos`
+    ///
+    /// Used to shrink a selection that was not made by expanding it.
+    pub fn shrink_selection(
+        &mut self,
+        source: &str,
+        language: &Language,
+        range: Range<usize>,
+        pos: usize,
+    ) -> Option<Range<usize>> {
+        let tree = self.parse(source, language)?;
+        let mut node = tree
+            .root_node()
+            .named_descendant_for_byte_range(range.start, range.end)?;
+        let inside = contents(source, node.byte_range());
+        if node.byte_range() == range && inside != range && !inside.is_empty() {
+            return Some(inside);
+        }
+        loop {
+            let child = child_at(node, pos).or_else(|| node.named_child(0))?;
+            let child_range = child.byte_range();
+            if child_range != range
+                && range.start <= child_range.start
+                && child_range.end <= range.end
+            {
+                return Some(child_range);
+            }
+            node = child;
+        }
+    }
+}
+
+impl Default for TextObjectExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Named child of `node` containing `pos`
+fn child_at(node: Node, pos: usize) -> Option<Node> {
+    let mut cursor = node.walk();
+    let child = node
+        .named_children(&mut cursor)
+        .find(|child| child.start_byte() <= pos && pos < child.end_byte());
+    child
+}
+
+/// The part of `range` between its brackets, without surrounding whitespace
+///
+/// Ranges that are not bracketed are returned unchanged.
+fn contents(source: &str, range: Range<usize>) -> Range<usize> {
+    let text = &source[range.clone()];
+    let bracketed = matches!(
+        (text.chars().next(), text.chars().last()),
+        (Some('{'), Some('}')) | (Some('('), Some(')')) | (Some('['), Some(']'))
+    );
+    if !bracketed || text.len() < 2 {
+        return range;
+    }
+    let inside = &text[1..text.len() - 1];
+    let start = range.start + 1 + (inside.len() - inside.trim_start().len());
+    let end = range.end - 1 - (inside.len() - inside.trim_end().len());
+    if start > end {
+        // Only whitespace between the brackets
+        return range.start + 1..range.start + 1;
+    }
+    start..end
+}
+
+/// A list item together with the separator to its neighbour
+///
+/// The separator after the item is preferred; the last item takes the one before it.
+fn list_item_range(source: &str, item: Range<usize>) -> Range<usize> {
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let after = &source[item.end..];
+    let rest = after.trim_start_matches(is_blank);
+    if let Some(rest) = rest.strip_prefix(',') {
+        let end = source.len() - rest.trim_start_matches(is_blank).len();
+        return item.start..end;
+    }
+    let before = &source[..item.start];
+    let trimmed = before.trim_end_matches(is_blank);
+    if let Some(trimmed) = trimmed.strip_suffix(',') {
+        return trimmed.len()..item.end;
+    }
+    // Whitespace-separated items (e.g. shell arguments)
+    let trailing = after.len() - after.trim_start_matches(is_blank).len();
+    if trailing > 0 && !rest.is_empty() && !rest.starts_with('\n') {
+        return item.start..item.end + trailing;
+    }
+    trimmed.len()..item.end
+}
+
+/// The innermost object around `range` (a selection, or an empty range at the cursor)
+///
+/// An object exactly matching a selection does not count, so selecting again grows
+/// the selection to the enclosing object.
+pub fn object_around(objects: &[TextObject], range: Range<usize>) -> Option<&TextObject> {
+    objects
+        .iter()
+        .filter(|o| {
+            if range.is_empty() {
+                o.outer.start <= range.start && range.start < o.outer.end
+            } else {
+                o.outer.start <= range.start && range.end <= o.outer.end && o.outer != range
+            }
+        })
+        .min_by_key(|o| o.outer.len())
+}
+
+/// Start of the first object after `pos` (or the last one before it)
+pub fn next_object_start(objects: &[TextObject], pos: usize, forward: bool) -> Option<usize> {
+    if forward {
+        objects
+            .iter()
+            .map(|o| o.outer.start)
+            .filter(|&start| start > pos)
+            .min()
+    } else {
+        objects
+            .iter()
+            .map(|o| o.outer.start)
+            .filter(|&start| start < pos)
+            .max()
+    }
+}
+
+/// The list item around `pos` and its next (or previous) sibling, for swapping
+///
+/// Returns the inner ranges of both items, the one around `pos` first.
+pub fn swap_target(
+    objects: &[TextObject],
+    pos: usize,
+    forward: bool,
+) -> Option<(Range<usize>, Range<usize>)> {
+    let current = objects
+        .iter()
+        .filter(|o| o.inner.start <= pos && pos <= o.inner.end)
+        .min_by_key(|o| o.inner.len())?;
+    let mut siblings = objects
+        .iter()
+        .filter(|o| o.kind == current.kind && o.parent == current.parent);
+    let neighbour = if forward {
+        siblings.find(|o| o.inner.start >= current.inner.end)
+    } else {
+        siblings.rfind(|o| o.inner.end <= current.inner.start)
+    }?;
+    Some((current.inner.clone(), neighbour.inner.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(source: &'a str, objects: &[TextObject]) -> Vec<(&'a str, &'a str)> {
+        objects
+            .iter()
+            .map(|o| (&source[o.outer.clone()], &source[o.inner.clone()]))
+            .collect()
+    }
+
+    #[test]
+    fn test_all_textobjects_queries_compile() {
+        let languages = [
+            Language::Rust,
+            Language::Python,
+            Language::JavaScript,
+            Language::TypeScript,
+            Language::C,
+            Language::Cpp,
+            Language::Go,
+            Language::Java,
+            Language::CSharp,
+            Language::Ruby,
+            Language::Php,
+            Language::Bash,
+        ];
+        let mut extractor = TextObjectExtractor::new();
+        for language in &languages {
+            assert!(
+                extractor.get_config(language).is_some(),
+                "textobjects query for {:?} should compile",
+                language
+            );
+        }
+    }
+
+    #[test]
+    fn test_rust_functions_and_classes() {
+        let source = "struct Point {\n    x: i32,\n}\n\nfn main() {\n    let p = 1;\n}\n";
+        let mut extractor = TextObjectExtractor::new();
+
+        let functions = extractor
+            .objects(source, &Language::Rust, TextObjectKind::Function)
+            .unwrap();
+        assert_eq!(
+            texts(source, &functions),
+            vec![("fn main() {\n    let p = 1;\n}", "let p = 1;")]
+        );
+
+        let classes = extractor
+            .objects(source, &Language::Rust, TextObjectKind::Class)
+            .unwrap();
+        assert_eq!(
+            texts(source, &classes),
+            vec![("struct Point {\n    x: i32,\n}", "x: i32,")]
+        );
+    }
+
+    #[test]
+    fn test_list_items_include_separator() {
+        let source = "fn f() { g(a, b,  c); }\n";
+        let mut extractor = TextObjectExtractor::new();
+        let arguments = extractor
+            .objects(source, &Language::Rust, TextObjectKind::Argument)
+            .unwrap();
+        assert_eq!(
+            texts(source, &arguments),
+            vec![("a, ", "a"), ("b,  ", "b"), (",  c", "c")]
+        );
+    }
+
+    #[test]
+    fn test_object_at_and_navigation() {
+        let source = "def a():\n    pass\n\ndef b():\n    return lambda x: x\n";
+        let mut extractor = TextObjectExtractor::new();
+        let functions = extractor
+            .objects(source, &Language::Python, TextObjectKind::Function)
+            .unwrap();
+        assert_eq!(functions.len(), 3);
+
+        // Innermost function around the lambda's body
+        let pos = source.rfind('x').unwrap();
+        let lambda = object_around(&functions, pos..pos).unwrap();
+        assert_eq!(&source[lambda.outer.clone()], "lambda x: x");
+        let enclosing = object_around(&functions, lambda.outer.clone()).unwrap();
+        assert_eq!(enclosing.outer.start, 19);
+
+        assert_eq!(next_object_start(&functions, 0, true), Some(19));
+        assert_eq!(next_object_start(&functions, 19, false), Some(0));
+        assert_eq!(next_object_start(&functions, pos, true), None);
+    }
+
+    #[test]
+    fn test_swap_target_stays_in_list() {
+        let source = "fn f(a: u8, b: u8) { g(x, y); }\n";
+        let mut extractor = TextObjectExtractor::new();
+        let parameters = extractor
+            .objects(source, &Language::Rust, TextObjectKind::Parameter)
+            .unwrap();
+        let (current, neighbour) = swap_target(&parameters, 5, true).unwrap();
+        assert_eq!(&source[current], "a: u8");
+        assert_eq!(&source[neighbour], "b: u8");
+        assert!(swap_target(&parameters, 12, true).is_none());
+
+        let arguments = extractor
+            .objects(source, &Language::Rust, TextObjectKind::Argument)
+            .unwrap();
+        let (current, neighbour) = swap_target(&arguments, 26, false).unwrap();
+        assert_eq!(&source[current], "y");
+        assert_eq!(&source[neighbour], "x");
+    }
+
+    #[test]
+    fn test_expand_and_shrink_selection() {
+        let source = "fn f() {\n    call(alpha, beta);\n}\n";
+        let mut extractor = TextObjectExtractor::new();
+        let pos = source.find("alpha").unwrap() + 2;
+
+        let mut steps = Vec::new();
+        let mut range = pos..pos;
+        while let Some(next) = extractor.expand_selection(source, &Language::Rust, range.clone()) {
+            steps.push(&source[next.clone()]);
+            range = next;
+        }
+        assert_eq!(
+            &steps[..4],
+            &["alpha", "alpha, beta", "(alpha, beta)", "call(alpha, beta)"]
+        );
+
+        let call = source.find("call").unwrap();
+        let shrunk = extractor
+            .shrink_selection(source, &Language::Rust, call..call + 17, pos)
+            .unwrap();
+        assert_eq!(&source[shrunk], "(alpha, beta)");
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        let mut extractor = TextObjectExtractor::new();
+        assert!(extractor
+            .objects("{}", &Language::Json, TextObjectKind::Block)
+            .is_none());
+        assert!(!TextObjectExtractor::supports(&Language::Json));
+    }
+}
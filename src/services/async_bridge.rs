@@ -10,10 +10,13 @@
 //! - Computation should be sync (editing, rendering)
 //! - Main loop remains responsive and simple
 
+use crate::services::lsp::async_handler::HierarchyEntry;
 use crate::services::terminal::TerminalId;
 use crate::view::file_tree::{FileTreeView, NodeId};
 use lsp_types::{
-    CodeActionOrCommand, CompletionItem, Diagnostic, InlayHint, Location, SignatureHelp,
+    CodeActionOrCommand, CompletionItem, Diagnostic, DocumentSymbolResponse, FoldingRange,
+    InlayHint, Location, SemanticTokensFullDeltaResult, SignatureHelp, TextEdit,
+    WorkspaceSymbolResponse,
 };
 use serde_json::Value;
 use std::sync::mpsc;
@@ -24,6 +27,8 @@ pub enum AsyncMessage {
     /// LSP diagnostics received for a file
     LspDiagnostics {
         uri: String,
+        /// Name of the server that published them (see `LspServerConfig::server_name`)
+        server: String,
         diagnostics: Vec<Diagnostic>,
     },
 
@@ -41,11 +46,21 @@ pub enum AsyncMessage {
     /// LSP completion response
     LspCompletion {
         request_id: u64,
+        /// Name of the server that offered the items; they are resolved there
+        server: String,
         items: Vec<CompletionItem>,
+        /// More typing should re-query the server rather than filter these items
+        is_incomplete: bool,
     },
 
-    /// LSP go-to-definition response
-    LspGotoDefinition {
+    /// LSP completion item resolve response (completionItem/resolve)
+    LspCompletionItemResolved {
+        request_id: u64,
+        result: Result<CompletionItem, String>,
+    },
+
+    /// LSP go-to-definition, declaration, type definition or implementation response
+    LspGotoLocations {
         request_id: u64,
         locations: Vec<Location>,
     },
@@ -83,13 +98,58 @@ pub enum AsyncMessage {
     /// LSP code actions response
     LspCodeActions {
         request_id: u64,
+        language: String,
+        /// Name of the server that offered the actions; their commands run there
+        server: String,
         actions: Vec<CodeActionOrCommand>,
     },
 
+    /// LSP code action resolve response (codeAction/resolve)
+    LspCodeActionResolved {
+        request_id: u64,
+        result: Result<lsp_types::CodeAction, String>,
+    },
+
+    /// LSP execute command response (workspace/executeCommand)
+    LspCommandExecuted {
+        request_id: u64,
+        result: Result<(), String>,
+    },
+
+    /// Workspace edit pushed by a server (workspace/applyEdit)
+    LspApplyEdit {
+        language: String,
+        server: String,
+        /// Optional label describing the edit, e.g. the name of a refactoring
+        label: Option<String>,
+        edit: lsp_types::WorkspaceEdit,
+        /// Receives the outcome, which is the server's reply
+        response: tokio::sync::oneshot::Sender<lsp_types::ApplyWorkspaceEditResponse>,
+    },
+
+    /// File watchers registered by a server (client/registerCapability for
+    /// workspace/didChangeWatchedFiles)
+    LspWatchedFilesRegistered {
+        language: String,
+        server: String,
+        /// Registration ID, used to unregister the watchers later
+        id: String,
+        watchers: Vec<lsp_types::FileSystemWatcher>,
+    },
+
+    /// File watchers unregistered by a server (client/unregisterCapability)
+    LspWatchedFilesUnregistered {
+        language: String,
+        server: String,
+        id: String,
+    },
+
     /// LSP pulled diagnostics response (textDocument/diagnostic)
     LspPulledDiagnostics {
         request_id: u64,
         uri: String,
+        /// Name of the server that computed them
+        server: String,
         /// New result_id for incremental updates (None if server doesn't support)
         result_id: Option<String>,
         /// Diagnostics (empty if unchanged)
@@ -106,13 +166,102 @@ pub enum AsyncMessage {
         hints: Vec<InlayHint>,
     },
 
+    /// LSP formatting response (textDocument/formatting, rangeFormatting or onTypeFormatting)
+    LspFormatting {
+        request_id: u64,
+        uri: String,
+        /// Edits to apply, relative to the document version the request was made against
+        result: Result<Vec<TextEdit>, String>,
+    },
+
+    /// LSP document symbols response (textDocument/documentSymbol)
+    LspDocumentSymbols {
+        request_id: u64,
+        uri: String,
+        result: Result<DocumentSymbolResponse, String>,
+    },
+
+    /// LSP semantic tokens response (textDocument/semanticTokens/*)
+    ///
+    /// Full and range responses arrive as `SemanticTokensFullDeltaResult::Tokens`.
+    LspSemanticTokens {
+        request_id: u64,
+        uri: String,
+        result: Result<SemanticTokensFullDeltaResult, String>,
+    },
+
+    /// LSP folding range response (textDocument/foldingRange)
+    LspFoldingRanges {
+        request_id: u64,
+        uri: String,
+        result: Result<Vec<FoldingRange>, String>,
+    },
+
+    /// LSP code lens response (textDocument/codeLens)
+    LspCodeLens {
+        request_id: u64,
+        language: String,
+        /// Name of the server that provided the lenses; they are resolved and run there
+        server: String,
+        result: Result<Vec<lsp_types::CodeLens>, String>,
+    },
+
+    /// LSP code lens resolve response (codeLens/resolve)
+    LspCodeLensResolved {
+        request_id: u64,
+        result: Result<lsp_types::CodeLens, String>,
+    },
+
+    /// Server asked for code lenses to be requested again (workspace/codeLens/refresh)
+    LspCodeLensRefresh { language: String },
+
+    /// LSP call or type hierarchy response (prepare, incoming/outgoing calls,
+    /// supertypes or subtypes)
+    LspHierarchy {
+        request_id: u64,
+        result: Result<Vec<HierarchyEntry>, String>,
+    },
+
+    /// LSP workspace symbol search response (workspace/symbol)
+    LspWorkspaceSymbols {
+        request_id: u64,
+        language: String,
+        result: Result<WorkspaceSymbolResponse, String>,
+    },
+
     /// LSP server status became quiescent (project fully loaded)
     /// This is a rust-analyzer specific notification (experimental/serverStatus)
     LspServerQuiescent { language: String },
 
+    /// Response from a debug adapter to a request of the editor
+    DapResponse {
+        /// Debug session the adapter belongs to
+        session: u64,
+        request_id: u64,
+        command: String,
+        result: Result<Value, String>,
+    },
+
+    /// Event from a debug adapter (stopped, continued, output, terminated, ...)
+    DapEvent {
+        session: u64,
+        event: String,
+        body: Value,
+    },
+
+    /// A debug adapter exited or closed its output
+    DapExited { session: u64, error: String },
+
     /// File changed externally (future: file watching)
     FileChanged { path: String },
 
+    /// File created, changed or deleted in the working directory, for the file
+    /// watchers registered by language servers
+    WatchedFileChanged {
+        path: std::path::PathBuf,
+        change: lsp_types::FileChangeType,
+    },
+
     /// Git status updated (future: git integration)
     GitStatusChanged { status: String },
 
@@ -141,9 +290,18 @@ pub enum AsyncMessage {
         exit_code: i32,
     },
 
+    /// Shell command run on the selection finished (one output per piece of text)
+    ShellCommandFinished {
+        /// ID of the run, to ignore results of superseded runs
+        id: u64,
+        outputs: Vec<crate::services::shell_command::ShellOutput>,
+    },
+
     /// LSP progress notification ($/progress)
     LspProgress {
         language: String,
+        /// Name of the server among the language's servers
+        server: String,
         token: String,
         value: LspProgressValue,
     },
@@ -151,6 +309,8 @@ pub enum AsyncMessage {
     /// LSP window message (window/showMessage)
     LspWindowMessage {
         language: String,
+        /// Name of the server among the language's servers
+        server: String,
         message_type: LspMessageType,
         message: String,
     },
@@ -158,13 +318,26 @@ pub enum AsyncMessage {
     /// LSP log message (window/logMessage)
     LspLogMessage {
         language: String,
+        /// Name of the server among the language's servers
+        server: String,
         message_type: LspMessageType,
         message: String,
     },
 
+    /// A JSON-RPC message exchanged with an LSP server, while tracing is on
+    LspTrace {
+        language: String,
+        server: String,
+        /// Whether the editor sent the message (rather than received it)
+        outgoing: bool,
+        message: String,
+    },
+
     /// LSP server status update
     LspStatusUpdate {
         language: String,
+        /// Name of the server among the language's servers
+        server: String,
         status: LspServerStatus,
     },
     /// Generic notification from an LSP server
@@ -418,6 +591,7 @@ mod tests {
         sender
             .send(AsyncMessage::LspDiagnostics {
                 uri: "file:///test.rs".to_string(),
+                server: "rust-analyzer".to_string(),
                 diagnostics: diagnostics.clone(),
             })
             .unwrap();
@@ -428,9 +602,11 @@ mod tests {
         match &messages[0] {
             AsyncMessage::LspDiagnostics {
                 uri,
+                server,
                 diagnostics: diags,
             } => {
                 assert_eq!(uri, "file:///test.rs");
+                assert_eq!(server, "rust-analyzer");
                 assert_eq!(diags.len(), 1);
                 assert_eq!(diags[0].message, "test error");
             }
@@ -5,12 +5,18 @@
 //! - Uses crossterm's OSC 52 escape sequences for copying to system clipboard
 //! - Uses arboard crate for reading from system clipboard
 //! - Gracefully falls back to internal clipboard if system clipboard is unavailable
+//! - Keeps recent entries in a kill ring, for yank-pop and "Paste from History"
+//! - Holds named registers (`a`-`z`; `A`-`Z` append to them)
 
 use crossterm::clipboard::CopyToClipboard;
 use crossterm::execute;
+use std::collections::{HashMap, VecDeque};
 use std::io::{stdout, Write};
 use std::sync::Mutex;
 
+/// Number of entries kept in the kill ring
+pub const KILL_RING_MAX: usize = 60;
+
 /// Global clipboard holder to maintain X11 clipboard ownership for the application lifetime.
 /// On X11, the clipboard owner must stay alive to respond to paste requests from other apps.
 static SYSTEM_CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
@@ -20,6 +26,18 @@ static SYSTEM_CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
 pub struct Clipboard {
     /// Internal clipboard content (always available)
     internal: String,
+
+    /// Copied and killed text, most recent first
+    ring: VecDeque<String>,
+
+    /// Text of each cursor when `internal` came from a multi-cursor copy
+    pieces: Option<Vec<String>>,
+
+    /// Named registers
+    registers: HashMap<char, String>,
+
+    /// Whether `internal` has changed since it was last copied to the system clipboard
+    system_outdated: bool,
 }
 
 impl Clipboard {
@@ -27,66 +45,70 @@ impl Clipboard {
     pub fn new() -> Self {
         Self {
             internal: String::new(),
+            ring: VecDeque::new(),
+            pieces: None,
+            registers: HashMap::new(),
+            system_outdated: false,
         }
     }
 
-    /// Copy text to both internal and system clipboard
+    /// Copy the text of several cursors, to be pasted back one per cursor
     ///
-    /// Tries multiple methods to maximize compatibility:
-    /// 1. OSC 52 escape sequence (works in Konsole, Kitty, Alacritty, Wezterm, xterm, iTerm2)
-    /// 2. arboard crate (works via X11/Wayland APIs in Gnome Console, XFCE Terminal, etc.)
-    pub fn copy(&mut self, text: String) {
-        self.internal = text.clone();
-
-        // Try OSC 52 first (works in modern terminals)
-        // Note: This doesn't "fail" in a detectable way - it just sends escape sequences
-        // that the terminal may or may not handle
-        let osc52_result = execute!(stdout(), CopyToClipboard::to_clipboard_from(&text));
-        if let Err(e) = &osc52_result {
-            tracing::debug!("Crossterm OSC 52 clipboard copy failed: {}", e);
+    /// The pieces are joined with newlines for the system clipboard.
+    pub fn copy_pieces(&mut self, pieces: Vec<String>) {
+        self.copy(pieces.join("\n"));
+        if pieces.len() > 1 {
+            self.pieces = Some(pieces);
         }
-        // Ensure the escape sequence is flushed to the terminal
-        let _ = stdout().flush();
-
-        // Also try arboard (works via X11/Wayland in terminals without OSC 52 support)
-        // This provides coverage for Gnome Console, XFCE Terminal, and similar
-        //
-        // Important: On X11, the clipboard owner must stay alive to respond to paste requests.
-        // We store the clipboard in a static so it lives for the application lifetime.
-        if let Ok(mut guard) = SYSTEM_CLIPBOARD.lock() {
-            // Create clipboard if it doesn't exist yet
-            if guard.is_none() {
-                match arboard::Clipboard::new() {
-                    Ok(cb) => *guard = Some(cb),
-                    Err(e) => {
-                        tracing::debug!("arboard clipboard init failed: {}", e);
-                    }
-                }
-            }
+    }
 
-            // Try to set text on the clipboard
-            if let Some(clipboard) = guard.as_mut() {
-                if let Err(e) = clipboard.set_text(&text) {
-                    tracing::debug!("arboard copy failed: {}, recreating clipboard", e);
-                    // If set_text fails, try recreating the clipboard
-                    drop(guard);
-                    if let Ok(mut guard) = SYSTEM_CLIPBOARD.lock() {
-                        if let Ok(new_clipboard) = arboard::Clipboard::new() {
-                            *guard = Some(new_clipboard);
-                            if let Some(cb) = guard.as_mut() {
-                                let _ = cb.set_text(&text);
-                            }
-                        }
-                    }
-                }
-            }
+    /// Add killed text to the most recent kill ring entry, after it or
+    /// (for backward kills) before it
+    ///
+    /// Only the kill ring is updated; [`Self::sync_system`] copies the combined
+    /// entry to the system clipboard once the kills in a row are done.
+    pub fn append_kill(&mut self, text: &str, before: bool) {
+        let combined = match self.ring.pop_front() {
+            Some(last) if before => format!("{}{}", text, last),
+            Some(last) => format!("{}{}", last, text),
+            None => text.to_string(),
+        };
+        self.push_ring(&combined);
+        self.pieces = None;
+        self.internal = combined;
+        self.system_outdated = true;
+    }
+
+    /// Copy the internal clipboard to the system clipboard if it changed since
+    pub fn sync_system(&mut self) {
+        if std::mem::take(&mut self.system_outdated) {
+            copy_to_system(&self.internal);
         }
     }
 
+    /// Move `text` to the front of the kill ring
+    fn push_ring(&mut self, text: &str) {
+        self.ring.retain(|entry| entry != text);
+        self.ring.push_front(text.to_string());
+        self.ring.truncate(KILL_RING_MAX);
+    }
+
+    /// Copy text to both internal and system clipboard
+    pub fn copy(&mut self, text: String) {
+        self.push_ring(&text);
+        self.pieces = None;
+        copy_to_system(&text);
+        self.internal = text;
+        self.system_outdated = false;
+    }
+
     /// Get text from clipboard, preferring system clipboard
     ///
     /// Tries system clipboard first, falls back to internal clipboard
     pub fn paste(&mut self) -> Option<String> {
+        // The system clipboard must not look like another application's copy
+        self.sync_system();
+
         // Try arboard crate via the static clipboard (reads from system clipboard)
         if let Ok(mut guard) = SYSTEM_CLIPBOARD.lock() {
             // Create clipboard if it doesn't exist yet
@@ -99,7 +121,12 @@ impl Clipboard {
             if let Some(clipboard) = guard.as_mut() {
                 if let Ok(text) = clipboard.get_text() {
                     if !text.is_empty() {
-                        self.internal = text.clone();
+                        if text != self.internal {
+                            // Copied in another application
+                            self.push_ring(&text);
+                            self.pieces = None;
+                            self.internal = text.clone();
+                        }
                         return Some(text);
                     }
                 }
@@ -114,6 +141,48 @@ impl Clipboard {
         }
     }
 
+    /// Get clipboard text split into one piece per cursor
+    ///
+    /// Returns the pieces of the last multi-cursor copy when there are `cursors`
+    /// of them and the clipboard still holds that copy.
+    pub fn paste_pieces(&mut self, cursors: usize) -> Option<Vec<String>> {
+        self.paste()?;
+        self.pieces.clone().filter(|pieces| pieces.len() == cursors)
+    }
+
+    /// Kill ring entry `index` (0 is the most recent)
+    pub fn ring_entry(&self, index: usize) -> Option<&str> {
+        self.ring.get(index).map(String::as_str)
+    }
+
+    /// Number of entries in the kill ring
+    pub fn ring_len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Store text in a named register; an uppercase name appends to the lowercase register
+    ///
+    /// Returns false for names other than letters.
+    pub fn set_register(&mut self, name: char, text: &str) -> bool {
+        if !name.is_ascii_alphabetic() {
+            return false;
+        }
+        let register = self.registers.entry(name.to_ascii_lowercase()).or_default();
+        if name.is_ascii_uppercase() {
+            register.push_str(text);
+        } else {
+            *register = text.to_string();
+        }
+        true
+    }
+
+    /// Text of a named register (either case)
+    pub fn register(&self, name: char) -> Option<&str> {
+        self.registers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
     /// Get the internal clipboard content without checking system clipboard
     pub fn get_internal(&self) -> &str {
         &self.internal
@@ -149,6 +218,57 @@ impl Clipboard {
     }
 }
 
+/// Copy text to the system clipboard
+///
+/// Tries multiple methods to maximize compatibility:
+/// 1. OSC 52 escape sequence (works in Konsole, Kitty, Alacritty, Wezterm, xterm, iTerm2)
+/// 2. arboard crate (works via X11/Wayland APIs in Gnome Console, XFCE Terminal, etc.)
+fn copy_to_system(text: &str) {
+    // Try OSC 52 first (works in modern terminals)
+    // Note: This doesn't "fail" in a detectable way - it just sends escape sequences
+    // that the terminal may or may not handle
+    let osc52_result = execute!(stdout(), CopyToClipboard::to_clipboard_from(text));
+    if let Err(e) = &osc52_result {
+        tracing::debug!("Crossterm OSC 52 clipboard copy failed: {}", e);
+    }
+    // Ensure the escape sequence is flushed to the terminal
+    let _ = stdout().flush();
+
+    // Also try arboard (works via X11/Wayland in terminals without OSC 52 support)
+    // This provides coverage for Gnome Console, XFCE Terminal, and similar
+    //
+    // Important: On X11, the clipboard owner must stay alive to respond to paste requests.
+    // We store the clipboard in a static so it lives for the application lifetime.
+    if let Ok(mut guard) = SYSTEM_CLIPBOARD.lock() {
+        // Create clipboard if it doesn't exist yet
+        if guard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(cb) => *guard = Some(cb),
+                Err(e) => {
+                    tracing::debug!("arboard clipboard init failed: {}", e);
+                }
+            }
+        }
+
+        // Try to set text on the clipboard
+        if let Some(clipboard) = guard.as_mut() {
+            if let Err(e) = clipboard.set_text(text) {
+                tracing::debug!("arboard copy failed: {}, recreating clipboard", e);
+                // If set_text fails, try recreating the clipboard
+                drop(guard);
+                if let Ok(mut guard) = SYSTEM_CLIPBOARD.lock() {
+                    if let Ok(new_clipboard) = arboard::Clipboard::new() {
+                        *guard = Some(new_clipboard);
+                        if let Some(cb) = guard.as_mut() {
+                            let _ = cb.set_text(text);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,4 +288,47 @@ mod tests {
         clipboard.copy("hello".to_string());
         assert_eq!(clipboard.get_internal(), "hello");
     }
+
+    #[test]
+    fn test_kill_ring() {
+        let mut clipboard = Clipboard::new();
+        clipboard.copy("one".to_string());
+        clipboard.copy("two".to_string());
+        clipboard.append_kill(" more", false);
+        clipboard.append_kill("before ", true);
+
+        assert_eq!(clipboard.get_internal(), "before two more");
+        assert_eq!(clipboard.ring_len(), 2);
+        assert_eq!(clipboard.ring_entry(0), Some("before two more"));
+        assert_eq!(clipboard.ring_entry(1), Some("one"));
+    }
+
+    #[test]
+    fn test_chained_kills_sync_system_clipboard_once() {
+        let mut clipboard = Clipboard::new();
+        clipboard.copy("one".to_string());
+        assert!(!clipboard.system_outdated);
+
+        clipboard.append_kill(" two", false);
+        clipboard.append_kill(" three", false);
+        assert!(clipboard.system_outdated);
+
+        clipboard.sync_system();
+        assert!(!clipboard.system_outdated);
+        assert_eq!(clipboard.get_internal(), "one two three");
+    }
+
+    #[test]
+    fn test_named_registers() {
+        let mut clipboard = Clipboard::new();
+        assert!(clipboard.set_register('a', "foo"));
+        assert!(clipboard.set_register('A', "bar"));
+        assert!(!clipboard.set_register('1', "baz"));
+
+        assert_eq!(clipboard.register('a'), Some("foobar"));
+        assert_eq!(clipboard.register('A'), Some("foobar"));
+        assert_eq!(clipboard.register('b'), None);
+        // Registers don't touch the clipboard
+        assert!(clipboard.get_internal().is_empty());
+    }
 }
@@ -0,0 +1,461 @@
+//! Debug adapter process and its configuration
+//!
+//! A `DapHandle` owns one debug adapter, spawned from a `DebugAdapterConfig`. Two
+//! Tokio tasks talk to it over stdio:
+//! - the writer numbers requests, remembers which editor request each `seq` belongs
+//!   to and writes them to the adapter's stdin
+//! - the reader parses the adapter's stdout and forwards responses and events to the
+//!   main loop as `AsyncMessage`s, declining any reverse request it sends
+//!
+//! Dropping the handle closes the command channel, which ends the writer task and
+//! kills the adapter.
+
+use crate::services::async_bridge::{AsyncBridge, AsyncMessage};
+use crate::services::dap::protocol::{DapMessage, DapRequest, DapResponse};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc;
+
+/// How a debug session reaches the program being debugged
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DebugRequest {
+    /// The adapter starts the program
+    #[default]
+    Launch,
+    /// The adapter connects to a program that is already running
+    Attach,
+}
+
+impl DebugRequest {
+    /// Command of the DAP request starting the session
+    pub fn command(self) -> &'static str {
+        match self {
+            DebugRequest::Launch => "launch",
+            DebugRequest::Attach => "attach",
+        }
+    }
+}
+
+/// Configuration for a debug adapter
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DebugAdapterConfig {
+    /// Command to spawn the adapter, which speaks DAP over stdio
+    pub command: String,
+
+    /// Arguments to pass to the adapter
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Whether to launch the program or attach to a running one
+    #[serde(default)]
+    pub request: DebugRequest,
+
+    /// Arguments of the launch or attach request, specific to the adapter
+    /// (e.g. `{"program": "${file}"}`). `${file}`, `${fileDirname}`,
+    /// `${fileBasenameNoExtension}`, `${workspaceFolder}` and
+    /// `${workspaceFolderBasename}` are replaced in strings.
+    #[serde(default)]
+    pub configuration: Value,
+
+    /// Adapter ID sent in the initialize request. Defaults to the command's file name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adapter_id: Option<String>,
+}
+
+impl DebugAdapterConfig {
+    /// Adapter ID sent in the initialize request
+    pub fn adapter_id(&self) -> String {
+        self.adapter_id.clone().unwrap_or_else(|| {
+            Path::new(&self.command)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.command.clone())
+        })
+    }
+
+    /// Arguments of the launch or attach request, with variables replaced
+    pub fn request_arguments(&self, file: Option<&Path>, workspace: &Path) -> Value {
+        let path_string = |path: &Path| path.to_string_lossy().to_string();
+        let file_name = |path: Option<&Path>| {
+            path.and_then(|path| path.file_stem())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        let variables = [
+            ("${file}", file.map(path_string).unwrap_or_default()),
+            (
+                "${fileDirname}",
+                file.and_then(Path::parent)
+                    .map(path_string)
+                    .unwrap_or_default(),
+            ),
+            ("${fileBasenameNoExtension}", file_name(file)),
+            ("${workspaceFolder}", path_string(workspace)),
+            (
+                "${workspaceFolderBasename}",
+                workspace
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            ),
+        ];
+        substitute_variables(&self.configuration, &variables)
+    }
+}
+
+/// Replace variables in every string of a JSON value
+fn substitute_variables(value: &Value, variables: &[(&str, String)]) -> Value {
+    match value {
+        Value::String(text) => {
+            let mut text = text.clone();
+            for (name, replacement) in variables {
+                text = text.replace(name, replacement);
+            }
+            Value::String(text)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_variables(item, variables))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, item)| (key.clone(), substitute_variables(item, variables)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Commands sent from the handle (and the reader) to the writer task
+#[derive(Debug)]
+enum DapCommand {
+    /// Send a request; its response is reported with `request_id`
+    Request {
+        request_id: u64,
+        command: String,
+        arguments: Value,
+    },
+    /// Decline a reverse request from the adapter
+    Decline { request_seq: i64, command: String },
+}
+
+/// Editor request IDs and commands of the requests awaiting a response, by `seq`
+type PendingRequests = Arc<Mutex<HashMap<i64, (u64, String)>>>;
+
+/// Handle to a running debug adapter
+pub struct DapHandle {
+    /// Channel for sending requests to the writer task
+    command_tx: mpsc::UnboundedSender<DapCommand>,
+
+    /// Process ID of the adapter
+    pid: Option<u32>,
+}
+
+impl DapHandle {
+    /// Spawn a debug adapter
+    ///
+    /// Its responses and events are reported as `DapResponse`, `DapEvent` and
+    /// `DapExited` messages tagged with `session`.
+    pub fn spawn(
+        runtime: &tokio::runtime::Handle,
+        config: &DebugAdapterConfig,
+        session: u64,
+        working_dir: &Path,
+        async_bridge: &AsyncBridge,
+    ) -> Result<Self, String> {
+        tracing::info!(
+            "Spawning debug adapter: {} {:?}",
+            config.command,
+            config.args
+        );
+        let stderr_log_path = std::env::temp_dir().join(format!(
+            "fresh-dap-{}-{}.log",
+            config.adapter_id(),
+            std::process::id()
+        ));
+        let stderr_file = std::fs::File::create(&stderr_log_path).map_err(|e| {
+            format!(
+                "Failed to create debug adapter log file {:?}: {}",
+                stderr_log_path, e
+            )
+        })?;
+
+        // Process handles need the runtime's reactor
+        let _guard = runtime.enter();
+        let mut process = Command::new(&config.command)
+            .args(&config.args)
+            .current_dir(working_dir)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::from(stderr_file))
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to spawn {}: {}", config.command, e))?;
+        let pid = process.id();
+        let stdin = process
+            .stdin
+            .take()
+            .ok_or_else(|| "Failed to get stdin".to_string())?;
+        let stdout = BufReader::new(
+            process
+                .stdout
+                .take()
+                .ok_or_else(|| "Failed to get stdout".to_string())?,
+        );
+
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let (decline_tx, decline_rx) = mpsc::unbounded_channel();
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+
+        runtime.spawn(run_writer(
+            process,
+            stdin,
+            command_rx,
+            decline_rx,
+            pending.clone(),
+        ));
+        runtime.spawn(run_reader(
+            stdout,
+            pending,
+            decline_tx,
+            async_bridge.sender(),
+            session,
+            stderr_log_path,
+        ));
+
+        Ok(Self { command_tx, pid })
+    }
+
+    /// Send a request; its response arrives as a `DapResponse` with `request_id`
+    pub fn request(&self, request_id: u64, command: &str, arguments: Value) -> Result<(), String> {
+        self.command_tx
+            .send(DapCommand::Request {
+                request_id,
+                command: command.to_string(),
+                arguments,
+            })
+            .map_err(|_| format!("Failed to send {} request", command))
+    }
+
+    /// Process ID of the adapter
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+}
+
+/// Write requests to the adapter until the handle is dropped
+async fn run_writer(
+    process: Child,
+    mut stdin: ChildStdin,
+    mut command_rx: mpsc::UnboundedReceiver<DapCommand>,
+    mut decline_rx: mpsc::UnboundedReceiver<DapCommand>,
+    pending: PendingRequests,
+) {
+    let mut seq = 0;
+    loop {
+        let command = tokio::select! {
+            command = command_rx.recv() => match command {
+                Some(command) => command,
+                None => break,
+            },
+            Some(command) = decline_rx.recv() => command,
+        };
+        seq += 1;
+        let message = match command {
+            DapCommand::Request {
+                request_id,
+                command,
+                arguments,
+            } => {
+                pending
+                    .lock()
+                    .unwrap()
+                    .insert(seq, (request_id, command.clone()));
+                DapMessage::Request(DapRequest {
+                    seq,
+                    command,
+                    arguments,
+                })
+            }
+            DapCommand::Decline {
+                request_seq,
+                command,
+            } => DapMessage::Response(DapResponse {
+                seq,
+                request_seq,
+                success: false,
+                message: Some(format!("{} is not supported", command)),
+                command,
+                body: Value::Null,
+            }),
+        };
+        if let Err(e) = write_message(&mut stdin, &message).await {
+            tracing::warn!("Failed to write to debug adapter: {}", e);
+            break;
+        }
+    }
+    // Dropping the process kills the adapter
+    drop(process);
+    tracing::info!("Debug adapter writer task exiting");
+}
+
+/// Forward the adapter's responses and events to the main loop until it exits
+async fn run_reader<R: AsyncBufRead + Unpin>(
+    mut stdout: R,
+    pending: PendingRequests,
+    decline_tx: mpsc::UnboundedSender<DapCommand>,
+    async_tx: std_mpsc::Sender<AsyncMessage>,
+    session: u64,
+    stderr_log_path: PathBuf,
+) {
+    let error = loop {
+        let message = match read_message(&mut stdout).await {
+            Ok(message) => message,
+            Err(e) => break e,
+        };
+        tracing::trace!("Received debug adapter message: {:?}", message);
+        match message {
+            DapMessage::Response(response) => {
+                let Some((request_id, command)) =
+                    pending.lock().unwrap().remove(&response.request_seq)
+                else {
+                    continue;
+                };
+                let result = if response.success {
+                    Ok(response.body)
+                } else {
+                    Err(response.message.unwrap_or_else(|| command.clone()))
+                };
+                let _ = async_tx.send(AsyncMessage::DapResponse {
+                    session,
+                    request_id,
+                    command,
+                    result,
+                });
+            }
+            DapMessage::Event(event) => {
+                let _ = async_tx.send(AsyncMessage::DapEvent {
+                    session,
+                    event: event.event,
+                    body: event.body,
+                });
+            }
+            DapMessage::Request(request) => {
+                tracing::debug!("Declining reverse request: {}", request.command);
+                let _ = decline_tx.send(DapCommand::Decline {
+                    request_seq: request.seq,
+                    command: request.command,
+                });
+            }
+        }
+    };
+    tracing::info!("Debug adapter reader task exiting: {}", error);
+    let _ = async_tx.send(AsyncMessage::DapExited {
+        session,
+        error: format!("{} (stderr: {})", error, stderr_log_path.display()),
+    });
+}
+
+async fn write_message(stdin: &mut ChildStdin, message: &DapMessage) -> Result<(), String> {
+    let json = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    tracing::trace!("Sending debug adapter message: {}", json);
+    let content = format!("Content-Length: {}\r\n\r\n{}", json.len(), json);
+    stdin
+        .write_all(content.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+    stdin
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to flush stdin: {}", e))
+}
+
+async fn read_message<R: AsyncBufRead + Unpin>(stdout: &mut R) -> Result<DapMessage, String> {
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut line = String::new();
+        let bytes_read = stdout
+            .read_line(&mut line)
+            .await
+            .map_err(|e| format!("Failed to read from stdout: {}", e))?;
+        if bytes_read == 0 {
+            return Err("debug adapter closed stdout (EOF)".to_string());
+        }
+        if line == "\r\n" {
+            break;
+        }
+        if let Some(length) = line.strip_prefix("Content-Length: ") {
+            content_length = Some(
+                length
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("Invalid Content-Length: {}", e))?,
+            );
+        }
+    }
+    let content_length =
+        content_length.ok_or_else(|| "Missing Content-Length header".to_string())?;
+
+    let mut content = vec![0u8; content_length];
+    stdout
+        .read_exact(&mut content)
+        .await
+        .map_err(|e| format!("Failed to read content: {}", e))?;
+    serde_json::from_slice(&content).map_err(|e| format!("Failed to deserialize message: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_arguments_substitute_variables() {
+        let config = DebugAdapterConfig {
+            command: "/usr/bin/lldb-dap".to_string(),
+            args: vec![],
+            request: DebugRequest::Launch,
+            configuration: serde_json::json!({
+                "program": "${workspaceFolder}/target/debug/${workspaceFolderBasename}",
+                "args": ["${file}", "${fileBasenameNoExtension}"],
+                "cwd": "${fileDirname}",
+                "stopOnEntry": false,
+            }),
+            adapter_id: None,
+        };
+        let arguments = config.request_arguments(
+            Some(Path::new("/work/demo/src/main.rs")),
+            Path::new("/work/demo"),
+        );
+        assert_eq!(arguments["program"], "/work/demo/target/debug/demo");
+        assert_eq!(
+            arguments["args"],
+            serde_json::json!(["/work/demo/src/main.rs", "main"])
+        );
+        assert_eq!(arguments["cwd"], "/work/demo/src");
+        assert_eq!(arguments["stopOnEntry"], false);
+        assert_eq!(config.adapter_id(), "lldb-dap");
+    }
+
+    #[tokio::test]
+    async fn test_read_message_parses_framed_event() {
+        let body = r#"{"seq":1,"type":"event","event":"initialized"}"#;
+        let framed = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let mut reader = BufReader::new(framed.as_bytes());
+        let message = read_message(&mut reader).await.unwrap();
+        let DapMessage::Event(event) = message else {
+            panic!("expected an event");
+        };
+        assert_eq!(event.event, "initialized");
+        assert!(read_message(&mut reader).await.is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Debug Adapter Protocol messages
+//!
+//! Adapters exchange requests, responses and events framed like LSP messages
+//! (a `Content-Length` header, then JSON), but without JSON-RPC: every message has a
+//! `seq` number and a `type`, and responses point back at the `request_seq` they
+//! answer. Only the parts of the bodies the editor reads are modelled here.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A message exchanged with a debug adapter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DapMessage {
+    Request(DapRequest),
+    Response(DapResponse),
+    Event(DapEvent),
+}
+
+/// A request, sent by the editor or (for "reverse requests") by the adapter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DapRequest {
+    pub seq: i64,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub arguments: Value,
+}
+
+/// The answer to a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DapResponse {
+    pub seq: i64,
+    pub request_seq: i64,
+    pub success: bool,
+    pub command: String,
+    /// Short error reason when `success` is false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub body: Value,
+}
+
+/// An event sent by the adapter (stopped, continued, output, terminated, ...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DapEvent {
+    pub seq: i64,
+    pub event: String,
+    #[serde(default)]
+    pub body: Value,
+}
+
+/// A source file, as referenced by stack frames and breakpoints
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// A frame of a stopped thread's call stack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub source: Option<Source>,
+    /// 1-based line (the editor asks for `linesStartAt1`)
+    pub line: i64,
+    /// 1-based column
+    #[serde(default)]
+    pub column: i64,
+}
+
+/// A group of variables of a frame (locals, arguments, globals, ...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    pub name: String,
+    pub variables_reference: i64,
+    /// Whether fetching the scope's variables is slow, so it shouldn't be done eagerly
+    #[serde(default)]
+    pub expensive: bool,
+}
+
+/// A variable, or a child of a structured variable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
+    /// Non-zero when the variable has children, which are fetched with this reference
+    #[serde(default)]
+    pub variables_reference: i64,
+}
+
+/// A breakpoint as the adapter placed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub verified: bool,
+    #[serde(default)]
+    pub line: Option<i64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Body of the `stopped` event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoppedEvent {
+    /// Why the thread stopped ("breakpoint", "step", "pause", "exception", ...)
+    pub reason: String,
+    #[serde(default)]
+    pub thread_id: Option<i64>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// Arguments of the `initialize` request
+pub fn initialize_arguments(adapter_id: &str) -> Value {
+    serde_json::json!({
+        "clientID": "fresh",
+        "clientName": "Fresh",
+        "adapterID": adapter_id,
+        "pathFormat": "path",
+        "linesStartAt1": true,
+        "columnsStartAt1": true,
+        "supportsVariableType": true,
+        "supportsRunInTerminalRequest": false,
+    })
+}
+
+/// Arguments of a `setBreakpoints` request for the 0-based `lines` of a file
+pub fn set_breakpoints_arguments(path: &str, lines: &[usize]) -> Value {
+    let breakpoints: Vec<Value> = lines
+        .iter()
+        .map(|line| serde_json::json!({ "line": line + 1 }))
+        .collect();
+    serde_json::json!({
+        "source": { "path": path },
+        "breakpoints": breakpoints,
+        "lines": lines.iter().map(|line| line + 1).collect::<Vec<_>>(),
+    })
+}
+
+/// Parse a field of a response or event body, if present and well formed
+pub fn body_field<T: for<'de> Deserialize<'de>>(body: &Value, field: &str) -> Option<T> {
+    body.get(field)
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_type_tag() {
+        let request = DapMessage::Request(DapRequest {
+            seq: 1,
+            command: "initialize".to_string(),
+            arguments: serde_json::json!({"adapterID": "fake"}),
+        });
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["type"], "request");
+        assert_eq!(json["command"], "initialize");
+
+        let event: DapMessage = serde_json::from_str(
+            r#"{"seq":4,"type":"event","event":"stopped","body":{"reason":"breakpoint","threadId":1}}"#,
+        )
+        .unwrap();
+        let DapMessage::Event(event) = event else {
+            panic!("expected an event");
+        };
+        let stopped: StoppedEvent = serde_json::from_value(event.body).unwrap();
+        assert_eq!(stopped.reason, "breakpoint");
+        assert_eq!(stopped.thread_id, Some(1));
+
+        let response: DapMessage = serde_json::from_str(
+            r#"{"seq":5,"type":"response","request_seq":2,"success":false,"command":"launch","message":"no program"}"#,
+        )
+        .unwrap();
+        let DapMessage::Response(response) = response else {
+            panic!("expected a response");
+        };
+        assert!(!response.success);
+        assert_eq!(response.message.as_deref(), Some("no program"));
+        assert!(response.body.is_null());
+    }
+
+    #[test]
+    fn test_set_breakpoints_arguments_are_one_based() {
+        let arguments = set_breakpoints_arguments("/tmp/main.py", &[0, 9]);
+        assert_eq!(arguments["source"]["path"], "/tmp/main.py");
+        assert_eq!(arguments["breakpoints"][0]["line"], 1);
+        assert_eq!(arguments["breakpoints"][1]["line"], 10);
+        assert_eq!(arguments["lines"], serde_json::json!([1, 10]));
+    }
+}
@@ -14,17 +14,21 @@
 use crate::services::async_bridge::{
     AsyncBridge, AsyncMessage, LspMessageType, LspProgressValue, LspServerStatus,
 };
+use crate::services::lsp::client::LspFeature;
 use crate::services::process_limits::ProcessLimits;
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument, Initialized, Notification,
-        PublishDiagnostics,
+        DidChangeConfiguration, DidChangeTextDocument, DidChangeWatchedFiles,
+        DidChangeWorkspaceFolders, DidCloseTextDocument, DidOpenTextDocument, DidRenameFiles,
+        DidSaveTextDocument, Initialized, Notification, PublishDiagnostics, SetTrace,
+        WillSaveTextDocument,
     },
-    request::{Initialize, Request, Shutdown},
-    ClientCapabilities, DidChangeTextDocumentParams, DidOpenTextDocumentParams,
-    DidSaveTextDocumentParams, InitializeParams, InitializeResult, InitializedParams,
-    PublishDiagnosticsParams, ServerCapabilities, TextDocumentContentChangeEvent,
-    TextDocumentIdentifier, TextDocumentItem, Uri, VersionedTextDocumentIdentifier,
+    request::{Initialize, Request, Shutdown, WillRenameFiles, WillSaveWaitUntil},
+    ClientCapabilities, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, InitializeParams, InitializeResult,
+    InitializedParams, PublishDiagnosticsParams, ServerCapabilities,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentSyncCapability, Uri, VersionedTextDocumentIdentifier, WillSaveTextDocumentParams,
     WindowClientCapabilities, WorkspaceFolder,
 };
 use serde::{Deserialize, Serialize};
@@ -108,6 +112,175 @@ pub struct JsonRpcError {
     pub data: Option<Value>,
 }
 
+/// Which flavour of `textDocument/semanticTokens` to request
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticTokensRequest {
+    /// Tokens for the whole document (`/full`)
+    Full,
+    /// Changes since a previous full result (`/full/delta`)
+    FullDelta { previous_result_id: String },
+    /// Tokens for part of the document (`/range`), typically the viewport
+    Range(lsp_types::Range),
+}
+
+/// Which flavour of formatting to request
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormattingRequest {
+    /// The whole document (`textDocument/formatting`)
+    Document,
+    /// Part of the document (`textDocument/rangeFormatting`)
+    Range(lsp_types::Range),
+    /// After `ch` was typed, with the cursor at `position` (`textDocument/onTypeFormatting`)
+    OnType {
+        position: lsp_types::Position,
+        ch: String,
+    },
+}
+
+/// Which location of the symbol at the cursor to look up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GotoTarget {
+    /// Where the symbol is defined (`textDocument/definition`)
+    Definition,
+    /// Where the symbol is declared (`textDocument/declaration`)
+    Declaration,
+    /// Where the symbol's type is defined (`textDocument/typeDefinition`)
+    TypeDefinition,
+    /// Implementations of a trait, interface or method (`textDocument/implementation`)
+    Implementation,
+}
+
+impl GotoTarget {
+    /// The LSP request method
+    pub fn method(self) -> &'static str {
+        match self {
+            Self::Definition => "textDocument/definition",
+            Self::Declaration => "textDocument/declaration",
+            Self::TypeDefinition => "textDocument/typeDefinition",
+            Self::Implementation => "textDocument/implementation",
+        }
+    }
+
+    /// The feature used to route requests to a server
+    pub fn feature(self) -> LspFeature {
+        match self {
+            Self::Definition => LspFeature::Definition,
+            Self::Declaration => LspFeature::Declaration,
+            Self::TypeDefinition => LspFeature::TypeDefinition,
+            Self::Implementation => LspFeature::Implementation,
+        }
+    }
+
+    /// Human-readable name, e.g. for status messages
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Definition => "definition",
+            Self::Declaration => "declaration",
+            Self::TypeDefinition => "type definition",
+            Self::Implementation => "implementation",
+        }
+    }
+
+    /// Title of the picker shown when there are several locations
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Definition => "Definitions",
+            Self::Declaration => "Declarations",
+            Self::TypeDefinition => "Type Definitions",
+            Self::Implementation => "Implementations",
+        }
+    }
+}
+
+/// Which relation of a call or type hierarchy to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyDirection {
+    /// Functions that call the item (`callHierarchy/incomingCalls`)
+    IncomingCalls,
+    /// Functions the item calls (`callHierarchy/outgoingCalls`)
+    OutgoingCalls,
+    /// Types the item extends or implements (`typeHierarchy/supertypes`)
+    Supertypes,
+    /// Types that extend or implement the item (`typeHierarchy/subtypes`)
+    Subtypes,
+}
+
+impl HierarchyDirection {
+    /// Whether this is a call hierarchy (as opposed to a type hierarchy)
+    pub fn is_call(self) -> bool {
+        matches!(self, Self::IncomingCalls | Self::OutgoingCalls)
+    }
+
+    /// The feature used to route requests to a server
+    pub fn feature(self) -> LspFeature {
+        if self.is_call() {
+            LspFeature::CallHierarchy
+        } else {
+            LspFeature::TypeHierarchy
+        }
+    }
+
+    /// Human-readable title, e.g. for the results buffer
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::IncomingCalls => "Incoming Calls",
+            Self::OutgoingCalls => "Outgoing Calls",
+            Self::Supertypes => "Supertypes",
+            Self::Subtypes => "Subtypes",
+        }
+    }
+}
+
+/// An item of a call or type hierarchy, as returned by the server
+///
+/// Items are sent back unchanged when expanding them, so servers can keep their
+/// own state in the `data` field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HierarchyItem {
+    Call(lsp_types::CallHierarchyItem),
+    Type(lsp_types::TypeHierarchyItem),
+}
+
+impl HierarchyItem {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Call(item) => &item.name,
+            Self::Type(item) => &item.name,
+        }
+    }
+
+    pub fn kind(&self) -> lsp_types::SymbolKind {
+        match self {
+            Self::Call(item) => item.kind,
+            Self::Type(item) => item.kind,
+        }
+    }
+
+    pub fn uri(&self) -> &Uri {
+        match self {
+            Self::Call(item) => &item.uri,
+            Self::Type(item) => &item.uri,
+        }
+    }
+
+    /// Range of the item's name
+    pub fn selection_range(&self) -> lsp_types::Range {
+        match self {
+            Self::Call(item) => item.selection_range,
+            Self::Type(item) => item.selection_range,
+        }
+    }
+}
+
+/// An item related to the one being expanded
+#[derive(Debug, Clone, PartialEq)]
+pub struct HierarchyEntry {
+    pub item: HierarchyItem,
+    /// Call sites: in `item` for incoming calls, in the expanded item for outgoing
+    /// calls. Empty for prepare results and type hierarchies.
+    pub from_ranges: Vec<lsp_types::Range>,
+}
+
 /// LSP client state machine
 ///
 /// Tracks the lifecycle of the LSP client connection with proper state transitions.
@@ -196,11 +369,99 @@ impl LspClientState {
     }
 }
 
+/// `CompletionList.itemDefaults` properties applied by `apply_completion_item_defaults`
+const COMPLETION_ITEM_DEFAULTS: [&str; 4] =
+    ["commitCharacters", "editRange", "insertTextFormat", "data"];
+
+/// Parse a `textDocument/completion` result into its items and `isIncomplete` flag
+///
+/// The result is either a plain item array or a `CompletionList`, whose `itemDefaults`
+/// are filled into the items that don't set those properties themselves.
+fn parse_completion_result(result: Value) -> (Vec<lsp_types::CompletionItem>, bool) {
+    if result.is_array() {
+        let items = serde_json::from_value(result).unwrap_or_default();
+        return (items, false);
+    }
+    let Ok(list) = serde_json::from_value::<lsp_types::CompletionList>(result.clone()) else {
+        return (Vec::new(), false);
+    };
+    let mut items = list.items;
+    if let Some(defaults) = result.get("itemDefaults") {
+        apply_completion_item_defaults(defaults, &mut items);
+    }
+    (items, list.is_incomplete)
+}
+
+/// Fill `CompletionList.itemDefaults` into items that don't override them
+fn apply_completion_item_defaults(defaults: &Value, items: &mut [lsp_types::CompletionItem]) {
+    use lsp_types::{CompletionTextEdit, InsertReplaceEdit, InsertTextFormat, Range, TextEdit};
+
+    let commit_characters: Option<Vec<String>> = defaults
+        .get("commitCharacters")
+        .and_then(|value| serde_json::from_value(value.clone()).ok());
+    let insert_text_format: Option<InsertTextFormat> = defaults
+        .get("insertTextFormat")
+        .and_then(|value| serde_json::from_value(value.clone()).ok());
+    let data = defaults.get("data");
+    // Either a plain range or separate insert and replace ranges
+    let edit_range = defaults.get("editRange");
+    let range: Option<Range> =
+        edit_range.and_then(|value| serde_json::from_value(value.clone()).ok());
+    let insert_replace: Option<(Range, Range)> = edit_range.and_then(|value| {
+        let insert = serde_json::from_value(value.get("insert")?.clone()).ok()?;
+        let replace = serde_json::from_value(value.get("replace")?.clone()).ok()?;
+        Some((insert, replace))
+    });
+
+    for item in items {
+        if item.commit_characters.is_none() {
+            item.commit_characters = commit_characters.clone();
+        }
+        if item.insert_text_format.is_none() {
+            item.insert_text_format = insert_text_format;
+        }
+        if item.data.is_none() {
+            item.data = data.cloned();
+        }
+        if item.text_edit.is_none() {
+            let new_text = item
+                .insert_text
+                .clone()
+                .unwrap_or_else(|| item.label.clone());
+            item.text_edit = match (range, insert_replace) {
+                (Some(range), _) => Some(CompletionTextEdit::Edit(TextEdit { range, new_text })),
+                (None, Some((insert, replace))) => {
+                    Some(CompletionTextEdit::InsertAndReplace(InsertReplaceEdit {
+                        new_text,
+                        insert,
+                        replace,
+                    }))
+                }
+                (None, None) => None,
+            };
+        }
+    }
+}
+
 /// Create common LSP client capabilities with workDoneProgress support
 fn create_client_capabilities() -> ClientCapabilities {
+    use crate::primitives::semantic_tokens::{TOKEN_MODIFIERS, TOKEN_TYPES};
     use lsp_types::{
-        GeneralClientCapabilities, RenameClientCapabilities, TextDocumentClientCapabilities,
+        CallHierarchyClientCapabilities, CodeActionCapabilityResolveSupport,
+        CodeActionClientCapabilities, CodeActionKind, CodeActionKindLiteralSupport,
+        CodeActionLiteralSupport, CodeLensClientCapabilities, CodeLensWorkspaceClientCapabilities,
+        CompletionClientCapabilities, CompletionItemCapability,
+        CompletionItemCapabilityResolveSupport, CompletionListCapability,
+        DidChangeWatchedFilesClientCapabilities, DocumentFormattingClientCapabilities,
+        DocumentOnTypeFormattingClientCapabilities, DocumentRangeFormattingClientCapabilities,
+        DocumentSymbolClientCapabilities, DynamicRegistrationClientCapabilities,
+        FoldingRangeClientCapabilities, GeneralClientCapabilities, GotoCapability, MarkupKind,
+        RenameClientCapabilities, ResourceOperationKind, SemanticTokenModifier, SemanticTokenType,
+        SemanticTokensClientCapabilities, SemanticTokensClientCapabilitiesRequests,
+        SemanticTokensFullOptions, TextDocumentClientCapabilities,
+        TextDocumentSyncClientCapabilities, TokenFormat, TypeHierarchyClientCapabilities,
         WorkspaceClientCapabilities, WorkspaceEditClientCapabilities,
+        WorkspaceFileOperationsClientCapabilities, WorkspaceSymbolClientCapabilities,
     };
 
     ClientCapabilities {
@@ -210,19 +471,164 @@ fn create_client_capabilities() -> ClientCapabilities {
         }),
         workspace: Some(WorkspaceClientCapabilities {
             apply_edit: Some(true),
+            execute_command: Some(DynamicRegistrationClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            code_lens: Some(CodeLensWorkspaceClientCapabilities {
+                refresh_support: Some(true),
+            }),
             workspace_edit: Some(WorkspaceEditClientCapabilities {
                 document_changes: Some(true),
+                resource_operations: Some(vec![
+                    ResourceOperationKind::Create,
+                    ResourceOperationKind::Rename,
+                    ResourceOperationKind::Delete,
+                ]),
+                ..Default::default()
+            }),
+            symbol: Some(WorkspaceSymbolClientCapabilities {
+                dynamic_registration: Some(true),
+                ..Default::default()
+            }),
+            did_change_watched_files: Some(DidChangeWatchedFilesClientCapabilities {
+                dynamic_registration: Some(true),
+                relative_pattern_support: Some(true),
+            }),
+            file_operations: Some(WorkspaceFileOperationsClientCapabilities {
+                will_rename: Some(true),
+                did_rename: Some(true),
                 ..Default::default()
             }),
+            workspace_folders: Some(true),
+            configuration: Some(true),
+            did_change_configuration: Some(DynamicRegistrationClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
             ..Default::default()
         }),
         text_document: Some(TextDocumentClientCapabilities {
+            synchronization: Some(TextDocumentSyncClientCapabilities {
+                will_save: Some(true),
+                will_save_wait_until: Some(true),
+                did_save: Some(true),
+                ..Default::default()
+            }),
+            completion: Some(CompletionClientCapabilities {
+                completion_item: Some(CompletionItemCapability {
+                    snippet_support: Some(true),
+                    commit_characters_support: Some(true),
+                    documentation_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                    insert_replace_support: Some(true),
+                    resolve_support: Some(CompletionItemCapabilityResolveSupport {
+                        properties: vec![
+                            "documentation".to_string(),
+                            "detail".to_string(),
+                            "additionalTextEdits".to_string(),
+                        ],
+                    }),
+                    ..Default::default()
+                }),
+                context_support: Some(true),
+                completion_list: Some(CompletionListCapability {
+                    item_defaults: Some(
+                        COMPLETION_ITEM_DEFAULTS
+                            .iter()
+                            .map(|name| name.to_string())
+                            .collect(),
+                    ),
+                }),
+                ..Default::default()
+            }),
+            code_action: Some(CodeActionClientCapabilities {
+                code_action_literal_support: Some(CodeActionLiteralSupport {
+                    code_action_kind: CodeActionKindLiteralSupport {
+                        value_set: [
+                            CodeActionKind::EMPTY,
+                            CodeActionKind::QUICKFIX,
+                            CodeActionKind::REFACTOR,
+                            CodeActionKind::REFACTOR_EXTRACT,
+                            CodeActionKind::REFACTOR_INLINE,
+                            CodeActionKind::REFACTOR_REWRITE,
+                            CodeActionKind::SOURCE,
+                            CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+                        ]
+                        .into_iter()
+                        .map(|kind| kind.as_str().to_string())
+                        .collect(),
+                    },
+                }),
+                is_preferred_support: Some(true),
+                disabled_support: Some(true),
+                data_support: Some(true),
+                resolve_support: Some(CodeActionCapabilityResolveSupport {
+                    properties: vec!["edit".to_string()],
+                }),
+                ..Default::default()
+            }),
+            definition: Some(GotoCapability {
+                link_support: Some(true),
+                ..Default::default()
+            }),
+            declaration: Some(GotoCapability {
+                link_support: Some(true),
+                ..Default::default()
+            }),
+            type_definition: Some(GotoCapability {
+                link_support: Some(true),
+                ..Default::default()
+            }),
+            implementation: Some(GotoCapability {
+                link_support: Some(true),
+                ..Default::default()
+            }),
             rename: Some(RenameClientCapabilities {
                 dynamic_registration: Some(true),
                 prepare_support: Some(true),
                 honors_change_annotations: Some(true),
                 ..Default::default()
             }),
+            formatting: Some(DocumentFormattingClientCapabilities {
+                dynamic_registration: Some(true),
+            }),
+            range_formatting: Some(DocumentRangeFormattingClientCapabilities {
+                dynamic_registration: Some(true),
+            }),
+            on_type_formatting: Some(DocumentOnTypeFormattingClientCapabilities {
+                dynamic_registration: Some(true),
+            }),
+            document_symbol: Some(DocumentSymbolClientCapabilities {
+                hierarchical_document_symbol_support: Some(true),
+                ..Default::default()
+            }),
+            call_hierarchy: Some(CallHierarchyClientCapabilities::default()),
+            type_hierarchy: Some(TypeHierarchyClientCapabilities::default()),
+            folding_range: Some(FoldingRangeClientCapabilities {
+                line_folding_only: Some(true),
+                ..Default::default()
+            }),
+            code_lens: Some(CodeLensClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            semantic_tokens: Some(SemanticTokensClientCapabilities {
+                dynamic_registration: Some(true),
+                requests: SemanticTokensClientCapabilitiesRequests {
+                    range: Some(true),
+                    full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                },
+                token_types: TOKEN_TYPES
+                    .iter()
+                    .map(|name| SemanticTokenType::new(name))
+                    .collect(),
+                token_modifiers: TOKEN_MODIFIERS
+                    .iter()
+                    .map(|name| SemanticTokenModifier::new(name))
+                    .collect(),
+                formats: vec![TokenFormat::RELATIVE],
+                overlapping_token_support: Some(false),
+                multiline_token_support: Some(false),
+                server_cancel_support: Some(true),
+                augments_syntax_tokens: Some(true),
+            }),
             ..Default::default()
         }),
         general: Some(GeneralClientCapabilities {
@@ -236,6 +642,81 @@ fn create_client_capabilities() -> ClientCapabilities {
     }
 }
 
+/// Build the formatting options sent with formatting requests
+fn formatting_options(tab_size: u32, insert_spaces: bool) -> lsp_types::FormattingOptions {
+    lsp_types::FormattingOptions {
+        tab_size,
+        insert_spaces,
+        ..Default::default()
+    }
+}
+
+/// Convert a supertypes or subtypes response
+fn type_hierarchy_entries(items: Option<Vec<lsp_types::TypeHierarchyItem>>) -> Vec<HierarchyEntry> {
+    items
+        .unwrap_or_default()
+        .into_iter()
+        .map(|item| HierarchyEntry {
+            item: HierarchyItem::Type(item),
+            from_ranges: Vec::new(),
+        })
+        .collect()
+}
+
+/// A workspace folder named after its directory
+fn workspace_folder(uri: &Uri) -> WorkspaceFolder {
+    WorkspaceFolder {
+        uri: uri.clone(),
+        name: uri
+            .path()
+            .as_str()
+            .split('/')
+            .next_back()
+            .unwrap_or("workspace")
+            .to_string(),
+    }
+}
+
+/// Whether a server accepts `workspace/didChangeWorkspaceFolders`
+fn supports_workspace_folder_changes(caps: &ServerCapabilities) -> bool {
+    caps.workspace
+        .as_ref()
+        .and_then(|workspace| workspace.workspace_folders.as_ref())
+        .and_then(|folders| folders.change_notifications.as_ref())
+        .is_some_and(|notifications| match notifications {
+            lsp_types::OneOf::Left(enabled) => *enabled,
+            lsp_types::OneOf::Right(_) => true,
+        })
+}
+
+/// Answer a `workspace/configuration` request from the configured settings
+///
+/// Each item's `section` is a dotted path into the settings (the whole settings when
+/// it has none); sections that aren't configured are answered with `null`.
+fn configuration_response(settings: Option<&Value>, params: Option<&Value>) -> Value {
+    let items = params
+        .and_then(|params| params.get("items"))
+        .and_then(|items| items.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let values = items
+        .iter()
+        .map(|item| {
+            let Some(settings) = settings else {
+                return Value::Null;
+            };
+            let section = item.get("section").and_then(|section| section.as_str());
+            section
+                .into_iter()
+                .flat_map(|section| section.split('.'))
+                .try_fold(settings, |value, key| value.get(key))
+                .cloned()
+                .unwrap_or(Value::Null)
+        })
+        .collect();
+    Value::Array(values)
+}
+
 /// Commands sent from the main loop to the LSP task
 #[derive(Debug)]
 enum LspCommand {
@@ -262,17 +743,63 @@ enum LspCommand {
     /// Notify document saved
     DidSave { uri: Uri, text: Option<String> },
 
+    /// Notify document closed
+    DidClose { uri: Uri },
+
+    /// Notify document about to be saved
+    WillSave {
+        uri: Uri,
+        reason: lsp_types::TextDocumentSaveReason,
+    },
+
+    /// Request edits to apply before the document is saved
+    WillSaveWaitUntil {
+        request_id: u64,
+        uri: Uri,
+        reason: lsp_types::TextDocumentSaveReason,
+    },
+
+    /// Notify changes to files matching the server's registered watchers
+    DidChangeWatchedFiles { changes: Vec<lsp_types::FileEvent> },
+
+    /// Request edits to apply before files are renamed
+    WillRenameFiles {
+        files: Vec<lsp_types::FileRename>,
+        response: oneshot::Sender<Result<Option<lsp_types::WorkspaceEdit>, String>>,
+    },
+
+    /// Notify files renamed
+    DidRenameFiles { files: Vec<lsp_types::FileRename> },
+
+    /// Ask the server to send `$/logTrace` notifications, or to stop
+    SetTrace { verbose: bool },
+
+    /// Send the configured settings with `workspace/didChangeConfiguration`
+    DidChangeConfiguration { settings: Value },
+
+    /// Add workspace folders with `workspace/didChangeWorkspaceFolders`
+    DidChangeWorkspaceFolders { added: Vec<Uri> },
+
     /// Request completion at position
     Completion {
         request_id: u64,
         uri: Uri,
         line: u32,
         character: u32,
+        /// Re-query because the previous result was incomplete and the user kept typing
+        incomplete_retrigger: bool,
+    },
+
+    /// Resolve the lazily computed properties of a completion item
+    ResolveCompletionItem {
+        request_id: u64,
+        item: lsp_types::CompletionItem,
     },
 
-    /// Request go-to-definition
-    GotoDefinition {
+    /// Request the definition, declaration, type definition or implementations
+    Goto {
         request_id: u64,
+        target: GotoTarget,
         uri: Uri,
         line: u32,
         character: u32,
@@ -320,6 +847,20 @@ enum LspCommand {
         end_line: u32,
         end_char: u32,
         diagnostics: Vec<lsp_types::Diagnostic>,
+        /// Kinds of actions wanted (all kinds when `None`)
+        only: Option<Vec<lsp_types::CodeActionKind>>,
+    },
+
+    /// Fill in the edit of a code action offered without one
+    ResolveCodeAction {
+        request_id: u64,
+        action: lsp_types::CodeAction,
+    },
+
+    /// Run a command on the server (`workspace/executeCommand`)
+    ExecuteCommand {
+        request_id: u64,
+        command: lsp_types::Command,
     },
 
     /// Request document diagnostics (pull model)
@@ -341,6 +882,56 @@ enum LspCommand {
         end_char: u32,
     },
 
+    /// Request formatting edits for a document, a range or a typed character
+    Formatting {
+        request_id: u64,
+        uri: Uri,
+        request: FormattingRequest,
+        tab_size: u32,
+        insert_spaces: bool,
+    },
+
+    /// Request the symbol outline of a document
+    DocumentSymbols { request_id: u64, uri: Uri },
+
+    /// Search symbols across the workspace
+    WorkspaceSymbols { request_id: u64, query: String },
+
+    /// Request the foldable ranges of a document
+    FoldingRanges { request_id: u64, uri: Uri },
+
+    /// Request the code lenses of a document
+    CodeLens { request_id: u64, uri: Uri },
+
+    /// Fill in the command of a code lens
+    ResolveCodeLens {
+        request_id: u64,
+        lens: lsp_types::CodeLens,
+    },
+
+    /// Find the call or type hierarchy item at a position
+    PrepareHierarchy {
+        request_id: u64,
+        uri: Uri,
+        line: u32,
+        character: u32,
+        direction: HierarchyDirection,
+    },
+
+    /// Request the items related to a hierarchy item
+    ExpandHierarchy {
+        request_id: u64,
+        item: HierarchyItem,
+        direction: HierarchyDirection,
+    },
+
+    /// Request semantic tokens for a document
+    SemanticTokens {
+        request_id: u64,
+        uri: Uri,
+        request: SemanticTokensRequest,
+    },
+
     /// Cancel a pending request
     CancelRequest {
         /// Editor's request ID to cancel
@@ -385,9 +976,19 @@ struct LspState {
     /// Language ID (for error reporting)
     language: String,
 
+    /// Name of the server among its language's servers
+    server: String,
+
     /// Mapping from editor request_id to LSP JSON-RPC id for cancellation
     /// Key: editor request_id, Value: LSP JSON-RPC id
     active_requests: HashMap<u64, i64>,
+
+    /// Whether messages are forwarded to the main loop as `LspTrace`
+    trace: Arc<AtomicBool>,
+
+    /// Configured settings, shared with the reader task that answers
+    /// `workspace/configuration`
+    settings: Arc<Mutex<Option<Value>>>,
 }
 
 impl LspState {
@@ -429,6 +1030,14 @@ impl LspState {
                     tracing::info!("Replaying DidSave for {}", uri.as_str());
                     let _ = self.handle_did_save(uri, text).await;
                 }
+                LspCommand::DidClose { uri } => {
+                    tracing::info!("Replaying DidClose for {}", uri.as_str());
+                    let _ = self.handle_did_close(uri).await;
+                }
+                LspCommand::DidChangeWorkspaceFolders { added } => {
+                    tracing::info!("Replaying DidChangeWorkspaceFolders");
+                    let _ = self.handle_did_change_workspace_folders(added).await;
+                }
                 _ => {}
             }
         }
@@ -439,6 +1048,15 @@ impl LspState {
         let json =
             serde_json::to_string(message).map_err(|e| format!("Serialization error: {}", e))?;
 
+        if self.trace.load(Ordering::Relaxed) {
+            let _ = self.async_tx.send(AsyncMessage::LspTrace {
+                language: self.language.clone(),
+                server: self.server.clone(),
+                outgoing: true,
+                message: json.clone(),
+            });
+        }
+
         let content = format!("Content-Length: {}\r\n\r\n{}", json.len(), json);
 
         tracing::trace!("Writing LSP message to stdin ({} bytes)", content.len());
@@ -550,18 +1168,7 @@ impl LspState {
             initialization_options
         );
 
-        let workspace_folders = root_uri.as_ref().map(|uri| {
-            vec![WorkspaceFolder {
-                uri: uri.clone(),
-                name: uri
-                    .path()
-                    .as_str()
-                    .split('/')
-                    .last()
-                    .unwrap_or("workspace")
-                    .to_string(),
-            }]
-        });
+        let workspace_folders = root_uri.as_ref().map(|uri| vec![workspace_folder(uri)]);
 
         let params = InitializeParams {
             process_id: Some(std::process::id()),
@@ -569,6 +1176,10 @@ impl LspState {
             root_uri: root_uri.clone(),
             workspace_folders,
             initialization_options,
+            trace: self
+                .trace
+                .load(Ordering::Relaxed)
+                .then_some(lsp_types::TraceValue::Verbose),
             ..Default::default()
         };
 
@@ -584,6 +1195,12 @@ impl LspState {
 
         self.initialized = true;
 
+        // Servers that don't ask with workspace/configuration get the settings pushed
+        let settings = self.settings.lock().unwrap().clone();
+        if let Some(settings) = settings {
+            let _ = self.handle_did_change_configuration(settings).await;
+        }
+
         // Notify main loop
         let _ = self.async_tx.send(AsyncMessage::LspInitialized {
             language: self.language.clone(),
@@ -592,6 +1209,7 @@ impl LspState {
         // Send running status
         let _ = self.async_tx.send(AsyncMessage::LspStatusUpdate {
             language: self.language.clone(),
+            server: self.server.clone(),
             status: LspServerStatus::Running,
         });
 
@@ -700,38 +1318,197 @@ impl LspState {
         self.send_notification::<DidSaveTextDocument>(params).await
     }
 
-    /// Handle completion request
-    async fn handle_completion(
+    /// Handle did_close command
+    ///
+    /// The document is forgotten, so opening it again sends a fresh didOpen.
+    async fn handle_did_close(&mut self, uri: Uri) -> Result<(), String> {
+        tracing::trace!("LSP: did_close for {}", uri.as_str());
+
+        let path = PathBuf::from(uri.path().as_str());
+        if self.document_versions.remove(&path).is_none() {
+            tracing::debug!(
+                "LSP ({}): skipping didClose - document not open: {}",
+                self.language,
+                uri.as_str()
+            );
+            return Ok(());
+        }
+        self.pending_opens.remove(&path);
+
+        let params = DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri },
+        };
+
+        self.send_notification::<DidCloseTextDocument>(params).await
+    }
+
+    /// Handle will_save command
+    async fn handle_will_save(
+        &mut self,
+        uri: Uri,
+        reason: lsp_types::TextDocumentSaveReason,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: will_save for {}", uri.as_str());
+
+        let params = WillSaveTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri },
+            reason,
+        };
+
+        self.send_notification::<WillSaveTextDocument>(params).await
+    }
+
+    /// Handle will_save_wait_until request
+    ///
+    /// The edits are reported like formatting edits, since they are applied the same way.
+    async fn handle_will_save_wait_until(
         &mut self,
         request_id: u64,
         uri: Uri,
-        line: u32,
-        character: u32,
+        reason: lsp_types::TextDocumentSaveReason,
         pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
     ) -> Result<(), String> {
-        use lsp_types::{
-            CompletionParams, PartialResultParams, Position, TextDocumentIdentifier,
-            TextDocumentPositionParams, WorkDoneProgressParams,
+        tracing::trace!("LSP: will_save_wait_until request for {}", uri.as_str());
+
+        let params = WillSaveTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            reason,
         };
 
-        tracing::trace!(
-            "LSP: completion request at {}:{}:{}",
-            uri.as_str(),
-            line,
-            character
-        );
+        self.request_formatting_edits(WillSaveWaitUntil::METHOD, params, request_id, uri, pending)
+            .await
+    }
 
-        let params = CompletionParams {
-            text_document_position: TextDocumentPositionParams {
-                text_document: TextDocumentIdentifier { uri },
-                position: Position { line, character },
-            },
-            work_done_progress_params: WorkDoneProgressParams::default(),
-            partial_result_params: PartialResultParams::default(),
-            context: None,
-        };
+    /// Handle did_change_watched_files command
+    async fn handle_did_change_watched_files(
+        &mut self,
+        changes: Vec<lsp_types::FileEvent>,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: did_change_watched_files ({} changes)", changes.len());
 
-        // Send request and get response (tracked for cancellation)
+        let params = lsp_types::DidChangeWatchedFilesParams { changes };
+
+        self.send_notification::<DidChangeWatchedFiles>(params)
+            .await
+    }
+
+    /// Handle did_change_configuration command
+    async fn handle_did_change_configuration(&mut self, settings: Value) -> Result<(), String> {
+        tracing::trace!("LSP: did_change_configuration");
+
+        let params = lsp_types::DidChangeConfigurationParams { settings };
+
+        self.send_notification::<DidChangeConfiguration>(params)
+            .await
+    }
+
+    /// Handle did_change_workspace_folders command
+    ///
+    /// Servers that don't accept workspace folder changes keep only their root.
+    async fn handle_did_change_workspace_folders(&mut self, added: Vec<Uri>) -> Result<(), String> {
+        if !self
+            .capabilities
+            .as_ref()
+            .is_some_and(supports_workspace_folder_changes)
+        {
+            tracing::debug!(
+                "{} doesn't accept workspace folder changes, not adding {} folders",
+                self.server,
+                added.len()
+            );
+            return Ok(());
+        }
+        tracing::trace!("LSP: did_change_workspace_folders ({} added)", added.len());
+
+        let params = lsp_types::DidChangeWorkspaceFoldersParams {
+            event: lsp_types::WorkspaceFoldersChangeEvent {
+                added: added.iter().map(workspace_folder).collect(),
+                removed: Vec::new(),
+            },
+        };
+
+        self.send_notification::<DidChangeWorkspaceFolders>(params)
+            .await
+    }
+
+    /// Handle set_trace command
+    async fn handle_set_trace(&mut self, verbose: bool) -> Result<(), String> {
+        let value = if verbose {
+            lsp_types::TraceValue::Verbose
+        } else {
+            lsp_types::TraceValue::Off
+        };
+        self.send_notification::<SetTrace>(lsp_types::SetTraceParams { value })
+            .await
+    }
+
+    /// Handle will_rename_files request
+    async fn handle_will_rename_files(
+        &mut self,
+        files: Vec<lsp_types::FileRename>,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<Option<lsp_types::WorkspaceEdit>, String> {
+        tracing::trace!("LSP: will_rename_files ({} files)", files.len());
+
+        let params = lsp_types::RenameFilesParams { files };
+
+        self.send_request_sequential(WillRenameFiles::METHOD, Some(params), pending)
+            .await
+    }
+
+    /// Handle did_rename_files command
+    async fn handle_did_rename_files(
+        &mut self,
+        files: Vec<lsp_types::FileRename>,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: did_rename_files ({} files)", files.len());
+
+        let params = lsp_types::RenameFilesParams { files };
+
+        self.send_notification::<DidRenameFiles>(params).await
+    }
+
+    /// Handle completion request
+    async fn handle_completion(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        line: u32,
+        character: u32,
+        incomplete_retrigger: bool,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            CompletionContext, CompletionParams, CompletionTriggerKind, PartialResultParams,
+            Position, TextDocumentIdentifier, TextDocumentPositionParams, WorkDoneProgressParams,
+        };
+
+        tracing::trace!(
+            "LSP: completion request at {}:{}:{}",
+            uri.as_str(),
+            line,
+            character
+        );
+
+        let trigger_kind = if incomplete_retrigger {
+            CompletionTriggerKind::TRIGGER_FOR_INCOMPLETE_COMPLETIONS
+        } else {
+            CompletionTriggerKind::INVOKED
+        };
+        let params = CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position { line, character },
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            context: Some(CompletionContext {
+                trigger_kind,
+                trigger_character: None,
+            }),
+        };
+
+        // Send request and get response (tracked for cancellation)
         match self
             .send_request_sequential_tracked::<_, Value>(
                 "textDocument/completion",
@@ -742,23 +1519,15 @@ impl LspState {
             .await
         {
             Ok(result) => {
-                // Parse the completion response
-                let items = if let Ok(list) =
-                    serde_json::from_value::<lsp_types::CompletionList>(result.clone())
-                {
-                    list.items
-                } else if let Ok(items) =
-                    serde_json::from_value::<Vec<lsp_types::CompletionItem>>(result)
-                {
-                    items
-                } else {
-                    vec![]
-                };
+                let (items, is_incomplete) = parse_completion_result(result);
 
                 // Send to main loop
-                let _ = self
-                    .async_tx
-                    .send(AsyncMessage::LspCompletion { request_id, items });
+                let _ = self.async_tx.send(AsyncMessage::LspCompletion {
+                    request_id,
+                    server: self.server.clone(),
+                    items,
+                    is_incomplete,
+                });
                 Ok(())
             }
             Err(e) => {
@@ -766,17 +1535,46 @@ impl LspState {
                 // Send empty completion on error
                 let _ = self.async_tx.send(AsyncMessage::LspCompletion {
                     request_id,
+                    server: self.server.clone(),
                     items: vec![],
+                    is_incomplete: false,
                 });
                 Err(e)
             }
         }
     }
 
-    /// Handle go-to-definition request
-    async fn handle_goto_definition(
+    /// Handle completion item resolve request
+    async fn handle_resolve_completion_item(
         &mut self,
         request_id: u64,
+        item: lsp_types::CompletionItem,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: resolve completion item '{}'", item.label);
+
+        let result = self
+            .send_request_sequential::<_, lsp_types::CompletionItem>(
+                "completionItem/resolve",
+                Some(item),
+                pending,
+            )
+            .await;
+        if let Err(e) = &result {
+            tracing::error!("Completion item resolve request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspCompletionItemResolved {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle go-to-definition, declaration, type definition and implementation requests
+    async fn handle_goto(
+        &mut self,
+        request_id: u64,
+        target: GotoTarget,
         uri: Uri,
         line: u32,
         character: u32,
@@ -788,7 +1586,8 @@ impl LspState {
         };
 
         tracing::trace!(
-            "LSP: go-to-definition request at {}:{}:{}",
+            "LSP: go-to-{} request at {}:{}:{}",
+            target.name(),
             uri.as_str(),
             line,
             character
@@ -805,7 +1604,7 @@ impl LspState {
 
         // Send request and get response
         match self
-            .send_request_sequential::<_, Value>("textDocument/definition", Some(params), pending)
+            .send_request_sequential::<_, Value>(target.method(), Some(params), pending)
             .await
         {
             Ok(result) => {
@@ -834,16 +1633,16 @@ impl LspState {
                 };
 
                 // Send to main loop
-                let _ = self.async_tx.send(AsyncMessage::LspGotoDefinition {
+                let _ = self.async_tx.send(AsyncMessage::LspGotoLocations {
                     request_id,
                     locations,
                 });
                 Ok(())
             }
             Err(e) => {
-                tracing::error!("Go-to-definition request failed: {}", e);
+                tracing::error!("Go-to-{} request failed: {}", target.name(), e);
                 // Send empty locations on error
-                let _ = self.async_tx.send(AsyncMessage::LspGotoDefinition {
+                let _ = self.async_tx.send(AsyncMessage::LspGotoLocations {
                     request_id,
                     locations: vec![],
                 });
@@ -1187,44 +1986,27 @@ impl LspState {
         &mut self,
         request_id: u64,
         uri: Uri,
-        start_line: u32,
-        start_char: u32,
-        end_line: u32,
-        end_char: u32,
-        diagnostics: Vec<lsp_types::Diagnostic>,
+        range: lsp_types::Range,
+        context: lsp_types::CodeActionContext,
         pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
     ) -> Result<(), String> {
         use lsp_types::{
-            CodeActionContext, CodeActionParams, PartialResultParams, Position, Range,
-            TextDocumentIdentifier, WorkDoneProgressParams,
+            CodeActionParams, PartialResultParams, TextDocumentIdentifier, WorkDoneProgressParams,
         };
 
         tracing::trace!(
             "LSP: code actions request at {}:{}:{}-{}:{}",
             uri.as_str(),
-            start_line,
-            start_char,
-            end_line,
-            end_char
+            range.start.line,
+            range.start.character,
+            range.end.line,
+            range.end.character
         );
 
         let params = CodeActionParams {
             text_document: TextDocumentIdentifier { uri },
-            range: Range {
-                start: Position {
-                    line: start_line,
-                    character: start_char,
-                },
-                end: Position {
-                    line: end_line,
-                    character: end_char,
-                },
-            },
-            context: CodeActionContext {
-                diagnostics,
-                only: None,
-                trigger_kind: None,
-            },
+            range,
+            context,
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
         };
@@ -1248,6 +2030,8 @@ impl LspState {
                 // Send to main loop
                 let _ = self.async_tx.send(AsyncMessage::LspCodeActions {
                     request_id,
+                    language: self.language.clone(),
+                    server: self.server.clone(),
                     actions,
                 });
                 Ok(())
@@ -1257,6 +2041,8 @@ impl LspState {
                 // Send empty result on error
                 let _ = self.async_tx.send(AsyncMessage::LspCodeActions {
                     request_id,
+                    language: self.language.clone(),
+                    server: self.server.clone(),
                     actions: Vec::new(),
                 });
                 Err(e)
@@ -1264,6 +2050,65 @@ impl LspState {
         }
     }
 
+    /// Handle code action resolve request
+    async fn handle_resolve_code_action(
+        &mut self,
+        request_id: u64,
+        action: lsp_types::CodeAction,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: resolve code action '{}'", action.title);
+
+        let result = self
+            .send_request_sequential::<_, lsp_types::CodeAction>(
+                "codeAction/resolve",
+                Some(action),
+                pending,
+            )
+            .await;
+        if let Err(e) = &result {
+            tracing::error!("Code action resolve request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspCodeActionResolved {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle execute command request
+    async fn handle_execute_command(
+        &mut self,
+        request_id: u64,
+        command: lsp_types::Command,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{ExecuteCommandParams, WorkDoneProgressParams};
+
+        tracing::trace!("LSP: execute command {}", command.command);
+
+        let params = ExecuteCommandParams {
+            command: command.command,
+            arguments: command.arguments.unwrap_or_default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        // Any edits the command makes arrive as workspace/applyEdit requests; the result
+        // itself is only checked for errors
+        let result = self
+            .send_request_sequential::<_, Value>("workspace/executeCommand", Some(params), pending)
+            .await
+            .map(|_| ());
+        if let Err(e) = &result {
+            tracing::error!("Execute command request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspCommandExecuted {
+            request_id,
+            result: result.clone(),
+        });
+        result
+    }
+
     /// Handle document diagnostic request (pull diagnostics)
     async fn handle_document_diagnostic(
         &mut self,
@@ -1319,6 +2164,7 @@ impl LspState {
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
                         uri: uri_string,
+                        server: self.server.clone(),
                         result_id,
                         diagnostics,
                         unchanged: false,
@@ -1340,6 +2186,7 @@ impl LspState {
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
                         uri: uri_string,
+                        server: self.server.clone(),
                         result_id: Some(result_id),
                         diagnostics: Vec::new(),
                         unchanged: true,
@@ -1353,6 +2200,7 @@ impl LspState {
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
                         uri: uri_string,
+                        server: self.server.clone(),
                         result_id: None,
                         diagnostics: Vec::new(),
                         unchanged: false,
@@ -1367,6 +2215,7 @@ impl LspState {
                 let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                     request_id,
                     uri: uri.as_str().to_string(),
+                    server: self.server.clone(),
                     result_id: None,
                     diagnostics: Vec::new(),
                     unchanged: false,
@@ -1454,6 +2303,178 @@ impl LspState {
         }
     }
 
+    /// Handle a formatting request of any flavour
+    async fn handle_formatting(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        request: FormattingRequest,
+        options: lsp_types::FormattingOptions,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            DocumentFormattingParams, DocumentOnTypeFormattingParams,
+            DocumentRangeFormattingParams, TextDocumentIdentifier, TextDocumentPositionParams,
+            WorkDoneProgressParams,
+        };
+
+        tracing::trace!(
+            "LSP: formatting request for {}: {:?}",
+            uri.as_str(),
+            request
+        );
+
+        let text_document = TextDocumentIdentifier { uri: uri.clone() };
+        match request {
+            FormattingRequest::Document => {
+                let params = DocumentFormattingParams {
+                    text_document,
+                    options,
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                };
+                self.request_formatting_edits(
+                    "textDocument/formatting",
+                    params,
+                    request_id,
+                    uri,
+                    pending,
+                )
+                .await
+            }
+            FormattingRequest::Range(range) => {
+                let params = DocumentRangeFormattingParams {
+                    text_document,
+                    range,
+                    options,
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                };
+                self.request_formatting_edits(
+                    "textDocument/rangeFormatting",
+                    params,
+                    request_id,
+                    uri,
+                    pending,
+                )
+                .await
+            }
+            FormattingRequest::OnType { position, ch } => {
+                let params = DocumentOnTypeFormattingParams {
+                    text_document_position: TextDocumentPositionParams {
+                        text_document,
+                        position,
+                    },
+                    ch,
+                    options,
+                };
+                self.request_formatting_edits(
+                    "textDocument/onTypeFormatting",
+                    params,
+                    request_id,
+                    uri,
+                    pending,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Send a formatting request and forward the resulting edits to the main loop
+    ///
+    /// All three formatting methods return `TextEdit[] | null`, so they share
+    /// the same response handling.
+    async fn request_formatting_edits<P: Serialize>(
+        &mut self,
+        method: &str,
+        params: P,
+        request_id: u64,
+        uri: Uri,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        match self
+            .send_request_sequential::<_, Option<Vec<lsp_types::TextEdit>>>(
+                method,
+                Some(params),
+                pending,
+            )
+            .await
+        {
+            Ok(edits) => {
+                let edits = edits.unwrap_or_default();
+                tracing::trace!(
+                    "LSP: received {} formatting edits ({})",
+                    edits.len(),
+                    method
+                );
+
+                let _ = self.async_tx.send(AsyncMessage::LspFormatting {
+                    request_id,
+                    uri: uri.as_str().to_string(),
+                    result: Ok(edits),
+                });
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("Formatting request ({}) failed: {}", method, e);
+                let _ = self.async_tx.send(AsyncMessage::LspFormatting {
+                    request_id,
+                    uri: uri.as_str().to_string(),
+                    result: Err(e.clone()),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Handle document symbols request
+    async fn handle_document_symbols(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            DocumentSymbolParams, DocumentSymbolResponse, PartialResultParams,
+            TextDocumentIdentifier, WorkDoneProgressParams,
+        };
+
+        tracing::trace!("LSP: document symbols request for {}", uri.as_str());
+
+        let params = DocumentSymbolParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        match self
+            .send_request_sequential::<_, Option<DocumentSymbolResponse>>(
+                "textDocument/documentSymbol",
+                Some(params),
+                pending,
+            )
+            .await
+        {
+            Ok(response) => {
+                let response =
+                    response.unwrap_or_else(|| DocumentSymbolResponse::Nested(Vec::new()));
+                let _ = self.async_tx.send(AsyncMessage::LspDocumentSymbols {
+                    request_id,
+                    uri: uri.as_str().to_string(),
+                    result: Ok(response),
+                });
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("Document symbols request failed: {}", e);
+                let _ = self.async_tx.send(AsyncMessage::LspDocumentSymbols {
+                    request_id,
+                    uri: uri.as_str().to_string(),
+                    result: Err(e.clone()),
+                });
+                Err(e)
+            }
+        }
+    }
+
     /// Handle a plugin-initiated request by forwarding it to the server
     async fn handle_plugin_request(
         &mut self,
@@ -1510,11 +2531,466 @@ impl LspState {
         self.write_message(&exit).await
     }
 
-    /// Send a cancel request notification to the server
-    async fn send_cancel_request(&mut self, lsp_id: i64) -> Result<(), String> {
-        tracing::trace!("Sending $/cancelRequest for LSP id {}", lsp_id);
-
-        let notification = JsonRpcNotification {
+    /// Handle `textDocument/prepareCallHierarchy` or `textDocument/prepareTypeHierarchy`
+    async fn handle_prepare_hierarchy(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        line: u32,
+        character: u32,
+        direction: HierarchyDirection,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            CallHierarchyItem, CallHierarchyPrepareParams, Position, TextDocumentIdentifier,
+            TextDocumentPositionParams, TypeHierarchyItem, TypeHierarchyPrepareParams,
+            WorkDoneProgressParams,
+        };
+
+        tracing::trace!(
+            "LSP: prepare {:?} hierarchy at {}:{}:{}",
+            direction,
+            uri.as_str(),
+            line,
+            character
+        );
+
+        let text_document_position_params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position: Position { line, character },
+        };
+        let work_done_progress_params = WorkDoneProgressParams::default();
+
+        let result = if direction.is_call() {
+            let params = CallHierarchyPrepareParams {
+                text_document_position_params,
+                work_done_progress_params,
+            };
+            self.send_request_sequential::<_, Option<Vec<CallHierarchyItem>>>(
+                "textDocument/prepareCallHierarchy",
+                Some(params),
+                pending,
+            )
+            .await
+            .map(|items| {
+                items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(HierarchyItem::Call)
+                    .collect::<Vec<_>>()
+            })
+        } else {
+            let params = TypeHierarchyPrepareParams {
+                text_document_position_params,
+                work_done_progress_params,
+            };
+            self.send_request_sequential::<_, Option<Vec<TypeHierarchyItem>>>(
+                "textDocument/prepareTypeHierarchy",
+                Some(params),
+                pending,
+            )
+            .await
+            .map(|items| {
+                items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(HierarchyItem::Type)
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        let result = result.map(|items| {
+            items
+                .into_iter()
+                .map(|item| HierarchyEntry {
+                    item,
+                    from_ranges: Vec::new(),
+                })
+                .collect::<Vec<_>>()
+        });
+        if let Err(e) = &result {
+            tracing::error!("Prepare hierarchy request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspHierarchy {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle incoming/outgoing calls or supertypes/subtypes request
+    async fn handle_expand_hierarchy(
+        &mut self,
+        request_id: u64,
+        item: HierarchyItem,
+        direction: HierarchyDirection,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyOutgoingCall,
+            CallHierarchyOutgoingCallsParams, PartialResultParams, TypeHierarchyItem,
+            TypeHierarchySubtypesParams, TypeHierarchySupertypesParams, WorkDoneProgressParams,
+        };
+
+        tracing::trace!("LSP: {:?} of {}", direction, item.name());
+
+        let work_done_progress_params = WorkDoneProgressParams::default();
+        let partial_result_params = PartialResultParams::default();
+
+        let result = match (direction, item) {
+            (HierarchyDirection::IncomingCalls, HierarchyItem::Call(item)) => {
+                let params = CallHierarchyIncomingCallsParams {
+                    item,
+                    work_done_progress_params,
+                    partial_result_params,
+                };
+                self.send_request_sequential::<_, Option<Vec<CallHierarchyIncomingCall>>>(
+                    "callHierarchy/incomingCalls",
+                    Some(params),
+                    pending,
+                )
+                .await
+                .map(|calls| {
+                    calls
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|call| HierarchyEntry {
+                            item: HierarchyItem::Call(call.from),
+                            from_ranges: call.from_ranges,
+                        })
+                        .collect::<Vec<_>>()
+                })
+            }
+            (HierarchyDirection::OutgoingCalls, HierarchyItem::Call(item)) => {
+                let params = CallHierarchyOutgoingCallsParams {
+                    item,
+                    work_done_progress_params,
+                    partial_result_params,
+                };
+                self.send_request_sequential::<_, Option<Vec<CallHierarchyOutgoingCall>>>(
+                    "callHierarchy/outgoingCalls",
+                    Some(params),
+                    pending,
+                )
+                .await
+                .map(|calls| {
+                    calls
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|call| HierarchyEntry {
+                            item: HierarchyItem::Call(call.to),
+                            from_ranges: call.from_ranges,
+                        })
+                        .collect::<Vec<_>>()
+                })
+            }
+            (HierarchyDirection::Supertypes, HierarchyItem::Type(item)) => {
+                let params = TypeHierarchySupertypesParams {
+                    item,
+                    work_done_progress_params,
+                    partial_result_params,
+                };
+                self.send_request_sequential::<_, Option<Vec<TypeHierarchyItem>>>(
+                    "typeHierarchy/supertypes",
+                    Some(params),
+                    pending,
+                )
+                .await
+                .map(type_hierarchy_entries)
+            }
+            (HierarchyDirection::Subtypes, HierarchyItem::Type(item)) => {
+                let params = TypeHierarchySubtypesParams {
+                    item,
+                    work_done_progress_params,
+                    partial_result_params,
+                };
+                self.send_request_sequential::<_, Option<Vec<TypeHierarchyItem>>>(
+                    "typeHierarchy/subtypes",
+                    Some(params),
+                    pending,
+                )
+                .await
+                .map(type_hierarchy_entries)
+            }
+            _ => Err(format!("{:?} doesn't apply to this item", direction)),
+        };
+
+        if let Err(e) = &result {
+            tracing::error!("Hierarchy request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspHierarchy {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle folding range request
+    async fn handle_folding_ranges(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            FoldingRange, FoldingRangeParams, PartialResultParams, TextDocumentIdentifier,
+            WorkDoneProgressParams,
+        };
+
+        tracing::trace!("LSP: folding range request for {}", uri.as_str());
+
+        let params = FoldingRangeParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request_sequential::<_, Option<Vec<FoldingRange>>>(
+                "textDocument/foldingRange",
+                Some(params),
+                pending,
+            )
+            .await
+            .map(Option::unwrap_or_default);
+
+        if let Err(e) = &result {
+            tracing::error!("Folding range request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspFoldingRanges {
+            request_id,
+            uri: uri.as_str().to_string(),
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle code lens request
+    async fn handle_code_lens(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            CodeLens, CodeLensParams, PartialResultParams, TextDocumentIdentifier,
+            WorkDoneProgressParams,
+        };
+
+        tracing::trace!("LSP: code lens request for {}", uri.as_str());
+
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request_sequential::<_, Option<Vec<CodeLens>>>(
+                "textDocument/codeLens",
+                Some(params),
+                pending,
+            )
+            .await
+            .map(Option::unwrap_or_default);
+
+        if let Err(e) = &result {
+            tracing::error!("Code lens request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspCodeLens {
+            request_id,
+            language: self.language.clone(),
+            server: self.server.clone(),
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle code lens resolve request
+    async fn handle_resolve_code_lens(
+        &mut self,
+        request_id: u64,
+        lens: lsp_types::CodeLens,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: resolve code lens at line {}", lens.range.start.line);
+
+        let result = self
+            .send_request_sequential::<_, lsp_types::CodeLens>(
+                "codeLens/resolve",
+                Some(lens),
+                pending,
+            )
+            .await;
+        if let Err(e) = &result {
+            tracing::error!("Code lens resolve request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspCodeLensResolved {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle semantic tokens request (full, full/delta or range)
+    ///
+    /// All three responses are reported as a `SemanticTokensFullDeltaResult`; full and
+    /// range results use its `Tokens` variant.
+    async fn handle_semantic_tokens(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        request: SemanticTokensRequest,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            PartialResultParams, SemanticTokensDeltaParams, SemanticTokensFullDeltaResult,
+            SemanticTokensParams, SemanticTokensRangeParams, SemanticTokensRangeResult,
+            SemanticTokensResult, TextDocumentIdentifier, WorkDoneProgressParams,
+        };
+
+        tracing::trace!(
+            "LSP: semantic tokens request ({:?}) for {}",
+            request,
+            uri.as_str()
+        );
+
+        let text_document = TextDocumentIdentifier { uri: uri.clone() };
+        let result = match request {
+            SemanticTokensRequest::Full => self
+                .send_request_sequential::<_, Option<SemanticTokensResult>>(
+                    "textDocument/semanticTokens/full",
+                    Some(SemanticTokensParams {
+                        text_document,
+                        work_done_progress_params: WorkDoneProgressParams::default(),
+                        partial_result_params: PartialResultParams::default(),
+                    }),
+                    pending,
+                )
+                .await
+                .map(|result| match result {
+                    Some(SemanticTokensResult::Tokens(tokens)) => {
+                        SemanticTokensFullDeltaResult::Tokens(tokens)
+                    }
+                    Some(SemanticTokensResult::Partial(partial)) => {
+                        SemanticTokensFullDeltaResult::Tokens(lsp_types::SemanticTokens {
+                            result_id: None,
+                            data: partial.data,
+                        })
+                    }
+                    None => SemanticTokensFullDeltaResult::Tokens(Default::default()),
+                }),
+            SemanticTokensRequest::FullDelta { previous_result_id } => self
+                .send_request_sequential::<_, Option<SemanticTokensFullDeltaResult>>(
+                    "textDocument/semanticTokens/full/delta",
+                    Some(SemanticTokensDeltaParams {
+                        text_document,
+                        previous_result_id,
+                        work_done_progress_params: WorkDoneProgressParams::default(),
+                        partial_result_params: PartialResultParams::default(),
+                    }),
+                    pending,
+                )
+                .await
+                .map(|result| {
+                    result.unwrap_or_else(|| {
+                        SemanticTokensFullDeltaResult::Tokens(Default::default())
+                    })
+                }),
+            SemanticTokensRequest::Range(range) => self
+                .send_request_sequential::<_, Option<SemanticTokensRangeResult>>(
+                    "textDocument/semanticTokens/range",
+                    Some(SemanticTokensRangeParams {
+                        text_document,
+                        range,
+                        work_done_progress_params: WorkDoneProgressParams::default(),
+                        partial_result_params: PartialResultParams::default(),
+                    }),
+                    pending,
+                )
+                .await
+                .map(|result| match result {
+                    Some(SemanticTokensRangeResult::Tokens(tokens)) => {
+                        SemanticTokensFullDeltaResult::Tokens(tokens)
+                    }
+                    Some(SemanticTokensRangeResult::Partial(partial)) => {
+                        SemanticTokensFullDeltaResult::Tokens(lsp_types::SemanticTokens {
+                            result_id: None,
+                            data: partial.data,
+                        })
+                    }
+                    None => SemanticTokensFullDeltaResult::Tokens(Default::default()),
+                }),
+        };
+
+        if let Err(e) = &result {
+            tracing::error!("Semantic tokens request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspSemanticTokens {
+            request_id,
+            uri: uri.as_str().to_string(),
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle workspace symbol search request
+    async fn handle_workspace_symbols(
+        &mut self,
+        request_id: u64,
+        query: String,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            PartialResultParams, WorkDoneProgressParams, WorkspaceSymbolParams,
+            WorkspaceSymbolResponse,
+        };
+
+        tracing::trace!("LSP: workspace symbols request for {:?}", query);
+
+        let params = WorkspaceSymbolParams {
+            query,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        // Tracked so that a newer query can cancel this one via $/cancelRequest
+        match self
+            .send_request_sequential_tracked::<_, Option<WorkspaceSymbolResponse>>(
+                "workspace/symbol",
+                Some(params),
+                pending,
+                Some(request_id),
+            )
+            .await
+        {
+            Ok(response) => {
+                let response =
+                    response.unwrap_or_else(|| WorkspaceSymbolResponse::Flat(Vec::new()));
+                let _ = self.async_tx.send(AsyncMessage::LspWorkspaceSymbols {
+                    request_id,
+                    language: self.language.clone(),
+                    result: Ok(response),
+                });
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("Workspace symbols request failed: {}", e);
+                let _ = self.async_tx.send(AsyncMessage::LspWorkspaceSymbols {
+                    request_id,
+                    language: self.language.clone(),
+                    result: Err(e.clone()),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Send a cancel request notification to the server
+    async fn send_cancel_request(&mut self, lsp_id: i64) -> Result<(), String> {
+        tracing::trace!("Sending $/cancelRequest for LSP id {}", lsp_id);
+
+        let notification = JsonRpcNotification {
             jsonrpc: "2.0".to_string(),
             method: "$/cancelRequest".to_string(),
             params: Some(serde_json::json!({ "id": lsp_id })),
@@ -1542,6 +3018,25 @@ impl LspState {
     }
 }
 
+/// Which server a task talks to, and where it reports to the main loop
+#[derive(Clone)]
+struct ServerContext {
+    /// Language ID (for error reporting)
+    language: String,
+
+    /// Name of the server among its language's servers
+    server: String,
+
+    /// Sender for async messages to main loop
+    async_tx: std_mpsc::Sender<AsyncMessage>,
+
+    /// Whether messages are forwarded to the main loop as `LspTrace`
+    trace: Arc<AtomicBool>,
+
+    /// Configured settings, shared with the handle
+    settings: Arc<Mutex<Option<Value>>>,
+}
+
 /// Async LSP task that handles all I/O
 struct LspTask {
     /// Process handle
@@ -1578,11 +3073,20 @@ struct LspTask {
     /// Language ID (for error reporting)
     language: String,
 
+    /// Name of the server among its language's servers
+    server: String,
+
     /// Server command (for plugin identification)
     server_command: String,
 
     /// Path to stderr log file
     stderr_log_path: std::path::PathBuf,
+
+    /// Whether messages are forwarded to the main loop as `LspTrace`
+    trace: Arc<AtomicBool>,
+
+    /// Configured settings, shared with the handle
+    settings: Arc<Mutex<Option<Value>>>,
 }
 
 #[allow(dead_code)]
@@ -1591,8 +3095,7 @@ impl LspTask {
     async fn spawn(
         command: &str,
         args: &[String],
-        language: String,
-        async_tx: std_mpsc::Sender<AsyncMessage>,
+        context: ServerContext,
         process_limits: &ProcessLimits,
         stderr_log_path: std::path::PathBuf,
     ) -> Result<Self, String> {
@@ -1646,10 +3149,13 @@ impl LspTask {
             document_versions: HashMap::new(),
             pending_opens: HashMap::new(),
             initialized: false,
-            async_tx,
-            language,
+            async_tx: context.async_tx,
+            language: context.language,
+            server: context.server,
             server_command: command.to_string(),
             stderr_log_path,
+            trace: context.trace,
+            settings: context.settings,
         })
     }
 
@@ -1657,24 +3163,39 @@ impl LspTask {
     fn spawn_stdout_reader(
         mut stdout: BufReader<ChildStdout>,
         pending: Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
-        async_tx: std_mpsc::Sender<AsyncMessage>,
-        language: String,
+        context: ServerContext,
         server_command: String,
         server_response_tx: mpsc::Sender<JsonRpcResponse>,
         stderr_log_path: std::path::PathBuf,
         shutting_down: Arc<AtomicBool>,
     ) {
         tokio::spawn(async move {
+            let ServerContext {
+                language,
+                server,
+                async_tx,
+                trace,
+                ..
+            } = &context;
             tracing::info!("LSP stdout reader task started for {}", language);
             loop {
                 match read_message_from_stdout(&mut stdout).await {
                     Ok(message) => {
                         tracing::trace!("Read message from LSP server: {:?}", message);
+                        if trace.load(Ordering::Relaxed) {
+                            if let Ok(json) = serde_json::to_string(&message) {
+                                let _ = async_tx.send(AsyncMessage::LspTrace {
+                                    language: language.clone(),
+                                    server: server.clone(),
+                                    outgoing: false,
+                                    message: json,
+                                });
+                            }
+                        }
                         if let Err(e) = handle_message_dispatch(
                             message,
                             &pending,
-                            &async_tx,
-                            &language,
+                            &context,
                             &server_command,
                             &server_response_tx,
                         )
@@ -1694,6 +3215,7 @@ impl LspTask {
                             tracing::error!("Error reading from LSP server: {}", e);
                             let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                                 language: language.clone(),
+                                server: server.clone(),
                                 status: LspServerStatus::Error,
                             });
                             let _ = async_tx.send(AsyncMessage::LspError {
@@ -1724,7 +3246,10 @@ impl LspTask {
             initialized: self.initialized,
             async_tx: self.async_tx.clone(),
             language: self.language.clone(),
+            server: self.server.clone(),
             active_requests: HashMap::new(),
+            trace: self.trace.clone(),
+            settings: self.settings.clone(),
         };
 
         let pending = Arc::new(Mutex::new(self.pending));
@@ -1741,8 +3266,13 @@ impl LspTask {
         Self::spawn_stdout_reader(
             self.stdout,
             pending.clone(),
-            async_tx.clone(),
-            language_clone.clone(),
+            ServerContext {
+                language: language_clone.clone(),
+                server: self.server.clone(),
+                async_tx: async_tx.clone(),
+                trace: self.trace,
+                settings: self.settings,
+            },
             self.server_command.clone(),
             server_response_tx,
             self.stderr_log_path,
@@ -1768,6 +3298,7 @@ impl LspTask {
                             // Send initializing status
                             let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                                 language: language_clone.clone(),
+                                server: state.server.clone(),
                                 status: LspServerStatus::Initializing,
                             });
                             tracing::info!("Processing Initialize command");
@@ -1836,11 +3367,102 @@ impl LspTask {
                                 pending_commands.push(LspCommand::DidSave { uri, text });
                             }
                         }
+                        LspCommand::DidClose { uri } => {
+                            if state.initialized {
+                                tracing::info!("Processing DidClose for {}", uri.as_str());
+                                let _ = state.handle_did_close(uri).await;
+                            } else {
+                                // A queued didOpen for the document must not outlive it
+                                pending_commands.retain(|command| {
+                                    !matches!(
+                                        command,
+                                        LspCommand::DidOpen { uri: opened, .. }
+                                            | LspCommand::DidChange { uri: opened, .. }
+                                            | LspCommand::DidSave { uri: opened, .. }
+                                            if *opened == uri
+                                    )
+                                });
+                            }
+                        }
+                        LspCommand::WillSave { uri, reason } => {
+                            if state.initialized {
+                                tracing::trace!("Processing WillSave for {}", uri.as_str());
+                                let _ = state.handle_will_save(uri, reason).await;
+                            }
+                        }
+                        LspCommand::WillSaveWaitUntil {
+                            request_id,
+                            uri,
+                            reason,
+                        } => {
+                            if state.initialized {
+                                tracing::info!(
+                                    "Processing WillSaveWaitUntil request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_will_save_wait_until(request_id, uri, reason, &pending)
+                                    .await;
+                            } else {
+                                let _ = state.async_tx.send(AsyncMessage::LspFormatting {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::DidChangeWatchedFiles { changes } => {
+                            if state.initialized {
+                                tracing::trace!("Processing DidChangeWatchedFiles");
+                                let _ = state.handle_did_change_watched_files(changes).await;
+                            }
+                        }
+                        LspCommand::SetTrace { verbose } => {
+                            // Before initialization the trace value goes in the initialize request
+                            if state.initialized {
+                                tracing::info!("Processing SetTrace ({})", verbose);
+                                let _ = state.handle_set_trace(verbose).await;
+                            }
+                        }
+                        LspCommand::DidChangeConfiguration { settings } => {
+                            // Before initialization the settings are sent once it completes
+                            if state.initialized {
+                                tracing::info!("Processing DidChangeConfiguration");
+                                let _ = state.handle_did_change_configuration(settings).await;
+                            }
+                        }
+                        LspCommand::DidChangeWorkspaceFolders { added } => {
+                            if state.initialized {
+                                tracing::info!("Processing DidChangeWorkspaceFolders");
+                                let _ = state.handle_did_change_workspace_folders(added).await;
+                            } else {
+                                tracing::trace!(
+                                    "Queueing DidChangeWorkspaceFolders until initialization completes"
+                                );
+                                pending_commands.push(LspCommand::DidChangeWorkspaceFolders { added });
+                            }
+                        }
+                        LspCommand::WillRenameFiles { files, response } => {
+                            let result = if state.initialized {
+                                tracing::info!("Processing WillRenameFiles request");
+                                state.handle_will_rename_files(files, &pending).await
+                            } else {
+                                Err("LSP not initialized".to_string())
+                            };
+                            let _ = response.send(result);
+                        }
+                        LspCommand::DidRenameFiles { files } => {
+                            if state.initialized {
+                                tracing::info!("Processing DidRenameFiles");
+                                let _ = state.handle_did_rename_files(files).await;
+                            }
+                        }
                         LspCommand::Completion {
                             request_id,
                             uri,
                             line,
                             character,
+                            incomplete_retrigger,
                         } => {
                             if state.initialized {
                                 tracing::info!(
@@ -1848,35 +3470,64 @@ impl LspTask {
                                     uri.as_str()
                                 );
                                 let _ = state
-                                    .handle_completion(request_id, uri, line, character, &pending)
+                                    .handle_completion(
+                                        request_id,
+                                        uri,
+                                        line,
+                                        character,
+                                        incomplete_retrigger,
+                                        &pending,
+                                    )
                                     .await;
                             } else {
                                 tracing::trace!("LSP not initialized, sending empty completion");
                                 let _ = state.async_tx.send(AsyncMessage::LspCompletion {
                                     request_id,
+                                    server: state.server.clone(),
                                     items: vec![],
+                                    is_incomplete: false,
                                 });
                             }
                         }
-                        LspCommand::GotoDefinition {
+                        LspCommand::ResolveCompletionItem { request_id, item } => {
+                            if state.initialized {
+                                tracing::trace!("Processing ResolveCompletionItem request");
+                                let _ = state
+                                    .handle_resolve_completion_item(request_id, item, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!(
+                                    "LSP not initialized, cannot resolve completion item"
+                                );
+                                let _ = state.async_tx.send(
+                                    AsyncMessage::LspCompletionItemResolved {
+                                        request_id,
+                                        result: Err("LSP not initialized".to_string()),
+                                    },
+                                );
+                            }
+                        }
+                        LspCommand::Goto {
                             request_id,
+                            target,
                             uri,
                             line,
                             character,
                         } => {
                             if state.initialized {
                                 tracing::info!(
-                                    "Processing GotoDefinition request for {}",
+                                    "Processing Goto {:?} request for {}",
+                                    target,
                                     uri.as_str()
                                 );
                                 let _ = state
-                                    .handle_goto_definition(
-                                        request_id, uri, line, character, &pending,
+                                    .handle_goto(
+                                        request_id, target, uri, line, character, &pending,
                                     )
                                     .await;
                             } else {
                                 tracing::trace!("LSP not initialized, sending empty locations");
-                                let _ = state.async_tx.send(AsyncMessage::LspGotoDefinition {
+                                let _ = state.async_tx.send(AsyncMessage::LspGotoLocations {
                                     request_id,
                                     locations: vec![],
                                 });
@@ -1971,29 +3622,63 @@ impl LspTask {
                             end_line,
                             end_char,
                             diagnostics,
+                            only,
                         } => {
                             if state.initialized {
                                 tracing::info!("Processing CodeActions request for {}", uri.as_str());
+                                let range = lsp_types::Range::new(
+                                    lsp_types::Position::new(start_line, start_char),
+                                    lsp_types::Position::new(end_line, end_char),
+                                );
+                                let context = lsp_types::CodeActionContext {
+                                    diagnostics,
+                                    only,
+                                    trigger_kind: None,
+                                };
                                 let _ = state
-                                    .handle_code_actions(
-                                        request_id,
-                                        uri,
-                                        start_line,
-                                        start_char,
-                                        end_line,
-                                        end_char,
-                                        diagnostics,
-                                        &pending,
-                                    )
+                                    .handle_code_actions(request_id, uri, range, context, &pending)
                                     .await;
                             } else {
                                 tracing::trace!("LSP not initialized, cannot get code actions");
                                 let _ = state.async_tx.send(AsyncMessage::LspCodeActions {
                                     request_id,
+                                    language: state.language.clone(),
+                                    server: state.server.clone(),
                                     actions: Vec::new(),
                                 });
                             }
                         }
+                        LspCommand::ResolveCodeAction { request_id, action } => {
+                            if state.initialized {
+                                tracing::trace!("Processing ResolveCodeAction request");
+                                let _ = state
+                                    .handle_resolve_code_action(request_id, action, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot resolve code action");
+                                let _ = state.async_tx.send(AsyncMessage::LspCodeActionResolved {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::ExecuteCommand {
+                            request_id,
+                            command,
+                        } => {
+                            if state.initialized {
+                                tracing::trace!("Processing ExecuteCommand request");
+                                let _ = state
+                                    .handle_execute_command(request_id, command, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot execute command");
+                                let _ = state.async_tx.send(AsyncMessage::LspCommandExecuted {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
                         LspCommand::DocumentDiagnostic {
                             request_id,
                             uri,
@@ -2019,6 +3704,7 @@ impl LspTask {
                                 let _ = state.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                                     request_id,
                                     uri: uri.as_str().to_string(),
+                                    server: state.server.clone(),
                                     result_id: None,
                                     diagnostics: Vec::new(),
                                     unchanged: false,
@@ -2039,24 +3725,198 @@ impl LspTask {
                                     uri.as_str()
                                 );
                                 let _ = state
-                                    .handle_inlay_hints(
-                                        request_id,
-                                        uri,
-                                        start_line,
-                                        start_char,
-                                        end_line,
-                                        end_char,
-                                        &pending,
-                                    )
+                                    .handle_inlay_hints(
+                                        request_id,
+                                        uri,
+                                        start_line,
+                                        start_char,
+                                        end_line,
+                                        end_char,
+                                        &pending,
+                                    )
+                                    .await;
+                            } else {
+                                tracing::trace!(
+                                    "LSP not initialized, cannot get inlay hints"
+                                );
+                                let _ = state.async_tx.send(AsyncMessage::LspInlayHints {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    hints: Vec::new(),
+                                });
+                            }
+                        }
+                        LspCommand::Formatting {
+                            request_id,
+                            uri,
+                            request,
+                            tab_size,
+                            insert_spaces,
+                        } => {
+                            if state.initialized {
+                                tracing::info!(
+                                    "Processing Formatting request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_formatting(
+                                        request_id,
+                                        uri,
+                                        request,
+                                        formatting_options(tab_size, insert_spaces),
+                                        &pending,
+                                    )
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot format document");
+                                let _ = state.async_tx.send(AsyncMessage::LspFormatting {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::DocumentSymbols { request_id, uri } => {
+                            if state.initialized {
+                                tracing::trace!(
+                                    "Processing DocumentSymbols request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_document_symbols(request_id, uri, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, skipping document symbols");
+                                let _ = state.async_tx.send(AsyncMessage::LspDocumentSymbols {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::CodeLens { request_id, uri } => {
+                            if state.initialized {
+                                tracing::trace!("Processing CodeLens request for {}", uri.as_str());
+                                let _ = state.handle_code_lens(request_id, uri, &pending).await;
+                            } else {
+                                tracing::trace!("LSP not initialized, skipping code lens");
+                                let _ = state.async_tx.send(AsyncMessage::LspCodeLens {
+                                    request_id,
+                                    language: state.language.clone(),
+                                    server: state.server.clone(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::ResolveCodeLens { request_id, lens } => {
+                            if state.initialized {
+                                tracing::trace!("Processing ResolveCodeLens request");
+                                let _ = state
+                                    .handle_resolve_code_lens(request_id, lens, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot resolve code lens");
+                                let _ = state.async_tx.send(AsyncMessage::LspCodeLensResolved {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::FoldingRanges { request_id, uri } => {
+                            if state.initialized {
+                                tracing::trace!(
+                                    "Processing FoldingRanges request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_folding_ranges(request_id, uri, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, skipping folding ranges");
+                                let _ = state.async_tx.send(AsyncMessage::LspFoldingRanges {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::SemanticTokens {
+                            request_id,
+                            uri,
+                            request,
+                        } => {
+                            if state.initialized {
+                                tracing::trace!(
+                                    "Processing SemanticTokens request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_semantic_tokens(request_id, uri, request, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, skipping semantic tokens");
+                                let _ = state.async_tx.send(AsyncMessage::LspSemanticTokens {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::PrepareHierarchy {
+                            request_id,
+                            uri,
+                            line,
+                            character,
+                            direction,
+                        } => {
+                            if state.initialized {
+                                tracing::trace!(
+                                    "Processing PrepareHierarchy request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_prepare_hierarchy(
+                                        request_id, uri, line, character, direction, &pending,
+                                    )
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, skipping prepare hierarchy");
+                                let _ = state.async_tx.send(AsyncMessage::LspHierarchy {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::ExpandHierarchy {
+                            request_id,
+                            item,
+                            direction,
+                        } => {
+                            if state.initialized {
+                                tracing::trace!("Processing ExpandHierarchy request");
+                                let _ = state
+                                    .handle_expand_hierarchy(request_id, item, direction, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, skipping expand hierarchy");
+                                let _ = state.async_tx.send(AsyncMessage::LspHierarchy {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::WorkspaceSymbols { request_id, query } => {
+                            if state.initialized {
+                                tracing::trace!("Processing WorkspaceSymbols request");
+                                let _ = state
+                                    .handle_workspace_symbols(request_id, query, &pending)
                                     .await;
                             } else {
-                                tracing::trace!(
-                                    "LSP not initialized, cannot get inlay hints"
-                                );
-                                let _ = state.async_tx.send(AsyncMessage::LspInlayHints {
+                                tracing::trace!("LSP not initialized, skipping workspace symbols");
+                                let _ = state.async_tx.send(AsyncMessage::LspWorkspaceSymbols {
                                     request_id,
-                                    uri: uri.as_str().to_string(),
-                                    hints: Vec::new(),
+                                    language: state.language.clone(),
+                                    result: Err("LSP not initialized".to_string()),
                                 });
                             }
                         }
@@ -2173,6 +4033,7 @@ impl LspTask {
         // Send running status
         let _ = self.async_tx.send(AsyncMessage::LspStatusUpdate {
             language: self.language.clone(),
+            server: self.server.clone(),
             status: LspServerStatus::Running,
         });
 
@@ -2486,6 +4347,7 @@ impl LspTask {
                     // Send to main loop
                     let _ = self.async_tx.send(AsyncMessage::LspDiagnostics {
                         uri: params.uri.to_string(),
+                        server: self.server.clone(),
                         diagnostics: params.diagnostics,
                     });
                 }
@@ -2521,6 +4383,7 @@ impl LspTask {
                         // Send to UI
                         let _ = self.async_tx.send(AsyncMessage::LspWindowMessage {
                             language: self.language.clone(),
+                            server: self.server.clone(),
                             message_type,
                             message,
                         });
@@ -2558,6 +4421,7 @@ impl LspTask {
                         // Send to UI
                         let _ = self.async_tx.send(AsyncMessage::LspLogMessage {
                             language: self.language.clone(),
+                            server: self.server.clone(),
                             message_type,
                             message,
                         });
@@ -2657,6 +4521,7 @@ impl LspTask {
                             if let Some(value) = value {
                                 let _ = self.async_tx.send(AsyncMessage::LspProgress {
                                     language: self.language.clone(),
+                                    server: self.server.clone(),
                                     token,
                                     value,
                                 });
@@ -2733,11 +4598,17 @@ async fn read_message_from_stdout(
 async fn handle_message_dispatch(
     message: JsonRpcMessage,
     pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
-    async_tx: &std_mpsc::Sender<AsyncMessage>,
-    language: &str,
+    context: &ServerContext,
     server_command: &str,
     server_response_tx: &mpsc::Sender<JsonRpcResponse>,
 ) -> Result<(), String> {
+    let ServerContext {
+        language,
+        server,
+        async_tx,
+        settings,
+        ..
+    } = context;
     match message {
         JsonRpcMessage::Response(response) => {
             tracing::trace!("Received LSP response for request id={}", response.id);
@@ -2767,7 +4638,7 @@ async fn handle_message_dispatch(
         }
         JsonRpcMessage::Notification(notification) => {
             tracing::trace!("Received LSP notification: {}", notification.method);
-            handle_notification_dispatch(notification, async_tx, language).await?;
+            handle_notification_dispatch(notification, async_tx, language, server).await?;
         }
         JsonRpcMessage::Request(request) => {
             // Handle server-to-client requests - MUST respond to avoid timeouts
@@ -2784,56 +4655,159 @@ async fn handle_message_dispatch(
                     }
                 }
                 "workspace/configuration" => {
-                    // Return configuration with inlay hints enabled for rust-analyzer
-                    // The request contains items asking for configuration sections
-                    // We return an array with one config object per requested item
-                    tracing::trace!(
-                        "Responding to workspace/configuration with inlay hints enabled"
-                    );
-
-                    // Parse request params to see how many items are requested
-                    let num_items = request
+                    // One value per requested item, looked up in the configured settings
+                    tracing::trace!("Responding to workspace/configuration (id={})", request.id);
+                    let settings = settings.lock().unwrap().clone();
+                    JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        result: Some(configuration_response(
+                            settings.as_ref(),
+                            request.params.as_ref(),
+                        )),
+                        error: None,
+                    }
+                }
+                "workspace/applyEdit" => {
+                    // The edit is applied by the main loop, which sends back the
+                    // outcome; the reply waits for it in its own task so the reader
+                    // keeps going meanwhile
+                    let result = match request
                         .params
-                        .as_ref()
-                        .and_then(|p| p.get("items"))
-                        .and_then(|items| items.as_array())
-                        .map(|arr| arr.len())
-                        .unwrap_or(1);
-
-                    // rust-analyzer configuration with inlay hints enabled
-                    let ra_config = serde_json::json!({
-                        "inlayHints": {
-                            "typeHints": {
-                                "enable": true
-                            },
-                            "parameterHints": {
-                                "enable": true
-                            },
-                            "chainingHints": {
-                                "enable": true
-                            },
-                            "closureReturnTypeHints": {
-                                "enable": "always"
-                            }
+                        .clone()
+                        .map(serde_json::from_value::<lsp_types::ApplyWorkspaceEditParams>)
+                    {
+                        Some(Ok(params)) => {
+                            tracing::trace!(
+                                "Forwarding workspace/applyEdit (id={}) to main loop",
+                                request.id
+                            );
+                            let (tx, rx) = oneshot::channel();
+                            let _ = async_tx.send(AsyncMessage::LspApplyEdit {
+                                language: language.to_string(),
+                                server: server.to_string(),
+                                label: params.label,
+                                edit: params.edit,
+                                response: tx,
+                            });
+                            let id = request.id;
+                            let response_tx = server_response_tx.clone();
+                            tokio::spawn(async move {
+                                let result = rx.await.unwrap_or_else(|_| {
+                                    lsp_types::ApplyWorkspaceEditResponse {
+                                        applied: false,
+                                        failure_reason: Some(
+                                            "Editor dropped the workspace edit".to_string(),
+                                        ),
+                                        failed_change: None,
+                                    }
+                                });
+                                let response = JsonRpcResponse {
+                                    jsonrpc: "2.0".to_string(),
+                                    id,
+                                    result: serde_json::to_value(result).ok(),
+                                    error: None,
+                                };
+                                if let Err(e) = response_tx.send(response).await {
+                                    tracing::error!("Failed to queue server response: {}", e);
+                                }
+                            });
+                            return Ok(());
                         }
+                        Some(Err(e)) => lsp_types::ApplyWorkspaceEditResponse {
+                            applied: false,
+                            failure_reason: Some(format!("Invalid workspace edit: {}", e)),
+                            failed_change: None,
+                        },
+                        None => lsp_types::ApplyWorkspaceEditResponse {
+                            applied: false,
+                            failure_reason: Some("Missing workspace edit".to_string()),
+                            failed_change: None,
+                        },
+                    };
+                    JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        result: serde_json::to_value(result).ok(),
+                        error: None,
+                    }
+                }
+                "workspace/codeLens/refresh" => {
+                    // Lenses are requested again by the main loop
+                    tracing::trace!("Forwarding workspace/codeLens/refresh (id={})", request.id);
+                    let _ = async_tx.send(AsyncMessage::LspCodeLensRefresh {
+                        language: language.to_string(),
                     });
-
-                    // Return one config object for each requested item
-                    let configs: Vec<Value> = (0..num_items).map(|_| ra_config.clone()).collect();
-
                     JsonRpcResponse {
                         jsonrpc: "2.0".to_string(),
                         id: request.id,
-                        result: Some(Value::Array(configs)),
+                        result: Some(Value::Null),
                         error: None,
                     }
                 }
                 "client/registerCapability" => {
-                    // Server wants to register a capability dynamically - acknowledge
+                    // File watchers are kept by the main loop; other dynamic
+                    // registrations are just acknowledged
                     tracing::trace!(
                         "Acknowledging client/registerCapability (id={})",
                         request.id
                     );
+                    let registrations = request
+                        .params
+                        .clone()
+                        .and_then(|params| {
+                            serde_json::from_value::<lsp_types::RegistrationParams>(params).ok()
+                        })
+                        .map(|params| params.registrations)
+                        .unwrap_or_default();
+                    for registration in registrations {
+                        if registration.method != DidChangeWatchedFiles::METHOD {
+                            continue;
+                        }
+                        let Some(options) = registration.register_options.and_then(|options| {
+                            serde_json::from_value::<
+                                lsp_types::DidChangeWatchedFilesRegistrationOptions,
+                            >(options)
+                            .ok()
+                        }) else {
+                            continue;
+                        };
+                        let _ = async_tx.send(AsyncMessage::LspWatchedFilesRegistered {
+                            language: language.to_string(),
+                            server: server.to_string(),
+                            id: registration.id,
+                            watchers: options.watchers,
+                        });
+                    }
+                    JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        result: Some(Value::Null),
+                        error: None,
+                    }
+                }
+                "client/unregisterCapability" => {
+                    tracing::trace!(
+                        "Acknowledging client/unregisterCapability (id={})",
+                        request.id
+                    );
+                    let unregistrations = request
+                        .params
+                        .clone()
+                        .and_then(|params| {
+                            serde_json::from_value::<lsp_types::UnregistrationParams>(params).ok()
+                        })
+                        .map(|params| params.unregisterations)
+                        .unwrap_or_default();
+                    for unregistration in unregistrations {
+                        if unregistration.method == DidChangeWatchedFiles::METHOD {
+                            let _ = async_tx.send(AsyncMessage::LspWatchedFilesUnregistered {
+                                language: language.to_string(),
+                                server: server.to_string(),
+                                id: unregistration.id,
+                            });
+                        }
+                    }
                     JsonRpcResponse {
                         jsonrpc: "2.0".to_string(),
                         id: request.id,
@@ -2873,6 +4847,7 @@ async fn handle_notification_dispatch(
     notification: JsonRpcNotification,
     async_tx: &std_mpsc::Sender<AsyncMessage>,
     language: &str,
+    server: &str,
 ) -> Result<(), String> {
     match notification.method.as_str() {
         PublishDiagnostics::METHOD => {
@@ -2889,6 +4864,7 @@ async fn handle_notification_dispatch(
                 // Send to main loop
                 let _ = async_tx.send(AsyncMessage::LspDiagnostics {
                     uri: params.uri.to_string(),
+                    server: server.to_string(),
                     diagnostics: params.diagnostics,
                 });
             }
@@ -2923,6 +4899,7 @@ async fn handle_notification_dispatch(
                     // Send to UI
                     let _ = async_tx.send(AsyncMessage::LspWindowMessage {
                         language: language.to_string(),
+                        server: server.to_string(),
                         message_type,
                         message,
                     });
@@ -2959,12 +4936,29 @@ async fn handle_notification_dispatch(
                     // Send to UI
                     let _ = async_tx.send(AsyncMessage::LspLogMessage {
                         language: language.to_string(),
+                        server: server.to_string(),
                         message_type,
                         message,
                     });
                 }
             }
         }
+        "$/logTrace" => {
+            if let Some(params) = notification.params {
+                if let Ok(params) = serde_json::from_value::<lsp_types::LogTraceParams>(params) {
+                    let message = match params.verbose {
+                        Some(verbose) => format!("{}\n{}", params.message, verbose),
+                        None => params.message,
+                    };
+                    let _ = async_tx.send(AsyncMessage::LspLogMessage {
+                        language: language.to_string(),
+                        server: server.to_string(),
+                        message_type: LspMessageType::Log,
+                        message,
+                    });
+                }
+            }
+        }
         "$/progress" => {
             if let Some(params) = notification.params {
                 if let Ok(progress) =
@@ -3054,6 +5048,7 @@ async fn handle_notification_dispatch(
                         if let Some(value) = value {
                             let _ = async_tx.send(AsyncMessage::LspProgress {
                                 language: language.to_string(),
+                                server: server.to_string(),
                                 token,
                                 value,
                             });
@@ -3100,6 +5095,21 @@ pub struct LspHandle {
     /// Client state
     state: Arc<Mutex<LspClientState>>,
 
+    /// Capabilities reported by the server in its initialize response
+    capabilities: Arc<Mutex<Option<ServerCapabilities>>>,
+
+    /// Process ID of the server, once it has been spawned
+    pid: Arc<Mutex<Option<u32>>>,
+
+    /// Whether JSON-RPC messages are traced, shared with the task
+    trace: Arc<AtomicBool>,
+
+    /// Settings answering `workspace/configuration`, shared with the task
+    settings: Arc<Mutex<Option<Value>>>,
+
+    /// File the server's stderr is written to
+    stderr_log_path: PathBuf,
+
     /// Runtime handle for blocking operations
     runtime: tokio::runtime::Handle,
 }
@@ -3111,40 +5121,59 @@ impl LspHandle {
         command: &str,
         args: &[String],
         language: String,
+        server: String,
         async_bridge: &AsyncBridge,
         process_limits: ProcessLimits,
     ) -> Result<Self, String> {
         let (command_tx, command_rx) = mpsc::channel(100); // Buffer up to 100 commands
         let async_tx = async_bridge.sender();
         let language_clone = language.clone();
+        let server_clone = server.clone();
         let command = command.to_string();
         let args = args.to_vec();
         let state = Arc::new(Mutex::new(LspClientState::Starting));
 
         // Create stderr log path in system temp directory (cross-platform)
-        let stderr_log_path =
-            std::env::temp_dir().join(format!("fresh-lsp-{}-{}.log", language, std::process::id()));
+        let stderr_log_path = std::env::temp_dir().join(format!(
+            "fresh-lsp-{}-{}-{}.log",
+            language,
+            server,
+            std::process::id()
+        ));
 
         // Send starting status
         let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
             language: language.clone(),
+            server,
             status: LspServerStatus::Starting,
         });
 
         let state_clone = state.clone();
         let stderr_log_path_clone = stderr_log_path.clone();
+        let pid = Arc::new(Mutex::new(None));
+        let pid_clone = pid.clone();
+        let trace = Arc::new(AtomicBool::new(false));
+        let trace_clone = trace.clone();
+        let settings = Arc::new(Mutex::new(None));
+        let settings_clone = settings.clone();
         runtime.spawn(async move {
             match LspTask::spawn(
                 &command,
                 &args,
-                language_clone.clone(),
-                async_tx.clone(),
+                ServerContext {
+                    language: language_clone.clone(),
+                    server: server_clone.clone(),
+                    async_tx: async_tx.clone(),
+                    trace: trace_clone,
+                    settings: settings_clone,
+                },
                 &process_limits,
                 stderr_log_path_clone.clone(),
             )
             .await
             {
                 Ok(task) => {
+                    *pid_clone.lock().unwrap() = task.process.id();
                     task.run(command_rx).await;
                 }
                 Err(e) => {
@@ -3157,6 +5186,7 @@ impl LspHandle {
 
                     let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                         language: language_clone.clone(),
+                        server: server_clone,
                         status: LspServerStatus::Error,
                     });
                     let _ = async_tx.send(AsyncMessage::LspError {
@@ -3171,6 +5201,11 @@ impl LspHandle {
         Ok(Self {
             command_tx,
             state,
+            capabilities: Arc::new(Mutex::new(None)),
+            pid,
+            trace,
+            settings,
+            stderr_log_path,
             runtime: runtime.clone(),
         })
     }
@@ -3202,6 +5237,7 @@ impl LspHandle {
         }
 
         let state = self.state.clone();
+        let capabilities = self.capabilities.clone();
 
         // Create a channel for the response, but don't wait for it
         let (tx, rx) = oneshot::channel();
@@ -3218,8 +5254,11 @@ impl LspHandle {
         let runtime = self.runtime.clone();
         runtime.spawn(async move {
             match tokio::time::timeout(std::time::Duration::from_secs(10), rx).await {
-                Ok(Ok(Ok(_))) => {
+                Ok(Ok(Ok(result))) => {
                     // Successfully initialized
+                    if let Ok(mut c) = capabilities.lock() {
+                        *c = Some(result.capabilities);
+                    }
                     if let Ok(mut s) = state.lock() {
                         let _ = s.transition_to(LspClientState::Running);
                     }
@@ -3259,6 +5298,55 @@ impl LspHandle {
         *self.state.lock().unwrap()
     }
 
+    /// Get the server capabilities (None until initialization completes)
+    pub fn capabilities(&self) -> Option<ServerCapabilities> {
+        self.capabilities.lock().unwrap().clone()
+    }
+
+    /// Get the server's process ID (None until the process has been spawned)
+    pub fn pid(&self) -> Option<u32> {
+        *self.pid.lock().unwrap()
+    }
+
+    /// Get the file the server's stderr is written to
+    pub fn stderr_log_path(&self) -> &std::path::Path {
+        &self.stderr_log_path
+    }
+
+    /// Whether JSON-RPC messages are being traced
+    pub fn is_tracing(&self) -> bool {
+        self.trace.load(Ordering::Relaxed)
+    }
+
+    /// Turn tracing on or off
+    ///
+    /// While on, every JSON-RPC message is forwarded as `AsyncMessage::LspTrace`, and the
+    /// server is asked (with `$/setTrace`) to send `$/logTrace` notifications.
+    pub fn set_trace(&self, verbose: bool) -> Result<(), String> {
+        self.trace.store(verbose, Ordering::Relaxed);
+        self.command_tx
+            .try_send(LspCommand::SetTrace { verbose })
+            .map_err(|_| "Failed to send set_trace command".to_string())
+    }
+
+    /// Set the settings that answer `workspace/configuration` requests
+    ///
+    /// They are also sent with `workspace/didChangeConfiguration`, right away if the
+    /// server is running or once it has been initialized.
+    pub fn set_settings(&self, settings: Value) -> Result<(), String> {
+        *self.settings.lock().unwrap() = Some(settings.clone());
+        self.command_tx
+            .try_send(LspCommand::DidChangeConfiguration { settings })
+            .map_err(|_| "Failed to send did_change_configuration command".to_string())
+    }
+
+    /// Add workspace folders, for files opened in other projects
+    pub fn did_change_workspace_folders(&self, added: Vec<Uri>) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DidChangeWorkspaceFolders { added })
+            .map_err(|_| "Failed to send did_change_workspace_folders command".to_string())
+    }
+
     /// Notify document opened
     pub fn did_open(&self, uri: Uri, text: String, language_id: String) -> Result<(), String> {
         // Send command to LspTask which will queue it if not initialized yet
@@ -3293,6 +5381,95 @@ impl LspHandle {
             .map_err(|_| "Failed to send did_save command".to_string())
     }
 
+    /// Send didClose notification
+    pub fn did_close(&self, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DidClose { uri })
+            .map_err(|_| "Failed to send did_close command".to_string())
+    }
+
+    /// Whether the server asked for `textDocument/willSave` notifications
+    pub fn wants_will_save(&self) -> bool {
+        self.text_document_sync()
+            .is_some_and(|sync| sync.will_save == Some(true))
+    }
+
+    /// Whether the server asked to edit documents before they are saved
+    pub fn wants_will_save_wait_until(&self) -> bool {
+        self.text_document_sync()
+            .is_some_and(|sync| sync.will_save_wait_until == Some(true))
+    }
+
+    fn text_document_sync(&self) -> Option<lsp_types::TextDocumentSyncOptions> {
+        match self.capabilities()?.text_document_sync? {
+            TextDocumentSyncCapability::Options(options) => Some(options),
+            TextDocumentSyncCapability::Kind(_) => None,
+        }
+    }
+
+    /// The file operations the server wants to hear about
+    pub fn file_operations(&self) -> Option<lsp_types::WorkspaceFileOperationsServerCapabilities> {
+        self.capabilities()?.workspace?.file_operations
+    }
+
+    /// Send willSave notification
+    pub fn will_save(
+        &self,
+        uri: Uri,
+        reason: lsp_types::TextDocumentSaveReason,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::WillSave { uri, reason })
+            .map_err(|_| "Failed to send will_save command".to_string())
+    }
+
+    /// Request the edits to apply before saving; they arrive as a formatting response
+    pub fn will_save_wait_until(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        reason: lsp_types::TextDocumentSaveReason,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::WillSaveWaitUntil {
+                request_id,
+                uri,
+                reason,
+            })
+            .map_err(|_| "Failed to send will_save_wait_until command".to_string())
+    }
+
+    /// Send didChangeWatchedFiles notification
+    pub fn did_change_watched_files(
+        &self,
+        changes: Vec<lsp_types::FileEvent>,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DidChangeWatchedFiles { changes })
+            .map_err(|_| "Failed to send did_change_watched_files command".to_string())
+    }
+
+    /// Request the edits to apply before files are renamed
+    ///
+    /// The caller waits on the returned receiver, since the rename must not happen first.
+    pub fn will_rename_files(
+        &self,
+        files: Vec<lsp_types::FileRename>,
+    ) -> Result<oneshot::Receiver<Result<Option<lsp_types::WorkspaceEdit>, String>>, String> {
+        let (response, rx) = oneshot::channel();
+        self.command_tx
+            .try_send(LspCommand::WillRenameFiles { files, response })
+            .map_err(|_| "Failed to send will_rename_files command".to_string())?;
+        Ok(rx)
+    }
+
+    /// Send didRenameFiles notification
+    pub fn did_rename_files(&self, files: Vec<lsp_types::FileRename>) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DidRenameFiles { files })
+            .map_err(|_| "Failed to send did_rename_files command".to_string())
+    }
+
     /// Request completion at position
     pub fn completion(
         &self,
@@ -3300,6 +5477,7 @@ impl LspHandle {
         uri: Uri,
         line: u32,
         character: u32,
+        incomplete_retrigger: bool,
     ) -> Result<(), String> {
         self.command_tx
             .try_send(LspCommand::Completion {
@@ -3307,26 +5485,40 @@ impl LspHandle {
                 uri,
                 line,
                 character,
+                incomplete_retrigger,
             })
             .map_err(|_| "Failed to send completion command".to_string())
     }
 
-    /// Request go-to-definition
-    pub fn goto_definition(
+    /// Resolve the documentation, detail and additional edits of a completion item
+    pub fn resolve_completion_item(
+        &self,
+        request_id: u64,
+        item: lsp_types::CompletionItem,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::ResolveCompletionItem { request_id, item })
+            .map_err(|_| "Failed to send resolve_completion_item command".to_string())
+    }
+
+    /// Request the definition, declaration, type definition or implementations
+    pub fn goto(
         &self,
         request_id: u64,
+        target: GotoTarget,
         uri: Uri,
         line: u32,
         character: u32,
     ) -> Result<(), String> {
         self.command_tx
-            .try_send(LspCommand::GotoDefinition {
+            .try_send(LspCommand::Goto {
                 request_id,
+                target,
                 uri,
                 line,
                 character,
             })
-            .map_err(|_| "Failed to send goto_definition command".to_string())
+            .map_err(|_| "Failed to send goto command".to_string())
     }
 
     /// Request rename
@@ -3423,10 +5615,58 @@ impl LspHandle {
                 end_line,
                 end_char,
                 diagnostics,
+                only: None,
             })
             .map_err(|_| "Failed to send code_actions command".to_string())
     }
 
+    /// Request the quick fixes for some diagnostics (code actions of kind `quickfix`)
+    pub fn quick_fixes(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        range: lsp_types::Range,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::CodeActions {
+                request_id,
+                uri,
+                start_line: range.start.line,
+                start_char: range.start.character,
+                end_line: range.end.line,
+                end_char: range.end.character,
+                diagnostics,
+                only: Some(vec![lsp_types::CodeActionKind::QUICKFIX]),
+            })
+            .map_err(|_| "Failed to send quick_fixes command".to_string())
+    }
+
+    /// Resolve the edit of a code action (`codeAction/resolve`)
+    pub fn resolve_code_action(
+        &self,
+        request_id: u64,
+        action: lsp_types::CodeAction,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::ResolveCodeAction { request_id, action })
+            .map_err(|_| "Failed to send resolve_code_action command".to_string())
+    }
+
+    /// Run a server command (`workspace/executeCommand`)
+    pub fn execute_command(
+        &self,
+        request_id: u64,
+        command: lsp_types::Command,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::ExecuteCommand {
+                request_id,
+                command,
+            })
+            .map_err(|_| "Failed to send execute_command command".to_string())
+    }
+
     /// Request document diagnostics (pull model)
     ///
     /// This sends a textDocument/diagnostic request to fetch diagnostics on demand.
@@ -3470,6 +5710,120 @@ impl LspHandle {
             .map_err(|_| "Failed to send inlay_hints command".to_string())
     }
 
+    /// Request formatting edits for the whole document, a range or a typed character
+    ///
+    /// On-type formatting is only meaningful for characters listed in the server's
+    /// `documentOnTypeFormattingProvider` trigger characters.
+    pub fn formatting(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        request: FormattingRequest,
+        tab_size: u32,
+        insert_spaces: bool,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::Formatting {
+                request_id,
+                uri,
+                request,
+                tab_size,
+                insert_spaces,
+            })
+            .map_err(|_| "Failed to send formatting command".to_string())
+    }
+
+    /// Request the symbol outline of a document
+    pub fn document_symbols(&self, request_id: u64, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DocumentSymbols { request_id, uri })
+            .map_err(|_| "Failed to send document_symbols command".to_string())
+    }
+
+    /// Request semantic tokens for a document
+    pub fn semantic_tokens(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        request: SemanticTokensRequest,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::SemanticTokens {
+                request_id,
+                uri,
+                request,
+            })
+            .map_err(|_| "Failed to send semantic_tokens command".to_string())
+    }
+
+    /// Request the code lenses of a document
+    pub fn code_lens(&self, request_id: u64, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::CodeLens { request_id, uri })
+            .map_err(|_| "Failed to send code_lens command".to_string())
+    }
+
+    /// Fill in the command of a code lens
+    pub fn resolve_code_lens(
+        &self,
+        request_id: u64,
+        lens: lsp_types::CodeLens,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::ResolveCodeLens { request_id, lens })
+            .map_err(|_| "Failed to send resolve_code_lens command".to_string())
+    }
+
+    /// Request the foldable ranges of a document
+    pub fn folding_ranges(&self, request_id: u64, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::FoldingRanges { request_id, uri })
+            .map_err(|_| "Failed to send folding_ranges command".to_string())
+    }
+
+    /// Find the call or type hierarchy item at a position
+    pub fn prepare_hierarchy(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        line: u32,
+        character: u32,
+        direction: HierarchyDirection,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::PrepareHierarchy {
+                request_id,
+                uri,
+                line,
+                character,
+                direction,
+            })
+            .map_err(|_| "Failed to send prepare_hierarchy command".to_string())
+    }
+
+    /// Request the items related to a hierarchy item
+    pub fn expand_hierarchy(
+        &self,
+        request_id: u64,
+        item: HierarchyItem,
+        direction: HierarchyDirection,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::ExpandHierarchy {
+                request_id,
+                item,
+                direction,
+            })
+            .map_err(|_| "Failed to send expand_hierarchy command".to_string())
+    }
+
+    /// Search symbols across the workspace
+    pub fn workspace_symbols(&self, request_id: u64, query: String) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::WorkspaceSymbols { request_id, query })
+            .map_err(|_| "Failed to send workspace_symbols command".to_string())
+    }
+
     /// Cancel a pending request by its editor request_id
     ///
     /// This sends a $/cancelRequest notification to the LSP server.
@@ -3691,6 +6045,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_configuration_response_looks_up_sections() {
+        let settings = serde_json::json!({
+            "rust-analyzer": {"cargo": {"features": "all"}},
+            "python": {"analysis": {"typeCheckingMode": "strict"}}
+        });
+        let params = serde_json::json!({"items": [
+            {"section": "rust-analyzer"},
+            {"section": "python.analysis"},
+            {"section": "gopls"},
+            {"scopeUri": "file:///project"}
+        ]});
+
+        assert_eq!(
+            configuration_response(Some(&settings), Some(&params)),
+            serde_json::json!([
+                {"cargo": {"features": "all"}},
+                {"typeCheckingMode": "strict"},
+                null,
+                settings.clone()
+            ])
+        );
+        assert_eq!(
+            configuration_response(None, Some(&params)),
+            serde_json::json!([null, null, null, null])
+        );
+    }
+
+    #[test]
+    fn test_supports_workspace_folder_changes() {
+        let caps =
+            |change_notifications: Option<lsp_types::OneOf<bool, String>>| ServerCapabilities {
+                workspace: Some(lsp_types::WorkspaceServerCapabilities {
+                    workspace_folders: Some(lsp_types::WorkspaceFoldersServerCapabilities {
+                        supported: Some(true),
+                        change_notifications,
+                    }),
+                    file_operations: None,
+                }),
+                ..Default::default()
+            };
+        assert!(supports_workspace_folder_changes(&caps(Some(
+            lsp_types::OneOf::Left(true)
+        ))));
+        assert!(supports_workspace_folder_changes(&caps(Some(
+            lsp_types::OneOf::Right("folders".to_string())
+        ))));
+        assert!(!supports_workspace_folder_changes(&caps(None)));
+        assert!(!supports_workspace_folder_changes(
+            &ServerCapabilities::default()
+        ));
+    }
+
     #[tokio::test]
     async fn test_lsp_handle_spawn_and_drop() {
         // This test spawns a mock LSP server (cat command that echoes input)
@@ -3705,6 +6112,7 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
         );
@@ -3731,6 +6139,7 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -3757,6 +6166,7 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -3789,6 +6199,7 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -3822,6 +6233,7 @@ mod tests {
             "this-command-does-not-exist-12345",
             &[],
             "test".to_string(),
+            "this-command-does-not-exist-12345".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
         );
@@ -3859,6 +6271,7 @@ mod tests {
                     "cat",
                     &[],
                     "test".to_string(),
+                    "cat".to_string(),
                     &async_bridge,
                     ProcessLimits::unlimited(),
                 )
@@ -3928,6 +6341,7 @@ mod tests {
             "cat", // Simple command that will exit immediately
             &[],
             "test".to_string(),
+            "cat".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -3977,6 +6391,7 @@ mod tests {
             "bash",
             &["-c".to_string(), fake_lsp_script.to_string()],
             "fake".to_string(),
+            "bash".to_string(),
             &async_bridge,
             ProcessLimits::unlimited(),
         )
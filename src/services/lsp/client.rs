@@ -479,6 +479,116 @@ pub struct LspServerConfig {
     /// For example, Deno requires `{"enable": true}` to enable completions.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub initialization_options: Option<serde_json::Value>,
+
+    /// Name shown in the status bar and messages when a language has several servers.
+    /// Defaults to the command's file name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Features this server is used for (all features if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub only_features: Option<Vec<LspFeature>>,
+
+    /// Features this server is never used for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub except_features: Option<Vec<LspFeature>>,
+
+    /// Settings returned for `workspace/configuration` requests and sent with
+    /// `workspace/didChangeConfiguration`, keyed by section
+    /// (e.g. `{"rust-analyzer": {"cargo": {"features": "all"}}}`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<serde_json::Value>,
+
+    /// Files that mark the root of a project, such as `Cargo.toml` or `package.json`.
+    /// The nearest directory above a file that contains one is the file's workspace
+    /// folder; without markers the working directory is used.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub root_markers: Vec<String>,
+}
+
+impl LspServerConfig {
+    /// Name identifying this server among the servers of its language
+    pub fn server_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            std::path::Path::new(&self.command)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.command.clone())
+        })
+    }
+
+    /// Whether this server should be used for a feature, according to its configuration
+    pub fn handles_feature(&self, feature: LspFeature) -> bool {
+        self.only_features
+            .as_ref()
+            .is_none_or(|only| only.contains(&feature))
+            && !self
+                .except_features
+                .as_ref()
+                .is_some_and(|except| except.contains(&feature))
+    }
+}
+
+/// An LSP feature that can be routed to some of a language's servers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LspFeature {
+    Diagnostics,
+    Completion,
+    Hover,
+    Definition,
+    Declaration,
+    TypeDefinition,
+    Implementation,
+    References,
+    Rename,
+    CodeAction,
+    SignatureHelp,
+    Formatting,
+    DocumentSymbols,
+    WorkspaceSymbols,
+    SemanticTokens,
+    FoldingRange,
+    InlayHints,
+    CallHierarchy,
+    TypeHierarchy,
+    CodeLens,
+}
+
+/// Language servers for a language: a single server, or a list of servers used together
+///
+/// With several servers, document changes go to all of them, diagnostics, completions
+/// and code actions are merged, and other requests go to the first server that handles
+/// the feature and advertises the capability.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum LspLanguageConfig {
+    Single(LspServerConfig),
+    Multiple(Vec<LspServerConfig>),
+}
+
+impl LspLanguageConfig {
+    /// The language's servers, in priority order
+    pub fn servers(&self) -> &[LspServerConfig] {
+        match self {
+            Self::Single(server) => std::slice::from_ref(server),
+            Self::Multiple(servers) => servers,
+        }
+    }
+
+    pub fn servers_mut(&mut self) -> &mut [LspServerConfig] {
+        match self {
+            Self::Single(server) => std::slice::from_mut(server),
+            Self::Multiple(servers) => servers,
+        }
+    }
+}
+
+impl From<LspServerConfig> for LspLanguageConfig {
+    fn from(server: LspServerConfig) -> Self {
+        Self::Single(server)
+    }
 }
 
 fn default_true() -> bool {
@@ -2,11 +2,16 @@
 ///!
 ///! This module handles converting LSP diagnostics to visual overlays in the editor.
 ///! Diagnostics are displayed as colored underlines (red for errors, yellow for warnings, etc.)
+///! and, above a configurable severity, as a message at the end of their line.
+use crate::config::InlineDiagnostics;
 use crate::model::buffer::Buffer;
 use crate::state::EditorState;
 use crate::view::overlay::{Overlay, OverlayFace, OverlayNamespace};
+use crate::view::virtual_text::{VirtualTextNamespace, VirtualTextPosition};
 use lsp_types::{Diagnostic, DiagnosticSeverity};
+use ratatui::style::Style;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 use std::sync::Mutex;
@@ -16,6 +21,11 @@ pub fn lsp_diagnostic_namespace() -> OverlayNamespace {
     OverlayNamespace::from_string("lsp-diagnostic".to_string())
 }
 
+/// Namespace for the diagnostic messages shown at the end of lines
+pub fn inline_diagnostic_namespace() -> VirtualTextNamespace {
+    VirtualTextNamespace::from_string("lsp-inline-diagnostic".to_string())
+}
+
 /// Cache for diagnostic hash to avoid redundant updates
 /// Using a global static with Mutex for simplicity - could be moved to EditorState later
 static DIAGNOSTIC_CACHE: Mutex<Option<u64>> = Mutex::new(None);
@@ -141,6 +151,36 @@ pub fn diagnostic_to_overlay(
     Some((start_byte..end_byte, face, priority))
 }
 
+/// Diagnostics of one document, kept separately for each server that publishes them
+///
+/// With several servers per language (e.g. a type checker and a linter), each publish
+/// replaces only that server's diagnostics, and the document shows all of them.
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsBySource {
+    /// Server name -> its latest diagnostics (ordered for a stable merge)
+    by_server: BTreeMap<String, Vec<Diagnostic>>,
+}
+
+impl DiagnosticsBySource {
+    /// Replace a server's diagnostics
+    pub fn update(&mut self, server: &str, diagnostics: Vec<Diagnostic>) {
+        if diagnostics.is_empty() {
+            self.by_server.remove(server);
+        } else {
+            self.by_server.insert(server.to_string(), diagnostics);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_server.is_empty()
+    }
+
+    /// All servers' diagnostics
+    pub fn merged(&self) -> Vec<Diagnostic> {
+        self.by_server.values().flatten().cloned().collect()
+    }
+}
+
 /// Apply LSP diagnostics to editor state as overlays
 ///
 /// This function:
@@ -178,6 +218,86 @@ pub fn apply_diagnostics_to_state(
     }
 }
 
+/// Show diagnostics at the end of their line as virtual text
+///
+/// Each line shows its most severe diagnostic among those `shown` allows (the first
+/// one when several are equally severe), with a count of the others.
+pub fn apply_inline_diagnostics_to_state(
+    state: &mut EditorState,
+    diagnostics: &[Diagnostic],
+    shown: InlineDiagnostics,
+    theme: &crate::view::theme::Theme,
+) {
+    let ns = inline_diagnostic_namespace();
+    state
+        .virtual_texts
+        .clear_namespace(&mut state.marker_list, &ns);
+
+    for (line, diagnostic, others) in inline_diagnostics(diagnostics, shown) {
+        let Some(start) = state.buffer.line_start_offset(line) else {
+            continue;
+        };
+        let end = state
+            .buffer
+            .line_start_offset(line + 1)
+            .unwrap_or_else(|| state.buffer.len());
+        if end <= start {
+            // An empty last line has no character to show the message after
+            continue;
+        }
+        // The text follows the line's newline, or its last character at the end of the file
+        let anchor = end - 1;
+
+        let color = match diagnostic.severity {
+            Some(DiagnosticSeverity::WARNING) => theme.diagnostic_warning_fg,
+            Some(DiagnosticSeverity::INFORMATION) => theme.diagnostic_info_fg,
+            Some(DiagnosticSeverity::HINT) => theme.diagnostic_hint_fg,
+            _ => theme.diagnostic_error_fg,
+        };
+        let message = diagnostic.message.lines().next().unwrap_or_default();
+        let text = if others > 0 {
+            format!("■ {} (+{})", message, others)
+        } else {
+            format!("■ {}", message)
+        };
+        state.virtual_texts.add_inline(
+            &mut state.marker_list,
+            anchor,
+            text,
+            Style::default().fg(color),
+            VirtualTextPosition::AfterChar,
+            ns.clone(),
+            0,
+        );
+    }
+}
+
+/// The diagnostic shown at the end of each line, with the number of other shown
+/// diagnostics on that line, in line order
+fn inline_diagnostics(
+    diagnostics: &[Diagnostic],
+    shown: InlineDiagnostics,
+) -> Vec<(usize, &Diagnostic, usize)> {
+    let mut by_line: BTreeMap<usize, (&Diagnostic, usize)> = BTreeMap::new();
+    let severity = |d: &Diagnostic| d.severity.unwrap_or(DiagnosticSeverity::ERROR);
+    for diagnostic in diagnostics.iter().filter(|d| shown.shows(d.severity)) {
+        let line = diagnostic.range.start.line as usize;
+        by_line
+            .entry(line)
+            .and_modify(|(best, others)| {
+                *others += 1;
+                if severity(diagnostic) < severity(best) {
+                    *best = diagnostic;
+                }
+            })
+            .or_insert((diagnostic, 0));
+    }
+    by_line
+        .into_iter()
+        .map(|(line, (diagnostic, others))| (line, diagnostic, others))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -325,4 +445,68 @@ mod tests {
         assert_eq!(range.start, 3);
         assert_eq!(range.end, 8);
     }
+
+    #[test]
+    fn test_inline_diagnostics_threshold_and_most_severe() {
+        let diagnostic = |line: u32, severity: DiagnosticSeverity, message: &str| Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, 1)),
+            severity: Some(severity),
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let diagnostics = vec![
+            diagnostic(2, DiagnosticSeverity::WARNING, "unused variable"),
+            diagnostic(2, DiagnosticSeverity::ERROR, "mismatched types"),
+            diagnostic(0, DiagnosticSeverity::HINT, "consider renaming"),
+        ];
+        let lines = |shown: InlineDiagnostics| -> Vec<(usize, String, usize)> {
+            inline_diagnostics(&diagnostics, shown)
+                .into_iter()
+                .map(|(line, d, others)| (line, d.message.clone(), others))
+                .collect()
+        };
+
+        assert_eq!(
+            lines(InlineDiagnostics::Warning),
+            vec![(2, "mismatched types".to_string(), 1)]
+        );
+        assert_eq!(
+            lines(InlineDiagnostics::Error),
+            vec![(2, "mismatched types".to_string(), 0)]
+        );
+        assert_eq!(
+            lines(InlineDiagnostics::Hint),
+            vec![
+                (0, "consider renaming".to_string(), 0),
+                (2, "mismatched types".to_string(), 1)
+            ]
+        );
+        assert!(lines(InlineDiagnostics::Off).is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics_by_source_replaces_per_server() {
+        let diagnostic = |message: &str| Diagnostic {
+            range: Range::new(Position::new(0, 0), Position::new(0, 1)),
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let messages = |set: &DiagnosticsBySource| -> Vec<String> {
+            set.merged().into_iter().map(|d| d.message).collect()
+        };
+
+        let mut set = DiagnosticsBySource::default();
+        set.update("pyright", vec![diagnostic("type error")]);
+        set.update("ruff", vec![diagnostic("unused import")]);
+        assert_eq!(messages(&set), vec!["type error", "unused import"]);
+
+        // A new publish replaces only that server's diagnostics
+        set.update("ruff", vec![diagnostic("line too long")]);
+        assert_eq!(messages(&set), vec!["type error", "line too long"]);
+
+        set.update("pyright", vec![]);
+        assert_eq!(messages(&set), vec!["line too long"]);
+        set.update("ruff", vec![]);
+        assert!(set.is_empty());
+    }
 }
@@ -0,0 +1,254 @@
+//! Glob patterns of file system watchers and file operation filters
+//!
+//! Servers register `workspace/didChangeWatchedFiles` watchers and declare which file
+//! renames they want to hear about with LSP glob patterns: `*` and `?` within a path
+//! segment, `**` across segments, `{a,b}` alternatives and `[...]`/`[!...]` ranges.
+
+use lsp_types::{
+    FileChangeType, FileOperationPatternKind, FileOperationRegistrationOptions, FileSystemWatcher,
+    GlobPattern, OneOf, WatchKind,
+};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// A compiled LSP glob pattern
+#[derive(Debug, Clone)]
+pub struct Glob {
+    regex: Regex,
+}
+
+impl Glob {
+    /// Compile a pattern; returns None if it is malformed
+    pub fn new(pattern: &str, ignore_case: bool) -> Option<Self> {
+        let mut regex = String::from(if ignore_case { "(?i)^" } else { "^" });
+        let mut chars = pattern.chars().peekable();
+        let mut braces = 0;
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                '{' => {
+                    braces += 1;
+                    regex.push_str("(?:");
+                }
+                '}' if braces > 0 => {
+                    braces -= 1;
+                    regex.push(')');
+                }
+                ',' if braces > 0 => regex.push('|'),
+                '[' => {
+                    regex.push('[');
+                    if chars.peek() == Some(&'!') {
+                        chars.next();
+                        regex.push('^');
+                    }
+                    loop {
+                        match chars.next()? {
+                            ']' => break,
+                            '\\' => regex.push_str("\\\\"),
+                            c => regex.push(c),
+                        }
+                    }
+                    regex.push(']');
+                }
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        if braces > 0 {
+            return None;
+        }
+        regex.push('$');
+        Regex::new(&regex).ok().map(|regex| Self { regex })
+    }
+
+    /// Whether a `/`-separated path matches the whole pattern
+    pub fn is_match(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
+
+/// A file system watcher registered by a server
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    /// Directory a relative pattern is matched against
+    base: Option<PathBuf>,
+    glob: Glob,
+    kind: WatchKind,
+}
+
+impl FileWatcher {
+    /// Compile a registered watcher; returns None if its pattern is unusable
+    pub fn new(watcher: &FileSystemWatcher) -> Option<Self> {
+        let (base, pattern) = match &watcher.glob_pattern {
+            GlobPattern::String(pattern) => (None, pattern),
+            GlobPattern::Relative(relative) => {
+                let uri = match &relative.base_uri {
+                    OneOf::Left(folder) => &folder.uri,
+                    OneOf::Right(uri) => uri,
+                };
+                let base = url::Url::parse(uri.as_str()).ok()?.to_file_path().ok()?;
+                (Some(base), &relative.pattern)
+            }
+        };
+        Some(Self {
+            base,
+            glob: Glob::new(pattern, false)?,
+            kind: watcher
+                .kind
+                .unwrap_or(WatchKind::Create | WatchKind::Change | WatchKind::Delete),
+        })
+    }
+
+    /// Whether the watcher wants to hear about a change to `path`
+    ///
+    /// Plain patterns are tried against the absolute path and against the path
+    /// relative to `root`, since servers use both forms.
+    pub fn matches(&self, path: &Path, change: FileChangeType, root: &Path) -> bool {
+        let kind = match change {
+            FileChangeType::CREATED => WatchKind::Create,
+            FileChangeType::DELETED => WatchKind::Delete,
+            _ => WatchKind::Change,
+        };
+        if !self.kind.contains(kind) {
+            return false;
+        }
+        match &self.base {
+            Some(base) => path
+                .strip_prefix(base)
+                .is_ok_and(|relative| self.glob.is_match(&slash_path(relative))),
+            None => {
+                self.glob.is_match(&slash_path(path))
+                    || path
+                        .strip_prefix(root)
+                        .is_ok_and(|relative| self.glob.is_match(&slash_path(relative)))
+            }
+        }
+    }
+}
+
+/// Whether a file operation (such as a rename) of `path` matches a server's filters
+pub fn file_operation_matches(
+    options: Option<&FileOperationRegistrationOptions>,
+    path: &Path,
+    is_dir: bool,
+) -> bool {
+    let Some(options) = options else {
+        return false;
+    };
+    options.filters.iter().any(|filter| {
+        let pattern = &filter.pattern;
+        let kind_matches = match pattern.matches {
+            Some(FileOperationPatternKind::File) => !is_dir,
+            Some(FileOperationPatternKind::Folder) => is_dir,
+            None => true,
+        };
+        let ignore_case = pattern
+            .options
+            .as_ref()
+            .and_then(|options| options.ignore_case)
+            .unwrap_or(false);
+        filter
+            .scheme
+            .as_deref()
+            .is_none_or(|scheme| scheme == "file")
+            && kind_matches
+            && Glob::new(&pattern.glob, ignore_case)
+                .is_some_and(|glob| glob.is_match(&slash_path(path)))
+    })
+}
+
+fn slash_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{FileOperationFilter, FileOperationPattern, RelativePattern, Uri};
+
+    fn glob(pattern: &str) -> Glob {
+        Glob::new(pattern, false).unwrap()
+    }
+
+    #[test]
+    fn test_glob_syntax() {
+        assert!(glob("**/*.rs").is_match("/home/user/src/main.rs"));
+        assert!(glob("**/*.rs").is_match("main.rs"));
+        assert!(!glob("**/*.rs").is_match("/home/user/main.rsx"));
+        assert!(glob("src/*.rs").is_match("src/lib.rs"));
+        assert!(!glob("src/*.rs").is_match("src/app/lib.rs"));
+        assert!(glob("**/Cargo.{toml,lock}").is_match("/p/Cargo.lock"));
+        assert!(glob("file.?").is_match("file.c"));
+        assert!(!glob("file.?").is_match("file.cc"));
+        assert!(glob("v[0-9]").is_match("v1"));
+        assert!(glob("v[!0-9]").is_match("vx"));
+        assert!(!glob("v[!0-9]").is_match("v1"));
+        assert!(glob("target/**").is_match("target/debug/build"));
+        assert!(Glob::new("**/*.{rs", false).is_none());
+        assert!(Glob::new("**/*.RS", true).unwrap().is_match("/p/a.rs"));
+    }
+
+    #[test]
+    fn test_watcher_kind_and_relative_pattern() {
+        let root = Path::new("/project");
+        let watcher = FileWatcher::new(&FileSystemWatcher {
+            glob_pattern: GlobPattern::String("**/*.rs".to_string()),
+            kind: Some(WatchKind::Create | WatchKind::Delete),
+        })
+        .unwrap();
+        let path = Path::new("/project/src/main.rs");
+        assert!(watcher.matches(path, FileChangeType::CREATED, root));
+        assert!(!watcher.matches(path, FileChangeType::CHANGED, root));
+
+        let relative = FileWatcher::new(&FileSystemWatcher {
+            glob_pattern: GlobPattern::Relative(RelativePattern {
+                base_uri: OneOf::Right("file:///project/src".parse::<Uri>().unwrap()),
+                pattern: "*.rs".to_string(),
+            }),
+            kind: None,
+        })
+        .unwrap();
+        assert!(relative.matches(path, FileChangeType::CHANGED, root));
+        assert!(!relative.matches(Path::new("/project/main.rs"), FileChangeType::CHANGED, root));
+
+        // Plain patterns also match paths relative to the root
+        let plain = FileWatcher::new(&FileSystemWatcher {
+            glob_pattern: GlobPattern::String("src/*.rs".to_string()),
+            kind: None,
+        })
+        .unwrap();
+        assert!(plain.matches(path, FileChangeType::DELETED, root));
+    }
+
+    #[test]
+    fn test_file_operation_filters() {
+        let options = FileOperationRegistrationOptions {
+            filters: vec![FileOperationFilter {
+                scheme: Some("file".to_string()),
+                pattern: FileOperationPattern {
+                    glob: "**/*.rs".to_string(),
+                    matches: Some(FileOperationPatternKind::File),
+                    options: None,
+                },
+            }],
+        };
+        let path = Path::new("/project/src/lib.rs");
+        assert!(file_operation_matches(Some(&options), path, false));
+        assert!(!file_operation_matches(Some(&options), path, true));
+        assert!(!file_operation_matches(
+            Some(&options),
+            Path::new("/project/README.md"),
+            false
+        ));
+        assert!(!file_operation_matches(None, path, false));
+    }
+}
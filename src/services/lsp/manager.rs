@@ -1,16 +1,24 @@
 //! LSP Manager - manages multiple language servers using async I/O
 //!
 //! This module provides a manager for multiple LSP servers that:
-//! - Spawns one server per language
+//! - Spawns the configured servers of each language (one or several)
 //! - Uses async LspHandle for non-blocking I/O
-//! - Routes requests to appropriate servers
+//! - Routes requests to appropriate servers, by feature and capability
+//! - Finds each file's project from root markers and adds it as a workspace folder
 //! - Configured via config.json
 
 use crate::services::async_bridge::AsyncBridge;
-use crate::services::lsp::async_handler::LspHandle;
-use crate::services::lsp::client::LspServerConfig;
-use lsp_types::Uri;
+use crate::services::lsp::async_handler::{LspClientState, LspHandle};
+use crate::services::lsp::client::{LspFeature, LspLanguageConfig, LspServerConfig};
+use crate::services::lsp::file_watch::FileWatcher;
+use lsp_types::{
+    CallHierarchyServerCapability, CodeActionProviderCapability, DeclarationCapability,
+    FileChangeType, FileEvent, FileSystemWatcher, FoldingRangeProviderCapability,
+    HoverProviderCapability, ImplementationProviderCapability, OneOf, ServerCapabilities,
+    TypeDefinitionProviderCapability, Uri,
+};
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 /// Result of attempting to spawn an LSP server
@@ -30,13 +38,104 @@ const MAX_RESTARTS_IN_WINDOW: usize = 5;
 const RESTART_WINDOW_SECS: u64 = 180; // 3 minutes
 const RESTART_BACKOFF_BASE_MS: u64 = 1000; // 1s, 2s, 4s, 8s...
 
+/// Identifies one server: its language and its name among that language's servers
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ServerKey {
+    language: String,
+    server: String,
+}
+
+impl ServerKey {
+    fn new(language: &str, server: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            server: server.to_string(),
+        }
+    }
+}
+
+/// Whether a server's capabilities cover a feature
+///
+/// Diagnostics are pushed by servers without a capability, and lsp-types has no type
+/// hierarchy capability, so both are always considered supported.
+fn capabilities_support(caps: &ServerCapabilities, feature: LspFeature) -> bool {
+    fn enabled<T>(provider: &Option<OneOf<bool, T>>) -> bool {
+        !matches!(provider, None | Some(OneOf::Left(false)))
+    }
+    match feature {
+        LspFeature::Diagnostics | LspFeature::TypeHierarchy => true,
+        LspFeature::Completion => caps.completion_provider.is_some(),
+        LspFeature::Hover => !matches!(
+            caps.hover_provider,
+            None | Some(HoverProviderCapability::Simple(false))
+        ),
+        LspFeature::Definition => enabled(&caps.definition_provider),
+        LspFeature::Declaration => !matches!(
+            caps.declaration_provider,
+            None | Some(DeclarationCapability::Simple(false))
+        ),
+        LspFeature::TypeDefinition => !matches!(
+            caps.type_definition_provider,
+            None | Some(TypeDefinitionProviderCapability::Simple(false))
+        ),
+        LspFeature::Implementation => !matches!(
+            caps.implementation_provider,
+            None | Some(ImplementationProviderCapability::Simple(false))
+        ),
+        LspFeature::References => enabled(&caps.references_provider),
+        LspFeature::Rename => enabled(&caps.rename_provider),
+        LspFeature::CodeAction => !matches!(
+            caps.code_action_provider,
+            None | Some(CodeActionProviderCapability::Simple(false))
+        ),
+        LspFeature::SignatureHelp => caps.signature_help_provider.is_some(),
+        LspFeature::Formatting => {
+            enabled(&caps.document_formatting_provider)
+                || enabled(&caps.document_range_formatting_provider)
+                || caps.document_on_type_formatting_provider.is_some()
+        }
+        LspFeature::DocumentSymbols => enabled(&caps.document_symbol_provider),
+        LspFeature::WorkspaceSymbols => enabled(&caps.workspace_symbol_provider),
+        LspFeature::SemanticTokens => caps.semantic_tokens_provider.is_some(),
+        LspFeature::FoldingRange => !matches!(
+            caps.folding_range_provider,
+            None | Some(FoldingRangeProviderCapability::Simple(false))
+        ),
+        LspFeature::InlayHints => enabled(&caps.inlay_hint_provider),
+        LspFeature::CodeLens => caps.code_lens_provider.is_some(),
+        LspFeature::CallHierarchy => !matches!(
+            caps.call_hierarchy_provider,
+            None | Some(CallHierarchyServerCapability::Simple(false))
+        ),
+    }
+}
+
+/// What the LSP Info command shows about a running server
+#[derive(Debug, Clone)]
+pub struct LspServerInfo {
+    pub language: String,
+    pub server: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub state: LspClientState,
+    pub pid: Option<u32>,
+    pub root_uri: Option<Uri>,
+    /// Workspace folders added after initialization, for files in other projects
+    pub workspace_folders: Vec<Uri>,
+    /// Times the server has been restarted, automatically or by the user
+    pub restarts: usize,
+    pub capabilities: Option<ServerCapabilities>,
+    pub stderr_log_path: PathBuf,
+    pub tracing: bool,
+}
+
 /// Manager for multiple language servers (async version)
 pub struct LspManager {
-    /// Map from language ID to LSP handle
-    handles: HashMap<String, LspHandle>,
+    /// Running servers
+    handles: HashMap<ServerKey, LspHandle>,
 
     /// Configuration for each language
-    config: HashMap<String, LspServerConfig>,
+    config: HashMap<String, LspLanguageConfig>,
 
     /// Root URI for workspace
     root_uri: Option<Uri>,
@@ -47,14 +146,14 @@ pub struct LspManager {
     /// Async bridge for communication
     async_bridge: Option<AsyncBridge>,
 
-    /// Restart attempt timestamps per language (for tracking restart frequency)
-    restart_attempts: HashMap<String, Vec<Instant>>,
+    /// Restart attempt timestamps per server (for tracking restart frequency)
+    restart_attempts: HashMap<ServerKey, Vec<Instant>>,
 
-    /// Languages currently in restart cooldown (gave up after too many restarts)
-    restart_cooldown: HashSet<String>,
+    /// Servers currently in restart cooldown (gave up after too many restarts)
+    restart_cooldown: HashSet<ServerKey>,
 
-    /// Scheduled restart times (language -> when to restart)
-    pending_restarts: HashMap<String, Instant>,
+    /// Scheduled restart times (server -> when to restart)
+    pending_restarts: HashMap<ServerKey, Instant>,
 
     /// Languages that have been manually started by the user
     /// If a language is in this set, it will spawn even if auto_start=false in config
@@ -63,6 +162,36 @@ pub struct LspManager {
     /// Languages that have been explicitly disabled/stopped by the user
     /// These will not auto-restart until user manually restarts them
     disabled_languages: HashSet<String>,
+
+    /// File watchers registered by each server, by registration ID
+    watched_files: HashMap<ServerKey, HashMap<String, Vec<FileWatcher>>>,
+
+    /// Number of restarts of each server since the project was opened
+    restart_counts: HashMap<ServerKey, usize>,
+
+    /// Whether JSON-RPC messages are traced, for running and newly spawned servers
+    trace: bool,
+
+    /// Workspace folders of each server, its root first
+    ///
+    /// Kept when a server crashes, so that it restarts with the same root.
+    workspace_folders: HashMap<ServerKey, Vec<Uri>>,
+}
+
+/// The project a file belongs to: the nearest directory above it containing one of
+/// the markers
+pub fn project_root(path: &Path, markers: &[String]) -> Option<PathBuf> {
+    if markers.is_empty() {
+        return None;
+    }
+    path.ancestors()
+        .skip(1)
+        .find(|dir| markers.iter().any(|marker| dir.join(marker).exists()))
+        .map(Path::to_path_buf)
+}
+
+fn path_to_uri(path: &Path) -> Option<Uri> {
+    url::Url::from_file_path(path).ok()?.as_str().parse().ok()
 }
 
 impl LspManager {
@@ -79,6 +208,10 @@ impl LspManager {
             pending_restarts: HashMap::new(),
             allowed_languages: HashSet::new(),
             disabled_languages: HashSet::new(),
+            watched_files: HashMap::new(),
+            restart_counts: HashMap::new(),
+            trace: false,
+            workspace_folders: HashMap::new(),
         }
     }
 
@@ -98,30 +231,52 @@ impl LspManager {
         &self.allowed_languages
     }
 
-    /// Get the configuration for a specific language
+    /// Get the configuration of a language's primary (first) server
     pub fn get_config(&self, language: &str) -> Option<&LspServerConfig> {
-        self.config.get(language)
+        self.server_configs(language).first()
+    }
+
+    /// Get the configurations of all of a language's servers, in priority order
+    pub fn server_configs(&self, language: &str) -> &[LspServerConfig] {
+        self.config
+            .get(language)
+            .map(|config| config.servers())
+            .unwrap_or_default()
+    }
+
+    /// Name a server in messages: the language alone when it has a single server
+    pub fn server_label(&self, language: &str, server: &str) -> String {
+        if self.server_configs(language).len() > 1 {
+            format!("{} ({})", language, server)
+        } else {
+            language.to_string()
+        }
     }
 
     /// Try to spawn an LSP server, checking auto_start configuration
     ///
     /// This is the main entry point for spawning LSP servers on file open.
     /// It returns:
-    /// - `LspSpawnResult::Spawned` if the server was spawned or already running
-    /// - `LspSpawnResult::NotAutoStart` if auto_start is false and not manually allowed
+    /// - `LspSpawnResult::Spawned` if a server was spawned or already running
+    /// - `LspSpawnResult::NotAutoStart` if no server auto-starts and the language isn't
+    ///   manually allowed
     /// - `LspSpawnResult::Failed` if spawn failed or language is disabled
-    pub fn try_spawn(&mut self, language: &str) -> LspSpawnResult {
-        // If handle already exists, return success
-        if self.handles.contains_key(language) {
+    ///
+    /// Servers spawned for a `file` are rooted at its project (see `project_root`).
+    pub fn try_spawn(&mut self, language: &str, file: Option<&Path>) -> LspSpawnResult {
+        // If a handle already exists, return success
+        if self.handles.keys().any(|key| key.language == language) {
             return LspSpawnResult::Spawned;
         }
 
         // Check if language is configured and enabled
-        let config = match self.config.get(language) {
-            Some(c) if c.enabled => c,
-            Some(_) => return LspSpawnResult::Failed, // Disabled
-            None => return LspSpawnResult::Failed,    // Not configured
-        };
+        let servers = self.server_configs(language);
+        if !servers.iter().any(|server| server.enabled) {
+            return LspSpawnResult::Failed; // Disabled or not configured
+        }
+        let auto_start = servers
+            .iter()
+            .any(|server| server.enabled && server.auto_start);
 
         // Check if we have runtime and bridge
         if self.runtime.is_none() || self.async_bridge.is_none() {
@@ -129,11 +284,12 @@ impl LspManager {
         }
 
         // Check if auto_start is enabled or language was manually allowed
-        if !config.auto_start && !self.allowed_languages.contains(language) {
+        if !auto_start && !self.allowed_languages.contains(language) {
             return LspSpawnResult::NotAutoStart;
         }
 
-        // Spawn the server
+        // Spawn the servers
+        self.spawn_missing(language, file);
         if self.get_or_spawn(language).is_some() {
             LspSpawnResult::Spawned
         } else {
@@ -150,7 +306,7 @@ impl LspManager {
     }
 
     /// Set configuration for a language
-    pub fn set_language_config(&mut self, language: String, config: LspServerConfig) {
+    pub fn set_language_config(&mut self, language: String, config: LspLanguageConfig) {
         self.config.insert(language, config);
     }
 
@@ -177,6 +333,7 @@ impl LspManager {
         self.restart_attempts.clear();
         self.restart_cooldown.clear();
         self.pending_restarts.clear();
+        self.restart_counts.clear();
 
         // Keep allowed_languages and disabled_languages as user preferences
         // Keep config as it's not project-specific
@@ -187,13 +344,96 @@ impl LspManager {
         );
     }
 
-    /// Get or spawn an LSP handle for a language
+    /// Get or spawn the LSP handles of a language, returning the primary one
+    ///
+    /// The primary handle is the first running server in configuration order. Use
+    /// `handle_for_feature` to pick the server for a request.
     pub fn get_or_spawn(&mut self, language: &str) -> Option<&mut LspHandle> {
-        // Return existing handle if available
-        if self.handles.contains_key(language) {
-            return self.handles.get_mut(language);
-        }
+        self.handles_for(language, None).into_iter().next()
+    }
 
+    /// Get or spawn the handle that serves a feature for a language
+    ///
+    /// This is the first running server in configuration order whose configuration
+    /// routes the feature to it and whose capabilities (once known) support it.
+    pub fn handle_for_feature(
+        &mut self,
+        language: &str,
+        feature: LspFeature,
+    ) -> Option<&mut LspHandle> {
+        self.handles_for(language, Some(feature)).into_iter().next()
+    }
+
+    /// Get or spawn every handle that serves a feature for a language, in priority order
+    ///
+    /// Used for requests whose results are merged across servers (completion, code actions).
+    pub fn handles_for_feature(
+        &mut self,
+        language: &str,
+        feature: LspFeature,
+    ) -> Vec<&mut LspHandle> {
+        self.handles_for(language, Some(feature))
+    }
+
+    /// Get or spawn every handle of a language, in priority order
+    ///
+    /// Used for document synchronization, which every server needs.
+    pub fn language_handles(&mut self, language: &str) -> Vec<&mut LspHandle> {
+        self.handles_for(language, None)
+    }
+
+    /// Get the running handle of one server of a language
+    ///
+    /// Used for follow-up requests that must reach the server that produced a result,
+    /// such as running the command of one of its code actions.
+    pub fn server_handle(&mut self, language: &str, server: &str) -> Option<&mut LspHandle> {
+        self.handles.get_mut(&ServerKey::new(language, server))
+    }
+
+    /// Whether a server's configuration routes a feature to it
+    pub fn server_handles_feature(
+        &self,
+        language: &str,
+        server: &str,
+        feature: LspFeature,
+    ) -> bool {
+        self.server_configs(language)
+            .iter()
+            .find(|config| config.server_name() == server)
+            .is_none_or(|config| config.handles_feature(feature))
+    }
+
+    fn handles_for(&mut self, language: &str, feature: Option<LspFeature>) -> Vec<&mut LspHandle> {
+        self.spawn_missing(language, None);
+
+        let order: Vec<String> = self
+            .server_configs(language)
+            .iter()
+            .filter(|config| feature.is_none_or(|feature| config.handles_feature(feature)))
+            .map(|config| config.server_name())
+            .collect();
+        let mut handles: Vec<(usize, &mut LspHandle)> = self
+            .handles
+            .iter_mut()
+            .filter(|(key, _)| key.language == language)
+            .filter_map(|(key, handle)| {
+                let index = order.iter().position(|server| *server == key.server)?;
+                let supported = feature.is_none_or(|feature| {
+                    handle
+                        .capabilities()
+                        .is_none_or(|caps| capabilities_support(&caps, feature))
+                });
+                supported.then_some((index, handle))
+            })
+            .collect();
+        handles.sort_by_key(|(index, _)| *index);
+        handles.into_iter().map(|(_, handle)| handle).collect()
+    }
+
+    /// Spawn the language's enabled servers that aren't running
+    ///
+    /// Servers waiting for a scheduled restart or in restart cooldown are left alone.
+    fn spawn_missing(&mut self, language: &str, file: Option<&Path>) {
         // Check if language was explicitly disabled by user (via stop command)
         // Don't auto-spawn disabled languages
         if self.disabled_languages.contains(language) {
@@ -201,102 +441,169 @@ impl LspManager {
                 "LSP for {} is disabled, not spawning (use manual restart to re-enable)",
                 language
             );
-            return None;
+            return;
         }
 
-        // Get config for this language
-        let config = self.config.get(language)?;
+        for server in self.missing_servers(language) {
+            self.spawn_server(language, &server, file);
+        }
+    }
+
+    /// Names of the language's servers that should be running but aren't
+    ///
+    /// Servers with `auto_start = false` only count once the language was
+    /// manually allowed.
+    fn missing_servers(&self, language: &str) -> Vec<String> {
+        let allowed = self.allowed_languages.contains(language);
+        self.server_configs(language)
+            .iter()
+            .filter(|config| config.enabled && (config.auto_start || allowed))
+            .map(|config| config.server_name())
+            .filter(|server| {
+                let key = ServerKey::new(language, server);
+                !self.handles.contains_key(&key)
+                    && !self.pending_restarts.contains_key(&key)
+                    && !self.restart_cooldown.contains(&key)
+            })
+            .collect()
+    }
+
+    /// Spawn one server; returns whether it started
+    ///
+    /// The server is rooted at the project of `file`, or else where it was rooted
+    /// before a restart, or else at the workspace root.
+    fn spawn_server(&mut self, language: &str, server: &str, file: Option<&Path>) -> bool {
+        let key = ServerKey::new(language, server);
+        // Get config for this server
+        let Some(config) = self
+            .server_configs(language)
+            .iter()
+            .find(|config| config.server_name() == server)
+        else {
+            return false;
+        };
 
         if !config.enabled {
-            return None;
+            return false;
         }
 
         // Check we have runtime and bridge
-        let runtime = self.runtime.as_ref()?;
-        let async_bridge = self.async_bridge.as_ref()?;
+        let (Some(runtime), Some(async_bridge)) =
+            (self.runtime.as_ref(), self.async_bridge.as_ref())
+        else {
+            return false;
+        };
+
+        let root_uri = file
+            .and_then(|file| project_root(file, &config.root_markers))
+            .and_then(|root| path_to_uri(&root))
+            .or_else(|| {
+                self.workspace_folders
+                    .get(&key)
+                    .and_then(|folders| folders.first().cloned())
+            })
+            .or_else(|| self.root_uri.clone());
 
         // Spawn new handle
-        tracing::info!("Spawning async LSP server for language: {}", language);
+        tracing::info!(
+            "Spawning async LSP server {} for language: {}",
+            server,
+            language
+        );
 
         match LspHandle::spawn(
             runtime,
             &config.command,
             &config.args,
             language.to_string(),
+            server.to_string(),
             async_bridge,
             config.process_limits.clone(),
         ) {
             Ok(handle) => {
+                if self.trace {
+                    let _ = handle.set_trace(true);
+                }
+                if let Some(settings) = &config.settings {
+                    let _ = handle.set_settings(settings.clone());
+                }
+
                 // Initialize the handle (non-blocking)
                 // The handle will become ready asynchronously
                 if let Err(e) =
-                    handle.initialize(self.root_uri.clone(), config.initialization_options.clone())
+                    handle.initialize(root_uri.clone(), config.initialization_options.clone())
                 {
                     tracing::error!("Failed to send initialize command for {}: {}", language, e);
-                    return None;
+                    return false;
                 }
 
                 tracing::info!(
-                    "LSP initialization started for {}, will be ready asynchronously",
-                    language
+                    "LSP initialization started for {} ({}), will be ready asynchronously",
+                    language,
+                    server
                 );
-                self.handles.insert(language.to_string(), handle);
-                self.handles.get_mut(language)
+                self.workspace_folders
+                    .insert(key.clone(), root_uri.into_iter().collect());
+                self.handles.insert(key, handle);
+                true
             }
             Err(e) => {
                 tracing::error!("Failed to spawn LSP handle for {}: {}", language, e);
-                None
+                false
             }
         }
     }
 
     /// Handle a server crash by scheduling a restart with exponential backoff
     ///
-    /// Returns a message describing the action taken (for UI notification)
-    pub fn handle_server_crash(&mut self, language: &str) -> String {
-        // Remove the crashed handle
-        if let Some(handle) = self.handles.remove(language) {
+    /// Restarts are tracked per server, so one crashing server of a language doesn't
+    /// hold back the others. Returns a message describing the action taken (for UI
+    /// notification).
+    pub fn handle_server_crash(&mut self, language: &str, server: &str) -> String {
+        let key = ServerKey::new(language, server);
+        let label = self.server_label(language, server);
+
+        // Remove the crashed handle; the restarted server registers its watchers again
+        if let Some(handle) = self.handles.remove(&key) {
             let _ = handle.shutdown(); // Best-effort cleanup
         }
+        self.watched_files.remove(&key);
 
         // Check if server was explicitly disabled by user (via stop command)
         // Don't auto-restart disabled servers
         if self.disabled_languages.contains(language) {
             return format!(
                 "LSP server for {} stopped. Use 'Restart LSP Server' command to start it again.",
-                language
+                label
             );
         }
 
         // Check if we're in cooldown
-        if self.restart_cooldown.contains(language) {
+        if self.restart_cooldown.contains(&key) {
             return format!(
                 "LSP server for {} crashed. Too many restarts - use 'Restart LSP Server' command to retry.",
-                language
+                label
             );
         }
 
         // Clean up old restart attempts outside the window
         let now = Instant::now();
         let window = Duration::from_secs(RESTART_WINDOW_SECS);
-        let attempts = self
-            .restart_attempts
-            .entry(language.to_string())
-            .or_default();
+        let attempts = self.restart_attempts.entry(key.clone()).or_default();
         attempts.retain(|t| now.duration_since(*t) < window);
 
         // Check if we've exceeded max restarts
         if attempts.len() >= MAX_RESTARTS_IN_WINDOW {
-            self.restart_cooldown.insert(language.to_string());
+            self.restart_cooldown.insert(key);
             tracing::warn!(
                 "LSP server for {} has crashed {} times in {} minutes, entering cooldown",
-                language,
+                label,
                 MAX_RESTARTS_IN_WINDOW,
                 RESTART_WINDOW_SECS / 60
             );
             return format!(
                 "LSP server for {} has crashed too many times ({} in {} min). Use 'Restart LSP Server' command to manually restart.",
-                language,
+                label,
                 MAX_RESTARTS_IN_WINDOW,
                 RESTART_WINDOW_SECS / 60
             );
@@ -308,12 +615,11 @@ impl LspManager {
         let restart_time = now + Duration::from_millis(delay_ms);
 
         // Schedule the restart
-        self.pending_restarts
-            .insert(language.to_string(), restart_time);
+        self.pending_restarts.insert(key, restart_time);
 
         tracing::info!(
             "LSP server for {} crashed (attempt {}/{}), will restart in {}ms",
-            language,
+            label,
             attempt_number + 1,
             MAX_RESTARTS_IN_WINDOW,
             delay_ms
@@ -321,7 +627,7 @@ impl LspManager {
 
         format!(
             "LSP server for {} crashed (attempt {}/{}), restarting in {}s...",
-            language,
+            label,
             attempt_number + 1,
             MAX_RESTARTS_IN_WINDOW,
             delay_ms / 1000
@@ -336,56 +642,64 @@ impl LspManager {
         let mut results = Vec::new();
 
         // Find restarts that are due
-        let due_restarts: Vec<String> = self
+        let due_restarts: Vec<ServerKey> = self
             .pending_restarts
             .iter()
             .filter(|(_, time)| **time <= now)
-            .map(|(lang, _)| lang.clone())
+            .map(|(key, _)| key.clone())
             .collect();
 
-        for language in due_restarts {
-            self.pending_restarts.remove(&language);
+        for key in due_restarts {
+            self.pending_restarts.remove(&key);
 
             // Record this restart attempt
             self.restart_attempts
-                .entry(language.clone())
+                .entry(key.clone())
                 .or_default()
                 .push(now);
+            *self.restart_counts.entry(key.clone()).or_default() += 1;
 
             // Attempt to spawn the server
-            if self.get_or_spawn(&language).is_some() {
-                let message = format!("LSP server for {} restarted successfully", language);
+            let label = self.server_label(&key.language, &key.server);
+            if self.spawn_server(&key.language, &key.server, None) {
+                let message = format!("LSP server for {} restarted successfully", label);
                 tracing::info!("{}", message);
-                results.push((language, true, message));
+                results.push((key.language, true, message));
             } else {
-                let message = format!("Failed to restart LSP server for {}", language);
+                let message = format!("Failed to restart LSP server for {}", label);
                 tracing::error!("{}", message);
-                results.push((language, false, message));
+                results.push((key.language, false, message));
             }
         }
 
         results
     }
 
-    /// Check if a language server is in restart cooldown
+    /// Check if any server of a language is in restart cooldown
     pub fn is_in_cooldown(&self, language: &str) -> bool {
-        self.restart_cooldown.contains(language)
+        self.restart_cooldown
+            .iter()
+            .any(|key| key.language == language)
     }
 
-    /// Check if a language server has a pending restart
+    /// Check if any server of a language has a pending restart
     pub fn has_pending_restart(&self, language: &str) -> bool {
-        self.pending_restarts.contains_key(language)
+        self.pending_restarts
+            .keys()
+            .any(|key| key.language == language)
     }
 
-    /// Clear cooldown for a language and allow manual restart
+    /// Clear cooldown for a language's servers and allow manual restart
     pub fn clear_cooldown(&mut self, language: &str) {
-        self.restart_cooldown.remove(language);
-        self.restart_attempts.remove(language);
-        self.pending_restarts.remove(language);
+        self.restart_cooldown.retain(|key| key.language != language);
+        self.restart_attempts
+            .retain(|key, _| key.language != language);
+        self.pending_restarts
+            .retain(|key, _| key.language != language);
         tracing::info!("Cleared restart cooldown for {}", language);
     }
 
-    /// Manually restart/start a language server (bypasses cooldown and auto_start check)
+    /// Manually restart/start a language's servers (bypasses cooldown and auto_start check)
     ///
     /// This is used both to restart a crashed server and to manually start a server
     /// that has auto_start=false in its configuration.
@@ -401,12 +715,15 @@ impl LspManager {
         // Add to allowed languages so it stays active even if auto_start=false
         self.allowed_languages.insert(language.to_string());
 
-        // Remove existing handle
-        if let Some(handle) = self.handles.remove(language) {
-            let _ = handle.shutdown();
+        // Remove existing handles, counting them as restarted
+        for key in self.handles.keys() {
+            if key.language == language {
+                *self.restart_counts.entry(key.clone()).or_default() += 1;
+            }
         }
+        self.shutdown_handles(language);
 
-        // Spawn new server
+        // Spawn new servers
         if self.get_or_spawn(language).is_some() {
             let message = format!("LSP server for {} started", language);
             tracing::info!("{}", message);
@@ -418,53 +735,109 @@ impl LspManager {
         }
     }
 
-    /// Get the number of recent restart attempts for a language
+    /// Get the number of recent restart attempts for a language's servers
     pub fn restart_attempt_count(&self, language: &str) -> usize {
         let now = Instant::now();
         let window = Duration::from_secs(RESTART_WINDOW_SECS);
         self.restart_attempts
-            .get(language)
-            .map(|attempts| {
+            .iter()
+            .filter(|(key, _)| key.language == language)
+            .map(|(_, attempts)| {
                 attempts
                     .iter()
                     .filter(|t| now.duration_since(**t) < window)
                     .count()
             })
-            .unwrap_or(0)
+            .sum()
+    }
+
+    /// Describe each running server, sorted by language and server name
+    pub fn server_infos(&self) -> Vec<LspServerInfo> {
+        let mut infos: Vec<LspServerInfo> = self
+            .handles
+            .iter()
+            .map(|(key, handle)| {
+                let config = self
+                    .server_configs(&key.language)
+                    .iter()
+                    .find(|config| config.server_name() == key.server);
+                let folders = self
+                    .workspace_folders
+                    .get(key)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                LspServerInfo {
+                    language: key.language.clone(),
+                    server: key.server.clone(),
+                    command: config.map(|c| c.command.clone()).unwrap_or_default(),
+                    args: config.map(|c| c.args.clone()).unwrap_or_default(),
+                    state: handle.state(),
+                    pid: handle.pid(),
+                    root_uri: folders.first().cloned(),
+                    workspace_folders: folders.iter().skip(1).cloned().collect(),
+                    restarts: self.restart_counts.get(key).copied().unwrap_or(0),
+                    capabilities: handle.capabilities(),
+                    stderr_log_path: handle.stderr_log_path().to_path_buf(),
+                    tracing: handle.is_tracing(),
+                }
+            })
+            .collect();
+        infos.sort_by(|a, b| (&a.language, &a.server).cmp(&(&b.language, &b.server)));
+        infos
+    }
+
+    /// Whether JSON-RPC messages are being traced
+    pub fn is_tracing(&self) -> bool {
+        self.trace
     }
 
-    /// Get a list of currently running LSP server languages
+    /// Turn tracing on or off for every server, including those spawned later
+    pub fn set_trace(&mut self, verbose: bool) {
+        self.trace = verbose;
+        for handle in self.handles.values() {
+            if let Err(e) = handle.set_trace(verbose) {
+                tracing::warn!("Failed to set LSP trace: {}", e);
+            }
+        }
+    }
+
+    /// Get a list of languages with running LSP servers
     pub fn running_servers(&self) -> Vec<String> {
-        self.handles.keys().cloned().collect()
+        let mut languages: Vec<String> = self
+            .handles
+            .keys()
+            .map(|key| key.language.clone())
+            .collect();
+        languages.sort();
+        languages.dedup();
+        languages
     }
 
     /// Check if an LSP server for a language is running and ready to serve requests
     pub fn is_server_ready(&self, language: &str) -> bool {
         self.handles
-            .get(language)
-            .map(|handle| handle.state().can_send_requests())
-            .unwrap_or(false)
+            .iter()
+            .any(|(key, handle)| key.language == language && handle.state().can_send_requests())
     }
 
-    /// Shutdown a specific language server
+    /// Shutdown a language's servers
     ///
-    /// This marks the server as disabled, preventing auto-restart until the user
+    /// This marks the language as disabled, preventing auto-restart until the user
     /// explicitly restarts it using the restart command.
     ///
-    /// Returns true if the server was found and shutdown, false otherwise
+    /// Returns true if a server was found and shutdown, false otherwise
     pub fn shutdown_server(&mut self, language: &str) -> bool {
-        if let Some(handle) = self.handles.remove(language) {
+        if self.shutdown_handles(language) {
             tracing::info!(
-                "Shutting down LSP server for {} (disabled until manual restart)",
+                "Shut down LSP server for {} (disabled until manual restart)",
                 language
             );
-            let _ = handle.shutdown();
             // Mark as disabled to prevent auto-restart
             self.disabled_languages.insert(language.to_string());
-            // Cancel any pending restarts
-            self.pending_restarts.remove(language);
-            // Remove from restart cooldown
-            self.restart_cooldown.remove(language);
+            // Cancel any pending restarts and remove from restart cooldown
+            self.pending_restarts
+                .retain(|key, _| key.language != language);
+            self.restart_cooldown.retain(|key| key.language != language);
             // Also remove from allowed languages so it will require confirmation again
             // if user tries to start it later
             self.allowed_languages.remove(language);
@@ -475,13 +848,156 @@ impl LspManager {
         }
     }
 
+    /// Shut down and remove a language's handles; returns whether there were any
+    fn shutdown_handles(&mut self, language: &str) -> bool {
+        let keys: Vec<ServerKey> = self
+            .handles
+            .keys()
+            .filter(|key| key.language == language)
+            .cloned()
+            .collect();
+        for key in &keys {
+            if let Some(handle) = self.handles.remove(key) {
+                let _ = handle.shutdown();
+            }
+            self.watched_files.remove(key);
+            self.workspace_folders.remove(key);
+        }
+        !keys.is_empty()
+    }
+
     /// Shutdown all language servers
     pub fn shutdown_all(&mut self) {
-        for (language, handle) in self.handles.iter() {
-            tracing::info!("Shutting down LSP server for {}", language);
+        for (key, handle) in self.handles.iter() {
+            tracing::info!(
+                "Shutting down LSP server {} for {}",
+                key.server,
+                key.language
+            );
             let _ = handle.shutdown();
         }
         self.handles.clear();
+        self.watched_files.clear();
+        self.workspace_folders.clear();
+    }
+
+    /// Every running handle, whatever its language
+    ///
+    /// Used for workspace-wide notifications such as file renames.
+    pub fn all_handles(&self) -> impl Iterator<Item = &LspHandle> {
+        self.handles.values()
+    }
+
+    /// Add the project of a file as a workspace folder of the language's running servers
+    ///
+    /// Called before a file is opened on the servers, so that a file in another project
+    /// of a monorepo is analyzed within that project. Servers without root markers, and
+    /// files outside any project, are left alone.
+    pub fn add_workspace_folder(&mut self, language: &str, file: &Path) {
+        for (key, handle) in &self.handles {
+            if key.language != language {
+                continue;
+            }
+            let Some(config) = self.config.get(language).and_then(|config| {
+                config
+                    .servers()
+                    .iter()
+                    .find(|config| config.server_name() == key.server)
+            }) else {
+                continue;
+            };
+            let Some(uri) =
+                project_root(file, &config.root_markers).and_then(|root| path_to_uri(&root))
+            else {
+                continue;
+            };
+            let folders = self.workspace_folders.entry(key.clone()).or_default();
+            if folders.contains(&uri) {
+                continue;
+            }
+            tracing::info!("Adding workspace folder {} to {}", uri.as_str(), key.server);
+            if let Err(e) = handle.did_change_workspace_folders(vec![uri.clone()]) {
+                tracing::warn!("Failed to add workspace folder: {}", e);
+                continue;
+            }
+            folders.push(uri);
+        }
+    }
+
+    /// Record the file watchers a server registered
+    ///
+    /// Watchers whose glob pattern can't be compiled are skipped.
+    pub fn register_watched_files(
+        &mut self,
+        language: &str,
+        server: &str,
+        id: String,
+        watchers: &[FileSystemWatcher],
+    ) {
+        let key = ServerKey::new(language, server);
+        if !self.handles.contains_key(&key) {
+            return;
+        }
+        let compiled: Vec<FileWatcher> = watchers.iter().filter_map(FileWatcher::new).collect();
+        tracing::info!(
+            "{} registered {} of {} file watchers",
+            self.server_label(language, server),
+            compiled.len(),
+            watchers.len()
+        );
+        self.watched_files
+            .entry(key)
+            .or_default()
+            .insert(id, compiled);
+    }
+
+    /// Drop the file watchers of one registration
+    pub fn unregister_watched_files(&mut self, language: &str, server: &str, id: &str) {
+        let key = ServerKey::new(language, server);
+        if let Some(registrations) = self.watched_files.get_mut(&key) {
+            registrations.remove(id);
+            if registrations.is_empty() {
+                self.watched_files.remove(&key);
+            }
+        }
+    }
+
+    /// Whether any server has registered file watchers
+    pub fn has_watched_files(&self) -> bool {
+        !self.watched_files.is_empty()
+    }
+
+    /// Send each server the changes its registered watchers match
+    pub fn notify_watched_files(&self, changes: &[(PathBuf, FileChangeType)], root: &Path) {
+        for (key, registrations) in &self.watched_files {
+            let Some(handle) = self.handles.get(key) else {
+                continue;
+            };
+            let events: Vec<FileEvent> = changes
+                .iter()
+                .filter(|(path, change)| {
+                    registrations
+                        .values()
+                        .flatten()
+                        .any(|watcher| watcher.matches(path, *change, root))
+                })
+                .filter_map(|(path, change)| {
+                    let uri = url::Url::from_file_path(path).ok()?;
+                    Some(FileEvent::new(uri.as_str().parse().ok()?, *change))
+                })
+                .collect();
+            if events.is_empty() {
+                continue;
+            }
+            tracing::debug!(
+                "Sending {} watched file changes to {}",
+                events.len(),
+                key.server
+            );
+            if let Err(e) = handle.did_change_watched_files(events) {
+                tracing::warn!("Failed to send watched file changes: {}", e);
+            }
+        }
     }
 }
 
@@ -537,13 +1053,18 @@ mod tests {
             process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
             auto_start: false,
             initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
         };
 
-        manager.set_language_config("rust".to_string(), config);
+        manager.set_language_config("rust".to_string(), config.into());
 
         assert_eq!(manager.config.len(), 1);
         assert!(manager.config.contains_key("rust"));
-        assert!(manager.config.get("rust").unwrap().enabled);
+        assert!(manager.get_config("rust").unwrap().enabled);
     }
 
     #[test]
@@ -560,7 +1081,13 @@ mod tests {
                 process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
                 auto_start: false,
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec![],
+            }
+            .into(),
         );
 
         // get_or_spawn should return None without runtime
@@ -599,7 +1126,13 @@ mod tests {
                 process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
                 auto_start: false,
                 initialization_options: None,
-            },
+                name: None,
+                only_features: None,
+                except_features: None,
+                settings: None,
+                root_markers: vec![],
+            }
+            .into(),
         );
 
         // get_or_spawn should return None for disabled language
@@ -607,6 +1140,39 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_missing_servers_respect_auto_start() {
+        let mut manager = LspManager::new(None);
+        let server = |name: &str, auto_start: bool| LspServerConfig {
+            enabled: true,
+            command: name.to_string(),
+            args: vec![],
+            process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
+            auto_start,
+            initialization_options: None,
+            name: Some(name.to_string()),
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        };
+        manager.set_language_config(
+            "python".to_string(),
+            LspLanguageConfig::Multiple(vec![server("pyright", true), server("ruff", false)]),
+        );
+
+        assert_eq!(
+            manager.missing_servers("python"),
+            vec!["pyright".to_string()]
+        );
+
+        manager.allow_language("python");
+        assert_eq!(
+            manager.missing_servers("python"),
+            vec!["pyright".to_string(), "ruff".to_string()]
+        );
+    }
+
     #[test]
     fn test_lsp_manager_shutdown_all() {
         let mut manager = LspManager::new(None);
@@ -616,6 +1182,30 @@ mod tests {
         assert_eq!(manager.handles.len(), 0);
     }
 
+    #[test]
+    fn test_project_root_finds_nearest_marker() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+        std::fs::create_dir_all(repo.join("crates/core/src")).unwrap();
+        std::fs::create_dir_all(repo.join("tools/src")).unwrap();
+        std::fs::write(repo.join("Cargo.toml"), "").unwrap();
+        std::fs::write(repo.join("crates/core/Cargo.toml"), "").unwrap();
+        let markers = vec!["Cargo.toml".to_string()];
+
+        assert_eq!(
+            project_root(&repo.join("crates/core/src/lib.rs"), &markers),
+            Some(repo.join("crates/core"))
+        );
+        assert_eq!(
+            project_root(&repo.join("tools/src/main.rs"), &markers),
+            Some(repo.to_path_buf())
+        );
+        assert_eq!(
+            project_root(&repo.join("crates/core/src/lib.rs"), &[]),
+            None
+        );
+    }
+
     fn test_languages() -> std::collections::HashMap<String, crate::config::LanguageConfig> {
         let mut languages = std::collections::HashMap::new();
         languages.insert(
@@ -627,6 +1217,8 @@ mod tests {
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
         languages.insert(
@@ -638,6 +1230,8 @@ mod tests {
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
         languages.insert(
@@ -649,6 +1243,8 @@ mod tests {
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_type: false,
             },
         );
         languages
@@ -3,4 +3,5 @@
 pub mod async_handler;
 pub mod client;
 pub mod diagnostics;
+pub mod file_watch;
 pub mod manager;
@@ -5,6 +5,7 @@
 
 pub mod async_bridge;
 pub mod clipboard;
+pub mod dap;
 pub mod fs;
 #[cfg(target_os = "linux")]
 pub mod gpm;
@@ -13,7 +14,9 @@ pub mod plugins;
 pub mod process_limits;
 pub mod recovery;
 pub mod release_checker;
+pub mod shell_command;
 pub mod signal_handler;
 pub mod terminal;
 pub mod tracing_setup;
+pub mod undo_history;
 pub mod warning_log;
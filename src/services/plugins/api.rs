@@ -604,6 +604,10 @@ pub enum PluginCommand {
         request_id: u64,
     },
 
+    /// Request the symbol outline of a buffer
+    /// Results arrive through the `document_symbols` hook
+    RequestDocumentSymbols { buffer_id: BufferId },
+
     /// Set the internal clipboard content
     SetClipboard { text: String },
 
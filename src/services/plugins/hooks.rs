@@ -7,6 +7,7 @@ use anyhow::{anyhow, Result};
 
 use crate::input::keybindings::Action;
 use crate::model::event::{BufferId, CursorId, SplitId};
+use crate::primitives::outline::OutlineSymbol;
 use crate::services::plugins::api::{ViewTokenWire, ViewTokenWireKind};
 use std::collections::HashMap;
 use std::ops::Range;
@@ -168,6 +169,15 @@ pub enum HookArgs {
         locations: Vec<LspLocation>,
     },
 
+    /// Document outline ready (response to a document symbols request)
+    DocumentSymbols {
+        buffer_id: BufferId,
+        /// Where the symbols came from: "lsp" or "tree-sitter"
+        source: String,
+        /// Symbols in document order, with nesting depth
+        symbols: Vec<OutlineSymbol>,
+    },
+
     /// View transform request - core pushes base tokens to plugins for transformation
     /// Plugins receive the tokenized viewport content and can transform it
     /// (e.g., converting newlines to soft breaks for markdown compose mode).
@@ -553,6 +563,32 @@ pub fn hook_args_to_json(args: &HookArgs) -> Result<String> {
                 .collect();
             serde_json::json!({ "symbol": symbol, "locations": locs })
         }
+        HookArgs::DocumentSymbols {
+            buffer_id,
+            source,
+            symbols,
+        } => {
+            let symbols_json: Vec<serde_json::Value> = symbols
+                .iter()
+                .map(|symbol| {
+                    serde_json::json!({
+                        "name": symbol.name,
+                        "detail": symbol.detail,
+                        "kind": symbol.kind,
+                        "depth": symbol.depth,
+                        "line": symbol.line,
+                        "start": symbol.range.start,
+                        "end": symbol.range.end,
+                        "selection_start": symbol.selection_start,
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "buffer_id": buffer_id.0,
+                "source": source,
+                "symbols": symbols_json,
+            })
+        }
         HookArgs::LinesChanged { buffer_id, lines } => {
             let lines_json: Vec<serde_json::Value> = lines
                 .iter()
@@ -2541,6 +2541,28 @@ async fn op_fresh_send_lsp_request(
     }
 }
 
+/// Request the symbol outline of a buffer
+///
+/// Uses the language server's textDocument/documentSymbol when a server is running,
+/// otherwise tree-sitter. Results are delivered via the "document_symbols" hook as
+/// { buffer_id, source, symbols } where each symbol has name, detail, kind, depth,
+/// line (1-indexed), start/end byte offsets and selection_start.
+/// @param buffer_id - The buffer ID
+/// @returns true if the request was queued
+#[op2(fast)]
+fn op_fresh_request_document_symbols(state: &mut OpState, buffer_id: u32) -> bool {
+    if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+        let runtime_state = runtime_state.borrow();
+        let result = runtime_state
+            .command_sender
+            .send(PluginCommand::RequestDocumentSymbols {
+                buffer_id: BufferId(buffer_id as usize),
+            });
+        return result.is_ok();
+    }
+    false
+}
+
 /// Define a buffer mode with keybindings
 /// @param name - Mode name (e.g., "diagnostics-list")
 /// @param parent - Parent mode name for inheritance (e.g., "special"), or null
@@ -2870,6 +2892,7 @@ extension!(
         op_fresh_create_virtual_buffer_in_existing_split,
         op_fresh_create_virtual_buffer,
         op_fresh_send_lsp_request,
+        op_fresh_request_document_symbols,
         op_fresh_define_mode,
         op_fresh_show_buffer,
         op_fresh_close_buffer,
@@ -3179,6 +3202,9 @@ impl TypeScriptRuntime {
                     sendLspRequest(language, method, params = null) {
                         return core.ops.op_fresh_send_lsp_request(language, method, params);
                     },
+                    requestDocumentSymbols(bufferId) {
+                        return core.ops.op_fresh_request_document_symbols(bufferId);
+                    },
 
                     // File system operations
                     readFile(path) {
@@ -0,0 +1,221 @@
+//! Shell commands run on text ("shell command on region")
+//!
+//! The text is written to the command's stdin and its stdout and stderr are
+//! collected. Commands run under the configured process limits, and a command
+//! still running when the timeout expires, or writing more output than
+//! [`MAX_OUTPUT_BYTES`], is killed.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::services::process_limits::ProcessLimits;
+
+/// Most bytes of stdout, and separately of stderr, kept from a shell command
+pub const MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+/// What to do with the output of a shell command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellOutputMode {
+    /// Replace the text the command read with its output
+    Replace,
+    /// Insert the output after the text (at the cursor without a selection)
+    Insert,
+    /// Show the output in a scratch buffer
+    Buffer,
+}
+
+/// Output of a shell command run on one piece of text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit code; `None` if the command couldn't be run, timed out or was killed
+    /// by a signal (`stderr` says which when it can)
+    pub exit_code: Option<i32>,
+}
+
+impl ShellOutput {
+    fn failed(message: String) -> Self {
+        Self {
+            stdout: String::new(),
+            stderr: message,
+            exit_code: None,
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// The system shell running `command`
+fn shell(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    }
+
+    #[cfg(not(windows))]
+    {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+/// Read `reader` to the end, failing once more than `limit` bytes were read
+async fn read_capped(
+    reader: Option<impl AsyncRead + Unpin>,
+    limit: usize,
+) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    if let Some(reader) = reader {
+        reader
+            .take(limit as u64 + 1)
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|e| format!("Failed to run command: {}", e))?;
+    }
+    if bytes.len() > limit {
+        return Err(format!(
+            "Output exceeded {} bytes and the command was stopped",
+            limit
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Run `command` in the shell with `input` on its stdin
+pub async fn run(
+    command: String,
+    input: String,
+    cwd: Option<PathBuf>,
+    limits: ProcessLimits,
+    timeout: Duration,
+) -> ShellOutput {
+    run_with_output_limit(command, input, cwd, limits, timeout, MAX_OUTPUT_BYTES).await
+}
+
+async fn run_with_output_limit(
+    command: String,
+    input: String,
+    cwd: Option<PathBuf>,
+    limits: ProcessLimits,
+    timeout: Duration,
+    max_output: usize,
+) -> ShellOutput {
+    let mut cmd = shell(&command);
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(dir) = cwd {
+        cmd.current_dir(dir);
+    }
+    if let Err(e) = limits.apply_to_command(&mut cmd) {
+        tracing::warn!("Failed to apply process limits to shell command: {}", e);
+    }
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return ShellOutput::failed(format!("Failed to run command: {}", e)),
+    };
+
+    // Write the input while the output is read, so that neither side blocks on a
+    // full pipe; closing stdin afterwards ends the command's input
+    let stdin = child.stdin.take();
+    let write_input = async move {
+        if let Some(mut stdin) = stdin {
+            // The command may exit without reading all of its input
+            let _ = stdin.write_all(input.as_bytes()).await;
+        }
+        Ok(())
+    };
+    let read_stdout = read_capped(child.stdout.take(), max_output);
+    let read_stderr = read_capped(child.stderr.take(), max_output);
+    let run = async move {
+        let (_, stdout, stderr) = match tokio::try_join!(write_input, read_stdout, read_stderr) {
+            Ok(output) => output,
+            Err(message) => {
+                // Nobody reads the pipes anymore, so the command would block
+                let _ = child.kill().await;
+                return Err(message);
+            }
+        };
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| format!("Failed to run command: {}", e))?;
+        Ok(ShellOutput {
+            stdout: String::from_utf8_lossy(&stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+            exit_code: status.code(),
+        })
+    };
+
+    // Dropping the child on timeout kills the command
+    match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(message)) => ShellOutput::failed(message),
+        Err(_) => ShellOutput::failed(format!("Timed out after {:?} and was stopped", timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run_with_timeout(command: &str, input: &str, timeout: Duration) -> ShellOutput {
+        run(
+            command.to_string(),
+            input.to_string(),
+            None,
+            ProcessLimits::unlimited(),
+            timeout,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_filters_input() {
+        let output = run_with_timeout("sort -u", "b\na\nb\n", Duration::from_secs(10)).await;
+        assert!(output.success());
+        assert_eq!(output.stdout, "a\nb\n");
+    }
+
+    #[tokio::test]
+    async fn test_failing_command() {
+        let output = run_with_timeout("echo oops >&2; exit 3", "", Duration::from_secs(10)).await;
+        assert_eq!(output.exit_code, Some(3));
+        assert_eq!(output.stderr, "oops\n");
+    }
+
+    #[tokio::test]
+    async fn test_oversized_output_is_stopped() {
+        let output = run_with_output_limit(
+            "yes".to_string(),
+            String::new(),
+            None,
+            ProcessLimits::unlimited(),
+            Duration::from_secs(10),
+            1000,
+        )
+        .await;
+        assert_eq!(output.exit_code, None);
+        assert!(output.stdout.is_empty());
+        assert!(output.stderr.contains("Output exceeded 1000 bytes"));
+    }
+
+    #[tokio::test]
+    async fn test_runaway_command_is_stopped() {
+        let output = run_with_timeout("sleep 10", "", Duration::from_millis(100)).await;
+        assert_eq!(output.exit_code, None);
+        assert!(output.stderr.contains("Timed out after 100ms"));
+    }
+}
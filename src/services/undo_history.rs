@@ -0,0 +1,343 @@
+//! Persistent undo history
+//!
+//! When a file buffer is closed (or the editor exits) its event log is written
+//! to the data directory, keyed by the file path. The log is only restored on
+//! the next open if the file on disk still has the content hash recorded when
+//! the log was written; otherwise the stored history is discarded, since its
+//! byte offsets no longer describe the file.
+//!
+//! ## File Layout
+//!
+//! ```text
+//! ~/.local/share/fresh/undo/
+//! ├── {path_hash}.meta.json     # Path, content hash, state in the undo tree
+//! ├── {path_hash}.events.jsonl  # The undo tree (JSON Lines)
+//! └── ...
+//! ```
+//!
+//! Histories are pruned oldest-first once their total size exceeds the
+//! configured limit.
+
+use crate::model::event::{EventLog, UndoPosition};
+use crate::services::recovery::path_hash;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Metadata stored next to each persisted event log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoHistoryMeta {
+    /// File the history belongs to
+    pub path: PathBuf,
+    /// SHA-256 of the buffer content at the time the log was written
+    pub content_hash: String,
+    /// State of the undo tree matching that content
+    pub position: UndoPosition,
+    /// When the history was written (seconds since epoch)
+    pub updated_at: u64,
+}
+
+/// Storage for undo histories of closed files
+#[derive(Debug)]
+pub struct UndoHistoryStore {
+    /// Directory holding the history files
+    dir: PathBuf,
+    /// Total size the stored histories may take up before the oldest are removed
+    max_bytes: u64,
+}
+
+impl UndoHistoryStore {
+    /// File extension for metadata files
+    const META_EXT: &'static str = "meta.json";
+    /// File extension for event log files
+    const EVENTS_EXT: &'static str = "events.jsonl";
+
+    /// Create a store in `dir` keeping at most `max_bytes` of history
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// Get the base directory
+    pub fn base_dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get paths for the history files of a file
+    fn history_paths(&self, path: &Path) -> (PathBuf, PathBuf) {
+        let id = path_hash(path);
+        (
+            self.dir.join(format!("{id}.{}", Self::META_EXT)),
+            self.dir.join(format!("{id}.{}", Self::EVENTS_EXT)),
+        )
+    }
+
+    /// Persist the event log of `path`, whose buffer currently holds `content`
+    ///
+    /// An empty log removes any stored history instead.
+    pub fn save(&self, path: &Path, content: &[u8], log: &EventLog) -> io::Result<()> {
+        if log.len() == 0 {
+            return self.delete(path);
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        let (meta_path, events_path) = self.history_paths(path);
+
+        // Files are written next to their final path and renamed into place, and the
+        // old metadata goes first: an interrupted save leaves a log without metadata,
+        // which is an orphan, rather than metadata describing another log
+        let temp_events_path = temp_path(&events_path);
+        log.save_to_file(&temp_events_path)?;
+        if meta_path.exists() {
+            fs::remove_file(&meta_path)?;
+        }
+        fs::rename(&temp_events_path, &events_path)?;
+
+        let meta = UndoHistoryMeta {
+            path: path.to_path_buf(),
+            content_hash: content_hash(content),
+            position: log.position(),
+            updated_at: now_secs(),
+        };
+        let json = serde_json::to_string_pretty(&meta)?;
+        let temp_meta_path = temp_path(&meta_path);
+        fs::write(&temp_meta_path, json)?;
+        fs::rename(&temp_meta_path, &meta_path)?;
+
+        tracing::debug!("Persisted {} undo events for {}", log.len(), path.display());
+        Ok(())
+    }
+
+    /// Load the stored history of `path` if it was written for `content`
+    ///
+    /// A history recorded for different content is deleted.
+    pub fn load(&self, path: &Path, content: &[u8]) -> io::Result<Option<EventLog>> {
+        let (meta_path, events_path) = self.history_paths(path);
+        let Some(meta) = Self::read_meta(&meta_path) else {
+            return Ok(None);
+        };
+
+        if meta.path != path || meta.content_hash != content_hash(content) {
+            tracing::debug!(
+                "Discarding stale undo history for {} (file changed)",
+                path.display()
+            );
+            self.delete(path)?;
+            return Ok(None);
+        }
+
+        let mut log = EventLog::load_from_file(&events_path)?;
+        log.restore_position(meta.position);
+        Ok(Some(log))
+    }
+
+    /// Delete the stored history of `path`
+    pub fn delete(&self, path: &Path) -> io::Result<()> {
+        let (meta_path, events_path) = self.history_paths(path);
+        for file in [meta_path, events_path] {
+            if file.exists() {
+                fs::remove_file(&file)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove orphaned files and the oldest histories beyond the size limit
+    ///
+    /// Returns the number of histories removed.
+    pub fn cleanup(&self) -> io::Result<usize> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+
+        let mut cleaned = 0;
+        // (updated_at, meta path, events path, total size)
+        let mut histories = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+
+            // Left behind by an interrupted save
+            if name.ends_with(".tmp") {
+                if fs::remove_file(&path).is_ok() {
+                    cleaned += 1;
+                }
+                continue;
+            }
+
+            if let Some(id) = name.strip_suffix(&format!(".{}", Self::EVENTS_EXT)) {
+                let meta_path = self.dir.join(format!("{id}.{}", Self::META_EXT));
+                if !meta_path.exists() && fs::remove_file(&path).is_ok() {
+                    cleaned += 1;
+                }
+                continue;
+            }
+
+            let Some(id) = name.strip_suffix(&format!(".{}", Self::META_EXT)) else {
+                continue;
+            };
+            let events_path = self.dir.join(format!("{id}.{}", Self::EVENTS_EXT));
+            let meta = Self::read_meta(&path);
+            let (Some(meta), true) = (meta, events_path.exists()) else {
+                let _ = fs::remove_file(&path);
+                let _ = fs::remove_file(&events_path);
+                cleaned += 1;
+                continue;
+            };
+
+            let size = file_size(&path) + file_size(&events_path);
+            histories.push((meta.updated_at, path, events_path, size));
+        }
+
+        let mut total: u64 = histories.iter().map(|(_, _, _, size)| size).sum();
+        // Oldest first
+        histories.sort_by_key(|(updated_at, _, _, _)| *updated_at);
+        for (_, meta_path, events_path, size) in histories {
+            if total <= self.max_bytes {
+                break;
+            }
+            let _ = fs::remove_file(&meta_path);
+            let _ = fs::remove_file(&events_path);
+            total = total.saturating_sub(size);
+            cleaned += 1;
+        }
+
+        if cleaned > 0 {
+            tracing::info!("Cleaned up {} undo histories", cleaned);
+        }
+
+        Ok(cleaned)
+    }
+
+    fn read_meta(meta_path: &Path) -> Option<UndoHistoryMeta> {
+        let json = fs::read_to_string(meta_path).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+}
+
+/// Hash buffer content for comparison with the file on disk
+pub fn content_hash(content: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(content);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Path a file is written to before being renamed to `path`
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::{CursorId, Event};
+    use tempfile::TempDir;
+
+    fn insert(position: usize, text: &str) -> Event {
+        Event::Insert {
+            position,
+            text: text.to_string(),
+            cursor_id: CursorId(0),
+        }
+    }
+
+    fn create_test_store(max_bytes: u64) -> (UndoHistoryStore, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let store = UndoHistoryStore::new(temp_dir.path().join("undo"), max_bytes);
+        (store, temp_dir)
+    }
+
+    #[test]
+    fn test_save_and_load_history() {
+        let (store, _temp) = create_test_store(u64::MAX);
+        let path = Path::new("/project/main.rs");
+
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        store.save(path, b"a", &log).unwrap();
+
+        let restored = store.load(path, b"a").unwrap().unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.current_index(), 1);
+        assert!(restored.can_redo());
+        assert!(restored.is_at_saved_position());
+    }
+
+    #[test]
+    fn test_changed_content_discards_history() {
+        let (store, _temp) = create_test_store(u64::MAX);
+        let path = Path::new("/project/main.rs");
+
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        store.save(path, b"a", &log).unwrap();
+
+        assert!(store.load(path, b"changed on disk").unwrap().is_none());
+        // The stale history is gone even for the original content
+        assert!(store.load(path, b"a").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cleanup_removes_oldest_beyond_limit() {
+        let (store, _temp) = create_test_store(0);
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        store.save(Path::new("/a"), b"a", &log).unwrap();
+        store.save(Path::new("/b"), b"a", &log).unwrap();
+
+        assert_eq!(store.cleanup().unwrap(), 2);
+        assert_eq!(fs::read_dir(store.base_dir()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_cleanup_removes_orphans() {
+        let (store, _temp) = create_test_store(u64::MAX);
+        fs::create_dir_all(store.base_dir()).unwrap();
+        fs::write(store.base_dir().join("dead.events.jsonl"), "").unwrap();
+        fs::write(store.base_dir().join("dead.events.jsonl.tmp"), "").unwrap();
+
+        assert_eq!(store.cleanup().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_save_leaves_no_temp_files() {
+        let (store, _temp) = create_test_store(u64::MAX);
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        store.save(Path::new("/a"), b"a", &log).unwrap();
+        log.append(insert(1, "b"));
+        store.save(Path::new("/a"), b"ab", &log).unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(store.base_dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 2);
+        assert!(names[0].ends_with(".events.jsonl"));
+        assert!(names[1].ends_with(".meta.json"));
+        assert_eq!(
+            store.load(Path::new("/a"), b"ab").unwrap().unwrap().len(),
+            2
+        );
+    }
+}
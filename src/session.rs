@@ -71,6 +71,10 @@ pub struct Session {
     #[serde(default)]
     pub bookmarks: HashMap<char, SerializedBookmark>,
 
+    /// Folded regions per file (relative to working_dir)
+    #[serde(default)]
+    pub folds: HashMap<PathBuf, Vec<SerializedFold>>,
+
     /// Open terminal sessions (for restoration)
     #[serde(default)]
     pub terminals: Vec<SerializedTerminalSession>,
@@ -266,6 +270,15 @@ pub struct SerializedBookmark {
     pub position: usize,
 }
 
+/// Serialized folded region (0-indexed lines, inclusive)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializedFold {
+    /// Header line that stays visible
+    pub start_line: usize,
+    /// Last hidden line
+    pub end_line: usize,
+}
+
 /// Reference to an open tab (file path or terminal index)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SerializedTabRef {
@@ -563,6 +576,7 @@ impl Session {
             histories: SessionHistories::default(),
             search_options: SearchOptions::default(),
             bookmarks: HashMap::new(),
+            folds: HashMap::new(),
             terminals: Vec::new(),
             saved_at: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
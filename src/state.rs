@@ -8,12 +8,15 @@ use crate::model::event::{
     PopupPositionData,
 };
 use crate::model::marker::MarkerList;
+use crate::model::snippet::SnippetSession;
 use crate::primitives::grammar_registry::GrammarRegistry;
 use crate::primitives::highlight_engine::HighlightEngine;
 use crate::primitives::highlighter::Language;
 use crate::primitives::indent::IndentCalculator;
 use crate::primitives::semantic_highlight::SemanticHighlighter;
+use crate::primitives::semantic_tokens::SemanticTokenStore;
 use crate::primitives::text_property::TextPropertyManager;
+use crate::view::folding::FoldManager;
 use crate::view::margin::{MarginAnnotation, MarginContent, MarginManager, MarginPosition};
 use crate::view::overlay::{Overlay, OverlayFace, OverlayManager, UnderlineStyle};
 use crate::view::popup::{Popup, PopupContent, PopupListItem, PopupManager, PopupPosition};
@@ -87,6 +90,15 @@ pub struct EditorState {
     /// Semantic highlighter for word occurrence highlighting
     pub semantic_highlighter: SemanticHighlighter,
 
+    /// LSP semantic tokens, painted above syntax highlighting
+    pub semantic_tokens: SemanticTokenStore,
+
+    /// Foldable ranges and folded regions
+    pub folds: FoldManager,
+
+    /// Tabstops of the snippet being filled in, if any
+    pub snippet: Option<SnippetSession>,
+
     /// View mode for this buffer (Source or Compose)
     pub view_mode: ViewMode,
 
@@ -125,6 +137,9 @@ impl EditorState {
             show_cursors: true,
             editing_disabled: false,
             semantic_highlighter: SemanticHighlighter::new(),
+            semantic_tokens: SemanticTokenStore::new(),
+            folds: FoldManager::new(),
+            snippet: None,
             view_mode: ViewMode::Source,
             compose_width: None,
             compose_prev_line_numbers: None,
@@ -202,6 +217,9 @@ impl EditorState {
             show_cursors: true,
             editing_disabled: false,
             semantic_highlighter,
+            semantic_tokens: SemanticTokenStore::new(),
+            folds: FoldManager::new(),
+            snippet: None,
             view_mode: ViewMode::Source,
             compose_width: None,
             compose_prev_line_numbers: None,
@@ -219,9 +237,21 @@ impl EditorState {
     ) {
         let newlines_inserted = text.matches('\n').count();
 
+        // Reveal folded text the edit touches, then shift the rest
+        self.folds.unfold_for_edit(&self.buffer, position..position);
+        if newlines_inserted > 0 && !self.folds.ranges().is_empty() {
+            let line = self.buffer.get_line_number(position);
+            self.folds
+                .adjust_ranges_for_edit(line, 0, newlines_inserted);
+        }
+
         // CRITICAL: Adjust markers BEFORE modifying buffer
         self.marker_list.adjust_for_insert(position, text.len());
         self.margins.adjust_for_insert(position, text.len());
+        self.folds.adjust_for_insert(position, text.len());
+        if let Some(snippet) = &mut self.snippet {
+            snippet.adjust_for_insert(position, text.len());
+        }
 
         // Insert text into buffer
         self.buffer.insert(position, text);
@@ -264,9 +294,20 @@ impl EditorState {
         let len = range.len();
         let newlines_deleted = deleted_text.matches('\n').count();
 
+        // Reveal folded text the edit touches, then shift the rest
+        self.folds.unfold_for_edit(&self.buffer, range.clone());
+        if newlines_deleted > 0 && !self.folds.ranges().is_empty() {
+            let line = self.buffer.get_line_number(range.start);
+            self.folds.adjust_ranges_for_edit(line, newlines_deleted, 0);
+        }
+
         // CRITICAL: Adjust markers BEFORE modifying buffer
         self.marker_list.adjust_for_delete(range.start, len);
         self.margins.adjust_for_delete(range.start, len);
+        self.folds.adjust_for_delete(range.start, len);
+        if let Some(snippet) = &mut self.snippet {
+            snippet.adjust_for_delete(range.start, len);
+        }
 
         // Delete from buffer
         self.buffer.delete(range.clone());
@@ -329,6 +370,9 @@ impl EditorState {
                     cursor.sticky_column = *new_sticky_column;
                 }
 
+                // A cursor moved into a folded region reveals it
+                self.folds.unfold_containing(&self.buffer, *new_position);
+
                 // Update primary cursor line number if this is the primary cursor
                 // Try to get exact line number from buffer, or estimate for large files
                 if *cursor_id == self.cursors.primary_id() {
@@ -653,6 +697,7 @@ fn convert_popup_data_to_popup(data: &PopupData) -> Popup {
         border_style: Style::default().fg(Color::Gray),
         background_style: Style::default().bg(Color::Rgb(30, 30, 30)),
         scroll_offset: 0,
+        side_popup: None,
     };
 
     popup
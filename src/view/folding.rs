@@ -0,0 +1,394 @@
+//! Folded regions of a buffer
+//!
+//! A fold keeps its header line visible and hides the lines below it. Folds are
+//! anchored with markers at the start of the header line and of the last hidden
+//! line, so edits elsewhere in the buffer move them with the text.
+
+use crate::model::buffer::Buffer;
+use crate::model::marker::{MarkerId, MarkerList};
+use crate::primitives::folding::FoldRange;
+use std::ops::Range;
+
+/// A folded region resolved against the current buffer contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldedRegion {
+    /// Header line that stays visible
+    pub header_line: usize,
+    /// Last hidden line
+    pub end_line: usize,
+    /// Bytes hidden by the fold (from the line after the header to the end of `end_line`)
+    pub hidden: Range<usize>,
+}
+
+/// Foldable ranges and folded regions for a buffer
+#[derive(Debug)]
+pub struct FoldManager {
+    /// Foldable ranges from the last refresh, sorted by start line
+    ranges: Vec<FoldRange>,
+
+    /// Folded regions as (header marker, last hidden line marker)
+    folds: Vec<(MarkerId, MarkerId)>,
+
+    /// Markers anchoring the folded regions
+    markers: MarkerList,
+}
+
+impl FoldManager {
+    pub fn new() -> Self {
+        Self {
+            ranges: Vec::new(),
+            folds: Vec::new(),
+            markers: MarkerList::new(),
+        }
+    }
+
+    /// Foldable ranges, sorted by start line
+    pub fn ranges(&self) -> &[FoldRange] {
+        &self.ranges
+    }
+
+    /// Replace the foldable ranges (after a refresh from LSP, tree-sitter or indentation)
+    pub fn set_ranges(&mut self, ranges: Vec<FoldRange>) {
+        self.ranges = ranges;
+    }
+
+    /// Whether nothing is folded
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    // =========================================================================
+    // Edit Propagation - called when buffer content changes
+    // =========================================================================
+
+    /// Adjust fold markers after an insertion
+    pub fn adjust_for_insert(&mut self, position: usize, length: usize) {
+        self.markers.adjust_for_insert(position, length);
+    }
+
+    /// Adjust fold markers after a deletion
+    pub fn adjust_for_delete(&mut self, position: usize, length: usize) {
+        self.markers.adjust_for_delete(position, length);
+    }
+
+    /// Shift foldable ranges after an edit replaced lines `line+1..=line+removed`
+    /// with `added` new lines, until the next refresh replaces them
+    pub fn adjust_ranges_for_edit(&mut self, line: usize, removed: usize, added: usize) {
+        if removed == added {
+            return;
+        }
+        let shift = |l: usize| {
+            if l <= line {
+                l
+            } else if l <= line + removed {
+                line + added.min(l - line)
+            } else {
+                l - removed + added
+            }
+        };
+        for range in &mut self.ranges {
+            range.start_line = shift(range.start_line);
+            range.end_line = shift(range.end_line);
+        }
+        self.ranges
+            .retain(|range| range.end_line > range.start_line);
+    }
+
+    /// Unfold regions an edit touches, so no text changes while hidden
+    ///
+    /// `range` is the edited byte range (empty for an insertion).
+    pub fn unfold_for_edit(&mut self, buffer: &Buffer, range: Range<usize>) {
+        if self.folds.is_empty() {
+            return;
+        }
+        self.remove_folds(buffer, |region| {
+            if range.is_empty() {
+                region.hidden.contains(&range.start)
+            } else {
+                // Includes deleting the newline that ends the header line
+                range.start < region.hidden.end && range.end >= region.hidden.start
+            }
+        });
+    }
+
+    // =========================================================================
+    // Queries
+    // =========================================================================
+
+    /// Folded regions sorted by header line; regions nested in another fold are omitted
+    pub fn folded_regions(&self, buffer: &Buffer) -> Vec<FoldedRegion> {
+        let mut regions: Vec<FoldedRegion> = self
+            .folds
+            .iter()
+            .filter_map(|&(start, end)| self.resolve(buffer, start, end))
+            .collect();
+        regions.sort_by_key(|region| (region.hidden.start, std::cmp::Reverse(region.hidden.end)));
+
+        let mut outermost: Vec<FoldedRegion> = Vec::new();
+        for region in regions {
+            match outermost.last() {
+                Some(last) if region.hidden.start < last.hidden.end => {}
+                _ => outermost.push(region),
+            }
+        }
+        outermost
+    }
+
+    /// Byte ranges hidden by folds, sorted and non-overlapping
+    pub fn hidden_ranges(&self, buffer: &Buffer) -> Vec<Range<usize>> {
+        if self.folds.is_empty() {
+            return Vec::new();
+        }
+        self.folded_regions(buffer)
+            .into_iter()
+            .map(|region| region.hidden)
+            .collect()
+    }
+
+    /// Folded line ranges, including folds nested inside other folds
+    pub fn folded_lines(&self, buffer: &Buffer) -> Vec<FoldRange> {
+        let mut lines: Vec<FoldRange> = self
+            .folds
+            .iter()
+            .filter_map(|&(start, end)| self.resolve(buffer, start, end))
+            .map(|region| FoldRange::new(region.header_line, region.end_line))
+            .collect();
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+
+    fn resolve(&self, buffer: &Buffer, start: MarkerId, end: MarkerId) -> Option<FoldedRegion> {
+        let header_line = buffer.get_line_number(self.markers.get_position(start)?);
+        let end_line = buffer.get_line_number(self.markers.get_position(end)?);
+        if end_line <= header_line {
+            return None;
+        }
+        let hidden_start = buffer.line_start_offset(header_line + 1)?;
+        let hidden_end = buffer
+            .line_start_offset(end_line + 1)
+            .unwrap_or_else(|| buffer.len());
+        Some(FoldedRegion {
+            header_line,
+            end_line,
+            hidden: hidden_start..hidden_end,
+        })
+    }
+
+    // =========================================================================
+    // Folding and unfolding
+    // =========================================================================
+
+    /// Fold a line range; returns false if it's already folded or invalid
+    pub fn fold(&mut self, buffer: &Buffer, range: FoldRange) -> bool {
+        if range.end_line <= range.start_line || self.folded_lines(buffer).contains(&range) {
+            return false;
+        }
+        let (Some(start), Some(end)) = (
+            buffer.line_start_offset(range.start_line),
+            buffer.line_start_offset(range.end_line),
+        ) else {
+            return false;
+        };
+        let start = self.markers.create(start, true);
+        let end = self.markers.create(end, true);
+        self.folds.push((start, end));
+        true
+    }
+
+    /// Fold the innermost unfolded range containing a line
+    pub fn fold_at_line(&mut self, buffer: &Buffer, line: usize) -> bool {
+        let folded = self.folded_lines(buffer);
+        let innermost = self
+            .ranges
+            .iter()
+            .filter(|range| range.contains_line(line) && !folded.contains(range))
+            .min_by_key(|range| range.end_line - range.start_line)
+            .copied();
+        innermost.is_some_and(|range| self.fold(buffer, range))
+    }
+
+    /// Unfold every fold containing a line; returns whether anything was unfolded
+    pub fn unfold_at_line(&mut self, buffer: &Buffer, line: usize) -> bool {
+        self.remove_folds(buffer, |region| {
+            region.header_line <= line && line <= region.end_line
+        })
+    }
+
+    /// Unfold the fold headed by a line, or fold the innermost range containing it
+    pub fn toggle_at_line(&mut self, buffer: &Buffer, line: usize) -> bool {
+        if self.remove_folds(buffer, |region| region.header_line == line) {
+            return true;
+        }
+        self.fold_at_line(buffer, line)
+    }
+
+    /// Fold every foldable range
+    pub fn fold_all(&mut self, buffer: &Buffer) {
+        for range in self.ranges.clone() {
+            self.fold(buffer, range);
+        }
+    }
+
+    /// Fold the ranges nested at `level` (1 = top level), except those containing `keep_line`
+    pub fn fold_level(&mut self, buffer: &Buffer, level: usize, keep_line: usize) {
+        // Ranges are sorted by start line, so enclosing ranges come first
+        let mut enclosing: Vec<usize> = Vec::new();
+        for range in self.ranges.clone() {
+            while enclosing.last().is_some_and(|&end| range.start_line > end) {
+                enclosing.pop();
+            }
+            if enclosing.len() + 1 == level && !range.contains_line(keep_line) {
+                self.fold(buffer, range);
+            }
+            enclosing.push(range.end_line);
+        }
+    }
+
+    /// Unfold everything
+    pub fn unfold_all(&mut self) {
+        for (start, end) in self.folds.drain(..) {
+            self.markers.delete(start);
+            self.markers.delete(end);
+        }
+    }
+
+    /// Unfold regions hiding a byte position; returns whether anything was unfolded
+    pub fn unfold_containing(&mut self, buffer: &Buffer, position: usize) -> bool {
+        if self.folds.is_empty() {
+            return false;
+        }
+        self.remove_folds(buffer, |region| region.hidden.contains(&position))
+    }
+
+    /// Remove folds whose resolved region matches, and folds that no longer resolve
+    fn remove_folds(&mut self, buffer: &Buffer, matches: impl Fn(&FoldedRegion) -> bool) -> bool {
+        let mut removed = false;
+        let mut kept = Vec::with_capacity(self.folds.len());
+        for (start, end) in std::mem::take(&mut self.folds) {
+            match self.resolve(buffer, start, end) {
+                Some(region) if !matches(&region) => kept.push((start, end)),
+                resolved => {
+                    removed |= resolved.is_some();
+                    self.markers.delete(start);
+                    self.markers.delete(end);
+                }
+            }
+        }
+        self.folds = kept;
+        removed
+    }
+}
+
+impl Default for FoldManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Move a cursor position that landed inside a hidden range out of it
+///
+/// Moving forward continues on the line after the fold; moving backward stops on
+/// the header line. The column is kept where the target line is long enough.
+pub fn skip_hidden(
+    buffer: &Buffer,
+    hidden: &[Range<usize>],
+    old_position: usize,
+    new_position: usize,
+) -> usize {
+    let Some(range) = hidden.iter().find(|range| range.contains(&new_position)) else {
+        return new_position;
+    };
+    let line = buffer.get_line_number(new_position);
+    let column = new_position - buffer.line_start_offset(line).unwrap_or(new_position);
+    let target_line = if new_position > old_position {
+        if range.end >= buffer.len() {
+            return buffer.len();
+        }
+        buffer.get_line_number(range.end)
+    } else {
+        buffer.get_line_number(range.start.saturating_sub(1))
+    };
+    let line_start = buffer.line_start_offset(target_line).unwrap_or(0);
+    let line_len = buffer
+        .get_line(target_line)
+        .map(|text| text.strip_suffix(b"\n").map_or(text.len(), <[u8]>::len))
+        .unwrap_or(0);
+    buffer.snap_to_char_boundary(line_start + column.min(line_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main() {\n    let a = 1;\n    let b = 2;\n}\nfn other() {}\n";
+
+    #[test]
+    fn test_fold_hides_lines_after_header() {
+        let buffer = Buffer::from_str_test(SOURCE);
+        let mut folds = FoldManager::new();
+        folds.set_ranges(vec![FoldRange::new(0, 2)]);
+
+        assert!(folds.fold_at_line(&buffer, 1));
+        assert_eq!(folds.hidden_ranges(&buffer), vec![12..42]);
+        assert_eq!(&SOURCE[12..42], "    let a = 1;\n    let b = 2;\n");
+
+        assert!(folds.toggle_at_line(&buffer, 0));
+        assert!(folds.is_empty());
+    }
+
+    #[test]
+    fn test_fold_follows_edits_above() {
+        let mut buffer = Buffer::from_str_test(SOURCE);
+        let mut folds = FoldManager::new();
+        folds.fold(&buffer, FoldRange::new(0, 2));
+
+        folds.adjust_for_insert(0, 3);
+        buffer.insert(0, "//\n");
+
+        assert_eq!(folds.folded_lines(&buffer), vec![FoldRange::new(1, 3)]);
+    }
+
+    #[test]
+    fn test_edit_inside_fold_unfolds() {
+        let buffer = Buffer::from_str_test(SOURCE);
+        let mut folds = FoldManager::new();
+        folds.fold(&buffer, FoldRange::new(0, 2));
+
+        folds.unfold_for_edit(&buffer, 5..5);
+        assert!(!folds.is_empty());
+        folds.unfold_for_edit(&buffer, 11..12);
+        assert!(folds.is_empty());
+    }
+
+    #[test]
+    fn test_fold_level_skips_cursor_range() {
+        let buffer = Buffer::from_str_test("a\n b\n  c\n  d\n e\nf\n g\n");
+        let mut folds = FoldManager::new();
+        folds.set_ranges(vec![
+            FoldRange::new(0, 4),
+            FoldRange::new(1, 3),
+            FoldRange::new(5, 6),
+        ]);
+
+        folds.fold_level(&buffer, 1, 6);
+        assert_eq!(folds.folded_lines(&buffer), vec![FoldRange::new(0, 4)]);
+
+        folds.unfold_all();
+        folds.fold_level(&buffer, 2, 6);
+        assert_eq!(folds.folded_lines(&buffer), vec![FoldRange::new(1, 3)]);
+    }
+
+    #[test]
+    fn test_skip_hidden_moves_past_fold() {
+        let buffer = Buffer::from_str_test(SOURCE);
+        let hidden = vec![12..42];
+
+        // Down from column 2 of the header lands on the closing brace line
+        assert_eq!(skip_hidden(&buffer, &hidden, 2, 14), 43);
+        // Up from the closing brace lands on the header
+        assert_eq!(skip_hidden(&buffer, &hidden, 43, 28), 1);
+        // Positions outside the fold are unchanged
+        assert_eq!(skip_hidden(&buffer, &hidden, 0, 5), 5);
+    }
+}
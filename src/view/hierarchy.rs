@@ -0,0 +1,384 @@
+//! Call and type hierarchy trees
+//!
+//! A hierarchy view starts with the items found at the cursor
+//! (`textDocument/prepareCallHierarchy` or `prepareTypeHierarchy`). Like the file
+//! tree, nodes load their children lazily: expanding a node asks the server for its
+//! callers, callees, supertypes or subtypes. The tree is rendered as the text of a
+//! read-only virtual buffer, one node per line, with each line tagged by its node ID.
+
+use std::collections::HashMap;
+
+use lsp_types::{Position, Uri};
+use serde_json::json;
+
+use crate::primitives::outline::symbol_kind_name;
+use crate::primitives::text_property::TextPropertyEntry;
+use crate::services::lsp::async_handler::{HierarchyDirection, HierarchyEntry, HierarchyItem};
+use crate::view::file_tree::{NodeId, NodeState};
+
+/// Text property key holding the node ID of a line
+pub const HIERARCHY_NODE_PROPERTY: &str = "hierarchy_node";
+
+/// A node of a hierarchy tree
+#[derive(Debug, Clone)]
+pub struct HierarchyNode {
+    pub id: NodeId,
+    pub item: HierarchyItem,
+    /// Number of call sites (0 for roots and types)
+    pub call_count: usize,
+    /// Where Enter jumps: the call site for calls, the declaration otherwise
+    pub target: (Uri, Position),
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+    pub state: NodeState,
+    /// The item already appears among its ancestors (recursion); never expanded
+    pub recursive: bool,
+}
+
+/// A lazily expanded call or type hierarchy
+#[derive(Debug)]
+pub struct HierarchyTree {
+    direction: HierarchyDirection,
+    /// Language of the server that produced the items
+    language: String,
+    nodes: HashMap<NodeId, HierarchyNode>,
+    roots: Vec<NodeId>,
+    next_id: usize,
+}
+
+impl HierarchyTree {
+    /// Create a tree from the items returned by a prepare request
+    pub fn new(direction: HierarchyDirection, language: String, items: Vec<HierarchyItem>) -> Self {
+        let mut tree = Self {
+            direction,
+            language,
+            nodes: HashMap::new(),
+            roots: Vec::new(),
+            next_id: 0,
+        };
+        for item in items {
+            let target = (item.uri().clone(), item.selection_range().start);
+            let id = tree.insert(item, target, 0, None, false);
+            tree.roots.push(id);
+        }
+        tree
+    }
+
+    pub fn direction(&self) -> HierarchyDirection {
+        self.direction
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn get_node(&self, id: NodeId) -> Option<&HierarchyNode> {
+        self.nodes.get(&id)
+    }
+
+    fn insert(
+        &mut self,
+        item: HierarchyItem,
+        target: (Uri, Position),
+        call_count: usize,
+        parent: Option<NodeId>,
+        recursive: bool,
+    ) -> NodeId {
+        let id = NodeId(self.next_id);
+        self.next_id += 1;
+        self.nodes.insert(
+            id,
+            HierarchyNode {
+                id,
+                item,
+                call_count,
+                target,
+                parent,
+                children: Vec::new(),
+                state: if recursive {
+                    NodeState::Leaf
+                } else {
+                    NodeState::Collapsed
+                },
+                recursive,
+            },
+        );
+        id
+    }
+
+    /// Mark a node as waiting for its children; returns false if it can't be expanded
+    pub fn start_loading(&mut self, id: NodeId) -> bool {
+        match self.nodes.get_mut(&id) {
+            Some(node) if matches!(node.state, NodeState::Collapsed | NodeState::Error(_)) => {
+                node.state = NodeState::Loading;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Store the children of an expanded node
+    ///
+    /// A node without related items becomes a leaf.
+    pub fn set_children(&mut self, id: NodeId, entries: Vec<HierarchyEntry>) {
+        let Some(parent) = self.nodes.get(&id) else {
+            return;
+        };
+        let parent_uri = parent.item.uri().clone();
+        let ancestors: Vec<HierarchyItem> = self
+            .ancestors(id)
+            .iter()
+            .filter_map(|ancestor| self.nodes.get(ancestor).map(|node| node.item.clone()))
+            .collect();
+
+        let mut children = Vec::new();
+        for entry in entries {
+            // Incoming call sites are in the caller, outgoing ones in the expanded item
+            let target = match (self.direction, entry.from_ranges.first()) {
+                (HierarchyDirection::IncomingCalls, Some(range)) => {
+                    (entry.item.uri().clone(), range.start)
+                }
+                (HierarchyDirection::OutgoingCalls, Some(range)) => {
+                    (parent_uri.clone(), range.start)
+                }
+                _ => (entry.item.uri().clone(), entry.item.selection_range().start),
+            };
+            let recursive = ancestors.contains(&entry.item);
+            let child = self.insert(
+                entry.item,
+                target,
+                entry.from_ranges.len(),
+                Some(id),
+                recursive,
+            );
+            children.push(child);
+        }
+
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.state = if children.is_empty() {
+                NodeState::Leaf
+            } else {
+                NodeState::Expanded
+            };
+            node.children = children;
+        }
+    }
+
+    /// Record a failed expansion
+    pub fn set_error(&mut self, id: NodeId, error: String) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.state = NodeState::Error(error);
+        }
+    }
+
+    /// Collapse a node, dropping its descendants; they are requested again on expand
+    pub fn collapse(&mut self, id: NodeId) {
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return;
+        };
+        if node.state != NodeState::Expanded {
+            return;
+        }
+        node.state = NodeState::Collapsed;
+        let mut stack = std::mem::take(&mut node.children);
+        while let Some(child) = stack.pop() {
+            if let Some(removed) = self.nodes.remove(&child) {
+                stack.extend(removed.children);
+            }
+        }
+    }
+
+    /// The node and its ancestors, from the node up to its root
+    fn ancestors(&self, id: NodeId) -> Vec<NodeId> {
+        let mut ancestors = Vec::new();
+        let mut current = Some(id);
+        while let Some(node_id) = current {
+            ancestors.push(node_id);
+            current = self.nodes.get(&node_id).and_then(|node| node.parent);
+        }
+        ancestors
+    }
+
+    /// Visible nodes in display order, with their depth
+    pub fn visible_nodes(&self) -> Vec<(NodeId, usize)> {
+        let mut visible = Vec::new();
+        let mut stack: Vec<(NodeId, usize)> =
+            self.roots.iter().rev().map(|&root| (root, 0)).collect();
+        while let Some((id, depth)) = stack.pop() {
+            visible.push((id, depth));
+            if let Some(node) = self.nodes.get(&id) {
+                if node.state == NodeState::Expanded {
+                    stack.extend(node.children.iter().rev().map(|&child| (child, depth + 1)));
+                }
+            }
+        }
+        visible
+    }
+
+    /// Render the tree as buffer text, one line per visible node
+    ///
+    /// `describe_location` formats the file and line shown after each name.
+    pub fn render(
+        &self,
+        describe_location: impl Fn(&Uri, Position) -> String,
+    ) -> Vec<TextPropertyEntry> {
+        let mut entries = vec![
+            TextPropertyEntry::text(format!("{}\n", self.direction.title())),
+            TextPropertyEntry::text(
+                "Enter: go to location  Tab: expand/collapse  q: close\n\n".to_string(),
+            ),
+        ];
+        if self.roots.is_empty() {
+            entries.push(TextPropertyEntry::text("No results\n"));
+        }
+
+        for (id, depth) in self.visible_nodes() {
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+            let indicator = match node.state {
+                NodeState::Expanded => "▼ ",
+                NodeState::Collapsed => "▶ ",
+                NodeState::Loading => "⟳ ",
+                NodeState::Error(_) => "! ",
+                NodeState::Leaf => "  ",
+            };
+            let mut line = format!(
+                "{}{}{} {}  {}",
+                "  ".repeat(depth),
+                indicator,
+                symbol_kind_name(node.item.kind()),
+                node.item.name(),
+                describe_location(&node.target.0, node.target.1),
+            );
+            if node.call_count > 1 {
+                line.push_str(&format!(" ({} calls)", node.call_count));
+            }
+            if node.recursive {
+                line.push_str(" (recursive)");
+            }
+            if let NodeState::Error(error) = &node.state {
+                line.push_str(&format!(" ({})", error));
+            }
+            line.push('\n');
+            entries.push(
+                TextPropertyEntry::text(line).with_property(HIERARCHY_NODE_PROPERTY, json!(id.0)),
+            );
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{CallHierarchyItem, Range, SymbolKind};
+    use std::str::FromStr;
+
+    fn item(name: &str, line: u32) -> HierarchyItem {
+        let range = Range::new(
+            Position::new(line, 3),
+            Position::new(line, 3 + name.len() as u32),
+        );
+        HierarchyItem::Call(CallHierarchyItem {
+            name: name.to_string(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: Uri::from_str(&format!("file:///src/{}.rs", name)).unwrap(),
+            range,
+            selection_range: range,
+            data: None,
+        })
+    }
+
+    fn call(name: &str, line: u32, sites: &[u32]) -> HierarchyEntry {
+        HierarchyEntry {
+            item: item(name, line),
+            from_ranges: sites
+                .iter()
+                .map(|&site| Range::new(Position::new(site, 4), Position::new(site, 8)))
+                .collect(),
+        }
+    }
+
+    fn lines(tree: &HierarchyTree) -> Vec<String> {
+        tree.render(|uri, position| format!("{}:{}", uri.as_str(), position.line + 1))
+            .into_iter()
+            .skip(2)
+            .map(|entry| entry.text.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_and_collapse() {
+        let mut tree = HierarchyTree::new(
+            HierarchyDirection::IncomingCalls,
+            "rust".to_string(),
+            vec![item("target", 0)],
+        );
+        let root = tree.roots()[0];
+        assert_eq!(
+            lines(&tree),
+            vec!["▶ function target  file:///src/target.rs:1"]
+        );
+
+        assert!(tree.start_loading(root));
+        assert!(!tree.start_loading(root));
+        tree.set_children(root, vec![call("a", 10, &[20, 30]), call("b", 5, &[])]);
+        assert_eq!(
+            lines(&tree),
+            vec![
+                "▼ function target  file:///src/target.rs:1",
+                "  ▶ function a  file:///src/a.rs:21 (2 calls)",
+                "  ▶ function b  file:///src/b.rs:6",
+            ]
+        );
+
+        let a = tree.get_node(root).unwrap().children[0];
+        tree.start_loading(a);
+        tree.set_children(a, vec![]);
+        assert_eq!(tree.get_node(a).unwrap().state, NodeState::Leaf);
+
+        tree.collapse(root);
+        assert_eq!(lines(&tree).len(), 1);
+        assert!(tree.get_node(a).is_none());
+    }
+
+    #[test]
+    fn test_outgoing_call_site_is_in_the_caller() {
+        let mut tree = HierarchyTree::new(
+            HierarchyDirection::OutgoingCalls,
+            "rust".to_string(),
+            vec![item("caller", 0)],
+        );
+        let root = tree.roots()[0];
+        tree.start_loading(root);
+        tree.set_children(root, vec![call("callee", 40, &[7])]);
+
+        let callee = tree.get_node(root).unwrap().children[0];
+        let (uri, position) = &tree.get_node(callee).unwrap().target;
+        assert_eq!(uri.as_str(), "file:///src/caller.rs");
+        assert_eq!(*position, Position::new(7, 4));
+    }
+
+    #[test]
+    fn test_recursive_calls_are_not_expandable() {
+        let mut tree = HierarchyTree::new(
+            HierarchyDirection::IncomingCalls,
+            "rust".to_string(),
+            vec![item("recurse", 0)],
+        );
+        let root = tree.roots()[0];
+        tree.start_loading(root);
+        tree.set_children(root, vec![call("recurse", 0, &[2])]);
+
+        let child = tree.get_node(root).unwrap().children[0];
+        assert!(tree.get_node(child).unwrap().recursive);
+        assert!(!tree.start_loading(child));
+    }
+}
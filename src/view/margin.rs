@@ -56,6 +56,24 @@ impl LineIndicator {
     }
 }
 
+/// Fold state of a line, shown in the gutter in place of the separator's leading space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldIndicator {
+    /// The line starts a foldable region
+    Foldable,
+    /// The line is the header of a folded region
+    Folded,
+}
+
+impl FoldIndicator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            FoldIndicator::Foldable => "▾",
+            FoldIndicator::Folded => "▸",
+        }
+    }
+}
+
 /// Content type for a margin at a specific line
 #[derive(Debug, Clone, PartialEq)]
 pub enum MarginContent {
@@ -174,6 +192,14 @@ impl MarginConfig {
         }
     }
 
+    /// Separator for a line, with a fold indicator replacing its leading space
+    pub fn separator_with_fold(&self, fold: Option<FoldIndicator>) -> String {
+        match (fold, self.separator.strip_prefix(' ')) {
+            (Some(fold), Some(rest)) => format!("{}{}", fold.symbol(), rest),
+            _ => self.separator.clone(),
+        }
+    }
+
     /// Calculate the total width including indicator column and separator
     /// Format: [indicator (1 char)][line_number (N chars)][separator (3 chars)]
     pub fn total_width(&self) -> usize {
@@ -302,6 +328,9 @@ pub struct MarginManager {
     /// Whether to show line numbers by default
     pub show_line_numbers: bool,
 
+    /// Whether to mark foldable lines in the gutter (folded lines are always marked)
+    pub show_fold_indicators: bool,
+
     /// Diagnostic indicators per line (displayed between line numbers and separator)
     /// Maps line number to (symbol, color) tuple
     diagnostic_indicators: BTreeMap<usize, (String, Color)>,
@@ -325,6 +354,7 @@ impl MarginManager {
             left_annotations: BTreeMap::new(),
             right_annotations: BTreeMap::new(),
             show_line_numbers: true,
+            show_fold_indicators: true,
             diagnostic_indicators: BTreeMap::new(),
             indicator_markers: MarkerList::new(),
             line_indicators: BTreeMap::new(),
@@ -428,6 +458,20 @@ impl MarginManager {
         }
     }
 
+    /// Byte positions of the line indicators of a namespace, with their marker IDs
+    ///
+    /// Positions follow edits, so this is where indicators such as breakpoints are now.
+    pub fn line_indicator_positions(&self, namespace: &str) -> Vec<(MarkerId, usize)> {
+        self.line_indicators
+            .iter()
+            .filter(|(_, indicators)| indicators.contains_key(namespace))
+            .filter_map(|(&marker_id, _)| {
+                let position = self.indicator_markers.get_position(MarkerId(marker_id))?;
+                Some((MarkerId(marker_id), position))
+            })
+            .collect()
+    }
+
     /// Get the line indicator for a specific line number
     ///
     /// This looks up all indicators whose markers resolve to the given line.
@@ -692,6 +736,23 @@ mod tests {
         assert_eq!(config.total_width(), 0);
     }
 
+    #[test]
+    fn test_separator_with_fold_keeps_width() {
+        let config = MarginConfig::left_default();
+        assert_eq!(config.separator_with_fold(None), " │ ");
+        assert_eq!(
+            config.separator_with_fold(Some(FoldIndicator::Folded)),
+            "▸│ "
+        );
+        assert_eq!(
+            config
+                .separator_with_fold(Some(FoldIndicator::Foldable))
+                .chars()
+                .count(),
+            3
+        );
+    }
+
     #[test]
     fn test_margin_annotation_helpers() {
         let line_num = MarginAnnotation::line_number(5);
@@ -1003,4 +1064,27 @@ mod tests {
         assert!(manager.get_line_indicator(7, byte_to_line).is_some());
         assert!(manager.get_line_indicator(9, byte_to_line).is_some());
     }
+
+    #[test]
+    fn test_line_indicator_positions_of_namespace() {
+        let mut manager = MarginManager::new();
+        let breakpoint = manager.set_line_indicator(
+            line_to_byte(2),
+            "debug-breakpoint".to_string(),
+            LineIndicator::new("●", Color::Red, 30),
+        );
+        manager.set_line_indicator(
+            line_to_byte(4),
+            "git-gutter".to_string(),
+            LineIndicator::new("│", Color::Green, 10),
+        );
+
+        manager.adjust_for_insert(0, 10);
+
+        assert_eq!(
+            manager.line_indicator_positions("debug-breakpoint"),
+            vec![(breakpoint, line_to_byte(3))]
+        );
+        assert!(manager.line_indicator_positions("lsp-quick-fix").is_empty());
+    }
 }
@@ -3,6 +3,8 @@
 //! This module contains all presentation and rendering components.
 
 pub mod file_tree;
+pub mod folding;
+pub mod hierarchy;
 pub mod margin;
 pub mod overlay;
 pub mod popup;
@@ -11,5 +13,6 @@ pub mod split;
 pub mod stream;
 pub mod theme;
 pub mod ui;
+pub mod undo_tree;
 pub mod viewport;
 pub mod virtual_text;
@@ -294,6 +294,9 @@ pub struct Popup {
 
     /// Scroll offset for content (for scrolling through long lists)
     pub scroll_offset: usize,
+
+    /// Secondary popup shown beside this one (e.g. documentation of the selected item)
+    pub side_popup: Option<Box<Popup>>,
 }
 
 impl Popup {
@@ -309,6 +312,7 @@ impl Popup {
             border_style: Style::default().fg(theme.popup_border_fg),
             background_style: Style::default().bg(theme.popup_bg),
             scroll_offset: 0,
+            side_popup: None,
         }
     }
 
@@ -325,6 +329,15 @@ impl Popup {
             border_style: Style::default().fg(theme.popup_border_fg),
             background_style: Style::default().bg(theme.popup_bg),
             scroll_offset: 0,
+            side_popup: None,
+        }
+    }
+
+    /// Create a new popup with already styled lines using theme colors
+    pub fn styled(lines: Vec<StyledLine>, theme: &crate::view::theme::Theme) -> Self {
+        Self {
+            content: PopupContent::Markdown(lines),
+            ..Self::text(Vec::new(), theme)
         }
     }
 
@@ -340,6 +353,7 @@ impl Popup {
             border_style: Style::default().fg(theme.popup_border_fg),
             background_style: Style::default().bg(theme.popup_bg),
             scroll_offset: 0,
+            side_popup: None,
         }
     }
 
@@ -373,6 +387,12 @@ impl Popup {
         self
     }
 
+    /// Set the popup shown beside this one
+    pub fn with_side_popup(mut self, side_popup: Popup) -> Self {
+        self.side_popup = Some(Box::new(side_popup));
+        self
+    }
+
     /// Get the currently selected item (if this is a list popup)
     pub fn selected_item(&self) -> Option<&PopupListItem> {
         match &self.content {
@@ -521,6 +541,38 @@ impl Popup {
         }
     }
 
+    /// Calculate the area of the side popup, given this popup's area
+    ///
+    /// The side popup goes to the right of this one, or to the left when there isn't
+    /// room; it is top-aligned with it and at most as wide as the free space.
+    pub fn side_popup_area(&self, area: Rect, terminal_area: Rect) -> Option<Rect> {
+        let side = self.side_popup.as_ref()?;
+        let right_space = terminal_area
+            .width
+            .saturating_sub(area.x.saturating_add(area.width));
+        let left_space = area.x.saturating_sub(terminal_area.x);
+        let (x, width) = if right_space >= left_space {
+            (area.x + area.width, side.width.min(right_space))
+        } else {
+            let width = side.width.min(left_space);
+            (area.x - width, width)
+        };
+        // Too narrow to show anything useful
+        if width < 10 {
+            return None;
+        }
+        let height = side
+            .content_height()
+            .min(side.max_height)
+            .min(terminal_area.height.saturating_sub(area.y));
+        Some(Rect {
+            x,
+            y: area.y,
+            width,
+            height,
+        })
+    }
+
     /// Render the popup to the frame
     pub fn render(&self, frame: &mut Frame, area: Rect, theme: &crate::view::theme::Theme) {
         self.render_with_hover(frame, area, theme, None);
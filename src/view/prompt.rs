@@ -30,6 +30,10 @@ pub enum PromptType {
     Command,
     /// Go to a specific line number
     GotoLine,
+    /// Fold every region nested at a level
+    FoldLevel,
+    /// Move through the undo history by time (e.g. "5m")
+    UndoToTime { later: bool },
     /// Choose an ANSI background file
     SetBackgroundFile,
     /// Set background blend ratio (0-1)
@@ -53,10 +57,30 @@ pub enum PromptType {
     SetBookmark,
     /// Jump to a bookmark - prompts for register (0-9)
     JumpToBookmark,
+    /// Copy the selection to a named register - prompts for register (a-z)
+    CopyToRegister,
+    /// Insert a named register - prompts for register (a-z)
+    InsertRegister,
+    /// Paste an entry of the clipboard history (select from list)
+    PasteFromHistory,
+    /// Text for every line of a rectangle; `replace` replaces the rectangle's text
+    RectangleText { replace: bool },
+    /// Start (and step) of the numbers inserted in a rectangle
+    RectangleNumberLines,
+    /// Shell command run on the selection or buffer
+    ShellCommand {
+        mode: crate::services::shell_command::ShellOutputMode,
+    },
     /// Set compose width (empty clears to viewport)
     SetComposeWidth,
     /// Stop a running LSP server (select from list)
     StopLspServer,
+    /// Show the log of an LSP server (select from list)
+    ShowLspLog,
+    /// Add a watch expression to the debugger
+    AddWatch,
+    /// Run a vim `:` command
+    VimCommand,
     /// Select a theme (select from list)
     SelectTheme,
     /// Select a keybinding map (select from list)
@@ -80,6 +104,8 @@ pub enum PromptType {
     },
     /// Switch to a tab by name (from the current split's open buffers)
     SwitchToTab,
+    /// Go to a symbol anywhere in the workspace (LSP workspace/symbol)
+    WorkspaceSymbol,
 }
 
 /// Prompt state for the minibuffer
@@ -248,6 +248,15 @@ struct SyntaxColors {
     variable: ColorDef,
     constant: ColorDef,
     operator: ColorDef,
+    // LSP semantic token colors; themes that omit them fall back to the closest syntax color
+    #[serde(rename = "macro", default)]
+    macro_: Option<ColorDef>,
+    #[serde(default)]
+    parameter: Option<ColorDef>,
+    #[serde(default)]
+    namespace: Option<ColorDef>,
+    #[serde(rename = "unsafe", default)]
+    unsafe_: Option<ColorDef>,
 }
 
 /// Comprehensive theme structure with all UI colors
@@ -355,10 +364,21 @@ pub struct Theme {
     pub syntax_variable: Color,
     pub syntax_constant: Color,
     pub syntax_operator: Color,
+
+    // Semantic token colors (LSP textDocument/semanticTokens)
+    pub syntax_macro: Color,
+    pub syntax_parameter: Color,
+    pub syntax_namespace: Color,
+    /// Foreground for tokens with the `unsafe` modifier
+    pub syntax_unsafe: Color,
 }
 
 impl From<ThemeFile> for Theme {
     fn from(file: ThemeFile) -> Self {
+        let syntax_function: Color = file.syntax.function.into();
+        let syntax_type: Color = file.syntax.type_.into();
+        let syntax_variable: Color = file.syntax.variable.into();
+        let diagnostic_error_fg: Color = file.diagnostic.error_fg.into();
         Self {
             name: file.name,
             editor_bg: file.editor.bg.into(),
@@ -419,7 +439,7 @@ impl From<ThemeFile> for Theme {
             semantic_highlight_bg: file.ui.semantic_highlight_bg.into(),
             search_match_bg: file.search.match_bg.into(),
             search_match_fg: file.search.match_fg.into(),
-            diagnostic_error_fg: file.diagnostic.error_fg.into(),
+            diagnostic_error_fg,
             diagnostic_error_bg: file.diagnostic.error_bg.into(),
             diagnostic_warning_fg: file.diagnostic.warning_fg.into(),
             diagnostic_warning_bg: file.diagnostic.warning_bg.into(),
@@ -430,11 +450,15 @@ impl From<ThemeFile> for Theme {
             syntax_keyword: file.syntax.keyword.into(),
             syntax_string: file.syntax.string.into(),
             syntax_comment: file.syntax.comment.into(),
-            syntax_function: file.syntax.function.into(),
-            syntax_type: file.syntax.type_.into(),
-            syntax_variable: file.syntax.variable.into(),
+            syntax_function,
+            syntax_type,
+            syntax_variable,
             syntax_constant: file.syntax.constant.into(),
             syntax_operator: file.syntax.operator.into(),
+            syntax_macro: file.syntax.macro_.map_or(syntax_function, Into::into),
+            syntax_parameter: file.syntax.parameter.map_or(syntax_variable, Into::into),
+            syntax_namespace: file.syntax.namespace.map_or(syntax_type, Into::into),
+            syntax_unsafe: file.syntax.unsafe_.map_or(diagnostic_error_fg, Into::into),
         }
     }
 }
@@ -580,6 +604,10 @@ impl Theme {
             syntax_variable: Color::Rgb(156, 220, 254),
             syntax_constant: Color::Rgb(79, 193, 255),
             syntax_operator: Color::Rgb(212, 212, 212),
+            syntax_macro: Color::Rgb(197, 134, 192),
+            syntax_parameter: Color::Rgb(117, 190, 255),
+            syntax_namespace: Color::Rgb(78, 201, 176),
+            syntax_unsafe: Color::Rgb(244, 71, 71),
         }
     }
 
@@ -686,6 +714,10 @@ impl Theme {
             syntax_variable: Color::Rgb(0, 16, 128), // Dark blue variables
             syntax_constant: Color::Rgb(0, 112, 193), // Blue constants
             syntax_operator: Color::Rgb(0, 0, 0),    // Black operators
+            syntax_macro: Color::Rgb(128, 0, 128),   // Dark purple macros
+            syntax_parameter: Color::Rgb(0, 16, 128), // Same as variables
+            syntax_namespace: Color::Rgb(38, 127, 153), // Blue-teal namespaces
+            syntax_unsafe: Color::Rgb(205, 49, 49),  // Red unsafe operations
         }
     }
 
@@ -792,6 +824,10 @@ impl Theme {
             syntax_variable: Color::White,
             syntax_constant: Color::LightBlue,
             syntax_operator: Color::White,
+            syntax_macro: Color::LightMagenta,
+            syntax_parameter: Color::LightCyan,
+            syntax_namespace: Color::Magenta,
+            syntax_unsafe: Color::LightRed,
         }
     }
 
@@ -948,6 +984,10 @@ impl Theme {
             syntax_variable: Color::Rgb(255, 255, 85), // Yellow variables
             syntax_constant: Color::Rgb(255, 0, 255),  // Bright magenta constants
             syntax_operator: Color::Rgb(170, 170, 170), // Light gray operators
+            syntax_macro: Color::Rgb(255, 170, 0),     // Orange macros
+            syntax_parameter: Color::Rgb(255, 255, 170), // Pale yellow parameters
+            syntax_namespace: Color::Rgb(0, 170, 0),   // Dark green namespaces
+            syntax_unsafe: Color::Rgb(255, 85, 85),    // Light red unsafe operations
         }
     }
 }
@@ -11,6 +11,7 @@ use crate::primitives::ansi::AnsiParser;
 use crate::primitives::ansi_background::AnsiBackground;
 use crate::services::plugins::api::ViewTransformPayload;
 use crate::state::{EditorState, ViewMode};
+use crate::view::margin::FoldIndicator;
 use crate::view::split::SplitManager;
 use crate::view::ui::tabs::TabsRenderer;
 use crate::view::ui::view_pipeline::{
@@ -25,6 +26,9 @@ use ratatui::Frame;
 use std::collections::{HashMap, HashSet};
 use std::ops::Range;
 
+/// Shown at the end of a folded region's header line
+const FOLD_PLACEHOLDER: &str = " ⋯";
+
 fn push_span_with_map(
     spans: &mut Vec<Span<'static>>,
     map: &mut Vec<Option<usize>>,
@@ -68,11 +72,17 @@ struct SelectionContext {
 struct DecorationContext {
     highlight_spans: Vec<crate::primitives::highlighter::HighlightSpan>,
     semantic_spans: Vec<crate::primitives::highlighter::HighlightSpan>,
+    /// LSP semantic tokens in the viewport (byte range, style to patch over syntax colors)
+    semantic_token_spans: Vec<(Range<usize>, Style)>,
     viewport_overlays: Vec<(crate::view::overlay::Overlay, Range<usize>)>,
     virtual_text_lookup: HashMap<usize, Vec<crate::view::virtual_text::VirtualText>>,
     diagnostic_lines: HashSet<usize>,
     /// Line indicators indexed by line number (highest priority indicator per line)
     line_indicators: BTreeMap<usize, crate::view::margin::LineIndicator>,
+    /// Fold indicators indexed by line number
+    fold_indicators: BTreeMap<usize, FoldIndicator>,
+    /// Folded regions as header line -> last hidden line
+    folded_lines: BTreeMap<usize, usize>,
 }
 
 struct LineRenderOutput {
@@ -131,6 +141,7 @@ struct CharStyleContext<'a> {
     theme: &'a crate::view::theme::Theme,
     highlight_spans: &'a [crate::primitives::highlighter::HighlightSpan],
     semantic_spans: &'a [crate::primitives::highlighter::HighlightSpan],
+    semantic_token_spans: &'a [(Range<usize>, Style)],
     viewport_overlays: &'a [(crate::view::overlay::Overlay, Range<usize>)],
     primary_cursor_position: usize,
     is_active: bool,
@@ -152,6 +163,8 @@ struct LeftMarginContext<'a> {
     diagnostic_lines: &'a HashSet<usize>,
     /// Pre-computed line indicators (line_num -> indicator)
     line_indicators: &'a BTreeMap<usize, crate::view::margin::LineIndicator>,
+    /// Fold indicator for this line
+    fold_indicator: Option<FoldIndicator>,
 }
 
 /// Render the left margin (indicators + line numbers + separator) to line_spans
@@ -234,14 +247,17 @@ fn render_left_margin(
         push_span_with_map(
             line_spans,
             line_view_map,
-            ctx.state.margins.left_config.separator.clone(),
+            ctx.state
+                .margins
+                .left_config
+                .separator_with_fold(ctx.fold_indicator),
             separator_style,
             None,
         );
     }
 }
 
-/// Compute the style for a character by layering: token -> ANSI -> syntax -> semantic -> overlays -> selection -> cursor
+/// Compute the style for a character by layering: token -> ANSI -> syntax -> LSP semantic tokens -> semantic -> overlays -> selection -> cursor
 fn compute_char_style(ctx: &CharStyleContext) -> CharStyleOutput {
     use crate::view::overlay::OverlayFace;
 
@@ -316,6 +332,19 @@ fn compute_char_style(ctx: &CharStyleContext) -> CharStyleOutput {
         style = style.fg(highlight_color.unwrap());
     }
 
+    // Apply LSP semantic tokens over syntax colors (but not over explicit token/ANSI colors)
+    if ctx.token_style.is_none() && ctx.ansi_style.fg.is_none() {
+        if let Some(bp) = ctx.byte_pos {
+            if let Some((_, token_style)) = ctx
+                .semantic_token_spans
+                .iter()
+                .find(|(range, _)| range.contains(&bp))
+            {
+                style = style.patch(*token_style);
+            }
+        }
+    }
+
     // Apply semantic highlighting
     if let Some(bp) = ctx.byte_pos {
         if let Some(semantic_span) = ctx
@@ -559,6 +588,7 @@ impl SplitRenderer {
                     split_id,
                     is_active,
                 );
+                viewport.set_hidden_ranges(state.folds.hidden_ranges(&state.buffer));
                 Self::sync_viewport_to_content(
                     &mut viewport,
                     &mut state.buffer,
@@ -971,6 +1001,7 @@ impl SplitRenderer {
             estimated_line_length,
             visible_count,
             is_binary,
+            &viewport.hidden_ranges,
         );
 
         // Use plugin transform if available, otherwise use base tokens
@@ -1099,12 +1130,17 @@ impl SplitRenderer {
         result
     }
 
+    /// Build the base token stream for the visible lines
+    ///
+    /// Lines inside `hidden` (folded regions) are skipped, and the header line of each
+    /// fold ends with a placeholder.
     fn build_base_tokens(
         buffer: &mut Buffer,
         top_byte: usize,
         estimated_line_length: usize,
         visible_count: usize,
         is_binary: bool,
+        hidden: &[Range<usize>],
     ) -> Vec<crate::services::plugins::api::ViewTokenWire> {
         use crate::services::plugins::api::{ViewTokenWire, ViewTokenWireKind};
 
@@ -1128,12 +1164,22 @@ impl SplitRenderer {
         while lines_seen < max_lines {
             if let Some((line_start, line_content)) = iter.next() {
                 let mut byte_offset = 0usize;
+                let mut fold_end = None;
                 for ch in line_content.chars() {
                     let ch_len = ch.len_utf8();
                     let source_offset = Some(line_start + byte_offset);
 
                     match ch {
                         '\n' => {
+                            let next_line = line_start + byte_offset + 1;
+                            if let Some(range) = hidden.iter().find(|r| r.start == next_line) {
+                                tokens.push(ViewTokenWire {
+                                    source_offset: None,
+                                    kind: ViewTokenWireKind::Text(FOLD_PLACEHOLDER.to_string()),
+                                    style: None,
+                                });
+                                fold_end = Some(range.end);
+                            }
                             tokens.push(ViewTokenWire {
                                 source_offset,
                                 kind: ViewTokenWireKind::Newline,
@@ -1186,6 +1232,9 @@ impl SplitRenderer {
                     byte_offset += ch_len;
                 }
                 lines_seen += 1;
+                if let Some(end) = fold_end {
+                    iter = buffer.line_iterator(end, estimated_line_length);
+                }
             } else {
                 break;
             }
@@ -1373,6 +1422,7 @@ impl SplitRenderer {
         estimated_line_length: usize,
         visible_count: usize,
         is_binary: bool,
+        hidden: &[Range<usize>],
     ) -> Vec<crate::services::plugins::api::ViewTokenWire> {
         Self::build_base_tokens(
             buffer,
@@ -1380,6 +1430,7 @@ impl SplitRenderer {
             estimated_line_length,
             visible_count,
             is_binary,
+            hidden,
         )
     }
 
@@ -1727,6 +1778,9 @@ impl SplitRenderer {
             highlight_context_bytes,
         );
 
+        let semantic_token_spans =
+            Self::semantic_token_spans(state, viewport_start, viewport_end, theme);
+
         let viewport_overlays = state
             .overlays
             .query_viewport(viewport_start, viewport_end, &state.marker_list)
@@ -1761,14 +1815,69 @@ impl SplitRenderer {
             |byte_offset| state.buffer.get_line_number(byte_offset),
         );
 
+        // Fold indicators for the visible lines; folded headers take precedence
+        let first_line = state.buffer.get_line_number(viewport_start);
+        let last_line = state.buffer.get_line_number(viewport_end);
+        let mut fold_indicators = BTreeMap::new();
+        if state.margins.show_fold_indicators {
+            for range in state.folds.ranges() {
+                if (first_line..=last_line).contains(&range.start_line) {
+                    fold_indicators.insert(range.start_line, FoldIndicator::Foldable);
+                }
+            }
+        }
+        let folded_lines: BTreeMap<usize, usize> = state
+            .folds
+            .folded_regions(&state.buffer)
+            .into_iter()
+            .map(|region| (region.header_line, region.end_line))
+            .collect();
+        for header_line in folded_lines.keys() {
+            fold_indicators.insert(*header_line, FoldIndicator::Folded);
+        }
+
         DecorationContext {
             highlight_spans,
             semantic_spans,
+            semantic_token_spans,
             viewport_overlays,
             virtual_text_lookup,
             diagnostic_lines,
             line_indicators,
+            fold_indicators,
+            folded_lines,
+        }
+    }
+
+    /// Convert the LSP semantic tokens on the viewport's lines to byte ranges and styles
+    fn semantic_token_spans(
+        state: &EditorState,
+        viewport_start: usize,
+        viewport_end: usize,
+        theme: &crate::view::theme::Theme,
+    ) -> Vec<(Range<usize>, Style)> {
+        if state.semantic_tokens.is_empty() {
+            return Vec::new();
         }
+        let first_line = state.buffer.get_line_number(viewport_start) as u32;
+        let last_line = state.buffer.get_line_number(viewport_end) as u32;
+
+        state
+            .semantic_tokens
+            .tokens_in_lines(first_line..last_line + 1)
+            .iter()
+            .filter_map(|token| {
+                let style = token.style.style(theme)?;
+                let line = token.line as usize;
+                let start = state
+                    .buffer
+                    .lsp_position_to_byte(line, token.start as usize);
+                let end = state
+                    .buffer
+                    .lsp_position_to_byte(line, (token.start + token.length) as usize);
+                (start < end).then_some((start..end, style))
+            })
+            .collect()
     }
 
     fn calculate_viewport_end(
@@ -1817,10 +1926,13 @@ impl SplitRenderer {
 
         let highlight_spans = &decorations.highlight_spans;
         let semantic_spans = &decorations.semantic_spans;
+        let semantic_token_spans = &decorations.semantic_token_spans;
         let viewport_overlays = &decorations.viewport_overlays;
         let virtual_text_lookup = &decorations.virtual_text_lookup;
         let diagnostic_lines = &decorations.diagnostic_lines;
         let line_indicators = &decorations.line_indicators;
+        let fold_indicators = &decorations.fold_indicators;
+        let folded_lines = &decorations.folded_lines;
 
         let mut lines = Vec::new();
         let mut lines_rendered = 0usize;
@@ -1884,7 +1996,12 @@ impl SplitRenderer {
             // 2. The CURRENT line is also a source line
             // This ensures virtual/injected lines don't cause line numbers to skip
             if show_line_number && prev_was_source_line {
-                current_source_line_num += 1;
+                // Folded lines are not displayed
+                current_source_line_num = folded_lines
+                    .get(&current_source_line_num)
+                    .copied()
+                    .unwrap_or(current_source_line_num)
+                    + 1;
             }
             // Only update the flag when we see a source line - virtual lines
             // between source lines shouldn't reset the tracking
@@ -1916,6 +2033,11 @@ impl SplitRenderer {
                     estimated_lines,
                     diagnostic_lines,
                     line_indicators,
+                    fold_indicator: if is_continuation {
+                        None
+                    } else {
+                        fold_indicators.get(&current_source_line_num).copied()
+                    },
                 },
                 &mut line_spans,
                 &mut line_view_map,
@@ -2050,6 +2172,7 @@ impl SplitRenderer {
                         theme,
                         highlight_spans,
                         semantic_spans,
+                        semantic_token_spans,
                         viewport_overlays,
                         primary_cursor_position,
                         is_active,
@@ -2101,24 +2224,7 @@ impl SplitRenderer {
                         );
                     }
 
-                    if let Some(bp) = byte_pos {
-                        if let Some(vtexts) = virtual_text_lookup.get(&bp) {
-                            for vtext in vtexts
-                                .iter()
-                                .filter(|v| v.position == VirtualTextPosition::AfterChar)
-                            {
-                                let text_with_space = format!(" {}", vtext.text);
-                                push_span_with_map(
-                                    &mut line_spans,
-                                    &mut line_view_map,
-                                    text_with_space,
-                                    vtext.style,
-                                    None,
-                                );
-                            }
-                        }
-                    }
-
+                    // A cursor on the newline comes before text shown after the line
                     if is_cursor && ch == '\n' {
                         let should_add_indicator =
                             if is_active { is_secondary_cursor } else { true };
@@ -2142,6 +2248,24 @@ impl SplitRenderer {
                             );
                         }
                     }
+
+                    if let Some(bp) = byte_pos {
+                        if let Some(vtexts) = virtual_text_lookup.get(&bp) {
+                            for vtext in vtexts
+                                .iter()
+                                .filter(|v| v.position == VirtualTextPosition::AfterChar)
+                            {
+                                let text_with_space = format!(" {}", vtext.text);
+                                push_span_with_map(
+                                    &mut line_spans,
+                                    &mut line_view_map,
+                                    text_with_space,
+                                    vtext.style,
+                                    None,
+                                );
+                            }
+                        }
+                    }
                 }
 
                 char_index += ch.len_utf8();
@@ -2308,7 +2432,11 @@ impl SplitRenderer {
             if end.terminated_with_newline && lines_rendered < visible_line_count {
                 // Render the implicit line after the newline
                 let mut implicit_line_spans = Vec::new();
-                let implicit_line_num = current_source_line_num + 1;
+                let implicit_line_num = folded_lines
+                    .get(&current_source_line_num)
+                    .copied()
+                    .unwrap_or(current_source_line_num)
+                    + 1;
 
                 if state.margins.left_config.enabled {
                     // Indicator column (space)
@@ -381,6 +381,16 @@ impl StatusBarRenderer {
             _ => "",
         };
 
+        // Vim mode indicator (when the vim keymap is active)
+        let vim_mode = if keybindings.vim_emulation() {
+            format!(
+                "{} | ",
+                crate::input::vim::VimMode::from_mode_name(&state.mode).label()
+            )
+        } else {
+            String::new()
+        };
+
         let cursor = *state.primary_cursor();
 
         // Get line number and column efficiently using cached values
@@ -465,7 +475,7 @@ impl StatusBarRenderer {
         };
 
         let base_status = format!(
-            "{filename}{modified} | Ln {line}, Col {col}{diagnostics_summary}{cursor_count_indicator}{lsp_indicator}"
+            "{vim_mode}{filename}{modified} | Ln {line}, Col {col}{diagnostics_summary}{cursor_count_indicator}{lsp_indicator}"
         );
         let left_status = format!("{base_status}{chord_display}{message_suffix}");
 
@@ -0,0 +1,296 @@
+//! Undo tree visualizer layout
+//!
+//! Every state an undo step can stop at (a write action) is one line, below a
+//! line for the original content. At a branch point the most recent edit
+//! continues at the same indentation and the older branches are listed before
+//! it, indented one level. Each line is tagged with its state so the visualizer
+//! can find the state under the cursor. Below the tree, a few lines of the
+//! selected state's text around its edit are previewed.
+
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::model::event::{Event, EventLog, UndoPosition};
+use crate::primitives::text_property::TextPropertyEntry;
+
+/// Text property key holding the state of a line (`null` for the original content)
+pub const UNDO_TREE_NODE_PROPERTY: &str = "undo_tree_node";
+
+/// Lines of context shown above and below the edit in the preview
+const PREVIEW_CONTEXT: usize = 3;
+
+/// Longest inserted or deleted text quoted in a line
+const MAX_QUOTED_CHARS: usize = 24;
+
+/// States in display order, with their depth
+pub fn visible_states(log: &EventLog) -> Vec<(UndoPosition, usize)> {
+    let mut children: HashMap<UndoPosition, Vec<usize>> = HashMap::new();
+    for (index, entry) in log.entries().iter().enumerate() {
+        if entry.event.is_write_action() {
+            children
+                .entry(log.state_of(entry.parent))
+                .or_default()
+                .push(index);
+        }
+    }
+
+    let mut visible = Vec::new();
+    // (state, depth); branches are walked along their newest child iteratively
+    let mut stack = vec![(None, 0)];
+    while let Some((start, depth)) = stack.pop() {
+        let mut state = start;
+        let mut pending = Vec::new();
+        loop {
+            visible.push((state, depth));
+            let Some((&newest, older)) = children.get(&state).and_then(|c| c.split_last()) else {
+                break;
+            };
+            if older.is_empty() {
+                state = Some(newest);
+                continue;
+            }
+            // Older branches come before the continuation: finish this chain later
+            pending.push((Some(newest), depth));
+            for &child in older.iter().rev() {
+                pending.push((Some(child), depth + 1));
+            }
+            break;
+        }
+        stack.extend(pending);
+    }
+    visible
+}
+
+/// Render the undo tree of a buffer as buffer text
+///
+/// `selected` is the state previewed below the tree and `preview` its full text.
+pub fn render(
+    log: &EventLog,
+    title: &str,
+    now_ms: u64,
+    selected: UndoPosition,
+    preview: Option<&str>,
+) -> Vec<TextPropertyEntry> {
+    let mut entries = vec![
+        TextPropertyEntry::text(format!("Undo tree: {}\n", title)),
+        TextPropertyEntry::text("Enter: restore  Up/Down: preview  q: close\n\n".to_string()),
+    ];
+
+    let current = log.state_of(log.position());
+    let saved = log.saved_position().map(|saved| log.state_of(saved));
+    for (state, depth) in visible_states(log) {
+        let marker = if state == current { "●" } else { "○" };
+        let mut line = format!("{}{} ", "  ".repeat(depth), marker);
+        match state {
+            None => line.push_str("original"),
+            Some(index) => {
+                let entry = &log.entries()[index];
+                line.push_str(&format!(
+                    "#{}  {}  {}",
+                    index + 1,
+                    format_age(now_ms.saturating_sub(entry.timestamp)),
+                    describe_event(&entry.event)
+                ));
+            }
+        }
+        if saved == Some(state) {
+            line.push_str("  (saved)");
+        }
+        line.push('\n');
+        entries.push(
+            TextPropertyEntry::text(line).with_property(UNDO_TREE_NODE_PROPERTY, json!(state)),
+        );
+    }
+
+    if let Some(text) = preview {
+        let label = match selected {
+            Some(index) => format!("#{}", index + 1),
+            None => "original".to_string(),
+        };
+        entries.push(TextPropertyEntry::text(format!(
+            "\nPreview of {}:\n",
+            label
+        )));
+        let edit = selected.and_then(|index| edit_position(&log.entries()[index].event));
+        for line in preview_lines(text, edit) {
+            entries.push(TextPropertyEntry::text(format!("{}\n", line)));
+        }
+    }
+    entries
+}
+
+/// One-line summary of an edit
+pub fn describe_event(event: &Event) -> String {
+    match event {
+        Event::Insert { text, .. } => format!("insert {}", quote(text)),
+        Event::Delete { deleted_text, .. } => format!("delete {}", quote(deleted_text)),
+        Event::AddCursor { .. } => "add cursor".to_string(),
+        Event::RemoveCursor { .. } => "remove cursor".to_string(),
+        Event::Batch {
+            events,
+            description,
+        } => {
+            let edits: Vec<&Event> = events.iter().filter(|e| e.modifies_buffer()).collect();
+            match edits.as_slice() {
+                [edit] => describe_event(edit),
+                _ if !description.is_empty() => description.clone(),
+                _ => format!("{} edits", edits.len()),
+            }
+        }
+        _ => "edit".to_string(),
+    }
+}
+
+/// Where an edit was made in the text it produced
+pub fn edit_position(event: &Event) -> Option<usize> {
+    match event {
+        Event::Insert { position, .. } => Some(*position),
+        Event::Delete { range, .. } => Some(range.start),
+        Event::Batch { events, .. } => events.iter().find_map(edit_position),
+        _ => None,
+    }
+}
+
+/// Apply an edit to a copy of a buffer's text
+///
+/// Edits that don't fit the text are skipped.
+pub fn apply_to_text(text: &mut String, event: &Event) {
+    match event {
+        Event::Insert {
+            position,
+            text: inserted,
+            ..
+        } if text.is_char_boundary(*position) => text.insert_str(*position, inserted),
+        Event::Delete { range, .. }
+            if range.end <= text.len()
+                && text.is_char_boundary(range.start)
+                && text.is_char_boundary(range.end) =>
+        {
+            text.replace_range(range.clone(), "")
+        }
+        Event::Batch { events, .. } => {
+            for event in events {
+                apply_to_text(text, event);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Numbered lines around `edit` (the start of the text without one), the edit's
+/// line marked with `>`
+fn preview_lines(text: &str, edit: Option<usize>) -> Vec<String> {
+    let edit_line = edit.map(|position| {
+        let position = position.min(text.len());
+        text.as_bytes()[..position]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count()
+    });
+    let first = edit_line.map_or(0, |line| line.saturating_sub(PREVIEW_CONTEXT));
+    let count = PREVIEW_CONTEXT * 2 + 1;
+    text.lines()
+        .enumerate()
+        .skip(first)
+        .take(count)
+        .map(|(number, line)| {
+            let marker = if Some(number) == edit_line { ">" } else { " " };
+            format!("{} {:>5} │ {}", marker, number + 1, line)
+        })
+        .collect()
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted: String = text.chars().take(MAX_QUOTED_CHARS).collect();
+    let truncated = quoted.len() < text.len();
+    quoted = format!("{:?}", quoted);
+    if truncated {
+        quoted.push('…');
+    }
+    quoted
+}
+
+/// How long ago something happened, e.g. `5m ago`
+pub fn format_age(millis: u64) -> String {
+    let secs = millis / 1000;
+    if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86_400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86_400)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::CursorId;
+
+    fn insert(position: usize, text: &str) -> Event {
+        Event::Insert {
+            position,
+            text: text.to_string(),
+            cursor_id: CursorId(0),
+        }
+    }
+
+    #[test]
+    fn test_branches_are_indented() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        log.append(insert(1, "c"));
+        log.append(insert(2, "d"));
+
+        assert_eq!(
+            visible_states(&log),
+            vec![
+                (None, 0),
+                (Some(0), 0),
+                (Some(1), 1),
+                (Some(2), 0),
+                (Some(3), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_marks_current_and_saved() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.mark_saved();
+        log.append(insert(1, "b"));
+        log.undo();
+
+        let lines: Vec<String> = render(&log, "test.txt", 0, None, None)
+            .into_iter()
+            .skip(2)
+            .map(|entry| entry.text.trim_end().to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "○ original",
+                "● #1  0s ago  insert \"a\"  (saved)",
+                "○ #2  0s ago  insert \"b\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_to_text_and_preview() {
+        let mut text = "one\ntwo\n".to_string();
+        let event = insert(4, "2");
+        apply_to_text(&mut text, &event);
+        assert_eq!(text, "one\n2two\n");
+        assert_eq!(
+            preview_lines(&text, edit_position(&event)),
+            vec!["      1 │ one", ">     2 │ 2two"]
+        );
+    }
+}
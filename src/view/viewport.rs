@@ -34,6 +34,10 @@ pub struct Viewport {
     /// When true, horizontal scrolling is disabled
     pub line_wrap_enabled: bool,
 
+    /// Byte ranges hidden by folds in the displayed buffer, sorted
+    /// Refreshed on every render; scrolling steps over them
+    pub hidden_ranges: Vec<std::ops::Range<usize>>,
+
     /// Whether viewport needs synchronization with cursor positions
     /// When true, ensure_visible needs to be called before rendering
     /// This allows batching multiple cursor movements into a single viewport update
@@ -62,6 +66,7 @@ impl Viewport {
             scroll_offset: 3,
             horizontal_scroll_offset: 5,
             line_wrap_enabled: false,
+            hidden_ranges: Vec::new(),
             needs_sync: false,
             skip_resize_sync: false,
             skip_ensure_visible: false,
@@ -135,6 +140,34 @@ impl Viewport {
         1 + digits.max(4) + 3
     }
 
+    /// Set the folded byte ranges and move `top_byte` out of them
+    pub fn set_hidden_ranges(&mut self, hidden_ranges: Vec<std::ops::Range<usize>>) {
+        if let Some(range) = hidden_ranges
+            .iter()
+            .find(|range| range.contains(&self.top_byte))
+        {
+            // Show the fold's header line instead
+            self.top_byte = range.start.saturating_sub(1);
+        }
+        self.hidden_ranges = hidden_ranges;
+    }
+
+    /// End of the folded range starting at a line start, if any
+    fn hidden_range_end_at(&self, line_start: usize) -> Option<usize> {
+        self.hidden_ranges
+            .iter()
+            .find(|range| range.start == line_start)
+            .map(|range| range.end)
+    }
+
+    /// Position in the fold's header line if a line start is hidden
+    fn fold_header_for(&self, line_start: usize) -> Option<usize> {
+        self.hidden_ranges
+            .iter()
+            .find(|range| range.contains(&line_start))
+            .map(|range| range.start.saturating_sub(1))
+    }
+
     /// Scroll up by N lines (byte-based)
     /// LineCache automatically tracks line numbers
     pub fn scroll_up(&mut self, buffer: &mut Buffer, lines: usize) {
@@ -143,6 +176,9 @@ impl Viewport {
             if iter.prev().is_none() {
                 break;
             }
+            if let Some(header) = self.fold_header_for(iter.current_position()) {
+                iter = buffer.line_iterator(header, 80);
+            }
         }
         let new_position = iter.current_position();
         drop(iter); // Explicitly drop to release borrow
@@ -157,6 +193,9 @@ impl Viewport {
             if iter.next().is_none() {
                 break;
             }
+            if let Some(end) = self.hidden_range_end_at(iter.current_position()) {
+                iter = buffer.line_iterator(end, 80);
+            }
         }
         let new_position = iter.current_position();
         drop(iter); // Explicitly drop to release borrow
@@ -550,7 +589,6 @@ impl Viewport {
         while current_line < line {
             if let Some((line_start, _)) = iter.next() {
                 if current_line + 1 == line {
-                    drop(iter);
                     self.set_top_byte_with_limit(buffer, line_start);
                     return;
                 }
@@ -563,7 +601,6 @@ impl Viewport {
 
         // If we didn't find the line, stay at the last valid position
         let target_position = iter.current_position();
-        drop(iter);
         self.set_top_byte_with_limit(buffer, target_position);
     }
 
@@ -704,6 +741,9 @@ impl Viewport {
                         let line_text = line_content.trim_end_matches('\n');
                         let segments = wrap_line(line_text, &wrap_config);
                         visual_rows += segments.len();
+                        if let Some(end) = self.hidden_range_end_at(iter.current_position()) {
+                            iter = buffer.line_iterator(end, 80);
+                        }
 
                         // If we've exceeded the viewport, cursor is not visible
                         if visual_rows >= viewport_lines {
@@ -724,6 +764,9 @@ impl Viewport {
                     if iter.next().is_none() {
                         break;
                     }
+                    if let Some(end) = self.hidden_range_end_at(iter.current_position()) {
+                        iter = buffer.line_iterator(end, 80);
+                    }
                     lines_from_top += 1;
                 }
 
@@ -782,6 +825,9 @@ impl Viewport {
                     if iter.prev().is_none() {
                         break; // Hit beginning of buffer
                     }
+                    if let Some(header) = self.fold_header_for(iter.current_position()) {
+                        iter = buffer.line_iterator(header, 80);
+                    }
 
                     if let Some((_line_start, line_content)) = iter.next() {
                         let line_text = if line_content.ends_with('\n') {
@@ -807,6 +853,9 @@ impl Viewport {
                     if iter.prev().is_none() {
                         break; // Hit beginning of buffer
                     }
+                    if let Some(header) = self.fold_header_for(iter.current_position()) {
+                        iter = buffer.line_iterator(header, 80);
+                    }
                 }
 
                 let new_top_byte = iter.current_position();
@@ -891,7 +940,6 @@ impl Viewport {
                 }
             }
             let position = iter.current_position();
-            drop(iter);
             self.set_top_byte_with_limit(buffer, position);
         }
     }
@@ -1006,7 +1054,6 @@ impl Viewport {
                 }
             }
             let position = iter.current_position();
-            drop(iter);
             self.set_top_byte_with_limit(buffer, position);
         } else {
             // Can't fit all cursors, ensure primary is visible
@@ -242,6 +242,52 @@ impl VirtualTextManager {
         id
     }
 
+    /// Add an inline virtual text (BeforeChar or AfterChar) with namespace for bulk removal
+    ///
+    /// Used for LSP inlay hints, so refreshing them leaves other virtual text alone.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_inline(
+        &mut self,
+        marker_list: &mut MarkerList,
+        position: usize,
+        text: String,
+        style: Style,
+        placement: VirtualTextPosition,
+        namespace: VirtualTextNamespace,
+        priority: i32,
+    ) -> VirtualTextId {
+        debug_assert!(
+            placement.is_inline(),
+            "add_inline requires BeforeChar or AfterChar"
+        );
+
+        let marker_id = marker_list.create(position, false);
+
+        let id = VirtualTextId(self.next_id);
+        self.next_id += 1;
+
+        self.texts.insert(
+            id,
+            VirtualText {
+                marker_id,
+                text,
+                style,
+                position: placement,
+                priority,
+                string_id: None,
+                namespace: Some(namespace),
+            },
+        );
+
+        id
+    }
+
+    /// Get the current byte position of a virtual text entry's anchor
+    pub fn position(&self, marker_list: &MarkerList, id: VirtualTextId) -> Option<usize> {
+        let vtext = self.texts.get(&id)?;
+        marker_list.get_position(vtext.marker_id)
+    }
+
     /// Remove a virtual text entry by its string identifier
     pub fn remove_by_id(&mut self, marker_list: &mut MarkerList, string_id: &str) -> bool {
         // Find the entry with matching string_id
@@ -837,4 +883,39 @@ mod tests {
         assert_eq!(before.unwrap().text, "/*param=*/");
         assert_eq!(after.unwrap().text, ": Type");
     }
+
+    #[test]
+    fn test_clear_namespace_keeps_other_namespaces() {
+        let mut marker_list = MarkerList::new();
+        let mut manager = VirtualTextManager::new();
+        let hints = VirtualTextNamespace::from_string("hints".to_string());
+        let lenses = VirtualTextNamespace::from_string("lenses".to_string());
+
+        manager.add_inline(
+            &mut marker_list,
+            10,
+            ": i32".to_string(),
+            hint_style(),
+            VirtualTextPosition::AfterChar,
+            hints.clone(),
+            0,
+        );
+        let lens = manager.add_line(
+            &mut marker_list,
+            0,
+            "2 references".to_string(),
+            hint_style(),
+            VirtualTextPosition::LineAbove,
+            lenses,
+            0,
+        );
+
+        manager.clear_namespace(&mut marker_list, &hints);
+
+        assert_eq!(manager.len(), 1);
+        assert_eq!(marker_list.marker_count(), 1);
+        marker_list.adjust_for_insert(0, 4);
+        // Line anchors keep right affinity, so an insert at the anchor pushes it along
+        assert_eq!(manager.position(&marker_list, lens), Some(4));
+    }
 }
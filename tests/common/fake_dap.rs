@@ -0,0 +1,187 @@
+#![cfg(test)]
+//! Fake debug adapter for E2E testing
+//!
+//! Like the fake LSP servers, this writes a Bash script that speaks the Debug Adapter
+//! Protocol over stdio with canned answers, so debugger features can be tested
+//! without a real adapter.
+
+use std::sync::mpsc;
+use std::thread;
+
+/// A fake debug adapter for testing
+pub struct FakeDebugAdapter {
+    /// Handle to the helper thread
+    handle: Option<thread::JoinHandle<()>>,
+    /// Channel to stop the helper thread
+    stop_tx: mpsc::Sender<()>,
+}
+
+impl FakeDebugAdapter {
+    /// Spawn a fake debug adapter
+    ///
+    /// Every request it receives is appended to `log_path`, one per line. The program
+    /// (the launch request's `program`) stops on a breakpoint at line 2 once configured,
+    /// with a `main` frame on top of a `start` frame. Locals are `count: i32 = 42` and
+    /// a structured `point` whose child is `x = 1`; every watch evaluates to `43`.
+    /// Stepping stops again at line 3, and continuing ends the program.
+    pub fn spawn(log_path: &str) -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    printf 'Content-Length: %d\r\n\r\n%s' "$length" "$message"
+}
+
+out_seq=0
+program=""
+line=2
+
+# Answer the request being handled
+respond() {
+    out_seq=$((out_seq + 1))
+    send_message '{"seq":'$out_seq',"type":"response","request_seq":'$seq',"success":true,"command":"'$command'","body":'"$1"'}'
+}
+
+# Send an event
+event() {
+    out_seq=$((out_seq + 1))
+    send_message '{"seq":'$out_seq',"type":"event","event":"'$1'","body":'"$2"'}'
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+    echo "$msg" >> "LOG_PATH"
+
+    command=$(echo "$msg" | grep -o '"command":"[^"]*"' | head -1 | cut -d'"' -f4)
+    seq=$(echo "$msg" | grep -o '"seq":[0-9]*' | head -1 | cut -d':' -f2)
+
+    case "$command" in
+        "initialize")
+            respond '{"supportsConfigurationDoneRequest":true}'
+            event "initialized" '{}'
+            ;;
+        "launch")
+            program=$(echo "$msg" | grep -o '"program":"[^"]*"' | cut -d'"' -f4)
+            respond '{}'
+            ;;
+        "setBreakpoints")
+            respond '{"breakpoints":[{"verified":true,"line":2}]}'
+            ;;
+        "configurationDone")
+            respond '{}'
+            event "stopped" '{"reason":"breakpoint","threadId":1,"allThreadsStopped":true}'
+            ;;
+        "threads")
+            respond '{"threads":[{"id":1,"name":"main"}]}'
+            ;;
+        "stackTrace")
+            respond '{"stackFrames":[{"id":1,"name":"main","source":{"path":"'$program'"},"line":'$line',"column":5},{"id":2,"name":"start","source":{"path":"'$program'"},"line":1,"column":1}],"totalFrames":2}'
+            ;;
+        "scopes")
+            respond '{"scopes":[{"name":"Locals","variablesReference":10,"expensive":false}]}'
+            ;;
+        "variables")
+            reference=$(echo "$msg" | grep -o '"variablesReference":[0-9]*' | cut -d':' -f2)
+            if [ "$reference" = "10" ]; then
+                respond '{"variables":[{"name":"count","value":"42","type":"i32","variablesReference":0},{"name":"point","value":"Point","variablesReference":11}]}'
+            else
+                respond '{"variables":[{"name":"x","value":"1","variablesReference":0}]}'
+            fi
+            ;;
+        "evaluate")
+            respond '{"result":"43","variablesReference":0}'
+            ;;
+        "next"|"stepIn"|"stepOut")
+            respond '{}'
+            line=3
+            event "stopped" '{"reason":"step","threadId":1}'
+            ;;
+        "continue")
+            respond '{"allThreadsContinued":true}'
+            event "exited" '{"exitCode":0}'
+            event "terminated" '{}'
+            ;;
+        "disconnect")
+            respond '{}'
+            break
+            ;;
+        *)
+            respond '{}'
+            ;;
+    esac
+done
+"#
+        .replace("LOG_PATH", log_path);
+
+        // Write script to a temporary file
+        let script_path = Self::script_path();
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the fake debug adapter script
+    pub fn script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_debug_adapter.sh")
+    }
+
+    /// Stop the adapter's helper thread
+    pub fn stop(&mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for FakeDebugAdapter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
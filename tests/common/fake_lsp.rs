@@ -77,7 +77,7 @@ case "$method" in
         ;;
     "textDocument/completion")
         # Send completion response with sample items
-        send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"items":[{"label":"test_function","kind":3,"detail":"fn test_function()","insertText":"test_function"},{"label":"test_variable","kind":6,"detail":"let test_variable","insertText":"test_variable"},{"label":"test_struct","kind":22,"detail":"struct TestStruct","insertText":"test_struct"}]}}'
+        send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"isIncomplete":false,"items":[{"label":"test_function","kind":3,"detail":"fn test_function()","insertText":"test_function"},{"label":"test_variable","kind":6,"detail":"let test_variable","insertText":"test_variable"},{"label":"test_struct","kind":22,"detail":"struct TestStruct","insertText":"test_struct"}]}}'
         ;;
     "textDocument/definition")
         # Send definition response (points to line 0, col 0)
@@ -820,6 +820,1349 @@ done
         std::env::temp_dir().join("fake_lsp_server_inlay_hints.sh")
     }
 
+    /// Spawn a fake LSP server that supports document formatting (textDocument/formatting)
+    ///
+    /// This version responds to textDocument/formatting requests with a single edit
+    /// that inserts a space at (0, 9), turning "fn main(){}" into "fn main() {}".
+    pub fn spawn_with_formatting() -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that supports document formatting
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    echo -en "Content-Length: $length\r\n\r\n$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            # Send initialize response with documentFormattingProvider capability
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"documentFormattingProvider":true}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave")
+            # No response for notifications
+            ;;
+        "textDocument/formatting")
+            # Insert a space before the opening brace of "fn main(){}"
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"range":{"start":{"line":0,"character":9},"end":{"line":0,"character":9}},"newText":" "}]}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#;
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_formatting.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the formatting fake LSP server script
+    pub fn formatting_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_formatting.sh")
+    }
+
+    /// Spawn a fake linter LSP server, meant to run alongside another server
+    ///
+    /// It publishes a warning on every save, formats like `spawn_with_formatting`, and
+    /// offers a single completion item, `test_lint_fix`.
+    pub fn spawn_linter() -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    echo -en "Content-Length: $length\r\n\r\n$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"documentFormattingProvider":true,"completionProvider":{}}}}'
+            ;;
+        "initialized"|"textDocument/didOpen"|"textDocument/didChange")
+            # No response for notifications
+            ;;
+        "textDocument/didSave")
+            uri=$(echo "$msg" | grep -o '"uri":"[^"]*"' | head -1 | cut -d'"' -f4)
+            send_message '{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{"uri":"'$uri'","diagnostics":[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":2}},"severity":2,"source":"linter","message":"Lint warning from fake linter"}]}}'
+            ;;
+        "textDocument/formatting")
+            # Insert a space before the opening brace of "fn main(){"
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"range":{"start":{"line":0,"character":9},"end":{"line":0,"character":9}},"newText":" "}]}'
+            ;;
+        "textDocument/completion")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"label":"test_lint_fix","kind":3,"insertText":"test_lint_fix"}]}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#;
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_linter.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the linter fake LSP server script
+    pub fn linter_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_linter.sh")
+    }
+
+    /// Spawn a fake LSP server that offers a code action backed by a server command
+    ///
+    /// The "Add header" code action carries only a command. Executing it makes the server
+    /// send a `workspace/applyEdit` request that creates `new_uri`, writes into it and
+    /// inserts a header comment at the top of `file_uri`. The editor's reply to that
+    /// request is written to [`Self::code_actions_reply_log_path`].
+    pub fn spawn_with_code_actions(file_uri: &str, new_uri: &str) -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let reply_log = Self::code_actions_reply_log_path();
+        let _ = std::fs::remove_file(&reply_log);
+
+        // Create a Bash script that supports code actions and executeCommand
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message (printf keeps the JSON escapes in edits intact)
+send_message() {
+    local message="$1"
+    local length=${#message}
+    printf 'Content-Length: %d\r\n\r\n%s' "$length" "$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            # Send initialize response with code action and execute command capabilities
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"codeActionProvider":true,"executeCommandProvider":{"commands":["fake.addHeader"]}}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave"|"textDocument/didClose")
+            # No response for notifications
+            ;;
+        "textDocument/codeAction")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"title":"Add header","kind":"refactor","command":{"title":"Add header","command":"fake.addHeader","arguments":["FILE_URI"]}}]}'
+            ;;
+        "workspace/executeCommand")
+            # Push the edit before answering, as real servers do
+            send_message '{"jsonrpc":"2.0","id":9000,"method":"workspace/applyEdit","params":{"label":"Add header","edit":{"documentChanges":[{"kind":"create","uri":"NEW_URI"},{"textDocument":{"uri":"NEW_URI","version":null},"edits":[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":0}},"newText":"// generated\n"}]},{"textDocument":{"uri":"FILE_URI","version":null},"edits":[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":0}},"newText":"// header\n"}]}]}}}'
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            ;;
+        "")
+            # Response to our workspace/applyEdit request
+            echo "$msg" >> "REPLY_LOG"
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#
+        .replace("FILE_URI", file_uri)
+        .replace("NEW_URI", new_uri)
+        .replace("REPLY_LOG", &reply_log.to_string_lossy());
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_code_actions.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the code actions fake LSP server script
+    pub fn code_actions_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_code_actions.sh")
+    }
+
+    /// Get the path of the file logging the editor's replies to `workspace/applyEdit`
+    pub fn code_actions_reply_log_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_code_actions_replies.log")
+    }
+
+    /// Spawn a fake LSP server that offers a code lens backed by a server command
+    ///
+    /// The lens on line 2 comes without a command; `codeLens/resolve` turns it into
+    /// "Add header". Executing that command makes the server send a `workspace/applyEdit`
+    /// request that inserts a header comment at the top of `file_uri`.
+    pub fn spawn_with_code_lens(file_uri: &str) -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that supports code actions and executeCommand
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message (printf keeps the JSON escapes in edits intact)
+send_message() {
+    local message="$1"
+    local length=${#message}
+    printf 'Content-Length: %d\r\n\r\n%s' "$length" "$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            # Send initialize response with code lens and execute command capabilities
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"codeLensProvider":{"resolveProvider":true},"executeCommandProvider":{"commands":["fake.addHeader"]}}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave"|"textDocument/didClose")
+            # No response for notifications
+            ;;
+        "textDocument/codeLens")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"range":{"start":{"line":1,"character":0},"end":{"line":1,"character":0}},"data":1}]}'
+            ;;
+        "codeLens/resolve")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"range":{"start":{"line":1,"character":0},"end":{"line":1,"character":0}},"command":{"title":"Add header","command":"fake.addHeader","arguments":["FILE_URI"]}}}'
+            ;;
+        "workspace/executeCommand")
+            # Push the edit before answering, as real servers do
+            send_message '{"jsonrpc":"2.0","id":9000,"method":"workspace/applyEdit","params":{"label":"Add header","edit":{"changes":{"FILE_URI":[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":0}},"newText":"// header\n"}]}}}}'
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            ;;
+        "")
+            # Response to our workspace/applyEdit request
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#
+        .replace("FILE_URI", file_uri);
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_code_lens.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the code lens fake LSP server script
+    pub fn code_lens_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_code_lens.sh")
+    }
+
+    /// Spawn a fake LSP server whose completions need resolving and re-querying
+    ///
+    /// An invoked completion returns `HashMap` and `HashSet` as an incomplete list
+    /// whose `itemDefaults` carry the edit range; re-querying for the incomplete list
+    /// returns only `HashMap`. Resolving `HashMap` adds its documentation and the
+    /// `use std::collections::HashMap;` import as an additional edit.
+    pub fn spawn_with_completion_resolve() -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that supports completion and completionItem/resolve
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message (printf keeps the JSON escapes in edits intact)
+send_message() {
+    local message="$1"
+    local length=${#message}
+    printf 'Content-Length: %d\r\n\r\n%s' "$length" "$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            # Send initialize response with resolvable completion
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"completionProvider":{"resolveProvider":true}}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave"|"textDocument/didClose")
+            # No response for notifications
+            ;;
+        "textDocument/completion")
+            trigger_kind=$(echo "$msg" | grep -o '"triggerKind":[0-9]*' | cut -d':' -f2)
+            if [ "$trigger_kind" = "3" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"isIncomplete":false,"itemDefaults":{"editRange":{"start":{"line":1,"character":4},"end":{"line":1,"character":8}}},"items":[{"label":"HashMap","sortText":"1","data":1}]}}'
+            else
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"isIncomplete":true,"itemDefaults":{"editRange":{"start":{"line":1,"character":4},"end":{"line":1,"character":7}}},"items":[{"label":"HashMap","sortText":"1","data":1},{"label":"HashSet","sortText":"2","data":2}]}}'
+            fi
+            ;;
+        "completionItem/resolve")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"label":"HashMap","documentation":{"kind":"markdown","value":"A hash map."},"additionalTextEdits":[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":0}},"newText":"use std::collections::HashMap;\n"}]}}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#;
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_completion_resolve.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the completion resolve fake LSP server script
+    pub fn completion_resolve_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_completion_resolve.sh")
+    }
+
+    /// Spawn a fake LSP server that finds definitions and implementations
+    ///
+    /// The definition is line 0 of `file_uri`; there are two implementations, on
+    /// lines 2 and 4, returned as location links.
+    pub fn spawn_with_implementations(file_uri: &str) -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that supports definition and implementation requests
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    printf 'Content-Length: %d\r\n\r\n%s' "$length" "$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"definitionProvider":true,"implementationProvider":true}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave"|"textDocument/didClose")
+            # No response for notifications
+            ;;
+        "textDocument/definition")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"uri":"FILE_URI","range":{"start":{"line":0,"character":6},"end":{"line":0,"character":11}}}}'
+            ;;
+        "textDocument/implementation")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"targetUri":"FILE_URI","targetRange":{"start":{"line":2,"character":0},"end":{"line":2,"character":19}},"targetSelectionRange":{"start":{"line":2,"character":0},"end":{"line":2,"character":4}}},{"targetUri":"FILE_URI","targetRange":{"start":{"line":4,"character":0},"end":{"line":4,"character":19}},"targetSelectionRange":{"start":{"line":4,"character":0},"end":{"line":4,"character":4}}}]}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#
+        .replace("FILE_URI", file_uri);
+
+        // Write script to a temporary file
+        let script_path = Self::implementations_script_path();
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the implementations fake LSP server script
+    pub fn implementations_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_implementations.sh")
+    }
+
+    /// Spawn a fake LSP server that follows documents through their lifecycle
+    ///
+    /// Every message it receives is appended to `log_path`, one per line. It asks for
+    /// willSave and willSaveWaitUntil (answered with an edit inserting "// saved\n" at
+    /// the start of the document), registers a watcher for `**/*.toml` files and wants
+    /// renames of `.rs` files (willRenameFiles inserts "// moved\n" at the start of
+    /// `file_uri`). Once initialized it logs "Loaded 3 crates" with
+    /// window/logMessage.
+    pub fn spawn_with_document_lifecycle(file_uri: &str, log_path: &str) -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that logs every message and answers lifecycle requests
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    printf 'Content-Length: %d\r\n\r\n%s' "$length" "$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+    echo "$msg" >> "LOG_PATH"
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":{"openClose":true,"change":1,"willSave":true,"willSaveWaitUntil":true,"save":{}},"workspace":{"fileOperations":{"willRename":{"filters":[{"pattern":{"glob":"**/*.rs"}}]},"didRename":{"filters":[{"pattern":{"glob":"**/*.rs"}}]}}}}}}'
+            ;;
+        "initialized")
+            send_message '{"jsonrpc":"2.0","method":"window/logMessage","params":{"type":3,"message":"Loaded 3 crates"}}'
+            send_message '{"jsonrpc":"2.0","id":1,"method":"client/registerCapability","params":{"registrations":[{"id":"toml-watcher","method":"workspace/didChangeWatchedFiles","registerOptions":{"watchers":[{"globPattern":"**/*.toml"}]}}]}}'
+            ;;
+        "")
+            # Response to our registration request
+            ;;
+        "textDocument/willSaveWaitUntil")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":0}},"newText":"// saved\n"}]}'
+            ;;
+        "workspace/willRenameFiles")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"changes":{"FILE_URI":[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":0}},"newText":"// moved\n"}]}}}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Notifications need no answer; answer any other request so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#
+        .replace("FILE_URI", file_uri)
+        .replace("LOG_PATH", log_path);
+
+        // Write script to a temporary file
+        let script_path = Self::document_lifecycle_script_path();
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the document lifecycle fake LSP server script
+    pub fn document_lifecycle_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_document_lifecycle.sh")
+    }
+
+    /// Spawn a fake LSP server whose completion item is a snippet
+    ///
+    /// Completion returns `vec!` with `insertTextFormat = Snippet` and the body
+    /// `vec![${1:elem}; ${2:n}]`.
+    pub fn spawn_with_snippet_completion() -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that offers a snippet completion
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    printf 'Content-Length: %d\r\n\r\n%s' "$length" "$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"completionProvider":{}}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave"|"textDocument/didClose")
+            # No response for notifications
+            ;;
+        "textDocument/completion")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"isIncomplete":false,"items":[{"label":"vec!","insertText":"vec![${1:elem}; ${2:n}]","insertTextFormat":2}]}}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#;
+
+        // Write script to a temporary file
+        let script_path = Self::snippet_completion_script_path();
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the snippet completion fake LSP server script
+    pub fn snippet_completion_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_snippet_completion.sh")
+    }
+
+    /// Spawn a fake LSP server that supports workspace symbol search (workspace/symbol)
+    ///
+    /// This version answers every workspace/symbol query with two symbols, `Widget`
+    /// and `render_widget`, both located in `symbol_uri`.
+    pub fn spawn_with_workspace_symbols(symbol_uri: &str) -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that supports workspace symbols
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    echo -en "Content-Length: $length\r\n\r\n$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            # Send initialize response with workspaceSymbolProvider capability
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"workspaceSymbolProvider":true}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave")
+            # No response for notifications
+            ;;
+        "workspace/symbol")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"name":"render_widget","kind":12,"location":{"uri":"SYMBOL_URI","range":{"start":{"line":0,"character":3},"end":{"line":0,"character":16}}}},{"name":"Widget","kind":23,"location":{"uri":"SYMBOL_URI","range":{"start":{"line":2,"character":7},"end":{"line":2,"character":13}}}}]}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#
+        .replace("SYMBOL_URI", symbol_uri);
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_workspace_symbols.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the workspace symbols fake LSP server script
+    pub fn workspace_symbols_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_workspace_symbols.sh")
+    }
+
+    /// Spawn a fake LSP server that supports call hierarchy
+    ///
+    /// For "fn target() {}\n\nfn caller() {\n    target();\n}\n" in `file_uri`, the
+    /// prepared item is `target`, called once by `caller` at (3, 4). `caller` has no
+    /// callers.
+    pub fn spawn_with_call_hierarchy(file_uri: &str) -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that supports call hierarchy
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    echo -en "Content-Length: $length\r\n\r\n$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            # Send initialize response with callHierarchyProvider capability
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"callHierarchyProvider":true}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave")
+            # No response for notifications
+            ;;
+        "textDocument/prepareCallHierarchy")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"name":"target","kind":12,"uri":"FILE_URI","range":{"start":{"line":0,"character":0},"end":{"line":0,"character":14}},"selectionRange":{"start":{"line":0,"character":3},"end":{"line":0,"character":9}}}]}'
+            ;;
+        "callHierarchy/incomingCalls")
+            if echo "$msg" | grep -q '"name":"target"'; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[{"from":{"name":"caller","kind":12,"uri":"FILE_URI","range":{"start":{"line":2,"character":0},"end":{"line":4,"character":1}},"selectionRange":{"start":{"line":2,"character":3},"end":{"line":2,"character":9}}},"fromRanges":[{"start":{"line":3,"character":4},"end":{"line":3,"character":10}}]}]}'
+            else
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":[]}'
+            fi
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#
+        .replace("FILE_URI", file_uri);
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_call_hierarchy.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the call hierarchy fake LSP server script
+    pub fn call_hierarchy_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_call_hierarchy.sh")
+    }
+
+    /// Spawn a fake LSP server that supports semantic tokens (full and full/delta)
+    ///
+    /// This version always answers with two tokens for
+    /// "fn main() {\n    let mut count = 0;\n}\n": `main` as a function at (0, 3)
+    /// and `count` as a mutable variable at (1, 12).
+    pub fn spawn_with_semantic_tokens() -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that supports workspace symbols
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    echo -en "Content-Length: $length\r\n\r\n$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            # Send initialize response with semanticTokensProvider capability
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"semanticTokensProvider":{"legend":{"tokenTypes":["function","variable"],"tokenModifiers":["mutable"]},"full":{"delta":true}}}}}'
+            ;;
+        "initialized")
+            # No response needed for notification
+            ;;
+        "textDocument/didOpen"|"textDocument/didChange"|"textDocument/didSave")
+            # No response for notifications
+            ;;
+        "textDocument/semanticTokens/full"|"textDocument/semanticTokens/full/delta")
+            # A full result is a valid answer to a delta request too
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"resultId":"1","data":[0,3,4,0,0,1,12,5,1,1]}}'
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Answer any other request (e.g. pull diagnostics) so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#;
+
+        // Write script to a temporary file
+        let script_path = std::env::temp_dir().join("fake_lsp_server_semantic_tokens.sh");
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the semantic tokens fake LSP server script
+    pub fn semantic_tokens_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_semantic_tokens.sh")
+    }
+
+    /// Spawn a fake LSP server that accepts workspace folder changes
+    ///
+    /// Every message it receives is appended to `log_path`, one per line, including
+    /// the answer to the `workspace/configuration` request (for the `fake.lint`
+    /// section) it sends once initialized.
+    pub fn spawn_with_workspace_folders(log_path: &str) -> std::io::Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel();
+
+        // Create a Bash script that logs every message and asks for its settings
+        let script = r#"#!/bin/bash
+
+# Function to read a message
+read_message() {
+    # Read headers
+    local content_length=0
+    while IFS=: read -r key value; do
+        key=$(echo "$key" | tr -d '\r\n')
+        value=$(echo "$value" | tr -d '\r\n ')
+        if [ "$key" = "Content-Length" ]; then
+            content_length=$value
+        fi
+        # Empty line marks end of headers
+        if [ -z "$key" ]; then
+            break
+        fi
+    done
+
+    # Read content
+    if [ $content_length -gt 0 ]; then
+        dd bs=1 count=$content_length 2>/dev/null
+    fi
+}
+
+# Function to send a message
+send_message() {
+    local message="$1"
+    local length=${#message}
+    printf 'Content-Length: %d\r\n\r\n%s' "$length" "$message"
+}
+
+# Main loop
+while true; do
+    # Read incoming message
+    msg=$(read_message)
+
+    if [ -z "$msg" ]; then
+        break
+    fi
+    echo "$msg" >> "LOG_PATH"
+
+    # Extract method from JSON
+    method=$(echo "$msg" | grep -o '"method":"[^"]*"' | cut -d'"' -f4)
+    msg_id=$(echo "$msg" | grep -o '"id":[0-9]*' | cut -d':' -f2)
+
+    case "$method" in
+        "initialize")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":{"capabilities":{"textDocumentSync":1,"workspace":{"workspaceFolders":{"supported":true,"changeNotifications":true}}}}}'
+            ;;
+        "initialized")
+            send_message '{"jsonrpc":"2.0","id":1,"method":"workspace/configuration","params":{"items":[{"section":"fake.lint"}]}}'
+            ;;
+        "")
+            # Response to our configuration request
+            ;;
+        "shutdown")
+            send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            break
+            ;;
+        *)
+            # Notifications need no answer; answer any other request so the queue keeps moving
+            if [ -n "$msg_id" ]; then
+                send_message '{"jsonrpc":"2.0","id":'$msg_id',"result":null}'
+            fi
+            ;;
+    esac
+done
+"#
+        .replace("LOG_PATH", log_path);
+
+        // Write script to a temporary file
+        let script_path = Self::workspace_folders_script_path();
+        std::fs::write(&script_path, script)?;
+
+        // Make it executable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&script_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&script_path, perms)?;
+        }
+
+        let handle = Some(thread::spawn(move || {
+            // Wait for stop signal
+            let _ = stop_rx.recv();
+        }));
+
+        Ok(Self { handle, stop_tx })
+    }
+
+    /// Get the path to the workspace folders fake LSP server script
+    pub fn workspace_folders_script_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("fake_lsp_server_workspace_folders.sh")
+    }
+
     /// Stop the server
     pub fn stop(&mut self) {
         let _ = self.stop_tx.send(());
@@ -407,6 +407,12 @@ impl EditorTestHarness {
             .map(|d| d.path().join("data").join("recovery"))
     }
 
+    /// Get the config directory path for this test harness
+    /// Like the recovery directory, it is isolated per-test under the temp directory
+    pub fn config_dir(&self) -> Option<PathBuf> {
+        self._temp_dir.as_ref().map(|d| d.path().join("config"))
+    }
+
     /// Take ownership of the temp directory, preventing it from being cleaned up
     /// when the harness is dropped. This is useful for tests that need to access
     /// the recovery directory after dropping the harness.
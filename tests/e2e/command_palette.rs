@@ -503,7 +503,12 @@ fn test_command_palette_from_file_explorer() {
     harness.assert_screen_contains("Command:");
 
     // Should show commands
+    harness.type_text("open file").unwrap();
+    harness.render().unwrap();
     harness.assert_screen_contains("Open File");
+    harness
+        .send_key_repeat(KeyCode::Backspace, KeyModifiers::NONE, "open file".len())
+        .unwrap();
 
     // Should be able to execute a command
     harness.type_text("toggle hidden").unwrap();
@@ -704,7 +709,10 @@ fn test_command_palette_shows_shortcuts() {
     harness.assert_screen_contains("Add Cursor Below");
 
     // Copy should show Ctrl+C (or ⌘+C on macOS)
+    harness.type_text("copy").unwrap();
+    harness.render().unwrap();
     harness.assert_screen_contains("Copy");
+    let screen = harness.screen_to_string();
     assert!(
         screen.contains("Ctrl+C") || screen.contains("⌘+C"),
         "Should show shortcut for Copy"
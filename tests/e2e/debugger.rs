@@ -0,0 +1,138 @@
+//! E2E tests for the debugger (Debug Adapter Protocol client)
+
+use crate::common::fake_dap::FakeDebugAdapter;
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::services::dap::client::{DebugAdapterConfig, DebugRequest};
+
+/// Run a command from the command palette
+fn run_palette_command(harness: &mut EditorTestHarness, command: &str) -> std::io::Result<()> {
+    harness.send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)?;
+    harness.type_text(command)?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)
+}
+
+/// Set up an editor with `main.rs` open and the fake adapter configured for Rust
+fn debugger_harness(
+    temp_dir: &tempfile::TempDir,
+    log_dir: &tempfile::TempDir,
+) -> std::io::Result<(EditorTestHarness, FakeDebugAdapter, std::path::PathBuf)> {
+    let main_file = temp_dir.path().join("main.rs");
+    std::fs::write(
+        &main_file,
+        "fn main() {\n    let count = 42;\n    println!(\"{}\", count);\n}\n",
+    )?;
+    let log_path = log_dir.path().join("requests.log");
+    let adapter = FakeDebugAdapter::spawn(&log_path.to_string_lossy())?;
+
+    let mut config = fresh::config::Config::default();
+    config.debug.insert(
+        "rust".to_string(),
+        DebugAdapterConfig {
+            command: FakeDebugAdapter::script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            request: DebugRequest::Launch,
+            configuration: serde_json::json!({ "program": "${file}" }),
+            adapter_id: None,
+        },
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+    Ok((harness, adapter, log_path))
+}
+
+/// Test toggling a breakpoint in the gutter
+#[test]
+fn test_toggle_breakpoint() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let (mut harness, _adapter, _log_path) = debugger_harness(&temp_dir, &log_dir)?;
+
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::F(9), KeyModifiers::NONE)?;
+    harness.render()?;
+    harness.assert_screen_contains("Breakpoint set on line 2");
+    harness.assert_screen_contains("●");
+
+    harness.send_key(KeyCode::F(9), KeyModifiers::NONE)?;
+    harness.render()?;
+    harness.assert_screen_contains("Breakpoint removed from line 2");
+    harness.assert_screen_not_contains("●");
+
+    Ok(())
+}
+
+/// Test a whole debug session: stopping on a breakpoint, inspecting variables, the
+/// call stack and watches, stepping and running to the end
+#[test]
+fn test_debug_session() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let (mut harness, _adapter, log_path) = debugger_harness(&temp_dir, &log_dir)?;
+
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::F(9), KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::F(6), KeyModifiers::NONE)?;
+
+    let paused = harness.wait_for_async(
+        |h| h.screen_to_string().contains("Paused on breakpoint"),
+        5000,
+    )?;
+    assert!(paused, "Debugger should stop on the breakpoint");
+    // The frame marker follows once the stack trace arrives
+    let marked = harness.wait_for_async(|h| h.screen_to_string().contains("▶"), 5000)?;
+    assert!(marked, "The stopped line should be marked");
+
+    // The breakpoint was sent with a 1-based line number
+    let log = std::fs::read_to_string(&log_path)?;
+    let set_breakpoints = log
+        .lines()
+        .find(|line| line.contains("\"setBreakpoints\""))
+        .expect("setBreakpoints request should be sent");
+    assert!(
+        set_breakpoints.contains("\"line\":2"),
+        "{}",
+        set_breakpoints
+    );
+
+    run_palette_command(&mut harness, "Debug: Show Variables")?;
+    let shown =
+        harness.wait_for_async(|h| h.screen_to_string().contains("count: i32 = 42"), 5000)?;
+    assert!(shown, "Variables panel should list locals");
+    harness.assert_screen_contains("*Debug Variables*");
+
+    run_palette_command(&mut harness, "Debug: Show Call Stack")?;
+    harness.render()?;
+    harness.assert_screen_contains("▶ main");
+    harness.assert_screen_contains("start");
+
+    run_palette_command(&mut harness, "Debug: Add Watch")?;
+    harness.type_text("count + 1")?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    run_palette_command(&mut harness, "Debug: Show Watches")?;
+    let watched =
+        harness.wait_for_async(|h| h.screen_to_string().contains("count + 1 = 43"), 5000)?;
+    assert!(watched, "Watch should be evaluated");
+
+    run_palette_command(&mut harness, "Debug: Step Over")?;
+    let stepped =
+        harness.wait_for_async(|h| h.screen_to_string().contains("Paused on step"), 5000)?;
+    assert!(stepped, "Debugger should stop after stepping");
+
+    run_palette_command(&mut harness, "Debug: Continue")?;
+    let ended = harness.wait_for_async(
+        |h| h.screen_to_string().contains("Debug session ended"),
+        5000,
+    )?;
+    assert!(ended, "Debug session should end when the program exits");
+
+    Ok(())
+}
@@ -390,3 +390,131 @@ fn test_ctrl_g_cancels_mark_mode() {
         "deselect_on_move should be true (mark mode cancelled)"
     );
 }
+
+// =============================================================================
+// Kill Ring (C-k, C-y, M-y) Tests
+// =============================================================================
+
+/// Test consecutive kills are yanked back as one piece
+#[test]
+fn test_consecutive_kills_yank_together() {
+    let mut harness = emacs_harness(80, 24);
+
+    harness.type_text("one\ntwo\nthree").unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+
+    // C-k three times: "one", the newline, "two"
+    for _ in 0..3 {
+        harness
+            .send_key(KeyCode::Char('k'), KeyModifiers::CONTROL)
+            .unwrap();
+    }
+    harness.render().unwrap();
+    harness.assert_buffer_content("\nthree");
+
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("one\ntwo\nthree");
+}
+
+/// Test yank-pop replaces the yanked text with older kills, cycling through the ring
+#[test]
+fn test_yank_pop_cycles_kill_ring() {
+    let mut harness = emacs_harness(80, 24);
+
+    harness.type_text("first\nsecond").unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('k'), KeyModifiers::CONTROL)
+        .unwrap();
+    // Moving ends the kill, so the next one is a new entry
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Char('k'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("\n");
+
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("\nsecond");
+
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::ALT)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("\nfirst");
+
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::ALT)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("\nsecond");
+
+    // Undo takes back the whole yank-pop at once
+    harness
+        .send_key(KeyCode::Char('/'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("\nfirst");
+}
+
+/// Test yank replaces the selection in one undo step and ends the selection
+#[test]
+fn test_yank_replaces_selection() {
+    let mut harness = emacs_harness(80, 24);
+
+    harness.type_text("new\nold").unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('k'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key_repeat(KeyCode::Right, KeyModifiers::SHIFT, 3)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("\nold");
+
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("\nnew");
+
+    // Typing inserts after the yanked text instead of replacing a selection
+    harness.type_text("!").unwrap();
+    harness.assert_buffer_content("\nnew!");
+
+    harness
+        .send_key(KeyCode::Char('/'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('/'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("\nold");
+}
+
+/// Test yank-pop does nothing when the previous command was not a yank
+#[test]
+fn test_yank_pop_without_yank() {
+    let mut harness = emacs_harness(80, 24);
+
+    harness.type_text("text").unwrap();
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::ALT)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("text");
+}
@@ -0,0 +1,97 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use tempfile::TempDir;
+
+const SOURCE: &str = "fn main() {\n    let alpha = 1;\n    let beta = 2;\n}\nfn other() {}\n";
+
+fn harness_with_rust_file(temp_dir: &TempDir) -> EditorTestHarness {
+    let file_path = temp_dir.path().join("test.rs");
+    std::fs::write(&file_path, SOURCE).unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    // Foldable ranges are computed on the next tick
+    harness.process_async_and_render().unwrap();
+    harness
+}
+
+/// Folding hides the body but keeps the header and closing brace visible
+#[test]
+fn test_fold_and_unfold_function_body() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_rust_file(&temp_dir);
+    harness.assert_screen_contains("▾");
+
+    harness
+        .send_key(
+            KeyCode::Char('['),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        )
+        .unwrap();
+
+    harness.assert_screen_not_contains("let alpha");
+    harness.assert_screen_not_contains("let beta");
+    harness.assert_screen_contains("fn main() { ⋯");
+    harness.assert_screen_contains("▸");
+    harness.assert_screen_contains("fn other");
+
+    // The buffer itself is unchanged
+    assert_eq!(harness.get_buffer_content().unwrap(), SOURCE);
+
+    harness
+        .send_key(
+            KeyCode::Char(']'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        )
+        .unwrap();
+
+    harness.assert_screen_contains("let alpha");
+    harness.assert_screen_contains("let beta");
+    harness.assert_screen_not_contains("⋯");
+}
+
+/// Moving down from a fold header skips the hidden lines
+#[test]
+fn test_cursor_down_skips_folded_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_rust_file(&temp_dir);
+
+    harness
+        .send_key(
+            KeyCode::Char('['),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        )
+        .unwrap();
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+
+    // Lands on the closing brace line
+    let closing_brace = SOURCE.find("}\n").unwrap();
+    assert_eq!(harness.cursor_position(), closing_brace);
+
+    harness.send_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), 0);
+}
+
+/// Editing inside a folded region unfolds it
+#[test]
+fn test_edit_inside_fold_unfolds() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_rust_file(&temp_dir);
+
+    harness
+        .send_key(
+            KeyCode::Char('['),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        )
+        .unwrap();
+    harness.assert_screen_not_contains("let alpha");
+
+    // Delete the newline at the end of the header line, joining it with the body
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Delete, KeyModifiers::NONE)
+        .unwrap();
+
+    harness.assert_screen_contains("let alpha");
+    harness.assert_screen_not_contains("⋯");
+}
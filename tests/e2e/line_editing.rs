@@ -0,0 +1,97 @@
+//! End-to-end tests for the line commands (move, duplicate, join, sort...)
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+fn run_command(harness: &mut EditorTestHarness, command: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+fn undo(harness: &mut EditorTestHarness) {
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Alt+Up/Down move the selected lines, keeping them selected, one undo step each
+#[test]
+fn test_move_selected_lines() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.type_text("one\ntwo\nthree").unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key_repeat(KeyCode::Down, KeyModifiers::SHIFT, 2)
+        .unwrap();
+
+    harness.send_key(KeyCode::Down, KeyModifiers::ALT).unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("three\none\ntwo");
+
+    // The selection moved along: moving it back up restores the order
+    harness.send_key(KeyCode::Up, KeyModifiers::ALT).unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("one\ntwo\nthree");
+
+    undo(&mut harness);
+    harness.assert_buffer_content("three\none\ntwo");
+}
+
+/// Duplicating and joining work on the line of every cursor
+#[test]
+fn test_duplicate_and_join_with_multiple_cursors() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.type_text("a\nb").unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Down, KeyModifiers::CONTROL | KeyModifiers::ALT)
+        .unwrap();
+
+    harness
+        .send_key(
+            KeyCode::Char('d'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        )
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("a\na\nb\nb");
+
+    // The cursors moved onto the copies, which are joined with the next line
+    harness
+        .send_key(KeyCode::Char('j'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("a\na b\nb");
+
+    undo(&mut harness);
+    harness.assert_buffer_content("a\na\nb\nb");
+}
+
+/// Without a selection, sorting works on the whole buffer
+#[test]
+fn test_sort_and_unique_lines() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.type_text("item10\nitem2\nitem10\nitem1\n").unwrap();
+
+    run_command(&mut harness, "Sort Lines Natural");
+    harness.render().unwrap();
+    harness.assert_buffer_content("item1\nitem2\nitem10\nitem10\n");
+
+    run_command(&mut harness, "Unique Lines");
+    harness.render().unwrap();
+    harness.assert_buffer_content("item1\nitem2\nitem10\n");
+
+    undo(&mut harness);
+    undo(&mut harness);
+    harness.assert_buffer_content("item10\nitem2\nitem10\nitem1\n");
+}
@@ -776,7 +776,13 @@ fn test_lsp_waiting_indicator() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -949,7 +955,13 @@ fn test_lsp_completion_canceled_on_cursor_move() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1014,7 +1026,13 @@ fn test_lsp_cursor_animation() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1080,7 +1098,13 @@ fn test_lsp_completion_canceled_on_text_edit() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1665,7 +1689,13 @@ fn test_lsp_diagnostics_non_blocking() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -1822,7 +1852,13 @@ fn test_rust_analyzer_rename_real_scenario() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // CRITICAL: Set working directory to the temp project so rust-analyzer
@@ -2127,6 +2163,66 @@ fn test_lsp_rename_consecutive_same_position() -> std::io::Result<()> {
     Ok(())
 }
 
+/// A workspace edit with a change that can't be applied applies none of its changes
+#[test]
+fn test_workspace_edit_checked_before_applying() -> std::io::Result<()> {
+    use lsp_types::{
+        DeleteFile, DocumentChangeOperation, DocumentChanges, OneOf,
+        OptionalVersionedTextDocumentIdentifier, Position, Range, ResourceOp, TextDocumentEdit,
+        TextEdit, Uri, WorkspaceEdit,
+    };
+
+    let mut harness = EditorTestHarness::new(80, 30)?;
+    let temp_dir = tempfile::tempdir()?;
+    let test_file = temp_dir.path().join("test.rs");
+    std::fs::write(&test_file, "fn value() {}\n")?;
+    harness.open_file(&test_file)?;
+
+    let uri_of = |path: &std::path::Path| {
+        url::Url::from_file_path(path)
+            .unwrap()
+            .as_str()
+            .parse::<Uri>()
+            .unwrap()
+    };
+    let edit = WorkspaceEdit {
+        changes: None,
+        document_changes: Some(DocumentChanges::Operations(vec![
+            DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: uri_of(&test_file),
+                    version: None,
+                },
+                edits: vec![OneOf::Left(TextEdit {
+                    range: Range {
+                        start: Position {
+                            line: 0,
+                            character: 3,
+                        },
+                        end: Position {
+                            line: 0,
+                            character: 8,
+                        },
+                    },
+                    new_text: "total".to_string(),
+                })],
+            }),
+            DocumentChangeOperation::Op(ResourceOp::Delete(DeleteFile {
+                uri: uri_of(&temp_dir.path().join("missing.rs")),
+                options: None,
+            })),
+        ])),
+        change_annotations: None,
+    };
+
+    let result = harness.editor_mut().handle_rename_response(1, Ok(edit));
+    assert!(result.is_err(), "Deleting a missing file should fail");
+    harness.render()?;
+    harness.assert_buffer_content("fn value() {}\n");
+
+    Ok(())
+}
+
 /// Test that consecutive renames with real rust-analyzer work correctly
 ///
 /// This test reproduces the bug where the second rename fails because LSP
@@ -2430,7 +2526,13 @@ fn test_lsp_progress_status_display() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -2590,7 +2692,13 @@ fn test_lsp_crash_detection_and_restart() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -2802,6 +2910,7 @@ fn test_pull_diagnostics_message_handling() -> std::io::Result<()> {
         let _ = bridge.sender().send(AsyncMessage::LspPulledDiagnostics {
             request_id: 1,
             uri: uri.as_str().to_string(),
+            server: "rust-analyzer".to_string(),
             result_id: Some("test-result-id-123".to_string()),
             diagnostics: vec![diagnostic],
             unchanged: false,
@@ -2848,6 +2957,7 @@ fn test_pull_diagnostics_unchanged_response() -> std::io::Result<()> {
         let _ = bridge.sender().send(AsyncMessage::LspPulledDiagnostics {
             request_id: 2,
             uri: uri.as_str().to_string(),
+            server: "rust-analyzer".to_string(),
             result_id: Some("test-result-id-456".to_string()),
             diagnostics: Vec::new(), // Empty when unchanged
             unchanged: true,
@@ -2886,7 +2996,13 @@ fn test_pull_diagnostics_auto_trigger_after_open() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create a temp directory and test file
@@ -2964,7 +3080,13 @@ fn test_pull_diagnostics_result_id_tracking() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create a temp directory and test file
@@ -3221,7 +3343,13 @@ fn test_stopped_lsp_does_not_auto_restart_on_edit() -> std::io::Result<()> {
             auto_start: true, // Auto-start so it starts when we open the file
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -3318,3 +3446,1200 @@ fn test_stopped_lsp_does_not_auto_restart_on_edit() -> std::io::Result<()> {
 
     Ok(())
 }
+
+/// Test that saving with format_on_save applies the server's formatting edits before writing
+#[test]
+fn test_format_on_save_applies_lsp_edits() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _fake_server = FakeLspServer::spawn_with_formatting()?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let test_file = temp_dir.path().join("test.rs");
+    std::fs::write(&test_file, "fn main(){}\n")?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::formatting_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+    config
+        .languages
+        .get_mut("rust")
+        .expect("rust language config")
+        .format_on_save = true;
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&test_file)?;
+
+    // Format on save only waits for a server that has finished initializing
+    let ready = harness.wait_for_async(|h| h.screen_to_string().contains("rust: ready"), 5000)?;
+    assert!(ready, "LSP server should become ready");
+
+    harness.send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)?;
+    let saved = harness.wait_for_async(
+        |_| std::fs::read_to_string(&test_file).unwrap_or_default() == "fn main() {}\n",
+        5000,
+    )?;
+    assert!(
+        saved,
+        "File should be saved with formatting applied. On disk: {:?}",
+        std::fs::read_to_string(&test_file)?
+    );
+    assert_eq!(harness.get_buffer_content().unwrap(), "fn main() {}\n");
+
+    Ok(())
+}
+
+/// Test that "Go to Symbol in Workspace" queries the server and opens the chosen symbol
+#[test]
+fn test_workspace_symbol_opens_chosen_symbol() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let temp_dir = tempfile::tempdir()?;
+    let main_file = temp_dir.path().join("main.rs");
+    let lib_file = temp_dir.path().join("lib.rs");
+    std::fs::write(&main_file, "fn main() {}\n")?;
+    std::fs::write(&lib_file, "fn render_widget() {}\n\npub struct Widget;\n")?;
+
+    let lib_uri = url::Url::from_file_path(&lib_file).unwrap().to_string();
+    let _fake_server = FakeLspServer::spawn_with_workspace_symbols(&lib_uri)?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::workspace_symbols_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+
+    let ready = harness.wait_for_async(|h| h.screen_to_string().contains("rust: ready"), 5000)?;
+    assert!(ready, "LSP server should become ready");
+
+    harness.send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)?;
+    harness.type_text("Go to Symbol in Workspace")?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    harness.render()?;
+    harness.assert_screen_contains("Go to symbol in workspace:");
+
+    // Both symbols come back; fuzzy ranking puts the exact name first
+    harness.type_text("Widget")?;
+    let found = harness.wait_for_async(|h| h.screen_to_string().contains("lib.rs:3"), 5000)?;
+    assert!(found, "Workspace symbol results should be listed");
+
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    harness.render()?;
+
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "fn render_widget() {}\n\npub struct Widget;\n"
+    );
+    // Line 3, column 8: the start of "Widget"
+    assert_eq!(harness.cursor_position(), 30);
+
+    Ok(())
+}
+
+/// Test that choosing a code action runs its command, whose workspace/applyEdit
+/// creates a file and edits both it and the open buffer
+#[test]
+fn test_code_action_command_applies_workspace_edit() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let temp_dir = tempfile::tempdir()?;
+    let main_file = temp_dir.path().join("main.rs");
+    let new_file = temp_dir.path().join("generated.rs");
+    std::fs::write(&main_file, "fn main() {}\n")?;
+
+    let main_uri = url::Url::from_file_path(&main_file).unwrap().to_string();
+    let new_uri = url::Url::from_file_path(&new_file).unwrap().to_string();
+    let _fake_server = FakeLspServer::spawn_with_code_actions(&main_uri, &new_uri)?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::code_actions_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+
+    let ready = harness.wait_for_async(|h| h.screen_to_string().contains("rust: ready"), 5000)?;
+    assert!(ready, "LSP server should become ready");
+
+    harness.send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)?;
+    harness.type_text("Code Actions")?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    let shown = harness.wait_for_async(|h| h.screen_to_string().contains("Add header"), 5000)?;
+    assert!(shown, "Code actions popup should list the action");
+
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    let applied = harness.wait_for_async(
+        |h| h.get_buffer_content().unwrap() == "// header\nfn main() {}\n",
+        5000,
+    )?;
+    assert!(applied, "The server's workspace edit should be applied");
+
+    // The created file exists on disk; its edit stays unsaved in a background buffer
+    assert!(new_file.exists());
+    assert_eq!(std::fs::read_to_string(&new_file)?, "");
+    harness.assert_screen_contains("main.rs");
+
+    // The server hears that the edit was applied once it has been
+    let replied = harness.wait_for_async(
+        |_| {
+            std::fs::read_to_string(FakeLspServer::code_actions_reply_log_path())
+                .is_ok_and(|log| log.contains("\"applied\":true"))
+        },
+        5000,
+    )?;
+    assert!(replied, "The server should be told the edit was applied");
+
+    Ok(())
+}
+
+/// Test that a resolved code lens is shown above its line and runs its server command
+#[test]
+fn test_code_lens_shown_above_line_and_runs_command() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let temp_dir = tempfile::tempdir()?;
+    let main_file = temp_dir.path().join("main.rs");
+    std::fs::write(&main_file, "fn main() {\n    run();\n}\n")?;
+
+    let main_uri = url::Url::from_file_path(&main_file).unwrap().to_string();
+    let _fake_server = FakeLspServer::spawn_with_code_lens(&main_uri)?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::code_lens_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+
+    // The lens arrives unresolved and gets its title from codeLens/resolve
+    let shown = harness.wait_for_async(|h| h.screen_to_string().contains("Add header"), 5000)?;
+    assert!(shown, "The resolved code lens should be rendered");
+
+    // It sits on its own row, indented like the line below it
+    let screen = harness.screen_to_string();
+    let lines: Vec<&str> = screen.lines().collect();
+    let lens_row = lines
+        .iter()
+        .position(|line| line.contains("Add header"))
+        .unwrap();
+    assert!(lines[lens_row].contains("    Add header"));
+    assert!(!lines[lens_row].contains("run();"));
+    assert!(lines[lens_row + 1].contains("run();"));
+
+    // Run it from its line
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)?;
+    harness.type_text("Run Code Lens")?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    let applied = harness.wait_for_async(
+        |h| h.get_buffer_content().unwrap() == "// header\nfn main() {\n    run();\n}\n",
+        5000,
+    )?;
+    assert!(applied, "Running the lens should apply the server's edit");
+
+    Ok(())
+}
+
+/// Test that accepting a resolved completion applies its edit range and auto-import
+#[test]
+fn test_completion_resolve_applies_additional_edits() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _fake_server = FakeLspServer::spawn_with_completion_resolve()?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let main_file = temp_dir.path().join("main.rs");
+    std::fs::write(&main_file, "fn main() {\n    Has\n}\n")?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::completion_resolve_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::End, KeyModifiers::NONE)?;
+
+    // The selected item is resolved and its documentation shown beside the list.
+    // Requests sent before the server finished initializing come back empty, so retry.
+    let mut shown = false;
+    for _ in 0..10 {
+        harness.send_key(KeyCode::Char(' '), KeyModifiers::CONTROL)?;
+        shown = harness.wait_for_async(
+            |h| {
+                let screen = h.screen_to_string();
+                screen.contains("HashSet") && screen.contains("A hash map.")
+            },
+            1000,
+        )?;
+        if shown {
+            break;
+        }
+    }
+    assert!(shown, "Completion list with documentation should be shown");
+
+    // The list was incomplete, so typing asks the server again
+    harness.send_key(KeyCode::Char('M'), KeyModifiers::SHIFT)?;
+    let requeried = harness.wait_for_async(
+        |h| {
+            let screen = h.screen_to_string();
+            !screen.contains("HashSet") && screen.contains("A hash map.")
+        },
+        5000,
+    )?;
+    assert!(requeried, "Typing should re-query the incomplete list");
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "fn main() {\n    HasM\n}\n"
+    );
+
+    // Accepting replaces the edit range and adds the import
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    harness.render()?;
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "use std::collections::HashMap;\nfn main() {\n    HashMap\n}\n"
+    );
+
+    Ok(())
+}
+
+/// Test that a snippet completion is expanded into its tabstops instead of inserted
+/// literally
+#[test]
+fn test_snippet_completion_expands_tabstops() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _fake_server = FakeLspServer::spawn_with_snippet_completion()?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let main_file = temp_dir.path().join("main.rs");
+    std::fs::write(&main_file, "fn main() {\n    vec\n}\n")?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::snippet_completion_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::End, KeyModifiers::NONE)?;
+
+    // Requests sent before the server finished initializing come back empty, so retry
+    let mut shown = false;
+    for _ in 0..10 {
+        harness.send_key(KeyCode::Char(' '), KeyModifiers::CONTROL)?;
+        shown = harness.wait_for_async(|h| h.screen_to_string().contains("vec!"), 1000)?;
+        if shown {
+            break;
+        }
+    }
+    assert!(shown, "Snippet completion should be offered");
+
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "fn main() {\n    vec![elem; n]\n}\n"
+    );
+
+    // The first placeholder is selected; Tab moves to the second
+    harness.type_text("0")?;
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE)?;
+    harness.type_text("8")?;
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "fn main() {\n    vec![0; 8]\n}\n"
+    );
+
+    Ok(())
+}
+
+const IMPLEMENTATIONS_SOURCE: &str =
+    "trait Shape {}\nstruct Square;\nimpl Shape for Square {}\nstruct Circle;\nimpl Shape for Circle {}\n";
+
+/// Harness with a Rust file open, using the fake server with implementations
+fn implementations_harness(
+    temp_dir: &tempfile::TempDir,
+) -> std::io::Result<(EditorTestHarness, crate::common::fake_lsp::FakeLspServer)> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let main_file = temp_dir.path().join("main.rs");
+    std::fs::write(&main_file, IMPLEMENTATIONS_SOURCE)?;
+    let main_uri = url::Url::from_file_path(&main_file).unwrap().to_string();
+    let fake_server = FakeLspServer::spawn_with_implementations(&main_uri)?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::implementations_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+    Ok((harness, fake_server))
+}
+
+/// Test that several implementations are offered in a picker instead of jumping to
+/// the first
+#[test]
+fn test_goto_implementation_picker() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let (mut harness, _fake_server) = implementations_harness(&temp_dir)?;
+
+    // Requests sent before the server finished initializing come back empty, so retry
+    let mut shown = false;
+    for _ in 0..10 {
+        harness.send_key(KeyCode::F(12), KeyModifiers::CONTROL)?;
+        shown = harness.wait_for_async(
+            |h| h.screen_to_string().contains("Implementations (2)"),
+            1000,
+        )?;
+        if shown {
+            break;
+        }
+    }
+    assert!(shown, "Both implementations should be offered");
+    harness.assert_screen_contains("main.rs:3");
+    harness.assert_screen_contains("impl Shape for Circle {}");
+
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    harness.render()?;
+    let circle_impl = IMPLEMENTATIONS_SOURCE
+        .find("impl Shape for Circle")
+        .unwrap();
+    assert_eq!(harness.cursor_position(), circle_impl);
+    harness.assert_screen_not_contains("Implementations (2)");
+
+    Ok(())
+}
+
+/// Test that peeking shows the definition in a popup without moving the cursor, and
+/// Enter in the popup jumps there
+#[test]
+fn test_peek_definition_popup() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let (mut harness, _fake_server) = implementations_harness(&temp_dir)?;
+
+    // Peek from the last line
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::End, KeyModifiers::NONE)?;
+    let start = harness.cursor_position();
+
+    let mut shown = false;
+    for _ in 0..10 {
+        harness.send_key(KeyCode::F(12), KeyModifiers::ALT)?;
+        shown = harness.wait_for_async(|h| h.screen_to_string().contains("main.rs:1"), 1000)?;
+        if shown {
+            break;
+        }
+    }
+    assert!(shown, "The definition should be peeked at");
+    harness.assert_screen_contains("1 │ trait Shape {}");
+    harness.assert_screen_contains("3 │ impl Shape for Square {}");
+    assert_eq!(harness.cursor_position(), start);
+
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    harness.render()?;
+    assert_eq!(harness.cursor_position(), "trait ".len());
+    harness.assert_screen_not_contains("main.rs:1");
+
+    Ok(())
+}
+
+/// Test that semantic tokens from the server are layered over syntax highlighting
+#[test]
+fn test_semantic_tokens_style_mutable_variable() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+    use ratatui::style::Modifier;
+
+    let _fake_server = FakeLspServer::spawn_with_semantic_tokens()?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let test_file = temp_dir.path().join("main.rs");
+    std::fs::write(&test_file, "fn main() {\n    let mut count = 0;\n}\n")?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::semantic_tokens_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&test_file)?;
+
+    // Screen position of "count"
+    let count_cell = |h: &EditorTestHarness| {
+        h.screen_to_string()
+            .lines()
+            .enumerate()
+            .find_map(|(row, line)| {
+                let col = line.find("mut count")? + "mut ".len();
+                Some((line[..col].chars().count() as u16, row as u16))
+            })
+    };
+    let is_underlined = |h: &EditorTestHarness| {
+        count_cell(h)
+            .and_then(|(x, y)| h.get_cell_style(x, y))
+            .is_some_and(|style| style.add_modifier.contains(Modifier::UNDERLINED))
+    };
+
+    let styled = harness.wait_for_async(|h| is_underlined(h), 5000)?;
+    assert!(
+        styled,
+        "Mutable variable should be underlined by semantic tokens"
+    );
+
+    // The function name has no modifiers, so it isn't underlined
+    let (_, count_row) = count_cell(&harness).unwrap();
+    let main_line = harness
+        .screen_to_string()
+        .lines()
+        .nth(count_row as usize - 1)
+        .unwrap()
+        .to_string();
+    let main_col = main_line[..main_line.find("main").unwrap()].chars().count() as u16;
+    let main_style = harness.get_cell_style(main_col, count_row - 1).unwrap();
+    assert!(!main_style.add_modifier.contains(Modifier::UNDERLINED));
+
+    Ok(())
+}
+
+/// Test that incoming calls open as a tree and Enter jumps to the call site
+#[test]
+fn test_incoming_calls_jump_to_call_site() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    const SOURCE: &str = "fn target() {}\n\nfn caller() {\n    target();\n}\n";
+
+    let temp_dir = tempfile::tempdir()?;
+    let main_file = temp_dir.path().join("main.rs");
+    std::fs::write(&main_file, SOURCE)?;
+
+    let main_uri = url::Url::from_file_path(&main_file).unwrap().to_string();
+    let _fake_server = FakeLspServer::spawn_with_call_hierarchy(&main_uri)?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::call_hierarchy_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+
+    let ready = harness.wait_for_async(|h| h.screen_to_string().contains("rust: ready"), 5000)?;
+    assert!(ready, "LSP server should become ready");
+
+    harness.send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)?;
+    harness.type_text("Show Incoming Calls")?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+
+    // The root is expanded right away, listing its caller
+    let found = harness.wait_for_async(
+        |h| {
+            h.screen_to_string()
+                .contains("▶ function caller  main.rs:4")
+        },
+        5000,
+    )?;
+    assert!(found, "Caller should be listed under the target");
+    harness.assert_screen_contains("▼ function target  main.rs:1");
+
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    harness.render()?;
+
+    assert_eq!(harness.get_buffer_content().unwrap(), SOURCE);
+    // Line 4, column 5: the call of target() inside caller()
+    assert_eq!(harness.cursor_position(), SOURCE.find("target();").unwrap());
+
+    Ok(())
+}
+
+/// Test two servers for one language: completion and diagnostics from both are merged,
+/// and formatting goes to the server it is routed to
+#[test]
+fn test_multiple_servers_per_language() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+    use fresh::services::lsp::client::{LspFeature, LspLanguageConfig, LspServerConfig};
+
+    let _checker = FakeLspServer::spawn()?;
+    let _linter = FakeLspServer::spawn_linter()?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let test_file = temp_dir.path().join("test.rs");
+    std::fs::write(&test_file, "fn main(){\n    test_\n}\n")?;
+
+    let server = |command: std::path::PathBuf, name: &str| LspServerConfig {
+        command: command.to_string_lossy().to_string(),
+        args: vec![],
+        enabled: true,
+        auto_start: true,
+        process_limits: fresh::services::process_limits::ProcessLimits::default(),
+        initialization_options: None,
+        name: Some(name.to_string()),
+        only_features: None,
+        except_features: None,
+        settings: None,
+        root_markers: vec![],
+    };
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        LspLanguageConfig::Multiple(vec![
+            LspServerConfig {
+                except_features: Some(vec![LspFeature::Formatting]),
+                ..server(FakeLspServer::script_path(), "checker")
+            },
+            LspServerConfig {
+                only_features: Some(vec![
+                    LspFeature::Diagnostics,
+                    LspFeature::Completion,
+                    LspFeature::Formatting,
+                ]),
+                ..server(FakeLspServer::linter_script_path(), "linter")
+            },
+        ]),
+    );
+    config
+        .languages
+        .get_mut("rust")
+        .expect("rust language config")
+        .format_on_save = true;
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        160,
+        30,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&test_file)?;
+
+    let ready = harness.wait_for_async(
+        |h| {
+            let screen = h.screen_to_string();
+            screen.contains("rust (checker): ready") && screen.contains("rust (linter): ready")
+        },
+        5000,
+    )?;
+    assert!(ready, "Both LSP servers should become ready");
+
+    // Completion lists the items of both servers
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::End, KeyModifiers::NONE)?;
+    harness.send_key(KeyCode::Char(' '), KeyModifiers::CONTROL)?;
+    let merged = harness.wait_for_async(
+        |h| {
+            let screen = h.screen_to_string();
+            screen.contains("test_function") && screen.contains("test_lint_fix")
+        },
+        5000,
+    )?;
+    assert!(
+        merged,
+        "Completion should merge items from both servers. Screen:\n{}",
+        harness.screen_to_string()
+    );
+    harness.send_key(KeyCode::Esc, KeyModifiers::NONE)?;
+
+    // Formatting comes from the linter; diagnostics published on save from both
+    harness.send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)?;
+    let saved = harness.wait_for_async(
+        |_| {
+            std::fs::read_to_string(&test_file).unwrap_or_default() == "fn main() {\n    test_\n}\n"
+        },
+        5000,
+    )?;
+    assert!(saved, "File should be saved with the linter's formatting");
+
+    let diagnostics = harness.wait_for_async(
+        |h| {
+            let screen = h.screen_to_string();
+            screen.contains("E:1") && screen.contains("W:1")
+        },
+        5000,
+    )?;
+    assert!(
+        diagnostics,
+        "Diagnostics from both servers should be shown. Screen:\n{}",
+        harness.screen_to_string()
+    );
+
+    Ok(())
+}
+
+/// Harness with main.rs open and a server that logs every message it receives
+fn document_lifecycle_harness(
+    temp_dir: &tempfile::TempDir,
+    log_dir: &tempfile::TempDir,
+) -> std::io::Result<(
+    EditorTestHarness,
+    crate::common::fake_lsp::FakeLspServer,
+    std::path::PathBuf,
+)> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let main_file = temp_dir.path().join("main.rs");
+    std::fs::write(&main_file, "fn main() {}\n")?;
+    let main_uri = url::Url::from_file_path(&main_file).unwrap().to_string();
+    let log_path = log_dir.path().join("messages.log");
+    let fake_server =
+        FakeLspServer::spawn_with_document_lifecycle(&main_uri, &log_path.to_string_lossy())?;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::document_lifecycle_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&main_file)?;
+    let ready = harness.wait_for_async(|h| h.screen_to_string().contains("rust: ready"), 5000)?;
+    assert!(ready, "LSP server should become ready");
+    Ok((harness, fake_server, log_path))
+}
+
+/// Whether the server logged a message with `method` that mentions `text`
+fn lifecycle_logged(log_path: &std::path::Path, method: &str, text: &str) -> bool {
+    let method = format!("\"method\":\"{}\"", method);
+    std::fs::read_to_string(log_path)
+        .unwrap_or_default()
+        .lines()
+        .any(|line| line.contains(&method) && line.contains(text))
+}
+
+/// Test that saving sends willSave and writes the edits returned by willSaveWaitUntil
+#[test]
+fn test_will_save_wait_until_edits_are_saved() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let (mut harness, _fake_server, log_path) = document_lifecycle_harness(&temp_dir, &log_dir)?;
+    let main_file = temp_dir.path().join("main.rs");
+
+    harness.send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)?;
+    let saved = harness.wait_for_async(
+        |_| std::fs::read_to_string(&main_file).unwrap_or_default() == "// saved\nfn main() {}\n",
+        5000,
+    )?;
+    assert!(
+        saved,
+        "File should be saved with the server's edits. On disk: {:?}",
+        std::fs::read_to_string(&main_file)?
+    );
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "// saved\nfn main() {}\n"
+    );
+    assert!(lifecycle_logged(
+        &log_path,
+        "textDocument/willSave",
+        "main.rs"
+    ));
+
+    Ok(())
+}
+
+/// Test that closing a buffer sends didClose for its file
+#[test]
+fn test_did_close_sent_when_buffer_closes() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let (mut harness, _fake_server, log_path) = document_lifecycle_harness(&temp_dir, &log_dir)?;
+    let lib_file = temp_dir.path().join("lib.rs");
+    std::fs::write(&lib_file, "pub fn lib() {}\n")?;
+    harness.open_file(&lib_file)?;
+
+    let opened = harness.wait_for_async(
+        |_| lifecycle_logged(&log_path, "textDocument/didOpen", "lib.rs"),
+        5000,
+    )?;
+    assert!(opened, "lib.rs should be opened on the server");
+
+    let buffer_id = harness.editor().active_buffer();
+    harness.editor_mut().close_buffer(buffer_id)?;
+    let closed = harness.wait_for_async(
+        |_| lifecycle_logged(&log_path, "textDocument/didClose", "lib.rs"),
+        5000,
+    )?;
+    assert!(closed, "didClose should be sent for lib.rs");
+    assert!(!lifecycle_logged(
+        &log_path,
+        "textDocument/didClose",
+        "main.rs"
+    ));
+
+    Ok(())
+}
+
+/// Test that changes to files matching a registered watcher are sent to the server,
+/// and changes to other files aren't
+#[test]
+fn test_watched_file_changes_sent_to_server() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let (mut harness, _fake_server, log_path) = document_lifecycle_harness(&temp_dir, &log_dir)?;
+
+    // The watcher starts once the registration is processed, so keep touching the file
+    let mut notified = false;
+    for attempt in 0..10 {
+        std::fs::write(temp_dir.path().join("notes.md"), format!("{}\n", attempt))?;
+        std::fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            format!("# {}\n", attempt),
+        )?;
+        notified = harness.wait_for_async(
+            |_| lifecycle_logged(&log_path, "workspace/didChangeWatchedFiles", "Cargo.toml"),
+            1000,
+        )?;
+        if notified {
+            break;
+        }
+    }
+    assert!(notified, "Cargo.toml changes should be sent to the server");
+    assert!(!lifecycle_logged(
+        &log_path,
+        "workspace/didChangeWatchedFiles",
+        "notes.md"
+    ));
+
+    Ok(())
+}
+
+/// Test that renaming a file in the file explorer applies the willRenameFiles edits,
+/// moves the buffer along and sends didRenameFiles
+#[test]
+fn test_file_explorer_rename_notifies_server() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let (mut harness, _fake_server, log_path) = document_lifecycle_harness(&temp_dir, &log_dir)?;
+    let main_file = temp_dir.path().join("main.rs");
+
+    harness.editor_mut().perform_file_explorer_rename(
+        main_file.clone(),
+        "main.rs".to_string(),
+        "app.rs".to_string(),
+    );
+    harness.render()?;
+
+    assert!(temp_dir.path().join("app.rs").exists());
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "// moved\nfn main() {}\n"
+    );
+    let renamed = harness.wait_for_async(
+        |_| {
+            lifecycle_logged(&log_path, "workspace/didRenameFiles", "app.rs")
+                && lifecycle_logged(&log_path, "textDocument/didOpen", "app.rs")
+        },
+        5000,
+    )?;
+    assert!(
+        renamed,
+        "didRenameFiles and didOpen should be sent for app.rs"
+    );
+    assert!(lifecycle_logged(
+        &log_path,
+        "workspace/willRenameFiles",
+        "main.rs"
+    ));
+
+    Ok(())
+}
+
+/// Run a command from the command palette
+fn run_palette_command(harness: &mut EditorTestHarness, command: &str) -> std::io::Result<()> {
+    harness.send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)?;
+    harness.type_text(command)?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)
+}
+
+/// Test that a server's log messages, and with tracing on its JSON-RPC messages,
+/// are shown in its log buffer
+#[test]
+fn test_show_lsp_log_with_trace() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let (mut harness, _fake_server, _log_path) = document_lifecycle_harness(&temp_dir, &log_dir)?;
+
+    run_palette_command(&mut harness, "Show LSP Log")?;
+    // The server of the current buffer is selected
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    let logged = harness.wait_for_async(
+        |h| h.screen_to_string().contains("[info] Loaded 3 crates"),
+        5000,
+    )?;
+    assert!(logged, "The server's log message should be shown");
+    harness.assert_screen_contains("*LSP Log: rust*");
+
+    run_palette_command(&mut harness, "Toggle LSP Trace")?;
+    let lib_file = temp_dir.path().join("lib.rs");
+    std::fs::write(&lib_file, "pub fn lib() {}\n")?;
+    harness.open_file(&lib_file)?;
+    run_palette_command(&mut harness, "Show LSP Log")?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    let traced = harness.wait_for_async(
+        |h| {
+            let screen = h.screen_to_string();
+            screen.contains("[sent]") && screen.contains("textDocument/didOpen")
+        },
+        5000,
+    )?;
+    assert!(traced, "The didOpen sent while tracing should be logged");
+
+    Ok(())
+}
+
+/// Test that LSP Info describes the running server
+#[test]
+fn test_lsp_info_lists_running_servers() -> std::io::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let (mut harness, _fake_server, _log_path) = document_lifecycle_harness(&temp_dir, &log_dir)?;
+
+    run_palette_command(&mut harness, "LSP Info")?;
+    harness.render()?;
+
+    let screen = harness.screen_to_string();
+    assert!(screen.contains("*LSP Info*"), "Screen:\n{}", screen);
+    assert!(
+        screen.contains("State:        Running"),
+        "Screen:\n{}",
+        screen
+    );
+    assert!(screen.contains("Restarts:     0"), "Screen:\n{}", screen);
+    assert!(screen.contains("textDocumentSync"), "Screen:\n{}", screen);
+    let pid_line = screen
+        .lines()
+        .find(|line| line.contains("PID:"))
+        .expect("PID should be listed");
+    assert!(
+        pid_line.chars().any(|c| c.is_ascii_digit()),
+        "PID should be known: {}",
+        pid_line
+    );
+
+    Ok(())
+}
+
+/// Test that a server is rooted at the project of the file it was started for, that
+/// files in other projects add their project as a workspace folder, and that the
+/// configured settings answer workspace/configuration
+#[test]
+fn test_projects_become_workspace_folders() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let temp_dir = tempfile::tempdir()?;
+    let log_dir = tempfile::tempdir()?;
+    let log_path = log_dir.path().join("messages.log");
+    let _fake_server = FakeLspServer::spawn_with_workspace_folders(&log_path.to_string_lossy())?;
+
+    // Two projects side by side, as in a monorepo
+    for project in ["alpha", "beta"] {
+        let dir = temp_dir.path().join(project);
+        std::fs::create_dir_all(dir.join("src"))?;
+        std::fs::write(dir.join("Cargo.toml"), "[package]\n")?;
+        std::fs::write(dir.join("src").join("lib.rs"), "pub fn f() {}\n")?;
+    }
+    let project_uri = |project: &str| {
+        url::Url::from_file_path(temp_dir.path().join(project))
+            .unwrap()
+            .to_string()
+    };
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::workspace_folders_script_path()
+                .to_string_lossy()
+                .to_string(),
+            args: vec![],
+            enabled: true,
+            auto_start: true,
+            process_limits: fresh::services::process_limits::ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: Some(serde_json::json!({"fake": {"lint": {"level": "strict"}}})),
+            root_markers: vec!["Cargo.toml".to_string()],
+        }
+        .into(),
+    );
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        config,
+        temp_dir.path().to_path_buf(),
+    )?;
+    harness.open_file(&temp_dir.path().join("alpha").join("src").join("lib.rs"))?;
+    let ready = harness.wait_for_async(|h| h.screen_to_string().contains("rust: ready"), 5000)?;
+    assert!(ready, "LSP server should become ready");
+
+    assert!(
+        lifecycle_logged(
+            &log_path,
+            "initialize",
+            &format!("\"rootUri\":\"{}\"", project_uri("alpha"))
+        ),
+        "The server should be rooted at the alpha project"
+    );
+    let answered = harness.wait_for_async(
+        |_| {
+            std::fs::read_to_string(&log_path)
+                .unwrap_or_default()
+                .contains("\"result\":[{\"level\":\"strict\"}]")
+        },
+        5000,
+    )?;
+    assert!(
+        answered,
+        "workspace/configuration should get the fake.lint settings"
+    );
+    assert!(lifecycle_logged(
+        &log_path,
+        "workspace/didChangeConfiguration",
+        "strict"
+    ));
+
+    harness.open_file(&temp_dir.path().join("beta").join("src").join("lib.rs"))?;
+    let added = harness.wait_for_async(
+        |_| {
+            lifecycle_logged(
+                &log_path,
+                "workspace/didChangeWorkspaceFolders",
+                &project_uri("beta"),
+            )
+        },
+        5000,
+    )?;
+    assert!(
+        added,
+        "The beta project should be added as a workspace folder"
+    );
+    assert!(!lifecycle_logged(
+        &log_path,
+        "workspace/didChangeWorkspaceFolders",
+        &project_uri("alpha")
+    ));
+
+    Ok(())
+}
@@ -6,13 +6,16 @@ pub mod binary_file;
 pub mod buffer_lifecycle;
 pub mod command_palette;
 pub mod crlf_rendering;
+pub mod debugger;
 pub mod document_model;
 pub mod emacs_actions;
 pub mod explorer_menu;
 pub mod file_browser;
 pub mod file_explorer;
+pub mod folding;
 pub mod large_file_mode;
 pub mod lifecycle;
+pub mod line_editing;
 pub mod line_wrapping;
 pub mod live_grep;
 pub mod lsp;
@@ -33,14 +36,17 @@ pub mod position_history_truncate_debug;
 pub mod prompt;
 pub mod prompt_editing;
 pub mod recovery;
+pub mod rectangle;
 pub mod rendering;
 pub mod scroll_clearing;
 pub mod scrolling;
 pub mod search;
 pub mod selection;
 pub mod session;
+pub mod shell_command;
 pub mod slow_filesystem;
 pub mod smart_editing;
+pub mod snippets;
 pub mod split_tabs;
 pub mod split_view;
 pub mod split_view_expectations;
@@ -48,9 +54,11 @@ pub mod tab_scrolling;
 pub mod terminal;
 pub mod terminal_resize;
 pub mod test_scrollbar_keybinds_cursor;
+pub mod text_objects;
 pub mod theme;
 pub mod undo_redo;
 pub mod unicode_cursor;
 pub mod update_notification;
+pub mod vim;
 pub mod virtual_lines;
 pub mod visual_regression;
@@ -1335,3 +1335,34 @@ fn test_esc_returns_to_original_cursor_position() {
         "After pressing Esc, cursor should return to original position {original_position} but is at {final_position}"
     );
 }
+
+/// Text copied with several cursors pastes back one piece per cursor
+#[test]
+fn test_multi_cursor_copy_paste_per_cursor() {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+
+    harness.type_text("a1\nb2\nc3").unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness.editor_mut().add_cursor_below();
+    harness.editor_mut().add_cursor_below();
+    assert_eq!(harness.editor().active_state().cursors.iter().count(), 3);
+
+    // Select and copy each line
+    harness.send_key(KeyCode::End, KeyModifiers::SHIFT).unwrap();
+    harness
+        .send_key(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    // Paste after a space at the end of each line
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text(" ").unwrap();
+    harness
+        .send_key(KeyCode::Char('v'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+
+    harness.assert_buffer_content("a1 a1\nb2 b2\nc3 c3");
+}
@@ -135,7 +135,13 @@ done
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
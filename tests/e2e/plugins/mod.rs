@@ -5,5 +5,6 @@ pub mod config_editor;
 pub mod git;
 pub mod gutter;
 pub mod lsp_find_references;
+pub mod outline;
 pub mod plugin;
 pub mod theme_editor;
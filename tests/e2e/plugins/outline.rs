@@ -0,0 +1,70 @@
+//! Outline panel tests that depend on the outline plugin
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::fs;
+
+/// Test that the outline panel falls back to tree-sitter symbols when no
+/// language server is running, and that Enter jumps to the selected symbol
+#[test]
+fn test_outline_plugin_tree_sitter_fallback() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let project_root = temp_dir.path().to_path_buf();
+
+    let plugins_dir = project_root.join("plugins");
+    fs::create_dir(&plugins_dir).unwrap();
+    let plugin_source = std::env::current_dir().unwrap().join("plugins/outline.ts");
+    fs::copy(&plugin_source, plugins_dir.join("outline.ts")).unwrap();
+
+    let test_file = project_root.join("shapes.rs");
+    fs::write(
+        &test_file,
+        "struct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn origin() -> Self {\n        Point { x: 0 }\n    }\n}\n\nfn main() {}\n",
+    )
+    .unwrap();
+
+    // No LSP configured for rust, so symbols must come from tree-sitter
+    let mut config = fresh::config::Config::default();
+    config.lsp.clear();
+
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(120, 30, config, project_root).unwrap();
+    harness.open_file(&test_file).unwrap();
+    harness.render().unwrap();
+
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Show Outline").unwrap();
+    harness.render().unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+
+    harness
+        .wait_until(|h| {
+            h.screen_to_string()
+                .contains("Outline: shapes.rs (tree-sitter)")
+        })
+        .unwrap();
+
+    let screen = harness.screen_to_string();
+    assert!(screen.contains("struct Point"), "Screen:\n{}", screen);
+    assert!(screen.contains("fn origin"), "Screen:\n{}", screen);
+    assert!(screen.contains("fn main  :11"), "Screen:\n{}", screen);
+
+    // The panel cursor follows the source cursor (at the start of the file),
+    // so moving down three lines lands on the impl's method
+    for _ in 0..3 {
+        harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    }
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness
+        .wait_until(|h| {
+            h.editor().active_state().cursors.primary().position
+                == "struct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn ".len()
+        })
+        .unwrap();
+}
@@ -690,7 +690,13 @@ fn test_diagnostics_panel_plugin_loads() {
             auto_start: true,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     // Create harness with the project directory and LSP config
@@ -1569,7 +1575,13 @@ fn test_clangd_plugin_file_status_notification() -> std::io::Result<()> {
             auto_start: false,
             process_limits: ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     let mut harness =
@@ -1651,7 +1663,13 @@ fn test_clangd_plugin_switch_source_header() -> std::io::Result<()> {
             auto_start: false,
             process_limits: ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     let mut harness =
@@ -1882,7 +1900,13 @@ editor.setStatus("Test diagnostics plugin loaded");
             auto_start: false,
             process_limits: ProcessLimits::default(),
             initialization_options: None,
-        },
+            name: None,
+            only_features: None,
+            except_features: None,
+            settings: None,
+            root_markers: vec![],
+        }
+        .into(),
     );
 
     let mut harness =
@@ -0,0 +1,136 @@
+//! End-to-end tests for the rectangle commands on block selections
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+fn run_command(harness: &mut EditorTestHarness, command: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+/// Type `text`, then block select from `column` on the first line, `width`
+/// columns wide and down to the last line
+fn block_select(harness: &mut EditorTestHarness, text: &str, column: usize, width: usize) {
+    harness.type_text(text).unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key_repeat(KeyCode::Right, KeyModifiers::NONE, column)
+        .unwrap();
+    harness
+        .send_key_repeat(
+            KeyCode::Right,
+            KeyModifiers::ALT | KeyModifiers::SHIFT,
+            width,
+        )
+        .unwrap();
+    harness
+        .send_key_repeat(
+            KeyCode::Down,
+            KeyModifiers::ALT | KeyModifiers::SHIFT,
+            text.lines().count() - 1,
+        )
+        .unwrap();
+}
+
+/// A killed rectangle is yanked back as a column, and each command undoes at once
+#[test]
+fn test_kill_and_yank_rectangle() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    block_select(&mut harness, "ab12\ncd34\nef56", 2, 2);
+
+    run_command(&mut harness, "Kill Rectangle");
+    harness.render().unwrap();
+    harness.assert_buffer_content("ab\ncd\nef");
+
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    run_command(&mut harness, "Yank Rectangle");
+    harness.render().unwrap();
+    harness.assert_buffer_content("12ab\n34cd\n56ef");
+
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("ab\ncd\nef");
+}
+
+/// Text and numbers are inserted at the rectangle's left edge on every line
+#[test]
+fn test_insert_text_and_numbers_in_rectangle() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    block_select(&mut harness, "a\nb\nc", 0, 0);
+
+    run_command(&mut harness, "Insert in Rectangle");
+    harness.type_text(". ").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content(". a\n. b\n. c");
+
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key_repeat(KeyCode::Down, KeyModifiers::ALT | KeyModifiers::SHIFT, 2)
+        .unwrap();
+    // The prompt starts with `1`; counting from 9 pads to the width of 11
+    run_command(&mut harness, "Number Rectangle Lines");
+    harness
+        .send_key(KeyCode::Backspace, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("9").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content(" 9. a\n10. b\n11. c");
+}
+
+/// Each line of the rectangle gets a cursor selecting its part of the rectangle
+#[test]
+fn test_rectangle_to_cursors() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    block_select(&mut harness, "abc\ndef\nghi", 1, 1);
+
+    run_command(&mut harness, "Rectangle to Cursors");
+    assert_eq!(harness.editor().active_state().cursors.iter().count(), 3);
+
+    harness.type_text("X").unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("aXc\ndXf\ngXi");
+}
+
+/// Other cursors go away: only the rectangle's lines get one
+#[test]
+fn test_rectangle_to_cursors_replaces_other_cursors() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.type_text("ab\ncd\nab").unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .send_key_repeat(KeyCode::Right, KeyModifiers::SHIFT, 2)
+        .unwrap();
+    harness.editor_mut().add_cursor_at_next_match();
+    assert_eq!(harness.editor().active_state().cursors.iter().count(), 2);
+
+    // The primary cursor's selection is a rectangle of one line
+    run_command(&mut harness, "Rectangle to Cursors");
+    assert_eq!(harness.editor().active_state().cursors.iter().count(), 1);
+
+    harness.type_text("X").unwrap();
+    harness.render().unwrap();
+    let content = harness.get_buffer_content().unwrap();
+    assert_eq!(content.matches('X').count(), 1);
+    assert_eq!(content.matches("ab").count(), 1);
+}
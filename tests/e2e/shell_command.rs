@@ -0,0 +1,123 @@
+//! End-to-end tests for running shell commands on the selection or buffer
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Run a shell command through the command palette entry `palette_command`
+fn run_shell_command(harness: &mut EditorTestHarness, palette_command: &str, command: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(palette_command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text(command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+/// Type "foo bar foo" and select both "foo"s, one per cursor
+fn select_both_foos(harness: &mut EditorTestHarness) {
+    harness.type_text("foo bar foo").unwrap();
+    harness.send_key(KeyCode::Home, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key_repeat(KeyCode::Right, KeyModifiers::SHIFT, 3)
+        .unwrap();
+    harness.editor_mut().add_cursor_at_next_match();
+    assert_eq!(harness.editor().active_state().cursors.iter().count(), 2);
+}
+
+/// Each cursor's selection is replaced with the output of its own run
+#[test]
+fn test_replace_each_selection_with_output() {
+    let mut harness = EditorTestHarness::new(120, 24).unwrap();
+    select_both_foos(&mut harness);
+
+    run_shell_command(&mut harness, "Pipe Through Shell Command", "tr a-z A-Z");
+    harness
+        .wait_until(|h| h.get_buffer_content().unwrap() == "FOO bar FOO")
+        .unwrap();
+
+    // The cursors select the output
+    let state = harness.editor().active_state();
+    let mut selections: Vec<_> = state
+        .cursors
+        .iter()
+        .filter_map(|(_, cursor)| cursor.selection_range())
+        .collect();
+    selections.sort_by_key(|range| range.start);
+    assert_eq!(selections, vec![0..3, 8..11]);
+}
+
+/// One undo reverts the replacement at every cursor
+#[test]
+fn test_undo_multi_cursor_replace_at_once() {
+    let mut harness = EditorTestHarness::new(120, 24).unwrap();
+    select_both_foos(&mut harness);
+
+    run_shell_command(&mut harness, "Pipe Through Shell Command", "tr a-z A-Z");
+    harness
+        .wait_until(|h| h.get_buffer_content().unwrap() == "FOO bar FOO")
+        .unwrap();
+
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("foo bar foo");
+}
+
+/// Without a selection, the output is inserted at the cursor
+#[test]
+fn test_insert_output_at_cursor() {
+    let mut harness = EditorTestHarness::new(120, 24).unwrap();
+    harness.type_text("ab").unwrap();
+    harness.send_key(KeyCode::Left, KeyModifiers::NONE).unwrap();
+
+    run_shell_command(&mut harness, "Insert Shell Command Output", "echo hi");
+    harness
+        .wait_until(|h| h.get_buffer_content().unwrap() == "ahib")
+        .unwrap();
+    assert_eq!(harness.cursor_position(), 3);
+}
+
+/// The output is shown in a read-only buffer and the source buffer is left alone
+#[test]
+fn test_show_output_in_buffer() {
+    let mut harness = EditorTestHarness::new(120, 24).unwrap();
+    harness.type_text("b\na\n").unwrap();
+    let source = harness.editor().active_buffer();
+
+    run_shell_command(&mut harness, "Show Shell Command Output", "sort");
+    harness
+        .wait_until(|h| h.editor().active_buffer() != source)
+        .unwrap();
+    harness.assert_buffer_content("a\nb\n");
+    harness.assert_screen_contains("*Shell Command Output*");
+
+    assert_eq!(
+        harness.editor().get_buffer_content(source).as_deref(),
+        Some("b\na\n")
+    );
+}
+
+/// A failing command leaves the buffer unchanged and shows its error output
+#[test]
+fn test_failed_command_leaves_buffer_unchanged() {
+    let mut harness = EditorTestHarness::new(120, 24).unwrap();
+    harness.type_text("keep me").unwrap();
+
+    run_shell_command(
+        &mut harness,
+        "Pipe Through Shell Command",
+        "echo oops >&2; exit 3",
+    );
+    harness
+        .wait_until(|h| h.get_status_bar().contains("oops"))
+        .unwrap();
+
+    harness.assert_screen_contains("exited with status 3");
+    harness.assert_buffer_content("keep me");
+}
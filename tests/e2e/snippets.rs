@@ -0,0 +1,103 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use tempfile::TempDir;
+
+const SNIPPETS: &str = r#"{
+    "Print value": {
+        "prefix": "pv",
+        "body": ["let ${1:x} = ${2:value};", "println!(\"{}\", $1);$0"]
+    },
+    "Integer": {
+        "prefix": "int",
+        "body": "let n: ${1|i32,u64|} = 0;"
+    }
+}"#;
+
+/// Harness with a user snippet file for Rust and an empty Rust file open
+fn harness_with_snippets(temp_dir: &TempDir) -> EditorTestHarness {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    let snippets_dir = harness.config_dir().unwrap().join("snippets");
+    std::fs::create_dir_all(&snippets_dir).unwrap();
+    std::fs::write(snippets_dir.join("rust.json"), SNIPPETS).unwrap();
+
+    let file_path = temp_dir.path().join("test.rs");
+    std::fs::write(&file_path, "").unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness
+}
+
+/// Tab expands a prefix, typing fills in mirrored placeholders together, and Tab
+/// moves on until the final tabstop
+#[test]
+fn test_expand_snippet_and_fill_tabstops() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_snippets(&temp_dir);
+
+    harness.type_text("pv").unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "let x = value;\nprintln!(\"{}\", x);"
+    );
+
+    // The placeholder is selected at both occurrences
+    harness.type_text("count").unwrap();
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "let count = value;\nprintln!(\"{}\", count);"
+    );
+
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.type_text("3").unwrap();
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "let count = 3;\nprintln!(\"{}\", count);"
+    );
+
+    // Shift+Tab goes back to the previous tabstop
+    harness.send_key(KeyCode::Tab, KeyModifiers::SHIFT).unwrap();
+    harness.type_text("total").unwrap();
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "let total = 3;\nprintln!(\"{}\", total);"
+    );
+
+    // The last Tab lands on $0 and ends the snippet
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.type_text(" // done").unwrap();
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "let total = 3;\nprintln!(\"{}\", total); // done"
+    );
+}
+
+/// A choice tabstop offers its values in a popup
+#[test]
+fn test_snippet_choice_popup() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_snippets(&temp_dir);
+
+    harness.type_text("int").unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.render().unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "let n: i32 = 0;");
+    harness.assert_screen_contains("u64");
+
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "let n: u64 = 0;");
+}
+
+/// Tab without a matching prefix still indents
+#[test]
+fn test_tab_without_snippet_prefix_inserts_indent() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_snippets(&temp_dir);
+
+    harness.type_text("nope").unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "nope    ");
+}
@@ -0,0 +1,167 @@
+//! End-to-end tests for syntax text objects, structural navigation and
+//! expanding/shrinking the selection along the syntax tree
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::Config;
+use tempfile::TempDir;
+
+const SOURCE: &str =
+    "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn main() {\n    let x = add(1, 2);\n}\n";
+
+fn harness_with_rust_file(temp_dir: &TempDir, config: Config) -> EditorTestHarness {
+    let file_path = temp_dir.path().join("test.rs");
+    std::fs::write(&file_path, SOURCE).unwrap();
+
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness
+}
+
+fn run_command(harness: &mut EditorTestHarness, command: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+/// Put the cursor on the `1` in `add(1, 2)`
+fn move_to_first_argument(harness: &mut EditorTestHarness) {
+    harness
+        .send_key_repeat(KeyCode::Down, KeyModifiers::NONE, 5)
+        .unwrap();
+    harness.send_key(KeyCode::Home, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key_repeat(KeyCode::Right, KeyModifiers::NONE, 16)
+        .unwrap();
+    assert_eq!(harness.cursor_position(), SOURCE.find("1, 2").unwrap());
+}
+
+fn selected_text(harness: &mut EditorTestHarness) -> String {
+    let range = harness
+        .editor()
+        .active_state()
+        .cursors
+        .primary()
+        .selection_range()
+        .unwrap_or(0..0);
+    harness
+        .editor_mut()
+        .active_state_mut()
+        .get_text_range(range.start, range.end)
+}
+
+/// Expanding climbs the syntax tree and shrinking walks back down
+#[test]
+fn test_expand_and_shrink_selection_along_syntax_tree() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_rust_file(&temp_dir, Config::default());
+    move_to_first_argument(&mut harness);
+
+    let expand = |harness: &mut EditorTestHarness| {
+        harness
+            .send_key(KeyCode::Right, KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+            .unwrap();
+    };
+    let shrink = |harness: &mut EditorTestHarness| {
+        harness
+            .send_key(KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::ALT)
+            .unwrap();
+    };
+
+    expand(&mut harness);
+    assert_eq!(selected_text(&mut harness), "1");
+    expand(&mut harness);
+    assert_eq!(selected_text(&mut harness), "1, 2");
+    expand(&mut harness);
+    assert_eq!(selected_text(&mut harness), "(1, 2)");
+    expand(&mut harness);
+    assert_eq!(selected_text(&mut harness), "add(1, 2)");
+
+    shrink(&mut harness);
+    assert_eq!(selected_text(&mut harness), "(1, 2)");
+    shrink(&mut harness);
+    assert_eq!(selected_text(&mut harness), "1, 2");
+}
+
+/// Deleting a function removes its lines
+#[test]
+fn test_delete_function() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_rust_file(&temp_dir, Config::default());
+    move_to_first_argument(&mut harness);
+
+    run_command(&mut harness, "Delete Function");
+    harness.assert_buffer_content("fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\n");
+}
+
+/// Selecting again grows the selection to the enclosing object
+#[test]
+fn test_select_argument_then_enclosing_function() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_rust_file(&temp_dir, Config::default());
+    move_to_first_argument(&mut harness);
+
+    run_command(&mut harness, "Select Argument");
+    assert_eq!(selected_text(&mut harness), "1, ");
+    run_command(&mut harness, "Select Function");
+    assert_eq!(
+        selected_text(&mut harness),
+        "fn main() {\n    let x = add(1, 2);\n}"
+    );
+}
+
+/// Swapping an argument keeps the cursor on it
+#[test]
+fn test_swap_argument_with_next() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_rust_file(&temp_dir, Config::default());
+    move_to_first_argument(&mut harness);
+
+    run_command(&mut harness, "Swap Argument with Next");
+    assert!(harness
+        .get_buffer_content()
+        .unwrap()
+        .contains("let x = add(2, 1);"));
+    assert_eq!(harness.cursor_position(), SOURCE.find("2);").unwrap());
+}
+
+/// Function navigation jumps between function starts
+#[test]
+fn test_next_and_previous_function() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_rust_file(&temp_dir, Config::default());
+
+    run_command(&mut harness, "Go to Next Function");
+    assert_eq!(harness.cursor_position(), SOURCE.find("fn main").unwrap());
+
+    run_command(&mut harness, "Go to Previous Function");
+    assert_eq!(harness.cursor_position(), 0);
+}
+
+/// Vim's `cif` changes a function's body and `daa` deletes an argument
+#[test]
+fn test_vim_syntax_text_objects() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = Config {
+        active_keybinding_map: "vim".to_string(),
+        ..Default::default()
+    };
+    let mut harness = harness_with_rust_file(&temp_dir, config);
+
+    harness.type_text("jcifb + a").unwrap();
+    harness.send_key(KeyCode::Esc, KeyModifiers::NONE).unwrap();
+    assert!(harness
+        .get_buffer_content()
+        .unwrap()
+        .starts_with("fn add(a: i32, b: i32) -> i32 {\n    b + a\n}\n"));
+
+    harness.type_text("gg0f(ldaa").unwrap();
+    assert!(harness
+        .get_buffer_content()
+        .unwrap()
+        .starts_with("fn add(b: i32) -> i32 {"));
+}
@@ -504,3 +504,147 @@ fn test_undo_to_empty_after_save_as() {
         final_content
     );
 }
+
+/// Create a harness that keeps undo histories of closed files
+///
+/// A second buffer stays open so that closing the file keeps the editor focused
+/// (closing the last buffer focuses the file explorer).
+fn persistent_undo_harness() -> EditorTestHarness {
+    let config = fresh::config::Config {
+        editor: fresh::config::EditorConfig {
+            persistent_undo: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.new_buffer().unwrap();
+    harness
+}
+
+/// Undo history survives closing and reopening an unchanged file
+#[test]
+fn test_undo_history_restored_after_reopen() {
+    use crate::common::fixtures::TestFixture;
+
+    let fixture = TestFixture::new("test_persistent_undo.txt", "initial").unwrap();
+    let mut harness = persistent_undo_harness();
+    harness.open_file(&fixture.path).unwrap();
+
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text("X").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    let buffer_id = harness.editor().active_buffer();
+    harness.editor_mut().close_buffer(buffer_id).unwrap();
+
+    harness.open_file(&fixture.path).unwrap();
+    harness.assert_buffer_content("initialX");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("initial");
+    assert!(harness.editor().active_state().buffer.is_modified());
+
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("initialX");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+}
+
+/// A file changed while closed starts with an empty undo history
+#[test]
+fn test_undo_history_discarded_when_file_changed() {
+    use crate::common::fixtures::TestFixture;
+
+    let fixture = TestFixture::new("test_persistent_undo_stale.txt", "initial").unwrap();
+    let mut harness = persistent_undo_harness();
+    harness.open_file(&fixture.path).unwrap();
+
+    harness.type_text("X").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    let buffer_id = harness.editor().active_buffer();
+    harness.editor_mut().close_buffer(buffer_id).unwrap();
+
+    std::fs::write(&fixture.path, "changed elsewhere").unwrap();
+    harness.open_file(&fixture.path).unwrap();
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("changed elsewhere");
+}
+
+/// Editing after undo starts a branch; the undone edit can be restored from the undo tree
+#[test]
+fn test_undo_tree_restores_abandoned_branch() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.type_text("abc").unwrap();
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("X").unwrap();
+    harness.assert_buffer_content("abX");
+
+    harness.editor_mut().show_undo_tree();
+    harness.render().unwrap();
+    harness.assert_screen_contains("insert \"c\"");
+    harness.assert_screen_contains("insert \"X\"");
+
+    // The cursor starts on the current state; the branch with "c" is listed above it
+    harness.send_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("Preview of #");
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("abc");
+
+    // Undo and redo now follow the restored branch
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("ab");
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("abc");
+}
+
+/// "Undo to Earlier Time" and "Redo to Later Time" move through the history by time
+#[test]
+fn test_undo_to_earlier_time() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.type_text("abc").unwrap();
+
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Undo to Earlier Time").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("1h").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("");
+
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Redo to Later Time").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("1h").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("abc");
+}
@@ -0,0 +1,155 @@
+//! End-to-end tests for the vim keybinding style
+//!
+//! Covers the operator/motion grammar, text objects, dot-repeat and the
+//! `:` command line. Shadow validation stays off because modal keys do not
+//! insert the characters the harness would expect.
+
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::Config;
+use tempfile::TempDir;
+
+/// Create a harness using the vim keymap with `content` opened from disk
+fn vim_harness(content: &str) -> (EditorTestHarness, TempDir, std::path::PathBuf) {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, content).unwrap();
+
+    let config = Config {
+        active_keybinding_map: "vim".to_string(),
+        ..Default::default()
+    };
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+    (harness, temp_dir, file_path)
+}
+
+fn esc(harness: &mut EditorTestHarness) {
+    harness.send_key(KeyCode::Esc, KeyModifiers::NONE).unwrap();
+}
+
+fn ex(harness: &mut EditorTestHarness, command: &str) {
+    harness.type_text(":").unwrap();
+    harness.type_text(command).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+/// The status bar shows the current vim mode
+#[test]
+fn test_vim_mode_indicator() {
+    let (mut harness, _dir, _path) = vim_harness("hello\n");
+    harness.render().unwrap();
+    harness.assert_screen_contains("NORMAL");
+
+    harness.type_text("i").unwrap();
+    harness.assert_screen_contains("INSERT");
+
+    esc(&mut harness);
+    harness.assert_screen_contains("NORMAL");
+}
+
+/// Typing in normal mode runs commands instead of inserting text
+#[test]
+fn test_vim_normal_mode_does_not_insert() {
+    let (mut harness, _dir, _path) = vim_harness("hello world\n");
+    harness.type_text("qzy").unwrap();
+    harness.assert_buffer_content("hello world\n");
+}
+
+/// Counts compose with operators and motions
+#[test]
+fn test_vim_delete_words_with_count() {
+    let (mut harness, _dir, _path) = vim_harness("one two three four\n");
+    harness.type_text("d3w").unwrap();
+    harness.assert_buffer_content("four\n");
+
+    harness.type_text("u").unwrap();
+    harness.assert_buffer_content("one two three four\n");
+}
+
+/// `ci"` replaces the contents of a quoted string
+#[test]
+fn test_vim_change_inside_quotes() {
+    let (mut harness, _dir, _path) = vim_harness("let s = \"old text\";\n");
+    harness.type_text("fo").unwrap();
+    harness.type_text("ci\"new").unwrap();
+    esc(&mut harness);
+    harness.assert_buffer_content("let s = \"new\";\n");
+}
+
+/// `>ip` indents the whole paragraph
+#[test]
+fn test_vim_indent_paragraph() {
+    let (mut harness, _dir, _path) = vim_harness("a\nb\n\nc\n");
+    harness.type_text(">ip").unwrap();
+    harness.assert_buffer_content("    a\n    b\n\nc\n");
+}
+
+/// `.` repeats the last change, including text typed in insert mode
+#[test]
+fn test_vim_dot_repeat() {
+    let (mut harness, _dir, _path) = vim_harness("foo\nbar\nbaz\n");
+    harness.type_text("A;").unwrap();
+    esc(&mut harness);
+    harness.type_text("j.j.").unwrap();
+    harness.assert_buffer_content("foo;\nbar;\nbaz;\n");
+
+    harness.type_text("ggdd.").unwrap();
+    harness.assert_buffer_content("baz;\n");
+}
+
+/// Linewise yank and put
+#[test]
+fn test_vim_yank_and_put_line() {
+    let (mut harness, _dir, _path) = vim_harness("first\nsecond\n");
+    harness.type_text("yyjp").unwrap();
+    harness.assert_buffer_content("first\nsecond\nfirst\n");
+}
+
+/// Visual mode selections feed operators
+#[test]
+fn test_vim_visual_line_delete() {
+    let (mut harness, _dir, _path) = vim_harness("a\nb\nc\nd\n");
+    harness.type_text("jVjd").unwrap();
+    harness.assert_buffer_content("a\nd\n");
+    harness.assert_screen_contains("NORMAL");
+}
+
+/// `:s` substitutes on the current line, `:%s` on the whole buffer
+#[test]
+fn test_vim_substitute() {
+    let (mut harness, _dir, _path) = vim_harness("foo foo\nfoo\n");
+    ex(&mut harness, "s/foo/bar/g");
+    harness.assert_buffer_content("bar bar\nfoo\n");
+
+    ex(&mut harness, "%s/o/0/g");
+    harness.assert_buffer_content("bar bar\nf00\n");
+}
+
+/// `:%norm` runs normal-mode keys on every line
+#[test]
+fn test_vim_norm_on_range() {
+    let (mut harness, _dir, _path) = vim_harness("a\nb\nc\n");
+    ex(&mut harness, "%norm A;");
+    harness.assert_buffer_content("a;\nb;\nc;\n");
+}
+
+/// `:w` saves the buffer to disk
+#[test]
+fn test_vim_write() {
+    let (mut harness, _dir, path) = vim_harness("text\n");
+    harness.type_text("x").unwrap();
+    ex(&mut harness, "w");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "ext\n");
+}
+
+/// `:N` jumps to a line
+#[test]
+fn test_vim_goto_line() {
+    let (mut harness, _dir, _path) = vim_harness("a\nb\nc\n");
+    ex(&mut harness, "3");
+    harness.type_text("x").unwrap();
+    harness.assert_buffer_content("a\nb\n\n");
+}